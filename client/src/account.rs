@@ -0,0 +1,334 @@
+// Player account: a display name that sticks across sessions, backed by a persistent
+// player id from the lobby service on the web and in native bevygap builds. The name is
+// what others see in rooms, voice chat and above the character in game. Those clients
+// also hold a short-lived token from the service that proves who they are on room calls.
+use bevy::prelude::*;
+
+use crate::screens::{AppState, LobbyUI, UiNotice};
+use crate::settings::ClientSettings;
+use crate::text_input::{spawn_text_input, TextInput, TextSubmitted};
+use shared::{sanitize_display_name, MAX_DISPLAY_NAME_LEN};
+
+#[cfg(feature = "bevygap")]
+use {
+    crate::teams::RoomTeam,
+    lightyear::prelude::*,
+    shared::{Channel1, SetPlayerProfile},
+};
+
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+use {
+    crate::http::{http_base, spawn, unix_now, ApiRequest, Pending},
+    shared::{AuthToken, DisplayNameRequest, GuestAuthRequest, PlayerAccount, PlayerRating},
+    std::sync::Mutex,
+};
+
+// Seconds between checks whether the token is due for a refresh
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+const TOKEN_CHECK_SECS: f32 = 30.0;
+
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+static PENDING_ACCOUNT: Pending<PlayerAccount> = Pending::new();
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+static PENDING_RATING: Pending<PlayerRating> = Pending::new();
+// Read by every lobby call, so it lives outside the ECS
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+static AUTH_TOKEN: Mutex<Option<AuthToken>> = Mutex::new(None);
+
+#[derive(Component)]
+struct NameInput;
+
+#[derive(Component)]
+struct SaveNameButton;
+
+pub struct AccountPlugin;
+
+impl Plugin for AccountPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, submit_name.run_if(in_state(AppState::Lobby)));
+
+        #[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+        app.add_systems(Startup, register_account)
+            .add_systems(OnEnter(AppState::Lobby), fetch_rating)
+            .add_systems(Update, (pump_account, pump_rating, refresh_token));
+
+        #[cfg(feature = "bevygap")]
+        app.add_systems(Update, send_profile);
+    }
+}
+
+/// "Name:", a field to type the name in and a button to save it, for the settings screen
+pub fn spawn_name_row(commands: &mut Commands, name: &str) -> Entity {
+    let input = spawn_text_input(
+        commands,
+        TextInput::new(name, "Your name", MAX_DISPLAY_NAME_LEN),
+        200.0,
+    );
+    commands.entity(input).insert(NameInput);
+
+    let row = commands
+        .spawn(Node {
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            margin: UiRect::all(Val::Px(10.0)),
+            column_gap: Val::Px(12.0),
+            ..default()
+        })
+        .with_children(|row| {
+            row.spawn((
+                Text::new("Name:"),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            ));
+        })
+        .id();
+    commands.entity(row).add_child(input);
+
+    let save = commands
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(80.0),
+                height: Val::Px(32.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.3, 0.3, 0.4)),
+            SaveNameButton,
+        ))
+        .with_children(|btn| {
+            btn.spawn((
+                Text::new("✔ SAVE"),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            ));
+        })
+        .id();
+    commands.entity(row).add_child(save);
+    row
+}
+
+// Enter in the name field or the SAVE button
+fn submit_name(
+    mut submitted: EventReader<TextSubmitted>,
+    buttons: Query<&Interaction, (Changed<Interaction>, With<SaveNameButton>)>,
+    mut inputs: Query<&mut TextInput, With<NameInput>>,
+    mut settings: ResMut<ClientSettings>,
+    mut lobby: Query<&mut LobbyUI>,
+    mut notice: ResMut<UiNotice>,
+) {
+    let entered = submitted
+        .read()
+        .filter(|event| inputs.contains(event.entity))
+        .count()
+        > 0;
+    let clicked = buttons.iter().any(|i| *i == Interaction::Pressed);
+    if !entered && !clicked {
+        return;
+    }
+    let Ok(mut input) = inputs.single_mut() else {
+        return;
+    };
+    if let Some(name) = save_name(&input.value, &mut settings, &mut lobby, &mut notice) {
+        // Show the name the way it was saved, e.g. with extra spaces dropped
+        if input.value != name {
+            input.value = name;
+        }
+    }
+}
+
+// The name as saved, None if it is not a valid name
+fn save_name(
+    draft: &str,
+    settings: &mut ClientSettings,
+    lobby: &mut Query<&mut LobbyUI>,
+    notice: &mut UiNotice,
+) -> Option<String> {
+    let Some(name) = sanitize_display_name(draft) else {
+        notice.msg = Some(format!(
+            "Names are 2-{} letters, digits, spaces, - or _",
+            MAX_DISPLAY_NAME_LEN
+        ));
+        return None;
+    };
+    if name == settings.account.display_name {
+        return Some(name);
+    }
+    info!("🏷️ Display name is now {}", name);
+    settings.account.display_name = name.clone();
+    settings.save();
+    if let Ok(mut ui) = lobby.single_mut() {
+        ui.player_name = name.clone();
+    }
+
+    // The token carries the name the service shows in rooms
+    #[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+    {
+        sync_account(settings.account.player_id.clone(), name.clone());
+        request_token(settings.account.player_id.clone(), name.clone());
+    }
+
+    Some(name)
+}
+
+// Players get an account id the first time they open the client, and a token every time
+// they open it
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+fn register_account(settings: Res<ClientSettings>) {
+    request_token(
+        settings.account.player_id.clone(),
+        settings.account.display_name.clone(),
+    );
+}
+
+/// The player's token for lobby calls, None until the service handed one out
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+pub(crate) fn auth_token() -> Option<String> {
+    let auth = AUTH_TOKEN.lock().ok()?;
+    auth.as_ref().map(|auth| auth.token.clone())
+}
+
+/// The service turned the token down, get a new one on the next check
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+pub(crate) fn expire_auth_token() {
+    if let Ok(mut auth) = AUTH_TOKEN.lock() {
+        if let Some(auth) = auth.as_mut() {
+            auth.expires_at = 0;
+        }
+    }
+}
+
+// Ask for a token. The current one goes along (`ApiRequest` adds it) and keeps the same
+// player. The service creates the account for first-time players, services without tokens
+// still get `POST /players` for that.
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+fn request_token(player_id: Option<String>, display_name: String) {
+    spawn(async move {
+        let url = format!("{}/lobby/api/auth/guest", http_base());
+        let request = ApiRequest::post(
+            url,
+            &GuestAuthRequest {
+                display_name: display_name.clone(),
+                player_id: player_id.clone(),
+            },
+        );
+        match request.json::<AuthToken>().await {
+            Ok(auth) => {
+                let account = PlayerAccount {
+                    player_id: auth.player_id.clone(),
+                    display_name: auth.display_name.clone(),
+                };
+                if let Ok(mut token) = AUTH_TOKEN.lock() {
+                    *token = Some(auth);
+                }
+                PENDING_ACCOUNT.set(account);
+            }
+            Err(error) if error.status() == Some(404) && player_id.is_none() => {
+                sync_account(None, display_name);
+            }
+            Err(_) => {}
+        }
+    });
+}
+
+// A new token shortly before the old one expires, or right after the service refused it
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+fn refresh_token(time: Res<Time>, settings: Res<ClientSettings>, mut timer: Local<Option<Timer>>) {
+    let timer =
+        timer.get_or_insert_with(|| Timer::from_seconds(TOKEN_CHECK_SECS, TimerMode::Repeating));
+    if !timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let now = unix_now();
+    let due = AUTH_TOKEN
+        .lock()
+        .is_ok_and(|auth| auth.as_ref().is_some_and(|auth| auth.needs_refresh(now)));
+    if due {
+        request_token(
+            settings.account.player_id.clone(),
+            settings.account.display_name.clone(),
+        );
+    }
+}
+
+// Create the account, or rename it if it exists. Older lobby services have no accounts,
+// the name then just stays local.
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+fn sync_account(player_id: Option<String>, display_name: String) {
+    spawn(async move {
+        let (url, method) = match &player_id {
+            Some(id) => (format!("{}/lobby/api/players/{}", http_base(), id), "PUT"),
+            None => (format!("{}/lobby/api/players", http_base()), "POST"),
+        };
+        let request = ApiRequest::new(method, url).body(&DisplayNameRequest { display_name });
+        if let Ok(account) = request.json::<PlayerAccount>().await {
+            PENDING_ACCOUNT.set(account);
+        }
+    });
+}
+
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+fn pump_account(mut settings: ResMut<ClientSettings>, mut lobby: Query<&mut LobbyUI>) {
+    let Some(account) = PENDING_ACCOUNT.take() else {
+        return;
+    };
+    if settings.account.player_id.as_ref() == Some(&account.player_id) {
+        return;
+    }
+    info!("🏷️ Player account {}", account.player_id);
+    settings.account.player_id = Some(account.player_id.clone());
+    settings.save();
+    if let Ok(mut ui) = lobby.single_mut() {
+        ui.player_id = Some(account.player_id);
+    }
+}
+
+// The ranked rating shown on the main screen, fetched again after every match
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+fn fetch_rating(settings: Res<ClientSettings>) {
+    let Some(player_id) = settings.account.player_id.clone() else {
+        return;
+    };
+    spawn(async move {
+        let url = format!("{}/lobby/api/players/{}/rating", http_base(), player_id);
+        // Older lobby services have no ratings
+        if let Ok(rating) = ApiRequest::get(url).json::<PlayerRating>().await {
+            PENDING_RATING.set(rating);
+        }
+    });
+}
+
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+fn pump_rating(mut lobby: Query<&mut LobbyUI>) {
+    let Ok(mut ui) = lobby.single_mut() else {
+        return;
+    };
+    if let Some(rating) = PENDING_RATING.take() {
+        ui.rating = Some(rating);
+    }
+}
+
+// Tell the game server our name, color and room team as soon as we are connected. Our
+// room comes from the connect token, not from us.
+#[cfg(feature = "bevygap")]
+fn send_profile(
+    settings: Res<ClientSettings>,
+    room_team: Res<RoomTeam>,
+    mut senders: Query<&mut MessageSender<SetPlayerProfile>, Added<Connected>>,
+) {
+    for mut sender in senders.iter_mut() {
+        sender.send::<Channel1>(SetPlayerProfile {
+            name: settings.account.display_name.clone(),
+            color: settings.account.color,
+            player_id: settings.account.player_id.clone(),
+            team: room_team.0,
+        });
+    }
+}
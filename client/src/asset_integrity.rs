@@ -0,0 +1,126 @@
+use bevy::asset::io::{AssetReaderError, AssetSourceId, Reader};
+use bevy::prelude::*;
+use bevy::tasks::{block_on, futures_lite::future, IoTaskPool, Task};
+use shared::{verify_asset, ASSET_MANIFEST, ASSET_MANIFEST_VERSION};
+use std::path::Path;
+
+use crate::screens::UiNotice;
+
+// Outcome of verifying downloaded assets against the manifest baked in at build time
+#[derive(Resource, Default, Debug)]
+pub struct AssetIntegrity {
+    pub pending: usize,
+    /// (asset path, human readable reason)
+    pub failures: Vec<(String, String)>,
+}
+
+impl AssetIntegrity {
+    /// Why an asset failed verification, if it did
+    pub fn failure(&self, path: &str) -> Option<&str> {
+        self.failures
+            .iter()
+            .find(|(failed, _)| failed == path)
+            .map(|(_, reason)| reason.as_str())
+    }
+}
+
+// One in-flight verification
+#[derive(Component)]
+struct AssetCheckTask {
+    path: &'static str,
+    task: Task<Result<(), String>>,
+}
+
+pub struct AssetIntegrityPlugin;
+
+impl Plugin for AssetIntegrityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AssetIntegrity>()
+            .add_systems(Startup, start_asset_checks)
+            .add_systems(Update, poll_asset_checks);
+    }
+}
+
+fn start_asset_checks(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut integrity: ResMut<AssetIntegrity>,
+) {
+    info!(
+        "📦 Verifying {} assets against manifest {}",
+        ASSET_MANIFEST.len(),
+        ASSET_MANIFEST_VERSION
+    );
+
+    let pool = IoTaskPool::get();
+    for entry in ASSET_MANIFEST {
+        let server = asset_server.clone();
+        let path = entry.path;
+        let task = pool.spawn(async move { read_and_verify(&server, path).await });
+        commands.spawn(AssetCheckTask { path, task });
+        integrity.pending += 1;
+    }
+}
+
+/// Read the raw bytes through the same asset source the game loads from and hash them
+async fn read_and_verify(server: &AssetServer, path: &str) -> Result<(), String> {
+    let source = server
+        .get_source(AssetSourceId::Default)
+        .map_err(|e| e.to_string())?;
+    let mut reader = source
+        .reader()
+        .read(Path::new(path))
+        .await
+        .map_err(|e| match e {
+            AssetReaderError::NotFound(_) => format!("{} is missing from the asset host", path),
+            AssetReaderError::HttpError(status) => {
+                format!("{} could not be downloaded (HTTP {})", path, status)
+            }
+            e => format!("{} could not be read: {}", path, e),
+        })?;
+
+    let mut bytes = Vec::new();
+    reader
+        .read_to_end(&mut bytes)
+        .await
+        .map_err(|e| format!("{} download was interrupted: {}", path, e))?;
+
+    verify_asset(path, &bytes).map_err(|e| e.to_string())
+}
+
+fn poll_asset_checks(
+    mut commands: Commands,
+    mut tasks: Query<(Entity, &mut AssetCheckTask)>,
+    mut integrity: ResMut<AssetIntegrity>,
+    mut notice: ResMut<UiNotice>,
+) {
+    for (entity, mut check) in tasks.iter_mut() {
+        let Some(result) = block_on(future::poll_once(&mut check.task)) else {
+            continue;
+        };
+        commands.entity(entity).despawn();
+        integrity.pending = integrity.pending.saturating_sub(1);
+
+        match result {
+            Ok(()) => debug!("✅ Asset verified: {}", check.path),
+            Err(reason) => {
+                error!("❌ Asset integrity check failed: {}", reason);
+                notice.msg = Some(format!("⚠️ {} - try a hard refresh", reason));
+                integrity.failures.push((check.path.to_string(), reason));
+            }
+        }
+
+        if integrity.pending == 0 {
+            if integrity.failures.is_empty() {
+                info!("📦 All assets match manifest {}", ASSET_MANIFEST_VERSION);
+            } else {
+                warn!(
+                    "📦 {} of {} assets failed verification against manifest {}",
+                    integrity.failures.len(),
+                    ASSET_MANIFEST.len(),
+                    ASSET_MANIFEST_VERSION
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,147 @@
+// Match camera: follows the local player with a dead zone and some look-ahead in the
+// direction they run. With several local players it follows the middle of the group and
+// backs away until everyone fits instead. Either way it stops at the level's edges and
+// floor, and the tuning comes from the `camera` client settings.
+use bevy::prelude::*;
+
+use crate::client_plugin::GAME_CAMERA_POSITION;
+use crate::local_play::LocalSeat;
+use crate::screens::AppState;
+use crate::settings::{CameraSettings, ClientSettings};
+use shared::{Player, PlayerId, PlayerTransform, WorldBounds};
+
+// Room kept around the outermost local players
+const LOCAL_FRAMING_MARGIN: f32 = 120.0;
+// Void shown past the level's edges and below its floor when clamping
+const LEVEL_MARGIN: f32 = 40.0;
+// Slower than following, so turning around swings the view over instead of jerking it
+const LOOK_AHEAD_SPEED: f32 = 2.0;
+// Slower than this the look-ahead keeps pointing where the player last ran
+const LOOK_AHEAD_MIN_SPEED: f32 = 20.0;
+
+/// Where the camera is headed. Effects like the fall-out shake add `shake` on top instead
+/// of moving the camera themselves.
+#[derive(Resource, Default)]
+pub struct CameraRig {
+    /// Point the camera keeps the player near, None until there is someone to follow
+    focus: Option<Vec2>,
+    /// Current lead in x, eases towards `look_ahead` in the running direction
+    lead: f32,
+    /// Camera position before the shake
+    position: Option<Vec3>,
+    pub shake: Vec3,
+}
+
+pub struct CameraFollowPlugin;
+
+impl Plugin for CameraFollowPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraRig>()
+            .add_systems(Update, follow_players.run_if(in_state(AppState::InGame)))
+            .add_systems(OnExit(AppState::InGame), reset_camera_rig);
+    }
+}
+
+fn follow_players(
+    time: Res<Time>,
+    settings: Res<ClientSettings>,
+    bounds: Option<Res<WorldBounds>>,
+    mut rig: ResMut<CameraRig>,
+    players: Query<(&PlayerTransform, &Player, &PlayerId, Has<LocalSeat>)>,
+    mut cameras: Query<(&Camera, &Projection, &mut Transform), With<Camera3d>>,
+) {
+    // Everyone in a local game, otherwise the player we control
+    let followed: Vec<(Vec2, Vec2)> = players
+        .iter()
+        .filter(|(_, _, id, seated)| *seated || id.id == 0)
+        .map(|(transform, player, _, _)| (transform.translation.truncate(), player.velocity))
+        .collect();
+    if followed.is_empty() {
+        return;
+    }
+    let camera = &settings.camera;
+    let blend = |speed: f32| 1.0 - (-speed * time.delta_secs()).exp();
+
+    for (view, projection, mut transform) in cameras.iter_mut() {
+        let Projection::Perspective(perspective) = projection else {
+            continue;
+        };
+        let aspect = view
+            .logical_viewport_size()
+            .map(|size| size.x / size.y)
+            .unwrap_or(perspective.aspect_ratio);
+        let half_fov = (perspective.fov / 2.0).tan();
+
+        let (goal, distance) = if let [(position, velocity)] = followed[..] {
+            let focus = follow_dead_zone(rig.focus.unwrap_or(position), position, camera);
+            rig.focus = Some(focus);
+            if velocity.x.abs() > LOOK_AHEAD_MIN_SPEED {
+                let lead = velocity.x.signum() * camera.look_ahead;
+                rig.lead += (lead - rig.lead) * blend(LOOK_AHEAD_SPEED);
+            }
+            (focus + Vec2::X * rig.lead, GAME_CAMERA_POSITION.z)
+        } else {
+            let (min, max) = followed.iter().fold(
+                (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+                |(min, max), (position, _)| (min.min(*position), max.max(*position)),
+            );
+            let half_extent = (max - min) / 2.0 + Vec2::splat(LOCAL_FRAMING_MARGIN);
+            let distance = (half_extent.y / half_fov)
+                .max(half_extent.x / (half_fov * aspect))
+                .max(GAME_CAMERA_POSITION.z);
+            rig.focus = Some((min + max) / 2.0);
+            rig.lead = 0.0;
+            ((min + max) / 2.0, distance)
+        };
+
+        let half_view = Vec2::new(distance * half_fov * aspect, distance * half_fov);
+        let goal = match bounds.as_deref() {
+            Some(bounds) if camera.clamp_to_level => clamp_to_level(goal, half_view, bounds),
+            _ => goal,
+        };
+        let goal = goal.extend(distance);
+        let position = match rig.position {
+            Some(position) => position.lerp(goal, blend(camera.follow_speed)),
+            // Start on the player rather than sweeping over from wherever the camera was
+            None => goal,
+        };
+        rig.position = Some(position);
+        transform.translation = position + rig.shake;
+    }
+}
+
+// The focus only moves once the player leaves the box around it, and then just far enough
+// to keep them on its edge
+fn follow_dead_zone(focus: Vec2, player: Vec2, camera: &CameraSettings) -> Vec2 {
+    let dead_zone = Vec2::new(camera.dead_zone_x, camera.dead_zone_y).max(Vec2::ZERO);
+    let offset = player - focus;
+    focus + offset - offset.clamp(-dead_zone, dead_zone)
+}
+
+// Levels narrower than the view stay centered. There is no ceiling, levels go up as high
+// as they like.
+fn clamp_to_level(goal: Vec2, half_view: Vec2, bounds: &WorldBounds) -> Vec2 {
+    let left = bounds.min_x - LEVEL_MARGIN + half_view.x;
+    let right = bounds.max_x + LEVEL_MARGIN - half_view.x;
+    let x = if left < right {
+        goal.x.clamp(left, right)
+    } else {
+        (bounds.min_x + bounds.max_x) / 2.0
+    };
+    let bottom = if bounds.floor {
+        bounds.floor_y
+    } else {
+        bounds.kill_y
+    };
+    Vec2::new(x, goal.y.max(bottom - LEVEL_MARGIN + half_view.y))
+}
+
+fn reset_camera_rig(
+    mut rig: ResMut<CameraRig>,
+    mut cameras: Query<&mut Transform, With<Camera3d>>,
+) {
+    *rig = CameraRig::default();
+    for mut transform in cameras.iter_mut() {
+        transform.translation = GAME_CAMERA_POSITION;
+    }
+}
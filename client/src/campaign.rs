@@ -0,0 +1,317 @@
+use bevy::prelude::*;
+
+use crate::offline::spawn_level;
+use crate::screens::{AppState, UiNotice};
+use crate::settings::ClientSettings;
+use shared::{
+    Campaign, Door, GameConfig, LevelDefinition, Pickup, Platform, Player, PlayerAnimationState,
+    PlayerColor, PlayerId, PlayerTransform, PressurePlate,
+};
+
+// How close the player has to get to the goal flag
+const GOAL_RADIUS: f32 = 30.0;
+// Pause on the "level complete" banner before loading the next level
+const LEVEL_TRANSITION_SECS: f32 = 2.5;
+
+// Present while a campaign level is being played offline
+#[derive(Resource, Debug)]
+pub struct ActiveCampaign {
+    pub campaign: Campaign,
+    pub index: usize,
+    pub elapsed: f32,
+    /// Running once the goal is reached, the next level loads when it finishes
+    pub transition: Option<Timer>,
+}
+
+impl ActiveCampaign {
+    /// Start the campaign at `index`, returning the level to load with it
+    pub fn start(campaign: Campaign, index: usize) -> Option<(Self, LevelDefinition)> {
+        let level = campaign.level(index)?;
+        Some((
+            Self {
+                campaign,
+                index,
+                elapsed: 0.0,
+                transition: None,
+            },
+            level,
+        ))
+    }
+}
+
+// Everything spawned for a campaign level that is not a platform
+#[derive(Component)]
+struct CampaignEntity;
+
+#[derive(Component)]
+struct CampaignGoal;
+
+#[derive(Component)]
+struct CampaignHudText;
+
+pub struct CampaignPlugin;
+
+impl Plugin for CampaignPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::InGame),
+            (spawn_campaign_level, spawn_campaign_hud).run_if(resource_exists::<ActiveCampaign>),
+        )
+        .add_systems(
+            Update,
+            (
+                check_goal_reached,
+                advance_campaign,
+                update_campaign_hud,
+                quit_campaign,
+            )
+                .chain()
+                .run_if(in_state(AppState::InGame).and(resource_exists::<ActiveCampaign>)),
+        )
+        .add_systems(OnExit(AppState::InGame), cleanup_campaign);
+    }
+}
+
+// Platforms come from the offline world, the campaign adds the player and the goal
+fn spawn_campaign_level(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    level: Res<LevelDefinition>,
+    campaign: Res<ActiveCampaign>,
+    settings: Res<ClientSettings>,
+) {
+    spawn_player_and_goal(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &level,
+        &settings,
+    );
+    info!(
+        "🏁 Campaign '{}' level {}/{}: {}",
+        campaign.campaign.name,
+        campaign.index + 1,
+        campaign.campaign.levels.len(),
+        level.name
+    );
+}
+
+fn spawn_player_and_goal(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    level: &LevelDefinition,
+    settings: &ClientSettings,
+) {
+    commands.spawn((
+        Player::default(),
+        PlayerTransform {
+            translation: level.spawn_point(None, &[]),
+        },
+        PlayerColor::from_palette(settings.account.color),
+        PlayerAnimationState::default(),
+        PlayerId { id: 0 },
+        CampaignEntity,
+    ));
+
+    if let Some(goal) = level.goal {
+        commands.spawn((
+            Mesh3d(meshes.add(Cylinder::new(4.0, 60.0))),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: Color::srgb(1.0, 0.8, 0.2),
+                emissive: LinearRgba::rgb(0.6, 0.4, 0.0),
+                ..default()
+            })),
+            Transform::from_translation(goal.translation() + Vec3::new(0.0, 20.0, 0.0)),
+            CampaignGoal,
+            CampaignEntity,
+        ));
+    } else {
+        warn!(
+            "🏁 Level '{}' has no goal, it can't be completed",
+            level.name
+        );
+    }
+}
+
+fn spawn_campaign_hud(mut commands: Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 18.0,
+            ..default()
+        },
+        TextColor(Color::srgb(1.0, 1.0, 1.0)),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.0),
+            left: Val::Px(12.0),
+            ..default()
+        },
+        CampaignHudText,
+        CampaignEntity,
+    ));
+}
+
+fn check_goal_reached(
+    time: Res<Time>,
+    level: Res<LevelDefinition>,
+    mut campaign: ResMut<ActiveCampaign>,
+    mut settings: ResMut<ClientSettings>,
+    players: Query<&PlayerTransform, (With<Player>, With<CampaignEntity>)>,
+) {
+    if campaign.transition.is_some() {
+        return;
+    }
+    campaign.elapsed += time.delta_secs();
+
+    let Some(goal) = level.goal else {
+        return;
+    };
+    let reached = players.iter().any(|p| {
+        p.translation
+            .truncate()
+            .distance(goal.translation().truncate())
+            < GOAL_RADIUS
+    });
+    if !reached {
+        return;
+    }
+
+    let seconds = campaign.elapsed;
+    let index = campaign.index;
+    let new_best = settings.campaign.complete(index, &level.name, seconds);
+    settings.save();
+
+    info!(
+        "✅ Level '{}' complete in {:.2}s{}",
+        level.name,
+        seconds,
+        if new_best { " - new best!" } else { "" }
+    );
+    campaign.transition = Some(Timer::from_seconds(LEVEL_TRANSITION_SECS, TimerMode::Once));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn advance_campaign(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut campaign: ResMut<ActiveCampaign>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut notice: ResMut<UiNotice>,
+    settings: Res<ClientSettings>,
+    config: Res<GameConfig>,
+    level_entities: Query<
+        Entity,
+        Or<(
+            With<Platform>,
+            With<PressurePlate>,
+            With<Door>,
+            With<Pickup>,
+            With<CampaignGoal>,
+            With<Player>,
+        )>,
+    >,
+) {
+    let Some(timer) = campaign.transition.as_mut() else {
+        return;
+    };
+    if !timer.tick(time.delta()).finished() {
+        return;
+    }
+
+    if campaign.campaign.is_last(campaign.index) {
+        info!("🏆 Campaign '{}' complete!", campaign.campaign.name);
+        notice.msg = Some(format!("🏆 {} complete!", campaign.campaign.name));
+        next_state.set(AppState::Lobby);
+        return;
+    }
+
+    let next_index = campaign.index + 1;
+    let Some(next_level) = campaign.campaign.level(next_index) else {
+        error!("🏁 Campaign level {} is not a built-in level", next_index);
+        next_state.set(AppState::Lobby);
+        return;
+    };
+
+    for entity in level_entities.iter() {
+        commands.entity(entity).despawn();
+    }
+    spawn_level(&mut commands, &next_level, config.pickups);
+    spawn_player_and_goal(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &next_level,
+        &settings,
+    );
+    info!(
+        "🏁 Campaign level {}/{}: {}",
+        next_index + 1,
+        campaign.campaign.levels.len(),
+        next_level.name
+    );
+
+    campaign.index = next_index;
+    campaign.elapsed = 0.0;
+    campaign.transition = None;
+    commands.insert_resource(next_level);
+}
+
+fn update_campaign_hud(
+    campaign: Res<ActiveCampaign>,
+    level: Res<LevelDefinition>,
+    settings: Res<ClientSettings>,
+    mut hud: Query<&mut Text, With<CampaignHudText>>,
+) {
+    let Ok(mut text) = hud.single_mut() else {
+        return;
+    };
+    let best = settings
+        .campaign
+        .best_times
+        .get(&level.name)
+        .map(|t| format!("{:.2}s", t))
+        .unwrap_or_else(|| "--".to_string());
+
+    **text = if campaign.transition.is_some() {
+        format!(
+            "✅ {} complete in {:.2}s (best {})",
+            level.name, campaign.elapsed, best
+        )
+    } else {
+        format!(
+            "🏁 {} {}/{} - {}   ⏱ {:.2}s   best {}   [Esc] quit",
+            campaign.campaign.name,
+            campaign.index + 1,
+            campaign.campaign.levels.len(),
+            level.name,
+            campaign.elapsed,
+            best
+        )
+    };
+}
+
+fn quit_campaign(keys: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<AppState>>) {
+    if keys.just_pressed(KeyCode::Escape) {
+        info!("🏁 Leaving campaign");
+        next_state.set(AppState::Lobby);
+    }
+}
+
+fn cleanup_campaign(
+    mut commands: Commands,
+    campaign: Option<Res<ActiveCampaign>>,
+    entities: Query<Entity, Or<(With<CampaignEntity>, With<Platform>)>>,
+) {
+    if campaign.is_none() {
+        return;
+    }
+    for entity in entities.iter() {
+        commands.entity(entity).despawn();
+    }
+    commands.remove_resource::<ActiveCampaign>();
+}
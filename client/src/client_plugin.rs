@@ -1,3 +1,4 @@
+use bevy::asset::LoadState;
 use bevy::prelude::*;
 
 #[cfg(feature = "bevygap")]
@@ -8,21 +9,68 @@ use bevygap_client_plugin::prelude::BevygapClientConfig;
 
 use leafwing_input_manager::prelude::*;
 
+use crate::account::AccountPlugin;
+use crate::asset_integrity::{AssetIntegrity, AssetIntegrityPlugin};
+use crate::camera::CameraFollowPlugin;
+use crate::campaign::CampaignPlugin;
+#[cfg(feature = "bevygap")]
+use crate::clock_sync::ClockSyncPlugin;
+use crate::connection::ConnectionPlugin;
+use crate::coop::CoopPlugin;
+use crate::countdown::CountdownPlugin;
+use crate::fall_out::FallOutPlugin;
+use crate::game_config::GameConfigPlugin;
+use crate::ghost::GhostPlugin;
+use crate::graphics::GraphicsPlugin;
+#[cfg(feature = "bevygap")]
+use crate::highlights::HighlightsPlugin;
+#[cfg(feature = "bevygap")]
+use crate::input_delay::InputDelayPlugin;
+#[cfg(feature = "inspector")]
+use crate::inspector::InspectorPlugin;
+use crate::koth::KothPlugin;
+use crate::lobby_scene::LobbyScenePlugin;
+use crate::local_play::{LocalPlayPlugin, LocalSeat};
+use crate::nameplates::NameplatesPlugin;
+#[cfg(feature = "bevygap")]
+use crate::net_probe::NetProbePlugin;
+use crate::net_stats::NetStatsPlugin;
+use crate::network_config::ClientNetworkConfig;
+use crate::offline::OfflinePlugin;
+use crate::optimistic::OptimisticPlugin;
+use crate::pickups::PickupsPlugin;
+use crate::player_sounds::PlayerSoundsPlugin;
+use crate::race::RacePlugin;
+#[cfg(feature = "bevygap")]
+use crate::ranked::RankedPlugin;
+#[cfg(feature = "debug-gizmos")]
+use crate::reconcile_debug::ReconcileDebugPlugin;
 use crate::screens::{AppState, LobbyPlugin};
+use crate::settings::{ClientSettings, SettingsPlugin};
+#[cfg(feature = "bevygap")]
+use crate::shutdown::ShutdownPlugin;
+use crate::teams::TeamsPlugin;
+use crate::text_input::TextInputPlugin;
+use crate::time_scale::TimeScalePlugin;
+use crate::vey_animation::{VeyAnimationPlugin, VeyAnimations, VeyClip, VeyState};
+use crate::voice::VoicePlugin;
 use shared::{
-    Platform, Player, PlayerActions, PlayerAnimationState, PlayerColor, PlayerId, PlayerTransform,
-    SharedPlugin,
+    LevelDefinition, Platform, PlatformKind, Player, PlayerActions, PlayerAnimationState,
+    PlayerColor, PlayerId, PlayerTransform, SharedPlugin,
 };
 
+#[cfg(feature = "bevygap")]
+use {lightyear::prelude::MessageReceiver, shared::WorldBounds};
+
+/// Where the camera sits during a match, looking at the origin
+pub(crate) const GAME_CAMERA_POSITION: Vec3 = Vec3::new(0.0, 0.0, 500.0);
+
 // Resource to hold the Vey character model handle and animation graph
 #[derive(Resource)]
-struct VeyModel {
-    scene: Handle<Scene>,
-    animation_graph: Handle<AnimationGraph>,
-    idle_node: AnimationNodeIndex,
-    running_node: AnimationNodeIndex,
-    t_pose_node: AnimationNodeIndex,
-    jumping_node: AnimationNodeIndex,
+pub(crate) struct VeyModel {
+    pub(crate) scene: Handle<Scene>,
+    pub(crate) animation_graph: Handle<AnimationGraph>,
+    pub(crate) idle_node: AnimationNodeIndex,
 }
 
 // Component to mark entities that need the Vey model spawned
@@ -31,20 +79,24 @@ struct VeyModelToLoad;
 
 // Component to mark the actual 3D model entity with animation player
 #[derive(Component)]
-struct VeyModelEntity {
-    animation_player: Entity,
+pub(crate) struct VeyModelEntity {
+    /// `Entity::PLACEHOLDER` for the capsule fallback
+    pub(crate) animation_player: Entity,
 }
 
-#[derive(Resource, Default)]
-struct FloorSpawned(bool);
-
 pub struct ClientPlugin;
 
 impl Plugin for ClientPlugin {
     fn build(&self, app: &mut App) {
-        // Get matchmaker URL from browser location
-        let matchmaker_url = get_matchmaker_url();
-        info!("Matchmaker url: {}", matchmaker_url);
+        // Endpoints from the config file, the environment or the page's address
+        let network_config = ClientNetworkConfig::current();
+        let matchmaker_url = crate::http::matchmaker_ws_url();
+        info!(
+            "Lobby service: {}, matchmaker: {}",
+            crate::http::http_base(),
+            matchmaker_url
+        );
+        app.insert_resource(network_config);
 
         // Basic Bevy plugins
         app.add_plugins(DefaultPlugins.set(WindowPlugin {
@@ -64,12 +116,125 @@ impl Plugin for ClientPlugin {
         #[cfg(feature = "bevygap")]
         app.add_plugins(BevygapClientPlugin);
 
+        // Hold inputs back a few ticks so they reach everyone before they are needed
+        #[cfg(feature = "bevygap")]
+        app.add_plugins(InputDelayPlugin);
+
+        // Nudge the fixed tick rate when the server finds our clock running fast or slow
+        #[cfg(feature = "bevygap")]
+        app.add_plugins(ClockSyncPlugin);
+
+        // Probes for the connection quality summary in the server's match report
+        #[cfg(feature = "bevygap")]
+        app.add_plugins(NetProbePlugin);
+
+        // F3: ping, jitter, loss and tick offset, also kept in the `NetworkStats` resource
+        app.add_plugins(NetStatsPlugin);
+
+        // Countdown when the game server is about to shut down
+        #[cfg(feature = "bevygap")]
+        app.add_plugins(ShutdownPlugin);
+
+        // Where the player placed when a ranked match ends
+        #[cfg(feature = "bevygap")]
+        app.add_plugins(RankedPlugin);
+
+        // Awards handed out when any match ends
+        #[cfg(feature = "bevygap")]
+        app.add_plugins(HighlightsPlugin);
+
+        // Camera shake and fade when we fall out of the world and respawn
+        app.add_plugins(FallOutPlugin);
+
+        // 3-2-1-GO! before a match, nobody moves until it is over
+        app.add_plugins(CountdownPlugin);
+
+        // Persistent settings (campaign progress, ...) - loaded before the lobby needs them
+        app.add_plugins(SettingsPlugin);
+
+        // Graphics quality presets and the opt-in hardware report behind their defaults
+        app.add_plugins(GraphicsPlugin);
+
+        // Vey idling behind the lobby menus, doubles as a check that the model loads
+        app.add_plugins(LobbyScenePlugin);
+
+        // Text fields for the lobby screens (room id, name, password)
+        app.add_plugins(TextInputPlugin);
+
         // Lobby system - handles 4-player lobby UI and matchmaking
         app.add_plugins(LobbyPlugin);
 
+        // Matchmaking and the game server connection, one state for every screen
+        app.add_plugins(ConnectionPlugin);
+
+        // Display name (editable on the settings page) and the web account behind it
+        app.add_plugins(AccountPlugin);
+
+        // Offline single-player campaign
+        app.add_plugins(CampaignPlugin);
+
+        // Local play: 1-4 players on this machine
+        app.add_plugins(LocalPlayPlugin);
+
+        // Match camera: follows the local player, or frames every local player
+        app.add_plugins(CameraFollowPlugin);
+
+        // Slow motion, pause and tick stepping for local play and the campaign
+        app.add_plugins(TimeScalePlugin);
+
+        // Co-op pressure plates and doors (visuals and HUD prompts)
+        app.add_plugins(CoopPlugin);
+
+        // Local effects shown before the server confirms them, rolled back if it never does
+        app.add_plugins(OptimisticPlugin);
+
+        // Coins: visuals, sound, score HUD and predicted pickups
+        app.add_plugins(PickupsPlugin);
+
+        // Ghost mode: role messages, role HUD and hazard visuals
+        app.add_plugins(GhostPlugin);
+
+        // King of the hill: zone visuals and contested indicator
+        app.add_plugins(KothPlugin);
+
+        // Race: checkpoint rings and live standings
+        app.add_plugins(RacePlugin);
+
+        // Vey's animation states, blended into each other, and the landing squash
+        app.add_plugins(VeyAnimationPlugin);
+
+        // Jump and footstep sounds, other players' positioned around the local one
+        app.add_plugins(PlayerSoundsPlugin);
+
+        // Room voice chat with push-to-talk and per-player mute
+        app.add_plugins(VoicePlugin);
+
+        // Other players' names above their heads
+        app.add_plugins(NameplatesPlugin);
+
+        // Team rules from the server and our team on the HUD
+        app.add_plugins(TeamsPlugin);
+
+        // Physics from the server, standard ones for local games
+        app.add_plugins(GameConfigPlugin);
+
+        // F3: server-confirmed player outlines and a prediction correction graph
+        #[cfg(feature = "debug-gizmos")]
+        app.add_plugins(ReconcileDebugPlugin);
+
+        // F12: egui inspector for entities, the lobby state and the simulation resources
+        #[cfg(feature = "inspector")]
+        app.add_plugins(InspectorPlugin);
+
+        // Verify downloaded assets against the manifest baked in at build time
+        app.add_plugins(AssetIntegrityPlugin);
+
         // Shared game logic
         app.add_plugins(SharedPlugin);
 
+        // Without a server the client spawns the world and runs the server's rules itself
+        app.add_plugins(OfflinePlugin);
+
         #[cfg(feature = "bevygap")]
         {
             app.insert_resource(BevygapClientConfig {
@@ -83,21 +248,25 @@ impl Plugin for ClientPlugin {
         // Camera setup - needed for both Lobby UI and InGame
         app.add_systems(Startup, (setup_camera, load_vey_model));
 
-        // Game setup systems (only run when in game)
-        app.add_systems(OnEnter(AppState::InGame), setup_game);
         app.add_systems(
             Update,
             (
                 spawn_player_visual,
+                recolor_players,
                 spawn_platform_visual,
+                update_platform_visual,
                 update_player_visual,
                 handle_player_spawn,
                 update_vey_model_transform,
-                update_vey_model_animations, // Renamed and updated system
             )
                 .run_if(in_state(AppState::InGame)),
         );
-        app.insert_resource(FloorSpawned::default());
+        app.init_resource::<LevelDefinition>();
+
+        // Level edges: the chosen level's until the server sends the ones it plays with
+        app.add_systems(Update, sync_world_bounds);
+        #[cfg(feature = "bevygap")]
+        app.add_systems(Update, receive_world_bounds);
 
         // Remove auto-connect - now handled by lobby UI
         // app.add_systems(Startup, |mut commands: Commands| {
@@ -111,31 +280,11 @@ impl Plugin for ClientPlugin {
     }
 }
 
-fn get_matchmaker_url() -> String {
-    #[cfg(target_arch = "wasm32")]
-    {
-        info!("Creating matchmaker url from window.location");
-        let window = web_sys::window().expect("no global `window` exists");
-        let location = window.location();
-        let protocol = if location.protocol().unwrap() == "https:" {
-            "wss"
-        } else {
-            "ws"
-        };
-        let host = location.host().unwrap();
-        format!("{}://{}/matchmaker/ws", protocol, host)
-    }
-    #[cfg(not(target_arch = "wasm32"))]
-    {
-        "ws://localhost:3000/matchmaker/ws".to_string()
-    }
-}
-
 fn setup_camera(mut commands: Commands) {
     // Spawn 3D camera positioned for 2.5D platformer view
     commands.spawn((
         Camera3d::default(),
-        Transform::from_xyz(0.0, 0.0, 500.0).looking_at(Vec3::ZERO, Vec3::Y),
+        Transform::from_translation(GAME_CAMERA_POSITION).looking_at(Vec3::ZERO, Vec3::Y),
         Camera {
             clear_color: ClearColorConfig::Custom(Color::srgb(0.1, 0.2, 0.3)),
             ..default()
@@ -182,57 +331,62 @@ fn load_vey_model(
         scene: vey_scene,
         animation_graph: animation_graph_handle,
         idle_node,
-        running_node,
-        t_pose_node,
-        jumping_node,
     });
 
+    // There is no falling clip yet, falling holds the end of the jump
+    let mut animations = VeyAnimations::new(VeyClip::looping(idle_node));
+    animations
+        .set_clip(VeyState::Running, VeyClip::looping(running_node))
+        .set_clip(VeyState::Jumping, VeyClip::once(jumping_node))
+        .set_clip(VeyState::Falling, VeyClip::once(jumping_node))
+        .set_clip(VeyState::Stunned, VeyClip::looping(t_pose_node));
+    commands.insert_resource(animations);
+
     info!("🎭 Loading Vey character model with four animations: idle (Animation0), t-pose (Animation1), running (Animation2), jumping (Animation3)");
 }
 
-fn setup_game(mut commands: Commands) {
-    // Spawn some platforms for the level (only when entering game)
-    spawn_platforms(&mut commands);
+fn sync_world_bounds(mut commands: Commands, level: Res<LevelDefinition>) {
+    if level.is_changed() {
+        commands.insert_resource(level.bounds);
+    }
 }
 
-fn spawn_platforms(commands: &mut Commands) {
-    // Floor is handled in the physics system at y = -200
-
-    // Add some floating platforms
-    let platform_positions = vec![
-        Vec3::new(-200.0, -100.0, 0.0),
-        Vec3::new(0.0, 0.0, 0.0),
-        Vec3::new(200.0, -50.0, 0.0),
-        Vec3::new(-300.0, 50.0, 0.0),
-        Vec3::new(300.0, 100.0, 0.0),
-    ];
-
-    for pos in platform_positions {
-        commands.spawn((Platform, Transform::from_translation(pos)));
+#[cfg(feature = "bevygap")]
+fn receive_world_bounds(
+    mut commands: Commands,
+    mut receivers: Query<&mut MessageReceiver<WorldBounds>>,
+) {
+    for mut receiver in receivers.iter_mut() {
+        for bounds in receiver.receive() {
+            info!(
+                "🗺️ Level spans x {}..{}, floor at y {}",
+                bounds.min_x, bounds.max_x, bounds.floor_y
+            );
+            commands.insert_resource(bounds);
+        }
     }
 }
 
-// Handle when a new player spawns (add input to local player only)
+// Handle when a new player spawns (add input to local player only). Players of a local
+// game bring their own input, one map per seat.
 fn handle_player_spawn(
     mut commands: Commands,
-    new_players: Query<(Entity, &PlayerId), Added<Player>>,
+    settings: Res<ClientSettings>,
+    new_players: Query<(Entity, &PlayerId), (Added<Player>, Without<LocalSeat>)>,
 ) {
     for (entity, player_id) in new_players.iter() {
         // Only add input handling to the first player (local player)
         if player_id.id == 0 {
+            // Keys and gamepad buttons come from the settings screen
             commands.entity(entity).insert((
-                InputMap::<PlayerActions>::default()
-                    .with(PlayerActions::MoveLeft, KeyCode::KeyA)
-                    .with(PlayerActions::MoveLeft, KeyCode::ArrowLeft)
-                    .with(PlayerActions::MoveRight, KeyCode::KeyD)
-                    .with(PlayerActions::MoveRight, KeyCode::ArrowRight)
-                    .with(PlayerActions::Jump, KeyCode::Space)
-                    .with(PlayerActions::Jump, KeyCode::KeyW)
-                    .with(PlayerActions::Jump, KeyCode::ArrowUp),
+                settings.controls.input_map(),
                 ActionState::<PlayerActions>::default(),
             ));
 
-            info!("🎮 Local player {} spawned with controls: A/D or Arrow keys to move, Space/W to jump", player_id.id);
+            info!(
+                "🎮 Local player {} spawned with controls: {:?}",
+                player_id.id, settings.controls
+            );
         } else {
             info!("👤 Remote player {} spawned", player_id.id);
         }
@@ -245,26 +399,31 @@ fn spawn_player_visual(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     vey_model: Option<Res<VeyModel>>,
+    asset_server: Res<AssetServer>,
+    integrity: Res<AssetIntegrity>,
     new_players: Query<(Entity, &PlayerColor, &PlayerTransform, &PlayerId), Added<Player>>,
 ) {
+    // Never fall back to the capsule silently - find out why the model can't be used
+    let model_problem = vey_model.as_ref().and_then(|vey_model| {
+        integrity
+            .failure("vey.glb")
+            .map(str::to_string)
+            .or_else(|| match asset_server.get_load_state(&vey_model.scene) {
+                Some(LoadState::Failed(err)) => Some(err.to_string()),
+                _ => None,
+            })
+    });
+    let usable_model = vey_model.as_ref().filter(|_| model_problem.is_none());
+
     for (entity, color, transform, player_id) in new_players.iter() {
-        // Determine color variation for multiplayer
-        let final_color = if player_id.id == 0 {
-            color.color // Original color for player 1
-        } else {
-            // Lighter variant for player 2+
-            Color::srgb(
-                (color.color.to_srgba().red + 0.3).min(1.0),
-                (color.color.to_srgba().green + 0.3).min(1.0),
-                (color.color.to_srgba().blue + 0.3).min(1.0),
-            )
-        };
+        let final_color = player_tint(color, player_id);
 
-        let model_entity = if let Some(vey_model) = &vey_model {
+        let model_entity = if let Some(vey_model) = usable_model {
             // Use GLB model if available
             let animation_player = commands
                 .spawn((
                     AnimationPlayer::default(),
+                    AnimationTransitions::new(),
                     AnimationGraphHandle(vey_model.animation_graph.clone()),
                 ))
                 .id();
@@ -281,10 +440,17 @@ fn spawn_player_visual(
             model_entity
         } else {
             // Fallback: Create a simple geometric character (capsule)
-            info!(
-                "🎭 GLTF model not loaded, using geometric fallback for player {}",
-                player_id.id
-            );
+            if let Some(problem) = &model_problem {
+                error!(
+                    "🎭 Vey model unusable ({}), using geometric fallback for player {}",
+                    problem, player_id.id
+                );
+            } else {
+                info!(
+                    "🎭 GLTF model not loaded, using geometric fallback for player {}",
+                    player_id.id
+                );
+            }
             commands
                 .spawn((
                     Mesh3d(meshes.add(Capsule3d::new(8.0, 40.0))), // Simple capsule character
@@ -312,7 +478,7 @@ fn spawn_player_visual(
             ))
             .add_child(model_entity);
 
-        if vey_model.is_some() {
+        if usable_model.is_some() {
             info!(
                 "🎭 Spawned 3D Vey GLB model for player {} with animation support",
                 player_id.id
@@ -326,36 +492,97 @@ fn spawn_player_visual(
     }
 }
 
+// Color variation for multiplayer
+fn player_tint(color: &PlayerColor, player_id: &PlayerId) -> Color {
+    if player_id.id == 0 {
+        color.color // Original color for player 1
+    } else {
+        // Lighter variant for player 2+
+        Color::srgb(
+            (color.color.to_srgba().red + 0.3).min(1.0),
+            (color.color.to_srgba().green + 0.3).min(1.0),
+            (color.color.to_srgba().blue + 0.3).min(1.0),
+        )
+    }
+}
+
+// Players pick their color once connected, after their character already exists
+fn recolor_players(
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    players: Query<(Ref<PlayerColor>, &PlayerId, &Children)>,
+    models: Query<&MeshMaterial3d<StandardMaterial>, With<VeyModelEntity>>,
+) {
+    for (color, player_id, children) in players.iter() {
+        if !color.is_changed() || color.is_added() {
+            continue;
+        }
+        for child in children.iter() {
+            let Ok(material) = models.get(child) else {
+                continue;
+            };
+            if let Some(material) = materials.get_mut(&material.0) {
+                material.base_color = player_tint(&color, player_id);
+            }
+        }
+    }
+}
+
 // Spawn 3D visual representation for platforms
 fn spawn_platform_visual(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    new_platforms: Query<(Entity, &Transform), Added<Platform>>,
-    mut floor_spawned: ResMut<FloorSpawned>,
+    new_platforms: Query<(Entity, &Platform), Added<Platform>>,
 ) {
-    for (entity, transform) in new_platforms.iter() {
+    for (entity, platform) in new_platforms.iter() {
         commands.entity(entity).insert((
-            Mesh3d(meshes.add(Cuboid::new(200.0, 20.0, 50.0))), // 3D cuboid for platforms
+            Mesh3d(meshes.add(Cuboid::new(platform.width, 20.0, 50.0))), // 3D cuboid for platforms
             MeshMaterial3d(materials.add(StandardMaterial {
-                base_color: Color::srgb(0.3, 0.3, 0.3),
+                base_color: platform_color(platform),
                 ..default()
             })),
-            *transform,
+            // Moving platforms are then positioned by the shared motion system
+            Transform::from_translation(platform.origin.extend(0.0)),
+            Visibility::default(),
         ));
     }
+}
 
-    // Also spawn a visual floor (only once on startup)
-    if !floor_spawned.0 {
-        floor_spawned.0 = true;
-        commands.spawn((
-            Mesh3d(meshes.add(Cuboid::new(1000.0, 20.0, 100.0))), // 3D floor
-            MeshMaterial3d(materials.add(StandardMaterial {
-                base_color: Color::srgb(0.2, 0.2, 0.2),
-                ..default()
-            })),
-            Transform::from_xyz(0.0, -210.0, 0.0),
-        ));
+// Each platform kind gets its own color so players can tell what they're jumping on
+fn platform_color(platform: &Platform) -> Color {
+    match platform.kind {
+        PlatformKind::Static => Color::srgb(0.3, 0.3, 0.3),
+        PlatformKind::Moving { .. } => Color::srgb(0.2, 0.4, 0.7),
+        // Crumbling platforms turn red once they start to give way
+        PlatformKind::Crumbling { .. } if platform.crumble_timer.is_some() => {
+            Color::srgb(0.7, 0.25, 0.2)
+        }
+        PlatformKind::Crumbling { .. } => Color::srgb(0.55, 0.4, 0.25),
+        PlatformKind::Bouncy { .. } => Color::srgb(0.3, 0.75, 0.35),
+    }
+}
+
+// Crumbled platforms disappear until they respawn
+fn update_platform_visual(
+    mut platforms: Query<
+        (
+            &Platform,
+            &MeshMaterial3d<StandardMaterial>,
+            &mut Visibility,
+        ),
+        Changed<Platform>,
+    >,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (platform, material, mut visibility) in platforms.iter_mut() {
+        *visibility = if platform.solid {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if let Some(material) = materials.get_mut(&material.0) {
+            material.base_color = platform_color(platform);
+        }
     }
 }
 
@@ -387,54 +614,6 @@ fn update_vey_model_transform(
     }
 }
 
-// Update Vey model animations based on player state
-fn update_vey_model_animations(
-    player_query: Query<
-        (&PlayerAnimationState, &Children),
-        (With<Player>, Changed<PlayerAnimationState>),
-    >,
-    model_query: Query<&VeyModelEntity, Without<Player>>,
-    mut animation_players: Query<&mut AnimationPlayer>,
-    mut transforms: Query<&mut Transform, With<VeyModelEntity>>,
-    vey_model: Option<Res<VeyModel>>,
-) {
-    let Some(vey_model) = vey_model else {
-        return;
-    };
-
-    for (anim_state, children) in player_query.iter() {
-        for child in children.iter() {
-            if let Ok(vey_entity) = model_query.get(child) {
-                // Update model orientation (mirroring for left/right movement)
-                if let Ok(mut model_transform) = transforms.get_mut(child) {
-                    let scale_x = if anim_state.facing_left { -50.0 } else { 50.0 };
-                    model_transform.scale = Vec3::new(scale_x, 50.0, 50.0);
-                }
-
-                // Update animations
-                if vey_entity.animation_player != Entity::PLACEHOLDER {
-                    if let Ok(mut animation_player) =
-                        animation_players.get_mut(vey_entity.animation_player)
-                    {
-                        // Determine which animation to play based on state
-                        let (target_node, anim_name) = if anim_state.is_jumping {
-                            (vey_model.jumping_node, "jumping") // Use jumping animation for jumping/falling
-                        } else if anim_state.is_moving {
-                            (vey_model.running_node, "running")
-                        } else {
-                            (vey_model.idle_node, "idle")
-                        };
-
-                        // Play the animation
-                        animation_player.play(target_node).repeat();
-                        info!("🎬 Playing {} animation for player", anim_name);
-                    }
-                }
-            }
-        }
-    }
-}
-
 // ==== CUSTOM CLIENT RENDERING AREA - Add your visual effects and UI here ====
 // Example: Particle effects, UI overlays, animations, etc.
 //
@@ -0,0 +1,165 @@
+// Time dilation: the client reports how many fixed ticks it simulated, and when the
+// server finds it consistently fast or slow it sends a `TickRateHint`. The fixed timestep
+// then eases towards the hinted rate, a little at a time so movement doesn't visibly
+// change speed. Dilation only changes how often a tick runs, never what it simulates.
+//
+// The server also sends its simulation tick and world checksum. We take its tick when
+// ours is far off, and otherwise compare checksums: a mismatch means the shared
+// simulation came out differently here.
+use bevy::prelude::*;
+use lightyear::prelude::*;
+use std::time::Duration;
+
+use crate::net_stats::NetworkStats;
+use crate::screens::AppState;
+use shared::{
+    Channel1, ChecksumHistory, ClockReport, SimTick, TickRateHint, TickSync, CLOCK_REPORT_SECS,
+    MAX_TICK_RATE_ADJUST, TICK_SYNC_INTERVAL,
+};
+
+// Fastest the tick rate moves towards its target, per second
+const EASE_PER_SEC: f64 = 0.01;
+// Our tick may be this far from the server's before we jump to it
+const RESYNC_TICKS: u32 = 2 * TICK_SYNC_INTERVAL;
+// A tick sync is already old when it arrives, and predicted players run ahead of the
+// server anyway, so we jump a little past it
+const SYNC_LEAD_TICKS: u32 = 6;
+
+#[derive(Resource)]
+struct ClockSync {
+    fixed_ticks: u32,
+    report_timer: Timer,
+    // Timestep before any dilation, captured the first time it is changed
+    base_timestep: Option<Duration>,
+    // Tick rate multipliers, 1 is the normal rate
+    target: f64,
+    current: f64,
+    // Checksums that didn't match the server's this match
+    desyncs: u32,
+}
+
+impl Default for ClockSync {
+    fn default() -> Self {
+        Self {
+            fixed_ticks: 0,
+            report_timer: Timer::from_seconds(CLOCK_REPORT_SECS, TimerMode::Repeating),
+            base_timestep: None,
+            target: 1.0,
+            current: 1.0,
+            desyncs: 0,
+        }
+    }
+}
+
+pub struct ClockSyncPlugin;
+
+impl Plugin for ClockSyncPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ClockSync>()
+            .add_systems(
+                FixedUpdate,
+                count_fixed_ticks.run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(
+                Update,
+                (
+                    send_clock_report,
+                    receive_tick_rate_hints,
+                    ease_timestep,
+                    receive_tick_sync,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(OnExit(AppState::InGame), reset_clock);
+    }
+}
+
+fn count_fixed_ticks(mut sync: ResMut<ClockSync>) {
+    sync.fixed_ticks = sync.fixed_ticks.wrapping_add(1);
+}
+
+fn send_clock_report(
+    time: Res<Time<Real>>,
+    mut sync: ResMut<ClockSync>,
+    mut senders: Query<&mut MessageSender<ClockReport>, With<Connected>>,
+) {
+    if !sync.report_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    for mut sender in senders.iter_mut() {
+        sender.send::<Channel1>(ClockReport {
+            fixed_ticks: sync.fixed_ticks,
+        });
+    }
+}
+
+fn receive_tick_rate_hints(
+    mut sync: ResMut<ClockSync>,
+    mut receivers: Query<&mut MessageReceiver<TickRateHint>>,
+) {
+    for mut receiver in receivers.iter_mut() {
+        for hint in receiver.receive() {
+            // Hints are relative to the rate we already run at
+            sync.target = (sync.target * hint.speed as f64)
+                .clamp(1.0 - MAX_TICK_RATE_ADJUST, 1.0 + MAX_TICK_RATE_ADJUST);
+            info!("⏱️ Server hinted tick rate {:.3}x", sync.target);
+        }
+    }
+}
+
+fn ease_timestep(
+    time: Res<Time<Real>>,
+    mut sync: ResMut<ClockSync>,
+    mut fixed: ResMut<Time<Fixed>>,
+) {
+    if sync.current == sync.target {
+        return;
+    }
+    let step = EASE_PER_SEC * time.delta_secs_f64();
+    sync.current = if sync.current < sync.target {
+        (sync.current + step).min(sync.target)
+    } else {
+        (sync.current - step).max(sync.target)
+    };
+    let base = *sync.base_timestep.get_or_insert(fixed.timestep());
+    fixed.set_timestep(base.div_f64(sync.current));
+}
+
+fn receive_tick_sync(
+    mut sync: ResMut<ClockSync>,
+    mut tick: ResMut<SimTick>,
+    mut history: ResMut<ChecksumHistory>,
+    mut stats: ResMut<NetworkStats>,
+    mut receivers: Query<&mut MessageReceiver<TickSync>>,
+) {
+    for mut receiver in receivers.iter_mut() {
+        for server in receiver.receive() {
+            stats.tick_offset = Some(tick.0.wrapping_sub(server.tick) as i32);
+            if tick.0.abs_diff(server.tick) > RESYNC_TICKS {
+                info!("⏱️ Taking the server's tick {}", server.tick);
+                tick.0 = server.tick.wrapping_add(SYNC_LEAD_TICKS);
+                history.clear();
+                continue;
+            }
+            // Ticks we haven't simulated yet, or too long ago, can't be compared
+            let Some(checksum) = history.get(server.tick) else {
+                continue;
+            };
+            if checksum != server.checksum {
+                sync.desyncs += 1;
+                warn!(
+                    "⚠️ Desync at tick {}: {:016x} here, {:016x} on the server ({} this match)",
+                    server.tick, checksum, server.checksum, sync.desyncs
+                );
+            }
+        }
+    }
+}
+
+fn reset_clock(mut sync: ResMut<ClockSync>, mut fixed: ResMut<Time<Fixed>>) {
+    if let Some(base) = sync.base_timestep {
+        fixed.set_timestep(base);
+    }
+    *sync = ClockSync::default();
+}
@@ -0,0 +1,156 @@
+// 🔌 Where the client is on its way to a game server. `ConnectionState` is the one place
+// screens read it from, and it only changes through a `ConnectionEvent`: the lobby sends
+// them for what the player does, and with bevygap the matchmaker's state and lightyear's
+// client entity send the rest. Every change goes out as a `ConnectionChanged` for the
+// screens that react to it.
+use bevy::prelude::*;
+
+use crate::screens::AppState;
+
+#[cfg(feature = "bevygap")]
+use {
+    bevygap_client_plugin::prelude::BevygapClientState,
+    lightyear::prelude::{Client, Connected, Disconnected},
+};
+
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+pub enum ConnectionState {
+    /// Not looking for a server
+    #[default]
+    Idle,
+    /// Waiting for the matchmaker to find or deploy a server
+    Matchmaking,
+    /// The matchmaker handed out a server, connecting to it
+    Connecting,
+    /// On a game server
+    Connected,
+    /// The last attempt failed, with the error code or `ServiceError` JSON behind it
+    Failed(String),
+}
+
+impl ConnectionState {
+    /// The state `event` leads to, None if it doesn't apply in this one
+    pub fn next(&self, event: &ConnectionEvent) -> Option<ConnectionState> {
+        match (self, event) {
+            (Self::Idle | Self::Failed(_), ConnectionEvent::MatchmakingStarted) => {
+                Some(Self::Matchmaking)
+            }
+            (Self::Matchmaking, ConnectionEvent::ServerAssigned) => Some(Self::Connecting),
+            // Without bevygap there is nothing in between
+            (Self::Matchmaking | Self::Connecting, ConnectionEvent::Connected) => {
+                Some(Self::Connected)
+            }
+            (
+                Self::Matchmaking | Self::Connecting | Self::Connected,
+                ConnectionEvent::Failed(error),
+            ) => Some(Self::Failed(error.clone())),
+            (Self::Idle, ConnectionEvent::Reset) => None,
+            (_, ConnectionEvent::Reset) => Some(Self::Idle),
+            _ => None,
+        }
+    }
+
+    /// Looking for or connecting to a server, while the lobby shows the matchmaking banner
+    pub fn is_searching(&self) -> bool {
+        matches!(
+            self,
+            ConnectionState::Matchmaking | ConnectionState::Connecting
+        )
+    }
+}
+
+/// Something that moves `ConnectionState` along
+#[derive(Event, Clone, Debug, PartialEq)]
+pub enum ConnectionEvent {
+    /// The player queued or a room started
+    MatchmakingStarted,
+    /// The matchmaker answered with a server and a connect token
+    ServerAssigned,
+    Connected,
+    /// The matchmaker refused or gave up, we gave up waiting, or the server dropped us
+    Failed(String),
+    /// The player cancelled, or is back in the lobby after a match
+    Reset,
+}
+
+/// `ConnectionState` changed
+#[derive(Event, Clone, Debug, PartialEq)]
+pub struct ConnectionChanged {
+    pub from: ConnectionState,
+    pub to: ConnectionState,
+}
+
+pub struct ConnectionPlugin;
+
+impl Plugin for ConnectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ConnectionState>()
+            .add_event::<ConnectionEvent>()
+            .add_event::<ConnectionChanged>()
+            .add_systems(OnEnter(AppState::Lobby), reset_connection)
+            .add_systems(Update, apply_connection_events);
+        #[cfg(feature = "bevygap")]
+        app.add_systems(
+            Update,
+            (follow_matchmaker, follow_client).before(apply_connection_events),
+        );
+    }
+}
+
+fn apply_connection_events(
+    mut events: EventReader<ConnectionEvent>,
+    mut state: ResMut<ConnectionState>,
+    mut changed: EventWriter<ConnectionChanged>,
+) {
+    for event in events.read() {
+        let Some(next) = state.next(event) else {
+            debug!("🔌 Ignoring {:?} while {:?}", event, *state);
+            continue;
+        };
+        info!("🔌 Connection {:?} -> {:?}", *state, next);
+        changed.write(ConnectionChanged {
+            from: state.clone(),
+            to: next.clone(),
+        });
+        *state = next;
+    }
+}
+
+// Back in the lobby the last match is over
+fn reset_connection(mut events: EventWriter<ConnectionEvent>) {
+    events.write(ConnectionEvent::Reset);
+}
+
+#[cfg(feature = "bevygap")]
+fn follow_matchmaker(
+    bevygap_state: Res<State<BevygapClientState>>,
+    mut events: EventWriter<ConnectionEvent>,
+) {
+    if !bevygap_state.is_changed() {
+        return;
+    }
+    match bevygap_state.get() {
+        BevygapClientState::ReadyToConnect => {
+            events.write(ConnectionEvent::ServerAssigned);
+        }
+        BevygapClientState::Error(code, message) => {
+            warn!("🔌 Matchmaker error {}: {}", code, message);
+            events.write(ConnectionEvent::Failed(message.clone()));
+        }
+        _ => {}
+    }
+}
+
+#[cfg(feature = "bevygap")]
+fn follow_client(
+    connected: Query<(), (With<Client>, Added<Connected>)>,
+    disconnected: Query<(), (With<Client>, Added<Disconnected>)>,
+    mut events: EventWriter<ConnectionEvent>,
+) {
+    if !connected.is_empty() {
+        events.write(ConnectionEvent::Connected);
+    }
+    if !disconnected.is_empty() {
+        events.write(ConnectionEvent::Failed("DISCONNECTED".to_string()));
+    }
+}
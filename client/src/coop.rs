@@ -0,0 +1,155 @@
+use bevy::prelude::*;
+
+use crate::screens::AppState;
+use shared::{Door, PressurePlate, DOOR_HEIGHT, DOOR_WIDTH, PLATE_WIDTH};
+
+// Plates sit under the player's feet, the player's transform is its center
+const PLATE_VISUAL_OFFSET: f32 = -17.0;
+
+// Per-trigger material so pressed/released can be recolored individually
+#[derive(Component)]
+struct TriggerMaterial(Handle<StandardMaterial>);
+
+#[derive(Component)]
+struct CoopHudText;
+
+pub struct CoopPlugin;
+
+impl Plugin for CoopPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                spawn_trigger_visuals,
+                update_plate_visuals,
+                update_door_visuals,
+                update_coop_hud,
+            )
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(OnExit(AppState::InGame), cleanup_coop);
+    }
+}
+
+fn plate_color(pressed: bool) -> Color {
+    if pressed {
+        Color::srgb(0.2, 0.9, 0.3)
+    } else {
+        Color::srgb(0.8, 0.2, 0.2)
+    }
+}
+
+fn spawn_trigger_visuals(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    new_plates: Query<(Entity, &PressurePlate, &Transform), Added<PressurePlate>>,
+    new_doors: Query<(Entity, &Transform), Added<Door>>,
+) {
+    for (entity, plate, transform) in new_plates.iter() {
+        let material = materials.add(StandardMaterial {
+            base_color: plate_color(plate.pressed),
+            ..default()
+        });
+        let visual = commands
+            .spawn((
+                Mesh3d(meshes.add(Cuboid::new(PLATE_WIDTH, 6.0, 50.0))),
+                MeshMaterial3d(material.clone()),
+                Transform::from_xyz(0.0, PLATE_VISUAL_OFFSET, 0.0),
+            ))
+            .id();
+        commands
+            .entity(entity)
+            .insert((*transform, Visibility::default(), TriggerMaterial(material)))
+            .add_child(visual);
+    }
+
+    for (entity, transform) in new_doors.iter() {
+        commands.entity(entity).insert((
+            Mesh3d(meshes.add(Cuboid::new(DOOR_WIDTH, DOOR_HEIGHT, 50.0))),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: Color::srgb(0.5, 0.3, 0.7),
+                ..default()
+            })),
+            *transform,
+        ));
+    }
+}
+
+fn update_plate_visuals(
+    plates: Query<(&PressurePlate, &TriggerMaterial), Changed<PressurePlate>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (plate, material) in plates.iter() {
+        if let Some(material) = materials.get_mut(&material.0) {
+            material.base_color = plate_color(plate.pressed);
+        }
+    }
+}
+
+fn update_door_visuals(mut doors: Query<(&Door, &mut Visibility), Changed<Door>>) {
+    for (door, mut visibility) in doors.iter_mut() {
+        *visibility = if door.open {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+    }
+}
+
+// Tell players what the level expects of them while a co-op level is loaded
+fn update_coop_hud(
+    mut commands: Commands,
+    plates: Query<&PressurePlate>,
+    doors: Query<&Door>,
+    mut hud: Query<(Entity, &mut Text), With<CoopHudText>>,
+) {
+    if doors.is_empty() {
+        for (entity, _) in hud.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+
+    let pressed = plates.iter().filter(|p| p.pressed).count();
+    let prompt = if doors.iter().all(|d| d.open) {
+        "🚪 Door open - everyone through!".to_string()
+    } else {
+        format!(
+            "🤝 Co-op: stand on the plates at the same time to open the door ({}/{} pressed)",
+            pressed,
+            plates.iter().count()
+        )
+    };
+
+    if let Ok((_, mut text)) = hud.single_mut() {
+        if **text != prompt {
+            **text = prompt;
+        }
+        return;
+    }
+    commands.spawn((
+        Text::new(prompt),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(Color::srgb(1.0, 0.9, 0.5)),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(16.0),
+            left: Val::Px(16.0),
+            ..default()
+        },
+        CoopHudText,
+    ));
+}
+
+fn cleanup_coop(
+    mut commands: Commands,
+    entities: Query<Entity, Or<(With<CoopHudText>, With<PressurePlate>, With<Door>)>>,
+) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn();
+    }
+}
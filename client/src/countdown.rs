@@ -0,0 +1,112 @@
+// 3-2-1-GO! over the match while the `Countdown` runs. Online the server sends it, local
+// games and the campaign start their own. Movement waits for it in the shared systems.
+use bevy::prelude::*;
+
+#[cfg(feature = "bevygap")]
+use lightyear::prelude::*;
+
+use crate::screens::AppState;
+use shared::{Countdown, SimTick, GO_SECS};
+
+const COUNTDOWN_FONT_SIZE: f32 = 96.0;
+
+#[derive(Component)]
+struct CountdownBanner;
+
+#[derive(Component)]
+struct CountdownText;
+
+pub struct CountdownPlugin;
+
+impl Plugin for CountdownPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            update_countdown_text.run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(OnExit(AppState::InGame), reset_countdown);
+
+        #[cfg(feature = "bevygap")]
+        // Also in the lobby, the countdown can arrive as soon as we connect
+        app.add_systems(Update, receive_countdown);
+    }
+}
+
+#[cfg(feature = "bevygap")]
+fn receive_countdown(
+    mut countdown: ResMut<Countdown>,
+    mut receivers: Query<&mut MessageReceiver<Countdown>>,
+) {
+    for mut receiver in receivers.iter_mut() {
+        for message in receiver.receive() {
+            info!(
+                "🚦 Match countdown, players move from tick {}",
+                message.go_tick
+            );
+            *countdown = message;
+        }
+    }
+}
+
+fn update_countdown_text(
+    mut commands: Commands,
+    tick: Res<SimTick>,
+    countdown: Res<Countdown>,
+    banners: Query<Entity, With<CountdownBanner>>,
+    mut texts: Query<&mut Text, With<CountdownText>>,
+) {
+    let label = if countdown.is_running(*tick) {
+        countdown.seconds_left(*tick).to_string()
+    } else if countdown
+        .secs_since_go(*tick)
+        .is_some_and(|secs| secs < GO_SECS)
+    {
+        "GO!".to_string()
+    } else {
+        for entity in banners.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    };
+
+    if let Ok(mut text) = texts.single_mut() {
+        if **text != label {
+            **text = label;
+        }
+        return;
+    }
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(35.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            CountdownBanner,
+        ))
+        .with_children(|row| {
+            row.spawn((
+                Text::new(label),
+                TextFont {
+                    font_size: COUNTDOWN_FONT_SIZE,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 0.85, 0.3)),
+                CountdownText,
+            ));
+        });
+}
+
+// The next match counts down again, or doesn't
+fn reset_countdown(
+    mut commands: Commands,
+    mut countdown: ResMut<Countdown>,
+    banners: Query<Entity, With<CountdownBanner>>,
+) {
+    *countdown = Countdown::default();
+    for entity in banners.iter() {
+        commands.entity(entity).despawn();
+    }
+}
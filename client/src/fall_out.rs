@@ -0,0 +1,138 @@
+// Falling out of the world: the server puts our player back on a spawn point and tells us
+// what it cost, or offline our own world does. The camera shakes and the screen fades in
+// from black while we get our bearings again.
+use bevy::prelude::*;
+use lightyear::prelude::*;
+
+use crate::camera::CameraRig;
+use crate::screens::AppState;
+use shared::{PlayerFellOut, RespawnEvent};
+
+// How long the shake and the fade last
+const EFFECT_SECS: f32 = 0.8;
+// Furthest the camera is thrown off at the start of the shake
+const SHAKE_DISTANCE: f32 = 12.0;
+// Darkest the screen gets, right after the respawn
+const FADE_ALPHA: f32 = 0.85;
+
+#[derive(Resource)]
+struct FallEffect {
+    timer: Timer,
+}
+
+#[derive(Component)]
+struct FadeOverlay;
+
+pub struct FallOutPlugin;
+
+impl Plugin for FallOutPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlayerFellOut>()
+            .add_systems(
+                Update,
+                (receive_respawns, play_fall_effect)
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(OnExit(AppState::InGame), cleanup_fall_effect);
+    }
+}
+
+fn receive_respawns(
+    mut commands: Commands,
+    mut receivers: Query<&mut MessageReceiver<RespawnEvent>>,
+    mut fell_out: EventReader<PlayerFellOut>,
+    overlays: Query<Entity, With<FadeOverlay>>,
+) {
+    // Every player of an offline game is on this screen
+    let mut latest = fell_out.read().last().map(|fell| fell.penalty.clone());
+    for mut receiver in receivers.iter_mut() {
+        latest = receiver.receive().last().or(latest);
+    }
+    let Some(respawn) = latest else {
+        return;
+    };
+    info!(
+        "🕳️ Fell out of the world: -{} points, +{}s",
+        respawn.points_lost, respawn.seconds_added
+    );
+    for entity in overlays.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let mut penalties = Vec::new();
+    if respawn.points_lost > 0 {
+        penalties.push(format!("-{} points", respawn.points_lost));
+    }
+    if respawn.seconds_added > 0.0 {
+        penalties.push(format!("+{:.0}s", respawn.seconds_added));
+    }
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, FADE_ALPHA)),
+            FadeOverlay,
+        ))
+        .with_children(|overlay| {
+            if penalties.is_empty() {
+                return;
+            }
+            overlay.spawn((
+                Text::new(format!("🕳️ Fell out! {}", penalties.join(", "))),
+                TextFont {
+                    font_size: 28.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 0.4, 0.3)),
+            ));
+        });
+    commands.insert_resource(FallEffect {
+        timer: Timer::from_seconds(EFFECT_SECS, TimerMode::Once),
+    });
+}
+
+// Both fade out together, the shake also gets smaller as it goes
+fn play_fall_effect(
+    mut commands: Commands,
+    time: Res<Time>,
+    effect: Option<ResMut<FallEffect>>,
+    mut rig: ResMut<CameraRig>,
+    mut overlays: Query<(Entity, &mut BackgroundColor), With<FadeOverlay>>,
+) {
+    let Some(mut effect) = effect else {
+        return;
+    };
+    if effect.timer.tick(time.delta()).finished() {
+        commands.remove_resource::<FallEffect>();
+        rig.shake = Vec3::ZERO;
+        for (entity, _) in overlays.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+
+    let strength = effect.timer.fraction_remaining();
+    rig.shake = Vec3::new(
+        rand::random::<f32>() * 2.0 - 1.0,
+        rand::random::<f32>() * 2.0 - 1.0,
+        0.0,
+    ) * SHAKE_DISTANCE
+        * strength;
+    for (_, mut background) in overlays.iter_mut() {
+        background.0 = Color::srgba(0.0, 0.0, 0.0, FADE_ALPHA * strength);
+    }
+}
+
+fn cleanup_fall_effect(mut commands: Commands, overlays: Query<Entity, With<FadeOverlay>>) {
+    commands.remove_resource::<FallEffect>();
+    for entity in overlays.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,44 @@
+// Physics of the match: the server sends its `GameConfig` on connecting, and our prediction
+// has to move players with the same numbers. Local games play with the standard physics.
+use bevy::prelude::*;
+
+#[cfg(feature = "bevygap")]
+use lightyear::prelude::*;
+
+use crate::screens::AppState;
+use shared::GameConfig;
+
+pub struct GameConfigPlugin;
+
+impl Plugin for GameConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(AppState::InGame), reset_game_config);
+
+        #[cfg(feature = "bevygap")]
+        // Also in the lobby, the config arrives as soon as we connect
+        app.add_systems(Update, receive_game_config);
+    }
+}
+
+#[cfg(feature = "bevygap")]
+fn receive_game_config(
+    mut commands: Commands,
+    mut receivers: Query<&mut MessageReceiver<GameConfig>>,
+) {
+    for mut receiver in receivers.iter_mut() {
+        for config in receiver.receive() {
+            if config != GameConfig::default() {
+                info!(
+                    "🪐 Physics: move speed {}, jump force {}, gravity {}, max fall speed {}",
+                    config.move_speed, config.jump_force, config.gravity, config.max_fall_speed
+                );
+            }
+            commands.insert_resource(config);
+        }
+    }
+}
+
+// The next match says again
+fn reset_game_config(mut commands: Commands) {
+    commands.insert_resource(GameConfig::default());
+}
@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+
+#[cfg(feature = "bevygap")]
+use lightyear::prelude::*;
+
+use crate::screens::AppState;
+#[cfg(feature = "bevygap")]
+use shared::RoleAssignment;
+use shared::{Hazard, PlayerRole, HAZARD_RADIUS};
+
+// Role the server gave us in ghost mode, absent in every other mode
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalRole(pub PlayerRole);
+
+#[derive(Component)]
+struct GhostHudText;
+
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (spawn_hazard_visuals, update_ghost_hud).run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(OnExit(AppState::InGame), cleanup_ghost);
+
+        #[cfg(feature = "bevygap")]
+        app.add_systems(Update, receive_role.run_if(in_state(AppState::InGame)));
+    }
+}
+
+#[cfg(feature = "bevygap")]
+fn receive_role(
+    mut commands: Commands,
+    mut receivers: Query<&mut MessageReceiver<RoleAssignment>>,
+) {
+    for mut receiver in receivers.iter_mut() {
+        for message in receiver.receive() {
+            info!("👻 Ghost mode role: {:?}", message.role);
+            commands.insert_resource(LocalRole(message.role));
+        }
+    }
+}
+
+fn spawn_hazard_visuals(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    new_hazards: Query<(Entity, &Transform), Added<Hazard>>,
+) {
+    for (entity, transform) in new_hazards.iter() {
+        commands.entity(entity).insert((
+            Mesh3d(meshes.add(Cone {
+                radius: HAZARD_RADIUS,
+                height: 25.0,
+            })),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: Color::srgb(0.8, 0.1, 0.6),
+                emissive: LinearRgba::rgb(0.5, 0.0, 0.3),
+                ..default()
+            })),
+            *transform,
+        ));
+    }
+}
+
+fn update_ghost_hud(
+    mut commands: Commands,
+    role: Option<Res<LocalRole>>,
+    mut hud: Query<(Entity, &mut Text), With<GhostHudText>>,
+) {
+    let Some(role) = role else {
+        for (entity, _) in hud.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    };
+
+    let prompt = match role.0 {
+        PlayerRole::Ghost => "👻 You are the GHOST - the others can't see you. [E] drop a hazard",
+        PlayerRole::Runner => {
+            "🏃 You are a RUNNER - a hidden ghost is laying traps, watch your step"
+        }
+    };
+
+    if let Ok((_, mut text)) = hud.single_mut() {
+        if **text != prompt {
+            **text = prompt.to_string();
+        }
+        return;
+    }
+    commands.spawn((
+        Text::new(prompt),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(match role.0 {
+            PlayerRole::Ghost => Color::srgb(0.8, 0.6, 1.0),
+            PlayerRole::Runner => Color::srgb(0.6, 1.0, 0.7),
+        }),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(40.0),
+            left: Val::Px(16.0),
+            ..default()
+        },
+        GhostHudText,
+    ));
+}
+
+fn cleanup_ghost(
+    mut commands: Commands,
+    entities: Query<Entity, Or<(With<GhostHudText>, With<Hazard>)>>,
+) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn();
+    }
+    commands.remove_resource::<LocalRole>();
+}
@@ -0,0 +1,235 @@
+// Graphics quality presets and opt-in hardware telemetry. The preset in effect is the one
+// picked on the settings page, else the lobby's recommendation for this GPU class and
+// backend, else `QualityPreset::default_for`. Players who opt in send their GPU and the
+// frame rate they got once per session, and those reports are what the lobby bases its
+// recommendations on.
+use bevy::prelude::*;
+use bevy::render::renderer::RenderAdapterInfo;
+
+use crate::screens::AppState;
+use crate::settings::ClientSettings;
+use shared::{GpuClass, HardwareReport, QualityPreset};
+
+#[cfg(target_arch = "wasm32")]
+use {
+    crate::http::{http_base, ApiRequest},
+    shared::PresetRecommendation,
+    std::cell::RefCell,
+    wasm_bindgen_futures::spawn_local,
+};
+
+#[cfg(target_arch = "wasm32")]
+thread_local! {
+    static PENDING_RECOMMENDATIONS: RefCell<Option<Vec<PresetRecommendation>>> =
+        const { RefCell::new(None) };
+}
+
+// Frames right after a level starts are left out, assets are still loading then
+const WARMUP_SECS: f32 = 5.0;
+const SAMPLE_SECS: f32 = 60.0;
+
+/// The adapter Bevy picked at startup, the same one it logs
+#[derive(Resource, Clone, Debug)]
+pub struct GpuInfo {
+    pub class: GpuClass,
+    /// `Debug` name of the wgpu backend, `Gl` for WebGL2
+    pub backend: String,
+    pub adapter: String,
+}
+
+/// The preset the client renders with right now
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct ActivePreset(pub QualityPreset);
+
+// The lobby's default for this GPU, None until it answered or if it has too few reports
+#[derive(Resource, Default)]
+struct RecommendedPreset(Option<QualityPreset>);
+
+// Frame times in seconds of the current session's sample
+#[derive(Resource, Default)]
+struct FrameSample {
+    elapsed: f32,
+    preset: Option<QualityPreset>,
+    frame_times: Vec<f32>,
+    done: bool,
+}
+
+pub struct GraphicsPlugin;
+
+impl Plugin for GraphicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ActivePreset(QualityPreset::Medium))
+            .init_resource::<RecommendedPreset>()
+            .init_resource::<FrameSample>()
+            .add_systems(Startup, detect_gpu)
+            .add_systems(Update, (pick_preset, apply_preset).chain())
+            .add_systems(Update, sample_frame_rate.run_if(in_state(AppState::InGame)))
+            .add_systems(OnExit(AppState::InGame), drop_partial_sample);
+
+        #[cfg(target_arch = "wasm32")]
+        app.add_systems(Update, pump_recommendations.before(pick_preset));
+    }
+}
+
+fn detect_gpu(mut commands: Commands, adapter: Option<Res<RenderAdapterInfo>>) {
+    let info = match adapter {
+        Some(adapter) => GpuInfo {
+            class: GpuClass::from_device_type(&format!("{:?}", adapter.device_type)),
+            backend: format!("{:?}", adapter.backend),
+            adapter: adapter.name.clone(),
+        },
+        None => GpuInfo {
+            class: GpuClass::Unknown,
+            backend: "unknown".to_string(),
+            adapter: String::new(),
+        },
+    };
+    info!(
+        "🖥️ GPU class {:?} on {} ({})",
+        info.class, info.backend, info.adapter
+    );
+
+    #[cfg(target_arch = "wasm32")]
+    fetch_recommendations();
+
+    commands.insert_resource(info);
+}
+
+fn pick_preset(
+    settings: Res<ClientSettings>,
+    gpu: Option<Res<GpuInfo>>,
+    recommended: Res<RecommendedPreset>,
+    mut active: ResMut<ActivePreset>,
+) {
+    let Some(gpu) = gpu else {
+        return;
+    };
+    let preset = settings
+        .graphics
+        .preset
+        .or(recommended.0)
+        .unwrap_or_else(|| QualityPreset::default_for(gpu.class));
+    if active.0 != preset {
+        info!("🖥️ Graphics quality {:?}", preset);
+        active.0 = preset;
+    }
+}
+
+fn apply_preset(
+    active: Res<ActivePreset>,
+    mut cameras: Query<&mut Msaa, With<Camera3d>>,
+    mut lights: Query<&mut DirectionalLight>,
+) {
+    let msaa = match active.0 {
+        QualityPreset::Low => Msaa::Off,
+        QualityPreset::Medium | QualityPreset::High => Msaa::Sample4,
+    };
+    let shadows = active.0 == QualityPreset::High;
+    for mut camera_msaa in cameras.iter_mut() {
+        if *camera_msaa != msaa {
+            *camera_msaa = msaa;
+        }
+    }
+    for mut light in lights.iter_mut() {
+        if light.shadows_enabled != shadows {
+            light.shadows_enabled = shadows;
+        }
+    }
+}
+
+// One sample per session, taken in the first level played long enough
+fn sample_frame_rate(
+    time: Res<Time<Real>>,
+    settings: Res<ClientSettings>,
+    gpu: Option<Res<GpuInfo>>,
+    active: Res<ActivePreset>,
+    mut sample: ResMut<FrameSample>,
+) {
+    if sample.done {
+        return;
+    }
+    // Frame rates only mean something for the preset they were measured at
+    if sample.preset != Some(active.0) {
+        *sample = FrameSample {
+            preset: Some(active.0),
+            ..default()
+        };
+    }
+    sample.elapsed += time.delta_secs();
+    if sample.elapsed < WARMUP_SECS {
+        return;
+    }
+    sample.frame_times.push(time.delta_secs());
+    if sample.elapsed < WARMUP_SECS + SAMPLE_SECS {
+        return;
+    }
+    sample.done = true;
+    let Some(gpu) = gpu else {
+        return;
+    };
+
+    let total: f32 = sample.frame_times.iter().sum();
+    let mut slowest_first = sample.frame_times.clone();
+    slowest_first.sort_by(|a, b| b.total_cmp(a));
+    let low_frame = slowest_first[slowest_first.len() / 20];
+    let report = HardwareReport {
+        gpu_class: gpu.class,
+        backend: gpu.backend.clone(),
+        adapter: gpu.adapter.clone(),
+        preset: active.0,
+        avg_fps: sample.frame_times.len() as f32 / total.max(f32::EPSILON),
+        low_fps: 1.0 / low_frame.max(f32::EPSILON),
+        sample_secs: total,
+    };
+    info!(
+        "🖥️ {:.0} fps average, {:.0} fps in the slowest 5% of frames at {:?}",
+        report.avg_fps, report.low_fps, report.preset
+    );
+
+    if settings.graphics.share_hardware {
+        // Native builds have no lobby service to send it to
+        #[cfg(target_arch = "wasm32")]
+        send_report(report);
+    }
+}
+
+// Leaving a level early throws the sample away, the next level starts a new one
+fn drop_partial_sample(mut sample: ResMut<FrameSample>) {
+    if !sample.done {
+        *sample = FrameSample::default();
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn send_report(report: HardwareReport) {
+    spawn_local(async move {
+        let url = format!("{}/lobby/api/telemetry/hardware", http_base());
+        // Older lobby services have no telemetry endpoint, nothing to do about that
+        let _ = ApiRequest::post(url, &report).send().await;
+    });
+}
+
+#[cfg(target_arch = "wasm32")]
+fn fetch_recommendations() {
+    spawn_local(async {
+        let url = format!("{}/lobby/api/telemetry/presets", http_base());
+        if let Ok(recommendations) = ApiRequest::get(url).json().await {
+            PENDING_RECOMMENDATIONS.with(|cell| *cell.borrow_mut() = Some(recommendations));
+        }
+    });
+}
+
+#[cfg(target_arch = "wasm32")]
+fn pump_recommendations(gpu: Option<Res<GpuInfo>>, mut recommended: ResMut<RecommendedPreset>) {
+    let Some(gpu) = gpu else {
+        return;
+    };
+    let Some(recommendations) = PENDING_RECOMMENDATIONS.with(|cell| cell.borrow_mut().take())
+    else {
+        return;
+    };
+    recommended.0 = recommendations
+        .into_iter()
+        .find(|r| r.gpu_class == gpu.class && r.backend == gpu.backend)
+        .map(|r| r.preset);
+}
@@ -0,0 +1,89 @@
+// End of match awards from the server: MVP, longest airtime, most coins and fastest race
+// split. Shown under the results until we leave the game, ours in gold.
+use bevy::prelude::*;
+use lightyear::prelude::*;
+
+use crate::screens::AppState;
+use shared::{MatchHighlights, PlayerId, PlayerName};
+
+#[derive(Component)]
+struct HighlightsPanel;
+
+pub struct HighlightsPlugin;
+
+impl Plugin for HighlightsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, show_highlights.run_if(in_state(AppState::InGame)))
+            .add_systems(OnExit(AppState::InGame), cleanup_highlights);
+    }
+}
+
+fn show_highlights(
+    mut commands: Commands,
+    mut receivers: Query<&mut MessageReceiver<MatchHighlights>>,
+    players: Query<(&PlayerId, &PlayerName)>,
+    panels: Query<Entity, With<HighlightsPanel>>,
+) {
+    let mut latest = None;
+    for mut receiver in receivers.iter_mut() {
+        latest = receiver.receive().last().or(latest);
+    }
+    let Some(awards) = latest else {
+        return;
+    };
+    for entity in panels.iter() {
+        commands.entity(entity).despawn();
+    }
+    let our_name = players
+        .iter()
+        .find(|(id, _)| id.id == 0)
+        .map(|(_, name)| name.name.clone());
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(120.0),
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            HighlightsPanel,
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                Text::new("🎖️ Highlights"),
+                TextFont {
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            ));
+            for highlight in &awards.highlights {
+                let ours = our_name.as_ref() == Some(&highlight.name);
+                info!("🎖️ {}", highlight.describe());
+                panel.spawn((
+                    Text::new(highlight.describe()),
+                    TextFont {
+                        font_size: 16.0,
+                        ..default()
+                    },
+                    TextColor(if ours {
+                        Color::srgb(1.0, 0.85, 0.3)
+                    } else {
+                        Color::srgb(0.85, 0.85, 0.85)
+                    }),
+                    BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+                ));
+            }
+        });
+}
+
+fn cleanup_highlights(mut commands: Commands, panels: Query<Entity, With<HighlightsPanel>>) {
+    for entity in panels.iter() {
+        commands.entity(entity).despawn();
+    }
+}
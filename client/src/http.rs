@@ -0,0 +1,526 @@
+// 🌐 HTTP calls to the lobby service and the other endpoints next to it. Calls return
+// `Result<T, ApiError>` instead of a raw response:
+// - Bodies are only parsed when their `Content-Type` says JSON. A proxy's HTML error page
+//   becomes a `BAD_RESPONSE` error instead of a panic.
+// - Error statuses carry the service's `ServiceError`. Older services answer with a bare
+//   status, and get one made from the status.
+// - GETs are tried again when the service was briefly away.
+// The web client calls through the browser's fetch and native builds through one async
+// reqwest client, polled on bevy's IO task pool. Either way a failed call is logged with its
+// full trace id, and `ApiError::notice` is the text for `UiNotice`.
+
+// Native builds without bevygap only check the server status, their rooms are local
+#![cfg_attr(
+    not(any(target_arch = "wasm32", feature = "bevygap")),
+    allow(dead_code)
+)]
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+use std::future::Future;
+use std::sync::Mutex;
+
+use shared::{
+    is_json_content_type, is_retryable_status, retry_delay_ms, ServiceError, API_ATTEMPTS,
+    TRACE_HEADER,
+};
+
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+use crate::account::{auth_token, expire_auth_token};
+use crate::network_config::ClientNetworkConfig;
+#[cfg(not(target_arch = "wasm32"))]
+use {
+    bevy::tasks::IoTaskPool,
+    std::pin::Pin,
+    std::sync::OnceLock,
+    std::task::{Context, Poll},
+    std::time::Duration,
+};
+#[cfg(target_arch = "wasm32")]
+use {
+    wasm_bindgen::{JsCast, JsValue},
+    wasm_bindgen_futures::JsFuture,
+};
+
+// Native calls give up after this many seconds without an answer, browsers have their own
+#[cfg(not(target_arch = "wasm32"))]
+const NATIVE_TIMEOUT_SECS: u64 = 10;
+
+// reqwest's connections and timers need a tokio runtime. Its one thread only drives IO,
+// the calls themselves are polled on the IO task pool.
+#[cfg(not(target_arch = "wasm32"))]
+fn runtime() -> &'static tokio::runtime::Runtime {
+    static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("voidloop-http")
+            .enable_all()
+            .build()
+            .expect("the HTTP runtime starts")
+    })
+}
+
+// One client for every call, so connections to the lobby are kept and reused
+#[cfg(not(target_arch = "wasm32"))]
+fn client() -> Result<&'static reqwest::Client, reqwest::Error> {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    if let Some(client) = CLIENT.get() {
+        return Ok(client);
+    }
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(NATIVE_TIMEOUT_SECS))
+        .build()?;
+    Ok(CLIENT.get_or_init(|| client))
+}
+
+// Polls a future inside the HTTP runtime, wherever the task pool runs it
+#[cfg(not(target_arch = "wasm32"))]
+struct InRuntime<F>(Pin<Box<F>>);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<F: Future> Future for InRuntime<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let _runtime = runtime().enter();
+        self.0.as_mut().poll(cx)
+    }
+}
+
+/// Base URL of the lobby service and the matchmaker: the `ClientNetworkConfig` site, or
+/// the page's origin on the web and the local dev stack on native.
+pub fn http_base() -> String {
+    if let Some(site_url) = ClientNetworkConfig::current().site_url {
+        return site_url;
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let Some(location) = web_sys::window().map(|window| window.location()) else {
+            return String::new();
+        };
+        let scheme = match location.protocol().as_deref() {
+            Ok("https:") => "https",
+            _ => "http",
+        };
+        format!("{}://{}", scheme, location.host().unwrap_or_default())
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        "http://localhost:3000".to_string()
+    }
+}
+
+/// The matchmaker's WebSocket: the `ClientNetworkConfig` one, or next to `http_base`
+pub fn matchmaker_ws_url() -> String {
+    if let Some(matchmaker_url) = ClientNetworkConfig::current().matchmaker_url {
+        return matchmaker_url;
+    }
+    // http -> ws and https -> wss
+    format!("{}/matchmaker/ws", http_base().replacen("http", "ws", 1))
+}
+
+/// Seconds since the epoch, the clock of the service's timestamps and token expiry
+pub fn unix_now() -> u64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        (web_sys::js_sys::Date::now() / 1000.0) as u64
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs())
+    }
+}
+
+/// Fresh id for one lobby or matchmaker call
+pub fn new_trace_id() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+/// Run a call in the background
+#[cfg(target_arch = "wasm32")]
+pub fn spawn(future: impl Future<Output = ()> + 'static) {
+    wasm_bindgen_futures::spawn_local(future);
+}
+
+/// Run a call in the background, on the IO task pool
+#[cfg(not(target_arch = "wasm32"))]
+pub fn spawn(future: impl Future<Output = ()> + Send + 'static) {
+    IoTaskPool::get()
+        .spawn(InRuntime(Box::pin(future)))
+        .detach();
+}
+
+/// The answer of a call running in the background, until the system that shows it takes
+/// it. Web calls finish on the main thread and native ones on the task pool, so it is
+/// behind a mutex either way.
+pub struct Pending<T>(Mutex<Option<T>>);
+
+impl<T> Pending<T> {
+    pub const fn new() -> Self {
+        Self(Mutex::new(None))
+    }
+
+    /// Replaces an answer nobody took yet
+    pub fn set(&self, value: T) {
+        if let Ok(mut pending) = self.0.lock() {
+            *pending = Some(value);
+        }
+    }
+
+    pub fn take(&self) -> Option<T> {
+        self.0.lock().ok().and_then(|mut pending| pending.take())
+    }
+}
+
+impl<T> Default for Pending<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A call that failed, with the trace id it went out with
+#[derive(Clone, Debug, PartialEq)]
+pub enum ApiError {
+    /// No answer at all: offline, blocked, or nothing listening
+    Unreachable { trace_id: String },
+    /// The service turned the call down
+    Service {
+        status: u16,
+        error: ServiceError,
+        trace_id: String,
+    },
+    /// An answer we couldn't read
+    BadResponse { status: u16, trace_id: String },
+}
+
+impl ApiError {
+    /// HTTP status of the answer, None if there was none
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            ApiError::Unreachable { .. } => None,
+            ApiError::Service { status, .. } | ApiError::BadResponse { status, .. } => {
+                Some(*status)
+            }
+        }
+    }
+
+    pub fn trace_id(&self) -> &str {
+        match self {
+            ApiError::Unreachable { trace_id }
+            | ApiError::Service { trace_id, .. }
+            | ApiError::BadResponse { trace_id, .. } => trace_id,
+        }
+    }
+
+    /// The error as the services name it, for the toast and its hint
+    pub fn service_error(&self) -> ServiceError {
+        match self {
+            ApiError::Unreachable { .. } => ServiceError::unreachable(),
+            ApiError::Service { error, .. } => error.clone(),
+            ApiError::BadResponse { .. } => ServiceError::bad_response(),
+        }
+    }
+
+    /// "too many attempts, try again in 12s, error RATE_LIMITED — trace 7f3a…"
+    pub fn notice(&self) -> String {
+        let error = self.service_error();
+        match error.hint() {
+            Some(hint) => format!("{}, {}", hint, error.toast(self.trace_id())),
+            None => error.toast(self.trace_id()),
+        }
+    }
+
+    fn is_retryable(&self) -> bool {
+        match self {
+            ApiError::Unreachable { .. } => true,
+            ApiError::Service { status, .. } => is_retryable_status(*status),
+            ApiError::BadResponse { .. } => false,
+        }
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.notice())
+    }
+}
+
+/// What the service answered
+#[derive(Clone, Debug, Default)]
+pub struct ApiResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    /// Validators to send back with the next request for the same thing
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub body: String,
+    trace_id: String,
+}
+
+impl ApiResponse {
+    /// A 2xx status
+    pub fn ok(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// The body as `T`, `BAD_RESPONSE` unless it is JSON of that shape
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, ApiError> {
+        let content_type = self.content_type.as_deref().unwrap_or_default();
+        let parsed = if is_json_content_type(content_type) {
+            serde_json::from_str(&self.body).map_err(|e| e.to_string())
+        } else {
+            Err(format!("a {:?} body", content_type))
+        };
+        parsed.map_err(|detail| {
+            let start: String = self.body.chars().take(40).collect();
+            warn!(
+                "🌐 Unreadable answer, {} ({:?}…, trace {})",
+                detail, start, self.trace_id
+            );
+            ApiError::BadResponse {
+                status: self.status,
+                trace_id: self.trace_id.clone(),
+            }
+        })
+    }
+
+    // The service's `ServiceError`, or one made from the status
+    fn error(&self) -> ApiError {
+        let content_type = self.content_type.as_deref().unwrap_or_default();
+        let error = is_json_content_type(content_type)
+            .then(|| serde_json::from_str::<ServiceError>(&self.body).ok())
+            .flatten()
+            .unwrap_or_else(|| ServiceError::from_status(self.status));
+        ApiError::Service {
+            status: self.status,
+            error,
+            trace_id: self.trace_id.clone(),
+        }
+    }
+}
+
+/// One call: build it with `get` or `post` and the setters, then `json`, `send` or `fetch`
+#[derive(Clone, Debug)]
+pub struct ApiRequest {
+    method: &'static str,
+    url: String,
+    body: Option<String>,
+    headers: Vec<(&'static str, String)>,
+    /// Sent as `X-Request-Id`, and named in the notice if the call fails
+    pub trace_id: String,
+    attempts: u32,
+}
+
+impl ApiRequest {
+    pub fn new(method: &'static str, url: impl Into<String>) -> Self {
+        Self {
+            method,
+            url: url.into(),
+            body: None,
+            headers: Vec::new(),
+            trace_id: new_trace_id(),
+            // Only GETs are safe to send twice
+            attempts: if method == "GET" { API_ATTEMPTS } else { 1 },
+        }
+    }
+
+    pub fn get(url: impl Into<String>) -> Self {
+        Self::new("GET", url)
+    }
+
+    pub fn post(url: impl Into<String>, body: &impl Serialize) -> Self {
+        Self::new("POST", url).body(body)
+    }
+
+    /// Send `body` as JSON
+    pub fn body(mut self, body: &impl Serialize) -> Self {
+        self.body = Some(serde_json::to_string(body).expect("request bodies serialize"));
+        self
+    }
+
+    /// An extra header, such as `If-None-Match`
+    pub fn header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    /// Tries before giving up, 1 sends it once. GETs default to `API_ATTEMPTS`.
+    pub fn attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts.max(1);
+        self
+    }
+
+    /// The answer as `T`
+    pub async fn json<T: DeserializeOwned>(&self) -> Result<T, ApiError> {
+        self.send().await?.json()
+    }
+
+    /// The answer, error statuses turned into `ApiError::Service`
+    pub async fn send(&self) -> Result<ApiResponse, ApiError> {
+        let resp = self.fetch().await?;
+        if resp.status < 400 {
+            return Ok(resp);
+        }
+        let error = resp.error();
+        let service_error = error.service_error();
+        warn!(
+            "🌐 {} {} answered {} {}: {} (trace {})",
+            self.method,
+            self.url,
+            resp.status,
+            service_error.error,
+            service_error.message.as_deref().unwrap_or("-"),
+            self.trace_id
+        );
+        // Get a new token before the player tries again
+        #[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+        if resp.status == 401 && self.is_lobby_call() {
+            expire_auth_token();
+        }
+        Err(error)
+    }
+
+    /// Whatever the service answered, error statuses included. Only fails when there was
+    /// no answer at all.
+    pub async fn fetch(&self) -> Result<ApiResponse, ApiError> {
+        let mut retry = 0;
+        loop {
+            let result = self.fetch_once().await.map(|mut resp| {
+                resp.trace_id = self.trace_id.clone();
+                resp
+            });
+            let retryable = match &result {
+                Ok(resp) => is_retryable_status(resp.status),
+                Err(error) => error.is_retryable(),
+            };
+            retry += 1;
+            if !retryable || retry >= self.attempts {
+                return result;
+            }
+            debug!("🌐 Trying {} again, trace {}", self.url, self.trace_id);
+            sleep_ms(retry_delay_ms(retry)).await;
+        }
+    }
+
+    // The player's token goes to the lobby service only, never to region ping URLs
+    #[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+    fn is_lobby_call(&self) -> bool {
+        self.url.starts_with(&format!("{}/lobby/api/", http_base()))
+    }
+
+    fn all_headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = vec![
+            ("Content-Type", "application/json".to_string()),
+            (TRACE_HEADER, self.trace_id.clone()),
+        ];
+        #[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+        if self.is_lobby_call() {
+            if let Some(token) = auth_token() {
+                headers.push(("Authorization", format!("Bearer {}", token)));
+            }
+        }
+        headers.extend(self.headers.iter().cloned());
+        headers
+    }
+
+    fn unreachable(&self, detail: impl std::fmt::Display) -> ApiError {
+        warn!(
+            "🌐 {} {} got no answer: {} (trace {})",
+            self.method, self.url, detail, self.trace_id
+        );
+        ApiError::Unreachable {
+            trace_id: self.trace_id.clone(),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    async fn fetch_once(&self) -> Result<ApiResponse, ApiError> {
+        let unreachable = |e: JsValue| self.unreachable(format!("{:?}", e));
+
+        let mut opts = web_sys::RequestInit::new();
+        opts.set_method(self.method);
+        opts.set_mode(web_sys::RequestMode::Cors);
+        if let Some(body) = &self.body {
+            opts.set_body(&JsValue::from_str(body));
+        }
+        let request =
+            web_sys::Request::new_with_str_and_init(&self.url, &opts).map_err(unreachable)?;
+        for (name, value) in self.all_headers() {
+            request.headers().set(name, &value).map_err(unreachable)?;
+        }
+        let window = web_sys::window().ok_or_else(|| self.unreachable("no window"))?;
+        let resp: web_sys::Response = JsFuture::from(window.fetch_with_request(&request))
+            .await
+            .map_err(unreachable)?
+            .dyn_into()
+            .map_err(unreachable)?;
+        let body = JsFuture::from(resp.text().map_err(unreachable)?)
+            .await
+            .map_err(unreachable)?;
+        let header = |name: &str| resp.headers().get(name).ok().flatten();
+        Ok(ApiResponse {
+            status: resp.status(),
+            content_type: header("Content-Type"),
+            etag: header("ETag"),
+            last_modified: header("Last-Modified"),
+            body: body.as_string().unwrap_or_default(),
+            trace_id: String::new(),
+        })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn fetch_once(&self) -> Result<ApiResponse, ApiError> {
+        let client = client().map_err(|e| self.unreachable(e))?;
+        let method =
+            reqwest::Method::from_bytes(self.method.as_bytes()).map_err(|e| self.unreachable(e))?;
+        let mut request = client.request(method, &self.url);
+        for (name, value) in self.all_headers() {
+            request = request.header(name, value);
+        }
+        if let Some(body) = &self.body {
+            request = request.body(body.clone());
+        }
+        let resp = request.send().await.map_err(|e| self.unreachable(e))?;
+        let header = |name: reqwest::header::HeaderName| {
+            resp.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let content_type = header(reqwest::header::CONTENT_TYPE);
+        let etag = header(reqwest::header::ETAG);
+        let last_modified = header(reqwest::header::LAST_MODIFIED);
+        Ok(ApiResponse {
+            status: resp.status().as_u16(),
+            content_type,
+            etag,
+            last_modified,
+            body: resp.text().await.map_err(|e| self.unreachable(e))?,
+            trace_id: String::new(),
+        })
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn sleep_ms(ms: u64) {
+    let delay = web_sys::js_sys::Promise::new(&mut |resolve, _| {
+        let scheduled = web_sys::window().is_some_and(|window| {
+            window
+                .set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, ms as i32)
+                .is_ok()
+        });
+        if !scheduled {
+            let _ = resolve.call0(&JsValue::NULL);
+        }
+    });
+    let _ = JsFuture::from(delay).await;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn sleep_ms(ms: u64) {
+    // Calls run through `spawn`, so this is inside the HTTP runtime
+    tokio::time::sleep(Duration::from_millis(ms)).await;
+}
@@ -0,0 +1,34 @@
+// Input delay of our connection to the game server, from `NetworkConfig`. Set when the
+// client connects and again whenever the config changes.
+use bevy::prelude::*;
+use lightyear::prelude::client::{Input, InputTimeline};
+use lightyear::prelude::{Client, Timeline};
+
+use shared::NetworkConfig;
+
+pub struct InputDelayPlugin;
+
+impl Plugin for InputDelayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, apply_input_delay);
+    }
+}
+
+fn apply_input_delay(
+    mut commands: Commands,
+    network: Res<NetworkConfig>,
+    clients: Query<(Entity, Ref<Client>)>,
+) {
+    for (entity, client) in clients.iter() {
+        if !client.is_added() && !network.is_changed() {
+            continue;
+        }
+        commands.entity(entity).insert(InputTimeline(Timeline::from(
+            Input::default().with_input_delay(network.input_delay()),
+        )));
+        info!(
+            "⌨️ Input delay {} ticks, predicting past {} ticks of round trip",
+            network.input_delay_ticks, network.max_input_delay_ticks
+        );
+    }
+}
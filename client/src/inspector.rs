@@ -0,0 +1,68 @@
+// Developer inspector (`--features inspector`, native and web): F12 opens egui windows with
+// every entity and its components, the lobby state and the simulation resources, all
+// editable while the game runs.
+use bevy::input::common_conditions::input_toggle_active;
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::EguiPlugin;
+use bevy_inspector_egui::quick::{ResourceInspectorPlugin, WorldInspectorPlugin};
+
+use crate::screens::{LobbyConfig, LobbyUI};
+use shared::{
+    Checkpoint, Door, Hazard, NetworkConfig, Pickup, Platform, Player, PlayerAnimationState,
+    PlayerColor, PlayerId, PlayerName, PlayerRole, PlayerScore, PlayerStatus, PlayerTransform,
+    PressurePlate, RaceProgress, ScoringZone, SimTick, Stunned, Team, TeamRules, WorldBounds,
+};
+
+const TOGGLE_KEY: KeyCode = KeyCode::F12;
+
+pub struct InspectorPlugin;
+
+impl Plugin for InspectorPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<EguiPlugin>() {
+            app.add_plugins(EguiPlugin {
+                enable_multipass_for_primary_context: true,
+            });
+        }
+
+        // Replicated components, shown on their entities in the world inspector
+        app.register_type::<Player>()
+            .register_type::<PlayerTransform>()
+            .register_type::<PlayerId>()
+            .register_type::<PlayerName>()
+            .register_type::<PlayerStatus>()
+            .register_type::<PlayerColor>()
+            .register_type::<PlayerAnimationState>()
+            .register_type::<PlayerScore>()
+            .register_type::<PlayerRole>()
+            .register_type::<Team>()
+            .register_type::<Stunned>()
+            .register_type::<RaceProgress>()
+            .register_type::<Platform>()
+            .register_type::<PressurePlate>()
+            .register_type::<Door>()
+            .register_type::<Pickup>()
+            .register_type::<Checkpoint>()
+            .register_type::<ScoringZone>()
+            .register_type::<Hazard>()
+            // The lobby screen's state lives on its container entity
+            .register_type::<LobbyUI>();
+
+        app.add_plugins((
+            WorldInspectorPlugin::new().run_if(input_toggle_active(false, TOGGLE_KEY)),
+            ResourceInspectorPlugin::<LobbyConfig>::new()
+                .run_if(input_toggle_active(false, TOGGLE_KEY)),
+            ResourceInspectorPlugin::<SimTick>::new()
+                .run_if(input_toggle_active(false, TOGGLE_KEY)),
+            ResourceInspectorPlugin::<WorldBounds>::new()
+                .run_if(input_toggle_active(false, TOGGLE_KEY)),
+            ResourceInspectorPlugin::<TeamRules>::new()
+                .run_if(input_toggle_active(false, TOGGLE_KEY)),
+            ResourceInspectorPlugin::<GameConfig>::new()
+                .run_if(input_toggle_active(false, TOGGLE_KEY)),
+            ResourceInspectorPlugin::<NetworkConfig>::new()
+                .run_if(input_toggle_active(false, TOGGLE_KEY)),
+        ));
+        info!("🔍 Inspector ready, press F12 to open it");
+    }
+}
@@ -0,0 +1,123 @@
+use bevy::prelude::*;
+
+use crate::screens::AppState;
+use shared::ScoringZone;
+
+// Per-zone material so active/contested zones can be recolored individually
+#[derive(Component)]
+struct ZoneMaterial(Handle<StandardMaterial>);
+
+#[derive(Component)]
+struct KothHudText;
+
+pub struct KothPlugin;
+
+impl Plugin for KothPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (spawn_zone_visuals, update_zone_visuals, update_koth_hud)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(OnExit(AppState::InGame), cleanup_koth);
+    }
+}
+
+fn zone_color(zone: &ScoringZone) -> Color {
+    match (zone.active, zone.contested) {
+        (true, true) => Color::srgba(0.9, 0.2, 0.2, 0.45),
+        (true, false) => Color::srgba(1.0, 0.8, 0.2, 0.45),
+        _ => Color::srgba(0.6, 0.6, 0.6, 0.15),
+    }
+}
+
+fn spawn_zone_visuals(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    new_zones: Query<(Entity, &ScoringZone, &Transform), Added<ScoringZone>>,
+) {
+    for (entity, zone, transform) in new_zones.iter() {
+        let material = materials.add(StandardMaterial {
+            base_color: zone_color(zone),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        });
+        commands.entity(entity).insert((
+            // A flat disc facing the camera, behind the players
+            Mesh3d(meshes.add(Circle::new(zone.radius))),
+            MeshMaterial3d(material.clone()),
+            transform.with_translation(transform.translation - Vec3::Z * 30.0),
+            ZoneMaterial(material),
+        ));
+    }
+}
+
+fn update_zone_visuals(
+    zones: Query<(&ScoringZone, &ZoneMaterial), Changed<ScoringZone>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (zone, material) in zones.iter() {
+        if let Some(material) = materials.get_mut(&material.0) {
+            material.base_color = zone_color(zone);
+        }
+    }
+}
+
+fn update_koth_hud(
+    mut commands: Commands,
+    zones: Query<&ScoringZone>,
+    mut hud: Query<(Entity, &mut Text, &mut TextColor), With<KothHudText>>,
+) {
+    let Some(active) = zones.iter().find(|zone| zone.active) else {
+        for (entity, _, _) in hud.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    };
+
+    let (prompt, color) = if active.contested {
+        (
+            "⚔️ Zone contested - push them out!",
+            Color::srgb(1.0, 0.4, 0.4),
+        )
+    } else {
+        (
+            "👑 King of the hill: hold the glowing zone alone to score",
+            Color::srgb(1.0, 0.85, 0.3),
+        )
+    };
+
+    if let Ok((_, mut text, mut text_color)) = hud.single_mut() {
+        if **text != prompt {
+            **text = prompt.to_string();
+            text_color.0 = color;
+        }
+        return;
+    }
+    commands.spawn((
+        Text::new(prompt),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(color),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(40.0),
+            left: Val::Px(16.0),
+            ..default()
+        },
+        KothHudText,
+    ));
+}
+
+fn cleanup_koth(
+    mut commands: Commands,
+    entities: Query<Entity, Or<(With<KothHudText>, With<ScoringZone>)>>,
+) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,198 @@
+// Ambient scene behind the lobby menus: Vey idling on a platform while the camera slowly
+// orbits. It loads the same model and animations a match uses, so a broken asset shows
+// up here, before the player queues. Low-power devices can turn it off on the settings
+// page, the lobby then gets its plain background back.
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+
+use crate::asset_integrity::AssetIntegrity;
+use crate::client_plugin::{VeyModel, GAME_CAMERA_POSITION};
+use crate::screens::{AppState, LobbyUI, UiNotice};
+use crate::settings::ClientSettings;
+
+const ORBIT_RADIUS: f32 = 260.0;
+const ORBIT_HEIGHT: f32 = 40.0;
+// Radians per second, one lap takes a bit under two minutes
+const ORBIT_SPEED: f32 = 0.06;
+const LOOK_AT: Vec3 = Vec3::new(0.0, -20.0, 0.0);
+// Give up waiting for the model after this long and say so
+const LOAD_TIMEOUT_SECS: f32 = 20.0;
+
+const PLAIN_BACKGROUND: Color = Color::srgb(0.1, 0.1, 0.2);
+// Dark enough to keep the menu readable, light enough to see the scene
+const SCENE_BACKGROUND: Color = Color::srgba(0.1, 0.1, 0.2, 0.55);
+
+#[derive(Component)]
+struct LobbyScene;
+
+// Outcome of the model check, once per session
+#[derive(Resource, Default)]
+struct ModelCheck {
+    waited: f32,
+    done: bool,
+}
+
+#[derive(Resource, Default)]
+struct Orbit(f32);
+
+pub struct LobbyScenePlugin;
+
+impl Plugin for LobbyScenePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ModelCheck>()
+            .init_resource::<Orbit>()
+            .add_systems(
+                Update,
+                (sync_scene, orbit_camera, check_model).run_if(in_state(AppState::Lobby)),
+            )
+            .add_systems(OnExit(AppState::Lobby), (despawn_scene, reset_camera));
+    }
+}
+
+/// Background of the lobby container, see-through while the scene is on
+pub(crate) fn lobby_background(settings: &ClientSettings) -> Color {
+    if settings.graphics.lobby_scene {
+        SCENE_BACKGROUND
+    } else {
+        PLAIN_BACKGROUND
+    }
+}
+
+// Spawns or removes the scene whenever the setting changes, including on entering the lobby
+fn sync_scene(
+    mut commands: Commands,
+    settings: Res<ClientSettings>,
+    vey_model: Option<Res<VeyModel>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    scenes: Query<Entity, With<LobbyScene>>,
+    mut backgrounds: Query<&mut BackgroundColor, With<LobbyUI>>,
+) {
+    let wanted = settings.graphics.lobby_scene;
+    for mut background in backgrounds.iter_mut() {
+        let color = lobby_background(&settings);
+        if background.0 != color {
+            background.0 = color;
+        }
+    }
+    if wanted == !scenes.is_empty() {
+        return;
+    }
+    if !wanted {
+        for entity in scenes.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+
+    commands.spawn((
+        Mesh3d(meshes.add(Cuboid::new(240.0, 20.0, 120.0))),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: Color::srgb(0.3, 0.35, 0.45),
+            perceptual_roughness: 0.9,
+            ..default()
+        })),
+        Transform::from_xyz(0.0, -60.0, 0.0),
+        LobbyScene,
+    ));
+    // No model (yet) means no character, `check_model` says why
+    let Some(vey_model) = vey_model else {
+        return;
+    };
+    let mut animation_player = AnimationPlayer::default();
+    animation_player.play(vey_model.idle_node).repeat();
+    let animation_player = commands
+        .spawn((
+            animation_player,
+            AnimationGraphHandle(vey_model.animation_graph.clone()),
+        ))
+        .id();
+    commands
+        .spawn((
+            SceneRoot(vey_model.scene.clone()),
+            Transform::from_xyz(0.0, -50.0, 0.0).with_scale(Vec3::splat(50.0)),
+            LobbyScene,
+        ))
+        .add_child(animation_player);
+}
+
+fn orbit_camera(
+    time: Res<Time>,
+    settings: Res<ClientSettings>,
+    mut orbit: ResMut<Orbit>,
+    mut cameras: Query<&mut Transform, With<Camera3d>>,
+) {
+    if !settings.graphics.lobby_scene {
+        return;
+    }
+    orbit.0 = (orbit.0 + ORBIT_SPEED * time.delta_secs()) % std::f32::consts::TAU;
+    let position = Vec3::new(
+        orbit.0.sin() * ORBIT_RADIUS,
+        ORBIT_HEIGHT,
+        orbit.0.cos() * ORBIT_RADIUS,
+    );
+    for mut transform in cameras.iter_mut() {
+        *transform = Transform::from_translation(position).looking_at(LOOK_AT, Vec3::Y);
+    }
+}
+
+// The smoke test: the model and its animations either load or the player hears about it
+// now, not as a capsule in their next match
+fn check_model(
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    integrity: Res<AssetIntegrity>,
+    vey_model: Option<Res<VeyModel>>,
+    mut check: ResMut<ModelCheck>,
+    mut notice: ResMut<UiNotice>,
+) {
+    let Some(vey_model) = vey_model else {
+        return;
+    };
+    if check.done {
+        return;
+    }
+    check.waited += time.delta_secs();
+
+    let problem = integrity
+        .failure("vey.glb")
+        .map(str::to_string)
+        .or_else(|| match asset_server.get_load_state(&vey_model.scene) {
+            Some(LoadState::Failed(err)) => Some(err.to_string()),
+            _ => None,
+        });
+    if let Some(problem) = problem {
+        check.done = true;
+        error!("🎭 Lobby scene: Vey model failed to load ({})", problem);
+        notice.msg = Some("⚠️ Character model failed to load, matches will show capsules".into());
+        return;
+    }
+    // The animations come out of the same file as the scene
+    if asset_server.is_loaded_with_dependencies(&vey_model.scene) {
+        check.done = true;
+        info!(
+            "🎭 Lobby scene: Vey model and animations loaded after {:.1}s",
+            check.waited
+        );
+    } else if check.waited > LOAD_TIMEOUT_SECS {
+        check.done = true;
+        warn!(
+            "🎭 Lobby scene: Vey model still loading after {}s",
+            LOAD_TIMEOUT_SECS
+        );
+    }
+}
+
+fn despawn_scene(mut commands: Commands, scenes: Query<Entity, With<LobbyScene>>) {
+    for entity in scenes.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+// Matches use the fixed side view
+fn reset_camera(mut cameras: Query<&mut Transform, With<Camera3d>>) {
+    for mut transform in cameras.iter_mut() {
+        *transform =
+            Transform::from_translation(GAME_CAMERA_POSITION).looking_at(Vec3::ZERO, Vec3::Y);
+    }
+}
@@ -0,0 +1,164 @@
+// Local play: one to four players sharing this machine. The first plays with the keys from
+// the settings, the second with the arrow keys (taken away from the first), and the third
+// and fourth with the first two gamepads, bound as in the settings, once they are plugged
+// in. The camera frames everyone, see `camera.rs`.
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+use crate::screens::AppState;
+use crate::settings::{ClientSettings, ControlSettings};
+use shared::{
+    LevelDefinition, Platform, Player, PlayerActions, PlayerAnimationState, PlayerColor, PlayerId,
+    PlayerTransform,
+};
+
+/// Most players that fit around one machine
+pub const MAX_LOCAL_PLAYERS: usize = 4;
+
+// Second player's keys, never bound for the first one in a local game
+const ARROW_KEYS: [(PlayerActions, KeyCode); 4] = [
+    (PlayerActions::MoveLeft, KeyCode::ArrowLeft),
+    (PlayerActions::MoveRight, KeyCode::ArrowRight),
+    (PlayerActions::Jump, KeyCode::ArrowUp),
+    (PlayerActions::Sabotage, KeyCode::ShiftRight),
+];
+
+// Seats from this one on play with gamepads
+const FIRST_GAMEPAD_SEAT: usize = 2;
+
+/// A local game is being played, with this many players
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct LocalGame {
+    pub players: usize,
+}
+
+/// Which local player this is, 0 for the first
+#[derive(Component, Clone, Copy, Debug)]
+pub struct LocalSeat(pub usize);
+
+pub struct LocalPlayPlugin;
+
+impl Plugin for LocalPlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::InGame),
+            spawn_local_players.run_if(resource_exists::<LocalGame>),
+        )
+        .add_systems(
+            Update,
+            (assign_gamepads, quit_local_game)
+                .run_if(in_state(AppState::InGame).and(resource_exists::<LocalGame>)),
+        )
+        .add_systems(OnExit(AppState::InGame), cleanup_local_game);
+    }
+}
+
+fn spawn_local_players(
+    mut commands: Commands,
+    game: Res<LocalGame>,
+    level: Res<LevelDefinition>,
+    settings: Res<ClientSettings>,
+) {
+    let mut occupied = Vec::new();
+    for seat in 0..game.players.clamp(1, MAX_LOCAL_PLAYERS) {
+        let translation = level.spawn_point(None, &occupied);
+        occupied.push(translation.truncate());
+        let mut player = commands.spawn((
+            Player::default(),
+            PlayerTransform { translation },
+            PlayerColor::from_palette(settings.account.color + seat),
+            PlayerAnimationState::default(),
+            PlayerId { id: seat as u32 },
+            LocalSeat(seat),
+        ));
+        // Gamepad seats get their input once their gamepad is there
+        if let Some(input_map) = keyboard_input_map(seat, game.players, &settings.controls) {
+            player.insert((input_map, ActionState::<PlayerActions>::default()));
+        }
+    }
+    info!(
+        "🎮 Local game on '{}' with {} players",
+        level.name, game.players
+    );
+}
+
+// Alone, the first player keeps every key from the settings and the gamepad too
+fn keyboard_input_map(
+    seat: usize,
+    players: usize,
+    controls: &ControlSettings,
+) -> Option<InputMap<PlayerActions>> {
+    match seat {
+        0 if players == 1 => Some(controls.input_map()),
+        0 => {
+            let mut map = InputMap::default();
+            for action in PlayerActions::ALL {
+                for key in controls.keys(action) {
+                    if !ARROW_KEYS.iter().any(|(_, arrow)| arrow == key) {
+                        map.insert(action, *key);
+                    }
+                }
+            }
+            Some(map)
+        }
+        1 => Some(InputMap::new(ARROW_KEYS)),
+        _ => None,
+    }
+}
+
+// Gamepads go to the gamepad seats in the order they were connected. A seat without one
+// stands still, leafwing would otherwise read it from whichever gamepad it finds first.
+fn assign_gamepads(
+    mut commands: Commands,
+    settings: Res<ClientSettings>,
+    gamepads: Query<Entity, With<Gamepad>>,
+    seats: Query<(Entity, &LocalSeat, Option<&InputMap<PlayerActions>>)>,
+) {
+    let mut gamepads: Vec<Entity> = gamepads.iter().collect();
+    gamepads.sort();
+    for (entity, seat, input_map) in seats.iter() {
+        if seat.0 < FIRST_GAMEPAD_SEAT {
+            continue;
+        }
+        let gamepad = gamepads.get(seat.0 - FIRST_GAMEPAD_SEAT).copied();
+        if input_map.and_then(|map| map.gamepad()) == gamepad {
+            continue;
+        }
+        match gamepad {
+            Some(gamepad) => {
+                info!("🎮 Player {} plays with gamepad {:?}", seat.0 + 1, gamepad);
+                commands.entity(entity).insert((
+                    settings.controls.gamepad.input_map().with_gamepad(gamepad),
+                    ActionState::<PlayerActions>::default(),
+                ));
+            }
+            None => {
+                info!("🎮 Player {} waits for a gamepad", seat.0 + 1);
+                commands
+                    .entity(entity)
+                    .remove::<(InputMap<PlayerActions>, ActionState<PlayerActions>)>();
+            }
+        }
+    }
+}
+
+fn quit_local_game(keys: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<AppState>>) {
+    if keys.just_pressed(KeyCode::Escape) {
+        info!("🎮 Leaving local game");
+        next_state.set(AppState::Lobby);
+    }
+}
+
+fn cleanup_local_game(
+    mut commands: Commands,
+    game: Option<Res<LocalGame>>,
+    entities: Query<Entity, Or<(With<LocalSeat>, With<Platform>)>>,
+) {
+    if game.is_none() {
+        return;
+    }
+    for entity in entities.iter() {
+        commands.entity(entity).despawn();
+    }
+    commands.remove_resource::<LocalGame>();
+}
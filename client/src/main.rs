@@ -1,8 +1,53 @@
 use bevy::prelude::*;
 use client_plugin::ClientPlugin;
 
+mod account;
+mod asset_integrity;
+mod camera;
+mod campaign;
 mod client_plugin;
+#[cfg(feature = "bevygap")]
+mod clock_sync;
+mod connection;
+mod coop;
+mod countdown;
+mod fall_out;
+mod game_config;
+mod ghost;
+mod graphics;
+#[cfg(feature = "bevygap")]
+mod highlights;
+mod http;
+#[cfg(feature = "bevygap")]
+mod input_delay;
+#[cfg(feature = "inspector")]
+mod inspector;
+mod koth;
+mod lobby_scene;
+mod local_play;
+mod nameplates;
+#[cfg(feature = "bevygap")]
+mod net_probe;
+mod net_stats;
+mod network_config;
+mod offline;
+mod optimistic;
+mod pickups;
+mod player_sounds;
+mod race;
+#[cfg(feature = "bevygap")]
+mod ranked;
+#[cfg(feature = "debug-gizmos")]
+mod reconcile_debug;
 mod screens;
+mod settings;
+#[cfg(feature = "bevygap")]
+mod shutdown;
+mod teams;
+mod text_input;
+mod time_scale;
+mod vey_animation;
+mod voice;
 
 fn main() {
     println!(
@@ -42,5 +87,15 @@ fn main() {
     info!("🎮 Simple Platformer Client starting...");
     info!("🔐 Using BevyGap for matchmaking and connection");
 
-    App::new().add_plugins(ClientPlugin).run();
+    // Endpoints first, the lobby calls start with the first frame
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        network_config::load();
+        App::new().add_plugins(ClientPlugin).run();
+    }
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_futures::spawn_local(async {
+        network_config::load().await;
+        App::new().add_plugins(ClientPlugin).run();
+    });
 }
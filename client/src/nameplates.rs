@@ -0,0 +1,268 @@
+// Nameplates: UI floating over every other player with their name, a crown on the room's
+// host, a badge on bots, their ping and a bar counting down a stun. Plates shrink as the camera backs away
+// but never below a readable size, and plates that would cover each other stack upwards.
+// The `nameplates` graphics setting hides them all.
+use bevy::prelude::*;
+
+use crate::client_plugin::GAME_CAMERA_POSITION;
+use crate::screens::AppState;
+use crate::settings::ClientSettings;
+use shared::{
+    BotTag, ConnectionVerdict, PlayerColor, PlayerId, PlayerName, PlayerStatus, PlayerTransform,
+    Stunned, Team, STUN_SECS,
+};
+
+// How far above a player's origin the name floats, in world units
+const NAME_HEIGHT: f32 = 45.0;
+// Nameplates are centered on a box this wide, in pixels
+const NAMEPLATE_WIDTH: f32 = 160.0;
+// Height one plate takes up when stacking, at full size
+const NAMEPLATE_HEIGHT: f32 = 34.0;
+const NAME_FONT_SIZE: f32 = 14.0;
+const PING_FONT_SIZE: f32 = 11.0;
+// Smallest a plate gets when the camera backs away to fit several local players
+const MIN_SCALE: f32 = 0.7;
+
+// UI following the player it names around the screen, with the parts that change
+#[derive(Component)]
+struct Nameplate {
+    player: Entity,
+    name: Entity,
+    ping: Entity,
+    stun_bar: Entity,
+    stun_fill: Entity,
+    scale: f32,
+}
+
+pub struct NameplatesPlugin;
+
+impl Plugin for NameplatesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (spawn_nameplates, update_nameplates)
+                .chain()
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(OnExit(AppState::InGame), cleanup_nameplates);
+    }
+}
+
+// Everyone but the local player gets a name over their head
+fn spawn_nameplates(
+    mut commands: Commands,
+    named: Query<(Entity, &PlayerId, &PlayerName), Added<PlayerName>>,
+) {
+    for (entity, id, name) in named.iter() {
+        if id.id == 0 {
+            continue;
+        }
+        let name = commands
+            .spawn((
+                Text::new(name.name.clone()),
+                TextFont {
+                    font_size: NAME_FONT_SIZE,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ))
+            .id();
+        let ping = commands
+            .spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: PING_FONT_SIZE,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ))
+            .id();
+        let stun_fill = commands
+            .spawn((
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(1.0, 0.85, 0.2)),
+            ))
+            .id();
+        let stun_bar = commands
+            .spawn((
+                Node {
+                    width: Val::Px(48.0),
+                    height: Val::Px(4.0),
+                    margin: UiRect::top(Val::Px(2.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+                Visibility::Hidden,
+            ))
+            .add_child(stun_fill)
+            .id();
+        let row = commands
+            .spawn(Node {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(4.0),
+                ..default()
+            })
+            .add_children(&[name, ping])
+            .id();
+        commands
+            .spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    width: Val::Px(NAMEPLATE_WIDTH),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                Visibility::Hidden,
+                Nameplate {
+                    player: entity,
+                    name,
+                    ping,
+                    stun_bar,
+                    stun_fill,
+                    scale: 1.0,
+                },
+            ))
+            .add_children(&[row, stun_bar]);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_nameplates(
+    mut commands: Commands,
+    settings: Res<ClientSettings>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+    players: Query<(
+        &PlayerTransform,
+        &PlayerName,
+        Option<&PlayerColor>,
+        Option<&Team>,
+        Option<&PlayerStatus>,
+        Option<&Stunned>,
+        Option<&BotTag>,
+    )>,
+    mut plates: Query<(Entity, &mut Nameplate, &mut Node, &mut Visibility)>,
+    mut texts: Query<(&mut Text, &mut TextColor, &mut TextFont)>,
+    mut stun_bars: Query<(&mut Node, &mut Visibility), Without<Nameplate>>,
+) {
+    let Ok((camera, camera_transform)) = cameras.single() else {
+        return;
+    };
+    // Screen positions of the plates shown this frame, for stacking
+    let mut shown: Vec<(Entity, Vec2, f32)> = Vec::new();
+    for (plate_entity, mut plate, _, mut visibility) in plates.iter_mut() {
+        let Ok((transform, name, color, team, status, stunned, bot)) = players.get(plate.player)
+        else {
+            // The player left
+            commands.entity(plate_entity).despawn();
+            continue;
+        };
+        let above = transform.translation + Vec3::Y * NAME_HEIGHT;
+        let position = match camera.world_to_viewport(camera_transform, above) {
+            Ok(position) if settings.graphics.nameplates => position,
+            _ => {
+                visibility.set_if_neq(Visibility::Hidden);
+                continue;
+            }
+        };
+        visibility.set_if_neq(Visibility::Inherited);
+
+        // Shrink with the camera's distance, in steps so text isn't re-laid out every frame
+        let distance = camera_transform.translation().z - above.z;
+        let scale = (GAME_CAMERA_POSITION.z / distance).clamp(MIN_SCALE, 1.0);
+        let scale = (scale * 20.0).round() / 20.0;
+        let rescaled = plate.scale != scale;
+        plate.scale = scale;
+
+        if let Ok((mut text, mut text_color, mut font)) = texts.get_mut(plate.name) {
+            let host = status.is_some_and(|status| status.host);
+            let wanted_text = if host {
+                format!("👑 {}", name.name)
+            } else if bot.is_some() {
+                format!("🤖 {}", name.name)
+            } else {
+                name.name.clone()
+            };
+            if text.0 != wanted_text {
+                text.0 = wanted_text;
+            }
+            // Names are written in the player's team color, or the color they picked
+            let wanted = match (team, color) {
+                (Some(team), _) => team.color(),
+                (None, Some(color)) => color.color,
+                (None, None) => Color::WHITE,
+            };
+            if text_color.0 != wanted {
+                text_color.0 = wanted;
+            }
+            if rescaled {
+                font.font_size = NAME_FONT_SIZE * scale;
+            }
+        }
+        if let Ok((mut text, mut text_color, mut font)) = texts.get_mut(plate.ping) {
+            let ping_ms = status.and_then(|status| status.ping_ms);
+            let wanted_text = ping_ms.map_or(String::new(), |ms| format!("{} ms", ms));
+            if text.0 != wanted_text {
+                text.0 = wanted_text;
+            }
+            let wanted = ping_ms.map_or(Color::WHITE, ping_color);
+            if text_color.0 != wanted {
+                text_color.0 = wanted;
+            }
+            if rescaled {
+                font.font_size = PING_FONT_SIZE * scale;
+            }
+        }
+        if let Ok((_, mut bar_visibility)) = stun_bars.get_mut(plate.stun_bar) {
+            bar_visibility.set_if_neq(if stunned.is_some() {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            });
+        }
+        if let (Some(stunned), Ok((mut fill, _))) = (stunned, stun_bars.get_mut(plate.stun_fill)) {
+            fill.width = Val::Percent((stunned.remaining / STUN_SECS).clamp(0.0, 1.0) * 100.0);
+        }
+
+        shown.push((plate_entity, position, scale));
+    }
+
+    // Plates lower on the screen stay put, the ones they would cover move up above them
+    shown.sort_by(|a, b| b.1.y.total_cmp(&a.1.y));
+    let mut placed: Vec<(Vec2, f32)> = Vec::with_capacity(shown.len());
+    for (plate_entity, mut position, scale) in shown {
+        // Every move goes strictly up, past the plate it cleared, so this ends
+        while let Some((other, other_scale)) = placed.iter().find(|(other, other_scale)| {
+            let gap = NAMEPLATE_HEIGHT * scale.max(*other_scale);
+            (other.x - position.x).abs() < NAMEPLATE_WIDTH * scale.max(*other_scale)
+                && (other.y - position.y).abs() < gap
+        }) {
+            position.y = other.y - NAMEPLATE_HEIGHT * scale.max(*other_scale);
+        }
+        placed.push((position, scale));
+        if let Ok((_, _, mut node, _)) = plates.get_mut(plate_entity) {
+            node.left = Val::Px(position.x - NAMEPLATE_WIDTH / 2.0);
+            node.top = Val::Px(position.y);
+        }
+    }
+}
+
+// Same thresholds as the match report's connection verdict
+fn ping_color(ms: u16) -> Color {
+    match ConnectionVerdict::rate(ms as f32, 0.0, 0.0) {
+        ConnectionVerdict::Good => Color::srgb(0.4, 1.0, 0.4),
+        ConnectionVerdict::Fair => Color::srgb(1.0, 0.85, 0.3),
+        ConnectionVerdict::Poor => Color::srgb(1.0, 0.35, 0.35),
+    }
+}
+
+fn cleanup_nameplates(mut commands: Commands, plates: Query<Entity, With<Nameplate>>) {
+    for entity in plates.iter() {
+        commands.entity(entity).despawn();
+    }
+}
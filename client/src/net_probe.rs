@@ -0,0 +1,130 @@
+// Network probes for the server's connection quality report: one `NetworkProbe` a second,
+// carrying the round trip of the last echoed probe and how often prediction had to be
+// corrected so far. The echoes also feed our own `NetworkStats`.
+use bevy::prelude::*;
+use lightyear::prelude::*;
+use std::collections::VecDeque;
+
+use crate::net_stats::NetworkStats;
+use crate::screens::AppState;
+use shared::{
+    is_prediction_correction, GameConfig, NetworkProbe, PlayerId, PlayerTransform, ProbeChannel,
+    ProbeEcho,
+};
+
+const PROBE_SECS: f32 = 1.0;
+// Probes still waiting for their echo. Older ones are lost, the server counts those.
+const MAX_PENDING: usize = 10;
+
+#[derive(Resource)]
+struct ProbeState {
+    seq: u32,
+    timer: Timer,
+    // (seq, sent at), oldest first
+    pending: VecDeque<(u32, f64)>,
+    last_rtt_ms: Option<u16>,
+    corrections: u32,
+    last_predicted: Option<Vec3>,
+}
+
+impl Default for ProbeState {
+    fn default() -> Self {
+        Self {
+            seq: 0,
+            timer: Timer::from_seconds(PROBE_SECS, TimerMode::Repeating),
+            pending: VecDeque::new(),
+            last_rtt_ms: None,
+            corrections: 0,
+            last_predicted: None,
+        }
+    }
+}
+
+pub struct NetProbePlugin;
+
+impl Plugin for NetProbePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ProbeState>()
+            .add_systems(
+                Update,
+                (count_corrections, receive_echoes, send_probe)
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(OnExit(AppState::InGame), reset_probes);
+    }
+}
+
+fn count_corrections(
+    time: Res<Time>,
+    config: Res<GameConfig>,
+    mut state: ResMut<ProbeState>,
+    predicted: Query<(&PlayerId, &PlayerTransform), With<Predicted>>,
+) {
+    let position = predicted
+        .iter()
+        .find(|(id, _)| id.id == 0)
+        .map(|(_, transform)| transform.translation);
+    if let (Some(position), Some(last)) = (position, state.last_predicted) {
+        if is_prediction_correction(&config, last, position, time.delta_secs()) {
+            state.corrections += 1;
+        }
+    }
+    state.last_predicted = position;
+}
+
+fn receive_echoes(
+    time: Res<Time<Real>>,
+    mut state: ResMut<ProbeState>,
+    mut stats: ResMut<NetworkStats>,
+    mut receivers: Query<&mut MessageReceiver<ProbeEcho>>,
+) {
+    let now = time.elapsed_secs_f64();
+    for mut receiver in receivers.iter_mut() {
+        for echo in receiver.receive() {
+            let Some(index) = state.pending.iter().position(|(seq, _)| *seq == echo.seq) else {
+                continue;
+            };
+            let (_, sent) = state.pending[index];
+            state.pending.drain(..=index);
+            let rtt_ms = ((now - sent) * 1000.0).min(u16::MAX as f64);
+            state.last_rtt_ms = Some(rtt_ms as u16);
+            // Probes sent before this one didn't come back, and won't after it
+            for _ in 0..index {
+                stats.record_probe(false);
+            }
+            stats.record_probe(true);
+            stats.record_rtt(rtt_ms as f32);
+        }
+    }
+}
+
+fn send_probe(
+    time: Res<Time<Real>>,
+    mut state: ResMut<ProbeState>,
+    mut stats: ResMut<NetworkStats>,
+    mut senders: Query<&mut MessageSender<NetworkProbe>, With<Connected>>,
+) {
+    if !state.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let Ok(mut sender) = senders.single_mut() else {
+        return;
+    };
+    let seq = state.seq;
+    sender.send::<ProbeChannel>(NetworkProbe {
+        seq,
+        rtt_ms: state.last_rtt_ms,
+        corrections: state.corrections,
+    });
+    state.seq += 1;
+    state.pending.push_back((seq, time.elapsed_secs_f64()));
+    if state.pending.len() > MAX_PENDING {
+        state.pending.pop_front();
+        stats.record_probe(false);
+    }
+}
+
+fn reset_probes(mut state: ResMut<ProbeState>) {
+    *state = ProbeState::default();
+}
@@ -0,0 +1,180 @@
+// 📶 Network statistics of the current match: round trip, jitter and loss from our network
+// probes, and how far our simulation runs ahead of the server's tick. `NetworkStats` is
+// there for any system that wants to react to the connection, and F3 shows it in game.
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+use crate::screens::AppState;
+use shared::ConnectionVerdict;
+
+// Probes the loss is measured over, one is sent a second
+const LOSS_WINDOW: usize = 30;
+// Round trips the jitter is measured over
+const JITTER_WINDOW: usize = 10;
+
+/// How the connection to the game server is doing, all None or zero while offline
+#[derive(Resource, Clone, Debug, Default)]
+pub struct NetworkStats {
+    /// Round trip of the latest probe that came back, in milliseconds
+    pub rtt_ms: Option<f32>,
+    /// Mean change between consecutive round trips, in milliseconds
+    pub jitter_ms: f32,
+    /// Share of the recent probes that never came back, 0..1
+    pub packet_loss: f32,
+    /// Ticks our simulation is ahead of the last tick the server told us about. Predicted
+    /// players run ahead by about half the round trip plus the input delay.
+    pub tick_offset: Option<i32>,
+    rtts_ms: VecDeque<f32>,
+    // Whether each recent probe came back, oldest first
+    outcomes: VecDeque<bool>,
+}
+
+impl NetworkStats {
+    /// The verdict the match report would give the connection right now
+    pub fn verdict(&self) -> ConnectionVerdict {
+        ConnectionVerdict::rate(
+            self.rtt_ms.unwrap_or_default(),
+            self.jitter_ms,
+            self.packet_loss,
+        )
+    }
+
+    #[cfg_attr(not(feature = "bevygap"), allow(dead_code))]
+    pub(crate) fn record_rtt(&mut self, rtt_ms: f32) {
+        self.rtt_ms = Some(rtt_ms);
+        self.rtts_ms.push_back(rtt_ms);
+        if self.rtts_ms.len() > JITTER_WINDOW {
+            self.rtts_ms.pop_front();
+        }
+        self.jitter_ms = if self.rtts_ms.len() < 2 {
+            0.0
+        } else {
+            self.rtts_ms
+                .iter()
+                .zip(self.rtts_ms.iter().skip(1))
+                .map(|(before, after)| (after - before).abs())
+                .sum::<f32>()
+                / (self.rtts_ms.len() - 1) as f32
+        };
+    }
+
+    /// A probe came back, or was given up on
+    #[cfg_attr(not(feature = "bevygap"), allow(dead_code))]
+    pub(crate) fn record_probe(&mut self, arrived: bool) {
+        self.outcomes.push_back(arrived);
+        if self.outcomes.len() > LOSS_WINDOW {
+            self.outcomes.pop_front();
+        }
+        let lost = self.outcomes.iter().filter(|arrived| !**arrived).count();
+        self.packet_loss = lost as f32 / self.outcomes.len() as f32;
+    }
+}
+
+#[derive(Resource, Default)]
+struct NetStatsOverlay {
+    enabled: bool,
+}
+
+#[derive(Component)]
+struct NetStatsPanel;
+
+#[derive(Component)]
+struct NetStatsText;
+
+pub struct NetStatsPlugin;
+
+impl Plugin for NetStatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NetworkStats>()
+            .init_resource::<NetStatsOverlay>()
+            .add_systems(
+                Update,
+                (toggle_overlay, update_overlay)
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(OnExit(AppState::InGame), reset_stats);
+    }
+}
+
+fn toggle_overlay(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut overlay: ResMut<NetStatsOverlay>,
+    panels: Query<Entity, With<NetStatsPanel>>,
+) {
+    if !keys.just_pressed(KeyCode::F3) {
+        return;
+    }
+    overlay.enabled = !overlay.enabled;
+    if !overlay.enabled {
+        for entity in panels.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(12.0),
+                bottom: Val::Px(12.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+            NetStatsPanel,
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.9, 1.0)),
+                NetStatsText,
+            ));
+        });
+}
+
+fn update_overlay(
+    overlay: Res<NetStatsOverlay>,
+    stats: Res<NetworkStats>,
+    mut texts: Query<&mut Text, With<NetStatsText>>,
+) {
+    if !overlay.enabled {
+        return;
+    }
+    let label = match stats.rtt_ms {
+        Some(rtt) => format!(
+            "F3 network\nping: {:.0} ms ({:?})\njitter: {:.1} ms\nloss: {:.1}%\ntick offset: {}",
+            rtt,
+            stats.verdict(),
+            stats.jitter_ms,
+            stats.packet_loss * 100.0,
+            stats
+                .tick_offset
+                .map_or("-".to_string(), |offset| format!("{:+}", offset)),
+        ),
+        None => "F3 network\nno probe answered yet (offline?)".to_string(),
+    };
+    if let Ok(mut text) = texts.single_mut() {
+        if text.0 != label {
+            text.0 = label;
+        }
+    }
+}
+
+fn reset_stats(
+    mut commands: Commands,
+    mut stats: ResMut<NetworkStats>,
+    mut overlay: ResMut<NetStatsOverlay>,
+    panels: Query<Entity, With<NetStatsPanel>>,
+) {
+    for entity in panels.iter() {
+        commands.entity(entity).despawn();
+    }
+    *stats = NetworkStats::default();
+    *overlay = NetStatsOverlay::default();
+}
@@ -0,0 +1,120 @@
+// 🛰️ Where the client finds the lobby service and the matchmaker: the page's origin on the
+// web, the local dev stack on native. Self-hosters point a build at their own endpoints
+// without rebuilding it, each source overriding the one before:
+// 1. `assets/network.json`, served or shipped with the other assets
+// 2. native: the `VOIDLOOP_SITE_URL` and `MATCHMAKER_URL` environment variables
+// 3. web: `?site=` and `?matchmaker=` in the page's address
+// `main` reads them once before the app starts, so no call goes out to the defaults first.
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+
+/// File under `assets/` with the endpoints of this deployment
+pub const NETWORK_CONFIG_ASSET: &str = "network.json";
+
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct ClientNetworkConfig {
+    /// Site the lobby service (`/lobby/api`) and the matchmaker (`/matchmaker/ws`) live
+    /// under, e.g. `https://voidloop.quest`
+    pub site_url: Option<String>,
+    /// The matchmaker's WebSocket, for a matchmaker that doesn't live under `site_url`
+    pub matchmaker_url: Option<String>,
+}
+
+static NETWORK_CONFIG: RwLock<ClientNetworkConfig> = RwLock::new(ClientNetworkConfig {
+    site_url: None,
+    matchmaker_url: None,
+});
+
+impl ClientNetworkConfig {
+    /// The config `main` loaded, the defaults before that
+    pub fn current() -> ClientNetworkConfig {
+        NETWORK_CONFIG
+            .read()
+            .map(|config| config.clone())
+            .unwrap_or_default()
+    }
+
+    /// Take the endpoints `other` sets
+    fn merge(&mut self, other: ClientNetworkConfig) {
+        if other.site_url.is_some() {
+            self.site_url = other.site_url;
+        }
+        if other.matchmaker_url.is_some() {
+            self.matchmaker_url = other.matchmaker_url;
+        }
+    }
+
+    // Blank values are unset, and trailing slashes go so paths can be appended
+    fn normalized(self) -> Self {
+        let clean = |url: Option<String>| {
+            url.map(|url| url.trim().trim_end_matches('/').to_string())
+                .filter(|url| !url.is_empty())
+        };
+        Self {
+            site_url: clean(self.site_url),
+            matchmaker_url: clean(self.matchmaker_url),
+        }
+    }
+
+    fn install(self) {
+        if let Ok(mut config) = NETWORK_CONFIG.write() {
+            *config = self;
+        }
+    }
+}
+
+/// Read the config file next to the assets, then the environment
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load() {
+    use bevy::asset::io::file::FileAssetReader;
+
+    let path = FileAssetReader::get_base_path()
+        .join("assets")
+        .join(NETWORK_CONFIG_ASSET);
+    let mut config = match std::fs::read_to_string(&path) {
+        Ok(json) => serde_json::from_str::<ClientNetworkConfig>(&json).unwrap_or_else(|e| {
+            eprintln!("⚠️ Ignoring {}: {}", path.display(), e);
+            ClientNetworkConfig::default()
+        }),
+        Err(_) => ClientNetworkConfig::default(),
+    }
+    .normalized();
+    config.merge(
+        ClientNetworkConfig {
+            site_url: std::env::var("VOIDLOOP_SITE_URL").ok(),
+            matchmaker_url: std::env::var("MATCHMAKER_URL").ok(),
+        }
+        .normalized(),
+    );
+    config.install();
+}
+
+/// Fetch the config file next to the assets, then read the page's address
+#[cfg(target_arch = "wasm32")]
+pub async fn load() {
+    use crate::http::ApiRequest;
+
+    // Most deployments have no file, the page's origin is all they need
+    let request = ApiRequest::get(format!("assets/{}", NETWORK_CONFIG_ASSET)).attempts(1);
+    let mut config = request
+        .json::<ClientNetworkConfig>()
+        .await
+        .unwrap_or_default()
+        .normalized();
+
+    let search = web_sys::window()
+        .and_then(|window| window.location().search().ok())
+        .unwrap_or_default();
+    if let Ok(params) = web_sys::UrlSearchParams::new_with_str(&search) {
+        config.merge(
+            ClientNetworkConfig {
+                site_url: params.get("site"),
+                matchmaker_url: params.get("matchmaker"),
+            }
+            .normalized(),
+        );
+    }
+    config.install();
+}
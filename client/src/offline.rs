@@ -0,0 +1,80 @@
+// 🏠 Games without a server: LOCAL PLAY, the campaign, and every game of a build without
+// bevygap. The client stands in for the server there. It spawns the level's world, collects
+// pickups and respawns players who fall out of the world, with the same shared systems the
+// server runs, and counts the start down, so practice plays like an online match. Online,
+// the world comes from the server and the client only predicts it.
+use bevy::prelude::*;
+
+use crate::campaign::ActiveCampaign;
+use crate::local_play::LocalGame;
+use crate::screens::AppState;
+use shared::{
+    collect_pickups_system, respawn_fallen_players_system, ChecksumHistory, Countdown, GameConfig,
+    LevelDefinition, PlayerFellOut, SimTick, DEFAULT_COUNTDOWN_SECS,
+};
+
+pub struct OfflinePlugin;
+
+impl Plugin for OfflinePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlayerFellOut>()
+            .add_systems(
+                OnEnter(AppState::InGame),
+                setup_offline_world.run_if(plays_offline),
+            )
+            .add_systems(
+                FixedUpdate,
+                (collect_pickups_system, respawn_fallen_players_system)
+                    .run_if(in_state(AppState::InGame).and(plays_offline)),
+            );
+    }
+}
+
+/// The client is its own server for this game
+pub fn plays_offline(
+    local_game: Option<Res<LocalGame>>,
+    campaign: Option<Res<ActiveCampaign>>,
+) -> bool {
+    cfg!(not(feature = "bevygap")) || local_game.is_some() || campaign.is_some()
+}
+
+fn setup_offline_world(
+    mut commands: Commands,
+    level: Res<LevelDefinition>,
+    config: Res<GameConfig>,
+    mut tick: ResMut<SimTick>,
+    mut history: ResMut<ChecksumHistory>,
+    mut countdown: ResMut<Countdown>,
+) {
+    // A fresh server starts at tick 0, moving platforms start where they do online
+    *tick = SimTick::default();
+    *history = ChecksumHistory::default();
+    *countdown = Countdown::starting_at(*tick, DEFAULT_COUNTDOWN_SECS);
+    spawn_level(&mut commands, &level, config.pickups);
+}
+
+// Spawn the local copy of a level's entities, visuals are added by the Added<...> systems.
+// Rule sets can take the pickups out.
+pub(crate) fn spawn_level(commands: &mut Commands, level: &LevelDefinition, pickups: bool) {
+    // The floor, if the level has one, comes last as a platform spanning its bounds
+    for platform in level.platform_bundles() {
+        commands.spawn(platform);
+    }
+    // Co-op triggers, visuals are added by the coop module
+    for plate in level.plate_bundles() {
+        commands.spawn(plate);
+    }
+    for door in level.door_bundles() {
+        commands.spawn(door);
+    }
+    if pickups {
+        for pickup in level.pickup_bundles() {
+            commands.spawn(pickup);
+        }
+    }
+    info!(
+        "🗺️ Spawned level '{}' with {} platforms",
+        level.name,
+        level.platforms.len()
+    );
+}
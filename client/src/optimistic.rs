@@ -0,0 +1,55 @@
+// Optimistic actions: the client shows what an action does to an entity right away and
+// marks the entity `Optimistic` until the server answers. The server confirms by changing
+// the entity the same way (a collected pickup gets despawned). If nothing comes back in
+// time the action was mispredicted, and the module that started it puts things back.
+use bevy::prelude::*;
+
+use crate::screens::AppState;
+
+// How long an action waits for the server, well above the round trips players have
+const CONFIRM_TIMEOUT_SECS: f32 = 1.0;
+
+/// The entity shows a local effect the server has not confirmed yet
+#[derive(Component)]
+pub struct Optimistic {
+    started: f32,
+}
+
+impl Optimistic {
+    pub fn now(time: &Time) -> Self {
+        Self {
+            started: time.elapsed_secs(),
+        }
+    }
+}
+
+/// The server never confirmed the action on this entity, undo its local effect
+#[derive(Event)]
+pub struct Mispredicted(pub Entity);
+
+pub struct OptimisticPlugin;
+
+impl Plugin for OptimisticPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Mispredicted>()
+            // Before Update, so rollbacks are already applied when the game systems run
+            .add_systems(
+                PreUpdate,
+                expire_optimistic.run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+fn expire_optimistic(
+    mut commands: Commands,
+    time: Res<Time>,
+    pending: Query<(Entity, &Optimistic)>,
+    mut mispredicted: EventWriter<Mispredicted>,
+) {
+    for (entity, optimistic) in pending.iter() {
+        if time.elapsed_secs() - optimistic.started > CONFIRM_TIMEOUT_SECS {
+            commands.entity(entity).remove::<Optimistic>();
+            mispredicted.write(Mispredicted(entity));
+        }
+    }
+}
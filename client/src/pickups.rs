@@ -0,0 +1,211 @@
+use bevy::audio::Volume;
+use bevy::prelude::*;
+
+use crate::offline::plays_offline;
+use crate::optimistic::{Mispredicted, Optimistic};
+use crate::screens::AppState;
+use crate::settings::ClientSettings;
+use shared::{touches_pickup, Pickup, PlayerId, PlayerScore, PlayerTransform};
+
+#[derive(Resource)]
+struct PickupSound(Handle<AudioSource>);
+
+#[derive(Component)]
+struct ScoreText;
+
+// Highest local score the coin sound has played for this match
+#[derive(Resource, Default)]
+struct HeardScore(u32);
+
+// The server turned down this pickup, so it is not predicted again until the local
+// player has stepped off it
+#[derive(Component)]
+struct Rejected;
+
+pub struct PickupsPlugin;
+
+impl Plugin for PickupsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HeardScore>()
+            .add_systems(Startup, load_pickup_sound)
+            .add_systems(
+                Update,
+                (
+                    spawn_pickup_visuals,
+                    spin_pickups,
+                    // Offline the pickups are collected for real, see `offline.rs`
+                    (predict_pickups, restore_mispredicted_pickups)
+                        .chain()
+                        .run_if(not(plays_offline)),
+                    play_pickup_sound,
+                    update_score_hud,
+                )
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(OnExit(AppState::InGame), cleanup_pickups);
+    }
+}
+
+fn load_pickup_sound(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(PickupSound(asset_server.load("audio/coin.wav")));
+}
+
+fn spawn_pickup_visuals(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    new_pickups: Query<(Entity, &Pickup, &Transform), Added<Pickup>>,
+) {
+    for (entity, pickup, transform) in new_pickups.iter() {
+        // Bigger, redder coins are worth more
+        let (radius, color) = if pickup.value > 1 {
+            (12.0, Color::srgb(1.0, 0.5, 0.2))
+        } else {
+            (8.0, Color::srgb(1.0, 0.85, 0.2))
+        };
+        commands.entity(entity).insert((
+            Mesh3d(meshes.add(Cylinder::new(radius, 3.0))),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: color,
+                metallic: 0.8,
+                perceptual_roughness: 0.3,
+                ..default()
+            })),
+            // Stand the coin up so its face points at the camera
+            transform.with_rotation(Quat::from_rotation_x(std::f32::consts::FRAC_PI_2)),
+        ));
+    }
+}
+
+fn spin_pickups(time: Res<Time>, mut pickups: Query<&mut Transform, With<Pickup>>) {
+    for mut transform in pickups.iter_mut() {
+        transform.rotate_y(3.0 * time.delta_secs());
+    }
+}
+
+// Coins the local player touches vanish and count straight away. The server confirms by
+// despawning the coin and raising the score, or the coin comes back after a timeout.
+fn predict_pickups(
+    mut commands: Commands,
+    time: Res<Time>,
+    players: Query<(&PlayerId, &PlayerTransform)>,
+    mut pickups: Query<
+        (Entity, &Transform, &mut Visibility, Has<Rejected>),
+        (With<Pickup>, Without<Optimistic>),
+    >,
+) {
+    let Some((_, player)) = players.iter().find(|(id, _)| id.id == 0) else {
+        return;
+    };
+    for (entity, transform, mut visibility, rejected) in pickups.iter_mut() {
+        let touching = touches_pickup(player.translation, transform.translation);
+        if rejected {
+            if !touching {
+                commands.entity(entity).remove::<Rejected>();
+            }
+        } else if touching {
+            *visibility = Visibility::Hidden;
+            commands.entity(entity).insert(Optimistic::now(&time));
+        }
+    }
+}
+
+fn restore_mispredicted_pickups(
+    mut commands: Commands,
+    mut mispredicted: EventReader<Mispredicted>,
+    mut pickups: Query<&mut Visibility, With<Pickup>>,
+) {
+    for Mispredicted(entity) in mispredicted.read() {
+        if let Ok(mut visibility) = pickups.get_mut(*entity) {
+            info!(
+                "🪙 Server did not confirm pickup {:?}, putting it back",
+                entity
+            );
+            *visibility = Visibility::Inherited;
+            commands.entity(*entity).insert(Rejected);
+        }
+    }
+}
+
+// The local player's score from the server, plus the coins still waiting for it
+fn local_score(
+    scores: &Query<(&PlayerScore, &PlayerId)>,
+    predicted: &Query<&Pickup, With<Optimistic>>,
+) -> u32 {
+    let confirmed = scores
+        .iter()
+        .find(|(_, id)| id.id == 0)
+        .map(|(score, _)| score.score)
+        .unwrap_or_default();
+    confirmed + predicted.iter().map(|pickup| pickup.value).sum::<u32>()
+}
+
+// Play the coin sound when the local player's score goes up. The score can dip for a
+// moment when a confirmed coin despawns before the new score arrives, so only a score
+// above the highest one so far plays it.
+fn play_pickup_sound(
+    mut commands: Commands,
+    sound: Option<Res<PickupSound>>,
+    settings: Res<ClientSettings>,
+    scores: Query<(&PlayerScore, &PlayerId)>,
+    predicted: Query<&Pickup, With<Optimistic>>,
+    mut mispredicted: EventReader<Mispredicted>,
+    mut heard: ResMut<HeardScore>,
+) {
+    let score = local_score(&scores, &predicted);
+    // A coin the server took back really lowers the score
+    if mispredicted.read().count() > 0 {
+        heard.0 = score;
+    }
+    if score > heard.0 {
+        if let Some(sound) = &sound {
+            commands.spawn((
+                AudioPlayer(sound.0.clone()),
+                PlaybackSettings::DESPAWN.with_volume(Volume::Linear(settings.audio.sfx_volume)),
+            ));
+        }
+        heard.0 = score;
+    }
+}
+
+fn update_score_hud(
+    mut commands: Commands,
+    scores: Query<(&PlayerScore, &PlayerId)>,
+    predicted: Query<&Pickup, With<Optimistic>>,
+    mut hud: Query<&mut Text, With<ScoreText>>,
+) {
+    let label = format!("🪙 {}", local_score(&scores, &predicted));
+
+    if let Ok(mut text) = hud.single_mut() {
+        if **text != label {
+            **text = label;
+        }
+        return;
+    }
+    commands.spawn((
+        Text::new(label),
+        TextFont {
+            font_size: 20.0,
+            ..default()
+        },
+        TextColor(Color::srgb(1.0, 0.85, 0.2)),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.0),
+            right: Val::Px(16.0),
+            ..default()
+        },
+        ScoreText,
+    ));
+}
+
+fn cleanup_pickups(
+    mut commands: Commands,
+    mut heard: ResMut<HeardScore>,
+    entities: Query<Entity, Or<(With<ScoreText>, With<Pickup>)>>,
+) {
+    heard.0 = 0;
+    for entity in entities.iter() {
+        commands.entity(entity).despawn();
+    }
+}
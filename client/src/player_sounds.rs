@@ -0,0 +1,152 @@
+use bevy::audio::{SpatialScale, Volume};
+use bevy::prelude::*;
+
+use crate::screens::AppState;
+use crate::settings::{AudioSettings, ClientSettings};
+use shared::{PlayerAnimationState, PlayerId, PlayerTransform};
+
+// Seconds between footsteps while running
+const STEP_INTERVAL_SECS: f32 = 0.3;
+// Distance between the listener's ears in world units, sets how strongly sounds pan
+const EAR_GAP: f32 = 60.0;
+
+#[derive(Resource)]
+struct PlayerSounds {
+    jump: Handle<AudioSource>,
+    step: Handle<AudioSource>,
+}
+
+// Ears of the local player, other players are heard relative to it
+#[derive(Component)]
+struct Listener;
+
+// What a player was doing last frame, to trigger sounds on changes
+#[derive(Component)]
+struct SoundState {
+    was_jumping: bool,
+    step: Timer,
+}
+
+pub struct PlayerSoundsPlugin;
+
+impl Plugin for PlayerSoundsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_player_sounds)
+            .add_systems(OnEnter(AppState::InGame), spawn_listener)
+            .add_systems(
+                Update,
+                (follow_local_player, play_player_sounds).run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(OnExit(AppState::InGame), cleanup_listener);
+    }
+}
+
+fn load_player_sounds(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(PlayerSounds {
+        jump: asset_server.load("audio/jump.wav"),
+        step: asset_server.load("audio/step.wav"),
+    });
+}
+
+fn spawn_listener(mut commands: Commands) {
+    commands.spawn((
+        SpatialListener::new(EAR_GAP),
+        Transform::default(),
+        Listener,
+    ));
+}
+
+fn follow_local_player(
+    players: Query<(&PlayerId, &PlayerTransform)>,
+    mut listener: Query<&mut Transform, With<Listener>>,
+) {
+    let Some((_, player)) = players.iter().find(|(id, _)| id.id == 0) else {
+        return;
+    };
+    if let Ok(mut transform) = listener.single_mut() {
+        transform.translation = player.translation;
+    }
+}
+
+// Jumps and footsteps for every player, the local one plays flat
+fn play_player_sounds(
+    mut commands: Commands,
+    time: Res<Time>,
+    sounds: Option<Res<PlayerSounds>>,
+    settings: Res<ClientSettings>,
+    mut players: Query<(
+        Entity,
+        &PlayerId,
+        &PlayerTransform,
+        &PlayerAnimationState,
+        Option<&mut SoundState>,
+    )>,
+) {
+    let Some(sounds) = sounds else {
+        return;
+    };
+
+    for (entity, player_id, transform, animation, state) in players.iter_mut() {
+        let Some(mut state) = state else {
+            commands.entity(entity).insert(SoundState {
+                was_jumping: animation.is_jumping,
+                step: Timer::from_seconds(STEP_INTERVAL_SECS, TimerMode::Repeating),
+            });
+            continue;
+        };
+        let local = player_id.id == 0;
+
+        if animation.is_jumping && !state.was_jumping {
+            play(
+                &mut commands,
+                &sounds.jump,
+                local,
+                transform.translation,
+                &settings.audio,
+            );
+        }
+        state.was_jumping = animation.is_jumping;
+
+        if animation.is_moving && !animation.is_jumping {
+            if state.step.tick(time.delta()).just_finished() {
+                play(
+                    &mut commands,
+                    &sounds.step,
+                    local,
+                    transform.translation,
+                    &settings.audio,
+                );
+            }
+        } else {
+            state.step.reset();
+        }
+    }
+}
+
+fn play(
+    commands: &mut Commands,
+    sound: &Handle<AudioSource>,
+    local: bool,
+    position: Vec3,
+    audio: &AudioSettings,
+) {
+    let playback = PlaybackSettings::DESPAWN.with_volume(Volume::Linear(audio.sfx_volume));
+    if local || !audio.positional {
+        commands.spawn((AudioPlayer(sound.clone()), playback));
+        return;
+    }
+    // Full volume within the hearing range, inverse-square falloff beyond it
+    commands.spawn((
+        AudioPlayer(sound.clone()),
+        playback
+            .with_spatial(true)
+            .with_spatial_scale(SpatialScale::new(1.0 / audio.hearing_range.max(1.0))),
+        Transform::from_translation(position),
+    ));
+}
+
+fn cleanup_listener(mut commands: Commands, listeners: Query<Entity, With<Listener>>) {
+    for entity in listeners.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,151 @@
+use bevy::prelude::*;
+
+use crate::screens::AppState;
+use shared::{standings, Checkpoint, PlayerId, RaceProgress};
+
+// Per-checkpoint material so the next one to reach can be highlighted
+#[derive(Component)]
+struct CheckpointMaterial(Handle<StandardMaterial>);
+
+#[derive(Component)]
+struct StandingsText;
+
+pub struct RacePlugin;
+
+impl Plugin for RacePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                spawn_checkpoint_visuals,
+                highlight_next_checkpoint,
+                update_standings,
+            )
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(OnExit(AppState::InGame), cleanup_race);
+    }
+}
+
+fn spawn_checkpoint_visuals(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    new_checkpoints: Query<(Entity, &Checkpoint, &Transform), Added<Checkpoint>>,
+) {
+    for (entity, checkpoint, transform) in new_checkpoints.iter() {
+        let material = materials.add(StandardMaterial {
+            base_color: Color::srgb(0.4, 0.4, 0.5),
+            ..default()
+        });
+        commands.entity(entity).insert((
+            // Upright ring the player runs through
+            Mesh3d(meshes.add(Torus::new(checkpoint.radius - 4.0, checkpoint.radius))),
+            MeshMaterial3d(material.clone()),
+            transform.with_rotation(Quat::from_rotation_x(std::f32::consts::FRAC_PI_2)),
+            CheckpointMaterial(material),
+        ));
+    }
+}
+
+// Light up the checkpoint the local player has to reach next
+fn highlight_next_checkpoint(
+    racers: Query<(&PlayerId, &RaceProgress)>,
+    checkpoints: Query<(&Checkpoint, &CheckpointMaterial)>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let Some(next) = racers
+        .iter()
+        .find(|(id, _)| id.id == 0)
+        .map(|(_, progress)| progress.splits.len())
+    else {
+        return;
+    };
+
+    for (checkpoint, material) in checkpoints.iter() {
+        let color = match checkpoint.index.cmp(&next) {
+            std::cmp::Ordering::Less => Color::srgb(0.2, 0.6, 0.3),
+            std::cmp::Ordering::Equal => Color::srgb(1.0, 0.9, 0.2),
+            std::cmp::Ordering::Greater => Color::srgb(0.4, 0.4, 0.5),
+        };
+        if let Some(material) = materials.get_mut(&material.0) {
+            if material.base_color != color {
+                material.base_color = color;
+            }
+        }
+    }
+}
+
+// Live standings with each racer's latest split
+fn update_standings(
+    mut commands: Commands,
+    checkpoints: Query<&Checkpoint>,
+    racers: Query<(&PlayerId, &RaceProgress)>,
+    mut hud: Query<(Entity, &mut Text), With<StandingsText>>,
+) {
+    if checkpoints.is_empty() {
+        for (entity, _) in hud.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+
+    let total = checkpoints.iter().count();
+    let mut lines = vec!["🏁 Standings".to_string()];
+    for (place, standing) in standings(racers.iter().map(|(id, p)| (id.id, p)))
+        .iter()
+        .enumerate()
+    {
+        let you = if standing.player_id == 0 {
+            " (you)"
+        } else {
+            ""
+        };
+        let time = match (standing.finished, standing.last_split) {
+            (Some(finished), _) => format!("🏆 {:.2}s", finished),
+            (None, Some(split)) => format!("split {:.2}s", split),
+            (None, None) => "-".to_string(),
+        };
+        lines.push(format!(
+            "{}. P{}{}  {}/{}  {}",
+            place + 1,
+            standing.player_id,
+            you,
+            standing.checkpoints,
+            total,
+            time
+        ));
+    }
+    let label = lines.join("\n");
+
+    if let Ok((_, mut text)) = hud.single_mut() {
+        if **text != label {
+            **text = label;
+        }
+        return;
+    }
+    commands.spawn((
+        Text::new(label),
+        TextFont {
+            font_size: 14.0,
+            ..default()
+        },
+        TextColor(Color::srgb(1.0, 1.0, 1.0)),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(44.0),
+            right: Val::Px(16.0),
+            ..default()
+        },
+        StandingsText,
+    ));
+}
+
+fn cleanup_race(
+    mut commands: Commands,
+    entities: Query<Entity, Or<(With<StandingsText>, With<Checkpoint>)>>,
+) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,77 @@
+// Result banner at the end of a ranked match. The server drains right after, and the
+// lobby shows the new rating once the lobby service has updated it.
+use bevy::prelude::*;
+use lightyear::prelude::*;
+
+use crate::screens::AppState;
+use shared::RankedMatchOver;
+
+#[derive(Component)]
+struct RankedBanner;
+
+pub struct RankedPlugin;
+
+impl Plugin for RankedPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, show_result.run_if(in_state(AppState::InGame)))
+            .add_systems(OnExit(AppState::InGame), cleanup_banner);
+    }
+}
+
+fn show_result(
+    mut commands: Commands,
+    mut receivers: Query<&mut MessageReceiver<RankedMatchOver>>,
+) {
+    for mut receiver in receivers.iter_mut() {
+        for result in receiver.receive() {
+            info!(
+                "🏆 Ranked match over, placed {} of {}",
+                result.place, result.players
+            );
+            commands
+                .spawn((
+                    Node {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(40.0),
+                        width: Val::Percent(100.0),
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    RankedBanner,
+                ))
+                .with_children(|row| {
+                    row.spawn((
+                        Text::new(format!(
+                            "🏆 Ranked match over: {} of {}. Your new rating shows in the lobby.",
+                            ordinal(result.place),
+                            result.players
+                        )),
+                        TextFont {
+                            font_size: 22.0,
+                            ..default()
+                        },
+                        TextColor(Color::srgb(1.0, 0.85, 0.3)),
+                        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+                    ));
+                });
+        }
+    }
+}
+
+// 1st, 2nd, 3rd, 4th
+fn ordinal(place: usize) -> String {
+    let suffix = match (place % 10, place % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", place, suffix)
+}
+
+fn cleanup_banner(mut commands: Commands, banners: Query<Entity, With<RankedBanner>>) {
+    for entity in banners.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,237 @@
+// 🔍 Prediction debug overlay, toggled with F3 in game: the local player's last
+// server-confirmed position drawn as an outline next to the predicted one, and a graph
+// of how often prediction had to be corrected. Meant for working on movement code.
+use bevy::prelude::*;
+use lightyear::prelude::{Confirmed, Predicted};
+use std::collections::VecDeque;
+
+use crate::screens::AppState;
+use shared::{is_prediction_correction, GameConfig, PlayerId, PlayerTransform};
+
+// Seconds of history in the graph, one bar per second
+const GRAPH_SECS: usize = 60;
+const BAR_WIDTH: f32 = 4.0;
+// Pixels per correction, bars stop growing at GRAPH_HEIGHT
+const BAR_SCALE: f32 = 6.0;
+const GRAPH_HEIGHT: f32 = 60.0;
+// Outline size of the confirmed player, in world units
+const OUTLINE_SIZE: Vec2 = Vec2::new(30.0, 60.0);
+
+#[derive(Resource, Default)]
+struct ReconcileDebug {
+    enabled: bool,
+    // Corrections per second, oldest first
+    history: VecDeque<u32>,
+    this_second: u32,
+    second_timer: f32,
+    last_predicted: Option<Vec3>,
+}
+
+#[derive(Component)]
+struct ReconcileOverlay;
+
+#[derive(Component)]
+struct ReconcileText;
+
+#[derive(Component)]
+struct GraphBar(usize);
+
+pub struct ReconcileDebugPlugin;
+
+impl Plugin for ReconcileDebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReconcileDebug>()
+            .add_systems(
+                Update,
+                (
+                    toggle_overlay,
+                    count_corrections,
+                    draw_confirmed_outline,
+                    update_overlay,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(OnExit(AppState::InGame), cleanup_overlay);
+    }
+}
+
+fn toggle_overlay(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut debug: ResMut<ReconcileDebug>,
+    overlays: Query<Entity, With<ReconcileOverlay>>,
+) {
+    if !keys.just_pressed(KeyCode::F3) {
+        return;
+    }
+    debug.enabled = !debug.enabled;
+    if !debug.enabled {
+        for entity in overlays.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(12.0),
+                bottom: Val::Px(12.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(8.0)),
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+            ReconcileOverlay,
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 1.0, 0.8)),
+                ReconcileText,
+            ));
+            panel
+                .spawn(Node {
+                    height: Val::Px(GRAPH_HEIGHT),
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::FlexEnd,
+                    column_gap: Val::Px(1.0),
+                    ..default()
+                })
+                .with_children(|graph| {
+                    for index in 0..GRAPH_SECS {
+                        graph.spawn((
+                            Node {
+                                width: Val::Px(BAR_WIDTH),
+                                height: Val::Px(0.0),
+                                ..default()
+                            },
+                            BackgroundColor(Color::srgb(1.0, 0.5, 0.2)),
+                            GraphBar(index),
+                        ));
+                    }
+                });
+        });
+}
+
+// See `is_prediction_correction`
+fn count_corrections(
+    time: Res<Time>,
+    config: Res<GameConfig>,
+    mut debug: ResMut<ReconcileDebug>,
+    predicted: Query<(&PlayerId, &PlayerTransform), With<Predicted>>,
+) {
+    let position = predicted
+        .iter()
+        .find(|(id, _)| id.id == 0)
+        .map(|(_, transform)| transform.translation);
+    if let (Some(position), Some(last)) = (position, debug.last_predicted) {
+        if is_prediction_correction(&config, last, position, time.delta_secs()) {
+            debug.this_second += 1;
+        }
+    }
+    debug.last_predicted = position;
+
+    debug.second_timer += time.delta_secs();
+    if debug.second_timer >= 1.0 {
+        debug.second_timer -= 1.0;
+        let count = std::mem::take(&mut debug.this_second);
+        debug.history.push_back(count);
+        if debug.history.len() > GRAPH_SECS {
+            debug.history.pop_front();
+        }
+    }
+}
+
+fn draw_confirmed_outline(
+    debug: Res<ReconcileDebug>,
+    mut gizmos: Gizmos,
+    predicted: Query<(&PlayerId, &PlayerTransform), With<Predicted>>,
+    confirmed: Query<(&PlayerId, &PlayerTransform), With<Confirmed>>,
+) {
+    if !debug.enabled {
+        return;
+    }
+    for (id, server) in confirmed.iter() {
+        let color = if id.id == 0 {
+            Color::srgb(0.2, 1.0, 0.4)
+        } else {
+            Color::srgba(0.6, 0.6, 0.6, 0.6)
+        };
+        gizmos.rect(
+            Isometry3d::from_translation(server.translation),
+            OUTLINE_SIZE,
+            color,
+        );
+        // Line to where this client thinks the player is now
+        if let Some((_, local)) = predicted.iter().find(|(other, _)| other.id == id.id) {
+            gizmos.line(server.translation, local.translation, color);
+        }
+    }
+}
+
+fn update_overlay(
+    debug: Res<ReconcileDebug>,
+    predicted: Query<(&PlayerId, &PlayerTransform), With<Predicted>>,
+    confirmed: Query<(&PlayerId, &PlayerTransform), With<Confirmed>>,
+    mut texts: Query<&mut Text, With<ReconcileText>>,
+    mut bars: Query<(&GraphBar, &mut Node)>,
+) {
+    if !debug.enabled {
+        return;
+    }
+    let local = |id: &PlayerId| id.id == 0;
+    let drift = predicted
+        .iter()
+        .find(|(id, _)| local(id))
+        .zip(confirmed.iter().find(|(id, _)| local(id)))
+        .map(|((_, predicted), (_, confirmed))| {
+            predicted.translation.distance(confirmed.translation)
+        });
+    let label = match drift {
+        Some(drift) => format!(
+            "F3 prediction debug\ndrift from server: {:.1}\ncorrections last second: {}",
+            drift,
+            debug.history.back().copied().unwrap_or_default()
+        ),
+        None => "F3 prediction debug\nno predicted player (offline?)".to_string(),
+    };
+    if let Ok(mut text) = texts.single_mut() {
+        if text.0 != label {
+            text.0 = label;
+        }
+    }
+
+    // Newest second on the right
+    let offset = GRAPH_SECS - debug.history.len();
+    for (bar, mut node) in bars.iter_mut() {
+        let count = bar
+            .0
+            .checked_sub(offset)
+            .and_then(|index| debug.history.get(index))
+            .copied()
+            .unwrap_or_default();
+        let height = Val::Px((count as f32 * BAR_SCALE).min(GRAPH_HEIGHT));
+        if node.height != height {
+            node.height = height;
+        }
+    }
+}
+
+fn cleanup_overlay(
+    mut commands: Commands,
+    mut debug: ResMut<ReconcileDebug>,
+    overlays: Query<Entity, With<ReconcileOverlay>>,
+) {
+    for entity in overlays.iter() {
+        commands.entity(entity).despawn();
+    }
+    *debug = ReconcileDebug::default();
+}
@@ -0,0 +1,656 @@
+// 🚪 Join Room screen: a server browser with mode, free-slot and name filters, sorting
+// and pages. The lobby service filters when it can (see `RoomQuery`), the client runs the
+// same query itself on older services and on the local room list.
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+
+use shared::{BotStyle, GameMode, RoomInfo, RoomPage, RoomSort, ServerLobbyRoom};
+
+#[cfg(feature = "bevygap")]
+use {
+    super::lobby::LobbyMode,
+    crate::http::{http_base, spawn, ApiRequest, Pending},
+    crate::text_input::FocusedInput,
+    bevy::platform::time::Instant,
+    shared::{RegionEndpoint, RoomListResponse, RoomQuery},
+    std::collections::HashMap,
+    std::sync::Mutex,
+};
+
+use super::lobby::{
+    spawn_back_button_simple, spawn_input_row, ConfirmJoinButton, LobbyEvent, LobbyUI,
+    LobbyUIElements, RoomCodeInput, RoomIdButton, RoomPasswordInput, MAX_INPUT_LEN,
+};
+use crate::text_input::{spawn_text_input, TextInput, TextSubmitted};
+
+// Pixels one mouse wheel line scrolls the room list
+const LINE_HEIGHT: f32 = 24.0;
+
+// Requests timed per region, the fastest one counts. The first also sets up the
+// connection, so a single one would overstate the ping.
+#[cfg(feature = "bevygap")]
+const PINGS_PER_REGION: usize = 3;
+
+// Seconds between room list refreshes while the Join Room screen is open
+#[cfg(feature = "bevygap")]
+const ROOM_LIST_REFRESH_SECS: f32 = 5.0;
+
+// Validators of the last room list answer, a refresh of the same URL sends them back
+#[cfg(feature = "bevygap")]
+static ROOM_LIST_VALIDATORS: Mutex<Option<ListValidators>> = Mutex::new(None);
+#[cfg(feature = "bevygap")]
+static PENDING_REFRESHED_ROOMS: Pending<(RoomQuery, RoomPage)> = Pending::new();
+
+#[cfg(feature = "bevygap")]
+#[derive(Clone)]
+struct ListValidators {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+// Browser controls draw their own state, the generic lobby button colors leave them alone
+#[derive(Component)]
+pub(super) struct BrowserButton;
+
+#[derive(Component)]
+pub(super) struct ModeFilterButton(Option<String>);
+
+#[derive(Component)]
+pub(super) struct SpaceFilterButton;
+
+#[derive(Component)]
+pub(super) struct SortButton;
+
+#[derive(Component)]
+pub(super) struct RoomPageButton(i32);
+
+#[derive(Component)]
+pub(super) struct RefreshRoomsButton;
+
+#[derive(Component)]
+pub(super) struct RoomSearchInput;
+
+#[derive(Component)]
+pub(super) struct RoomListScroll;
+
+pub(super) fn spawn_join_room_ui(
+    commands: &mut Commands,
+    container_entity: Entity,
+    lobby_ui: &LobbyUI,
+) {
+    let query = &lobby_ui.room_query;
+
+    let title = commands
+        .spawn((
+            Text::new("Join Room"),
+            TextFont {
+                font_size: 28.0,
+                ..default()
+            },
+            TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            Node {
+                margin: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            LobbyUIElements,
+        ))
+        .id();
+
+    // Mode filter: every mode, or just one
+    let modes = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Row,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            LobbyUIElements,
+        ))
+        .with_children(|row| {
+            toggle(
+                row,
+                "ALL",
+                query.game_mode.is_none(),
+                ModeFilterButton(None),
+            );
+            for mode in GameMode::ALL {
+                toggle(
+                    row,
+                    &mode.id().to_uppercase(),
+                    query.game_mode.as_deref() == Some(mode.id()),
+                    ModeFilterButton(Some(mode.id().to_string())),
+                );
+            }
+        })
+        .id();
+
+    let search_input = spawn_text_input(
+        commands,
+        TextInput::new(
+            query.search.clone().unwrap_or_default(),
+            "Room or host",
+            MAX_INPUT_LEN,
+        ),
+        180.0,
+    );
+    commands.entity(search_input).insert(RoomSearchInput);
+    let filters = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Row,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            LobbyUIElements,
+        ))
+        .with_children(|row| {
+            toggle(row, "HAS SPACE", query.has_space, SpaceFilterButton);
+            let sort = match query.sort {
+                RoomSort::Newest => "↕ NEWEST",
+                RoomSort::MostPlayers => "↕ MOST PLAYERS",
+            };
+            toggle(row, sort, false, SortButton);
+        })
+        .id();
+    commands.entity(filters).add_child(search_input);
+    commands.entity(filters).with_children(|row| {
+        toggle(row, "🔄 REFRESH", false, RefreshRoomsButton);
+    });
+
+    // The current page of rooms, scrolled with the mouse wheel if it doesn't fit
+    let rooms_container = commands
+        .spawn((
+            Node {
+                width: Val::Px(420.0),
+                max_height: Val::Px(240.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                margin: UiRect::all(Val::Px(10.0)),
+                overflow: Overflow::scroll_y(),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.3)),
+            ScrollPosition::default(),
+            RoomListScroll,
+            LobbyUIElements,
+        ))
+        .id();
+
+    if lobby_ui.available_rooms.is_empty() {
+        let empty_text = commands
+            .spawn((
+                Text::new(if lobby_ui.room_total == 0 {
+                    "No rooms match"
+                } else {
+                    "Nothing on this page"
+                }),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                Node {
+                    margin: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+            ))
+            .id();
+        commands.entity(rooms_container).add_child(empty_text);
+    }
+    for room in &lobby_ui.available_rooms {
+        let ping = room
+            .region
+            .as_ref()
+            .and_then(|region| lobby_ui.region_pings.get(region).copied());
+        let room_btn = spawn_room_row(commands, room, room.room_id == lobby_ui.room_id, ping);
+        commands.entity(rooms_container).add_child(room_btn);
+    }
+
+    let per_page = query.per_page();
+    let pages = lobby_ui.room_total.div_ceil(per_page).max(1);
+    let pager = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Row,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            LobbyUIElements,
+        ))
+        .with_children(|row| {
+            if query.page > 0 {
+                toggle(row, "◀", false, RoomPageButton(-1));
+            }
+            row.spawn((
+                Text::new(format!(
+                    "Page {}/{} - {} rooms",
+                    query.page + 1,
+                    pages,
+                    lobby_ui.room_total
+                )),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+            ));
+            if query.page + 1 < pages {
+                toggle(row, "▶", false, RoomPageButton(1));
+            }
+        })
+        .id();
+
+    // Type a room code, or pick one from the list above
+    let room_input = spawn_text_input(
+        commands,
+        TextInput::new(lobby_ui.room_code.clone(), "Room code", MAX_INPUT_LEN),
+        200.0,
+    );
+    commands.entity(room_input).insert(RoomCodeInput);
+    let room_row = spawn_input_row(commands, "Room code:", room_input);
+
+    let password_input = spawn_text_input(
+        commands,
+        TextInput::new(
+            lobby_ui.room_password.clone(),
+            "Only for locked rooms",
+            MAX_INPUT_LEN,
+        )
+        .masked(),
+        200.0,
+    );
+    commands.entity(password_input).insert(RoomPasswordInput);
+    let password_row = spawn_input_row(commands, "Password:", password_input);
+
+    let join_btn = commands
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(150.0),
+                height: Val::Px(50.0),
+                margin: UiRect::all(Val::Px(10.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.2, 0.4, 0.6)),
+            ConfirmJoinButton,
+            LobbyUIElements,
+        ))
+        .with_children(|btn| {
+            btn.spawn((
+                Text::new("JOIN"),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            ));
+        })
+        .id();
+
+    let back_btn = spawn_back_button_simple(commands);
+
+    commands.entity(container_entity).add_child(title);
+    commands.entity(container_entity).add_child(modes);
+    commands.entity(container_entity).add_child(filters);
+    commands.entity(container_entity).add_child(rooms_container);
+    commands.entity(container_entity).add_child(pager);
+    commands.entity(container_entity).add_child(room_row);
+    commands.entity(container_entity).add_child(password_row);
+    commands.entity(container_entity).add_child(join_btn);
+    commands.entity(container_entity).add_child(back_btn);
+}
+
+fn spawn_room_row(
+    commands: &mut Commands,
+    room: &RoomInfo,
+    selected: bool,
+    ping: Option<u32>,
+) -> Entity {
+    let mut room_text = format!(
+        "{} ({}/{}) - {} - {}",
+        room.code, room.current_players, room.max_players, room.game_mode, room.host_name
+    );
+    // Older services send no map name, the level id is the next best thing
+    let map = if room.map.is_empty() {
+        &room.level
+    } else {
+        &room.map
+    };
+    if !map.is_empty() {
+        room_text = format!("{} - {}", room_text, map);
+    }
+    match (&room.region, ping) {
+        (Some(region), Some(ms)) => room_text = format!("{} - 🌍 {} ~{} ms", room_text, region, ms),
+        (Some(region), None) => room_text = format!("{} - 🌍 {}", room_text, region),
+        _ => {}
+    }
+    if room.has_password {
+        room_text = format!("🔒 {}", room_text);
+    }
+    commands
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(400.0),
+                min_height: Val::Px(35.0),
+                margin: UiRect::all(Val::Px(4.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(if selected {
+                Color::srgb(0.2, 0.2, 0.2)
+            } else {
+                Color::srgb(0.3, 0.3, 0.3)
+            }),
+            RoomIdButton(room.room_id.clone()),
+        ))
+        .with_children(|btn| {
+            btn.spawn((
+                Text::new(room_text),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            ));
+        })
+        .id()
+}
+
+// A small button, green while its filter is on
+fn toggle(row: &mut ChildSpawnerCommands, label: &str, on: bool, marker: impl Bundle) {
+    row.spawn((
+        Button,
+        Node {
+            height: Val::Px(32.0),
+            padding: UiRect::horizontal(Val::Px(10.0)),
+            margin: UiRect::all(Val::Px(4.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(if on {
+            Color::srgb(0.4, 0.7, 0.4)
+        } else {
+            Color::srgb(0.3, 0.3, 0.3)
+        }),
+        BrowserButton,
+        marker,
+    ))
+    .with_children(|btn| {
+        btn.spawn((
+            Text::new(label),
+            TextFont {
+                font_size: 13.0,
+                ..default()
+            },
+            TextColor(Color::srgb(1.0, 1.0, 1.0)),
+        ));
+    });
+}
+
+// Any filter change starts over at the first page and asks for the list again
+pub(super) fn handle_browser_buttons(
+    buttons: Query<
+        (
+            &Interaction,
+            Option<&ModeFilterButton>,
+            Option<&SpaceFilterButton>,
+            Option<&SortButton>,
+            Option<&RoomPageButton>,
+            Option<&RefreshRoomsButton>,
+        ),
+        (Changed<Interaction>, With<BrowserButton>),
+    >,
+    search_inputs: Query<&TextInput, With<RoomSearchInput>>,
+    mut submitted: EventReader<TextSubmitted>,
+    mut lobby_ui_query: Query<&mut LobbyUI>,
+    mut lobby_events: EventWriter<LobbyEvent>,
+) {
+    let searched = submitted
+        .read()
+        .filter(|event| search_inputs.contains(event.entity))
+        .count()
+        > 0;
+    let pressed = buttons
+        .iter()
+        .find(|(interaction, ..)| **interaction == Interaction::Pressed);
+    if pressed.is_none() && !searched {
+        return;
+    }
+    let Ok(mut lobby_ui) = lobby_ui_query.single_mut() else {
+        return;
+    };
+
+    let mut query = lobby_ui.room_query.clone();
+    let typed = search_inputs
+        .single()
+        .map(|input| input.value.trim().to_string())
+        .unwrap_or_default();
+    query.search = Some(typed).filter(|search| !search.is_empty());
+    let mut page = 0;
+    if let Some((_, mode, space, sort, page_step, _)) = pressed {
+        if let Some(mode) = mode {
+            query.game_mode = mode.0.clone();
+        }
+        if space.is_some() {
+            query.has_space = !query.has_space;
+        }
+        if sort.is_some() {
+            query.sort = match query.sort {
+                RoomSort::Newest => RoomSort::MostPlayers,
+                RoomSort::MostPlayers => RoomSort::Newest,
+            };
+        }
+        if let Some(step) = page_step {
+            page = query.page.saturating_add_signed(step.0);
+        }
+    }
+    query.page = page;
+
+    lobby_ui.room_query = query;
+    lobby_events.write(LobbyEvent::RequestRoomList);
+}
+
+pub(super) fn scroll_room_list(
+    mut wheel: EventReader<MouseWheel>,
+    mut lists: Query<&mut ScrollPosition, With<RoomListScroll>>,
+) {
+    let dy: f32 = wheel
+        .read()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y * LINE_HEIGHT,
+            MouseScrollUnit::Pixel => event.y,
+        })
+        .sum();
+    if dy == 0.0 {
+        return;
+    }
+    for mut position in lists.iter_mut() {
+        position.offset_y = (position.offset_y - dy).max(0.0);
+    }
+}
+
+/// Run the browser's query over a room list that was not filtered yet
+pub(super) fn show_rooms(lobby_ui: &mut LobbyUI, rooms: Vec<ServerLobbyRoom>) {
+    let page = lobby_ui.room_query.apply(rooms);
+    show_page(lobby_ui, page);
+}
+
+pub(super) fn show_page(lobby_ui: &mut LobbyUI, page: RoomPage) {
+    lobby_ui.available_rooms = page.rooms.into_iter().map(RoomInfo::from).collect();
+    lobby_ui.room_total = page.total;
+}
+
+/// `GET /rooms` for `query`. A refresh sends the validators of the last answer for the same
+/// query back, and gets None when the list hasn't changed since. Errors are notice text.
+#[cfg(feature = "bevygap")]
+pub(super) async fn fetch_room_page(
+    query: &RoomQuery,
+    refresh: bool,
+) -> Result<Option<RoomPage>, String> {
+    let url = format!("{}/lobby/api/rooms{}", http_base(), query.to_query_string());
+    let mut request = ApiRequest::get(url.clone());
+    if refresh {
+        let last = ROOM_LIST_VALIDATORS
+            .lock()
+            .ok()
+            .and_then(|last| last.clone());
+        if let Some(last) = last {
+            if last.url == url {
+                if let Some(etag) = last.etag {
+                    request = request.header("If-None-Match", etag);
+                }
+                if let Some(last_modified) = last.last_modified {
+                    request = request.header("If-Modified-Since", last_modified);
+                }
+            }
+        }
+    }
+
+    let failed = |error| format!("Failed loading rooms ({})", error);
+    let resp = request.send().await.map_err(failed)?;
+    if resp.status == 304 {
+        return Ok(None);
+    }
+    // Older services send neither, and every refresh gets the whole list
+    let validators = ListValidators {
+        url,
+        etag: resp.etag.clone(),
+        last_modified: resp.last_modified.clone(),
+    };
+    if let Ok(mut last) = ROOM_LIST_VALIDATORS.lock() {
+        *last = Some(validators);
+    }
+    if !refresh {
+        // Anything a refresh brought in before this answer is older than it
+        PENDING_REFRESHED_ROOMS.take();
+    }
+
+    // Older services ignore the query and send every room
+    let page = match resp.json().map_err(failed)? {
+        RoomListResponse::Page(page) => page,
+        RoomListResponse::All(rooms) => query.apply(rooms),
+    };
+    Ok(Some(page))
+}
+
+/// Keeps the Join Room list current: every `ROOM_LIST_REFRESH_SECS` while the screen is
+/// open, ask again with the last answer's validators. An unchanged list costs a 304
+/// without a body.
+#[cfg(feature = "bevygap")]
+pub(super) fn refresh_room_list(
+    time: Res<Time>,
+    lobby_q: Query<&LobbyUI>,
+    mut timer: Local<Option<Timer>>,
+) {
+    let Ok(ui) = lobby_q.single() else {
+        return;
+    };
+    let timer = timer
+        .get_or_insert_with(|| Timer::from_seconds(ROOM_LIST_REFRESH_SECS, TimerMode::Repeating));
+    // The screen fetches the list itself when it opens
+    if ui.lobby_mode != LobbyMode::JoinRoom {
+        timer.reset();
+        return;
+    }
+    if !timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let query = ui.room_query.clone();
+    spawn(async move {
+        // A failed refresh stays quiet, the list just gets a little older
+        if let Ok(Some(page)) = fetch_room_page(&query, true).await {
+            PENDING_REFRESHED_ROOMS.set((query, page));
+        }
+    });
+}
+
+// A new list redraws the screen, so it waits while the player types, and only lands if
+// the player is still looking at the same query and something changed
+#[cfg(feature = "bevygap")]
+pub(super) fn pump_refreshed_rooms(focused: Res<FocusedInput>, mut lobby_q: Query<&mut LobbyUI>) {
+    if focused.0.is_some() {
+        return;
+    }
+    let Some((query, page)) = PENDING_REFRESHED_ROOMS.take() else {
+        return;
+    };
+    let Ok(mut ui) = lobby_q.single_mut() else {
+        return;
+    };
+    if ui.lobby_mode != LobbyMode::JoinRoom || ui.room_query != query {
+        return;
+    }
+    let rooms: Vec<RoomInfo> = page.rooms.iter().cloned().map(RoomInfo::from).collect();
+    if ui.available_rooms != rooms || ui.room_total != page.total {
+        show_page(&mut ui, page);
+    }
+}
+
+/// Round trip in milliseconds to every region the lobby service lists, regions that
+/// don't answer are left out
+#[cfg(feature = "bevygap")]
+pub(super) async fn ping_regions() -> HashMap<String, u32> {
+    let mut pings = HashMap::new();
+    let url = format!("{}/lobby/api/regions", http_base());
+    // Older services have no region list
+    let Ok(regions) = ApiRequest::get(url).json::<Vec<RegionEndpoint>>().await else {
+        return pings;
+    };
+    for endpoint in regions {
+        let mut best: Option<f64> = None;
+        for _ in 0..PINGS_PER_REGION {
+            let started = Instant::now();
+            // A retry would count its wait as ping
+            let request = ApiRequest::get(endpoint.ping_url.clone()).attempts(1);
+            if request.fetch().await.is_err() {
+                break;
+            }
+            let ms = started.elapsed().as_secs_f64() * 1000.0;
+            best = Some(best.map_or(ms, |best| best.min(ms)));
+        }
+        if let Some(ms) = best {
+            pings.insert(endpoint.region, ms.round() as u32);
+        }
+    }
+    pings
+}
+
+/// Rooms this client knows about without a lobby service, in the service's format
+pub(super) fn local_rooms(rooms: &[RoomInfo]) -> Vec<ServerLobbyRoom> {
+    rooms
+        .iter()
+        .enumerate()
+        .map(|(order, room)| ServerLobbyRoom {
+            id: room.room_id.clone(),
+            code: room.code.clone(),
+            host_name: room.host_name.clone(),
+            host_id: None,
+            game_mode: room.game_mode.clone(),
+            // Later in the list means created later
+            created_at: order as u64,
+            started: false,
+            current_players: room.current_players,
+            max_players: room.max_players,
+            level: room.level.clone(),
+            has_password: room.has_password,
+            map: room.map.clone(),
+            region: room.region.clone(),
+            teams: 0,
+            friendly_fire: false,
+            members: Vec::new(),
+            publish_results: false,
+            last_match: None,
+            rules: None,
+            bot_backfill: false,
+            bot_style: BotStyle::default(),
+        })
+        .collect()
+}
@@ -0,0 +1,243 @@
+// 🔗 Room links: `https://voidloop.quest/?join=K7QX2M` opens the web client straight into
+// room K7QX2M. Players who already have a lobby token join right away, everyone else, and
+// anyone following a link to a locked room, lands on the Join Room page with the code filled
+// in. Hosts copy the link from the room screen.
+use bevy::prelude::*;
+
+use super::lobby::LobbyUI;
+
+#[cfg(target_arch = "wasm32")]
+use {
+    super::lobby::{join_room, LobbyEvent, LobbyUIElements, UiNotice},
+    crate::account::auth_token,
+    crate::http::{http_base, ApiRequest},
+    shared::{join_code_from_query, room_link, ServerLobbyRoom},
+    std::cell::RefCell,
+    wasm_bindgen_futures::spawn_local,
+};
+
+// Seconds we wait for a lobby token before looking the room up without one
+#[cfg(target_arch = "wasm32")]
+const LINK_TOKEN_WAIT_SECS: f32 = 3.0;
+
+#[cfg(target_arch = "wasm32")]
+enum LinkedRoom {
+    Found(ServerLobbyRoom),
+    Gone,
+    // The service couldn't say, older ones have no `GET /rooms/{id}`
+    Unknown,
+}
+
+#[cfg(target_arch = "wasm32")]
+thread_local! {
+    static PENDING_LINKED_ROOM: RefCell<Option<(String, LinkedRoom)>> = const { RefCell::new(None) };
+}
+
+/// Room code from the page's `?join=`, until the lobby acted on it
+#[cfg(target_arch = "wasm32")]
+#[derive(Resource, Default)]
+pub struct DeepLink {
+    pub code: Option<String>,
+    /// The room is being looked up
+    pub looking_up: bool,
+}
+
+/// COPY LINK on the room screen
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+#[derive(Component)]
+pub(super) struct CopyLinkButton;
+
+/// Take the code out of the address, so reloading the page doesn't join again
+#[cfg(target_arch = "wasm32")]
+pub(super) fn read_deep_link(mut deep_link: ResMut<DeepLink>) {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let location = window.location();
+    let search = location.search().unwrap_or_default();
+    let Some(code) = join_code_from_query(&search) else {
+        return;
+    };
+    info!("🔗 Opened with a link to room {}", code);
+    deep_link.code = Some(code);
+
+    let rest: Vec<&str> = search
+        .trim_start_matches('?')
+        .split('&')
+        .filter(|pair| !pair.is_empty() && !pair.starts_with("join="))
+        .collect();
+    let mut url = location.pathname().unwrap_or_default();
+    if !rest.is_empty() {
+        url = format!("{}?{}", url, rest.join("&"));
+    }
+    if let Ok(history) = window.history() {
+        let _ = history.replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&url));
+    }
+}
+
+/// Look the linked room up once we have a token, or waited long enough for one
+#[cfg(target_arch = "wasm32")]
+pub(super) fn open_deep_link(
+    time: Res<Time>,
+    mut deep_link: ResMut<DeepLink>,
+    mut waited: Local<f32>,
+) {
+    let Some(code) = deep_link.code.clone() else {
+        return;
+    };
+    if deep_link.looking_up {
+        return;
+    }
+    *waited += time.delta_secs();
+    if auth_token().is_none() && *waited < LINK_TOKEN_WAIT_SECS {
+        return;
+    }
+    deep_link.looking_up = true;
+    spawn_local(async move {
+        // The service takes a room's code wherever it takes its id
+        let url = format!("{}/lobby/api/rooms/{}", http_base(), code);
+        let room = match ApiRequest::get(url).json().await {
+            Ok(room) => LinkedRoom::Found(room),
+            Err(error) if error.status() == Some(404) => LinkedRoom::Gone,
+            Err(_) => LinkedRoom::Unknown,
+        };
+        PENDING_LINKED_ROOM.with(|cell| cell.replace(Some((code, room))));
+    });
+}
+
+// Join open rooms when we have a token, show the Join Room page otherwise
+#[cfg(target_arch = "wasm32")]
+pub(super) fn pump_deep_link(
+    mut deep_link: ResMut<DeepLink>,
+    mut lobby_q: Query<&mut LobbyUI>,
+    mut lobby_events: EventWriter<LobbyEvent>,
+    mut notice: ResMut<UiNotice>,
+) {
+    let Some((code, room)) = PENDING_LINKED_ROOM.with(|cell| cell.borrow_mut().take()) else {
+        return;
+    };
+    *deep_link = DeepLink::default();
+    let Ok(mut ui) = lobby_q.single_mut() else {
+        return;
+    };
+    match room {
+        LinkedRoom::Found(room)
+            if auth_token().is_some() && !room.has_password && !room.started =>
+        {
+            info!("🔗 Joining room {} from the link", room.code);
+            ui.room_id = room.id;
+            ui.room_code = room.code;
+            // Joiners play whatever level the host picked
+            if !room.level.is_empty() {
+                ui.selected_level = room.level;
+            }
+            join_room(&mut ui);
+        }
+        LinkedRoom::Gone => {
+            notice.msg = Some(format!("Room {} from the link is gone", code));
+            notice.timer = 0.0;
+        }
+        _ => {
+            lobby_events.write(LobbyEvent::RequestRoomList);
+            lobby_events.write(LobbyEvent::EnterRoomCode(code));
+        }
+    }
+}
+
+/// The room's link with COPY LINK, for players to send around. Only the web client has an
+/// address to share.
+#[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
+pub(super) fn spawn_share_link(commands: &mut Commands, lobby_ui: &LobbyUI) -> Option<Entity> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        None
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        if lobby_ui.room_code.is_empty() {
+            return None;
+        }
+        let link = share_link(lobby_ui)?;
+        let label = commands
+            .spawn((
+                Text::new(format!("🔗 {}", link)),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.7, 0.8, 1.0)),
+            ))
+            .id();
+        let button = commands
+            .spawn((
+                Button,
+                Node {
+                    height: Val::Px(30.0),
+                    padding: UiRect::horizontal(Val::Px(10.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.3, 0.35, 0.5)),
+                CopyLinkButton,
+            ))
+            .with_children(|btn| {
+                btn.spawn((
+                    Text::new("COPY LINK"),
+                    TextFont {
+                        font_size: 13.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                ));
+            })
+            .id();
+        let row = commands
+            .spawn((
+                Node {
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(10.0),
+                    margin: UiRect::bottom(Val::Px(10.0)),
+                    ..default()
+                },
+                LobbyUIElements,
+            ))
+            .add_children(&[label, button])
+            .id();
+        Some(row)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn share_link(lobby_ui: &LobbyUI) -> Option<String> {
+    let origin = web_sys::window()?.location().origin().ok()?;
+    Some(room_link(&origin, &lobby_ui.room_code))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(super) fn handle_copy_link(
+    buttons: Query<&Interaction, (Changed<Interaction>, With<CopyLinkButton>)>,
+    lobby_q: Query<&LobbyUI>,
+    mut notice: ResMut<UiNotice>,
+) {
+    if !buttons
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        return;
+    }
+    let Ok(ui) = lobby_q.single() else {
+        return;
+    };
+    let (Some(window), Some(link)) = (web_sys::window(), share_link(ui)) else {
+        return;
+    };
+    let copied = window.navigator().clipboard().write_text(&link);
+    spawn_local(async move {
+        if let Err(e) = wasm_bindgen_futures::JsFuture::from(copied).await {
+            web_sys::console::warn_2(&"Couldn't copy the room link".into(), &e);
+        }
+    });
+    notice.msg = Some("🔗 Link copied, send it to your friends".to_string());
+    notice.timer = 0.0;
+}
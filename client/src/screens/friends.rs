@@ -0,0 +1,530 @@
+// 👥 Friends: a page to add and remove friends by player id and see who is online and in
+// which room, and a panel in the room to invite online friends with one click. The
+// presence heartbeat tells the lobby service we are around and brings the list back with
+// any invites for us, see `shared::friends`.
+use bevy::prelude::*;
+
+use shared::{Friend, RoomInvite};
+
+use super::lobby::{
+    spawn_back_button_simple, LobbyEvent, LobbyUI, LobbyUIElements, UiNotice, MAX_INPUT_LEN,
+};
+use crate::text_input::{spawn_text_input, TextInput, TextSubmitted};
+
+#[cfg(target_arch = "wasm32")]
+use {
+    crate::http::{http_base, ApiRequest},
+    shared::PRESENCE_INTERVAL_SECS,
+    shared::{AddFriendRequest, PresenceRequest, PresenceResponse, RoomInviteRequest},
+    std::cell::RefCell,
+    wasm_bindgen_futures::spawn_local,
+};
+
+#[cfg(target_arch = "wasm32")]
+thread_local! {
+    static PENDING_PRESENCE: RefCell<Option<PresenceResponse>> = const { RefCell::new(None) };
+    static PENDING_FRIENDS: RefCell<Option<Vec<Friend>>> = const { RefCell::new(None) };
+    static PENDING_FRIENDS_NOTICE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Our friends and the room invites we haven't answered, as the lobby service last said
+#[derive(Resource, Default)]
+pub struct Friends {
+    /// None until the first presence heartbeat came back
+    pub list: Option<Vec<Friend>>,
+    pub invites: Vec<RoomInvite>,
+    /// Player id typed to add a friend
+    pub draft: String,
+}
+
+#[derive(Component, Clone, Debug, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+pub(super) enum FriendButton {
+    Add,
+    /// Remove the friend with this player id
+    Remove(String),
+    /// Invite the friend with this player id to our room
+    Invite(String),
+    /// Join the room of an invite, by its code
+    Join(String),
+}
+
+#[derive(Component)]
+pub(super) struct FriendIdInput;
+
+pub(super) fn sync_friend_input(
+    inputs: Query<Ref<TextInput>, With<FriendIdInput>>,
+    mut submitted: EventReader<TextSubmitted>,
+    mut friends: ResMut<Friends>,
+    lobby_q: Query<&LobbyUI>,
+    mut notice: ResMut<UiNotice>,
+    mut lobby_events: EventWriter<LobbyEvent>,
+) {
+    for input in inputs.iter().filter(|input| input.is_changed()) {
+        friends.bypass_change_detection().draft = input.value.trim().to_string();
+    }
+    let Ok(ui) = lobby_q.single() else {
+        return;
+    };
+    // Enter adds, like the ADD button
+    for event in submitted.read() {
+        if inputs.contains(event.entity) {
+            press(
+                FriendButton::Add,
+                ui,
+                &mut friends,
+                &mut notice,
+                &mut lobby_events,
+            );
+        }
+    }
+}
+
+pub(super) fn handle_friend_buttons(
+    buttons: Query<(&Interaction, &FriendButton), Changed<Interaction>>,
+    lobby_q: Query<&LobbyUI>,
+    mut friends: ResMut<Friends>,
+    mut notice: ResMut<UiNotice>,
+    mut lobby_events: EventWriter<LobbyEvent>,
+) {
+    let Ok(ui) = lobby_q.single() else {
+        return;
+    };
+    for (interaction, button) in buttons.iter() {
+        if *interaction == Interaction::Pressed {
+            press(
+                button.clone(),
+                ui,
+                &mut friends,
+                &mut notice,
+                &mut lobby_events,
+            );
+        }
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
+fn press(
+    button: FriendButton,
+    ui: &LobbyUI,
+    friends: &mut Friends,
+    notice: &mut UiNotice,
+    lobby_events: &mut EventWriter<LobbyEvent>,
+) {
+    // Taking an invite opens the room browser with the code filled in, a locked room
+    // still wants its password
+    if let FriendButton::Join(code) = &button {
+        info!("✉️ Opening room {} from an invite", code);
+        friends.invites.retain(|invite| invite.room_code != *code);
+        lobby_events.write(LobbyEvent::RequestRoomList);
+        lobby_events.write(LobbyEvent::EnterRoomCode(code.clone()));
+        return;
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        notice.msg = Some("Friends need the lobby service".to_string());
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let Some(player_id) = ui.player_id.clone() else {
+            notice.msg = Some("Friends need an account, wait for the lobby".to_string());
+            return;
+        };
+        match button {
+            FriendButton::Add => {
+                let friend_id = friends.draft.clone();
+                if friend_id.is_empty() || friend_id.len() > MAX_INPUT_LEN {
+                    notice.msg = Some("Type your friend's player id".to_string());
+                    return;
+                }
+                if friend_id == player_id {
+                    notice.msg = Some("That's your own id".to_string());
+                    return;
+                }
+                info!("👥 Adding friend {}", friend_id);
+                let url = format!("{}/lobby/api/players/{}/friends", http_base(), player_id);
+                friends_call(ApiRequest::post(url, &AddFriendRequest { friend_id }));
+            }
+            FriendButton::Remove(friend_id) => {
+                info!("👥 Removing friend {}", friend_id);
+                let url = format!(
+                    "{}/lobby/api/players/{}/friends/{}",
+                    http_base(),
+                    player_id,
+                    friend_id
+                );
+                friends_call(ApiRequest::new("DELETE", url));
+            }
+            FriendButton::Invite(friend_id) => {
+                if ui.room_code.is_empty() {
+                    return;
+                }
+                let name = friend_name(friends, &friend_id);
+                info!("✉️ Inviting {} to room {}", name, ui.room_code);
+                notice.msg = Some(format!("✉️ Invited {} to room {}", name, ui.room_code));
+                send_invite(player_id, friend_id, ui.room_code.clone());
+            }
+            FriendButton::Join(_) => {}
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn friend_name(friends: &Friends, friend_id: &str) -> String {
+    friends
+        .list
+        .iter()
+        .flatten()
+        .find(|friend| friend.player_id == friend_id)
+        .map_or_else(
+            || friend_id.to_string(),
+            |friend| friend.display_name.clone(),
+        )
+}
+
+// Add and remove answer with the new list
+#[cfg(target_arch = "wasm32")]
+fn friends_call(request: ApiRequest) {
+    spawn_local(async move {
+        match request.json::<Vec<Friend>>().await {
+            Ok(list) => {
+                PENDING_FRIENDS.with(|cell| cell.replace(Some(list)));
+            }
+            Err(error) => {
+                PENDING_FRIENDS_NOTICE
+                    .with(|cell| cell.replace(Some(format!("Friends: {}", error))));
+            }
+        }
+    });
+}
+
+#[cfg(target_arch = "wasm32")]
+fn send_invite(player_id: String, friend_id: String, room_code: String) {
+    spawn_local(async move {
+        let url = format!(
+            "{}/lobby/api/players/{}/friends/{}/invite",
+            http_base(),
+            player_id,
+            friend_id
+        );
+        let request = ApiRequest::post(url, &RoomInviteRequest { room_code });
+        if let Err(error) = request.send().await {
+            PENDING_FRIENDS_NOTICE
+                .with(|cell| cell.replace(Some(format!("Invite failed ({})", error))));
+        }
+    });
+}
+
+/// Presence heartbeat while the lobby is open: right away, then every
+/// `PRESENCE_INTERVAL_SECS`. Needs the account, friends are kept by player id.
+#[cfg(target_arch = "wasm32")]
+pub(super) fn send_presence(
+    time: Res<Time>,
+    lobby_q: Query<&LobbyUI>,
+    mut timer: Local<Option<Timer>>,
+) {
+    let Ok(ui) = lobby_q.single() else {
+        return;
+    };
+    let Some(player_id) = ui.player_id.clone() else {
+        return;
+    };
+    let timer = timer.get_or_insert_with(|| {
+        let mut timer = Timer::from_seconds(PRESENCE_INTERVAL_SECS as f32, TimerMode::Repeating);
+        timer.set_elapsed(timer.duration());
+        timer
+    });
+    if !timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let url = format!("{}/lobby/api/players/{}/presence", http_base(), player_id);
+    let request = ApiRequest::post(
+        url,
+        &PresenceRequest {
+            display_name: ui.player_name.clone(),
+            room_code: Some(ui.room_code.clone()).filter(|code| !code.is_empty()),
+        },
+    );
+    spawn_local(async move {
+        // Older services have no friends
+        if let Ok(presence) = request.json::<PresenceResponse>().await {
+            PENDING_PRESENCE.with(|cell| cell.replace(Some(presence)));
+        }
+    });
+}
+
+// Only touch `Friends` when something changed, every change redraws the page. New
+// invites also show up as a notice wherever the player is.
+#[cfg(target_arch = "wasm32")]
+pub(super) fn pump_friends(mut friends: ResMut<Friends>, mut notice: ResMut<UiNotice>) {
+    if let Some(msg) = PENDING_FRIENDS_NOTICE.with(|cell| cell.borrow_mut().take()) {
+        notice.msg = Some(msg);
+    }
+    let (list, invites) = match PENDING_PRESENCE.with(|cell| cell.borrow_mut().take()) {
+        Some(presence) => (Some(presence.friends), presence.invites),
+        None => (
+            PENDING_FRIENDS.with(|cell| cell.borrow_mut().take()),
+            Vec::new(),
+        ),
+    };
+    if let Some(list) = list {
+        if friends.list.as_ref() != Some(&list) {
+            friends.list = Some(list);
+        }
+    }
+    for invite in invites {
+        notice.msg = Some(format!(
+            "✉️ {} invites you to room {} - see FRIENDS",
+            invite.from_name, invite.room_code
+        ));
+        friends
+            .invites
+            .retain(|known| known.room_code != invite.room_code);
+        friends.invites.push(invite);
+    }
+}
+
+/// The friends page: our id to hand out, the add field, invites and the list
+pub(super) fn spawn_friends_ui(
+    commands: &mut Commands,
+    container_entity: Entity,
+    friends: &Friends,
+    lobby_ui: &LobbyUI,
+) {
+    let title = commands
+        .spawn((
+            Text::new("👥 Friends"),
+            TextFont {
+                font_size: 28.0,
+                ..default()
+            },
+            TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            Node {
+                margin: UiRect::all(Val::Px(20.0)),
+                ..default()
+            },
+            LobbyUIElements,
+        ))
+        .id();
+    commands.entity(container_entity).add_child(title);
+
+    let own_id = commands
+        .spawn((
+            Text::new(match &lobby_ui.player_id {
+                Some(id) => format!("Your player id: {} - friends add you with it", id),
+                None => "No account yet, friends need the lobby service".to_string(),
+            }),
+            TextFont {
+                font_size: 14.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.8, 0.8, 0.8)),
+            LobbyUIElements,
+        ))
+        .id();
+    commands.entity(container_entity).add_child(own_id);
+
+    let input = spawn_text_input(
+        commands,
+        TextInput::new(friends.draft.clone(), "Friend's player id", MAX_INPUT_LEN),
+        300.0,
+    );
+    commands.entity(input).insert(FriendIdInput);
+    let add = button(commands, "ADD", FriendButton::Add);
+    let add_row = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(8.0),
+                margin: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            LobbyUIElements,
+        ))
+        .add_children(&[input, add])
+        .id();
+    commands.entity(container_entity).add_child(add_row);
+
+    let panel = commands
+        .spawn((
+            Node {
+                width: Val::Px(460.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(12.0)),
+                row_gap: Val::Px(6.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.4)),
+            LobbyUIElements,
+        ))
+        .id();
+    let mut lines = Vec::new();
+    for invite in &friends.invites {
+        let text = label(
+            commands,
+            format!(
+                "✉️ {} invited you to {}",
+                invite.from_name, invite.room_code
+            ),
+            Color::srgb(1.0, 0.85, 0.3),
+        );
+        let join = button(
+            commands,
+            "JOIN",
+            FriendButton::Join(invite.room_code.clone()),
+        );
+        lines.push(row(commands, &[text, join]));
+    }
+    match &friends.list {
+        None => lines.push(label(
+            commands,
+            if cfg!(target_arch = "wasm32") {
+                "⏳ loading..."
+            } else {
+                "Needs the lobby service, play the web version"
+            },
+            Color::srgb(0.8, 0.8, 0.8),
+        )),
+        Some(list) if list.is_empty() => lines.push(label(
+            commands,
+            "No friends yet, add one with their player id",
+            Color::srgb(0.6, 0.6, 0.6),
+        )),
+        Some(list) => {
+            for friend in list {
+                let text = label(commands, status_line(friend), status_color(friend));
+                let remove = button(
+                    commands,
+                    "REMOVE",
+                    FriendButton::Remove(friend.player_id.clone()),
+                );
+                lines.push(row(commands, &[text, remove]));
+            }
+        }
+    }
+    commands.entity(panel).add_children(&lines);
+    commands.entity(container_entity).add_child(panel);
+
+    let back = spawn_back_button_simple(commands);
+    commands.entity(container_entity).add_child(back);
+}
+
+/// Online friends who aren't in our room yet, each with an INVITE button. None when there
+/// is nobody to invite.
+pub(super) fn spawn_room_friends(
+    commands: &mut Commands,
+    friends: &Friends,
+    lobby_ui: &LobbyUI,
+) -> Option<Entity> {
+    let invitable: Vec<&Friend> = friends
+        .list
+        .iter()
+        .flatten()
+        .filter(|friend| friend.online && friend.room_code.as_ref() != Some(&lobby_ui.room_code))
+        .collect();
+    if invitable.is_empty() || lobby_ui.room_code.is_empty() {
+        return None;
+    }
+    let panel = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(8.0)),
+                margin: UiRect::all(Val::Px(6.0)),
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.4)),
+            LobbyUIElements,
+        ))
+        .id();
+    let mut lines = vec![label(
+        commands,
+        "👥 Friends online",
+        Color::srgb(1.0, 1.0, 1.0),
+    )];
+    for friend in invitable {
+        let text = label(commands, status_line(friend), status_color(friend));
+        let invite = button(
+            commands,
+            "INVITE",
+            FriendButton::Invite(friend.player_id.clone()),
+        );
+        lines.push(row(commands, &[text, invite]));
+    }
+    commands.entity(panel).add_children(&lines);
+    Some(panel)
+}
+
+fn status_line(friend: &Friend) -> String {
+    match (&friend.room_code, friend.online) {
+        (Some(code), true) => format!("● {} - in room {}", friend.display_name, code),
+        (None, true) => format!("● {} - online", friend.display_name),
+        (_, false) => format!("○ {}", friend.display_name),
+    }
+}
+
+fn status_color(friend: &Friend) -> Color {
+    if friend.online {
+        Color::srgb(0.5, 1.0, 0.5)
+    } else {
+        Color::srgb(0.6, 0.6, 0.6)
+    }
+}
+
+// Inside a panel, so without `LobbyUIElements`: the panel takes it along when it goes
+fn row(commands: &mut Commands, items: &[Entity]) -> Entity {
+    let row = commands
+        .spawn(Node {
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::SpaceBetween,
+            column_gap: Val::Px(8.0),
+            ..default()
+        })
+        .id();
+    commands.entity(row).add_children(items);
+    row
+}
+
+fn label(commands: &mut Commands, text: impl Into<String>, color: Color) -> Entity {
+    commands
+        .spawn((
+            Text::new(text),
+            TextFont {
+                font_size: 15.0,
+                ..default()
+            },
+            TextColor(color),
+        ))
+        .id()
+}
+
+fn button(commands: &mut Commands, text: &str, action: FriendButton) -> Entity {
+    commands
+        .spawn((
+            Button,
+            Node {
+                height: Val::Px(30.0),
+                padding: UiRect::horizontal(Val::Px(12.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.3, 0.35, 0.5)),
+            action,
+        ))
+        .with_children(|btn| {
+            btn.spawn((
+                Text::new(text),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            ));
+        })
+        .id()
+}
@@ -0,0 +1,277 @@
+// 🏆 Leaderboard page: the top accounts by rating, wins or coins from the lobby service,
+// with the player's own row highlighted (and added below the table when they are
+// outside the top)
+use bevy::prelude::*;
+
+use shared::{Leaderboard, LeaderboardEntry, LeaderboardSort};
+
+use super::lobby::{spawn_back_button_simple, LobbyMode, LobbyUI, LobbyUIElements};
+
+#[cfg(target_arch = "wasm32")]
+use {
+    crate::http::{http_base, ApiRequest},
+    shared::LeaderboardQuery,
+    std::cell::RefCell,
+    wasm_bindgen_futures::spawn_local,
+};
+
+#[cfg(target_arch = "wasm32")]
+thread_local! {
+    static PENDING_LEADERBOARD: RefCell<Option<Result<Leaderboard, String>>> =
+        const { RefCell::new(None) };
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum LeaderboardLoad {
+    #[default]
+    Loading,
+    Loaded(Leaderboard),
+    Failed(String),
+}
+
+// The board the page shows and how it is sorted
+#[derive(Resource, Default)]
+pub struct LeaderboardPage {
+    pub sort: LeaderboardSort,
+    pub load: LeaderboardLoad,
+}
+
+#[derive(Component)]
+pub(super) struct LeaderboardSortButton(LeaderboardSort);
+
+// Fetch when the page opens and whenever the sort changes while it is open
+pub(super) fn refresh_leaderboard(
+    lobby_q: Query<&LobbyUI>,
+    mut page: ResMut<LeaderboardPage>,
+    mut fetched: Local<Option<LeaderboardSort>>,
+) {
+    let Ok(ui) = lobby_q.single() else {
+        return;
+    };
+    if ui.lobby_mode != LobbyMode::Leaderboard {
+        *fetched = None;
+        return;
+    }
+    if *fetched == Some(page.sort) {
+        return;
+    }
+    *fetched = Some(page.sort);
+    page.load = LeaderboardLoad::Loading;
+    start_leaderboard_fetch(&mut page, ui.player_id.clone());
+}
+
+#[cfg(target_arch = "wasm32")]
+fn start_leaderboard_fetch(page: &mut LeaderboardPage, player_id: Option<String>) {
+    let query = LeaderboardQuery {
+        sort: page.sort,
+        player_id,
+        ..default()
+    };
+    spawn_local(async move {
+        let url = format!(
+            "{}/lobby/api/leaderboard{}",
+            http_base(),
+            query.to_query_string()
+        );
+        let result = match ApiRequest::get(url).json::<Leaderboard>().await {
+            Ok(leaderboard) => Ok(leaderboard),
+            // Older lobby services have no leaderboard
+            Err(error) if error.status() == Some(404) => {
+                Err("not available on this server yet".to_string())
+            }
+            Err(error) => Err(error.notice()),
+        };
+        PENDING_LEADERBOARD.with(|cell| *cell.borrow_mut() = Some(result));
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn start_leaderboard_fetch(page: &mut LeaderboardPage, _player_id: Option<String>) {
+    page.load = LeaderboardLoad::Failed("needs the lobby service, play the web version".into());
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(super) fn pump_leaderboard(mut page: ResMut<LeaderboardPage>) {
+    let Some(result) = PENDING_LEADERBOARD.with(|cell| cell.borrow_mut().take()) else {
+        return;
+    };
+    page.load = match result {
+        // A board for a sort the player already switched away from
+        Ok(board) if board.sort != page.sort => return,
+        Ok(board) => LeaderboardLoad::Loaded(board),
+        Err(e) => LeaderboardLoad::Failed(e),
+    };
+}
+
+pub(super) fn handle_leaderboard_buttons(
+    buttons: Query<(&Interaction, &LeaderboardSortButton), Changed<Interaction>>,
+    mut page: ResMut<LeaderboardPage>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction == Interaction::Pressed && page.sort != button.0 {
+            page.sort = button.0;
+        }
+    }
+}
+
+pub(super) fn spawn_leaderboard_ui(
+    commands: &mut Commands,
+    container_entity: Entity,
+    page: &LeaderboardPage,
+    player_id: Option<&str>,
+) {
+    let title = commands
+        .spawn((
+            Text::new("🏆 Leaderboard"),
+            TextFont {
+                font_size: 28.0,
+                ..default()
+            },
+            TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            Node {
+                margin: UiRect::all(Val::Px(20.0)),
+                ..default()
+            },
+            LobbyUIElements,
+        ))
+        .id();
+    commands.entity(container_entity).add_child(title);
+
+    let sorts = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Row,
+                column_gap: Val::Px(8.0),
+                margin: UiRect::bottom(Val::Px(10.0)),
+                ..default()
+            },
+            LobbyUIElements,
+        ))
+        .with_children(|row| {
+            for sort in LeaderboardSort::ALL {
+                row.spawn((
+                    Button,
+                    Node {
+                        width: Val::Px(110.0),
+                        height: Val::Px(32.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    BackgroundColor(if *sort == page.sort {
+                        Color::srgb(0.6, 0.5, 0.1)
+                    } else {
+                        Color::srgb(0.3, 0.3, 0.4)
+                    }),
+                    LeaderboardSortButton(*sort),
+                ))
+                .with_children(|btn| {
+                    btn.spawn((
+                        Text::new(sort_label(*sort)),
+                        TextFont {
+                            font_size: 14.0,
+                            ..default()
+                        },
+                        TextColor(Color::srgb(1.0, 1.0, 1.0)),
+                    ));
+                });
+            }
+        })
+        .id();
+    commands.entity(container_entity).add_child(sorts);
+
+    let panel = commands
+        .spawn((
+            Node {
+                width: Val::Px(420.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(12.0)),
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.4)),
+            LobbyUIElements,
+        ))
+        .with_children(|panel| match &page.load {
+            LeaderboardLoad::Loading => {
+                line(panel, "⏳ loading...", Color::srgb(0.8, 0.8, 0.8));
+            }
+            LeaderboardLoad::Failed(reason) => {
+                line(
+                    panel,
+                    &format!("❌ Leaderboard: {}", reason),
+                    Color::srgb(1.0, 0.4, 0.4),
+                );
+            }
+            LeaderboardLoad::Loaded(board) => {
+                if board.entries.is_empty() {
+                    line(panel, "Nobody on the board yet", Color::srgb(0.6, 0.6, 0.6));
+                }
+                for entry in &board.entries {
+                    entry_row(panel, entry, board.sort, player_id);
+                }
+                if let Some(own) = &board.own {
+                    line(panel, "...", Color::srgb(0.6, 0.6, 0.6));
+                    entry_row(panel, own, board.sort, player_id);
+                }
+                if player_id.is_none() {
+                    line(
+                        panel,
+                        "Matches count once the lobby has given you an account.",
+                        Color::srgb(0.8, 0.8, 0.8),
+                    );
+                }
+            }
+        })
+        .id();
+    commands.entity(container_entity).add_child(panel);
+
+    let back = spawn_back_button_simple(commands);
+    commands.entity(container_entity).add_child(back);
+}
+
+fn sort_label(sort: LeaderboardSort) -> &'static str {
+    match sort {
+        LeaderboardSort::Rating => "RATING",
+        LeaderboardSort::Wins => "WINS",
+        LeaderboardSort::Coins => "COINS",
+    }
+}
+
+fn entry_row(
+    panel: &mut ChildSpawnerCommands,
+    entry: &LeaderboardEntry,
+    sort: LeaderboardSort,
+    player_id: Option<&str>,
+) {
+    let value = match sort {
+        LeaderboardSort::Rating => format!("{:.0}", entry.stats.rating),
+        LeaderboardSort::Wins => format!("{} wins", entry.stats.wins),
+        LeaderboardSort::Coins => format!("{} coins", entry.stats.coins),
+    };
+    let text = format!("#{}  {} - {}", entry.rank, entry.stats.display_name, value);
+    if player_id == Some(entry.stats.player_id.as_str()) {
+        panel.spawn((
+            Text::new(format!("{} (you)", text)),
+            TextFont {
+                font_size: 16.0,
+                ..default()
+            },
+            TextColor(Color::srgb(1.0, 0.85, 0.3)),
+            BackgroundColor(Color::srgba(1.0, 0.85, 0.3, 0.15)),
+        ));
+    } else {
+        line(panel, &text, Color::srgb(1.0, 1.0, 1.0));
+    }
+}
+
+fn line(panel: &mut ChildSpawnerCommands, text: &str, color: Color) {
+    panel.spawn((
+        Text::new(text),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(color),
+    ));
+}
@@ -1,19 +1,113 @@
 use bevy::prelude::*;
+use std::collections::HashMap;
+
+#[cfg(not(feature = "bevygap"))]
 use rand::Rng;
 
 #[cfg(feature = "bevygap")]
 use bevygap_client_plugin::prelude::BevygapConnectExt;
 
-use shared::RoomInfo;
+use shared::{
+    parse_room_code, BotStyle, Campaign, GameMode, LevelDefinition, Party, PartyInvite,
+    PlayerRating, RoomInfo, RoomMatchResult, RoomMember, RoomQuery, ServiceError, DEFAULT_LEVEL,
+    DEFAULT_RULE_SET,
+};
+
+use super::browser::{
+    handle_browser_buttons, local_rooms, scroll_room_list, show_rooms, spawn_join_room_ui,
+    BrowserButton,
+};
+use super::deep_link::{spawn_share_link, CopyLinkButton};
+use super::friends::{
+    handle_friend_buttons, spawn_friends_ui, spawn_room_friends, sync_friend_input, FriendButton,
+    Friends,
+};
+use super::leaderboard::{
+    handle_leaderboard_buttons, refresh_leaderboard, spawn_leaderboard_ui, LeaderboardPage,
+};
+use super::matchmaking::{
+    despawn_matchmaking_banner, handle_cancel_matchmaking, update_matchmaking_banner,
+    CancelMatchmakingButton,
+};
+use super::party::{
+    can_queue, handle_party_buttons, spawn_party_panel, sync_party_inputs, PartyButton,
+};
+use super::room_bots::{handle_bot_backfill, spawn_bot_backfill_button, BotBackfillButton};
+use super::room_chat::{spawn_room_chat, sync_chat_input, update_chat_log, ChatButton, RoomChat};
+use super::room_results::{
+    handle_publish_results, spawn_last_match, spawn_publish_results_button, PublishResultsButton,
+};
+use super::room_roster::{
+    handle_kick_buttons, handle_ready_button, spawn_kick_row, spawn_ready_button,
+    spawn_room_roster, start_button_color, start_button_label, start_secs_left,
+    update_start_button, KickButton, ReadyButton,
+};
+use super::room_rules::{
+    handle_rules_button, match_rules, spawn_room_rules, spawn_rules_button, RulesButton,
+};
+use super::room_teams::{
+    drag_members, handle_team_options, spawn_team_columns, spawn_team_option_button,
+    sync_room_team, TeamOptionButton,
+};
+use super::settings_page::{
+    capture_rebind_key, handle_settings_buttons, spawn_settings_ui, update_settings_page,
+    ColorSwatch, Rebinding,
+};
+use super::status::{pump_status_results, refresh_server_status, spawn_status_ui, ServerStatus};
+use crate::campaign::ActiveCampaign;
+use crate::connection::{ConnectionChanged, ConnectionEvent, ConnectionState};
+use crate::lobby_scene::lobby_background;
+use crate::local_play::{LocalGame, MAX_LOCAL_PLAYERS};
+use crate::settings::ClientSettings;
+use crate::text_input::{spawn_text_input, TextInput, TextSubmitted};
+
+#[cfg(feature = "bevygap")]
+use {
+    super::matchmaking::abort_matchmaking, super::party::queue_region,
+    super::room_bots::append_bot_params, crate::http::new_trace_id,
+    bevygap_client_plugin::prelude::BevygapClientConfig, shared::format_pings,
+    url::form_urlencoded,
+};
 
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+use super::browser::show_page;
+#[cfg(target_arch = "wasm32")]
+use super::deep_link::{
+    handle_copy_link, open_deep_link, pump_deep_link, read_deep_link, DeepLink,
+};
+#[cfg(target_arch = "wasm32")]
+use super::friends::{pump_friends, send_presence};
+#[cfg(target_arch = "wasm32")]
+use super::leaderboard::pump_leaderboard;
+#[cfg(all(target_arch = "wasm32", feature = "bevygap"))]
+use super::party::announce_party_queue;
+#[cfg(target_arch = "wasm32")]
+use super::party::{pump_party, refresh_party};
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+use super::room_bots::{pump_room_bots, queue_room_bots};
 #[cfg(target_arch = "wasm32")]
+use super::room_chat::{poll_room_chat, pump_room_chat};
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+use super::room_results::{pump_room_results, queue_room_results};
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+use super::room_rules::{pump_room_rules, queue_room_rules};
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+use super::room_teams::{pump_room_teams, queue_room_teams};
+#[cfg(feature = "bevygap")]
+use {
+    super::browser::{fetch_room_page, ping_regions, pump_refreshed_rooms, refresh_room_list},
+    super::room_roster::fetch_room_detail,
+    shared::CreateRoomRequest,
+};
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
 use {
-    serde::{Deserialize, Serialize},
-    std::cell::RefCell,
-    wasm_bindgen::JsCast,
-    wasm_bindgen_futures::spawn_local,
-    web_sys::{Request, RequestInit, RequestMode},
+    crate::http::{http_base, spawn, ApiRequest, Pending},
+    shared::{RoomPage, RoomPlayerRequest, ServerLobbyRoom},
 };
+
+// Longest room id or room password the text fields take
+pub(super) const MAX_INPUT_LEN: usize = 32;
+
 // Placeholder EdgegapLobbyState for compilation
 #[derive(Resource, Default)]
 pub struct EdgegapLobbyState {
@@ -27,19 +121,26 @@ pub struct ClientRoomRegistry {
     pub rooms: Vec<RoomInfo>,
 }
 
-#[derive(Resource, Default)]
-pub struct ConnectionState {
-    // Reserved for future connection state tracking
-}
-
-#[cfg(target_arch = "wasm32")]
-thread_local! {
-    static PENDING_ROOM_CREATED: RefCell<Option<RoomInfo>> = RefCell::new(None);
-    static PENDING_ROOM_LIST: RefCell<Option<Vec<RoomInfo>>> = RefCell::new(None);
-    static PENDING_NOTICE: RefCell<Option<String>> = RefCell::new(None);
-    static PENDING_PLAYER_COUNT: RefCell<Option<u32>> = RefCell::new(None);
-    static PENDING_ROOM_STARTED: RefCell<Option<bool>> = RefCell::new(None);
-}
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+static PENDING_ROOM_CREATED: Pending<RoomInfo> = Pending::new();
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+static PENDING_ROOM_JOINED: Pending<RoomInfo> = Pending::new();
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+static PENDING_ROOM_LIST: Pending<RoomPage> = Pending::new();
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+static PENDING_REGION_PINGS: Pending<HashMap<String, u32>> = Pending::new();
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+static PENDING_NOTICE: Pending<String> = Pending::new();
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+static PENDING_PLAYER_COUNT: Pending<u32> = Pending::new();
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+static PENDING_ROOM_STARTED: Pending<bool> = Pending::new();
+// Host name and account of the room we are in, as the lobby service last said
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+static PENDING_ROOM_HOST: Pending<(String, Option<String>)> = Pending::new();
+// The service no longer has us in the room, we were kicked or timed out
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+static PENDING_ROOM_REMOVED: Pending<()> = Pending::new();
 
 #[derive(Resource, Default)]
 pub struct UiNotice {
@@ -50,41 +151,85 @@ pub struct UiNotice {
 #[derive(Component)]
 struct NoticeText;
 
-#[derive(Resource, Clone, Debug)]
+#[derive(Resource, Clone, Debug, Reflect)]
+#[reflect(Resource)]
 pub struct LobbyConfig {
     pub domain: String,           // "voidloop.quest"
     pub matchmaker_url: String,   // "wss://voidloop.quest/matchmaker/ws"
     pub max_players: u32,         // 4
-    pub lobby_modes: Vec<String>, // ["casual", "ranked", "custom"]
+    pub lobby_modes: Vec<String>, // GameMode ids: ["casual", "ranked", ...]
 }
 
 impl Default for LobbyConfig {
     fn default() -> Self {
         Self {
             domain: "voidloop.quest".to_string(),
-            matchmaker_url: get_matchmaker_url(),
+            matchmaker_url: crate::http::matchmaker_ws_url(),
             max_players: 4,
-            lobby_modes: vec![
-                "casual".to_string(),
-                "ranked".to_string(),
-                "custom".to_string(),
-            ],
+            lobby_modes: GameMode::ALL.iter().map(|m| m.id().to_string()).collect(),
         }
     }
 }
 
 // 🏠 Lobby UI component
-#[derive(Component, Default)]
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
 pub struct LobbyUI {
     pub current_players: u32,
     pub selected_mode: String,
     pub is_host: bool,
-    pub is_searching: bool,
+    /// The lobby service's id of the room, for its API and never shown
     pub room_id: String,
+    /// Short code of the room, what players see and type
+    pub room_code: String,
     pub room_started: bool,
     pub lobby_mode: LobbyMode,
     pub available_rooms: Vec<RoomInfo>,
     pub player_name: String,
+    /// Persistent account id from the lobby service, sent along with the name
+    pub player_id: Option<String>,
+    /// Ranked rating of the account, once the lobby service answered
+    pub rating: Option<PlayerRating>,
+    /// Password for the room being created or joined, empty for open rooms
+    pub room_password: String,
+    /// Filters, sorting and page of the Join Room browser
+    pub room_query: RoomQuery,
+    /// Rooms matching `room_query` over all pages
+    pub room_total: u32,
+    /// Round trip to each region in milliseconds, measured before the first room list
+    pub region_pings: HashMap<String, u32>,
+    pub selected_level: String,
+    /// Teams of the room being created or the one we are in, 0 for free-for-all
+    pub room_teams: usize,
+    pub friendly_fire: bool,
+    /// Players in the room and their teams, as the lobby service last said
+    pub room_members: Vec<RoomMember>,
+    /// The room stays open after its match and keeps the results
+    pub publish_results: bool,
+    /// Bots fill the empty slots of the room being created or the one we are in
+    pub bot_backfill: bool,
+    /// How those bots play
+    pub bot_style: BotStyle,
+    /// Results of the room's last match, when its host published them
+    pub last_match: Option<RoomMatchResult>,
+    /// Built-in rule set picked for a custom room or match
+    pub selected_rules: String,
+    /// Rule set of the custom room we are in, as RON
+    pub room_rules: Option<String>,
+    /// Trace id of the last matchmaker connection, shown if it fails
+    pub matchmaker_trace: Option<String>,
+    /// Players sharing this machine in LOCAL PLAY
+    pub local_players: usize,
+    /// Party we queue with, as the lobby service last said
+    pub party: Option<Party>,
+    /// Parties that invited us, while we are in none
+    pub party_invites: Vec<PartyInvite>,
+    /// Party code typed to join one
+    pub party_code: String,
+    /// Name typed to invite to our party
+    pub party_invitee: String,
+    /// `queued_at` of the party queue we last followed
+    pub party_queued_at: u64,
 }
 
 impl LobbyUI {
@@ -93,24 +238,57 @@ impl LobbyUI {
             current_players: 1, // Start with 1 (local player)
             selected_mode: "casual".to_string(),
             is_host: false,
-            is_searching: false,
             room_id: String::new(),
+            room_code: String::new(),
             room_started: false,
             lobby_mode: LobbyMode::Main,
             available_rooms: Vec::new(),
             player_name: format!("Player{}", rand::random::<u32>() % 1000),
+            player_id: None,
+            rating: None,
+            room_password: String::new(),
+            room_query: RoomQuery::default(),
+            room_total: 0,
+            region_pings: HashMap::new(),
+            selected_level: DEFAULT_LEVEL.to_string(),
+            room_teams: 0,
+            friendly_fire: false,
+            room_members: Vec::new(),
+            publish_results: false,
+            bot_backfill: false,
+            bot_style: BotStyle::default(),
+            last_match: None,
+            selected_rules: DEFAULT_RULE_SET.to_string(),
+            room_rules: None,
+            matchmaker_trace: None,
+            local_players: 1,
+            party: None,
+            party_invites: Vec::new(),
+            party_code: String::new(),
+            party_invitee: String::new(),
+            party_queued_at: 0,
         }
     }
+
+    /// The typed room password, None if it was left empty
+    pub fn password(&self) -> Option<String> {
+        Some(self.room_password.clone()).filter(|password| !password.is_empty())
+    }
 }
 
 // Different lobby screens/modes
-#[derive(Default, Clone, PartialEq)]
+#[derive(Default, Clone, Debug, PartialEq, Reflect)]
 pub enum LobbyMode {
     #[default]
     Main,
     CreateRoom,
     JoinRoom,
     InRoom,
+    Campaign,
+    Status,
+    Settings,
+    Leaderboard,
+    Friends,
 }
 
 // 🎮 Game states
@@ -121,19 +299,6 @@ pub enum AppState {
     InGame,
 }
 
-// Helper: server-side lobby room representation from bevygap httpd
-#[derive(Deserialize, Debug, Clone)]
-#[cfg(target_arch = "wasm32")]
-struct ServerLobbyRoom {
-    id: String,
-    host_name: String,
-    game_mode: String,
-    created_at: u64,
-    started: bool,
-    current_players: u32,
-    max_players: u32,
-}
-
 // 🌟 Lobby events
 #[derive(Event)]
 pub enum LobbyEvent {
@@ -141,19 +306,21 @@ pub enum LobbyEvent {
     PlayerLeft(u32),
     StartGame,
     StartLocalGame,
+    OpenCampaign,
+    OpenPage(LobbyMode),
+    StartCampaignLevel(usize),
     SelectMode(String),
+    SelectLevel(String),
     CreateRoom,
     ConfirmCreateRoom,
     JoinRoom,
-    EnterRoomId(String),
+    EnterRoomCode(String),
     LeaveRoom,
     // New events for real matchmaking
     StartMatchmaking,
     RequestRoomList,
     RoomListReceived(Vec<RoomInfo>),
     LobbyCreated(String), // lobby name
-    LobbyDeploymentFailed(String),
-    ConnectedToServer,
 }
 
 // 🎯 Lobby plugin
@@ -164,26 +331,101 @@ impl Plugin for LobbyPlugin {
         app.init_state::<AppState>()
             .add_event::<LobbyEvent>()
             .insert_resource(LobbyConfig::default())
-            .insert_resource(ConnectionState::default())
             .insert_resource(EdgegapLobbyState::default())
             .insert_resource(ClientRoomRegistry::default())
             .insert_resource(UiNotice::default())
+            .init_resource::<ServerStatus>()
+            .init_resource::<LeaderboardPage>()
+            .init_resource::<Friends>()
+            .init_resource::<Rebinding>()
+            .init_resource::<RoomChat>()
             .add_systems(OnEnter(AppState::Lobby), setup_lobby_ui)
-            .add_systems(OnExit(AppState::Lobby), cleanup_lobby_ui)
+            .add_systems(
+                OnExit(AppState::Lobby),
+                (cleanup_lobby_ui, despawn_matchmaking_banner),
+            )
             .add_systems(
                 Update,
                 (
                     handle_lobby_input,
+                    sync_lobby_inputs,
+                    (handle_browser_buttons, scroll_room_list),
                     update_lobby_display,
                     update_simple_ui,
                     handle_lobby_events,
-                    handle_connection_events,
+                    follow_connection,
                     show_notice,
+                    (refresh_server_status, pump_status_results),
+                    (handle_leaderboard_buttons, refresh_leaderboard).chain(),
+                    (handle_team_options, drag_members, sync_room_team),
+                    (
+                        handle_publish_results,
+                        handle_bot_backfill,
+                        handle_rules_button,
+                        handle_local_players,
+                        handle_ready_button,
+                        handle_kick_buttons,
+                        update_start_button,
+                    ),
+                    (
+                        handle_party_buttons,
+                        sync_party_inputs,
+                        handle_friend_buttons,
+                        sync_friend_input,
+                        sync_chat_input,
+                        update_chat_log,
+                    ),
+                    (
+                        handle_settings_buttons,
+                        capture_rebind_key,
+                        update_settings_page,
+                    )
+                        .chain(),
+                    // Teams, results, rules and chat are only taken for the room we are in, once
+                    // we are in it
+                    #[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+                    (
+                        pump_async_results,
+                        (
+                            pump_room_teams,
+                            pump_room_results,
+                            pump_room_bots,
+                            pump_room_rules,
+                            #[cfg(target_arch = "wasm32")]
+                            pump_room_chat,
+                        ),
+                    )
+                        .chain(),
                     #[cfg(target_arch = "wasm32")]
-                    pump_async_results,
+                    (refresh_party, pump_party, send_presence, pump_friends),
+                    #[cfg(target_arch = "wasm32")]
+                    (pump_leaderboard, poll_room_chat),
+                    #[cfg(feature = "bevygap")]
+                    (
+                        send_room_heartbeat,
+                        fetch_room_detail,
+                        refresh_room_list,
+                        pump_refreshed_rooms,
+                    ),
                 )
                     .run_if(in_state(AppState::Lobby)),
             );
+        app.add_systems(
+            Update,
+            (update_matchmaking_banner, handle_cancel_matchmaking)
+                .run_if(in_state(AppState::Lobby)),
+        );
+        #[cfg(feature = "bevygap")]
+        app.add_systems(Update, abort_matchmaking.run_if(in_state(AppState::Lobby)));
+        // Room links are only for the web client
+        #[cfg(target_arch = "wasm32")]
+        app.init_resource::<DeepLink>()
+            .add_systems(OnEnter(AppState::Lobby), read_deep_link)
+            .add_systems(
+                Update,
+                ((open_deep_link, pump_deep_link).chain(), handle_copy_link)
+                    .run_if(in_state(AppState::Lobby)),
+            );
     }
 }
 
@@ -239,106 +481,179 @@ fn show_notice(
     }
 }
 
-#[cfg(target_arch = "wasm32")]
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
 fn pump_async_results(
     mut notice: ResMut<UiNotice>,
     mut lobby_q: Query<&mut LobbyUI>,
     mut lobby_events: EventWriter<LobbyEvent>,
 ) {
     // room created
-    PENDING_ROOM_CREATED.with(|cell| {
-        if let Some(room) = cell.borrow_mut().take() {
-            if let Ok(mut ui) = lobby_q.single_mut() {
-                ui.room_id = room.room_id.clone();
-                ui.is_host = true;
-                ui.lobby_mode = LobbyMode::InRoom;
-                ui.is_searching = true; // Keep searching while deploying server
-
-                // Automatically trigger matchmaking to deploy the server
-                info!(
-                    "🚀 Auto-starting server deployment for room: {}",
-                    room.room_id
-                );
-                lobby_events.send(LobbyEvent::StartMatchmaking);
-            }
+    if let Some(room) = PENDING_ROOM_CREATED.take() {
+        if let Ok(mut ui) = lobby_q.single_mut() {
+            ui.room_id = room.room_id.clone();
+            ui.room_code = room.code.clone();
+            ui.is_host = true;
+            ui.lobby_mode = LobbyMode::InRoom;
+
+            // Automatically trigger matchmaking to deploy the server
+            info!("🚀 Auto-starting server deployment for room: {}", room.code);
+            lobby_events.write(LobbyEvent::StartMatchmaking);
         }
-    });
+    }
+    // joined a room, maybe by its code only
+    if let Some(room) = PENDING_ROOM_JOINED.take() {
+        if let Ok(mut ui) = lobby_q.single_mut() {
+            ui.room_id = room.room_id;
+            ui.room_code = room.code;
+        }
+    }
+    // region pings, ahead of the room list they were measured for
+    if let Some(pings) = PENDING_REGION_PINGS.take() {
+        if let Ok(mut ui) = lobby_q.single_mut() {
+            ui.region_pings = pings;
+        }
+    }
     // room list
-    PENDING_ROOM_LIST.with(|cell| {
-        if let Some(list) = cell.borrow_mut().take() {
-            if let Ok(mut ui) = lobby_q.single_mut() {
-                ui.available_rooms = list;
-                ui.lobby_mode = LobbyMode::JoinRoom;
-            }
+    if let Some(page) = PENDING_ROOM_LIST.take() {
+        if let Ok(mut ui) = lobby_q.single_mut() {
+            show_page(&mut ui, page);
+            ui.lobby_mode = LobbyMode::JoinRoom;
         }
-    });
+    }
     // notices
-    PENDING_NOTICE.with(|cell| {
-        if let Some(msg) = cell.borrow_mut().take() {
-            notice.msg = Some(msg);
-            notice.timer = 0.0; // cause spawn next frame
-        }
-    });
+    if let Some(msg) = PENDING_NOTICE.take() {
+        notice.msg = Some(msg);
+        notice.timer = 0.0; // cause spawn next frame
+    }
     // player count updates
-    PENDING_PLAYER_COUNT.with(|cell| {
-        if let Some(count) = cell.borrow_mut().take() {
-            if let Ok(mut ui) = lobby_q.single_mut() {
-                ui.current_players = count;
-            }
+    if let Some(count) = PENDING_PLAYER_COUNT.take() {
+        if let Ok(mut ui) = lobby_q.single_mut() {
+            ui.current_players = count;
         }
-    });
+    }
     // room started updates
-    PENDING_ROOM_STARTED.with(|cell| {
-        if let Some(started) = cell.borrow_mut().take() {
-            if let Ok(mut ui) = lobby_q.single_mut() {
-                ui.room_started = started;
+    if let Some(started) = PENDING_ROOM_STARTED.take() {
+        if let Ok(mut ui) = lobby_q.single_mut() {
+            ui.room_started = started;
+        }
+    }
+    // the host left and the service picked us, or someone else
+    if let Some((host_name, host_id)) = PENDING_ROOM_HOST.take() {
+        if let Ok(mut ui) = lobby_q.single_mut() {
+            let is_host = match (&host_id, &ui.player_id) {
+                (Some(host_id), Some(player_id)) => host_id == player_id,
+                _ => host_name == ui.player_name,
+            };
+            // An answer for a room we already left changes nothing
+            if !ui.room_id.is_empty() && is_host != ui.is_host {
+                if is_host {
+                    notice.msg = Some("👑 The host left, you are now the host".to_string());
+                    notice.timer = 0.0;
+                }
+                ui.is_host = is_host;
             }
         }
-    });
-}
-#[cfg(target_arch = "wasm32")]
-fn http_base() -> String {
-    // Build http(s) base from current location
-    let window = web_sys::window().expect("no window");
-    let loc = window.location();
-    let protocol = loc.protocol().unwrap_or_else(|_| "http:".into());
-    let scheme = if protocol == "https:" {
-        "https"
-    } else {
-        "http"
-    };
-    let host = loc.host().unwrap();
-    format!("{}://{}", scheme, host)
+    }
+    // kicked out of the room
+    if PENDING_ROOM_REMOVED.take().is_some() {
+        if let Ok(mut ui) = lobby_q.single_mut() {
+            leave_room_locally(&mut ui);
+        }
+    }
 }
+// Seconds between presence heartbeats while in a room. The lobby service drops
+// players it hasn't heard from in a while, e.g. because they closed the tab.
+#[cfg(feature = "bevygap")]
+const HEARTBEAT_INTERVAL_SECS: f32 = 10.0;
 
-#[cfg(target_arch = "wasm32")]
-fn fetch_json(url: &str, method: &str, body: Option<String>) -> wasm_bindgen_futures::JsFuture {
-    use wasm_bindgen::JsValue;
-
-    let mut opts = RequestInit::new();
-    opts.set_method(method);
-    opts.set_mode(RequestMode::Cors);
-    if let Some(b) = body {
-        opts.set_body(&JsValue::from_str(&b));
+#[cfg(feature = "bevygap")]
+fn send_room_heartbeat(time: Res<Time>, lobby_q: Query<&LobbyUI>, mut timer: Local<Option<Timer>>) {
+    let Ok(ui) = lobby_q.single() else {
+        return;
+    };
+    if ui.room_id.is_empty() {
+        *timer = None;
+        return;
+    }
+    let timer = timer
+        .get_or_insert_with(|| Timer::from_seconds(HEARTBEAT_INTERVAL_SECS, TimerMode::Repeating));
+    if !timer.tick(time.delta()).just_finished() {
+        return;
     }
 
-    let request = Request::new_with_str_and_init(url, &opts).unwrap();
-    request
-        .headers()
-        .set("Content-Type", "application/json")
-        .unwrap();
+    let room_id = ui.room_id.clone();
+    let player_name = ui.player_name.clone();
+    let player_id = ui.player_id.clone();
+    spawn(async move {
+        let url = format!("{}/lobby/api/rooms/{}/heartbeat", http_base(), room_id);
+        let request = ApiRequest::post(
+            url,
+            &RoomPlayerRequest {
+                player_name,
+                player_id,
+                password: None,
+            },
+        );
+        match request.json::<ServerLobbyRoom>().await {
+            // The service answers with the room, pick up the corrected player count, teams
+            // and the last match
+            Ok(room) => queue_room(&room),
+            Err(error) if error.status() == Some(404) => {
+                PENDING_NOTICE.set(format!("Room no longer exists ({})", error));
+            }
+            Err(error) if error.status() == Some(403) => {
+                // NOT_IN_ROOM: the host kicked us, or the service no longer counts us as a
+                // member
+                PENDING_NOTICE.set(format!("Removed from the room ({})", error));
+                PENDING_ROOM_REMOVED.set(());
+            }
+            Err(_) => {}
+        }
+    });
+}
 
-    let window = web_sys::window().unwrap();
-    wasm_bindgen_futures::JsFuture::from(window.fetch_with_request(&request))
+/// Pick up the player count, members, results and rules from an answer that carries the
+/// room we are in
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+pub(super) fn queue_room(room: &ServerLobbyRoom) {
+    PENDING_PLAYER_COUNT.set(room.current_players);
+    PENDING_ROOM_HOST.set((room.host_name.clone(), room.host_id.clone()));
+    queue_room_teams(room);
+    queue_room_results(room);
+    queue_room_bots(room);
+    queue_room_rules(room);
 }
 
 // 🏠 Initialize lobby system
-fn setup_lobby_ui(mut commands: Commands, _asset_server: Res<AssetServer>) {
+fn setup_lobby_ui(
+    mut commands: Commands,
+    _asset_server: Res<AssetServer>,
+    settings: Res<ClientSettings>,
+    #[cfg(target_arch = "wasm32")] mut notice: ResMut<UiNotice>,
+) {
     info!("🏠 Setting up lobby UI - DEBUG");
 
+    // The service worker can start us without a network; point players at practice mode
+    #[cfg(target_arch = "wasm32")]
+    if let Some(window) = web_sys::window() {
+        if !window.navigator().on_line() {
+            notice.msg = Some("📴 Offline - LOCAL PLAY is still available".to_string());
+        }
+    }
+
+    // Ping the regions as the lobby opens, quick match queues in the closest one
+    #[cfg(feature = "bevygap")]
+    spawn(async move {
+        PENDING_REGION_PINGS.set(ping_regions().await);
+    });
+
     // Spawn main lobby UI container
     commands.spawn((
-        LobbyUI::new(),
+        LobbyUI {
+            player_name: settings.account.display_name.clone(),
+            player_id: settings.account.player_id.clone(),
+            ..LobbyUI::new()
+        },
         Node {
             width: Val::Percent(100.0),
             height: Val::Percent(100.0),
@@ -348,7 +663,8 @@ fn setup_lobby_ui(mut commands: Commands, _asset_server: Res<AssetServer>) {
             padding: UiRect::all(Val::Percent(2.0)),
             ..default()
         },
-        BackgroundColor(Color::srgb(0.1, 0.1, 0.2)), // Dark blue background
+        // Dark blue, see-through while the ambient scene runs behind it
+        BackgroundColor(lobby_background(&settings)),
         LobbyContainer,
     ));
 }
@@ -356,10 +672,34 @@ fn setup_lobby_ui(mut commands: Commands, _asset_server: Res<AssetServer>) {
 // Update lobby UI based on current mode
 fn update_lobby_display(
     mut commands: Commands,
-    lobby_ui_query: Query<(&LobbyUI, Entity), (With<LobbyContainer>, Changed<LobbyUI>)>,
+    lobby_ui_query: Query<(Ref<LobbyUI>, Entity), With<LobbyContainer>>,
     existing_ui: Query<Entity, (With<LobbyUIElements>, Without<LobbyContainer>)>,
+    settings: Res<ClientSettings>,
+    status: Res<ServerStatus>,
+    leaderboard: Res<LeaderboardPage>,
+    friends: Res<Friends>,
+    chat: Res<RoomChat>,
+    connection: Res<ConnectionState>,
 ) {
     if let Ok((lobby_ui, container_entity)) = lobby_ui_query.single() {
+        // The status, leaderboard and friends pages also redraw as results come in, and
+        // the room for its list of friends to invite and its server status
+        let status_changed = status.is_changed() && lobby_ui.lobby_mode == LobbyMode::Status;
+        let leaderboard_changed =
+            leaderboard.is_changed() && lobby_ui.lobby_mode == LobbyMode::Leaderboard;
+        let friends_changed = friends.is_changed()
+            && matches!(lobby_ui.lobby_mode, LobbyMode::Friends | LobbyMode::InRoom);
+        let connection_changed =
+            connection.is_changed() && lobby_ui.lobby_mode == LobbyMode::InRoom;
+        if !lobby_ui.is_changed()
+            && !status_changed
+            && !leaderboard_changed
+            && !friends_changed
+            && !connection_changed
+        {
+            return;
+        }
+
         // Clear existing UI elements safely
         for entity in existing_ui.iter() {
             if let Ok(mut entity_commands) = commands.get_entity(entity) {
@@ -370,22 +710,49 @@ fn update_lobby_display(
         // Rebuild UI based on current mode
         match lobby_ui.lobby_mode {
             LobbyMode::Main => {
-                spawn_main_lobby_ui(&mut commands, container_entity, lobby_ui);
+                spawn_main_lobby_ui(&mut commands, container_entity, &lobby_ui);
             }
             LobbyMode::CreateRoom => {
-                spawn_create_room_ui(&mut commands, container_entity, lobby_ui);
+                spawn_create_room_ui(&mut commands, container_entity, &lobby_ui);
             }
             LobbyMode::JoinRoom => {
-                spawn_join_room_ui(&mut commands, container_entity, lobby_ui);
+                spawn_join_room_ui(&mut commands, container_entity, &lobby_ui);
             }
             LobbyMode::InRoom => {
-                spawn_in_room_ui(&mut commands, container_entity, lobby_ui);
+                spawn_in_room_ui(
+                    &mut commands,
+                    container_entity,
+                    &lobby_ui,
+                    &friends,
+                    &chat,
+                    &connection,
+                );
+            }
+            LobbyMode::Campaign => {
+                spawn_campaign_ui(&mut commands, container_entity, &settings);
+            }
+            LobbyMode::Status => {
+                spawn_status_ui(&mut commands, container_entity, &status);
+            }
+            LobbyMode::Settings => {
+                spawn_settings_ui(&mut commands, container_entity, &settings);
+            }
+            LobbyMode::Leaderboard => {
+                spawn_leaderboard_ui(
+                    &mut commands,
+                    container_entity,
+                    &leaderboard,
+                    lobby_ui.player_id.as_deref(),
+                );
+            }
+            LobbyMode::Friends => {
+                spawn_friends_ui(&mut commands, container_entity, &friends, &lobby_ui);
             }
         }
     }
 }
 
-fn spawn_main_lobby_ui(commands: &mut Commands, container_entity: Entity, _lobby_ui: &LobbyUI) {
+fn spawn_main_lobby_ui(commands: &mut Commands, container_entity: Entity, lobby_ui: &LobbyUI) {
     let title_entity = commands
         .spawn((
             Text::new("🎮 Voidloop Quest"),
@@ -402,6 +769,25 @@ fn spawn_main_lobby_ui(commands: &mut Commands, container_entity: Entity, _lobby
         ))
         .id();
 
+    // Who others will see, changed on the settings page
+    let name_entity = commands
+        .spawn((
+            Text::new(match &lobby_ui.rating {
+                Some(rating) => format!(
+                    "Playing as {} · rating {:.0}",
+                    lobby_ui.player_name, rating.rating
+                ),
+                None => format!("Playing as {}", lobby_ui.player_name),
+            }),
+            TextFont {
+                font_size: 18.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.8, 0.8, 0.8)),
+            LobbyUIElements,
+        ))
+        .id();
+
     // Mode buttons container
     let mode_container = commands
         .spawn((
@@ -416,7 +802,7 @@ fn spawn_main_lobby_ui(commands: &mut Commands, container_entity: Entity, _lobby
         .id();
 
     // Add mode buttons as children
-    let modes = ["casual", "ranked", "custom"];
+    let modes: Vec<&str> = GameMode::ALL.iter().map(|m| m.id()).collect();
     for (i, mode) in modes.iter().enumerate() {
         let button_entity = commands
             .spawn((
@@ -463,7 +849,8 @@ fn spawn_main_lobby_ui(commands: &mut Commands, container_entity: Entity, _lobby
         ))
         .id();
 
-    // Quick match button (NEW)
+    // Quick match button (NEW). In a party only the leader queues, the others follow.
+    let queues = can_queue(lobby_ui);
     let quick_match_btn = commands
         .spawn((
             Button,
@@ -475,13 +862,21 @@ fn spawn_main_lobby_ui(commands: &mut Commands, container_entity: Entity, _lobby
                 align_items: AlignItems::Center,
                 ..default()
             },
-            BackgroundColor(Color::srgb(0.6, 0.2, 0.6)),
+            BackgroundColor(if queues {
+                Color::srgb(0.6, 0.2, 0.6)
+            } else {
+                Color::srgb(0.3, 0.3, 0.3)
+            }),
             QuickMatchButton,
             LobbyUIElements,
         ))
         .with_children(|btn| {
             btn.spawn((
-                Text::new("🎯 QUICK MATCH"),
+                Text::new(if queues {
+                    "🎯 QUICK MATCH"
+                } else {
+                    "⏳ LEADER QUEUES"
+                }),
                 TextFont {
                     font_size: 16.0,
                     ..default()
@@ -573,78 +968,159 @@ fn spawn_main_lobby_ui(commands: &mut Commands, container_entity: Entity, _lobby
         })
         .id();
 
-    // Add all buttons to container
-    commands.entity(button_container).add_child(quick_match_btn);
-    commands.entity(button_container).add_child(create_btn);
-    commands.entity(button_container).add_child(join_btn);
-    commands.entity(button_container).add_child(local_btn);
-
-    // Add all elements to main container
-    commands.entity(container_entity).add_child(title_entity);
-    commands.entity(container_entity).add_child(mode_container);
-    commands
-        .entity(container_entity)
-        .add_child(button_container);
-}
+    // Players for local play, pressed to cycle 1-4
+    let local_players_btn = commands
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(180.0),
+                height: Val::Px(36.0),
+                margin: UiRect::all(Val::Px(4.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.45, 0.3, 0.15)),
+            LocalPlayersButton,
+        ))
+        .with_children(|btn| {
+            btn.spawn((
+                Text::new(format!("👥 LOCAL PLAYERS: {}", lobby_ui.local_players)),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            ));
+        })
+        .id();
 
-fn spawn_create_room_ui(commands: &mut Commands, container_entity: Entity, lobby_ui: &LobbyUI) {
-    let title = commands
+    // Campaign button
+    let campaign_btn = commands
         .spawn((
-            Text::new("Create Room"),
-            TextFont {
-                font_size: 28.0,
+            Button,
+            Node {
+                width: Val::Px(180.0),
+                height: Val::Px(50.0),
+                margin: UiRect::all(Val::Px(10.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
                 ..default()
             },
-            TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            BackgroundColor(Color::srgb(0.6, 0.5, 0.1)),
+            CampaignButton,
+        ))
+        .with_children(|btn| {
+            btn.spawn((
+                Text::new("🏁 CAMPAIGN"),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            ));
+        })
+        .id();
+
+    // Server status button
+    let status_btn = commands
+        .spawn((
+            Button,
             Node {
-                margin: UiRect::all(Val::Px(20.0)),
+                width: Val::Px(180.0),
+                height: Val::Px(50.0),
+                margin: UiRect::all(Val::Px(10.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
                 ..default()
             },
-            LobbyUIElements,
+            BackgroundColor(Color::srgb(0.3, 0.3, 0.4)),
+            PageButton(LobbyMode::Status),
         ))
+        .with_children(|btn| {
+            btn.spawn((
+                Text::new("📡 SERVER STATUS"),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            ));
+        })
         .id();
 
-    let room_info = commands
+    // Friends button
+    let friends_btn = commands
         .spawn((
-            Text::new(format!(
-                "Room ID: {}",
-                if lobby_ui.room_id.is_empty() {
-                    "Auto-generated"
-                } else {
-                    &lobby_ui.room_id
-                }
-            )),
-            TextFont {
-                font_size: 16.0,
+            Button,
+            Node {
+                width: Val::Px(180.0),
+                height: Val::Px(50.0),
+                margin: UiRect::all(Val::Px(10.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
                 ..default()
             },
-            TextColor(Color::srgb(0.8, 0.8, 0.8)),
+            BackgroundColor(Color::srgb(0.3, 0.3, 0.4)),
+            PageButton(LobbyMode::Friends),
+        ))
+        .with_children(|btn| {
+            btn.spawn((
+                Text::new("👥 FRIENDS"),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            ));
+        })
+        .id();
+
+    // Leaderboard button
+    let leaderboard_btn = commands
+        .spawn((
+            Button,
             Node {
+                width: Val::Px(180.0),
+                height: Val::Px(50.0),
                 margin: UiRect::all(Val::Px(10.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
                 ..default()
             },
-            LobbyUIElements,
+            BackgroundColor(Color::srgb(0.3, 0.3, 0.4)),
+            PageButton(LobbyMode::Leaderboard),
         ))
+        .with_children(|btn| {
+            btn.spawn((
+                Text::new("🏆 LEADERBOARD"),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            ));
+        })
         .id();
 
-    let create_btn = commands
+    // Settings button
+    let settings_btn = commands
         .spawn((
             Button,
             Node {
-                width: Val::Px(150.0),
+                width: Val::Px(180.0),
                 height: Val::Px(50.0),
-                margin: UiRect::all(Val::Px(15.0)),
+                margin: UiRect::all(Val::Px(10.0)),
                 justify_content: JustifyContent::Center,
                 align_items: AlignItems::Center,
                 ..default()
             },
-            BackgroundColor(Color::srgb(0.2, 0.6, 0.2)),
-            ConfirmCreateButton,
-            LobbyUIElements,
+            BackgroundColor(Color::srgb(0.3, 0.3, 0.4)),
+            PageButton(LobbyMode::Settings),
         ))
         .with_children(|btn| {
             btn.spawn((
-                Text::new("CREATE"),
+                Text::new("⚙️ SETTINGS"),
                 TextFont {
                     font_size: 16.0,
                     ..default()
@@ -654,18 +1130,35 @@ fn spawn_create_room_ui(commands: &mut Commands, container_entity: Entity, lobby
         })
         .id();
 
-    let back_btn = spawn_back_button_simple(commands);
+    // Add all buttons to container
+    commands.entity(button_container).add_child(quick_match_btn);
+    commands.entity(button_container).add_child(create_btn);
+    commands.entity(button_container).add_child(join_btn);
+    commands.entity(button_container).add_child(local_btn);
+    commands
+        .entity(button_container)
+        .add_child(local_players_btn);
+    commands.entity(button_container).add_child(campaign_btn);
+    commands.entity(button_container).add_child(status_btn);
+    commands.entity(button_container).add_child(friends_btn);
+    commands.entity(button_container).add_child(leaderboard_btn);
+    commands.entity(button_container).add_child(settings_btn);
 
-    commands.entity(container_entity).add_child(title);
-    commands.entity(container_entity).add_child(room_info);
-    commands.entity(container_entity).add_child(create_btn);
-    commands.entity(container_entity).add_child(back_btn);
+    // Add all elements to main container
+    commands.entity(container_entity).add_child(title_entity);
+    commands.entity(container_entity).add_child(name_entity);
+    let party_panel = spawn_party_panel(commands, lobby_ui);
+    commands.entity(container_entity).add_child(party_panel);
+    commands.entity(container_entity).add_child(mode_container);
+    commands
+        .entity(container_entity)
+        .add_child(button_container);
 }
 
-fn spawn_join_room_ui(commands: &mut Commands, container_entity: Entity, lobby_ui: &LobbyUI) {
+fn spawn_create_room_ui(commands: &mut Commands, container_entity: Entity, lobby_ui: &LobbyUI) {
     let title = commands
         .spawn((
-            Text::new("Join Room"),
+            Text::new("Create Room"),
             TextFont {
                 font_size: 28.0,
                 ..default()
@@ -679,9 +1172,16 @@ fn spawn_join_room_ui(commands: &mut Commands, container_entity: Entity, lobby_u
         ))
         .id();
 
-    let room_input = commands
+    let room_info = commands
         .spawn((
-            Text::new(format!("Enter Room ID: {}", lobby_ui.room_id)),
+            Text::new(format!(
+                "Room code: {}",
+                if lobby_ui.room_code.is_empty() {
+                    "Auto-generated"
+                } else {
+                    &lobby_ui.room_code
+                }
+            )),
             TextFont {
                 font_size: 16.0,
                 ..default()
@@ -695,12 +1195,12 @@ fn spawn_join_room_ui(commands: &mut Commands, container_entity: Entity, lobby_u
         ))
         .id();
 
-    // Available rooms display
-    let rooms_container = commands
+    // Level picker
+    let level_container = commands
         .spawn((
             Node {
-                flex_direction: FlexDirection::Column,
-                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Row,
+                justify_content: JustifyContent::Center,
                 margin: UiRect::all(Val::Px(10.0)),
                 ..default()
             },
@@ -708,61 +1208,40 @@ fn spawn_join_room_ui(commands: &mut Commands, container_entity: Entity, lobby_u
         ))
         .id();
 
-    // Show available rooms or loading message
-    if lobby_ui.available_rooms.is_empty() {
-        let loading_text = commands
+    for level in LevelDefinition::builtin_names() {
+        let level_btn = commands
             .spawn((
-                Text::new("Loading rooms..."),
-                TextFont {
-                    font_size: 14.0,
-                    ..default()
-                },
-                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                Button,
                 Node {
-                    margin: UiRect::all(Val::Px(10.0)),
+                    width: Val::Px(100.0),
+                    height: Val::Px(40.0),
+                    margin: UiRect::all(Val::Px(5.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
                     ..default()
                 },
-                LobbyUIElements,
+                BackgroundColor(if level == lobby_ui.selected_level {
+                    Color::srgb(0.4, 0.7, 0.4)
+                } else {
+                    Color::srgb(0.3, 0.3, 0.3)
+                }),
+                LevelButton(level.to_string()),
             ))
-            .id();
-        commands.entity(rooms_container).add_child(loading_text);
-    } else {
-        for room in &lobby_ui.available_rooms {
-            let room_text = format!(
-                "{} ({}/{}) - {}",
-                room.room_id, room.current_players, room.max_players, room.game_mode
-            );
-            let room_btn = commands
-                .spawn((
-                    Button,
-                    Node {
-                        width: Val::Px(200.0),
-                        height: Val::Px(35.0),
-                        margin: UiRect::all(Val::Px(5.0)),
-                        justify_content: JustifyContent::Center,
-                        align_items: AlignItems::Center,
+            .with_children(|btn| {
+                btn.spawn((
+                    Text::new(format!("🗺️ {}", level.to_uppercase())),
+                    TextFont {
+                        font_size: 12.0,
                         ..default()
                     },
-                    BackgroundColor(Color::srgb(0.3, 0.3, 0.3)),
-                    RoomIdButton(room.room_id.clone()),
-                    LobbyUIElements,
-                ))
-                .with_children(|btn| {
-                    btn.spawn((
-                        Text::new(room_text),
-                        TextFont {
-                            font_size: 12.0,
-                            ..default()
-                        },
-                        TextColor(Color::srgb(1.0, 1.0, 1.0)),
-                    ));
-                })
-                .id();
-            commands.entity(rooms_container).add_child(room_btn);
-        }
+                    TextColor(Color::srgb(1.0, 1.0, 1.0)),
+                ));
+            })
+            .id();
+        commands.entity(level_container).add_child(level_btn);
     }
 
-    let join_btn = commands
+    let create_btn = commands
         .spawn((
             Button,
             Node {
@@ -773,35 +1252,143 @@ fn spawn_join_room_ui(commands: &mut Commands, container_entity: Entity, lobby_u
                 align_items: AlignItems::Center,
                 ..default()
             },
-            BackgroundColor(Color::srgb(0.2, 0.4, 0.6)),
-            ConfirmJoinButton,
+            BackgroundColor(Color::srgb(0.2, 0.6, 0.2)),
+            ConfirmCreateButton,
+            LobbyUIElements,
+        ))
+        .with_children(|btn| {
+            btn.spawn((
+                Text::new("CREATE"),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            ));
+        })
+        .id();
+
+    // Leave the password empty for a room anyone can join
+    let password_input = spawn_text_input(
+        commands,
+        TextInput::new(
+            lobby_ui.room_password.clone(),
+            "Password (optional)",
+            MAX_INPUT_LEN,
+        )
+        .masked(),
+        200.0,
+    );
+    commands.entity(password_input).insert(RoomPasswordInput);
+    let password_row = spawn_input_row(commands, "Password:", password_input);
+
+    let team_option = spawn_team_option_button(commands, lobby_ui);
+    let publish_option = spawn_publish_results_button(commands, lobby_ui);
+    let bots_option = spawn_bot_backfill_button(commands, lobby_ui);
+    let rules_option = (lobby_ui.selected_mode == GameMode::Custom.id())
+        .then(|| spawn_rules_button(commands, lobby_ui));
+
+    let back_btn = spawn_back_button_simple(commands);
+
+    commands.entity(container_entity).add_child(title);
+    commands.entity(container_entity).add_child(room_info);
+    commands.entity(container_entity).add_child(level_container);
+    commands.entity(container_entity).add_child(team_option);
+    if let Some(rules_option) = rules_option {
+        commands.entity(container_entity).add_child(rules_option);
+    }
+    commands.entity(container_entity).add_child(publish_option);
+    commands.entity(container_entity).add_child(bots_option);
+    commands.entity(container_entity).add_child(password_row);
+    commands.entity(container_entity).add_child(create_btn);
+    commands.entity(container_entity).add_child(back_btn);
+}
+
+// "Label:" next to a text field
+pub(super) fn spawn_input_row(commands: &mut Commands, label: &str, input: Entity) -> Entity {
+    let row = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                margin: UiRect::all(Val::Px(6.0)),
+                column_gap: Val::Px(10.0),
+                ..default()
+            },
             LobbyUIElements,
         ))
-        .with_children(|btn| {
-            btn.spawn((
-                Text::new("JOIN"),
+        .with_children(|row| {
+            row.spawn((
+                Text::new(label),
                 TextFont {
                     font_size: 16.0,
                     ..default()
                 },
-                TextColor(Color::srgb(1.0, 1.0, 1.0)),
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                Node {
+                    width: Val::Px(90.0),
+                    ..default()
+                },
             ));
         })
         .id();
+    commands.entity(row).add_child(input);
+    row
+}
 
-    let back_btn = spawn_back_button_simple(commands);
+// Copy what is typed into the lobby state. Typing must not rebuild the screen (that
+// would drop the focus), so this skips change detection.
+fn sync_lobby_inputs(
+    room_inputs: Query<Ref<TextInput>, With<RoomCodeInput>>,
+    password_inputs: Query<Ref<TextInput>, With<RoomPasswordInput>>,
+    mut submitted: EventReader<TextSubmitted>,
+    mut lobby_events: EventWriter<LobbyEvent>,
+    mut lobby_ui_query: Query<&mut LobbyUI>,
+) {
+    let Ok(mut lobby_ui) = lobby_ui_query.single_mut() else {
+        return;
+    };
+    for input in room_inputs.iter().filter(|input| input.is_changed()) {
+        let lobby_ui = lobby_ui.bypass_change_detection();
+        lobby_ui.room_code = input.value.trim().to_string();
+        lobby_ui.room_id = listed_room_id(lobby_ui, &lobby_ui.room_code).unwrap_or_default();
+    }
+    for input in password_inputs.iter().filter(|input| input.is_changed()) {
+        lobby_ui.bypass_change_detection().room_password = input.value.clone();
+    }
+    // Enter in the room code field selects that room, like picking it from the list
+    for event in submitted.read() {
+        if room_inputs.contains(event.entity) {
+            lobby_events.write(LobbyEvent::EnterRoomCode(event.value.trim().to_string()));
+        }
+    }
+}
 
-    commands.entity(container_entity).add_child(title);
-    commands.entity(container_entity).add_child(room_input);
-    commands.entity(container_entity).add_child(rooms_container);
-    commands.entity(container_entity).add_child(join_btn);
-    commands.entity(container_entity).add_child(back_btn);
+// Id of the listed room with the code the player typed, rooms that aren't listed (another
+// page, or not fetched yet) are joined by their code
+fn listed_room_id(lobby_ui: &LobbyUI, typed: &str) -> Option<String> {
+    let code = parse_room_code(typed)?;
+    lobby_ui
+        .available_rooms
+        .iter()
+        .find(|room| room.code == code)
+        .map(|room| room.room_id.clone())
 }
 
-fn spawn_in_room_ui(commands: &mut Commands, container_entity: Entity, lobby_ui: &LobbyUI) {
+fn spawn_in_room_ui(
+    commands: &mut Commands,
+    container_entity: Entity,
+    lobby_ui: &LobbyUI,
+    friends: &Friends,
+    chat: &RoomChat,
+    connection: &ConnectionState,
+) {
     let title = commands
         .spawn((
-            Text::new(format!("Room: {}", lobby_ui.room_id)),
+            Text::new(format!(
+                "Room: {} ({})",
+                lobby_ui.room_code, lobby_ui.selected_level
+            )),
             TextFont {
                 font_size: 24.0,
                 ..default()
@@ -833,8 +1420,39 @@ fn spawn_in_room_ui(commands: &mut Commands, container_entity: Entity, lobby_ui:
         .id();
 
     commands.entity(container_entity).add_child(title);
+    if let Some(share) = spawn_share_link(commands, lobby_ui) {
+        commands.entity(container_entity).add_child(share);
+    }
     commands.entity(container_entity).add_child(player_count);
 
+    if let Some(rules) = &lobby_ui.room_rules {
+        let rules = spawn_room_rules(commands, rules);
+        commands.entity(container_entity).add_child(rules);
+    }
+
+    if let Some(last_match) = &lobby_ui.last_match {
+        let results = spawn_last_match(commands, last_match);
+        commands.entity(container_entity).add_child(results);
+    }
+
+    if lobby_ui.room_teams > 0 {
+        let columns = spawn_team_columns(commands, lobby_ui);
+        commands.entity(container_entity).add_child(columns);
+        if let Some(kicks) = spawn_kick_row(commands, lobby_ui) {
+            commands.entity(container_entity).add_child(kicks);
+        }
+    } else if !lobby_ui.room_members.is_empty() {
+        let roster = spawn_room_roster(commands, lobby_ui);
+        commands.entity(container_entity).add_child(roster);
+    }
+
+    let chat = spawn_room_chat(commands, chat);
+    commands.entity(container_entity).add_child(chat);
+
+    if let Some(panel) = spawn_room_friends(commands, friends, lobby_ui) {
+        commands.entity(container_entity).add_child(panel);
+    }
+
     // Host indicator
     if lobby_ui.is_host {
         let host_indicator = commands
@@ -856,7 +1474,7 @@ fn spawn_in_room_ui(commands: &mut Commands, container_entity: Entity, lobby_ui:
     }
 
     // Status
-    let status_text = if lobby_ui.is_searching {
+    let status_text = if connection.is_searching() {
         "🔍 Creating game server..."
     } else if lobby_ui.current_players >= 1 {
         "✅ Ready to play!"
@@ -894,8 +1512,9 @@ fn spawn_in_room_ui(commands: &mut Commands, container_entity: Entity, lobby_ui:
         ))
         .id();
 
-    // Start game button
+    // Start game button, waiting for the ready check
     if lobby_ui.is_host || lobby_ui.current_players >= 1 {
+        let secs_left = start_secs_left(lobby_ui);
         let start_btn = commands
             .spawn((
                 Button,
@@ -907,12 +1526,12 @@ fn spawn_in_room_ui(commands: &mut Commands, container_entity: Entity, lobby_ui:
                     align_items: AlignItems::Center,
                     ..default()
                 },
-                BackgroundColor(Color::srgb(0.2, 0.6, 0.2)),
+                BackgroundColor(start_button_color(secs_left)),
                 StartGameButton,
             ))
             .with_children(|btn| {
                 btn.spawn((
-                    Text::new("START GAME"),
+                    Text::new(start_button_label(secs_left)),
                     TextFont {
                         font_size: 14.0,
                         ..default()
@@ -924,6 +1543,10 @@ fn spawn_in_room_ui(commands: &mut Commands, container_entity: Entity, lobby_ui:
         commands.entity(button_container).add_child(start_btn);
     }
 
+    if let Some(ready_btn) = spawn_ready_button(commands, lobby_ui) {
+        commands.entity(button_container).add_child(ready_btn);
+    }
+
     // Leave room button
     let leave_btn = commands
         .spawn((
@@ -957,7 +1580,95 @@ fn spawn_in_room_ui(commands: &mut Commands, container_entity: Entity, lobby_ui:
         .add_child(button_container);
 }
 
-fn spawn_back_button_simple(commands: &mut Commands) -> Entity {
+// Level select for the offline campaign, locked levels can't be started
+fn spawn_campaign_ui(commands: &mut Commands, container_entity: Entity, settings: &ClientSettings) {
+    let campaign = Campaign::default();
+    let progress = &settings.campaign;
+
+    let title = commands
+        .spawn((
+            Text::new(format!("🏁 {} Campaign", campaign.name)),
+            TextFont {
+                font_size: 28.0,
+                ..default()
+            },
+            TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            Node {
+                margin: UiRect::all(Val::Px(20.0)),
+                ..default()
+            },
+            LobbyUIElements,
+        ))
+        .id();
+    commands.entity(container_entity).add_child(title);
+
+    let list = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                margin: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            LobbyUIElements,
+        ))
+        .id();
+
+    for (index, level) in campaign.levels.iter().enumerate() {
+        let unlocked = progress.is_unlocked(index);
+        let best = progress
+            .best_times
+            .get(level)
+            .map(|t| format!("best {:.2}s", t))
+            .unwrap_or_else(|| "not completed".to_string());
+        let label = if unlocked {
+            format!("{}. {} - {}", index + 1, level, best)
+        } else {
+            format!("🔒 {}. {}", index + 1, level)
+        };
+
+        let button = commands
+            .spawn((
+                Button,
+                Node {
+                    width: Val::Px(320.0),
+                    height: Val::Px(44.0),
+                    margin: UiRect::all(Val::Px(6.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                BackgroundColor(if unlocked {
+                    Color::srgb(0.3, 0.3, 0.3)
+                } else {
+                    Color::srgb(0.15, 0.15, 0.15)
+                }),
+                CampaignLevelButton(index),
+            ))
+            .with_children(|btn| {
+                btn.spawn((
+                    Text::new(label),
+                    TextFont {
+                        font_size: 16.0,
+                        ..default()
+                    },
+                    TextColor(if unlocked {
+                        Color::srgb(1.0, 1.0, 1.0)
+                    } else {
+                        Color::srgb(0.5, 0.5, 0.5)
+                    }),
+                ));
+            })
+            .id();
+        commands.entity(list).add_child(button);
+    }
+    commands.entity(container_entity).add_child(list);
+
+    let back = spawn_back_button_simple(commands);
+    commands.entity(container_entity).add_child(back);
+}
+
+pub(super) fn spawn_back_button_simple(commands: &mut Commands) -> Entity {
     commands
         .spawn((
             Button,
@@ -995,11 +1706,49 @@ fn cleanup_lobby_ui(mut commands: Commands, lobby_query: Query<Entity, With<Lobb
     }
 }
 
+// One more local player on every press, back to one after the last seat
+fn handle_local_players(
+    buttons: Query<&Interaction, (Changed<Interaction>, With<LocalPlayersButton>)>,
+    mut lobby_q: Query<&mut LobbyUI>,
+) {
+    if !buttons
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        return;
+    }
+    let Ok(mut ui) = lobby_q.single_mut() else {
+        return;
+    };
+    ui.local_players = ui.local_players % MAX_LOCAL_PLAYERS + 1;
+}
+
 // 🎮 Handle lobby input and button clicks
 fn handle_lobby_input(
+    // Color swatches, text fields and browser filters draw themselves
     mut interaction_query: Query<
         (&Interaction, &mut BackgroundColor, Entity),
-        (Changed<Interaction>, With<Button>),
+        (
+            Changed<Interaction>,
+            With<Button>,
+            Without<ColorSwatch>,
+            Without<TextInput>,
+            Without<BrowserButton>,
+            Without<TeamOptionButton>,
+            Without<PublishResultsButton>,
+            Without<BotBackfillButton>,
+            Without<RulesButton>,
+            Without<LocalPlayersButton>,
+            Without<PartyButton>,
+            Without<FriendButton>,
+            (
+                Without<ReadyButton>,
+                Without<KickButton>,
+                Without<ChatButton>,
+                Without<CopyLinkButton>,
+                Without<CancelMatchmakingButton>,
+            ),
+        ),
     >,
     button_types: Query<(
         Option<&ModeButton>,
@@ -1013,9 +1762,14 @@ fn handle_lobby_input(
         Option<&StartGameButton>,
         Option<&LeaveRoomButton>,
         Option<&BackButton>,
+        Option<&LevelButton>,
+        Option<&CampaignButton>,
+        Option<&CampaignLevelButton>,
+        Option<&PageButton>,
     )>,
     mut lobby_events: EventWriter<LobbyEvent>,
     mut lobby_ui_query: Query<&mut LobbyUI>,
+    mut notice: ResMut<UiNotice>,
 ) {
     for (interaction, mut color, entity) in interaction_query.iter_mut() {
         if let Ok((
@@ -1030,6 +1784,10 @@ fn handle_lobby_input(
             start_btn,
             leave_btn,
             back_btn,
+            level_btn,
+            campaign_btn,
+            campaign_level_btn,
+            page_btn,
         )) = button_types.get(entity)
         {
             match *interaction {
@@ -1037,12 +1795,15 @@ fn handle_lobby_input(
                     if let Some(mode_button) = mode_btn {
                         lobby_events.write(LobbyEvent::SelectMode(mode_button.0.clone()));
                         *color = BackgroundColor(Color::srgb(0.4, 0.7, 0.4));
+                    } else if let Some(level_button) = level_btn {
+                        lobby_events.write(LobbyEvent::SelectLevel(level_button.0.clone()));
+                        *color = BackgroundColor(Color::srgb(0.4, 0.7, 0.4));
                     } else if quick_match_btn.is_some() {
+                        if lobby_ui_query.single().is_ok_and(|ui| !can_queue(ui)) {
+                            continue;
+                        }
                         info!("🎯 Starting quick match...");
                         // Trigger real BevyGap matchmaking via StartMatchmaking event
-                        if let Ok(mut lobby_ui) = lobby_ui_query.single_mut() {
-                            lobby_ui.is_searching = true;
-                        }
                         lobby_events.write(LobbyEvent::StartMatchmaking);
                         *color = BackgroundColor(Color::srgb(0.5, 0.1, 0.5));
                     } else if create_btn.is_some() {
@@ -1058,108 +1819,65 @@ fn handle_lobby_input(
                         info!("🎮 Starting local game...");
                         lobby_events.write(LobbyEvent::StartLocalGame);
                         *color = BackgroundColor(Color::srgb(0.5, 0.3, 0.1));
+                    } else if campaign_btn.is_some() {
+                        lobby_events.write(LobbyEvent::OpenCampaign);
+                        *color = BackgroundColor(Color::srgb(0.5, 0.4, 0.05));
+                    } else if let Some(page) = page_btn {
+                        lobby_events.write(LobbyEvent::OpenPage(page.0.clone()));
+                        *color = BackgroundColor(Color::srgb(0.2, 0.2, 0.3));
+                    } else if let Some(campaign_level) = campaign_level_btn {
+                        lobby_events.write(LobbyEvent::StartCampaignLevel(campaign_level.0));
+                        *color = BackgroundColor(Color::srgb(0.4, 0.7, 0.4));
                     } else if confirm_create.is_some() {
                         lobby_events.write(LobbyEvent::ConfirmCreateRoom);
                         *color = BackgroundColor(Color::srgb(0.1, 0.5, 0.1));
                     } else if confirm_join.is_some() {
                         if let Ok(mut lobby_ui) = lobby_ui_query.single_mut() {
-                            if !lobby_ui.room_id.is_empty() {
-                                lobby_ui.is_host = false;
-                                lobby_ui.lobby_mode = LobbyMode::InRoom;
-                                lobby_ui.is_searching = false;
-                                lobby_ui.current_players = lobby_ui.current_players.max(2);
-                                info!("🚪 Joined room: {}", lobby_ui.room_id);
-                                #[cfg(target_arch = "wasm32")]
-                                {
-                                    use serde::Serialize;
-                                    use wasm_bindgen_futures::spawn_local;
-                                    let room_id = lobby_ui.room_id.clone();
-                                    let player_name = lobby_ui.player_name.clone();
-                                    spawn_local(async move {
-                                        #[derive(Serialize)]
-                                        struct JoinReq<'a> {
-                                            player_name: &'a str,
-                                        }
-                                        let url = format!(
-                                            "{}/lobby/api/rooms/{}/join",
-                                            http_base(),
-                                            room_id
-                                        );
-                                        let body = serde_json::to_string(&JoinReq {
-                                            player_name: &player_name,
-                                        })
-                                        .unwrap();
-                                        match fetch_json(&url, "POST", Some(body)).await {
-                                            Ok(resp) => {
-                                                let resp: web_sys::Response =
-                                                    resp.dyn_into().unwrap();
-                                                if resp.ok() {
-                                                    match wasm_bindgen_futures::JsFuture::from(
-                                                        resp.json().unwrap(),
-                                                    )
-                                                    .await
-                                                    {
-                                                        Ok(js) => {
-                                                            let room: ServerLobbyRoom =
-                                                                serde_wasm_bindgen::from_value(js)
-                                                                    .unwrap();
-                                                            PENDING_PLAYER_COUNT.with(|cell| {
-                                                                cell.replace(Some(
-                                                                    room.current_players,
-                                                                ))
-                                                            });
-                                                        }
-                                                        Err(e) => web_sys::console::error_1(&e),
-                                                    }
-                                                } else {
-                                                    web_sys::console::error_1(
-                                                        &format!(
-                                                            "Join failed http {}",
-                                                            resp.status()
-                                                        )
-                                                        .into(),
-                                                    );
-                                                }
-                                            }
-                                            Err(e) => web_sys::console::error_1(&e),
-                                        }
-                                    });
-                                }
-                            }
+                            join_room(&mut lobby_ui);
                         }
                         *color = BackgroundColor(Color::srgb(0.1, 0.3, 0.5));
                     } else if let Some(room_id_btn) = room_id_btn {
-                        if let Ok(mut lobby_ui) = lobby_ui_query.single_mut() {
-                            lobby_ui.room_id = room_id_btn.0.clone();
-                            info!("🔤 Selected room ID: {}", lobby_ui.room_id);
+                        let room = lobby_ui_query.single_mut().ok().and_then(|lobby_ui| {
+                            let room = lobby_ui
+                                .available_rooms
+                                .iter()
+                                .find(|r| r.room_id == room_id_btn.0)
+                                .cloned();
+                            room.map(|room| (lobby_ui, room))
+                        });
+                        if let Some((mut lobby_ui, room)) = room {
+                            lobby_ui.room_id = room.room_id;
+                            lobby_ui.room_code = room.code;
+                            // Joiners play whatever level the host picked
+                            if !room.level.is_empty() {
+                                lobby_ui.selected_level = room.level;
+                            }
+                            info!("🔤 Selected room: {}", lobby_ui.room_code);
                         }
                         *color = BackgroundColor(Color::srgb(0.2, 0.2, 0.2));
                     } else if start_btn.is_some() {
+                        let secs_left = lobby_ui_query.single().map_or(0, start_secs_left);
+                        if secs_left > 0 {
+                            notice.msg = Some(format!(
+                                "⏳ Waiting for everyone to be ready, or {}s",
+                                secs_left
+                            ));
+                            continue;
+                        }
                         info!("🚀 Starting matchmaking...");
-                        #[cfg(target_arch = "wasm32")]
-                        {
-                            use wasm_bindgen_futures::spawn_local;
-                            if let Ok(lobby_ui) = lobby_ui_query.single() {
-                                if !lobby_ui.room_id.is_empty() {
-                                    let room_id = lobby_ui.room_id.clone();
-                                    spawn_local(async move {
-                                        let url = format!(
-                                            "{}/lobby/api/rooms/{}/start",
-                                            http_base(),
-                                            room_id
-                                        );
-                                        match fetch_json(&url, "POST", None).await {
-                                            Ok(resp) => {
-                                                let resp: web_sys::Response =
-                                                    resp.dyn_into().unwrap();
-                                                if !resp.ok() {
-                                                    web_sys::console::error_1(&format!("Failed to mark room started, status {}", resp.status()).into());
-                                                }
-                                            }
-                                            Err(e) => web_sys::console::error_1(&e),
-                                        }
-                                    });
-                                }
+                        #[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+                        if let Ok(lobby_ui) = lobby_ui_query.single() {
+                            if !lobby_ui.room_id.is_empty() {
+                                let room_id = lobby_ui.room_id.clone();
+                                spawn(async move {
+                                    let url = format!(
+                                        "{}/lobby/api/rooms/{}/start",
+                                        http_base(),
+                                        room_id
+                                    );
+                                    // Failures are logged, matchmaking goes ahead
+                                    let _ = ApiRequest::new("POST", url).send().await;
+                                });
                             }
                         }
                         lobby_events.write(LobbyEvent::StartMatchmaking);
@@ -1178,7 +1896,7 @@ fn handle_lobby_input(
 
                 Interaction::Hovered => {
                     // Lighter colors on hover
-                    if mode_btn.is_some() {
+                    if mode_btn.is_some() || level_btn.is_some() {
                         *color = BackgroundColor(Color::srgb(0.5, 0.8, 0.5));
                     } else if create_btn.is_some() {
                         *color = BackgroundColor(Color::srgb(0.3, 0.7, 0.3));
@@ -1186,6 +1904,10 @@ fn handle_lobby_input(
                         *color = BackgroundColor(Color::srgb(0.3, 0.5, 0.7));
                     } else if local_btn.is_some() {
                         *color = BackgroundColor(Color::srgb(0.7, 0.5, 0.3));
+                    } else if campaign_btn.is_some() {
+                        *color = BackgroundColor(Color::srgb(0.7, 0.6, 0.2));
+                    } else if page_btn.is_some() {
+                        *color = BackgroundColor(Color::srgb(0.4, 0.4, 0.5));
                     } else {
                         *color = BackgroundColor(Color::srgb(0.5, 0.5, 0.5));
                     }
@@ -1201,12 +1923,26 @@ fn handle_lobby_input(
                                 *color = BackgroundColor(Color::srgb(0.3, 0.3, 0.3));
                             }
                         }
+                    } else if let Some(level_button) = level_btn {
+                        if let Ok(lobby_ui) = lobby_ui_query.single() {
+                            if level_button.0 == lobby_ui.selected_level {
+                                *color = BackgroundColor(Color::srgb(0.4, 0.7, 0.4));
+                            } else {
+                                *color = BackgroundColor(Color::srgb(0.3, 0.3, 0.3));
+                            }
+                        }
                     } else if create_btn.is_some() {
                         *color = BackgroundColor(Color::srgb(0.2, 0.6, 0.2));
                     } else if join_btn.is_some() {
                         *color = BackgroundColor(Color::srgb(0.2, 0.4, 0.6));
                     } else if local_btn.is_some() {
                         *color = BackgroundColor(Color::srgb(0.6, 0.4, 0.2));
+                    } else if campaign_btn.is_some() {
+                        *color = BackgroundColor(Color::srgb(0.6, 0.5, 0.1));
+                    } else if page_btn.is_some() {
+                        *color = BackgroundColor(Color::srgb(0.3, 0.3, 0.4));
+                    } else if campaign_level_btn.is_some() {
+                        *color = BackgroundColor(Color::srgb(0.3, 0.3, 0.3));
                     } else if confirm_create.is_some() {
                         *color = BackgroundColor(Color::srgb(0.2, 0.6, 0.2));
                     } else if confirm_join.is_some() {
@@ -1214,7 +1950,8 @@ fn handle_lobby_input(
                     } else if room_id_btn.is_some() {
                         *color = BackgroundColor(Color::srgb(0.3, 0.3, 0.3));
                     } else if start_btn.is_some() {
-                        *color = BackgroundColor(Color::srgb(0.2, 0.6, 0.2));
+                        let secs_left = lobby_ui_query.single().map_or(0, start_secs_left);
+                        *color = BackgroundColor(start_button_color(secs_left));
                     } else if leave_btn.is_some() {
                         *color = BackgroundColor(Color::srgb(0.6, 0.2, 0.2));
                     } else if back_btn.is_some() {
@@ -1240,12 +1977,19 @@ fn update_simple_ui(
 }
 
 // 🎯 Handle lobby events
+#[allow(clippy::too_many_arguments)]
 fn handle_lobby_events(
     mut lobby_events: EventReader<LobbyEvent>,
     mut lobby_ui_query: Query<&mut LobbyUI>,
     mut next_state: ResMut<NextState<AppState>>,
-    mut room_registry: ResMut<ClientRoomRegistry>,
-    #[allow(unused_mut)] mut commands: Commands,
+    // Only builds without bevygap keep their rooms here
+    #[cfg(not(feature = "bevygap"))] mut room_registry: ResMut<ClientRoomRegistry>,
+    mut commands: Commands,
+    settings: Res<ClientSettings>,
+    mut notice: ResMut<UiNotice>,
+    mut connection_events: EventWriter<ConnectionEvent>,
+    #[cfg(feature = "bevygap")] bevygap_config: Option<ResMut<BevygapClientConfig>>,
+    #[cfg(feature = "bevygap")] lobby_config: Res<LobbyConfig>,
 ) {
     let mut lobby_ui = if let Ok(ui) = lobby_ui_query.single_mut() {
         ui
@@ -1274,134 +2018,229 @@ fn handle_lobby_events(
                     "🚀 Starting multiplayer game with {} players!",
                     lobby_ui.current_players
                 );
-                lobby_ui.is_searching = false;
                 next_state.set(AppState::InGame);
             }
             LobbyEvent::StartMatchmaking => {
+                // Ratings belong to lobby accounts, which builds without bevygap don't have
+                if lobby_ui.selected_mode == GameMode::Ranked.id() && lobby_ui.player_id.is_none() {
+                    notice.msg = Some("Ranked needs a player account from the lobby".to_string());
+                    continue;
+                }
                 info!("🔍 Starting matchmaking...");
-                lobby_ui.is_searching = true;
+                connection_events.write(ConnectionEvent::MatchmakingStarted);
+
+                commands.insert_resource(selected_level(&lobby_ui));
 
                 // Simplified matchmaking - just trigger bevygap connection
                 #[cfg(feature = "bevygap")]
                 {
+                    // Pass the level along as deployment metadata so the server starts with it
+                    if let Some(mut config) = bevygap_config {
+                        let base = config
+                            .matchmaker_url
+                            .split('?')
+                            .next()
+                            .unwrap_or_default()
+                            .to_string();
+                        // Level names come from files and custom levels, every value is
+                        // encoded
+                        let mut query = form_urlencoded::Serializer::new(String::new());
+                        query
+                            .append_pair("level", &lobby_ui.selected_level)
+                            .append_pair("game_mode", &lobby_ui.selected_mode);
+                        // The matchmaker checks we are in the room and signs it into our
+                        // connect token, the game server puts us in it from there
+                        if !lobby_ui.room_id.is_empty() {
+                            query.append_pair("room_id", &lobby_ui.room_id);
+                        }
+                        // Team rooms start their server with the room's teams
+                        if lobby_ui.room_teams > 0 && !lobby_ui.room_id.is_empty() {
+                            query
+                                .append_pair("teams", &lobby_ui.room_teams.to_string())
+                                .append_pair("friendly_fire", &lobby_ui.friendly_fire.to_string());
+                        }
+                        // Rooms with bot backfill start their server with bots
+                        append_bot_params(&mut query, &lobby_ui, lobby_config.max_players);
+                        // Custom matches start their server with the room's rule set
+                        if let Some(rules) = match_rules(&lobby_ui) {
+                            query.append_pair("rules", &rules);
+                        }
+                        // Queue in the closest region, or the party leader's. The matchmaker
+                        // deploys where every player of the match is close enough
+                        if let Some(region) = queue_region(&lobby_ui) {
+                            query
+                                .append_pair("region", &region)
+                                .append_pair("pings", &format_pings(&lobby_ui.region_pings));
+                        }
+                        // Party members queue together, the matchmaker waits for all of
+                        // them and puts them in one match
+                        if let Some(party) = lobby_ui
+                            .party
+                            .as_ref()
+                            .filter(|_| lobby_ui.room_id.is_empty())
+                        {
+                            query
+                                .append_pair("party_id", &party.id)
+                                .append_pair("party_size", &party.members.len().to_string());
+                        }
+                        // Ranked tickets are matched by the account's rating
+                        if let Some(player_id) = &lobby_ui.player_id {
+                            query.append_pair("player_id", player_id);
+                        }
+                        // Browsers can't set headers on a WebSocket, so the trace id rides
+                        // along in the query
+                        let trace_id = new_trace_id();
+                        query.append_pair("trace_id", &trace_id);
+                        config.matchmaker_url = format!("{}?{}", base, query.finish());
+                        info!("🔍 Matchmaking trace {}", trace_id);
+                        lobby_ui.matchmaker_trace = Some(trace_id);
+                    }
+                    #[cfg(target_arch = "wasm32")]
+                    if lobby_ui.room_id.is_empty() {
+                        announce_party_queue(&lobby_ui);
+                    }
                     commands.bevygap_connect_client();
                 }
+                // For local development without bevygap, just start the game
                 #[cfg(not(feature = "bevygap"))]
-                {
-                    // For local development without bevygap, just start the game
-                    next_state.set(AppState::InGame);
-                }
+                connection_events.write(ConnectionEvent::Connected);
             }
             LobbyEvent::StartLocalGame => {
-                info!("🎮 Starting local game!");
+                info!(
+                    "🎮 Starting local game for {} players!",
+                    lobby_ui.local_players
+                );
+                commands.insert_resource(selected_level(&lobby_ui));
+                commands.insert_resource(LocalGame {
+                    players: lobby_ui.local_players,
+                });
                 next_state.set(AppState::InGame);
             }
+            LobbyEvent::OpenCampaign => {
+                lobby_ui.lobby_mode = LobbyMode::Campaign;
+                info!("🏁 Switching to campaign level select");
+            }
+            LobbyEvent::OpenPage(mode) => {
+                lobby_ui.lobby_mode = mode.clone();
+                info!("📄 Switching to {:?}", mode);
+            }
+            LobbyEvent::StartCampaignLevel(index) => {
+                if !settings.campaign.is_unlocked(*index) {
+                    notice.msg = Some("🔒 Finish the previous level first".to_string());
+                    continue;
+                }
+                match ActiveCampaign::start(Campaign::default(), *index) {
+                    Some((campaign, level)) => {
+                        info!("🏁 Starting campaign at level {}", index + 1);
+                        commands.insert_resource(level);
+                        commands.insert_resource(campaign);
+                        next_state.set(AppState::InGame);
+                    }
+                    None => warn!("🏁 Campaign level {} is not a built-in level", index + 1),
+                }
+            }
             LobbyEvent::SelectMode(mode) => {
                 lobby_ui.selected_mode = mode.clone();
                 info!("🎯 Selected game mode: {}", mode);
+
+                // Some modes only work on a particular level (co-op plates and doors)
+                let game_mode = GameMode::from_id(mode).unwrap_or_default();
+                if let Some(level) = game_mode.required_level() {
+                    lobby_ui.selected_level = level.to_string();
+                } else if selected_level(&lobby_ui).is_coop() {
+                    lobby_ui.selected_level = DEFAULT_LEVEL.to_string();
+                }
+            }
+            LobbyEvent::SelectLevel(level) => {
+                lobby_ui.selected_level = level.clone();
+                info!("🗺️ Selected level: {}", level);
             }
             LobbyEvent::CreateRoom => {
                 lobby_ui.lobby_mode = LobbyMode::CreateRoom;
                 info!("🏠 Switching to create room mode");
             }
             LobbyEvent::ConfirmCreateRoom => {
-                #[cfg(all(target_arch = "wasm32", feature = "bevygap"))]
+                #[cfg(feature = "bevygap")]
                 {
                     let player_name = lobby_ui.player_name.clone();
+                    let host_id = lobby_ui.player_id.clone();
                     let game_mode = lobby_ui.selected_mode.clone();
-                    spawn_local(async move {
+                    let level = lobby_ui.selected_level.clone();
+                    let password = lobby_ui.password();
+                    let teams = lobby_ui.room_teams;
+                    let friendly_fire = lobby_ui.friendly_fire;
+                    let publish_results = lobby_ui.publish_results;
+                    let bot_backfill = lobby_ui.bot_backfill;
+                    let bot_style = lobby_ui.bot_style;
+                    let rules = match_rules(&lobby_ui);
+                    spawn(async move {
                         let url = format!("{}/lobby/api/rooms", http_base());
-                        #[derive(Serialize)]
-                        struct CreateReq<'a> {
-                            host_name: &'a str,
-                            game_mode: &'a str,
-                            max_players: u32,
-                        }
-                        let body = serde_json::to_string(&CreateReq {
-                            host_name: &player_name,
-                            game_mode: &game_mode,
-                            max_players: 4,
-                        })
-                        .unwrap();
-                        match fetch_json(&url, "POST", Some(body)).await {
-                            Ok(resp) => {
-                                let resp: web_sys::Response = resp.dyn_into().unwrap();
-                                if !resp.ok() {
-                                    let status = resp.status();
-                                    web_sys::console::error_1(
-                                        &format!("Create room failed http {}", status).into(),
-                                    );
-                                    return;
-                                }
-                                match wasm_bindgen_futures::JsFuture::from(resp.json().unwrap())
-                                    .await
-                                {
-                                    Ok(js) => {
-                                        let room: ServerLobbyRoom =
-                                            serde_wasm_bindgen::from_value(js).unwrap();
-                                        web_sys::console::log_1(
-                                            &format!("Room created {}", room.id).into(),
-                                        );
-                                        PENDING_ROOM_CREATED.with(|cell| {
-                                            cell.replace(Some(RoomInfo {
-                                                room_id: room.id,
-                                                current_players: room.current_players,
-                                                max_players: room.max_players,
-                                                host_name: room.host_name,
-                                                game_mode: room.game_mode,
-                                            }));
-                                        });
-                                    }
-                                    Err(e) => web_sys::console::error_1(&e),
-                                }
+                        let request = ApiRequest::post(
+                            url,
+                            &CreateRoomRequest {
+                                host_name: player_name,
+                                game_mode,
+                                max_players: 4,
+                                level,
+                                host_id,
+                                password,
+                                teams,
+                                friendly_fire,
+                                publish_results,
+                                rules,
+                                bot_backfill,
+                                bot_style,
+                            },
+                        );
+                        match request.json::<ServerLobbyRoom>().await {
+                            Ok(room) => {
+                                info!("🏠 Room created {}", room.id);
+                                queue_room_teams(&room);
+                                queue_room_results(&room);
+                                queue_room_bots(&room);
+                                queue_room_rules(&room);
+                                PENDING_ROOM_CREATED.set(RoomInfo::from(room));
+                            }
+                            Err(error) => {
+                                PENDING_NOTICE.set(format!("Couldn't create the room ({})", error));
                             }
-                            Err(e) => web_sys::console::error_1(&e),
                         }
                     });
                 }
-                #[cfg(all(target_arch = "wasm32", not(feature = "bevygap")))]
+                #[cfg(not(feature = "bevygap"))]
                 {
-                    // Fallback for WASM builds without bevygap - create local room
+                    // Without bevygap there is no lobby service, the room only lives here
                     let mut rng = rand::thread_rng();
                     let room_num = rng.gen_range(1..=999);
                     let room_id = format!("ROOM{:03}", room_num);
+                    let code = shared::unique_room_code(
+                        || rng.gen(),
+                        |code| room_registry.rooms.iter().any(|room| room.code == code),
+                    )
+                    .unwrap_or_default();
                     let room_info = RoomInfo {
                         room_id: room_id.clone(),
+                        code: code.clone(),
                         current_players: 1,
                         max_players: 4,
                         host_name: lobby_ui.player_name.clone(),
                         game_mode: lobby_ui.selected_mode.clone(),
+                        level: lobby_ui.selected_level.clone(),
+                        has_password: lobby_ui.password().is_some(),
+                        map: lobby_ui.selected_level.clone(),
+                        region: None,
                     };
                     room_registry.rooms.push(room_info);
+                    lobby_ui.room_members = vec![host_member(&lobby_ui)];
                     lobby_ui.room_id = room_id;
+                    lobby_ui.room_code = code;
                     lobby_ui.is_host = true;
                     lobby_ui.lobby_mode = LobbyMode::InRoom;
-                    lobby_ui.is_searching = false;
                     info!(
                         "🏠 Created local room: {} (bevygap disabled)",
-                        lobby_ui.room_id
+                        lobby_ui.room_code
                     );
                 }
-                #[cfg(not(target_arch = "wasm32"))]
-                {
-                    // Keep local fallback for native
-                    let mut rng = rand::thread_rng();
-                    let room_num = rng.gen_range(1..=999);
-                    let room_id = format!("ROOM{:03}", room_num);
-                    let room_info = RoomInfo {
-                        room_id: room_id.clone(),
-                        current_players: 1,
-                        max_players: 4,
-                        host_name: lobby_ui.player_name.clone(),
-                        game_mode: lobby_ui.selected_mode.clone(),
-                    };
-                    room_registry.rooms.push(room_info);
-                    lobby_ui.room_id = room_id;
-                    lobby_ui.is_host = true;
-                    lobby_ui.lobby_mode = LobbyMode::InRoom;
-                    lobby_ui.is_searching = false;
-                    info!("🏠 Created room: {}", lobby_ui.room_id);
-                }
             }
             LobbyEvent::JoinRoom => {
                 lobby_ui.lobby_mode = LobbyMode::JoinRoom;
@@ -1409,188 +2248,223 @@ fn handle_lobby_events(
             }
             LobbyEvent::RequestRoomList => {
                 info!("📋 Requesting room list from server...");
-                #[cfg(all(target_arch = "wasm32", feature = "bevygap"))]
+                #[cfg(feature = "bevygap")]
                 {
-                    spawn_local(async move {
-                        let url = format!("{}/lobby/api/rooms", http_base());
-                        match fetch_json(&url, "GET", None).await {
-                            Ok(resp) => {
-                                let resp: web_sys::Response = resp.dyn_into().unwrap();
-                                match wasm_bindgen_futures::JsFuture::from(resp.json().unwrap())
-                                    .await
-                                {
-                                    Ok(js) => {
-                                        let rooms: Vec<ServerLobbyRoom> =
-                                            serde_wasm_bindgen::from_value(js).unwrap_or_default();
-                                        let list: Vec<RoomInfo> = rooms
-                                            .into_iter()
-                                            .filter(|r| !r.started)
-                                            .map(|r| RoomInfo {
-                                                room_id: r.id,
-                                                current_players: r.current_players,
-                                                max_players: r.max_players,
-                                                host_name: r.host_name,
-                                                game_mode: r.game_mode,
-                                            })
-                                            .collect();
-                                        PENDING_ROOM_LIST.with(|cell| cell.replace(Some(list)));
-                                    }
-                                    Err(e) => {
-                                        PENDING_NOTICE.with(|cell| {
-                                            cell.replace(Some(format!(
-                                                "Failed loading rooms: {e:?}"
-                                            )))
-                                        });
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                PENDING_NOTICE.with(|cell| {
-                                    cell.replace(Some(format!("Failed http rooms: {e:?}")))
-                                });
-                            }
+                    let query = lobby_ui.room_query.clone();
+                    // Ping the regions before the first list if the lobby's own pings
+                    // haven't come back, so rooms show up with their latency
+                    let measure_pings = lobby_ui.region_pings.is_empty();
+                    spawn(async move {
+                        if measure_pings {
+                            PENDING_REGION_PINGS.set(ping_regions().await);
+                        }
+                        match fetch_room_page(&query, false).await {
+                            Ok(Some(page)) => PENDING_ROOM_LIST.set(page),
+                            Ok(None) => {}
+                            Err(msg) => PENDING_NOTICE.set(msg),
                         }
                     });
                 }
-                #[cfg(all(target_arch = "wasm32", not(feature = "bevygap")))]
+                #[cfg(not(feature = "bevygap"))]
                 {
-                    // Fallback for WASM builds without bevygap - use local room registry
-                    lobby_ui.available_rooms = room_registry.rooms.clone();
+                    // Without bevygap the rooms created here are all there is
+                    show_rooms(&mut lobby_ui, local_rooms(&room_registry.rooms));
+                    lobby_ui.lobby_mode = LobbyMode::JoinRoom;
                     info!(
                         "📋 Loaded {} local rooms (bevygap disabled)",
                         lobby_ui.available_rooms.len()
                     );
                 }
-                #[cfg(not(target_arch = "wasm32"))]
-                {
-                    // Combine registry rooms with some default rooms for testing
-                    let mut available_rooms = room_registry.rooms.clone();
-
-                    // Add some default test rooms if the registry is empty
-                    if available_rooms.is_empty() {
-                        available_rooms = vec![
-                            RoomInfo {
-                                room_id: "ROOM001".to_string(),
-                                current_players: 2,
-                                max_players: 4,
-                                host_name: "Player1".to_string(),
-                                game_mode: "casual".to_string(),
-                            },
-                            RoomInfo {
-                                room_id: "ROOM002".to_string(),
-                                current_players: 1,
-                                max_players: 4,
-                                host_name: "Player2".to_string(),
-                                game_mode: "ranked".to_string(),
-                            },
-                        ];
-                    }
-
-                    lobby_ui.available_rooms = available_rooms;
-                    lobby_ui.lobby_mode = LobbyMode::JoinRoom;
-                }
             }
             LobbyEvent::RoomListReceived(rooms) => {
                 info!("📋 Received {} rooms from server", rooms.len());
-                lobby_ui.available_rooms = rooms.clone();
+                show_rooms(&mut lobby_ui, local_rooms(rooms));
                 lobby_ui.lobby_mode = LobbyMode::JoinRoom;
             }
-            LobbyEvent::EnterRoomId(room_id) => {
-                lobby_ui.room_id = room_id.clone();
-                info!("🔤 Entered room ID: {}", room_id);
-            }
+            LobbyEvent::EnterRoomCode(typed) => match parse_room_code(typed) {
+                Some(code) => {
+                    lobby_ui.room_id = listed_room_id(&lobby_ui, &code).unwrap_or_default();
+                    lobby_ui.room_code = code;
+                    info!("🔤 Entered room code: {}", lobby_ui.room_code);
+                }
+                None => {
+                    notice.msg = Some(format!("'{}' isn't a room code", typed));
+                    notice.timer = 0.0;
+                }
+            },
             LobbyEvent::LeaveRoom => {
-                #[cfg(all(target_arch = "wasm32", feature = "bevygap"))]
+                #[cfg(feature = "bevygap")]
                 {
                     if !lobby_ui.room_id.is_empty() {
                         let room_id = lobby_ui.room_id.clone();
                         let player_name = lobby_ui.player_name.clone();
-                        spawn_local(async move {
+                        let player_id = lobby_ui.player_id.clone();
+                        spawn(async move {
                             let url = format!("{}/lobby/api/rooms/{}/leave", http_base(), room_id);
-                            #[derive(Serialize)]
-                            struct LeaveReq<'a> {
-                                player_name: &'a str,
-                            }
-                            let body = serde_json::to_string(&LeaveReq {
-                                player_name: &player_name,
-                            })
-                            .unwrap();
-                            match fetch_json(&url, "POST", Some(body)).await {
-                                Ok(resp) => {
-                                    let resp: web_sys::Response = resp.dyn_into().unwrap();
-                                    if !resp.ok() {
-                                        web_sys::console::error_1(
-                                            &format!(
-                                                "Failed to leave room, status {}",
-                                                resp.status()
-                                            )
-                                            .into(),
-                                        );
-                                    }
-                                }
-                                Err(e) => web_sys::console::error_1(&e),
-                            }
+                            let request = ApiRequest::post(
+                                url,
+                                &RoomPlayerRequest {
+                                    player_name,
+                                    player_id,
+                                    password: None,
+                                },
+                            );
+                            // Failures are logged, the service drops us once the
+                            // heartbeats stop
+                            let _ = request.send().await;
                         });
                     }
                 }
-                #[cfg(all(target_arch = "wasm32", not(feature = "bevygap")))]
+                #[cfg(not(feature = "bevygap"))]
                 {
-                    // Fallback for WASM builds without bevygap - just reset UI locally
+                    // Without bevygap the room was only ever local
                     info!(
                         "🚪 Left local room: {} (bevygap disabled)",
                         lobby_ui.room_id
                     );
                 }
-                // Reset UI locally
-                lobby_ui.lobby_mode = LobbyMode::Main;
-                lobby_ui.room_id.clear();
-                lobby_ui.is_host = false;
-                lobby_ui.current_players = 1;
-                lobby_ui.is_searching = false;
+                leave_room_locally(&mut lobby_ui);
+                connection_events.write(ConnectionEvent::Reset);
                 info!("👋 Left room, returning to main lobby");
             }
             LobbyEvent::LobbyCreated(lobby_name) => {
                 info!("🏠 Lobby created: {}", lobby_name);
                 // Continue showing searching status while deploying
             }
-            LobbyEvent::LobbyDeploymentFailed(error) => {
-                error!("❌ Lobby deployment failed: {}", error);
-                lobby_ui.is_searching = false;
-            }
-            LobbyEvent::ConnectedToServer => {
-                info!("🎮 Connected to game server!");
-                lobby_ui.is_searching = false;
-                next_state.set(AppState::InGame);
-            }
         }
     }
 }
 
-// Handle bevygap connection events to transition from lobby to game
-fn handle_connection_events() {
-    // TODO: Listen for actual BevyGap connection success/failure events here
-    // For now, the real connection handling is done via LobbyEvent::ConnectedToServer
-    // in handle_lobby_events, so this function is currently a placeholder
+// "Matchmaking failed (all servers are busy, try again shortly, error TOO_MANY_DEPLOYMENTS
+// — trace 7f3a…)"
+fn matchmaking_failed(error: &ServiceError, trace_id: Option<&str>) -> String {
+    let detail = match trace_id {
+        Some(trace_id) => error.toast(trace_id),
+        None => format!("error {}", error.error),
+    };
+    match error.hint() {
+        Some(hint) => format!("Matchmaking failed ({}, {})", hint, detail),
+        None => format!("Matchmaking failed ({})", detail),
+    }
 }
 
-// Helper function to get matchmaker URL (similar to client_plugin.rs)
-fn get_matchmaker_url() -> String {
-    #[cfg(target_arch = "wasm32")]
-    {
-        use web_sys;
-        let window = web_sys::window().expect("no global `window` exists");
-        let location = window.location();
-        let protocol = if location.protocol().unwrap() == "https:" {
-            "wss"
-        } else {
-            "ws"
-        };
-        let host = location.host().unwrap();
-        format!("{}://{}/matchmaker/ws", protocol, host)
+/// Join the room picked in the browser, by its id or else the code typed. We show the room
+/// right away, the service's answer fills it in through `PENDING_ROOM_JOINED`.
+pub(super) fn join_room(lobby_ui: &mut LobbyUI) {
+    // The service takes a room's code wherever it takes its id
+    let room_id = if lobby_ui.room_id.is_empty() {
+        parse_room_code(&lobby_ui.room_code)
+    } else {
+        Some(lobby_ui.room_id.clone())
+    };
+    if let Some(room_id) = room_id {
+        lobby_ui.is_host = false;
+        lobby_ui.lobby_mode = LobbyMode::InRoom;
+        lobby_ui.current_players = lobby_ui.current_players.max(2);
+        info!("🚪 Joined room: {}", room_id);
+        #[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+        {
+            let player_name = lobby_ui.player_name.clone();
+            let player_id = lobby_ui.player_id.clone();
+            let password = lobby_ui.password();
+            spawn(async move {
+                let url = format!("{}/lobby/api/rooms/{}/join", http_base(), room_id);
+                let request = ApiRequest::post(
+                    url,
+                    &RoomPlayerRequest {
+                        player_name,
+                        player_id,
+                        password,
+                    },
+                );
+                match request.json::<ServerLobbyRoom>().await {
+                    Ok(room) => {
+                        queue_room(&room);
+                        PENDING_ROOM_JOINED.set(room.into());
+                    }
+                    Err(error) if error.status() == Some(403) => {
+                        PENDING_NOTICE.set("🔒 Wrong room password".to_string());
+                    }
+                    Err(error) => {
+                        PENDING_NOTICE.set(format!("Couldn't join the room ({})", error));
+                    }
+                }
+            });
+        }
     }
-    #[cfg(not(target_arch = "wasm32"))]
-    {
-        "ws://voidloop.quest:3000/matchmaker/ws".to_string()
+}
+
+// Back to the main lobby, forgetting the room we were in
+fn leave_room_locally(lobby_ui: &mut LobbyUI) {
+    lobby_ui.lobby_mode = LobbyMode::Main;
+    lobby_ui.room_id.clear();
+    lobby_ui.room_code.clear();
+    lobby_ui.is_host = false;
+    lobby_ui.current_players = 1;
+    lobby_ui.room_teams = 0;
+    lobby_ui.friendly_fire = false;
+    lobby_ui.room_members.clear();
+    lobby_ui.publish_results = false;
+    lobby_ui.bot_backfill = false;
+    lobby_ui.bot_style = BotStyle::default();
+    lobby_ui.last_match = None;
+    lobby_ui.room_rules = None;
+}
+
+// Without a lobby service the host is alone in the room, on the first team in team rooms
+#[cfg(not(feature = "bevygap"))]
+fn host_member(lobby_ui: &LobbyUI) -> RoomMember {
+    RoomMember {
+        name: lobby_ui.player_name.clone(),
+        player_id: lobby_ui.player_id.clone(),
+        team: (lobby_ui.room_teams > 0).then_some(0),
+        ready: true,
+        joined_at: 0,
+    }
+}
+
+// Level chosen in the lobby, falling back to the default if it isn't built in
+fn selected_level(lobby_ui: &LobbyUI) -> LevelDefinition {
+    LevelDefinition::builtin(&lobby_ui.selected_level).unwrap_or_else(|| {
+        warn!(
+            "🗺️ Unknown level '{}', using default",
+            lobby_ui.selected_level
+        );
+        LevelDefinition::default()
+    })
+}
+
+// Into the game once we are on a server, and a notice when matchmaking failed
+fn follow_connection(
+    mut changes: EventReader<ConnectionChanged>,
+    lobby_q: Query<&LobbyUI>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut notice: ResMut<UiNotice>,
+) {
+    for change in changes.read() {
+        match &change.to {
+            ConnectionState::Connected => {
+                info!("🎮 Connected to game server!");
+                next_state.set(AppState::InGame);
+            }
+            ConnectionState::Failed(error) if change.from.is_searching() => {
+                error!("❌ Matchmaking failed: {}", error);
+                // Matchmakers that validate requests send a ServiceError, older ones a code
+                let error =
+                    serde_json::from_str::<ServiceError>(error).unwrap_or_else(|_| ServiceError {
+                        error: error.clone(),
+                        message: None,
+                        retry_after_secs: None,
+                    });
+                let trace_id = lobby_q
+                    .single()
+                    .ok()
+                    .and_then(|ui| ui.matchmaker_trace.clone());
+                notice.msg = Some(matchmaking_failed(&error, trace_id.as_deref()));
+                notice.timer = 0.0;
+            }
+            _ => {}
+        }
     }
 }
 
@@ -1602,11 +2476,14 @@ struct PlayerCountText;
 struct LobbyContainer;
 
 #[derive(Component)]
-struct LobbyUIElements;
+pub(super) struct LobbyUIElements;
 
 #[derive(Component)]
 struct ModeButton(String);
 
+#[derive(Component)]
+struct LevelButton(String);
+
 #[derive(Component)]
 struct QuickMatchButton;
 
@@ -1619,17 +2496,20 @@ struct JoinRoomButton;
 #[derive(Component)]
 struct LocalPlayButton;
 
+#[derive(Component)]
+struct LocalPlayersButton;
+
 #[derive(Component)]
 struct ConfirmCreateButton;
 
 #[derive(Component)]
-struct ConfirmJoinButton;
+pub(super) struct ConfirmJoinButton;
 
 #[derive(Component)]
-struct RoomIdButton(String);
+pub(super) struct RoomIdButton(pub(super) String);
 
 #[derive(Component)]
-struct StartGameButton;
+pub(super) struct StartGameButton;
 
 #[derive(Component)]
 struct LeaveRoomButton;
@@ -1637,6 +2517,22 @@ struct LeaveRoomButton;
 #[derive(Component)]
 struct BackButton;
 
+#[derive(Component)]
+struct CampaignButton;
+
+#[derive(Component)]
+struct CampaignLevelButton(usize);
+
+#[derive(Component)]
+pub(super) struct RoomCodeInput;
+
+#[derive(Component)]
+pub(super) struct RoomPasswordInput;
+
+// Opens a page that only has a back button to leave it, e.g. server status or settings
+#[derive(Component)]
+struct PageButton(LobbyMode);
+
 // ==== PLACEHOLDER FOR FUTURE NETWORKING FEATURES ====
 // TODO: Add room message handling when networking integration is complete
 // ==== END PLACEHOLDER ====
@@ -0,0 +1,171 @@
+// ⏱️ Backing out of matchmaking: while a server is being found a banner counts the seconds
+// with a CANCEL button, and after `matchmaking.timeout_secs` in the settings we give up on
+// our own. Either way the matchmaker connection is dropped, which tells the matchmaker to
+// tear down the lobby it deployed for us, see "Cancelled requests" in docs/matchmaker.md.
+use bevy::prelude::*;
+
+use super::lobby::UiNotice;
+use crate::connection::{ConnectionEvent, ConnectionState};
+use crate::settings::ClientSettings;
+
+#[cfg(feature = "bevygap")]
+use {
+    crate::connection::ConnectionChanged,
+    bevygap_client_plugin::prelude::BevygapClientState,
+    lightyear::prelude::{Client, Disconnect},
+};
+
+#[derive(Component)]
+struct MatchmakingBanner;
+
+#[derive(Component)]
+struct MatchmakingText;
+
+#[derive(Component)]
+pub(super) struct CancelMatchmakingButton;
+
+/// Show the banner while searching, and give up once the timeout passes
+pub(super) fn update_matchmaking_banner(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<ClientSettings>,
+    connection: Res<ConnectionState>,
+    banners: Query<Entity, With<MatchmakingBanner>>,
+    mut texts: Query<&mut Text, With<MatchmakingText>>,
+    mut connection_events: EventWriter<ConnectionEvent>,
+    mut waited: Local<f32>,
+) {
+    if !connection.is_searching() {
+        *waited = 0.0;
+        for banner in banners.iter() {
+            commands.entity(banner).despawn();
+        }
+        return;
+    }
+
+    *waited += time.delta_secs();
+    let timeout = settings.matchmaking.timeout_secs;
+    if timeout > 0 && *waited >= timeout as f32 {
+        warn!("⌛ No server after {}s, giving up", timeout);
+        *waited = 0.0;
+        connection_events.write(ConnectionEvent::Failed("MATCHMAKING_TIMEOUT".to_string()));
+        return;
+    }
+
+    let label = format!("🔍 Finding a server... {}s", *waited as u32);
+    if let Ok(mut text) = texts.single_mut() {
+        if **text != label {
+            **text = label;
+        }
+        return;
+    }
+    if !banners.is_empty() {
+        return;
+    }
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(16.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            MatchmakingBanner,
+        ))
+        .with_children(|row| {
+            row.spawn((
+                Node {
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(12.0),
+                    padding: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.05, 0.05, 0.05, 0.85)),
+            ))
+            .with_children(|panel| {
+                panel.spawn((
+                    Text::new(label),
+                    TextFont {
+                        font_size: 16.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.9, 0.9, 0.9)),
+                    MatchmakingText,
+                ));
+                panel
+                    .spawn((
+                        Button,
+                        Node {
+                            height: Val::Px(32.0),
+                            padding: UiRect::horizontal(Val::Px(12.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        BackgroundColor(Color::srgb(0.6, 0.2, 0.2)),
+                        CancelMatchmakingButton,
+                    ))
+                    .with_children(|btn| {
+                        btn.spawn((
+                            Text::new("CANCEL"),
+                            TextFont {
+                                font_size: 14.0,
+                                ..default()
+                            },
+                            TextColor(Color::WHITE),
+                        ));
+                    });
+            });
+        });
+}
+
+pub(super) fn handle_cancel_matchmaking(
+    buttons: Query<&Interaction, (Changed<Interaction>, With<CancelMatchmakingButton>)>,
+    mut connection_events: EventWriter<ConnectionEvent>,
+    mut notice: ResMut<UiNotice>,
+) {
+    if buttons
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        info!("🛑 Matchmaking cancelled");
+        connection_events.write(ConnectionEvent::Reset);
+        notice.msg = Some("Matchmaking cancelled".to_string());
+        notice.timer = 0.0;
+    }
+}
+
+/// Stop the matchmaker request, or the connection to a server it already handed out
+#[cfg(feature = "bevygap")]
+pub(super) fn abort_matchmaking(
+    mut commands: Commands,
+    mut changes: EventReader<ConnectionChanged>,
+    mut bevygap_state: ResMut<NextState<BevygapClientState>>,
+    clients: Query<Entity, With<Client>>,
+) {
+    // Cancelled or timed out while searching. The matchmaker's own errors end the request
+    // already, aborting again does no harm.
+    let aborted = changes
+        .read()
+        .filter(|change| change.from.is_searching() && !change.to.is_searching())
+        .filter(|change| change.to != ConnectionState::Connected)
+        .count()
+        > 0;
+    if !aborted {
+        return;
+    }
+    bevygap_state.set(BevygapClientState::Dormant);
+    for client in clients.iter() {
+        commands.trigger_targets(Disconnect, client);
+    }
+}
+
+pub(super) fn despawn_matchmaking_banner(
+    mut commands: Commands,
+    banners: Query<Entity, With<MatchmakingBanner>>,
+) {
+    for banner in banners.iter() {
+        commands.entity(banner).despawn();
+    }
+}
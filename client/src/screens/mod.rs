@@ -1,3 +1,17 @@
+mod browser;
+mod deep_link;
+mod friends;
+mod leaderboard;
 pub mod lobby;
+mod matchmaking;
+mod party;
+mod room_bots;
+mod room_chat;
+mod room_results;
+mod room_roster;
+mod room_rules;
+mod room_teams;
+mod settings_page;
+mod status;
 
 pub use lobby::*;
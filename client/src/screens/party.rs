@@ -0,0 +1,497 @@
+// 👪 Parties: a panel on the main lobby screen to create a party, join one by its code or
+// an invite, and invite others by name. The leader's QUICK MATCH queues the whole party,
+// the other members' clients follow on their next heartbeat, and the matchmaker keeps
+// everyone together. The lobby service keeps the parties, see `shared::party`.
+use bevy::prelude::*;
+
+use super::lobby::{LobbyUI, LobbyUIElements, UiNotice, MAX_INPUT_LEN};
+use crate::text_input::{spawn_text_input, TextInput, TextSubmitted};
+
+#[cfg(target_arch = "wasm32")]
+use {
+    super::lobby::LobbyEvent,
+    crate::connection::ConnectionState,
+    crate::http::{http_base, ApiRequest},
+    serde::Serialize,
+    shared::{parse_room_code, Party, PartyInvite, PartyInviteRequest, PartyRequest},
+    std::cell::RefCell,
+    wasm_bindgen_futures::spawn_local,
+};
+
+// What a party call brought back
+#[cfg(target_arch = "wasm32")]
+enum PartyUpdate {
+    // We just created or joined it, its last queue is not ours to follow
+    Joined(Party),
+    Changed(Party),
+    // We left, or the service no longer knows the party
+    Gone,
+}
+
+#[cfg(target_arch = "wasm32")]
+thread_local! {
+    static PENDING_PARTY: RefCell<Option<PartyUpdate>> = const { RefCell::new(None) };
+    static PENDING_PARTY_INVITES: RefCell<Option<Vec<PartyInvite>>> = const { RefCell::new(None) };
+    static PENDING_PARTY_NOTICE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+// Seconds between party heartbeats, also how long members take to follow the leader
+#[cfg(target_arch = "wasm32")]
+const PARTY_HEARTBEAT_SECS: f32 = 3.0;
+// Seconds between looking for invites while not in a party
+#[cfg(target_arch = "wasm32")]
+const INVITE_POLL_SECS: f32 = 10.0;
+
+#[derive(Component, Clone, Debug, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+pub(super) enum PartyButton {
+    Create,
+    Join,
+    Invite,
+    Leave,
+    /// Join the party of an invite, by its code
+    Accept(String),
+}
+
+#[derive(Component)]
+pub(super) struct PartyCodeInput;
+
+#[derive(Component)]
+pub(super) struct PartyInviteInput;
+
+/// Whether QUICK MATCH is ours to press: not in a party, or leading it
+pub(super) fn can_queue(ui: &LobbyUI) -> bool {
+    ui.party
+        .as_ref()
+        .is_none_or(|party| party.is_leader(&ui.player_name))
+}
+
+pub(super) fn sync_party_inputs(
+    codes: Query<Ref<TextInput>, With<PartyCodeInput>>,
+    invitees: Query<Ref<TextInput>, With<PartyInviteInput>>,
+    mut submitted: EventReader<TextSubmitted>,
+    mut lobby_q: Query<&mut LobbyUI>,
+    mut notice: ResMut<UiNotice>,
+) {
+    let Ok(mut ui) = lobby_q.single_mut() else {
+        return;
+    };
+    for input in codes.iter().filter(|input| input.is_changed()) {
+        ui.bypass_change_detection().party_code = input.value.trim().to_string();
+    }
+    for input in invitees.iter().filter(|input| input.is_changed()) {
+        ui.bypass_change_detection().party_invitee = input.value.trim().to_string();
+    }
+    // Enter joins or invites, like the button next to the field
+    for event in submitted.read() {
+        if codes.contains(event.entity) {
+            press(PartyButton::Join, &ui, &mut notice);
+        } else if invitees.contains(event.entity) {
+            press(PartyButton::Invite, &ui, &mut notice);
+        }
+    }
+}
+
+pub(super) fn handle_party_buttons(
+    buttons: Query<(&Interaction, &PartyButton), Changed<Interaction>>,
+    lobby_q: Query<&LobbyUI>,
+    mut notice: ResMut<UiNotice>,
+) {
+    let Ok(ui) = lobby_q.single() else {
+        return;
+    };
+    for (interaction, button) in buttons.iter() {
+        if *interaction == Interaction::Pressed {
+            press(button.clone(), ui, &mut notice);
+        }
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
+fn press(button: PartyButton, ui: &LobbyUI, notice: &mut UiNotice) {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        notice.msg = Some("Parties need the lobby service".to_string());
+    }
+    #[cfg(target_arch = "wasm32")]
+    match button {
+        PartyButton::Create => {
+            info!("👪 Creating a party");
+            party_call("/lobby/api/parties", &party_request(ui, None), true);
+        }
+        PartyButton::Join | PartyButton::Accept(_) => {
+            let typed = match &button {
+                PartyButton::Accept(code) => code.clone(),
+                _ => ui.party_code.clone(),
+            };
+            let Some(code) = parse_room_code(&typed) else {
+                notice.msg = Some("Party codes are 6 letters and digits".to_string());
+                return;
+            };
+            info!("👪 Joining party {}", code);
+            let body = party_request(ui, Some(code));
+            party_call("/lobby/api/parties/join", &body, true);
+        }
+        PartyButton::Invite => {
+            let Some(party) = &ui.party else {
+                return;
+            };
+            if ui.party_invitee.is_empty() {
+                notice.msg = Some("Type the name of the player to invite".to_string());
+                return;
+            }
+            info!("👪 Inviting {}", ui.party_invitee);
+            let body = PartyInviteRequest {
+                player_name: ui.player_name.clone(),
+                player_id: ui.player_id.clone(),
+                invitee: ui.party_invitee.clone(),
+            };
+            party_call(
+                &format!("/lobby/api/parties/{}/invite", party.id),
+                &body,
+                false,
+            );
+        }
+        PartyButton::Leave => {
+            let Some(party) = &ui.party else {
+                return;
+            };
+            info!("👪 Leaving party {}", party.code);
+            leave_party(&party.id, party_request(ui, None));
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn party_request(ui: &LobbyUI, code: Option<String>) -> PartyRequest {
+    PartyRequest {
+        player_name: ui.player_name.clone(),
+        player_id: ui.player_id.clone(),
+        code,
+    }
+}
+
+// POST a party call that answers with the party
+#[cfg(target_arch = "wasm32")]
+fn party_call(path: &str, body: &impl Serialize, joined: bool) {
+    let request = ApiRequest::post(format!("{}{}", http_base(), path), body);
+    spawn_local(async move {
+        match request.json::<Party>().await {
+            Ok(party) => {
+                let update = if joined {
+                    PartyUpdate::Joined(party)
+                } else {
+                    PartyUpdate::Changed(party)
+                };
+                PENDING_PARTY.with(|cell| cell.replace(Some(update)));
+            }
+            Err(error) => {
+                PENDING_PARTY_NOTICE.with(|cell| cell.replace(Some(format!("Party: {}", error))));
+            }
+        }
+    });
+}
+
+#[cfg(target_arch = "wasm32")]
+fn leave_party(party_id: &str, body: PartyRequest) {
+    let url = format!("{}/lobby/api/parties/{}/leave", http_base(), party_id);
+    // Out of the party right away, the service drops us on its own if this call fails
+    PENDING_PARTY.with(|cell| cell.replace(Some(PartyUpdate::Gone)));
+    let request = ApiRequest::post(url, &body);
+    spawn_local(async move {
+        let _ = request.send().await;
+    });
+}
+
+/// Region to queue in: the leader's for the other members, so the whole party waits in
+/// one queue, otherwise the one with the lowest ping
+#[cfg(feature = "bevygap")]
+pub(super) fn queue_region(ui: &LobbyUI) -> Option<String> {
+    let leaders = ui
+        .party
+        .as_ref()
+        .and_then(|party| party.queue.as_ref())
+        .filter(|_| !can_queue(ui));
+    match leaders {
+        Some(queue) => queue.region.clone(),
+        None => shared::best_region(&ui.region_pings).map(str::to_string),
+    }
+}
+
+/// The leader queued: tell the service, so the members follow into matchmaking
+#[cfg(all(target_arch = "wasm32", feature = "bevygap"))]
+pub(super) fn announce_party_queue(ui: &LobbyUI) {
+    let Some(party) = ui.party.as_ref().filter(|p| p.is_leader(&ui.player_name)) else {
+        return;
+    };
+    let body = shared::PartyQueueRequest {
+        player_name: ui.player_name.clone(),
+        player_id: ui.player_id.clone(),
+        game_mode: ui.selected_mode.clone(),
+        level: ui.selected_level.clone(),
+        region: queue_region(ui),
+    };
+    party_call(
+        &format!("/lobby/api/parties/{}/queue", party.id),
+        &body,
+        false,
+    );
+}
+
+/// Heartbeat while in a party, which also brings its members and queue, and look for
+/// invites otherwise
+#[cfg(target_arch = "wasm32")]
+pub(super) fn refresh_party(
+    time: Res<Time>,
+    lobby_q: Query<&LobbyUI>,
+    mut heartbeat: Local<Option<Timer>>,
+    mut invites: Local<Option<Timer>>,
+) {
+    let Ok(ui) = lobby_q.single() else {
+        return;
+    };
+    let Some(party) = &ui.party else {
+        *heartbeat = None;
+        // Right away when the lobby opens, then every few seconds
+        let timer = invites.get_or_insert_with(|| {
+            let mut timer = Timer::from_seconds(INVITE_POLL_SECS, TimerMode::Repeating);
+            timer.set_elapsed(timer.duration());
+            timer
+        });
+        if timer.tick(time.delta()).just_finished() {
+            fetch_invites(&ui.player_name);
+        }
+        return;
+    };
+    *invites = None;
+    let timer = heartbeat
+        .get_or_insert_with(|| Timer::from_seconds(PARTY_HEARTBEAT_SECS, TimerMode::Repeating));
+    if !timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let url = format!("{}/lobby/api/parties/{}/heartbeat", http_base(), party.id);
+    let request = ApiRequest::post(url, &party_request(ui, None));
+    spawn_local(async move {
+        match request.json::<Party>().await {
+            Ok(party) => {
+                PENDING_PARTY.with(|cell| cell.replace(Some(PartyUpdate::Changed(party))));
+            }
+            Err(error) if error.status() == Some(404) => {
+                PENDING_PARTY.with(|cell| cell.replace(Some(PartyUpdate::Gone)));
+                PENDING_PARTY_NOTICE
+                    .with(|cell| cell.replace(Some("The party no longer exists".to_string())));
+            }
+            Err(_) => {}
+        }
+    });
+}
+
+#[cfg(target_arch = "wasm32")]
+fn fetch_invites(player_name: &str) {
+    let url = format!(
+        "{}/lobby/api/parties/invites?player_name={}",
+        http_base(),
+        String::from(web_sys::js_sys::encode_uri_component(player_name))
+    );
+    spawn_local(async move {
+        // Older services have no parties
+        if let Ok(invites) = ApiRequest::get(url).json::<Vec<PartyInvite>>().await {
+            PENDING_PARTY_INVITES.with(|cell| cell.replace(Some(invites)));
+        }
+    });
+}
+
+// Only touch the lobby state when something changed, every change redraws the lobby. A
+// member whose leader queued again starts matchmaking for the same mode and level.
+#[cfg(target_arch = "wasm32")]
+pub(super) fn pump_party(
+    mut lobby_q: Query<&mut LobbyUI>,
+    connection: Res<ConnectionState>,
+    mut notice: ResMut<UiNotice>,
+    mut lobby_events: EventWriter<LobbyEvent>,
+) {
+    if let Some(msg) = PENDING_PARTY_NOTICE.with(|cell| cell.borrow_mut().take()) {
+        notice.msg = Some(msg);
+    }
+    let Ok(mut ui) = lobby_q.single_mut() else {
+        return;
+    };
+    if let Some(invites) = PENDING_PARTY_INVITES.with(|cell| cell.borrow_mut().take()) {
+        if ui.party_invites != invites {
+            ui.party_invites = invites;
+        }
+    }
+    let Some(update) = PENDING_PARTY.with(|cell| cell.borrow_mut().take()) else {
+        return;
+    };
+    let party = match update {
+        PartyUpdate::Gone => {
+            if ui.party.is_some() {
+                ui.party = None;
+            }
+            return;
+        }
+        PartyUpdate::Joined(party) => {
+            info!("👪 In party {} led by {}", party.code, party.leader);
+            ui.party_invites.clear();
+            ui.party_queued_at = party.queue.as_ref().map_or(0, |queue| queue.queued_at);
+            party
+        }
+        PartyUpdate::Changed(party) => party,
+    };
+    let queue = party
+        .queue
+        .clone()
+        .filter(|queue| queue.queued_at > ui.party_queued_at);
+    if ui.party.as_ref() != Some(&party) {
+        ui.party = Some(party);
+    }
+    let Some(queue) = queue else {
+        return;
+    };
+    ui.party_queued_at = queue.queued_at;
+    if can_queue(&ui) || connection.is_searching() {
+        return;
+    }
+    info!(
+        "👪 Following the party leader into {} on {}",
+        queue.game_mode, queue.level
+    );
+    ui.selected_mode = queue.game_mode;
+    ui.selected_level = queue.level;
+    lobby_events.write(LobbyEvent::StartMatchmaking);
+}
+
+/// The party panel of the main lobby screen: the members and invite field, or ways into
+/// a party
+pub(super) fn spawn_party_panel(commands: &mut Commands, lobby_ui: &LobbyUI) -> Entity {
+    let panel = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(8.0)),
+                margin: UiRect::all(Val::Px(6.0)),
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.4)),
+            LobbyUIElements,
+        ))
+        .id();
+    let mut children = Vec::new();
+    match &lobby_ui.party {
+        Some(party) => {
+            children.push(label(commands, format!("👪 Party {}", party.code), 16.0));
+            for member in &party.members {
+                let mut name = member.clone();
+                if party.is_leader(member) {
+                    name = format!("👑 {}", name);
+                }
+                if *member == lobby_ui.player_name {
+                    name.push_str(" (you)");
+                }
+                children.push(label(commands, name, 14.0));
+            }
+            for invited in &party.invited {
+                children.push(label(commands, format!("✉️ {} (invited)", invited), 13.0));
+            }
+            if party.is_leader(&lobby_ui.player_name) {
+                if !party.is_full() {
+                    let input = spawn_text_input(
+                        commands,
+                        TextInput::new(
+                            lobby_ui.party_invitee.clone(),
+                            "Player name",
+                            MAX_INPUT_LEN,
+                        ),
+                        160.0,
+                    );
+                    commands.entity(input).insert(PartyInviteInput);
+                    let invite = button(commands, "INVITE", PartyButton::Invite);
+                    children.push(row(commands, &[input, invite]));
+                }
+                children.push(label(commands, "QUICK MATCH queues the whole party", 13.0));
+            } else {
+                children.push(label(
+                    commands,
+                    format!("{} queues the party", party.leader),
+                    13.0,
+                ));
+            }
+            children.push(button(commands, "LEAVE PARTY", PartyButton::Leave));
+        }
+        None => {
+            for invite in &lobby_ui.party_invites {
+                let text = label(commands, format!("✉️ {} invited you", invite.leader), 14.0);
+                let accept = button(commands, "JOIN", PartyButton::Accept(invite.code.clone()));
+                children.push(row(commands, &[text, accept]));
+            }
+            let input = spawn_text_input(
+                commands,
+                TextInput::new(lobby_ui.party_code.clone(), "Party code", MAX_INPUT_LEN),
+                120.0,
+            );
+            commands.entity(input).insert(PartyCodeInput);
+            let join = button(commands, "JOIN", PartyButton::Join);
+            let create = button(commands, "CREATE PARTY", PartyButton::Create);
+            children.push(row(commands, &[input, join, create]));
+        }
+    }
+    commands.entity(panel).add_children(&children);
+    panel
+}
+
+// Inside the panel, so without `LobbyUIElements`: the panel takes it along when it goes
+fn row(commands: &mut Commands, items: &[Entity]) -> Entity {
+    let row = commands
+        .spawn(Node {
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(8.0),
+            ..default()
+        })
+        .id();
+    commands.entity(row).add_children(items);
+    row
+}
+
+fn label(commands: &mut Commands, text: impl Into<String>, size: f32) -> Entity {
+    commands
+        .spawn((
+            Text::new(text),
+            TextFont {
+                font_size: size,
+                ..default()
+            },
+            TextColor(Color::srgb(0.85, 0.85, 0.85)),
+        ))
+        .id()
+}
+
+fn button(commands: &mut Commands, text: &str, action: PartyButton) -> Entity {
+    commands
+        .spawn((
+            Button,
+            Node {
+                height: Val::Px(32.0),
+                padding: UiRect::horizontal(Val::Px(12.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.35, 0.3, 0.5)),
+            action,
+        ))
+        .with_children(|btn| {
+            btn.spawn((
+                Text::new(text),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            ));
+        })
+        .id()
+}
@@ -0,0 +1,120 @@
+// 🤖 Bot backfill: a host can let the game server fill the empty slots of the room's match
+// with bots, and pick how they play. The lobby service keeps the choice with the room, and
+// everyone in it queues with `bot_fill` and `bot_style`, which the matchmaker hands the
+// server as `VOIDLOOP_BOT_FILL` and `VOIDLOOP_BOT_STYLE`.
+use bevy::prelude::*;
+
+use super::lobby::{LobbyUI, LobbyUIElements};
+use shared::BotStyle;
+
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+use {crate::http::Pending, shared::ServerLobbyRoom};
+
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+static PENDING_ROOM_BOTS: Pending<(bool, BotStyle)> = Pending::new();
+
+/// Create Room: no bots, or bots of each style filling the empty slots
+#[derive(Component)]
+pub(super) struct BotBackfillButton;
+
+/// Pick up the room's bot backfill from any lobby answer that carries the room
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+pub(super) fn queue_room_bots(room: &ServerLobbyRoom) {
+    PENDING_ROOM_BOTS.set((room.bot_backfill, room.bot_style));
+}
+
+// Only touch the lobby state when something changed, every change redraws the room
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+pub(super) fn pump_room_bots(mut lobby_q: Query<&mut LobbyUI>) {
+    let Some((bot_backfill, bot_style)) = PENDING_ROOM_BOTS.take() else {
+        return;
+    };
+    let Ok(mut ui) = lobby_q.single_mut() else {
+        return;
+    };
+    if !ui.room_id.is_empty() && (ui.bot_backfill, ui.bot_style) != (bot_backfill, bot_style) {
+        ui.bot_backfill = bot_backfill;
+        ui.bot_style = bot_style;
+    }
+}
+
+/// Have the matchmaker start the room's server with bots, nothing outside a room that
+/// wants them
+#[cfg(feature = "bevygap")]
+pub(super) fn append_bot_params(
+    query: &mut url::form_urlencoded::Serializer<'_, String>,
+    ui: &LobbyUI,
+    max_players: u32,
+) {
+    if ui.bot_backfill && !ui.room_id.is_empty() {
+        query
+            .append_pair("bot_fill", &max_players.to_string())
+            .append_pair("bot_style", ui.bot_style.id());
+    }
+}
+
+pub(super) fn handle_bot_backfill(
+    buttons: Query<&Interaction, (Changed<Interaction>, With<BotBackfillButton>)>,
+    mut lobby_q: Query<&mut LobbyUI>,
+) {
+    if !buttons
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        return;
+    }
+    let Ok(mut ui) = lobby_q.single_mut() else {
+        return;
+    };
+    // No bots, then every style from the mildest up, then no bots again
+    match (ui.bot_backfill, ui.bot_style.next()) {
+        (false, _) => {
+            ui.bot_backfill = true;
+            ui.bot_style = BotStyle::ALL[0];
+        }
+        (true, next) if next == BotStyle::ALL[0] => {
+            ui.bot_backfill = false;
+            ui.bot_style = BotStyle::default();
+        }
+        (true, next) => ui.bot_style = next,
+    }
+}
+
+pub(super) fn spawn_bot_backfill_button(commands: &mut Commands, lobby_ui: &LobbyUI) -> Entity {
+    let label = match (lobby_ui.bot_backfill, lobby_ui.bot_style) {
+        (false, _) => "🤖 No bots",
+        (true, BotStyle::Passive) => "🤖 Passive bots fill empty slots",
+        (true, BotStyle::Normal) => "🤖 Bots fill empty slots",
+        (true, BotStyle::Aggressive) => "🤖 Aggressive bots fill empty slots",
+    };
+    commands
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(260.0),
+                height: Val::Px(36.0),
+                margin: UiRect::all(Val::Px(6.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(if lobby_ui.bot_backfill {
+                Color::srgb(0.25, 0.5, 0.35)
+            } else {
+                Color::srgb(0.3, 0.3, 0.3)
+            }),
+            BotBackfillButton,
+            LobbyUIElements,
+        ))
+        .with_children(|btn| {
+            btn.spawn((
+                Text::new(label),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            ));
+        })
+        .id()
+}
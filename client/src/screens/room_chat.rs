@@ -0,0 +1,319 @@
+// 💬 Room chat: players in a room talk before the match, so nobody has to guess when the
+// host will start. The lobby service keeps the last `CHAT_HISTORY_LEN` messages of every
+// room. We long-poll it for new ones while in a room and post ours with
+// `POST /rooms/{id}/chat`, see "Room chat" in docs/lobby-service.md.
+use bevy::prelude::*;
+
+use shared::{ChatMessage, CHAT_HISTORY_LEN, MAX_CHAT_MESSAGE_LEN};
+
+use super::lobby::{LobbyUI, LobbyUIElements, UiNotice};
+use crate::text_input::{spawn_text_input, TextInput, TextSubmitted};
+
+#[cfg(target_arch = "wasm32")]
+use {
+    crate::http::{http_base, ApiRequest},
+    shared::ChatRequest,
+    std::cell::{Cell, RefCell},
+    wasm_bindgen_futures::spawn_local,
+};
+
+// Seconds between the end of one chat poll and the next, the service holds each poll open
+// until something is said
+#[cfg(target_arch = "wasm32")]
+const CHAT_POLL_GAP_SECS: f32 = 1.0;
+
+#[cfg(target_arch = "wasm32")]
+thread_local! {
+    // Messages for a room, from a poll or our own post
+    static PENDING_CHAT: RefCell<Vec<(String, Vec<ChatMessage>)>> = const { RefCell::new(Vec::new()) };
+    static PENDING_CHAT_NOTICE: RefCell<Option<String>> = const { RefCell::new(None) };
+    static CHAT_POLLING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Chat of the room we are in
+#[derive(Resource, Default)]
+pub struct RoomChat {
+    /// Room the messages belong to
+    pub room_id: String,
+    /// The last `CHAT_HISTORY_LEN`, oldest first
+    pub messages: Vec<ChatMessage>,
+    /// Message being typed
+    pub draft: String,
+}
+
+impl RoomChat {
+    /// Add messages for `room_id`, skipping ones we already have. Messages for another
+    /// room start the chat over.
+    pub fn add(&mut self, room_id: &str, messages: Vec<ChatMessage>) {
+        if self.room_id != room_id {
+            self.room_id = room_id.to_string();
+            self.messages.clear();
+        }
+        let last_seq = self.last_seq();
+        self.messages.extend(
+            messages
+                .into_iter()
+                .filter(|message| message.seq > last_seq),
+        );
+        let extra = self.messages.len().saturating_sub(CHAT_HISTORY_LEN);
+        self.messages.drain(..extra);
+    }
+
+    pub fn last_seq(&self) -> u64 {
+        self.messages.last().map_or(0, |message| message.seq)
+    }
+}
+
+#[derive(Component)]
+pub(super) struct ChatInput;
+
+/// SEND next to the chat field
+#[derive(Component)]
+pub(super) struct ChatButton;
+
+// The messages, updated in place so a new one doesn't redraw the room
+#[derive(Component)]
+pub(super) struct ChatLogText;
+
+pub(super) fn sync_chat_input(
+    mut inputs: Query<&mut TextInput, With<ChatInput>>,
+    mut submitted: EventReader<TextSubmitted>,
+    buttons: Query<&Interaction, (Changed<Interaction>, With<ChatButton>)>,
+    mut chat: ResMut<RoomChat>,
+    #[cfg(target_arch = "wasm32")] lobby_q: Query<&LobbyUI>,
+    #[cfg(not(target_arch = "wasm32"))] mut notice: ResMut<UiNotice>,
+) {
+    for input in inputs.iter().filter(|input| input.is_changed()) {
+        chat.bypass_change_detection().draft = input.value.clone();
+    }
+    // Enter sends, like SEND
+    let entered = submitted
+        .read()
+        .filter(|event| inputs.contains(event.entity))
+        .count()
+        > 0;
+    let pressed = buttons
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed);
+    if !(entered || pressed) || chat.draft.trim().is_empty() {
+        return;
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let Ok(ui) = lobby_q.single() else {
+            return;
+        };
+        send_chat(ui, chat.draft.clone());
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        notice.msg = Some("Chat needs the lobby service".to_string());
+        notice.timer = 0.0;
+    }
+    chat.bypass_change_detection().draft.clear();
+    for mut input in inputs.iter_mut() {
+        input.value.clear();
+    }
+}
+
+/// Show the messages as they come in
+pub(super) fn update_chat_log(
+    chat: Res<RoomChat>,
+    lobby_q: Query<&LobbyUI>,
+    mut logs: Query<(Ref<ChatLogText>, &mut Text)>,
+) {
+    let Ok(ui) = lobby_q.single() else {
+        return;
+    };
+    for (log, mut text) in logs.iter_mut() {
+        if chat.is_changed() || log.is_added() {
+            text.0 = chat_lines(&chat, ui);
+        }
+    }
+}
+
+fn chat_lines(chat: &RoomChat, ui: &LobbyUI) -> String {
+    if chat.room_id != ui.room_id || chat.messages.is_empty() {
+        return if cfg!(target_arch = "wasm32") {
+            "No messages yet, say hi".to_string()
+        } else {
+            "Chat needs the lobby service, play the web version".to_string()
+        };
+    }
+    chat.messages
+        .iter()
+        .map(|message| format!("{}: {}", message.player_name, message.text))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The messages with a field and SEND under them
+pub(super) fn spawn_room_chat(commands: &mut Commands, chat: &RoomChat) -> Entity {
+    let input = spawn_text_input(
+        commands,
+        TextInput::new(chat.draft.clone(), "Say something", MAX_CHAT_MESSAGE_LEN),
+        300.0,
+    );
+    commands.entity(input).insert(ChatInput);
+    let send = commands
+        .spawn((
+            Button,
+            Node {
+                height: Val::Px(36.0),
+                padding: UiRect::horizontal(Val::Px(12.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.3, 0.35, 0.5)),
+            ChatButton,
+        ))
+        .with_children(|btn| {
+            btn.spawn((
+                Text::new("SEND"),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+        })
+        .id();
+    let input_row = commands
+        .spawn(Node {
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(8.0),
+            ..default()
+        })
+        .add_children(&[input, send])
+        .id();
+    let log = commands
+        .spawn((
+            Text::new(""),
+            TextFont {
+                font_size: 13.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.85, 0.85, 0.85)),
+            ChatLogText,
+        ))
+        .id();
+    // Newest at the bottom, the oldest scroll out of the top
+    let log_box = commands
+        .spawn(Node {
+            width: Val::Px(400.0),
+            height: Val::Px(140.0),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::End,
+            overflow: Overflow::clip(),
+            ..default()
+        })
+        .add_child(log)
+        .id();
+    commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(8.0)),
+                margin: UiRect::all(Val::Px(6.0)),
+                row_gap: Val::Px(6.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.4)),
+            LobbyUIElements,
+        ))
+        .add_children(&[log_box, input_row])
+        .id()
+}
+
+/// Keep one long poll open while we are in a room
+#[cfg(target_arch = "wasm32")]
+pub(super) fn poll_room_chat(
+    time: Res<Time>,
+    lobby_q: Query<&LobbyUI>,
+    chat: Res<RoomChat>,
+    mut gap: Local<Option<Timer>>,
+) {
+    let Ok(ui) = lobby_q.single() else {
+        return;
+    };
+    if ui.room_id.is_empty() || CHAT_POLLING.get() {
+        return;
+    }
+    let gap = gap.get_or_insert_with(|| Timer::from_seconds(CHAT_POLL_GAP_SECS, TimerMode::Once));
+    if !gap.tick(time.delta()).finished() {
+        return;
+    }
+    gap.reset();
+
+    let room_id = ui.room_id.clone();
+    let after = if chat.room_id == room_id {
+        chat.last_seq()
+    } else {
+        0
+    };
+    CHAT_POLLING.set(true);
+    spawn_local(async move {
+        let url = format!(
+            "{}/lobby/api/rooms/{}/chat?after={}",
+            http_base(),
+            room_id,
+            after
+        );
+        // Older services have no chat, the gap keeps us from hammering them. The next poll
+        // comes soon enough, so no retries.
+        let request = ApiRequest::get(url).attempts(1);
+        if let Ok(messages) = request.json::<Vec<ChatMessage>>().await {
+            PENDING_CHAT.with(|cell| cell.borrow_mut().push((room_id, messages)));
+        }
+        CHAT_POLLING.set(false);
+    });
+}
+
+#[cfg(target_arch = "wasm32")]
+fn send_chat(ui: &LobbyUI, text: String) {
+    let room_id = ui.room_id.clone();
+    let url = format!("{}/lobby/api/rooms/{}/chat", http_base(), room_id);
+    let request = ApiRequest::post(
+        url,
+        &ChatRequest {
+            player_name: ui.player_name.clone(),
+            player_id: ui.player_id.clone(),
+            text,
+        },
+    );
+    spawn_local(async move {
+        match request.json::<ChatMessage>().await {
+            Ok(message) => {
+                PENDING_CHAT.with(|cell| cell.borrow_mut().push((room_id, vec![message])));
+            }
+            Err(error) => {
+                PENDING_CHAT_NOTICE.with(|cell| {
+                    *cell.borrow_mut() = Some(format!("Message not sent ({})", error))
+                });
+            }
+        }
+    });
+}
+
+// Only messages for the room we are in, answers for a room we left are dropped
+#[cfg(target_arch = "wasm32")]
+pub(super) fn pump_room_chat(
+    lobby_q: Query<&LobbyUI>,
+    mut chat: ResMut<RoomChat>,
+    mut notice: ResMut<UiNotice>,
+) {
+    if let Some(msg) = PENDING_CHAT_NOTICE.with(|cell| cell.borrow_mut().take()) {
+        notice.msg = Some(msg);
+        notice.timer = 0.0;
+    }
+    let pending = PENDING_CHAT.with(|cell| std::mem::take(&mut *cell.borrow_mut()));
+    let Ok(ui) = lobby_q.single() else {
+        return;
+    };
+    for (room_id, messages) in pending {
+        if room_id == ui.room_id && !messages.is_empty() {
+            chat.add(&room_id, messages);
+        }
+    }
+}
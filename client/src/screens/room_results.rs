@@ -0,0 +1,148 @@
+// 🏁 Published results: a host can keep the room open after its match, and the lobby
+// service then keeps the last match's standings with the room for a day. Anyone who comes
+// back to the room sees them above the team columns.
+use bevy::prelude::*;
+
+use shared::RoomMatchResult;
+
+use super::lobby::{LobbyUI, LobbyUIElements};
+
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+use {crate::http::Pending, shared::ServerLobbyRoom};
+
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+static PENDING_ROOM_RESULTS: Pending<(bool, Option<RoomMatchResult>)> = Pending::new();
+
+/// Create Room: keep the room and its results after the match, or close it
+#[derive(Component)]
+pub(super) struct PublishResultsButton;
+
+/// Pick up the last match from any lobby answer that carries the room
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+pub(super) fn queue_room_results(room: &ServerLobbyRoom) {
+    PENDING_ROOM_RESULTS.set((room.publish_results, room.last_match.clone()));
+}
+
+// Only touch the lobby state when something changed, every change redraws the room
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+pub(super) fn pump_room_results(mut lobby_q: Query<&mut LobbyUI>) {
+    let Some((publish_results, last_match)) = PENDING_ROOM_RESULTS.take() else {
+        return;
+    };
+    let Ok(mut ui) = lobby_q.single_mut() else {
+        return;
+    };
+    if ui.room_id.is_empty() {
+        return;
+    }
+    if ui.publish_results != publish_results || ui.last_match != last_match {
+        ui.publish_results = publish_results;
+        ui.last_match = last_match;
+    }
+}
+
+pub(super) fn handle_publish_results(
+    buttons: Query<&Interaction, (Changed<Interaction>, With<PublishResultsButton>)>,
+    mut lobby_q: Query<&mut LobbyUI>,
+) {
+    if !buttons
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        return;
+    }
+    let Ok(mut ui) = lobby_q.single_mut() else {
+        return;
+    };
+    ui.publish_results = !ui.publish_results;
+}
+
+pub(super) fn spawn_publish_results_button(commands: &mut Commands, lobby_ui: &LobbyUI) -> Entity {
+    let label = if lobby_ui.publish_results {
+        "🏁 Post results to the room"
+    } else {
+        "🏁 Close the room after the match"
+    };
+    commands
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(260.0),
+                height: Val::Px(36.0),
+                margin: UiRect::all(Val::Px(6.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(if lobby_ui.publish_results {
+                Color::srgb(0.25, 0.5, 0.35)
+            } else {
+                Color::srgb(0.3, 0.3, 0.3)
+            }),
+            PublishResultsButton,
+            LobbyUIElements,
+        ))
+        .with_children(|btn| {
+            btn.spawn((
+                Text::new(label),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            ));
+        })
+        .id()
+}
+
+/// The room's last match: level, mode, length and the standings
+pub(super) fn spawn_last_match(commands: &mut Commands, result: &RoomMatchResult) -> Entity {
+    let secs = result.duration_secs.max(0.0) as u32;
+    commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(8.0)),
+                margin: UiRect::all(Val::Px(6.0)),
+                row_gap: Val::Px(2.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.4)),
+            LobbyUIElements,
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                Text::new(format!(
+                    "🏁 Last match: {} on {}, {}:{:02}",
+                    result.game_mode,
+                    result.level,
+                    secs / 60,
+                    secs % 60
+                )),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 0.85, 0.3)),
+            ));
+            for standing in &result.standings {
+                let outcome = match standing.time {
+                    Some(time) => format!("{:.2}s", time),
+                    None => format!("{} points", standing.score),
+                };
+                panel.spawn((
+                    Text::new(format!(
+                        "{}. {} - {}",
+                        standing.place, standing.name, outcome
+                    )),
+                    TextFont {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.85, 0.85, 0.85)),
+                ));
+            }
+        })
+        .id()
+}
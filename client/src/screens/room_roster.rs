@@ -0,0 +1,374 @@
+// 🧑‍🚀 Room roster: free-for-all rooms list their players, whether they are ready and when
+// they joined, instead of only a count. Entering a room asks the lobby service for it with
+// `GET /rooms/{id}`, the heartbeats keep it current after that. Team rooms show the same
+// players in their team columns.
+//
+// ✋ Ready check: players say they are ready with `POST /rooms/{id}/ready`, and START GAME
+// waits until everyone is or `READY_CHECK_SECS` passed since the last join. The service
+// refuses early starts the same way.
+//
+// 👢 Hosts kick players with `POST /rooms/{id}/kick`. The kicked player's next heartbeat
+// takes them back to the main lobby, and when the host leaves the service hands the room
+// to whoever has been in it longest.
+use bevy::prelude::*;
+
+use shared::{ready_check_secs_left, RoomMember};
+
+use super::lobby::{LobbyUI, LobbyUIElements, StartGameButton};
+use crate::http::unix_now;
+
+#[cfg(feature = "bevygap")]
+use super::lobby::LobbyMode;
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+use {
+    super::lobby::queue_room,
+    super::room_teams::queue_room_teams,
+    crate::http::{http_base, spawn, ApiRequest},
+    shared::{KickRequest, ReadyRequest, ServerLobbyRoom},
+};
+
+/// Players other than the host say they are ready, or no longer
+#[derive(Component)]
+pub(super) struct ReadyButton;
+
+/// The host takes this player out of the room
+#[derive(Component)]
+pub(super) struct KickButton {
+    name: String,
+    player_id: Option<String>,
+}
+
+/// Ask the lobby service for the room and its members once per room we enter
+#[cfg(feature = "bevygap")]
+pub(super) fn fetch_room_detail(
+    lobby_q: Query<&LobbyUI, Changed<LobbyUI>>,
+    mut fetched: Local<String>,
+) {
+    let Ok(ui) = lobby_q.single() else {
+        return;
+    };
+    // The browser fills in `room_id` for the code being typed, only rooms we are in count
+    let room_id = match ui.lobby_mode {
+        LobbyMode::InRoom => ui.room_id.as_str(),
+        _ => "",
+    };
+    if *fetched == room_id {
+        return;
+    }
+    *fetched = room_id.to_string();
+    if room_id.is_empty() {
+        return;
+    }
+
+    let url = format!("{}/lobby/api/rooms/{}", http_base(), room_id);
+    spawn(async move {
+        // Older services don't have the route, the first heartbeat brings the members. A
+        // room that is gone is reported by the heartbeat too.
+        if let Ok(room) = ApiRequest::get(url).json::<ServerLobbyRoom>().await {
+            queue_room(&room);
+        }
+    });
+}
+
+/// A row per player in the room: whether they are ready, and since when they are in it
+pub(super) fn spawn_room_roster(commands: &mut Commands, lobby_ui: &LobbyUI) -> Entity {
+    let now = unix_now();
+    commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Start,
+                padding: UiRect::all(Val::Px(8.0)),
+                margin: UiRect::all(Val::Px(6.0)),
+                row_gap: Val::Px(2.0),
+                min_width: Val::Px(260.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.4)),
+            LobbyUIElements,
+        ))
+        .with_children(|panel| {
+            for member in &lobby_ui.room_members {
+                panel
+                    .spawn(Node {
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(8.0),
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        row.spawn((
+                            Text::new(roster_line(member, lobby_ui, now)),
+                            TextFont {
+                                font_size: 14.0,
+                                ..default()
+                            },
+                            TextColor(if member.ready {
+                                Color::srgb(0.85, 0.85, 0.85)
+                            } else {
+                                Color::srgb(0.6, 0.6, 0.6)
+                            }),
+                        ));
+                        if lobby_ui.is_host && !is_us(member, lobby_ui) {
+                            spawn_kick_button(row, member, "✖");
+                        }
+                    });
+            }
+        })
+        .id()
+}
+
+/// Team rooms list their players in the team columns, the host kicks them from this row
+pub(super) fn spawn_kick_row(commands: &mut Commands, lobby_ui: &LobbyUI) -> Option<Entity> {
+    let others: Vec<&RoomMember> = lobby_ui
+        .room_members
+        .iter()
+        .filter(|member| !is_us(member, lobby_ui))
+        .collect();
+    if !lobby_ui.is_host || others.is_empty() {
+        return None;
+    }
+    let row = commands
+        .spawn((
+            Node {
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(6.0),
+                margin: UiRect::all(Val::Px(4.0)),
+                ..default()
+            },
+            LobbyUIElements,
+        ))
+        .with_children(|row| {
+            row.spawn((
+                Text::new("Kick:"),
+                TextFont {
+                    font_size: 13.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.7, 0.7, 0.7)),
+            ));
+            for member in others {
+                spawn_kick_button(row, member, &format!("✖ {}", member.name));
+            }
+        })
+        .id();
+    Some(row)
+}
+
+fn spawn_kick_button(parent: &mut ChildSpawnerCommands, member: &RoomMember, label: &str) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.5, 0.2, 0.2)),
+            KickButton {
+                name: member.name.clone(),
+                player_id: member.player_id.clone(),
+            },
+        ))
+        .with_children(|btn| {
+            btn.spawn((
+                Text::new(label),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+        });
+}
+
+/// The host takes the player out of the room, here right away and then with the lobby
+/// service, which keeps them from joining again
+pub(super) fn handle_kick_buttons(
+    buttons: Query<(&Interaction, &KickButton), Changed<Interaction>>,
+    mut lobby_q: Query<&mut LobbyUI>,
+) {
+    let Some((_, kicked)) = buttons
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Pressed)
+    else {
+        return;
+    };
+    let Ok(mut ui) = lobby_q.single_mut() else {
+        return;
+    };
+    if !ui.is_host {
+        return;
+    }
+    ui.room_members
+        .retain(|member| !member.is(&kicked.name, kicked.player_id.as_deref()));
+    ui.current_players = ui.current_players.saturating_sub(1).max(1);
+    #[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+    send_kick(&ui, kicked);
+}
+
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+fn send_kick(ui: &LobbyUI, kicked: &KickButton) {
+    let url = format!("{}/lobby/api/rooms/{}/kick", http_base(), ui.room_id);
+    let request = ApiRequest::post(
+        url,
+        &KickRequest {
+            player_name: ui.player_name.clone(),
+            player_id: ui.player_id.clone(),
+            member: kicked.name.clone(),
+            member_id: kicked.player_id.clone(),
+        },
+    );
+    spawn(async move {
+        // On failure the next heartbeat brings the player back if they are still in the room
+        if let Ok(room) = request.json::<ServerLobbyRoom>().await {
+            queue_room(&room);
+        }
+    });
+}
+
+// "✅ Nova (you) - joined 2m ago"
+fn roster_line(member: &RoomMember, lobby_ui: &LobbyUI, now: u64) -> String {
+    let mut line = format!("{} {}", if member.ready { "✅" } else { "⏳" }, member.name);
+    if is_us(member, lobby_ui) {
+        line.push_str(" (you)");
+    }
+    if let Some(joined) = member.joined_ago(now) {
+        line.push_str(" - ");
+        line.push_str(&joined);
+    }
+    line
+}
+
+/// Whether `member` is the player on this client
+fn is_us(member: &RoomMember, lobby_ui: &LobbyUI) -> bool {
+    member.is(&lobby_ui.player_name, lobby_ui.player_id.as_deref())
+}
+
+/// Seconds until START GAME works, 0 once everyone is ready or the ready check ran out
+pub(super) fn start_secs_left(lobby_ui: &LobbyUI) -> u64 {
+    ready_check_secs_left(&lobby_ui.room_members, unix_now())
+}
+
+/// Players in the roster, not the host, who is ready from the start
+pub(super) fn spawn_ready_button(commands: &mut Commands, lobby_ui: &LobbyUI) -> Option<Entity> {
+    if lobby_ui.is_host {
+        return None;
+    }
+    let ready = lobby_ui
+        .room_members
+        .iter()
+        .find(|member| is_us(member, lobby_ui))?
+        .ready;
+    let button = commands
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(120.0),
+                height: Val::Px(50.0),
+                margin: UiRect::all(Val::Px(10.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(if ready {
+                Color::srgb(0.25, 0.5, 0.35)
+            } else {
+                Color::srgb(0.3, 0.3, 0.3)
+            }),
+            ReadyButton,
+        ))
+        .with_children(|btn| {
+            btn.spawn((
+                Text::new(if ready { "✅ READY" } else { "✋ NOT READY" }),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            ));
+        })
+        .id();
+    Some(button)
+}
+
+/// Flip our ready flag, right away here and then with the lobby service
+pub(super) fn handle_ready_button(
+    buttons: Query<&Interaction, (Changed<Interaction>, With<ReadyButton>)>,
+    mut lobby_q: Query<&mut LobbyUI>,
+) {
+    if !buttons
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        return;
+    }
+    let Ok(mut ui) = lobby_q.single_mut() else {
+        return;
+    };
+    let Some(index) = ui.room_members.iter().position(|member| is_us(member, &ui)) else {
+        return;
+    };
+    let ready = !ui.room_members[index].ready;
+    ui.room_members[index].ready = ready;
+    #[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+    send_ready(&ui, ready);
+}
+
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+fn send_ready(ui: &LobbyUI, ready: bool) {
+    let url = format!("{}/lobby/api/rooms/{}/ready", http_base(), ui.room_id);
+    let request = ApiRequest::post(
+        url,
+        &ReadyRequest {
+            player_name: ui.player_name.clone(),
+            player_id: ui.player_id.clone(),
+            ready,
+        },
+    );
+    spawn(async move {
+        // On failure the next heartbeat brings the service's ready flags back
+        if let Ok(room) = request.json::<ServerLobbyRoom>().await {
+            queue_room_teams(&room);
+        }
+    });
+}
+
+/// START GAME counts down the ready check, and is grey until it may start
+pub(super) fn update_start_button(
+    lobby_q: Query<&LobbyUI>,
+    mut buttons: Query<(Ref<StartGameButton>, &mut BackgroundColor, &Children)>,
+    mut texts: Query<&mut Text>,
+    mut shown: Local<Option<u64>>,
+) {
+    let Ok(ui) = lobby_q.single() else {
+        return;
+    };
+    let secs_left = start_secs_left(ui);
+    for (button, mut color, children) in buttons.iter_mut() {
+        if !button.is_added() && *shown == Some(secs_left) {
+            continue;
+        }
+        *color = BackgroundColor(start_button_color(secs_left));
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(child) {
+                text.0 = start_button_label(secs_left);
+            }
+        }
+    }
+    *shown = Some(secs_left);
+}
+
+pub(super) fn start_button_color(secs_left: u64) -> Color {
+    if secs_left == 0 {
+        Color::srgb(0.2, 0.6, 0.2)
+    } else {
+        Color::srgb(0.3, 0.3, 0.3)
+    }
+}
+
+pub(super) fn start_button_label(secs_left: u64) -> String {
+    if secs_left == 0 {
+        "START GAME".to_string()
+    } else {
+        format!("READY CHECK {}s", secs_left)
+    }
+}
@@ -0,0 +1,128 @@
+// 📜 Custom rooms play by a rule set: the host cycles through the ones in assets/rules/
+// on Create Room, the lobby service keeps it with the room, everyone in the room sees what
+// it changes, and the matchmaker starts the room's server with it (see `shared::rule_set`).
+use bevy::prelude::*;
+
+use shared::{GameMode, RuleSet};
+
+use super::lobby::{LobbyUI, LobbyUIElements};
+
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+use {crate::http::Pending, shared::ServerLobbyRoom};
+
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+static PENDING_ROOM_RULES: Pending<Option<String>> = Pending::new();
+
+/// Create Room: the next rule set, custom mode only
+#[derive(Component)]
+pub(super) struct RulesButton;
+
+/// The rule set the match is played with as RON: the room's, or the one picked for a
+/// custom match. None for every other mode.
+#[cfg_attr(not(feature = "bevygap"), allow(dead_code))]
+pub(super) fn match_rules(lobby_ui: &LobbyUI) -> Option<String> {
+    lobby_ui.room_rules.clone().or_else(|| {
+        (lobby_ui.selected_mode == GameMode::Custom.id())
+            .then(|| RuleSet::builtin_ron(&lobby_ui.selected_rules))
+            .flatten()
+            .map(str::to_string)
+    })
+}
+
+/// Pick up the rules from any lobby answer that carries the room
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+pub(super) fn queue_room_rules(room: &ServerLobbyRoom) {
+    PENDING_ROOM_RULES.set(room.rules.clone());
+}
+
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+pub(super) fn pump_room_rules(mut lobby_q: Query<&mut LobbyUI>) {
+    let Some(rules) = PENDING_ROOM_RULES.take() else {
+        return;
+    };
+    let Ok(mut ui) = lobby_q.single_mut() else {
+        return;
+    };
+    if !ui.room_id.is_empty() && ui.room_rules != rules {
+        ui.room_rules = rules;
+    }
+}
+
+pub(super) fn handle_rules_button(
+    buttons: Query<&Interaction, (Changed<Interaction>, With<RulesButton>)>,
+    mut lobby_q: Query<&mut LobbyUI>,
+) {
+    if !buttons
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        return;
+    }
+    let Ok(mut ui) = lobby_q.single_mut() else {
+        return;
+    };
+    let names: Vec<&str> = RuleSet::builtin_names().collect();
+    let next = names
+        .iter()
+        .position(|name| *name == ui.selected_rules)
+        .map_or(0, |index| (index + 1) % names.len());
+    ui.selected_rules = names[next].to_string();
+}
+
+pub(super) fn spawn_rules_button(commands: &mut Commands, lobby_ui: &LobbyUI) -> Entity {
+    let summary = RuleSet::builtin(&lobby_ui.selected_rules)
+        .map_or_else(|| lobby_ui.selected_rules.clone(), |rules| rules.summary());
+    commands
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(320.0),
+                height: Val::Px(36.0),
+                margin: UiRect::all(Val::Px(6.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.35, 0.3, 0.5)),
+            RulesButton,
+            LobbyUIElements,
+        ))
+        .with_children(|btn| {
+            btn.spawn((
+                Text::new(format!("📜 Rules: {}", summary)),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            ));
+        })
+        .id()
+}
+
+/// What the room's rules change, for everyone in it
+pub(super) fn spawn_room_rules(commands: &mut Commands, ron: &str) -> Entity {
+    let label = match RuleSet::from_ron(ron) {
+        Ok(rules) => format!("📜 {}", rules.summary()),
+        // The lobby validated it, so this is a newer client's rule set
+        Err(e) => {
+            warn!("📜 Can't read the room's rules: {}", e);
+            "📜 Custom rules".to_string()
+        }
+    };
+    commands
+        .spawn((
+            Text::new(label),
+            TextFont {
+                font_size: 14.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.8, 0.75, 1.0)),
+            Node {
+                margin: UiRect::all(Val::Px(6.0)),
+                ..default()
+            },
+            LobbyUIElements,
+        ))
+        .id()
+}
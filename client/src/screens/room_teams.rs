@@ -0,0 +1,358 @@
+// 👥 Teams in lobby rooms: the host picks free-for-all or two teams (with or without
+// friendly fire) when creating the room, and inside the room players are dragged between
+// the team columns. The host can move anyone, everyone else only themselves. The lobby
+// service keeps the teams, the game server gets ours with our profile.
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+
+use shared::{RoomMember, Team};
+
+use super::lobby::{LobbyUI, LobbyUIElements};
+use crate::teams::RoomTeam;
+
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+use {
+    crate::http::{http_base, spawn, ApiRequest, Pending},
+    shared::{ServerLobbyRoom, TeamChangeRequest},
+};
+
+// Teams of a room the lobby service told us about: count, friendly fire and members
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+type RoomTeams = (usize, bool, Vec<RoomMember>);
+
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+static PENDING_ROOM_TEAMS: Pending<RoomTeams> = Pending::new();
+
+// Teams a team room is created with
+const ROOM_TEAMS: usize = 2;
+
+/// Create Room: cycles free-for-all, teams, teams with friendly fire
+#[derive(Component)]
+pub(super) struct TeamOptionButton;
+
+// A column players are dropped on
+#[derive(Component)]
+pub(super) struct TeamColumn(usize);
+
+// A player in a team column, pressed to start dragging them
+#[derive(Component)]
+pub(super) struct MemberChip(RoomMember);
+
+/// Pick up the teams from any lobby answer that carries the room
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+pub(super) fn queue_room_teams(room: &ServerLobbyRoom) {
+    PENDING_ROOM_TEAMS.set((room.teams, room.friendly_fire, room.members.clone()));
+}
+
+// Only touch the lobby state when something changed, every change redraws the room
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+pub(super) fn pump_room_teams(mut lobby_q: Query<&mut LobbyUI>) {
+    let Some((teams, friendly_fire, members)) = PENDING_ROOM_TEAMS.take() else {
+        return;
+    };
+    let Ok(mut ui) = lobby_q.single_mut() else {
+        return;
+    };
+    if ui.room_id.is_empty() {
+        return;
+    }
+    if ui.room_teams != teams || ui.friendly_fire != friendly_fire || ui.room_members != members {
+        ui.room_teams = teams;
+        ui.friendly_fire = friendly_fire;
+        ui.room_members = members;
+    }
+}
+
+/// Our team in the room, for the profile we send the game server
+pub(super) fn sync_room_team(
+    lobby_q: Query<&LobbyUI, Changed<LobbyUI>>,
+    mut room_team: ResMut<RoomTeam>,
+) {
+    let Ok(ui) = lobby_q.single() else {
+        return;
+    };
+    let team = ui
+        .room_members
+        .iter()
+        .find(|member| member.name == ui.player_name)
+        .and_then(|member| member.team);
+    if room_team.0 != team {
+        room_team.0 = team;
+    }
+}
+
+pub(super) fn handle_team_options(
+    buttons: Query<&Interaction, (Changed<Interaction>, With<TeamOptionButton>)>,
+    mut lobby_q: Query<&mut LobbyUI>,
+) {
+    if !buttons
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        return;
+    }
+    let Ok(mut ui) = lobby_q.single_mut() else {
+        return;
+    };
+    (ui.room_teams, ui.friendly_fire) = match (ui.room_teams, ui.friendly_fire) {
+        (0, _) => (ROOM_TEAMS, false),
+        (teams, false) => (teams, true),
+        _ => (0, false),
+    };
+}
+
+// Press on a player, let go over a team column
+pub(super) fn drag_members(
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut chips: Query<(&Interaction, &MemberChip, &mut BackgroundColor)>,
+    columns: Query<(&Interaction, &TeamColumn)>,
+    mut lobby_q: Query<&mut LobbyUI>,
+    mut dragging: Local<Option<RoomMember>>,
+) {
+    let Ok(mut ui) = lobby_q.single_mut() else {
+        return;
+    };
+    if mouse.just_pressed(MouseButton::Left) {
+        *dragging = chips
+            .iter()
+            .find(|(interaction, chip, _)| {
+                **interaction == Interaction::Pressed && can_move(&ui, &chip.0)
+            })
+            .map(|(_, chip, _)| chip.0.clone());
+        for (_, chip, mut background) in chips.iter_mut() {
+            if dragging.as_ref() == Some(&chip.0) {
+                background.0 = Color::srgba(1.0, 1.0, 1.0, 0.35);
+            }
+        }
+    }
+    if !mouse.just_released(MouseButton::Left) {
+        return;
+    }
+    let Some(dragged) = dragging.take() else {
+        return;
+    };
+    let target = columns
+        .iter()
+        .find(|(interaction, _)| **interaction != Interaction::None)
+        .map(|(_, column)| column.0);
+    let Some(team) = target else {
+        // Dropped outside the columns, the redraw puts the chip back
+        ui.set_changed();
+        return;
+    };
+    let Some(member) = ui
+        .room_members
+        .iter_mut()
+        .find(|m| m.is(&dragged.name, dragged.player_id.as_deref()))
+    else {
+        return;
+    };
+    if member.team == Some(team) {
+        ui.set_changed();
+        return;
+    }
+    info!("👥 Moving {} to {}", dragged.name, Team(team).name());
+    member.team = Some(team);
+    #[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+    send_team_change(&ui, dragged, team);
+}
+
+// The host can move anyone, everyone else only themselves
+fn can_move(ui: &LobbyUI, member: &RoomMember) -> bool {
+    ui.is_host || member.is(&ui.player_name, ui.player_id.as_deref())
+}
+
+#[cfg(any(target_arch = "wasm32", feature = "bevygap"))]
+fn send_team_change(ui: &LobbyUI, member: RoomMember, team: usize) {
+    let url = format!("{}/lobby/api/rooms/{}/team", http_base(), ui.room_id);
+    let request = ApiRequest::post(
+        url,
+        &TeamChangeRequest {
+            player_name: ui.player_name.clone(),
+            player_id: ui.player_id.clone(),
+            member: member.name,
+            member_id: member.player_id,
+            team,
+        },
+    );
+    spawn(async move {
+        // On failure the next heartbeat brings the service's teams back
+        if let Ok(room) = request.json::<ServerLobbyRoom>().await {
+            queue_room_teams(&room);
+        }
+    });
+}
+
+pub(super) fn spawn_team_option_button(commands: &mut Commands, lobby_ui: &LobbyUI) -> Entity {
+    let label = match (lobby_ui.room_teams, lobby_ui.friendly_fire) {
+        (0, _) => "👥 Free-for-all".to_string(),
+        (teams, false) => format!("👥 {} teams", teams),
+        (teams, true) => format!("👥 {} teams, friendly fire", teams),
+    };
+    commands
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(220.0),
+                height: Val::Px(36.0),
+                margin: UiRect::all(Val::Px(6.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(if lobby_ui.room_teams > 0 {
+                Color::srgb(0.25, 0.4, 0.7)
+            } else {
+                Color::srgb(0.3, 0.3, 0.3)
+            }),
+            TeamOptionButton,
+            LobbyUIElements,
+        ))
+        .with_children(|btn| {
+            btn.spawn((
+                Text::new(label),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            ));
+        })
+        .id()
+}
+
+/// One column per team with its players, and the players the service has not placed yet
+pub(super) fn spawn_team_columns(commands: &mut Commands, lobby_ui: &LobbyUI) -> Entity {
+    let unplaced: Vec<&RoomMember> = lobby_ui
+        .room_members
+        .iter()
+        .filter(|member| member.team.is_none_or(|team| team >= lobby_ui.room_teams))
+        .collect();
+    commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                margin: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            LobbyUIElements,
+        ))
+        .with_children(|panel| {
+            panel
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(12.0),
+                    ..default()
+                })
+                .with_children(|row| {
+                    for team in 0..lobby_ui.room_teams {
+                        let members = lobby_ui
+                            .room_members
+                            .iter()
+                            .filter(|member| member.team == Some(team));
+                        team_column(row, lobby_ui, Team(team), members);
+                    }
+                });
+            if !unplaced.is_empty() {
+                let names: Vec<&str> = unplaced.iter().map(|m| m.name.as_str()).collect();
+                panel.spawn((
+                    Text::new(format!("Joining: {}", names.join(", "))),
+                    TextFont {
+                        font_size: 13.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.7, 0.7, 0.7)),
+                ));
+            }
+            let hint = match (lobby_ui.is_host, lobby_ui.friendly_fire) {
+                (true, false) => "Drag players between the teams",
+                (true, true) => "Drag players between the teams (friendly fire is on)",
+                (false, false) => "Drag yourself to switch teams",
+                (false, true) => "Drag yourself to switch teams (friendly fire is on)",
+            };
+            panel.spawn((
+                Text::new(hint),
+                TextFont {
+                    font_size: 13.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.7, 0.7, 0.7)),
+                Node {
+                    margin: UiRect::top(Val::Px(6.0)),
+                    ..default()
+                },
+            ));
+        })
+        .id()
+}
+
+fn team_column<'a>(
+    row: &mut ChildSpawnerCommands,
+    lobby_ui: &LobbyUI,
+    team: Team,
+    members: impl Iterator<Item = &'a RoomMember>,
+) {
+    row.spawn((
+        Node {
+            width: Val::Px(170.0),
+            min_height: Val::Px(140.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            padding: UiRect::all(Val::Px(8.0)),
+            row_gap: Val::Px(4.0),
+            border: UiRect::all(Val::Px(2.0)),
+            ..default()
+        },
+        BorderColor(team.color()),
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.4)),
+        Interaction::default(),
+        TeamColumn(team.0),
+    ))
+    .with_children(|column| {
+        column.spawn((
+            Text::new(team.name()),
+            TextFont {
+                font_size: 16.0,
+                ..default()
+            },
+            TextColor(team.color()),
+        ));
+        for member in members {
+            let you = member.name == lobby_ui.player_name;
+            column
+                .spawn((
+                    Node {
+                        width: Val::Percent(100.0),
+                        padding: UiRect::all(Val::Px(4.0)),
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    BackgroundColor(team.color().with_alpha(0.25)),
+                    Interaction::default(),
+                    // Let the column under the pointer see it too, that is where a drag ends
+                    FocusPolicy::Pass,
+                    MemberChip(member.clone()),
+                ))
+                .with_children(|chip| {
+                    chip.spawn((
+                        Text::new(format!(
+                            "{} {}{}",
+                            if member.ready { "✅" } else { "⏳" },
+                            member.name,
+                            if you { " (you)" } else { "" }
+                        )),
+                        TextFont {
+                            font_size: 14.0,
+                            ..default()
+                        },
+                        TextColor(if can_move(lobby_ui, member) {
+                            Color::WHITE
+                        } else {
+                            Color::srgb(0.75, 0.75, 0.75)
+                        }),
+                    ));
+                });
+        }
+    });
+}
@@ -0,0 +1,548 @@
+// ⚙️ Settings page: display name, player color, key and gamepad bindings and graphics.
+// Everything is saved right away (localStorage on the web, the settings file on native).
+use bevy::prelude::*;
+
+use shared::{PlayerActions, QualityPreset, PLAYER_COLORS};
+
+use super::lobby::{spawn_back_button_simple, LobbyMode, LobbyUI, LobbyUIElements};
+use crate::account::spawn_name_row;
+use crate::graphics::ActivePreset;
+use crate::settings::{ClientSettings, ControlSettings};
+
+// Action waiting for its new key or gamepad button, None while not rebinding
+#[derive(Resource, Default)]
+pub(super) struct Rebinding(Option<(PlayerActions, BindingDevice)>);
+
+#[derive(Clone, Copy, PartialEq)]
+enum BindingDevice {
+    Keyboard,
+    Gamepad,
+}
+
+#[derive(Component)]
+pub(super) struct ColorSwatch(usize);
+
+#[derive(Component)]
+pub(super) struct RebindButton(PlayerActions, BindingDevice);
+
+// Names the connected gamepads, filled in by `update_settings_page`
+#[derive(Component)]
+pub(super) struct GamepadStatusText;
+
+#[derive(Component)]
+pub(super) struct ResetControlsButton;
+
+// Cycles Auto, Low, Medium, High
+#[derive(Component)]
+pub(super) struct QualityButton;
+
+#[derive(Component)]
+pub(super) struct ShareHardwareButton;
+
+#[derive(Component)]
+pub(super) struct LobbySceneButton;
+
+#[derive(Component)]
+pub(super) struct NameplatesButton;
+
+pub(super) fn spawn_settings_ui(
+    commands: &mut Commands,
+    container_entity: Entity,
+    settings: &ClientSettings,
+) {
+    let title = commands
+        .spawn((
+            Text::new("⚙️ Settings"),
+            TextFont {
+                font_size: 28.0,
+                ..default()
+            },
+            TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            Node {
+                margin: UiRect::all(Val::Px(20.0)),
+                ..default()
+            },
+            LobbyUIElements,
+        ))
+        .id();
+    commands.entity(container_entity).add_child(title);
+
+    let name_row = spawn_name_row(commands, &settings.account.display_name);
+    commands.entity(name_row).insert(LobbyUIElements);
+    commands.entity(container_entity).add_child(name_row);
+
+    // One swatch per palette color, the picked one has a white border
+    let color_row = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                margin: UiRect::all(Val::Px(10.0)),
+                column_gap: Val::Px(8.0),
+                ..default()
+            },
+            LobbyUIElements,
+        ))
+        .with_children(|row| {
+            row.spawn((
+                Text::new("Color:"),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            ));
+            for (index, color) in PLAYER_COLORS.iter().enumerate() {
+                row.spawn((
+                    Button,
+                    Node {
+                        width: Val::Px(32.0),
+                        height: Val::Px(32.0),
+                        border: UiRect::all(Val::Px(3.0)),
+                        ..default()
+                    },
+                    BackgroundColor(*color),
+                    BorderColor(swatch_border(index, settings)),
+                    ColorSwatch(index),
+                ));
+            }
+        })
+        .id();
+    commands.entity(container_entity).add_child(color_row);
+
+    let controls = commands
+        .spawn((
+            Node {
+                width: Val::Px(420.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(12.0)),
+                row_gap: Val::Px(6.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.4)),
+            LobbyUIElements,
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                Text::new("Controls - click a key or button, then press the new one"),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+            ));
+            panel.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                GamepadStatusText,
+            ));
+            for action in PlayerActions::ALL {
+                panel
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::SpaceBetween,
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        row.spawn((
+                            Text::new(action_label(action)),
+                            TextFont {
+                                font_size: 16.0,
+                                ..default()
+                            },
+                            TextColor(Color::srgb(1.0, 1.0, 1.0)),
+                        ));
+                        spawn_rebind_button(
+                            row,
+                            150.0,
+                            RebindButton(action, BindingDevice::Keyboard),
+                            key_names(&settings.controls, action),
+                        );
+                        spawn_rebind_button(
+                            row,
+                            100.0,
+                            RebindButton(action, BindingDevice::Gamepad),
+                            button_name(settings.controls.gamepad.button(action)),
+                        );
+                    });
+            }
+        })
+        .id();
+    commands.entity(container_entity).add_child(controls);
+
+    let reset = commands
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(180.0),
+                height: Val::Px(40.0),
+                margin: UiRect::all(Val::Px(10.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.5, 0.3, 0.2)),
+            ResetControlsButton,
+            LobbyUIElements,
+        ))
+        .with_children(|btn| {
+            btn.spawn((
+                Text::new("RESET CONTROLS"),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            ));
+        })
+        .id();
+    commands.entity(container_entity).add_child(reset);
+
+    let graphics = commands
+        .spawn((
+            Node {
+                width: Val::Px(360.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(12.0)),
+                row_gap: Val::Px(6.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.4)),
+            LobbyUIElements,
+        ))
+        .with_children(|panel| {
+            spawn_graphics_button(panel, QualityButton);
+            spawn_graphics_button(panel, ShareHardwareButton);
+            spawn_graphics_button(panel, LobbySceneButton);
+            spawn_graphics_button(panel, NameplatesButton);
+            panel.spawn((
+                Text::new(
+                    "Sharing sends your GPU model and frame rate to the lobby once per \
+                     session. It sets the default quality for GPUs like yours. Turn the \
+                     lobby scene off to save power on slow devices.",
+                ),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+            ));
+        })
+        .id();
+    commands.entity(container_entity).add_child(graphics);
+
+    let back = spawn_back_button_simple(commands);
+    commands.entity(container_entity).add_child(back);
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(super) fn handle_settings_buttons(
+    swatches: Query<(&Interaction, &ColorSwatch), Changed<Interaction>>,
+    rebinds: Query<(&Interaction, &RebindButton), Changed<Interaction>>,
+    resets: Query<&Interaction, (Changed<Interaction>, With<ResetControlsButton>)>,
+    quality: Query<&Interaction, (Changed<Interaction>, With<QualityButton>)>,
+    share: Query<&Interaction, (Changed<Interaction>, With<ShareHardwareButton>)>,
+    scene: Query<&Interaction, (Changed<Interaction>, With<LobbySceneButton>)>,
+    nameplates: Query<&Interaction, (Changed<Interaction>, With<NameplatesButton>)>,
+    mut rebinding: ResMut<Rebinding>,
+    mut settings: ResMut<ClientSettings>,
+) {
+    for (interaction, swatch) in swatches.iter() {
+        if *interaction == Interaction::Pressed && settings.account.color != swatch.0 {
+            settings.account.color = swatch.0;
+            settings.save();
+        }
+    }
+    for (interaction, button) in rebinds.iter() {
+        if *interaction == Interaction::Pressed {
+            rebinding.0 = Some((button.0, button.1));
+        }
+    }
+    if resets.iter().any(|i| *i == Interaction::Pressed) {
+        rebinding.0 = None;
+        settings.controls = ControlSettings::default();
+        settings.save();
+        info!("⌨️ Controls reset to the defaults");
+    }
+    if quality.iter().any(|i| *i == Interaction::Pressed) {
+        settings.graphics.preset = match settings.graphics.preset {
+            None => Some(QualityPreset::Low),
+            Some(QualityPreset::Low) => Some(QualityPreset::Medium),
+            Some(QualityPreset::Medium) => Some(QualityPreset::High),
+            Some(QualityPreset::High) => None,
+        };
+        settings.save();
+    }
+    if share.iter().any(|i| *i == Interaction::Pressed) {
+        settings.graphics.share_hardware = !settings.graphics.share_hardware;
+        settings.save();
+        info!(
+            "🖥️ Hardware sharing {}",
+            if settings.graphics.share_hardware {
+                "on"
+            } else {
+                "off"
+            }
+        );
+    }
+    if scene.iter().any(|i| *i == Interaction::Pressed) {
+        settings.graphics.lobby_scene = !settings.graphics.lobby_scene;
+        settings.save();
+    }
+    if nameplates.iter().any(|i| *i == Interaction::Pressed) {
+        settings.graphics.nameplates = !settings.graphics.nameplates;
+        settings.save();
+    }
+}
+
+// The next key pressed becomes the action's main key, or the next button pressed on any
+// gamepad its button. Escape cancels either.
+pub(super) fn capture_rebind_key(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    lobby_q: Query<&LobbyUI>,
+    mut rebinding: ResMut<Rebinding>,
+    mut settings: ResMut<ClientSettings>,
+) {
+    let Some((action, device)) = rebinding.0 else {
+        return;
+    };
+    if lobby_q
+        .single()
+        .is_ok_and(|ui| ui.lobby_mode != LobbyMode::Settings)
+    {
+        rebinding.0 = None;
+        return;
+    }
+    if keys.just_pressed(KeyCode::Escape) {
+        rebinding.0 = None;
+        return;
+    }
+    match device {
+        BindingDevice::Keyboard => {
+            let Some(key) = keys.get_just_pressed().next().copied() else {
+                return;
+            };
+            info!("⌨️ {} is now bound to {:?}", action_label(action), key);
+            settings.controls.rebind(action, key);
+        }
+        BindingDevice::Gamepad => {
+            let Some(button) = gamepads
+                .iter()
+                .find_map(|gamepad| gamepad.get_just_pressed().next().copied())
+            else {
+                return;
+            };
+            info!("🎮 {} is now bound to {:?}", action_label(action), button);
+            settings.controls.gamepad.rebind(action, button);
+        }
+    }
+    rebinding.0 = None;
+    settings.save();
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(super) fn update_settings_page(
+    settings: Res<ClientSettings>,
+    rebinding: Res<Rebinding>,
+    active: Res<ActivePreset>,
+    mut swatches: Query<(&ColorSwatch, &mut BorderColor)>,
+    buttons: Query<(&RebindButton, &Children)>,
+    quality: Query<&Children, With<QualityButton>>,
+    share: Query<&Children, With<ShareHardwareButton>>,
+    scene: Query<&Children, With<LobbySceneButton>>,
+    nameplates: Query<&Children, With<NameplatesButton>>,
+    gamepad_status: Query<Entity, With<GamepadStatusText>>,
+    gamepads: Query<&Name, With<Gamepad>>,
+    mut texts: Query<&mut Text>,
+) {
+    for (swatch, mut border) in swatches.iter_mut() {
+        let wanted = swatch_border(swatch.0, &settings);
+        if border.0 != wanted {
+            border.0 = wanted;
+        }
+    }
+    for (button, children) in buttons.iter() {
+        let caption = match (rebinding.0 == Some((button.0, button.1)), button.1) {
+            (true, BindingDevice::Keyboard) => "press a key...".to_string(),
+            (true, BindingDevice::Gamepad) => "press...".to_string(),
+            (false, BindingDevice::Keyboard) => key_names(&settings.controls, button.0),
+            (false, BindingDevice::Gamepad) => {
+                button_name(settings.controls.gamepad.button(button.0))
+            }
+        };
+        for child in children.iter() {
+            set_caption(&mut texts, child, &caption);
+        }
+    }
+
+    let preset = match settings.graphics.preset {
+        Some(preset) => format!("Quality: {:?}", preset),
+        None => format!("Quality: Auto ({:?})", active.0),
+    };
+    let sharing = if settings.graphics.share_hardware {
+        "Share hardware info: ON"
+    } else {
+        "Share hardware info: OFF"
+    };
+    let lobby_scene = if settings.graphics.lobby_scene {
+        "Lobby scene: ON"
+    } else {
+        "Lobby scene: OFF"
+    };
+    let nameplate_caption = if settings.graphics.nameplates {
+        "Nameplates: ON"
+    } else {
+        "Nameplates: OFF"
+    };
+    for children in quality.iter() {
+        for child in children.iter() {
+            set_caption(&mut texts, child, &preset);
+        }
+    }
+    for children in share.iter() {
+        for child in children.iter() {
+            set_caption(&mut texts, child, sharing);
+        }
+    }
+    for children in scene.iter() {
+        for child in children.iter() {
+            set_caption(&mut texts, child, lobby_scene);
+        }
+    }
+    for children in nameplates.iter() {
+        for child in children.iter() {
+            set_caption(&mut texts, child, nameplate_caption);
+        }
+    }
+
+    let names: Vec<&str> = gamepads.iter().map(|name| name.as_str()).collect();
+    let status = if names.is_empty() {
+        "🎮 No gamepad connected".to_string()
+    } else {
+        format!("🎮 {}", names.join(", "))
+    };
+    for entity in gamepad_status.iter() {
+        set_caption(&mut texts, entity, &status);
+    }
+}
+
+fn set_caption(texts: &mut Query<&mut Text>, entity: Entity, caption: &str) {
+    if let Ok(mut text) = texts.get_mut(entity) {
+        if text.0 != caption {
+            text.0 = caption.to_string();
+        }
+    }
+}
+
+fn spawn_rebind_button(
+    row: &mut ChildSpawnerCommands,
+    width: f32,
+    button: RebindButton,
+    caption: String,
+) {
+    row.spawn((
+        Button,
+        Node {
+            width: Val::Px(width),
+            height: Val::Px(32.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.3, 0.3, 0.4)),
+        button,
+    ))
+    .with_children(|btn| {
+        btn.spawn((
+            Text::new(caption),
+            TextFont {
+                font_size: 14.0,
+                ..default()
+            },
+            TextColor(Color::srgb(1.0, 1.0, 1.0)),
+        ));
+    });
+}
+
+// Captions are filled in by `update_settings_page`
+fn spawn_graphics_button(panel: &mut ChildSpawnerCommands, marker: impl Bundle) {
+    panel
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(240.0),
+                height: Val::Px(32.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.3, 0.3, 0.4)),
+            marker,
+        ))
+        .with_children(|btn| {
+            btn.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            ));
+        });
+}
+
+fn swatch_border(index: usize, settings: &ClientSettings) -> Color {
+    if index == settings.account.color % PLAYER_COLORS.len() {
+        Color::WHITE
+    } else {
+        Color::srgb(0.2, 0.2, 0.2)
+    }
+}
+
+fn action_label(action: PlayerActions) -> &'static str {
+    match action {
+        PlayerActions::MoveLeft => "Move left",
+        PlayerActions::MoveRight => "Move right",
+        PlayerActions::Jump => "Jump",
+        PlayerActions::Sabotage => "Sabotage (ghost)",
+    }
+}
+
+// "A / ArrowLeft", with the Key/Digit prefixes of the key code names dropped
+fn key_names(controls: &ControlSettings, action: PlayerActions) -> String {
+    let keys = controls.keys(action);
+    if keys.is_empty() {
+        return "unbound".to_string();
+    }
+    keys.iter()
+        .map(|key| {
+            let name = format!("{:?}", key);
+            name.strip_prefix("Key")
+                .or_else(|| name.strip_prefix("Digit"))
+                .unwrap_or(&name)
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join(" / ")
+}
+
+// Xbox-style names for the face buttons, the layout most pads print
+fn button_name(button: GamepadButton) -> String {
+    match button {
+        GamepadButton::South => "A".to_string(),
+        GamepadButton::East => "B".to_string(),
+        GamepadButton::West => "X".to_string(),
+        GamepadButton::North => "Y".to_string(),
+        GamepadButton::DPadLeft => "D-pad ←".to_string(),
+        GamepadButton::DPadRight => "D-pad →".to_string(),
+        GamepadButton::DPadUp => "D-pad ↑".to_string(),
+        GamepadButton::DPadDown => "D-pad ↓".to_string(),
+        other => format!("{:?}", other),
+    }
+}
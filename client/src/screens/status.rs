@@ -0,0 +1,273 @@
+// 📡 Server status page: lobby and matchmaker health, region capacity and the MOTD,
+// so players can tell an outage from a problem on their end
+use bevy::prelude::*;
+use std::sync::Mutex;
+
+use shared::{RegionCapacity, ServiceStatusResponse, StatusDocument};
+
+use super::lobby::{spawn_back_button_simple, LobbyMode, LobbyUI, LobbyUIElements};
+use crate::http::{http_base, spawn, ApiRequest};
+
+// Seconds between re-checks while the page is open
+const REFRESH_INTERVAL_SECS: f32 = 10.0;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum ServiceHealth {
+    #[default]
+    Checking,
+    Up,
+    Down(String),
+    /// `/status.json` left the service out
+    NotChecked,
+}
+
+// What the services last said about themselves
+#[derive(Resource, Default)]
+pub struct ServerStatus {
+    pub lobby: ServiceHealth,
+    pub matchmaker: ServiceHealth,
+    /// Everything else `/status.json` checks, e.g. NATS and the Edgegap API
+    pub others: Vec<(String, ServiceHealth)>,
+    pub motd: Option<String>,
+    pub regions: Vec<RegionCapacity>,
+}
+
+enum StatusUpdate {
+    Lobby(ServiceHealth),
+    Matchmaker(ServiceHealth),
+    Details(ServiceStatusResponse),
+    Document(StatusDocument),
+}
+
+// Native checks answer from threads of their own, so this is no thread local
+static PENDING_STATUS: Mutex<Vec<StatusUpdate>> = Mutex::new(Vec::new());
+
+fn push_status(update: StatusUpdate) {
+    if let Ok(mut pending) = PENDING_STATUS.lock() {
+        pending.push(update);
+    }
+}
+
+// Check right away when the page opens, then every few seconds while it stays open
+pub(super) fn refresh_server_status(
+    time: Res<Time>,
+    lobby_q: Query<&LobbyUI>,
+    mut status: ResMut<ServerStatus>,
+    mut timer: Local<Option<Timer>>,
+) {
+    let Ok(ui) = lobby_q.single() else {
+        return;
+    };
+    if ui.lobby_mode != LobbyMode::Status {
+        *timer = None;
+        return;
+    }
+    match timer.as_mut() {
+        None => {
+            *timer = Some(Timer::from_seconds(
+                REFRESH_INTERVAL_SECS,
+                TimerMode::Repeating,
+            ));
+            status.lobby = ServiceHealth::Checking;
+            status.matchmaker = ServiceHealth::Checking;
+        }
+        Some(timer) => {
+            if !timer.tick(time.delta()).just_finished() {
+                return;
+            }
+        }
+    }
+    start_status_checks();
+}
+
+fn start_status_checks() {
+    spawn(async {
+        // One document covers everything, older lobby services need one call per service
+        let url = format!("{}/status.json", http_base());
+        if let Some(document) = fetch_body::<StatusDocument>(&url).await {
+            push_status(StatusUpdate::Document(document));
+            return;
+        }
+        probe_services();
+    });
+}
+
+fn probe_services() {
+    spawn(async {
+        let health = check_health(&format!("{}/lobby/health", http_base())).await;
+        push_status(StatusUpdate::Lobby(health));
+    });
+    spawn(async {
+        let health = check_health(&format!("{}/matchmaker/healthz", http_base())).await;
+        push_status(StatusUpdate::Matchmaker(health));
+    });
+    spawn(async {
+        // Older lobby services have no status endpoint, the page then just lacks details
+        let url = format!("{}/lobby/api/status", http_base());
+        if let Some(details) = fetch_body::<ServiceStatusResponse>(&url).await {
+            push_status(StatusUpdate::Details(details));
+        }
+    });
+}
+
+// The parsed body, whatever the status code: `/status.json` answers 503 with a
+// full document when a check fails. The page checks again soon, so no retries.
+async fn fetch_body<T: serde::de::DeserializeOwned>(url: &str) -> Option<T> {
+    let resp = ApiRequest::get(url).attempts(1).fetch().await.ok()?;
+    resp.json().ok()
+}
+
+async fn check_health(url: &str) -> ServiceHealth {
+    match ApiRequest::get(url).attempts(1).fetch().await {
+        Ok(resp) if resp.ok() => ServiceHealth::Up,
+        Ok(resp) => ServiceHealth::Down(format!("HTTP {}", resp.status)),
+        Err(_) => ServiceHealth::Down("unreachable".to_string()),
+    }
+}
+
+pub(super) fn pump_status_results(mut status: ResMut<ServerStatus>) {
+    let updates = match PENDING_STATUS.lock() {
+        Ok(mut pending) => std::mem::take(&mut *pending),
+        Err(_) => return,
+    };
+    for update in updates {
+        match update {
+            StatusUpdate::Lobby(health) => status.lobby = health,
+            StatusUpdate::Matchmaker(health) => status.matchmaker = health,
+            StatusUpdate::Details(details) => {
+                status.motd = details.motd;
+                status.regions = details.regions;
+            }
+            StatusUpdate::Document(document) => {
+                // The document came from the lobby service, so it is up
+                status.lobby = ServiceHealth::Up;
+                status.matchmaker = ServiceHealth::NotChecked;
+                status.others.clear();
+                for check in document.checks {
+                    let health = if check.ok {
+                        ServiceHealth::Up
+                    } else {
+                        ServiceHealth::Down(check.error.unwrap_or_else(|| "failing".to_string()))
+                    };
+                    match check.name.as_str() {
+                        "lobby" => status.lobby = health,
+                        "matchmaker" => status.matchmaker = health,
+                        _ => status.others.push((check.name, health)),
+                    }
+                }
+                status.motd = document.details.motd;
+                status.regions = document.details.regions;
+            }
+        }
+    }
+}
+
+pub(super) fn spawn_status_ui(
+    commands: &mut Commands,
+    container_entity: Entity,
+    status: &ServerStatus,
+) {
+    let title = commands
+        .spawn((
+            Text::new("📡 Server Status"),
+            TextFont {
+                font_size: 28.0,
+                ..default()
+            },
+            TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            Node {
+                margin: UiRect::all(Val::Px(20.0)),
+                ..default()
+            },
+            LobbyUIElements,
+        ))
+        .id();
+    commands.entity(container_entity).add_child(title);
+
+    let panel = commands
+        .spawn((
+            Node {
+                width: Val::Px(360.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(12.0)),
+                row_gap: Val::Px(6.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.4)),
+            LobbyUIElements,
+        ))
+        .with_children(|panel| {
+            health_line(panel, "Lobby", &status.lobby);
+            health_line(panel, "Matchmaker", &status.matchmaker);
+            for (service, health) in &status.others {
+                health_line(panel, service, health);
+            }
+
+            line(panel, "Regions", Color::srgb(0.8, 0.8, 0.8));
+            if status.regions.is_empty() {
+                line(panel, "  no region data", Color::srgb(0.6, 0.6, 0.6));
+            }
+            for region in &status.regions {
+                let full = region.players >= region.capacity;
+                line(
+                    panel,
+                    &format!(
+                        "  {} - {}/{} players{}",
+                        region.region,
+                        region.players,
+                        region.capacity,
+                        if full { " (full)" } else { "" }
+                    ),
+                    if full {
+                        Color::srgb(1.0, 0.6, 0.2)
+                    } else {
+                        Color::srgb(1.0, 1.0, 1.0)
+                    },
+                );
+            }
+
+            if let Some(motd) = &status.motd {
+                line(panel, &format!("📢 {}", motd), Color::srgb(1.0, 0.8, 0.2));
+            }
+
+            let down = [&status.lobby, &status.matchmaker]
+                .into_iter()
+                .chain(status.others.iter().map(|(_, health)| health))
+                .any(|health| matches!(health, ServiceHealth::Down(_)));
+            if down {
+                line(
+                    panel,
+                    "Online play is affected right now. LOCAL PLAY and CAMPAIGN still work.",
+                    Color::srgb(1.0, 0.5, 0.5),
+                );
+            }
+        })
+        .id();
+    commands.entity(container_entity).add_child(panel);
+
+    let back = spawn_back_button_simple(commands);
+    commands.entity(container_entity).add_child(back);
+}
+
+fn health_line(panel: &mut ChildSpawnerCommands, service: &str, health: &ServiceHealth) {
+    let (text, color) = match health {
+        ServiceHealth::Checking => ("⏳ checking...".to_string(), Color::srgb(0.8, 0.8, 0.8)),
+        ServiceHealth::Up => ("✅ up".to_string(), Color::srgb(0.4, 0.9, 0.4)),
+        ServiceHealth::Down(reason) => {
+            (format!("❌ down ({})", reason), Color::srgb(1.0, 0.4, 0.4))
+        }
+        ServiceHealth::NotChecked => ("not checked".to_string(), Color::srgb(0.6, 0.6, 0.6)),
+    };
+    line(panel, &format!("{}: {}", service, text), color);
+}
+
+fn line(panel: &mut ChildSpawnerCommands, text: &str, color: Color) {
+    panel.spawn((
+        Text::new(text),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(color),
+    ));
+}
@@ -0,0 +1,420 @@
+use bevy::input::gamepad::{GamepadConnection, GamepadConnectionEvent};
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::{GamepadControlDirection, InputMap};
+use serde::{Deserialize, Serialize};
+use shared::{PlayerActions, QualityPreset};
+use std::collections::HashMap;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+// localStorage key on the web, file name on native
+const SETTINGS_KEY: &str = "voidloop-quest.settings";
+
+// Campaign unlocks and records
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct CampaignProgress {
+    /// Number of campaign levels that can be played (the first one is always open)
+    #[serde(default)]
+    pub unlocked: usize,
+    /// Best completion time in seconds, keyed by level name
+    #[serde(default)]
+    pub best_times: HashMap<String, f32>,
+}
+
+impl CampaignProgress {
+    pub fn is_unlocked(&self, index: usize) -> bool {
+        index == 0 || index < self.unlocked
+    }
+
+    /// Record a finished level, returns true if it is a new best time
+    pub fn complete(&mut self, index: usize, level: &str, seconds: f32) -> bool {
+        self.unlocked = self.unlocked.max(index + 2);
+        let best = self.best_times.entry(level.to_string()).or_insert(f32::MAX);
+        if seconds < *best {
+            *best = seconds;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+// Voice chat preferences
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct VoiceSettings {
+    /// Player names whose voice is muted
+    #[serde(default)]
+    pub muted: Vec<String>,
+}
+
+// Sound effect preferences
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct AudioSettings {
+    /// Volume of all sound effects, 0..1
+    pub sfx_volume: f32,
+    /// Place other players' sounds where they are (distance falloff and stereo panning)
+    pub positional: bool,
+    /// Distance in world units within which other players are heard at full volume
+    pub hearing_range: f32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            sfx_volume: 1.0,
+            positional: true,
+            hearing_range: 150.0,
+        }
+    }
+}
+
+// Rendering quality and whether the player shares hardware info
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct GraphicsSettings {
+    /// Preset the player picked, None to follow the recommendation for their GPU
+    pub preset: Option<QualityPreset>,
+    /// Send the GPU and achieved frame rate to the lobby service, off until the player opts in
+    pub share_hardware: bool,
+    /// Animated scene behind the lobby menus, low-power devices may want it off
+    pub lobby_scene: bool,
+    /// Names, host crowns, pings and stun timers floating over other players
+    pub nameplates: bool,
+}
+
+impl Default for GraphicsSettings {
+    fn default() -> Self {
+        Self {
+            preset: None,
+            share_hardware: false,
+            lobby_scene: true,
+            nameplates: true,
+        }
+    }
+}
+
+// How the match camera follows the player
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct CameraSettings {
+    /// Distance in world units the player can move sideways before the camera follows
+    pub dead_zone_x: f32,
+    /// Same for up and down, so small jumps don't bob the view
+    pub dead_zone_y: f32,
+    /// How far the camera leads in the direction the player runs
+    pub look_ahead: f32,
+    /// How quickly the camera catches up, higher is snappier
+    pub follow_speed: f32,
+    /// Stop at the level's edges and floor instead of showing the void beyond them
+    pub clamp_to_level: bool,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            dead_zone_x: 60.0,
+            dead_zone_y: 40.0,
+            look_ahead: 80.0,
+            follow_speed: 4.0,
+            clamp_to_level: true,
+        }
+    }
+}
+
+// Finding a server for a match
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct MatchmakingSettings {
+    /// Seconds to wait for a server before giving up, 0 waits until the matchmaker answers.
+    /// Longer than the matchmaker's own deployment timeout, so its error usually comes first.
+    pub timeout_secs: u32,
+}
+
+impl Default for MatchmakingSettings {
+    fn default() -> Self {
+        Self { timeout_secs: 150 }
+    }
+}
+
+// Who the player is to others
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct AccountSettings {
+    /// Name shown in rooms and above the player in game
+    #[serde(default)]
+    pub display_name: String,
+    /// Account id the lobby service issued, None in builds without it
+    #[serde(default)]
+    pub player_id: Option<String>,
+    /// Index into `shared::PLAYER_COLORS`
+    #[serde(default)]
+    pub color: usize,
+}
+
+impl AccountSettings {
+    /// Give first-time players a random name they can change later, true if it did
+    fn fill_in_name(&mut self) -> bool {
+        if shared::sanitize_display_name(&self.display_name).is_some() {
+            return false;
+        }
+        self.display_name = format!("Player{}", rand::random::<u32>() % 1000);
+        true
+    }
+}
+
+// Keyboard bindings, every action can have several keys
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ControlSettings {
+    pub move_left: Vec<KeyCode>,
+    pub move_right: Vec<KeyCode>,
+    pub jump: Vec<KeyCode>,
+    pub sabotage: Vec<KeyCode>,
+    pub gamepad: GamepadControls,
+}
+
+// Gamepad bindings, one button per action. The left stick always moves as well.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct GamepadControls {
+    pub move_left: GamepadButton,
+    pub move_right: GamepadButton,
+    pub jump: GamepadButton,
+    pub sabotage: GamepadButton,
+}
+
+impl Default for GamepadControls {
+    fn default() -> Self {
+        Self {
+            move_left: GamepadButton::DPadLeft,
+            move_right: GamepadButton::DPadRight,
+            jump: GamepadButton::South,
+            sabotage: GamepadButton::West,
+        }
+    }
+}
+
+impl GamepadControls {
+    pub fn button(&self, action: PlayerActions) -> GamepadButton {
+        match action {
+            PlayerActions::MoveLeft => self.move_left,
+            PlayerActions::MoveRight => self.move_right,
+            PlayerActions::Jump => self.jump,
+            PlayerActions::Sabotage => self.sabotage,
+        }
+    }
+
+    fn button_mut(&mut self, action: PlayerActions) -> &mut GamepadButton {
+        match action {
+            PlayerActions::MoveLeft => &mut self.move_left,
+            PlayerActions::MoveRight => &mut self.move_right,
+            PlayerActions::Jump => &mut self.jump,
+            PlayerActions::Sabotage => &mut self.sabotage,
+        }
+    }
+
+    /// Bind `button` to `action`, the action that had it gets the old button of `action`
+    pub fn rebind(&mut self, action: PlayerActions, button: GamepadButton) {
+        let previous = self.button(action);
+        for other in PlayerActions::ALL {
+            if self.button(other) == button {
+                *self.button_mut(other) = previous;
+            }
+        }
+        *self.button_mut(action) = button;
+    }
+
+    /// Buttons and the left stick, read from whichever gamepad leafwing finds first
+    pub fn input_map(&self) -> InputMap<PlayerActions> {
+        let mut map = InputMap::default();
+        for action in PlayerActions::ALL {
+            map.insert(action, self.button(action));
+        }
+        map.insert(PlayerActions::MoveLeft, GamepadControlDirection::LEFT_LEFT);
+        map.insert(
+            PlayerActions::MoveRight,
+            GamepadControlDirection::LEFT_RIGHT,
+        );
+        map
+    }
+}
+
+impl Default for ControlSettings {
+    fn default() -> Self {
+        Self {
+            move_left: vec![KeyCode::KeyA, KeyCode::ArrowLeft],
+            move_right: vec![KeyCode::KeyD, KeyCode::ArrowRight],
+            jump: vec![KeyCode::Space, KeyCode::KeyW, KeyCode::ArrowUp],
+            sabotage: vec![KeyCode::KeyE],
+            gamepad: GamepadControls::default(),
+        }
+    }
+}
+
+impl ControlSettings {
+    pub fn keys(&self, action: PlayerActions) -> &[KeyCode] {
+        match action {
+            PlayerActions::MoveLeft => &self.move_left,
+            PlayerActions::MoveRight => &self.move_right,
+            PlayerActions::Jump => &self.jump,
+            PlayerActions::Sabotage => &self.sabotage,
+        }
+    }
+
+    fn keys_mut(&mut self, action: PlayerActions) -> &mut Vec<KeyCode> {
+        match action {
+            PlayerActions::MoveLeft => &mut self.move_left,
+            PlayerActions::MoveRight => &mut self.move_right,
+            PlayerActions::Jump => &mut self.jump,
+            PlayerActions::Sabotage => &mut self.sabotage,
+        }
+    }
+
+    /// Make `key` the main key of `action`, taking it away from any other action
+    pub fn rebind(&mut self, action: PlayerActions, key: KeyCode) {
+        for other in PlayerActions::ALL {
+            self.keys_mut(other).retain(|bound| *bound != key);
+        }
+        let keys = self.keys_mut(action);
+        if keys.is_empty() {
+            keys.push(key);
+        } else {
+            keys[0] = key;
+        }
+    }
+
+    /// Keys only, for when the gamepad belongs to someone else
+    pub fn keyboard_input_map(&self) -> InputMap<PlayerActions> {
+        let mut map = InputMap::default();
+        for action in PlayerActions::ALL {
+            for key in self.keys(action) {
+                map.insert(action, *key);
+            }
+        }
+        map
+    }
+
+    /// Keys and gamepad together
+    pub fn input_map(&self) -> InputMap<PlayerActions> {
+        let mut map = self.keyboard_input_map();
+        map.merge(&self.gamepad.input_map());
+        map
+    }
+}
+
+// Persistent client settings (localStorage on the web, a JSON file on native)
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ClientSettings {
+    #[serde(default)]
+    pub campaign: CampaignProgress,
+    #[serde(default)]
+    pub voice: VoiceSettings,
+    #[serde(default)]
+    pub audio: AudioSettings,
+    #[serde(default)]
+    pub account: AccountSettings,
+    #[serde(default)]
+    pub controls: ControlSettings,
+    #[serde(default)]
+    pub graphics: GraphicsSettings,
+    #[serde(default)]
+    pub camera: CameraSettings,
+    #[serde(default)]
+    pub matchmaking: MatchmakingSettings,
+}
+
+impl ClientSettings {
+    /// Load saved settings, falling back to defaults if there are none or they are unreadable
+    pub fn load() -> Self {
+        let Some(json) = read_settings() else {
+            return Self::default();
+        };
+        serde_json::from_str(&json).unwrap_or_else(|e| {
+            warn!("⚙️ Ignoring unreadable settings: {}", e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) {
+        match serde_json::to_string_pretty(self) {
+            Ok(json) => write_settings(&json),
+            Err(e) => warn!("⚙️ Failed to serialize settings: {}", e),
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_settings() -> Option<String> {
+    let storage = web_sys::window()?.local_storage().ok()??;
+    storage.get_item(SETTINGS_KEY).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_settings(json: &str) {
+    let storage = web_sys::window().and_then(|w| w.local_storage().ok().flatten());
+    match storage {
+        Some(storage) => {
+            if storage.set_item(SETTINGS_KEY, json).is_err() {
+                warn!("⚙️ Failed to write settings to localStorage");
+            }
+        }
+        None => warn!("⚙️ localStorage unavailable, settings not saved"),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn settings_path() -> PathBuf {
+    if let Ok(path) = std::env::var("VOIDLOOP_SETTINGS") {
+        return PathBuf::from(path);
+    }
+    let config_dir = std::env::var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|_| std::env::var("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_else(|_| PathBuf::from("."));
+    config_dir
+        .join("voidloop-quest")
+        .join(format!("{}.json", SETTINGS_KEY))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_settings() -> Option<String> {
+    std::fs::read_to_string(settings_path()).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_settings(json: &str) {
+    let path = settings_path();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Err(e) = std::fs::write(&path, json) {
+        warn!("⚙️ Failed to write settings to {}: {}", path.display(), e);
+    }
+}
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let mut settings = ClientSettings::load();
+        if settings.account.fill_in_name() {
+            settings.save();
+        }
+        app.insert_resource(settings)
+            .add_systems(Update, log_gamepad_connections);
+    }
+}
+
+fn log_gamepad_connections(mut events: EventReader<GamepadConnectionEvent>) {
+    for event in events.read() {
+        match &event.connection {
+            GamepadConnection::Connected { name, .. } => {
+                info!("🎮 Gamepad connected: {}", name);
+            }
+            GamepadConnection::Disconnected => {
+                info!("🎮 Gamepad {:?} disconnected", event.gamepad);
+            }
+        }
+    }
+}
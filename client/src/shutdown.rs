@@ -0,0 +1,92 @@
+// Countdown banner while the game server drains its players before shutting down
+use bevy::prelude::*;
+use lightyear::prelude::*;
+
+use crate::screens::AppState;
+use shared::ServerShuttingDown;
+
+// Seconds until the server goes away, counted down locally
+#[derive(Resource)]
+struct ShutdownCountdown(f32);
+
+#[derive(Component)]
+struct ShutdownBanner;
+
+#[derive(Component)]
+struct ShutdownText;
+
+pub struct ShutdownPlugin;
+
+impl Plugin for ShutdownPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (receive_shutdown, update_banner)
+                .chain()
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(OnExit(AppState::InGame), cleanup_banner);
+    }
+}
+
+fn receive_shutdown(
+    mut commands: Commands,
+    mut receivers: Query<&mut MessageReceiver<ServerShuttingDown>>,
+) {
+    for mut receiver in receivers.iter_mut() {
+        for message in receiver.receive() {
+            info!("🛑 Server shutting down in {}s", message.seconds);
+            commands.insert_resource(ShutdownCountdown(message.seconds));
+        }
+    }
+}
+
+fn update_banner(
+    mut commands: Commands,
+    time: Res<Time>,
+    countdown: Option<ResMut<ShutdownCountdown>>,
+    mut banner: Query<&mut Text, With<ShutdownText>>,
+) {
+    let Some(mut countdown) = countdown else {
+        return;
+    };
+    countdown.0 = (countdown.0 - time.delta_secs()).max(0.0);
+    let label = format!("⚠️ Server shutting down in {}s", countdown.0.ceil());
+
+    if let Ok(mut text) = banner.single_mut() {
+        if **text != label {
+            **text = label;
+        }
+        return;
+    }
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(8.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ShutdownBanner,
+        ))
+        .with_children(|row| {
+            row.spawn((
+                Text::new(label),
+                TextFont {
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 0.8, 0.3)),
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+                ShutdownText,
+            ));
+        });
+}
+
+fn cleanup_banner(mut commands: Commands, banners: Query<Entity, With<ShutdownBanner>>) {
+    for entity in banners.iter() {
+        commands.entity(entity).despawn();
+    }
+    commands.remove_resource::<ShutdownCountdown>();
+}
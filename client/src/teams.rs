@@ -0,0 +1,95 @@
+// Teams: the rules the server plays by, the team the lobby room put us on (sent to the
+// server with our profile) and a HUD label in our team's color
+use bevy::prelude::*;
+
+#[cfg(feature = "bevygap")]
+use lightyear::prelude::*;
+
+use crate::screens::AppState;
+use shared::{PlayerId, Team, TeamRules};
+
+/// Team the lobby room put us on, None outside team rooms
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoomTeam(pub Option<usize>);
+
+#[derive(Component)]
+struct TeamHudText;
+
+pub struct TeamsPlugin;
+
+impl Plugin for TeamsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RoomTeam>()
+            .add_systems(Update, update_team_hud.run_if(in_state(AppState::InGame)))
+            .add_systems(OnExit(AppState::InGame), cleanup_teams);
+
+        #[cfg(feature = "bevygap")]
+        // Also in the lobby, the rules arrive as soon as we connect
+        app.add_systems(Update, receive_team_rules);
+    }
+}
+
+#[cfg(feature = "bevygap")]
+fn receive_team_rules(
+    mut commands: Commands,
+    mut receivers: Query<&mut MessageReceiver<TeamRules>>,
+) {
+    for mut receiver in receivers.iter_mut() {
+        for rules in receiver.receive() {
+            info!(
+                "👥 Teams: {}, friendly fire: {}",
+                rules.teams, rules.friendly_fire
+            );
+            commands.insert_resource(rules);
+        }
+    }
+}
+
+fn update_team_hud(
+    mut commands: Commands,
+    players: Query<(&PlayerId, &Team)>,
+    mut hud: Query<(Entity, &mut Text, &mut TextColor), With<TeamHudText>>,
+) {
+    let Some(team) = players
+        .iter()
+        .find(|(id, _)| id.id == 0)
+        .map(|(_, team)| *team)
+    else {
+        for (entity, ..) in hud.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    };
+
+    let label = format!("👥 {}", team.name());
+    if let Ok((_, mut text, mut color)) = hud.single_mut() {
+        if text.0 != label {
+            text.0 = label;
+            color.0 = team.color();
+        }
+        return;
+    }
+    commands.spawn((
+        Text::new(label),
+        TextFont {
+            font_size: 18.0,
+            ..default()
+        },
+        TextColor(team.color()),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(70.0),
+            left: Val::Px(16.0),
+            ..default()
+        },
+        TeamHudText,
+    ));
+}
+
+fn cleanup_teams(mut commands: Commands, hud: Query<Entity, With<TeamHudText>>) {
+    for entity in hud.iter() {
+        commands.entity(entity).despawn();
+    }
+    // The next match says again
+    commands.insert_resource(TeamRules::default());
+}
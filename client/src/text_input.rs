@@ -0,0 +1,210 @@
+// Single-line text fields for the lobby screens: click to focus, type, Backspace deletes,
+// Enter submits, Escape or a click anywhere else drops the focus
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+
+// Seconds the caret stays on, then off
+const CARET_BLINK_SECS: f32 = 0.5;
+
+/// A text field, spawn it with `spawn_text_input`
+#[derive(Component)]
+pub struct TextInput {
+    pub value: String,
+    placeholder: String,
+    max_len: usize,
+    masked: bool,
+}
+
+impl TextInput {
+    pub fn new(value: impl Into<String>, placeholder: impl Into<String>, max_len: usize) -> Self {
+        Self {
+            value: value.into(),
+            placeholder: placeholder.into(),
+            max_len,
+            masked: false,
+        }
+    }
+
+    /// Show dots instead of the text, for passwords
+    pub fn masked(mut self) -> Self {
+        self.masked = true;
+        self
+    }
+
+    fn push(&mut self, c: char) {
+        if !c.is_control() && self.value.chars().count() < self.max_len {
+            self.value.push(c);
+        }
+    }
+}
+
+// The text inside a field
+#[derive(Component)]
+struct TextInputText;
+
+/// The field keyboard input goes to, if any
+#[derive(Resource, Default)]
+pub struct FocusedInput(pub Option<Entity>);
+
+/// Enter was pressed in a field
+#[derive(Event)]
+pub struct TextSubmitted {
+    pub entity: Entity,
+    pub value: String,
+}
+
+pub struct TextInputPlugin;
+
+impl Plugin for TextInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FocusedInput>()
+            .add_event::<TextSubmitted>()
+            .add_systems(
+                Update,
+                (focus_inputs, type_into_focused, draw_inputs).chain(),
+            );
+    }
+}
+
+/// A field `width` pixels wide. Add a marker component to the returned entity to find
+/// it again.
+pub fn spawn_text_input(commands: &mut Commands, input: TextInput, width: f32) -> Entity {
+    commands
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(width),
+                height: Val::Px(36.0),
+                border: UiRect::all(Val::Px(2.0)),
+                padding: UiRect::horizontal(Val::Px(8.0)),
+                align_items: AlignItems::Center,
+                overflow: Overflow::clip(),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.15, 0.15, 0.2)),
+            BorderColor(Color::srgb(0.4, 0.4, 0.4)),
+            input,
+        ))
+        .with_children(|field| {
+            field.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 1.0, 1.0)),
+                TextInputText,
+            ));
+        })
+        .id()
+}
+
+fn focus_inputs(
+    mouse: Res<ButtonInput<MouseButton>>,
+    inputs: Query<(Entity, &Interaction), With<TextInput>>,
+    pressed: Query<(Entity, &Interaction), (Changed<Interaction>, With<TextInput>)>,
+    mut focus: ResMut<FocusedInput>,
+) {
+    // The focused field went away with its screen
+    if focus.0.is_some_and(|entity| !inputs.contains(entity)) {
+        focus.0 = None;
+    }
+    if let Some((entity, _)) = pressed
+        .iter()
+        .find(|(_, interaction)| **interaction == Interaction::Pressed)
+    {
+        focus.0 = Some(entity);
+        return;
+    }
+    if mouse.just_pressed(MouseButton::Left)
+        && !inputs
+            .iter()
+            .any(|(_, interaction)| *interaction == Interaction::Pressed)
+    {
+        focus.0 = None;
+    }
+}
+
+fn type_into_focused(
+    mut keys: EventReader<KeyboardInput>,
+    mut focus: ResMut<FocusedInput>,
+    mut inputs: Query<&mut TextInput>,
+    mut submitted: EventWriter<TextSubmitted>,
+) {
+    let Some(entity) = focus.0 else {
+        keys.clear();
+        return;
+    };
+    let Ok(mut input) = inputs.get_mut(entity) else {
+        keys.clear();
+        return;
+    };
+    for key in keys.read().filter(|key| key.state.is_pressed()) {
+        match &key.logical_key {
+            Key::Enter => {
+                submitted.write(TextSubmitted {
+                    entity,
+                    value: input.value.clone(),
+                });
+                focus.0 = None;
+                return;
+            }
+            Key::Escape => {
+                focus.0 = None;
+                return;
+            }
+            Key::Backspace => {
+                input.value.pop();
+            }
+            Key::Space => input.push(' '),
+            Key::Character(text) => {
+                for c in text.chars() {
+                    input.push(c);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn draw_inputs(
+    time: Res<Time>,
+    focus: Res<FocusedInput>,
+    mut inputs: Query<(Entity, &TextInput, &Children, &mut BorderColor)>,
+    mut texts: Query<(&mut Text, &mut TextColor), With<TextInputText>>,
+) {
+    let caret_on = (time.elapsed_secs() / CARET_BLINK_SECS) as u32 % 2 == 0;
+    for (entity, input, children, mut border) in inputs.iter_mut() {
+        let focused = focus.0 == Some(entity);
+        let shown = if input.masked {
+            "•".repeat(input.value.chars().count())
+        } else {
+            input.value.clone()
+        };
+        let (content, color) = if shown.is_empty() && !focused {
+            (input.placeholder.clone(), Color::srgb(0.5, 0.5, 0.5))
+        } else {
+            let caret = if focused && caret_on { "|" } else { "" };
+            (format!("{}{}", shown, caret), Color::srgb(1.0, 1.0, 1.0))
+        };
+        for child in children.iter() {
+            if let Ok((mut text, mut text_color)) = texts.get_mut(child) {
+                if text.0 != content {
+                    text.0 = content.clone();
+                }
+                if text_color.0 != color {
+                    text_color.0 = color;
+                }
+            }
+        }
+
+        let wanted = if focused {
+            Color::srgb(0.4, 0.7, 1.0)
+        } else {
+            Color::srgb(0.4, 0.4, 0.4)
+        };
+        if border.0 != wanted {
+            border.0 = wanted;
+        }
+    }
+}
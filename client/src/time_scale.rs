@@ -0,0 +1,158 @@
+// Practice controls for games simulated here (local play and the campaign): slow motion,
+// pause and stepping one tick at a time. Only the fixed timestep changes, so every tick
+// still simulates the same `FIXED_DT` and the rest of the client (camera, animations,
+// menus) keeps running at full speed.
+//
+//   -  slower (1x, 0.5x, 0.25x)     =  faster
+//   P  pause / resume               .  one tick while paused
+use bevy::input::common_conditions::input_just_pressed;
+use bevy::prelude::*;
+use std::time::Duration;
+
+use crate::campaign::ActiveCampaign;
+use crate::local_play::LocalGame;
+use crate::screens::AppState;
+
+// Speeds to pick from, slowest last
+const RATES: [f64; 3] = [1.0, 0.5, 0.25];
+// Timestep while paused, long enough that no tick comes due on its own
+const PAUSED_TIMESTEP: Duration = Duration::from_secs(3600);
+
+#[derive(Resource, Default)]
+struct TimeScale {
+    // Index into `RATES`
+    rate: usize,
+    paused: bool,
+    // Timestep before any scaling, captured the first time it is changed
+    base_timestep: Option<Duration>,
+}
+
+impl TimeScale {
+    fn is_normal(&self) -> bool {
+        self.rate == 0 && !self.paused
+    }
+}
+
+#[derive(Component)]
+struct TimeScaleText;
+
+pub struct TimeScalePlugin;
+
+impl Plugin for TimeScalePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TimeScale>()
+            .add_systems(OnEnter(AppState::InGame), spawn_time_scale_hud)
+            .add_systems(
+                Update,
+                (
+                    change_time_scale,
+                    apply_time_scale,
+                    step_one_tick.run_if(is_paused.and(input_just_pressed(KeyCode::Period))),
+                    update_time_scale_hud,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::InGame).and(simulates_locally)),
+            )
+            .add_systems(OnExit(AppState::InGame), reset_time_scale);
+    }
+}
+
+// Online the server owns the clock
+fn simulates_locally(local: Option<Res<LocalGame>>, campaign: Option<Res<ActiveCampaign>>) -> bool {
+    local.is_some() || campaign.is_some()
+}
+
+fn is_paused(scale: Res<TimeScale>) -> bool {
+    scale.paused
+}
+
+fn change_time_scale(keys: Res<ButtonInput<KeyCode>>, mut scale: ResMut<TimeScale>) {
+    if keys.just_pressed(KeyCode::Minus) && scale.rate + 1 < RATES.len() {
+        scale.rate += 1;
+        info!("🐢 Simulation at {}x", RATES[scale.rate]);
+    }
+    if keys.just_pressed(KeyCode::Equal) && scale.rate > 0 {
+        scale.rate -= 1;
+        info!("🐢 Simulation at {}x", RATES[scale.rate]);
+    }
+    if keys.just_pressed(KeyCode::KeyP) {
+        scale.paused = !scale.paused;
+        info!(
+            "⏸️ Simulation {}",
+            if scale.paused { "paused" } else { "resumed" }
+        );
+    }
+}
+
+fn apply_time_scale(mut scale: ResMut<TimeScale>, mut fixed: ResMut<Time<Fixed>>) {
+    // Time that passes while paused is dropped, or resuming would catch up on all of it
+    if scale.paused {
+        let overstep = fixed.overstep();
+        fixed.discard_overstep(overstep);
+    }
+    if !scale.is_changed() {
+        return;
+    }
+    let base = *scale.base_timestep.get_or_insert(fixed.timestep());
+    fixed.set_timestep(if scale.paused {
+        PAUSED_TIMESTEP
+    } else {
+        base.div_f64(RATES[scale.rate])
+    });
+}
+
+// Runs the fixed schedule once, the way the main loop would when a tick is due
+fn step_one_tick(world: &mut World) {
+    *world.resource_mut::<Time>() = world.resource::<Time<Fixed>>().as_generic();
+    world.run_schedule(FixedMain);
+    *world.resource_mut::<Time>() = world.resource::<Time<Virtual>>().as_generic();
+}
+
+fn spawn_time_scale_hud(mut commands: Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 18.0,
+            ..default()
+        },
+        TextColor(Color::srgb(1.0, 0.9, 0.4)),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(12.0),
+            right: Val::Px(12.0),
+            ..default()
+        },
+        TimeScaleText,
+    ));
+}
+
+fn update_time_scale_hud(scale: Res<TimeScale>, mut texts: Query<&mut Text, With<TimeScaleText>>) {
+    if !scale.is_changed() {
+        return;
+    }
+    let caption = if scale.paused {
+        "⏸️ PAUSED  (P resume, . step)".to_string()
+    } else if scale.is_normal() {
+        String::new()
+    } else {
+        format!("🐢 {}x  (- slower, = faster)", RATES[scale.rate])
+    };
+    for mut text in texts.iter_mut() {
+        text.0 = caption.clone();
+    }
+}
+
+fn reset_time_scale(
+    mut commands: Commands,
+    mut scale: ResMut<TimeScale>,
+    mut fixed: ResMut<Time<Fixed>>,
+    texts: Query<Entity, With<TimeScaleText>>,
+) {
+    if let Some(base) = scale.base_timestep {
+        fixed.set_timestep(base);
+    }
+    *scale = TimeScale::default();
+    for entity in texts.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,235 @@
+// Vey's animation controller: picks a state for every player from the replicated movement
+// (idle, running, jumping, falling, stunned), blends into that state's clip instead of
+// cutting to it, and squashes the model for a moment when it lands.
+//
+// New states plug in without touching this file: give them a clip with
+// `VeyAnimations::set_clip` and a rule with `add_rule`, picked from the player's movement.
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::client_plugin::VeyModelEntity;
+use crate::screens::AppState;
+use shared::{Player, PlayerAnimationState};
+
+// Scale the Vey model is spawned with, the capsule fallback keeps 1
+const VEY_SCALE: f32 = 50.0;
+// Moving up slower than this already counts as falling, so the apex isn't a flicker
+const FALLING_BELOW_SPEED: f32 = 20.0;
+
+/// What a player's model is doing
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VeyState {
+    Idle,
+    Running,
+    Jumping,
+    Falling,
+    Stunned,
+}
+
+/// The clip a state plays
+#[derive(Clone, Copy, Debug)]
+pub struct VeyClip {
+    pub node: AnimationNodeIndex,
+    /// Loop it, otherwise it holds its last frame
+    pub repeat: bool,
+}
+
+impl VeyClip {
+    pub fn looping(node: AnimationNodeIndex) -> Self {
+        Self { node, repeat: true }
+    }
+
+    pub fn once(node: AnimationNodeIndex) -> Self {
+        Self {
+            node,
+            repeat: false,
+        }
+    }
+}
+
+/// What a state rule gets to look at
+pub struct VeyContext<'a> {
+    pub player: &'a Player,
+    pub animation: &'a PlayerAnimationState,
+}
+
+struct VeyStateRule {
+    priority: i32,
+    state: VeyState,
+    applies: fn(&VeyContext) -> bool,
+}
+
+/// Clips, rules and timing of the animation controller
+#[derive(Resource)]
+pub struct VeyAnimations {
+    clips: HashMap<VeyState, VeyClip>,
+    // Highest priority first
+    rules: Vec<VeyStateRule>,
+    /// How long the old clip fades out while the new one fades in
+    pub blend: Duration,
+    /// How much flatter the model gets on landing, 0..1
+    pub landing_squash: f32,
+    /// How long it takes to spring back from the landing squash
+    pub landing_secs: f32,
+}
+
+impl VeyAnimations {
+    /// The built-in rules, with `idle` as the clip for states that have none
+    pub fn new(idle: VeyClip) -> Self {
+        let mut animations = Self {
+            clips: HashMap::from([(VeyState::Idle, idle)]),
+            rules: Vec::new(),
+            blend: Duration::from_millis(150),
+            landing_squash: 0.25,
+            landing_secs: 0.15,
+        };
+        animations.add_rule(100, VeyState::Stunned, |context| {
+            context.animation.is_stunned
+        });
+        animations.add_rule(30, VeyState::Jumping, |context| {
+            context.animation.is_jumping && context.player.velocity.y > FALLING_BELOW_SPEED
+        });
+        animations.add_rule(20, VeyState::Falling, |context| {
+            context.animation.is_jumping
+        });
+        animations.add_rule(10, VeyState::Running, |context| context.animation.is_moving);
+        animations
+    }
+
+    /// Play `clip` in `state`, replacing the clip it had
+    pub fn set_clip(&mut self, state: VeyState, clip: VeyClip) -> &mut Self {
+        self.clips.insert(state, clip);
+        self
+    }
+
+    /// Pick `state` whenever `applies` holds and no rule of a higher priority does. Players
+    /// no rule applies to are idle.
+    pub fn add_rule(
+        &mut self,
+        priority: i32,
+        state: VeyState,
+        applies: fn(&VeyContext) -> bool,
+    ) -> &mut Self {
+        let index = self.rules.partition_point(|rule| rule.priority >= priority);
+        self.rules.insert(
+            index,
+            VeyStateRule {
+                priority,
+                state,
+                applies,
+            },
+        );
+        self
+    }
+
+    fn state(&self, context: &VeyContext) -> VeyState {
+        self.rules
+            .iter()
+            .find(|rule| (rule.applies)(context))
+            .map_or(VeyState::Idle, |rule| rule.state)
+    }
+
+    fn clip(&self, state: VeyState) -> VeyClip {
+        self.clips
+            .get(&state)
+            .or_else(|| self.clips.get(&VeyState::Idle))
+            .copied()
+            .expect("VeyAnimations always has an idle clip")
+    }
+}
+
+// Where a player's model is in its state machine
+#[derive(Component, Default)]
+struct VeyAnimator {
+    state: Option<VeyState>,
+    // Clip playing right now, states sharing a clip carry on with it
+    node: Option<AnimationNodeIndex>,
+    airborne: bool,
+    // Seconds of landing squash left
+    landing: f32,
+}
+
+pub struct VeyAnimationPlugin;
+
+impl Plugin for VeyAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (add_animators, animate_vey_models)
+                .chain()
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+fn add_animators(mut commands: Commands, players: Query<Entity, Added<Player>>) {
+    for entity in players.iter() {
+        commands.entity(entity).insert(VeyAnimator::default());
+    }
+}
+
+fn animate_vey_models(
+    time: Res<Time>,
+    animations: Option<Res<VeyAnimations>>,
+    mut players: Query<(&Player, &PlayerAnimationState, &mut VeyAnimator, &Children)>,
+    mut models: Query<(&VeyModelEntity, &mut Transform)>,
+    mut animation_players: Query<(&mut AnimationPlayer, &mut AnimationTransitions)>,
+) {
+    let Some(animations) = animations else {
+        return;
+    };
+    for (player, animation, mut animator, children) in players.iter_mut() {
+        let state = animations.state(&VeyContext { player, animation });
+
+        if animator.airborne && !animation.is_jumping {
+            animator.landing = animations.landing_secs;
+        }
+        animator.airborne = animation.is_jumping;
+        animator.landing = (animator.landing - time.delta_secs()).max(0.0);
+        let squash = if animations.landing_secs > 0.0 {
+            animations.landing_squash * animator.landing / animations.landing_secs
+        } else {
+            0.0
+        };
+
+        if animator.state != Some(state) {
+            debug!("🎬 {:?} -> {:?}", animator.state, state);
+            animator.state = Some(state);
+        }
+        let clip = animations.clip(state);
+
+        for child in children.iter() {
+            let Ok((model, mut transform)) = models.get_mut(child) else {
+                continue;
+            };
+            let scale = if model.animation_player == Entity::PLACEHOLDER {
+                1.0
+            } else {
+                VEY_SCALE
+            };
+            let facing = if animation.facing_left { -1.0 } else { 1.0 };
+            // Stomped players are squashed flat until the stun wears off
+            let stunned = if animation.is_stunned { 0.5 } else { 1.0 };
+            transform.scale = Vec3::new(
+                scale * facing * (1.0 + squash / 2.0),
+                scale * stunned * (1.0 - squash),
+                scale,
+            );
+
+            if animator.node == Some(clip.node) {
+                continue;
+            }
+            let Ok((mut animation_player, mut transitions)) =
+                animation_players.get_mut(model.animation_player)
+            else {
+                continue;
+            };
+            let active = transitions.play(&mut animation_player, clip.node, animations.blend);
+            if clip.repeat {
+                active.repeat();
+            }
+            animator.node = Some(clip.node);
+        }
+    }
+}
@@ -0,0 +1,587 @@
+// Room voice chat: peer-to-peer WebRTC audio between the players in a lobby room.
+// The lobby service only relays the signaling, audio never passes through our servers.
+use bevy::prelude::*;
+
+use crate::screens::LobbyUI;
+use crate::settings::ClientSettings;
+
+// Hold to talk, the microphone is silent otherwise
+const PUSH_TO_TALK_KEY: KeyCode = KeyCode::KeyV;
+
+// Voice session state the HUD is built from
+#[derive(Resource, Default)]
+pub struct VoiceChat {
+    /// Room the voice session belongs to, kept while the match is running
+    room: Option<String>,
+    /// Other players in the room's voice chat
+    peers: Vec<String>,
+    transmitting: bool,
+}
+
+#[derive(Component)]
+struct VoicePanel;
+
+#[derive(Component)]
+struct MuteButton(String);
+
+pub struct VoicePlugin;
+
+impl Plugin for VoicePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VoiceChat>().add_systems(
+            Update,
+            (
+                follow_room,
+                push_to_talk,
+                #[cfg(all(target_arch = "wasm32", feature = "bevygap"))]
+                sync_peers,
+                handle_mute_buttons,
+                update_voice_panel,
+            )
+                .chain(),
+        );
+    }
+}
+
+// Join the room's voice when entering a room and leave with it. The lobby UI is gone
+// during the match, so the session carries on until the player is back in the lobby.
+fn follow_room(lobby: Query<&LobbyUI>, mut voice: ResMut<VoiceChat>) {
+    let Ok(ui) = lobby.single() else {
+        return;
+    };
+    let room = (!ui.room_id.is_empty()).then(|| ui.room_id.clone());
+    if voice.room == room {
+        return;
+    }
+
+    #[cfg(all(target_arch = "wasm32", feature = "bevygap"))]
+    match &room {
+        Some(room) => web::join(room, &ui.player_name),
+        None => web::leave(),
+    }
+    voice.room = room;
+    voice.peers.clear();
+}
+
+fn push_to_talk(keys: Res<ButtonInput<KeyCode>>, mut voice: ResMut<VoiceChat>) {
+    let held = voice.room.is_some() && keys.pressed(PUSH_TO_TALK_KEY);
+    if voice.transmitting == held {
+        return;
+    }
+    voice.transmitting = held;
+    #[cfg(all(target_arch = "wasm32", feature = "bevygap"))]
+    web::set_transmitting(held);
+}
+
+// Pick up peers connecting and leaving, new ones start out with the saved mute
+#[cfg(all(target_arch = "wasm32", feature = "bevygap"))]
+fn sync_peers(mut voice: ResMut<VoiceChat>, settings: Res<ClientSettings>) {
+    let peers = web::peers();
+    if voice.peers == peers {
+        return;
+    }
+    for peer in peers.iter().filter(|p| !voice.peers.contains(*p)) {
+        web::set_muted(peer, settings.voice.muted.contains(peer));
+    }
+    voice.peers = peers;
+}
+
+// Mutes are saved by player name so they stick across rooms
+fn handle_mute_buttons(
+    buttons: Query<(&Interaction, &MuteButton), Changed<Interaction>>,
+    mut settings: ResMut<ClientSettings>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let muted = &mut settings.voice.muted;
+        let now_muted = match muted.iter().position(|name| *name == button.0) {
+            Some(index) => {
+                muted.remove(index);
+                false
+            }
+            None => {
+                muted.push(button.0.clone());
+                true
+            }
+        };
+        #[cfg(all(target_arch = "wasm32", feature = "bevygap"))]
+        web::set_muted(&button.0, now_muted);
+        info!(
+            "🎙 {} {}",
+            if now_muted { "Muted" } else { "Unmuted" },
+            button.0
+        );
+        settings.save();
+    }
+}
+
+// Bottom-left list of who is in voice, with a mute toggle per player
+fn update_voice_panel(
+    mut commands: Commands,
+    voice: Res<VoiceChat>,
+    settings: Res<ClientSettings>,
+    panels: Query<Entity, With<VoicePanel>>,
+) {
+    if !voice.is_changed() && !settings.is_changed() {
+        return;
+    }
+    for entity in panels.iter() {
+        commands.entity(entity).despawn();
+    }
+    if voice.room.is_none() {
+        return;
+    }
+
+    let header = if voice.transmitting {
+        "🎙 Talking...".to_string()
+    } else {
+        format!("🎙 Voice - hold {:?} to talk", PUSH_TO_TALK_KEY)
+    };
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(16.0),
+                left: Val::Px(16.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            VoicePanel,
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                Text::new(header),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 1.0, 1.0)),
+            ));
+            for peer in &voice.peers {
+                let muted = settings.voice.muted.contains(peer);
+                panel
+                    .spawn((
+                        Button,
+                        Node {
+                            padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+                            ..default()
+                        },
+                        BackgroundColor(if muted {
+                            Color::srgb(0.5, 0.2, 0.2)
+                        } else {
+                            Color::srgb(0.2, 0.2, 0.3)
+                        }),
+                        MuteButton(peer.clone()),
+                    ))
+                    .with_children(|button| {
+                        button.spawn((
+                            Text::new(format!("{} {}", if muted { "🔇" } else { "🔊" }, peer)),
+                            TextFont {
+                                font_size: 12.0,
+                                ..default()
+                            },
+                            TextColor(Color::srgb(1.0, 1.0, 1.0)),
+                        ));
+                    });
+            }
+        });
+}
+
+// Browser side: microphone, one RTCPeerConnection per other player, and the relay socket
+#[cfg(all(target_arch = "wasm32", feature = "bevygap"))]
+mod web {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+    use wasm_bindgen_futures::{spawn_local, JsFuture};
+    use web_sys::js_sys::{encode_uri_component, Array, Reflect};
+    use web_sys::{
+        console, HtmlAudioElement, MediaStream, MediaStreamConstraints, MediaStreamTrack,
+        MessageEvent, RtcConfiguration, RtcIceCandidateInit, RtcIceServer, RtcPeerConnection,
+        RtcPeerConnectionIceEvent, RtcSdpType, RtcSessionDescriptionInit, RtcTrackEvent, WebSocket,
+    };
+
+    use crate::http::http_base;
+    use shared::VoiceSignal;
+
+    const STUN_SERVER: &str = "stun:stun.l.google.com:19302";
+
+    struct Peer {
+        connection: RtcPeerConnection,
+        audio: HtmlAudioElement,
+        /// Candidates that arrived before the remote description was set
+        pending_ice: Vec<RtcIceCandidateInit>,
+    }
+
+    #[derive(Default)]
+    struct Session {
+        socket: Option<WebSocket>,
+        microphone: Option<MediaStream>,
+        peers: HashMap<String, Peer>,
+        /// Bumped on every join/leave so callbacks from an old session do nothing
+        generation: u32,
+    }
+
+    thread_local! {
+        static SESSION: RefCell<Session> = RefCell::new(Session::default());
+    }
+
+    pub fn join(room: &str, player: &str) {
+        leave();
+        let generation = SESSION.with(|s| s.borrow().generation);
+        let url = format!(
+            "{}/lobby/api/rooms/{}/voice?player_name={}",
+            http_base().replacen("http", "ws", 1),
+            room,
+            String::from(encode_uri_component(player))
+        );
+
+        spawn_local(async move {
+            // Players without a microphone (or who deny access) can still listen
+            let microphone = match open_microphone().await {
+                Ok(microphone) => Some(microphone),
+                Err(e) => {
+                    console::warn_1(&format!("🎙 No microphone, listening only: {:?}", e).into());
+                    None
+                }
+            };
+            if !is_current(generation) {
+                if let Some(microphone) = microphone {
+                    stop_tracks(&microphone);
+                }
+                return;
+            }
+
+            let socket = match WebSocket::new(&url) {
+                Ok(socket) => socket,
+                Err(e) => {
+                    console::error_1(&e);
+                    return;
+                }
+            };
+            let on_message = Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+                let Some(text) = event.data().as_string() else {
+                    return;
+                };
+                match serde_json::from_str::<VoiceSignal>(&text) {
+                    Ok(signal) => handle_signal(signal, generation),
+                    Err(e) => console::warn_1(&format!("🎙 Bad voice signal: {}", e).into()),
+                }
+            });
+            socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+            on_message.forget();
+
+            SESSION.with(|s| {
+                let mut session = s.borrow_mut();
+                session.microphone = microphone;
+                session.socket = Some(socket);
+            });
+        });
+    }
+
+    pub fn leave() {
+        let old = SESSION.with(|s| {
+            let mut session = s.borrow_mut();
+            let generation = session.generation + 1;
+            std::mem::replace(
+                &mut *session,
+                Session {
+                    generation,
+                    ..Default::default()
+                },
+            )
+        });
+        if let Some(socket) = old.socket {
+            let _ = socket.close();
+        }
+        if let Some(microphone) = old.microphone {
+            stop_tracks(&microphone);
+        }
+        for peer in old.peers.into_values() {
+            close_peer(peer);
+        }
+    }
+
+    pub fn peers() -> Vec<String> {
+        SESSION.with(|s| {
+            let mut peers: Vec<String> = s.borrow().peers.keys().cloned().collect();
+            peers.sort();
+            peers
+        })
+    }
+
+    pub fn set_transmitting(on: bool) {
+        SESSION.with(|s| {
+            if let Some(microphone) = &s.borrow().microphone {
+                set_tracks_enabled(microphone, on);
+            }
+        });
+    }
+
+    pub fn set_muted(player: &str, muted: bool) {
+        SESSION.with(|s| {
+            if let Some(peer) = s.borrow().peers.get(player) {
+                peer.audio.set_muted(muted);
+            }
+        });
+    }
+
+    fn is_current(generation: u32) -> bool {
+        SESSION.with(|s| s.borrow().generation == generation)
+    }
+
+    async fn open_microphone() -> Result<MediaStream, JsValue> {
+        let devices = web_sys::window()
+            .ok_or("no window")?
+            .navigator()
+            .media_devices()?;
+        let constraints = MediaStreamConstraints::new();
+        constraints.set_audio(&JsValue::TRUE);
+        let microphone: MediaStream =
+            JsFuture::from(devices.get_user_media_with_constraints(&constraints)?)
+                .await?
+                .dyn_into()?;
+        // Push-to-talk: nothing goes out until the key is held
+        set_tracks_enabled(&microphone, false);
+        Ok(microphone)
+    }
+
+    fn set_tracks_enabled(stream: &MediaStream, enabled: bool) {
+        for track in stream.get_audio_tracks().iter() {
+            track
+                .unchecked_into::<MediaStreamTrack>()
+                .set_enabled(enabled);
+        }
+    }
+
+    fn stop_tracks(stream: &MediaStream) {
+        for track in stream.get_tracks().iter() {
+            track.unchecked_into::<MediaStreamTrack>().stop();
+        }
+    }
+
+    fn close_peer(peer: Peer) {
+        peer.connection.close();
+        peer.audio.set_src_object(None);
+    }
+
+    fn send(generation: u32, signal: &VoiceSignal) {
+        SESSION.with(|s| {
+            let session = s.borrow();
+            if session.generation != generation {
+                return;
+            }
+            let (Some(socket), Ok(json)) = (&session.socket, serde_json::to_string(signal)) else {
+                return;
+            };
+            if let Err(e) = socket.send_with_str(&json) {
+                console::error_1(&e);
+            }
+        });
+    }
+
+    fn handle_signal(signal: VoiceSignal, generation: u32) {
+        if !is_current(generation) {
+            return;
+        }
+        match signal {
+            // Newcomers call everyone already there, the others wait for offers
+            VoiceSignal::Peers { peers } => {
+                for player in peers {
+                    spawn_local(log_failure(call(player, generation)));
+                }
+            }
+            VoiceSignal::Left { player } => {
+                let peer = SESSION.with(|s| s.borrow_mut().peers.remove(&player));
+                if let Some(peer) = peer {
+                    close_peer(peer);
+                }
+            }
+            VoiceSignal::Offer { from, sdp, .. } => {
+                spawn_local(log_failure(answer(from, sdp, generation)));
+            }
+            VoiceSignal::Answer { from, sdp, .. } => {
+                spawn_local(log_failure(accept(from, sdp)));
+            }
+            VoiceSignal::Ice {
+                from,
+                candidate,
+                sdp_mid,
+                sdp_m_line_index,
+                ..
+            } => {
+                let init = RtcIceCandidateInit::new(&candidate);
+                init.set_sdp_mid(sdp_mid.as_deref());
+                init.set_sdp_m_line_index(sdp_m_line_index);
+                let ready = SESSION.with(|s| {
+                    let mut session = s.borrow_mut();
+                    let peer = session.peers.get_mut(&from)?;
+                    if peer.connection.remote_description().is_none() {
+                        peer.pending_ice.push(init.clone());
+                        return None;
+                    }
+                    Some(peer.connection.clone())
+                });
+                if let Some(connection) = ready {
+                    spawn_local(log_failure(add_ice(connection, init)));
+                }
+            }
+        }
+    }
+
+    async fn log_failure(task: impl std::future::Future<Output = Result<(), JsValue>>) {
+        if let Err(e) = task.await {
+            console::warn_1(&format!("🎙 Voice connection failed: {:?}", e).into());
+        }
+    }
+
+    fn new_peer(player: &str, generation: u32) -> Result<RtcPeerConnection, JsValue> {
+        let server = RtcIceServer::new();
+        server.set_urls(&JsValue::from_str(STUN_SERVER));
+        let config = RtcConfiguration::new();
+        config.set_ice_servers(&Array::of1(&server));
+        let connection = RtcPeerConnection::new_with_configuration(&config)?;
+
+        let to = player.to_string();
+        let on_ice = Closure::<dyn FnMut(RtcPeerConnectionIceEvent)>::new(
+            move |event: RtcPeerConnectionIceEvent| {
+                if let Some(candidate) = event.candidate() {
+                    send(
+                        generation,
+                        &VoiceSignal::Ice {
+                            from: String::new(),
+                            to: to.clone(),
+                            candidate: candidate.candidate(),
+                            sdp_mid: candidate.sdp_mid(),
+                            sdp_m_line_index: candidate.sdp_m_line_index(),
+                        },
+                    );
+                }
+            },
+        );
+        connection.set_onicecandidate(Some(on_ice.as_ref().unchecked_ref()));
+        on_ice.forget();
+
+        let audio = HtmlAudioElement::new()?;
+        audio.set_autoplay(true);
+        let speaker = audio.clone();
+        let on_track = Closure::<dyn FnMut(RtcTrackEvent)>::new(move |event: RtcTrackEvent| {
+            if let Ok(stream) = event.streams().get(0).dyn_into::<MediaStream>() {
+                speaker.set_src_object(Some(&stream));
+            }
+        });
+        connection.set_ontrack(Some(on_track.as_ref().unchecked_ref()));
+        on_track.forget();
+
+        let replaced = SESSION.with(|s| {
+            let mut session = s.borrow_mut();
+            if let Some(microphone) = &session.microphone {
+                for track in microphone.get_audio_tracks().iter() {
+                    connection.add_track_0(&track.unchecked_into(), microphone);
+                }
+            }
+            session.peers.insert(
+                player.to_string(),
+                Peer {
+                    connection: connection.clone(),
+                    audio,
+                    pending_ice: Vec::new(),
+                },
+            )
+        });
+        if let Some(peer) = replaced {
+            close_peer(peer);
+        }
+        Ok(connection)
+    }
+
+    // The offer goes out before it is applied locally, so our ICE candidates can't overtake it
+    async fn call(player: String, generation: u32) -> Result<(), JsValue> {
+        let connection = new_peer(&player, generation)?;
+        let sdp = sdp_of(&JsFuture::from(connection.create_offer()).await?)?;
+        send(
+            generation,
+            &VoiceSignal::Offer {
+                from: String::new(),
+                to: player,
+                sdp: sdp.clone(),
+            },
+        );
+        JsFuture::from(connection.set_local_description(&description(RtcSdpType::Offer, &sdp)))
+            .await?;
+        Ok(())
+    }
+
+    async fn answer(player: String, offer: String, generation: u32) -> Result<(), JsValue> {
+        let connection = new_peer(&player, generation)?;
+        JsFuture::from(connection.set_remote_description(&description(RtcSdpType::Offer, &offer)))
+            .await?;
+        flush_ice(&player).await?;
+        let sdp = sdp_of(&JsFuture::from(connection.create_answer()).await?)?;
+        send(
+            generation,
+            &VoiceSignal::Answer {
+                from: String::new(),
+                to: player,
+                sdp: sdp.clone(),
+            },
+        );
+        JsFuture::from(connection.set_local_description(&description(RtcSdpType::Answer, &sdp)))
+            .await?;
+        Ok(())
+    }
+
+    async fn accept(player: String, answer: String) -> Result<(), JsValue> {
+        let Some(connection) =
+            SESSION.with(|s| s.borrow().peers.get(&player).map(|p| p.connection.clone()))
+        else {
+            return Ok(());
+        };
+        JsFuture::from(
+            connection.set_remote_description(&description(RtcSdpType::Answer, &answer)),
+        )
+        .await?;
+        flush_ice(&player).await
+    }
+
+    async fn flush_ice(player: &str) -> Result<(), JsValue> {
+        let Some((connection, pending)) = SESSION.with(|s| {
+            let mut session = s.borrow_mut();
+            let peer = session.peers.get_mut(player)?;
+            Some((
+                peer.connection.clone(),
+                std::mem::take(&mut peer.pending_ice),
+            ))
+        }) else {
+            return Ok(());
+        };
+        for init in pending {
+            add_ice(connection.clone(), init).await?;
+        }
+        Ok(())
+    }
+
+    async fn add_ice(
+        connection: RtcPeerConnection,
+        init: RtcIceCandidateInit,
+    ) -> Result<(), JsValue> {
+        JsFuture::from(connection.add_ice_candidate_with_opt_rtc_ice_candidate_init(Some(&init)))
+            .await?;
+        Ok(())
+    }
+
+    fn description(kind: RtcSdpType, sdp: &str) -> RtcSessionDescriptionInit {
+        let description = RtcSessionDescriptionInit::new(kind);
+        description.set_sdp(sdp);
+        description
+    }
+
+    fn sdp_of(description: &JsValue) -> Result<String, JsValue> {
+        Reflect::get(description, &JsValue::from_str("sdp"))?
+            .as_string()
+            .ok_or_else(|| JsValue::from_str("session description without sdp"))
+    }
+}
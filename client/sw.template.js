@@ -0,0 +1,73 @@
+// Voidloop Quest service worker.
+// GENERATED from client/sw.template.js by `cargo xtask service-worker` after each web build.
+
+const CACHE_NAME = "voidloop-quest-__CACHE_VERSION__";
+const OFFLINE_URL = "./offline.html";
+
+// The app shell and game assets, cached on install so second loads are instant
+const PRECACHE_URLS = __PRECACHE_URLS__;
+
+self.addEventListener("install", (event) => {
+    event.waitUntil(
+        caches.open(CACHE_NAME)
+            .then((cache) => cache.addAll(PRECACHE_URLS))
+            .then(() => self.skipWaiting())
+    );
+});
+
+self.addEventListener("activate", (event) => {
+    // Drop caches from older builds
+    event.waitUntil(
+        caches.keys()
+            .then((names) => Promise.all(
+                names
+                    .filter((name) => name.startsWith("voidloop-quest-") && name !== CACHE_NAME)
+                    .map((name) => caches.delete(name))
+            ))
+            .then(() => self.clients.claim())
+    );
+});
+
+self.addEventListener("fetch", (event) => {
+    const request = event.request;
+    const url = new URL(request.url);
+
+    // Only handle our own static files; lobby/matchmaker APIs and websockets always hit the network
+    if (request.method !== "GET" || url.origin !== self.location.origin) {
+        return;
+    }
+    if (url.pathname.startsWith("/lobby/") || url.pathname.startsWith("/matchmaker/")) {
+        return;
+    }
+
+    // The deployment's endpoints can change without a new build, never serve them stale
+    if (url.pathname.endsWith("/assets/network.json")) {
+        return;
+    }
+
+    // Pages: network first so deploys show up immediately, cached shell when offline
+    if (request.mode === "navigate") {
+        event.respondWith(
+            fetch(request).catch(() =>
+                caches.match("./index.html").then((cached) => cached || caches.match(OFFLINE_URL))
+            )
+        );
+        return;
+    }
+
+    // Bundle and assets: cache first, they are versioned by CACHE_NAME
+    event.respondWith(
+        caches.match(request).then((cached) => {
+            if (cached) {
+                return cached;
+            }
+            return fetch(request).then((response) => {
+                if (response.ok) {
+                    const copy = response.clone();
+                    caches.open(CACHE_NAME).then((cache) => cache.put(request, copy));
+                }
+                return response;
+            });
+        })
+    );
+});
@@ -124,24 +124,24 @@ install_wasm_target() {
     success "WASM target installed"
 }
 
-# Install trunk for WASM builds
-install_trunk() {
-    if command_exists trunk; then
-        success "Trunk is already installed"
+# Install wasm-bindgen-cli, which cargo xtask build-web runs after the WASM build
+install_wasm_bindgen() {
+    if command_exists wasm-bindgen; then
+        success "wasm-bindgen is already installed ($(wasm-bindgen --version))"
         return
     fi
     
-    info "Installing trunk for WASM builds (this may take a few minutes)..."
-    info "Note: You can skip this and install trunk later with 'cargo install trunk'"
+    info "Installing wasm-bindgen-cli for WASM builds (this may take a few minutes)..."
+    info "Note: You can skip this and install it later with 'cargo install wasm-bindgen-cli --version 0.2.100'"
     
-    # Give user option to skip trunk installation
-    if [[ "${SKIP_TRUNK:-}" == "1" ]]; then
-        warning "Skipping trunk installation (SKIP_TRUNK=1)"
+    # Give user option to skip wasm-bindgen installation
+    if [[ "${SKIP_WASM_BINDGEN:-}" == "1" ]]; then
+        warning "Skipping wasm-bindgen installation (SKIP_WASM_BINDGEN=1)"
         return
     fi
     
-    cargo install trunk
-    success "Trunk installed successfully"
+    cargo install wasm-bindgen-cli --version 0.2.100
+    success "wasm-bindgen installed successfully"
 }
 
 # Test build
@@ -185,9 +185,9 @@ main() {
         fi
     fi
     
-    # Install WASM target and trunk
+    # Install WASM target and wasm-bindgen
     install_wasm_target
-    install_trunk
+    install_wasm_bindgen
     
     # Test the build
     test_build
@@ -198,12 +198,12 @@ main() {
     echo "║     ✅ Development environment setup complete!               ║"
     echo "║                                                              ║"
     echo "║     Next steps:                                              ║"
-    echo "║     • cargo run --no-default-features -p server             ║"
+    echo "║     • cargo xtask dev  (server + web client on :8000)        ║"
     echo "║     • cargo run --no-default-features -p client             ║"
     echo "║                                                              ║"
     echo "║     💡 Tips:                                                 ║"
-    echo "║     • Run with SKIP_TRUNK=1 to skip trunk installation      ║"
-    echo "║     • Install trunk later: cargo install trunk              ║"
+    echo "║     • cargo xtask --help lists the other workflows          ║"
+    echo "║     • SKIP_WASM_BINDGEN=1 skips installing wasm-bindgen     ║"
     echo "║                                                              ║"
     echo "║     📖 See DEVELOPMENT.md for detailed instructions          ║"
     echo "║                                                              ║"
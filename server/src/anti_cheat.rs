@@ -0,0 +1,329 @@
+// Movement validation. Players are simulated here from their inputs, so a client can't
+// just claim a position, but it can send inputs no person produces, and any way the
+// simulation lets a player move too far is a cheat waiting to be found. Every fixed tick
+// the server checks that jumps start on the ground and that nobody moves further than the
+// level allows, and undoes what breaks the rules. Each violation is a strike, strikes wear
+// off over time, and a client with too many is disconnected. The first violation of each
+// kind per player, and every kick, go to the lobby service's audit log.
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::match_report::player_name;
+use crate::profiles::AccountId;
+use shared::{
+    apply_gravity_system, door_collision_system, max_player_step, player_movement_system,
+    record_checksum_system, AntiCheatReport, GameConfig, LevelDefinition, MatchRoom,
+    MovementViolation, Player, PlayerActions, PlayerId, PlayerName, PlayerTransform, FIXED_DT,
+};
+
+#[cfg(feature = "bevygap")]
+use lightyear::prelude::*;
+
+// Seconds for one strike to wear off
+const STRIKE_DECAY_SECS: f32 = 10.0;
+// Mashing gets to about 10 presses a second, scripts go as fast as the tick rate allows
+const MAX_JUMPS_PER_SEC: u32 = 15;
+const REPORT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// From --anticheat-kick-after, --lobby-url, --room-id and VOIDLOOP_LOBBY_TOKEN
+#[derive(Resource, Clone, Debug, Default)]
+pub struct AntiCheatConfig {
+    /// Strikes that get a client disconnected, 0 to only log and report
+    pub kick_after: u32,
+    pub lobby_url: Option<String>,
+    pub room_id: Option<String>,
+    pub token: Option<String>,
+}
+
+pub struct AntiCheatPlugin {
+    pub config: AntiCheatConfig,
+}
+
+impl Plugin for AntiCheatPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.config.clone())
+            .add_event::<MovementFlagged>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    (track_new_players, remember_ground_state)
+                        .chain()
+                        .before(player_movement_system),
+                    check_jumps
+                        .after(player_movement_system)
+                        .before(apply_gravity_system),
+                    check_steps
+                        .after(door_collision_system)
+                        .before(record_checksum_system),
+                ),
+            )
+            .add_systems(Update, punish_violations);
+    }
+}
+
+/// A player broke a movement rule, and the tick it happened in was already corrected
+#[derive(Event)]
+struct MovementFlagged {
+    player: Entity,
+    violation: MovementViolation,
+    detail: String,
+}
+
+// What the checks remember about a player between ticks
+#[derive(Component, Default)]
+struct MovementCheck {
+    // Position after the last tick, None before the player's first one
+    last: Option<Vec3>,
+    // State before this tick's movement, to tell a jump from the ground
+    was_grounded: bool,
+    velocity_y: f32,
+    // Jump presses in the current second, and how far into it we are
+    jumps: u32,
+    second: f32,
+    strikes: f32,
+    reported: HashSet<MovementViolation>,
+    kicked: bool,
+}
+
+fn track_new_players(
+    mut commands: Commands,
+    players: Query<Entity, (With<Player>, Without<MovementCheck>)>,
+) {
+    for entity in players.iter() {
+        commands.entity(entity).insert(MovementCheck::default());
+    }
+}
+
+fn remember_ground_state(mut players: Query<(&Player, &mut MovementCheck)>) {
+    for (player, mut check) in players.iter_mut() {
+        check.was_grounded = player.grounded;
+        check.velocity_y = player.velocity.y;
+    }
+}
+
+// Only a jump raises a player during the movement system, so a rise from the air is one
+fn check_jumps(
+    mut flagged: EventWriter<MovementFlagged>,
+    mut players: Query<(
+        Entity,
+        &mut Player,
+        &mut MovementCheck,
+        Option<&ActionState<PlayerActions>>,
+    )>,
+) {
+    for (entity, mut player, mut check, actions) in players.iter_mut() {
+        if player.velocity.y > check.velocity_y && !check.was_grounded {
+            flagged.write(MovementFlagged {
+                player: entity,
+                violation: MovementViolation::AirJump,
+                detail: format!(
+                    "vertical speed {:.0} -> {:.0} while airborne",
+                    check.velocity_y, player.velocity.y
+                ),
+            });
+            player.velocity.y = check.velocity_y;
+        }
+
+        if actions.is_some_and(|actions| actions.just_pressed(&PlayerActions::Jump)) {
+            check.jumps += 1;
+        }
+        check.second += FIXED_DT;
+        if check.second >= 1.0 {
+            if check.jumps > MAX_JUMPS_PER_SEC {
+                flagged.write(MovementFlagged {
+                    player: entity,
+                    violation: MovementViolation::InputRate,
+                    detail: format!("{} jump presses in a second", check.jumps),
+                });
+            }
+            check.jumps = 0;
+            check.second -= 1.0;
+        }
+    }
+}
+
+// Respawns are the only moves allowed to skip across the level, and they land on spawn
+// points. Anything else that goes too far is cut short.
+fn check_steps(
+    config: Res<GameConfig>,
+    level: Res<LevelDefinition>,
+    mut flagged: EventWriter<MovementFlagged>,
+    mut players: Query<(Entity, &mut PlayerTransform, &mut MovementCheck)>,
+) {
+    let max_step = max_player_step(&config, &level);
+    for (entity, mut transform, mut check) in players.iter_mut() {
+        if let Some(last) = check.last {
+            let step = transform.translation - last;
+            if step.length() > max_step && !level.is_spawn_point(transform.translation) {
+                flagged.write(MovementFlagged {
+                    player: entity,
+                    violation: MovementViolation::Speed,
+                    detail: format!(
+                        "moved {:.1} units in a tick, {:.1} allowed",
+                        step.length(),
+                        max_step
+                    ),
+                });
+                transform.translation = last + step.clamp_length_max(max_step);
+            }
+        }
+        check.last = Some(transform.translation);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn punish_violations(
+    time: Res<Time>,
+    config: Res<AntiCheatConfig>,
+    mut flagged: EventReader<MovementFlagged>,
+    mut players: Query<(
+        &mut MovementCheck,
+        &PlayerId,
+        Option<&PlayerName>,
+        Option<&AccountId>,
+        Option<&MatchRoom>,
+    )>,
+    #[cfg(feature = "bevygap")] mut commands: Commands,
+    #[cfg(feature = "bevygap")] owners: Query<&ControlledBy>,
+) {
+    for (mut check, ..) in players.iter_mut() {
+        check.strikes = (check.strikes - time.delta_secs() / STRIKE_DECAY_SECS).max(0.0);
+    }
+
+    for event in flagged.read() {
+        let Ok((mut check, id, name, account, room)) = players.get_mut(event.player) else {
+            continue;
+        };
+        if check.kicked {
+            continue;
+        }
+        check.strikes += 1.0;
+        let strikes = check.strikes.ceil() as u32;
+        let player = account.map_or_else(|| player_name(id, name), |account| account.0.clone());
+        warn!(
+            "🚨 {} broke the {:?} rule: {} ({} strikes)",
+            player, event.violation, event.detail, strikes
+        );
+
+        let kicked = config.kick_after > 0 && strikes >= config.kick_after;
+        if kicked {
+            check.kicked = true;
+            warn!("🚨 Disconnecting {} after {} strikes", player, strikes);
+            #[cfg(feature = "bevygap")]
+            if let Ok(owner) = owners.get(event.player) {
+                commands.trigger_targets(Disconnect, owner.owner);
+            }
+        }
+
+        let first = check.reported.insert(event.violation);
+        let Some(url) = &config.lobby_url else {
+            continue;
+        };
+        if first || kicked {
+            post_report(
+                format!("{}/servers/anticheat", url.trim_end_matches('/')),
+                config.token.clone(),
+                AntiCheatReport {
+                    room_id: room
+                        .map(|room| room.0.clone())
+                        .filter(|room_id| !room_id.is_empty())
+                        .or_else(|| config.room_id.clone()),
+                    player,
+                    violation: event.violation,
+                    strikes,
+                    kicked,
+                    detail: event.detail.clone(),
+                },
+            );
+        }
+    }
+}
+
+// Off the main thread like the match report, a report that doesn't arrive is only logged
+fn post_report(url: String, token: Option<String>, report: AntiCheatReport) {
+    std::thread::spawn(move || {
+        let mut request = reqwest::blocking::Client::new()
+            .post(&url)
+            .json(&report)
+            .timeout(REPORT_TIMEOUT);
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        if let Err(e) = request
+            .send()
+            .and_then(|response| response.error_for_status())
+        {
+            warn!("🚨 Failed to report {} to {}: {}", report.player, url, e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.insert_resource(LevelDefinition::default())
+            .insert_resource(GameConfig::default())
+            .insert_resource(AntiCheatConfig::default())
+            .add_event::<MovementFlagged>()
+            .add_systems(Update, (track_new_players, check_steps).chain());
+        app
+    }
+
+    fn flagged(app: &mut App) -> Vec<MovementViolation> {
+        app.world_mut()
+            .resource_mut::<Events<MovementFlagged>>()
+            .drain()
+            .map(|event| event.violation)
+            .collect()
+    }
+
+    #[test]
+    fn teleports_are_cut_short_but_respawns_are_not() {
+        let mut app = app();
+        let start = Vec3::new(0.0, 20.0, 0.0);
+        let player = app
+            .world_mut()
+            .spawn((Player::default(), PlayerTransform { translation: start }))
+            .id();
+        app.update();
+        app.update();
+
+        // A normal step
+        let walked = start + Vec3::X * 3.0;
+        app.world_mut()
+            .get_mut::<PlayerTransform>(player)
+            .unwrap()
+            .translation = walked;
+        app.update();
+        assert_eq!(flagged(&mut app), Vec::new());
+
+        // Across the level in one tick
+        app.world_mut()
+            .get_mut::<PlayerTransform>(player)
+            .unwrap()
+            .translation = walked + Vec3::X * 500.0;
+        app.update();
+        assert_eq!(flagged(&mut app), vec![MovementViolation::Speed]);
+        let clamped = app
+            .world()
+            .get::<PlayerTransform>(player)
+            .unwrap()
+            .translation;
+        let max_step = max_player_step(&GameConfig::default(), &LevelDefinition::default());
+        assert!((clamped.x - walked.x - max_step).abs() < 1e-3);
+
+        // Back to a spawn point after falling out
+        let spawn = LevelDefinition::default().spawn_point(None, &[]);
+        app.world_mut()
+            .get_mut::<PlayerTransform>(player)
+            .unwrap()
+            .translation = spawn;
+        app.update();
+        assert_eq!(flagged(&mut app), Vec::new());
+    }
+}
@@ -0,0 +1,315 @@
+// Bot fill: server-controlled players top a match up to --bot-fill, tuned to the humans' skill.
+// The room's --bot-style picks the behavior tree they think with (see `BotStyle::tree`), the
+// humans' skill how quickly they react and how well they jump.
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+use rand::Rng;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::server_plugin::spawn_replicated;
+use shared::{
+    ground_ahead, player_movement_system, BotDifficulty, BotNode, BotSenses, BotStyle, BotTag,
+    Checkpoint, MatchRoom, Pickup, Platform, Player, PlayerActions, PlayerAnimationState,
+    PlayerColor, PlayerId, PlayerName, PlayerTransform, RaceProgress, ScoringZone,
+    SkillRatingsRequest, SkillRatingsResponse, WorldBounds,
+};
+
+// Bot ids start here so they never clash with human players
+const BOT_ID_BASE: u32 = 1000;
+// Bots drop in above the middle of the level, spread out sideways
+const BOT_SPAWN: Vec3 = Vec3::new(0.0, 150.0, 0.0);
+const BOT_SPACING: f32 = 40.0;
+// Patrolling bots turn around this close to the edge of the level
+const PATROL_MARGIN: f32 = 50.0;
+
+// Bot fill settings, from --bot-fill, --bot-style and --skill-ratings-url
+#[derive(Resource, Clone, Debug, Default)]
+pub struct BotConfig {
+    /// Players a match is topped up to, 0 disables bots
+    pub fill: usize,
+    pub style: BotStyle,
+    /// Matchmaker endpoint the humans' skill ratings are fetched from
+    pub ratings_url: Option<String>,
+}
+
+// What the bot decided the last time it reacted
+#[derive(Component)]
+struct BotBrain {
+    tree: BotNode,
+    reaction: Timer,
+    direction: f32,
+    jump: bool,
+    /// Way the bot walks while patrolling
+    patrol: f32,
+}
+
+// Skill ratings request to the matchmaker, in flight. None once its answer was taken.
+#[derive(Resource)]
+struct RatingsFetch(Option<JoinHandle<Result<Vec<f32>, String>>>);
+
+pub struct BotsPlugin;
+
+impl Plugin for BotsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BotConfig>()
+            .add_systems(
+                Update,
+                (start_match, poll_ratings, fill_bots)
+                    .chain()
+                    .run_if(|config: Res<BotConfig>| config.fill > 0),
+            )
+            .add_systems(FixedUpdate, drive_bots.before(player_movement_system));
+    }
+}
+
+// The first human in starts the match, which is when bot difficulty is settled
+fn start_match(
+    mut commands: Commands,
+    config: Res<BotConfig>,
+    humans: Query<&PlayerId, (With<Player>, Without<BotTag>)>,
+    mut started: Local<bool>,
+) {
+    if humans.is_empty() {
+        if *started {
+            commands.remove_resource::<BotDifficulty>();
+            *started = false;
+        }
+        return;
+    }
+    if *started {
+        return;
+    }
+    *started = true;
+
+    let Some(url) = config.ratings_url.clone() else {
+        info!("🤖 No skill ratings URL, bots use the default difficulty");
+        commands.insert_resource(BotDifficulty::default());
+        return;
+    };
+    let players = humans.iter().map(|id| id.id).collect();
+    // On a thread of its own like the race results, the blocking call would hold up a
+    // task pool thread
+    let fetch = std::thread::spawn(move || fetch_ratings(&url, players));
+    commands.insert_resource(RatingsFetch(Some(fetch)));
+}
+
+fn fetch_ratings(url: &str, players: Vec<u32>) -> Result<Vec<f32>, String> {
+    let response: SkillRatingsResponse = reqwest::blocking::Client::new()
+        .post(url)
+        .json(&SkillRatingsRequest { players })
+        .timeout(Duration::from_secs(5))
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| response.json())
+        .map_err(|e| e.to_string())?;
+    Ok(response.ratings)
+}
+
+fn poll_ratings(mut commands: Commands, fetch: Option<ResMut<RatingsFetch>>) {
+    let Some(mut fetch) = fetch else {
+        return;
+    };
+    if !fetch.0.as_ref().is_some_and(JoinHandle::is_finished) {
+        return;
+    }
+    commands.remove_resource::<RatingsFetch>();
+    let Some(handle) = fetch.0.take() else {
+        return;
+    };
+    let result = handle
+        .join()
+        .unwrap_or_else(|_| Err("the request panicked".to_string()));
+
+    let difficulty = match result {
+        Ok(ratings) => BotDifficulty::from_ratings(&ratings),
+        Err(e) => {
+            warn!("🤖 Failed to fetch skill ratings, using the default: {}", e);
+            BotDifficulty::default()
+        }
+    };
+    info!(
+        "🤖 Bot difficulty: {:.2}s reaction, {:.0}% jump accuracy",
+        difficulty.reaction_time,
+        difficulty.jump_accuracy * 100.0
+    );
+    commands.insert_resource(difficulty);
+}
+
+// Keep humans + bots at the fill target, bots only play while a human is in. Bot fill is
+// for servers of one lobby room, bots join the room of the humans.
+fn fill_bots(
+    mut commands: Commands,
+    config: Res<BotConfig>,
+    difficulty: Option<Res<BotDifficulty>>,
+    humans: Query<Option<&MatchRoom>, (With<Player>, Without<BotTag>)>,
+    bots: Query<(Entity, &PlayerId), With<BotTag>>,
+) {
+    let Some(difficulty) = difficulty.filter(|_| !humans.is_empty()) else {
+        for (entity, _) in bots.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    };
+    let wanted = config.fill.saturating_sub(humans.iter().count());
+    let mut bots: Vec<(Entity, u32)> = bots.iter().map(|(e, id)| (e, id.id)).collect();
+    bots.sort_by_key(|(_, id)| *id);
+
+    // Newest bots make room for humans first
+    for (entity, id) in bots.iter().skip(wanted) {
+        info!("🤖 Bot {} leaves", id);
+        commands.entity(*entity).despawn();
+    }
+
+    let room = humans.iter().flatten().next().cloned();
+    let mut taken: Vec<u32> = bots.iter().map(|(_, id)| *id).collect();
+    for slot in taken.len()..wanted {
+        let id = (BOT_ID_BASE..)
+            .find(|id| !taken.contains(id))
+            .unwrap_or(BOT_ID_BASE);
+        taken.push(id);
+        info!("🤖 Bot {} joins", id);
+        let bot = spawn_replicated(
+            &mut commands,
+            (
+                Player::default(),
+                PlayerTransform {
+                    translation: BOT_SPAWN + Vec3::X * BOT_SPACING * slot as f32,
+                },
+                PlayerColor {
+                    color: Color::srgb(0.6, 0.6, 0.65),
+                },
+                PlayerAnimationState::default(),
+                PlayerId { id },
+                PlayerName {
+                    name: format!("Bot {}", id - BOT_ID_BASE + 1),
+                },
+                ActionState::<PlayerActions>::default(),
+                BotTag,
+                BotBrain {
+                    tree: config.style.tree(),
+                    reaction: Timer::from_seconds(difficulty.reaction_time, TimerMode::Repeating),
+                    direction: 0.0,
+                    jump: false,
+                    patrol: if slot % 2 == 0 { 1.0 } else { -1.0 },
+                },
+            ),
+        );
+        if let Some(room) = &room {
+            commands.entity(bot).insert(room.clone());
+        }
+    }
+}
+
+// Bots press the same actions humans do, re-deciding once per reaction time
+#[allow(clippy::too_many_arguments)]
+fn drive_bots(
+    time: Res<Time>,
+    difficulty: Option<Res<BotDifficulty>>,
+    bounds: Res<WorldBounds>,
+    mut bots: Query<
+        (
+            Entity,
+            &Player,
+            &PlayerTransform,
+            Option<&RaceProgress>,
+            &mut BotBrain,
+            &mut ActionState<PlayerActions>,
+            Option<&MatchRoom>,
+        ),
+        With<BotTag>,
+    >,
+    players: Query<(Entity, &PlayerTransform, Option<&MatchRoom>), With<Player>>,
+    checkpoints: Query<(&Checkpoint, &Transform)>,
+    zones: Query<(&ScoringZone, &Transform)>,
+    pickups: Query<(&Transform, Option<&MatchRoom>), With<Pickup>>,
+    platforms: Query<(&Platform, &Transform)>,
+) {
+    let Some(difficulty) = difficulty else {
+        return;
+    };
+    let mut rng = rand::thread_rng();
+
+    for (entity, player, transform, progress, mut brain, mut actions, room) in bots.iter_mut() {
+        if brain.reaction.tick(time.delta()).just_finished() {
+            brain
+                .reaction
+                .set_duration(Duration::from_secs_f32(difficulty.reaction_time));
+
+            let position = transform.translation.truncate();
+            if position.x <= bounds.min_x + PATROL_MARGIN {
+                brain.patrol = 1.0;
+            } else if position.x >= bounds.max_x - PATROL_MARGIN {
+                brain.patrol = -1.0;
+            }
+            let senses = BotSenses {
+                position,
+                grounded: player.grounded,
+                gap_left: !ground_ahead(transform.translation, -1.0, platforms.iter()),
+                gap_right: !ground_ahead(transform.translation, 1.0, platforms.iter()),
+                objective: bot_objective(progress, &checkpoints, &zones),
+                // Only what the bot's own lobby room plays with
+                pickup: nearest(
+                    position,
+                    pickups
+                        .iter()
+                        .filter(|(_, pickup_room)| *pickup_room == room)
+                        .map(|(pickup, _)| pickup.translation),
+                ),
+                rival: nearest(
+                    position,
+                    players
+                        .iter()
+                        .filter(|(other, _, other_room)| *other != entity && *other_room == room)
+                        .map(|(_, other, _)| other.translation),
+                ),
+                patrol: brain.patrol,
+            };
+            let intent = brain.tree.decide(&senses);
+            brain.direction = intent.direction;
+            // Weaker bots misjudge jumps and stay on the ground
+            brain.jump = intent.jump && rng.gen_bool(difficulty.jump_accuracy as f64);
+        }
+
+        set_pressed(&mut actions, PlayerActions::MoveLeft, brain.direction < 0.0);
+        set_pressed(
+            &mut actions,
+            PlayerActions::MoveRight,
+            brain.direction > 0.0,
+        );
+        set_pressed(&mut actions, PlayerActions::Jump, brain.jump);
+        brain.jump = false;
+    }
+}
+
+fn set_pressed(actions: &mut ActionState<PlayerActions>, action: PlayerActions, pressed: bool) {
+    if pressed {
+        actions.press(&action);
+    } else {
+        actions.release(&action);
+    }
+}
+
+// Whatever the mode is about: the next checkpoint, else the live zone
+fn bot_objective(
+    progress: Option<&RaceProgress>,
+    checkpoints: &Query<(&Checkpoint, &Transform)>,
+    zones: &Query<(&ScoringZone, &Transform)>,
+) -> Option<Vec2> {
+    if let Some(progress) = progress.filter(|p| p.finished.is_none()) {
+        let next = progress.splits.len();
+        if let Some((_, transform)) = checkpoints.iter().find(|(c, _)| c.index == next) {
+            return Some(transform.translation.truncate());
+        }
+    }
+    zones
+        .iter()
+        .find(|(zone, _)| zone.active)
+        .map(|(_, transform)| transform.translation.truncate())
+}
+
+fn nearest(position: Vec2, positions: impl Iterator<Item = Vec3>) -> Option<Vec2> {
+    positions
+        .map(|translation| translation.truncate())
+        .min_by(|a, b| a.distance(position).total_cmp(&b.distance(position)))
+}
@@ -20,6 +20,8 @@ pub struct BuildInfo {
     pub git_commit_author: &'static str,
     /// System info
     pub system_info: &'static str,
+    /// Hash over the asset manifest compiled into shared, must match the client's
+    pub asset_manifest_version: &'static str,
 }
 
 impl BuildInfo {
@@ -34,19 +36,21 @@ impl BuildInfo {
             package_version: env!("CARGO_PKG_VERSION"),
             git_commit_author: option_env!("VERGEN_GIT_COMMIT_AUTHOR_NAME").unwrap_or("unknown"),
             system_info: option_env!("VERGEN_SYSINFO_OS_VERSION").unwrap_or("unknown"),
+            asset_manifest_version: shared::ASSET_MANIFEST_VERSION,
         }
     }
 
     /// Format build info for logging
     pub fn format_for_log(&self) -> String {
         format!(
-            "Build: {} ({}), Git: {} @ {}, Rust: {}, Target: {}",
+            "Build: {} ({}), Git: {} @ {}, Rust: {}, Target: {}, Assets: {}",
             self.package_version,
             self.build_timestamp,
             &self.git_sha[..std::cmp::min(8, self.git_sha.len())], // Shortened SHA
             self.git_branch,
             self.rustc_version,
-            self.target_triple
+            self.target_triple,
+            self.asset_manifest_version
         )
     }
 }
@@ -69,6 +73,7 @@ mod tests {
         assert!(!build_info.package_version.is_empty());
         assert!(!build_info.git_sha.is_empty());
         assert!(!build_info.git_branch.is_empty());
+        assert_eq!(build_info.asset_manifest_version.len(), 16);
 
         // Verify formatted log doesn't panic and contains expected content
         let formatted = build_info.format_for_log();
@@ -0,0 +1,79 @@
+// Time dilation for clients whose clock drifts: cheap devices and battery saver can make
+// a client simulate consistently faster or slower than the server, which slowly pushes
+// it out of the prediction window. Clients report how many fixed ticks they ran, and the
+// server tells the ones that drift to nudge their tick rate. Every client also hears the
+// server's simulation tick as soon as it connects and then once a second, with a checksum to
+// compare its own world with.
+use bevy::prelude::*;
+use lightyear::prelude::*;
+
+use shared::{
+    Channel1, ChecksumHistory, ClockReport, ClockSkew, TickRateHint, TickSync, TICK_SYNC_INTERVAL,
+};
+
+pub struct ClockSyncPlugin;
+
+impl Plugin for ClockSyncPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (receive_clock_reports, send_tick_sync));
+    }
+}
+
+// Kept on a client's connection, goes away with it
+#[derive(Component)]
+struct ClientClock(ClockSkew);
+
+fn receive_clock_reports(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut clients: Query<(
+        Entity,
+        &mut MessageReceiver<ClockReport>,
+        &mut MessageSender<TickRateHint>,
+        Option<&mut ClientClock>,
+    )>,
+) {
+    let now = time.elapsed_secs_f64();
+    for (client, mut receiver, mut sender, clock) in clients.iter_mut() {
+        let Some(report) = receiver.receive().last() else {
+            continue;
+        };
+        let Some(mut clock) = clock else {
+            let mut skew = ClockSkew::default();
+            skew.observe(now, report.fixed_ticks);
+            commands.entity(client).insert(ClientClock(skew));
+            continue;
+        };
+        clock.0.observe(now, report.fixed_ticks);
+        if let Some(speed) = clock.0.hint() {
+            info!(
+                "⏱️ Client {:?} runs at {:.3}x, hinting {:.3}",
+                client,
+                clock.0.rate().unwrap_or(1.0),
+                speed
+            );
+            sender.send::<Channel1>(TickRateHint { speed });
+            clock.0.reset();
+        }
+    }
+}
+
+fn send_tick_sync(
+    history: Res<ChecksumHistory>,
+    mut senders: Query<(&mut MessageSender<TickSync>, Ref<Connected>)>,
+    mut last_sent: Local<Option<u32>>,
+) {
+    let Some((tick, checksum)) = history.latest() else {
+        return;
+    };
+    let due = !last_sent.is_some_and(|last| tick < last.saturating_add(TICK_SYNC_INTERVAL));
+    if due {
+        *last_sent = Some(tick);
+    }
+    // New clients take the tick right away, a match countdown is counted in ticks
+    for (mut sender, connected) in senders.iter_mut() {
+        if due || connected.is_added() {
+            sender.send::<Channel1>(TickSync { tick, checksum });
+        }
+    }
+}
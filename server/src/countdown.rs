@@ -0,0 +1,158 @@
+// Match countdown: once a room has the mode's players, everyone in it gets --countdown-secs
+// of 3-2-1 before they can move. Rooms sharing the server count down on their own. Clients
+// that connect during their room's countdown hear when it ends, later ones just play. A
+// room's countdown starts over when its humans are gone.
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+#[cfg(feature = "bevygap")]
+use {lightyear::prelude::*, shared::Channel1};
+
+use shared::{BotTag, Countdown, GameMode, MatchRoom, Player, SimTick};
+
+pub struct CountdownPlugin {
+    /// Seconds counted down, 0 lets players move as soon as they spawn
+    pub secs: u32,
+}
+
+#[derive(Resource)]
+struct CountdownSecs(u32);
+
+/// Countdown of every room that got its players. None is the room of local players.
+#[derive(Resource, Default)]
+struct RoomCountdowns(HashMap<Option<MatchRoom>, Countdown>);
+
+impl Plugin for CountdownPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(CountdownSecs(self.secs))
+            .init_resource::<RoomCountdowns>()
+            .add_systems(Update, (start_countdowns, hold_players).chain());
+
+        #[cfg(feature = "bevygap")]
+        app.add_systems(Update, send_countdowns.after(start_countdowns));
+    }
+}
+
+fn start_countdowns(
+    secs: Res<CountdownSecs>,
+    mode: Res<GameMode>,
+    tick: Res<SimTick>,
+    mut countdowns: ResMut<RoomCountdowns>,
+    players: Query<(Option<&MatchRoom>, Has<BotTag>), With<Player>>,
+) {
+    // Players and humans in each room
+    let mut rooms: HashMap<Option<MatchRoom>, (usize, usize)> = HashMap::new();
+    for (room, bot) in players.iter() {
+        let (players, humans) = rooms.entry(room.cloned()).or_default();
+        *players += 1;
+        if !bot {
+            *humans += 1;
+        }
+    }
+
+    // Only touch the map when a room emptied, a changed map is sent to every client again
+    let emptied = countdowns
+        .0
+        .keys()
+        .any(|room| rooms.get(room).is_none_or(|(_, humans)| *humans == 0));
+    if emptied {
+        countdowns
+            .0
+            .retain(|room, _| rooms.get(room).is_some_and(|(_, humans)| *humans > 0));
+    }
+
+    for (room, (players, humans)) in rooms {
+        if humans == 0 || players < mode.min_players() || countdowns.0.contains_key(&room) {
+            continue;
+        }
+        let countdown = if secs.0 > 0 {
+            let name = room.as_ref().map_or("local", |room| room.0.as_str());
+            info!("🚦 Match in room '{}' starts in {}s", name, secs.0);
+            Countdown::starting_at(*tick, secs.0)
+        } else {
+            Countdown::default()
+        };
+        countdowns.0.insert(room, countdown);
+    }
+}
+
+// Every player carries their room's countdown, rooms that are still filling up have none
+fn hold_players(
+    mut commands: Commands,
+    countdowns: Res<RoomCountdowns>,
+    mut players: Query<(Entity, Option<&MatchRoom>, Option<&mut Countdown>), With<Player>>,
+) {
+    for (entity, room, countdown) in players.iter_mut() {
+        let wanted = countdowns
+            .0
+            .get(&room.cloned())
+            .copied()
+            .unwrap_or_default();
+        match countdown {
+            Some(mut countdown) if *countdown != wanted => *countdown = wanted,
+            Some(_) => {}
+            None => {
+                commands.entity(entity).insert(wanted);
+            }
+        }
+    }
+}
+
+// Clients hear their own room's countdown, when it starts and when they connect during it
+#[cfg(feature = "bevygap")]
+fn send_countdowns(
+    tick: Res<SimTick>,
+    countdowns: Res<RoomCountdowns>,
+    mut senders: Query<(
+        &mut MessageSender<Countdown>,
+        Ref<Connected>,
+        Ref<MatchRoom>,
+    )>,
+) {
+    for (mut sender, connected, room) in senders.iter_mut() {
+        let Some(countdown) = countdowns.0.get(&Some(room.clone())) else {
+            continue;
+        };
+        if !countdown.is_running(*tick) {
+            continue;
+        }
+        if connected.is_added() || room.is_added() || countdowns.is_changed() {
+            sender.send::<Channel1>(*countdown);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rooms_count_down_on_their_own() {
+        let mut app = App::new();
+        app.insert_resource(CountdownSecs(3))
+            .init_resource::<RoomCountdowns>()
+            .insert_resource(GameMode::Casual)
+            .insert_resource(SimTick(100))
+            .add_systems(Update, (start_countdowns, hold_players).chain());
+        let player = |room: &str| (Player::default(), MatchRoom(room.to_string()));
+        let ann = app.world_mut().spawn(player("R1")).id();
+        app.update();
+
+        // Bob's room fills up later and gets a countdown of its own
+        app.insert_resource(SimTick(500));
+        let bob = app.world_mut().spawn(player("R2")).id();
+        app.update();
+
+        let countdown = |entity| *app.world().get::<Countdown>(entity).unwrap();
+        assert_eq!(countdown(ann), Countdown::starting_at(SimTick(100), 3));
+        assert_eq!(countdown(bob), Countdown::starting_at(SimTick(500), 3));
+        assert!(countdown(bob).is_running(SimTick(500)));
+
+        // Ann leaving starts R1 over, R2 keeps its countdown
+        app.world_mut().despawn(ann);
+        app.update();
+        let countdowns = &app.world().resource::<RoomCountdowns>().0;
+        assert!(!countdowns.contains_key(&Some(MatchRoom("R1".to_string()))));
+        assert!(countdowns.contains_key(&Some(MatchRoom("R2".to_string()))));
+    }
+}
@@ -0,0 +1,42 @@
+// Falling out of the world: a player below the level's kill plane is put back on a free
+// spawn point, for a penalty, see `respawn_fallen_players_system`. The player's client is
+// told so it can shake the camera and fade the screen.
+use bevy::prelude::*;
+
+use shared::{respawn_fallen_players_system, PlayerFellOut};
+
+#[cfg(feature = "bevygap")]
+use {
+    lightyear::prelude::*,
+    shared::{Channel1, RespawnEvent},
+};
+
+pub struct FallOutPlugin;
+
+impl Plugin for FallOutPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlayerFellOut>()
+            .add_systems(FixedUpdate, respawn_fallen_players_system);
+        #[cfg(feature = "bevygap")]
+        app.add_systems(
+            FixedUpdate,
+            tell_fallen_players.after(respawn_fallen_players_system),
+        );
+    }
+}
+
+#[cfg(feature = "bevygap")]
+fn tell_fallen_players(
+    mut fell_out: EventReader<PlayerFellOut>,
+    owners: Query<&ControlledBy>,
+    mut senders: Query<&mut MessageSender<RespawnEvent>>,
+) {
+    for fell in fell_out.read() {
+        if let Ok(mut sender) = owners
+            .get(fell.entity)
+            .and_then(|owner| senders.get_mut(owner.owner))
+        {
+            sender.send::<Channel1>(fell.penalty.clone());
+        }
+    }
+}
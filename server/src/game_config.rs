@@ -0,0 +1,31 @@
+// Physics of the match, from --game-config and its overrides. Clients hear them as soon as
+// they connect, so their prediction moves players the way the server does.
+use bevy::prelude::*;
+
+#[cfg(feature = "bevygap")]
+use {lightyear::prelude::*, shared::Channel1};
+
+use shared::GameConfig;
+
+pub struct GameConfigPlugin {
+    pub config: GameConfig,
+}
+
+impl Plugin for GameConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.config);
+
+        #[cfg(feature = "bevygap")]
+        app.add_systems(Update, send_game_config);
+    }
+}
+
+#[cfg(feature = "bevygap")]
+fn send_game_config(
+    config: Res<GameConfig>,
+    mut senders: Query<&mut MessageSender<GameConfig>, Added<Connected>>,
+) {
+    for mut sender in senders.iter_mut() {
+        sender.send::<Channel1>(*config);
+    }
+}
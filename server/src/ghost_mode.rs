@@ -0,0 +1,156 @@
+// Asymmetric ghost mode: one random player is hidden from everyone else and can drop hazards
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+use rand::seq::IteratorRandom;
+
+#[cfg(feature = "bevygap")]
+use lightyear::prelude::*;
+
+use crate::server_plugin::spawn_replicated;
+use shared::{
+    hazard_knockback_system, GameMode, Hazard, Player, PlayerActions, PlayerRole, PlayerTransform,
+};
+#[cfg(feature = "bevygap")]
+use shared::{Channel1, RoleAssignment};
+
+// Seconds between two hazards from the ghost
+const SABOTAGE_COOLDOWN_SECS: f32 = 4.0;
+// Seconds a hazard stays in the level
+const HAZARD_LIFETIME_SECS: f32 = 8.0;
+// Hazards sit on the ground under the ghost's feet
+const HAZARD_DROP_OFFSET: f32 = -15.0;
+
+// Server-side bookkeeping for a hazard, not replicated
+#[derive(Component)]
+struct HazardLifetime(Timer);
+
+// Cooldown for the ghost's sabotage action
+#[derive(Component)]
+struct SabotageCooldown(Timer);
+
+pub struct GhostModePlugin;
+
+impl Plugin for GhostModePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (assign_roles, expire_hazards)
+                .chain()
+                .run_if(resource_equals(GameMode::Ghost)),
+        )
+        // Inputs arrive per tick, so the ghost's press is only seen in the tick it belongs to
+        .add_systems(
+            FixedUpdate,
+            (sabotage, hazard_knockback_system).run_if(resource_equals(GameMode::Ghost)),
+        );
+    }
+}
+
+// Pick a ghost once enough players are in, everyone else (including late joiners) is a runner.
+// If the ghost leaves, a new one is picked from the remaining players.
+fn assign_roles(
+    mut commands: Commands,
+    players: Query<(Entity, Option<&PlayerRole>), With<Player>>,
+    #[cfg(feature = "bevygap")] owners: Query<&ControlledBy>,
+    #[cfg(feature = "bevygap")] mut senders: Query<&mut MessageSender<RoleAssignment>>,
+) {
+    let has_ghost = players
+        .iter()
+        .any(|(_, role)| role == Some(&PlayerRole::Ghost));
+    let ghost = if has_ghost || players.iter().count() < GameMode::Ghost.min_players() {
+        None
+    } else {
+        players
+            .iter()
+            .map(|(entity, _)| entity)
+            .choose(&mut rand::thread_rng())
+    };
+
+    for (entity, role) in players.iter() {
+        let new_role = if Some(entity) == ghost {
+            PlayerRole::Ghost
+        } else if role.is_none() {
+            PlayerRole::Runner
+        } else {
+            continue;
+        };
+        if role == Some(&new_role) {
+            continue;
+        }
+
+        let mut player = commands.entity(entity);
+        player.insert(new_role);
+        if new_role == PlayerRole::Ghost {
+            info!("👻 Player {:?} is the ghost", entity);
+            player.insert(SabotageCooldown(Timer::from_seconds(
+                SABOTAGE_COOLDOWN_SECS,
+                TimerMode::Once,
+            )));
+        }
+
+        #[cfg(feature = "bevygap")]
+        {
+            let Ok(owner) = owners.get(entity) else {
+                continue;
+            };
+            // Only the ghost's own client keeps replicating the ghost
+            if new_role == PlayerRole::Ghost {
+                let mut visibility = NetworkVisibility::default();
+                visibility.gain_visibility(owner.owner);
+                player.insert(visibility);
+            }
+            if let Ok(mut sender) = senders.get_mut(owner.owner) {
+                sender.send::<Channel1>(RoleAssignment { role: new_role });
+            }
+        }
+    }
+}
+
+// The ghost drops a hazard where it stands, rate limited by a cooldown
+fn sabotage(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut ghosts: Query<(
+        &PlayerRole,
+        &PlayerTransform,
+        &ActionState<PlayerActions>,
+        &mut SabotageCooldown,
+    )>,
+) {
+    for (role, transform, actions, mut cooldown) in ghosts.iter_mut() {
+        if *role != PlayerRole::Ghost {
+            continue;
+        }
+        cooldown.0.tick(time.delta());
+        if !actions.just_pressed(&PlayerActions::Sabotage) || !cooldown.0.finished() {
+            continue;
+        }
+        cooldown.0.reset();
+
+        let position = transform.translation + Vec3::new(0.0, HAZARD_DROP_OFFSET, 0.0);
+        spawn_replicated(
+            &mut commands,
+            (
+                Hazard,
+                Transform::from_translation(position),
+                HazardLifetime(Timer::from_seconds(HAZARD_LIFETIME_SECS, TimerMode::Once)),
+            ),
+        );
+        info!(
+            "👻 Ghost dropped a hazard at ({:.0}, {:.0})",
+            position.x, position.y
+        );
+    }
+}
+
+fn expire_hazards(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut hazards: Query<(Entity, &mut HazardLifetime)>,
+) {
+    for (entity, mut lifetime) in hazards.iter_mut() {
+        if lifetime.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
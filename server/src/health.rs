@@ -0,0 +1,236 @@
+// Liveness and readiness probes for container orchestration, served on their own port
+// by a tiny HTTP listener on a separate thread:
+// - `GET /healthz`: the game loop is still ticking
+// - `GET /readyz`: the Lightyear listener is up, NATS is reachable, the certificate
+//   digest WebTransport clients need is available and the server isn't shutting down
+use bevy::prelude::*;
+use serde::Serialize;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "bevygap")]
+use lightyear::prelude::server::{NetcodeServer, Started};
+
+use crate::server_plugin::ServerMetadata;
+use crate::shutdown::Draining;
+use shared::ServiceCheck;
+
+// /healthz fails once the game loop hasn't reported in for this long
+const STALE_AFTER: Duration = Duration::from_secs(5);
+const NATS_TIMEOUT: Duration = Duration::from_millis(500);
+// A prober that connects and goes quiet doesn't keep its thread forever
+const PROBE_IO_TIMEOUT: Duration = Duration::from_secs(2);
+const DEFAULT_NATS_PORT: u16 = 4222;
+
+pub struct HealthPlugin {
+    /// 0 turns the probes off
+    pub port: u16,
+}
+
+// Written by the game loop, read by the listener thread
+#[derive(Default)]
+struct HealthState {
+    last_tick: Option<Instant>,
+    listening: bool,
+    cert_digest: bool,
+    draining: bool,
+}
+
+#[derive(Resource, Clone, Default)]
+struct SharedHealth(Arc<Mutex<HealthState>>);
+
+impl SharedHealth {
+    // The state is plain values, still good to read after a panic while it was held
+    fn lock(&self) -> MutexGuard<'_, HealthState> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[derive(Serialize)]
+struct ProbeResponse {
+    ok: bool,
+    checks: Vec<ServiceCheck>,
+}
+
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut App) {
+        if self.port == 0 {
+            return;
+        }
+        let health = SharedHealth::default();
+        let listener = match TcpListener::bind(("0.0.0.0", self.port)) {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!("🩺 Can't serve health probes on port {}: {}", self.port, e);
+                return;
+            }
+        };
+        info!("🩺 Health probes on :{} (/healthz, /readyz)", self.port);
+        let shared = health.clone();
+        thread::spawn(move || serve_probes(listener, shared));
+
+        app.insert_resource(health)
+            .add_systems(Update, report_health);
+    }
+}
+
+fn report_health(
+    health: Res<SharedHealth>,
+    metadata: Res<ServerMetadata>,
+    draining: Option<Res<Draining>>,
+    #[cfg(feature = "bevygap")] servers: Query<Has<Started>, With<NetcodeServer>>,
+) {
+    // Without bevygap there is no network listener, so there is nothing to wait for
+    #[cfg(feature = "bevygap")]
+    let listening = servers.iter().any(|started| started);
+    #[cfg(not(feature = "bevygap"))]
+    let listening = true;
+
+    let mut state = health.lock();
+    state.last_tick = Some(Instant::now());
+    state.listening = listening;
+    state.cert_digest = metadata.get_certificate_digest().is_some();
+    state.draining = draining.is_some();
+}
+
+fn serve_probes(listener: TcpListener, health: SharedHealth) {
+    for stream in listener.incoming().flatten() {
+        let health = health.clone();
+        thread::spawn(move || {
+            let _ = handle_probe(stream, &health);
+        });
+    }
+}
+
+fn handle_probe(mut stream: TcpStream, health: &SharedHealth) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(PROBE_IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(PROBE_IO_TIMEOUT))?;
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+
+    let checks = match path {
+        "/healthz" => vec![loop_check(&health.lock(), Instant::now())],
+        "/readyz" => {
+            let mut checks = {
+                let state = health.lock();
+                vec![
+                    loop_check(&state, Instant::now()),
+                    check("lightyear", state.listening, "listener not started"),
+                    check("cert_digest", state.cert_digest, "no certificate digest"),
+                    check("draining", !state.draining, "shutting down"),
+                ]
+            };
+            // Probed outside the lock, this can take up to NATS_TIMEOUT
+            if let Some(nats) = nats_check() {
+                checks.push(nats);
+            }
+            checks
+        }
+        _ => {
+            return write!(
+                stream,
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            );
+        }
+    };
+
+    let response = probe_response(checks);
+    let body = serde_json::to_string(&response).unwrap_or_default();
+    let status = if response.ok {
+        "200 OK"
+    } else {
+        "503 Service Unavailable"
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+fn probe_response(checks: Vec<ServiceCheck>) -> ProbeResponse {
+    ProbeResponse {
+        ok: checks.iter().all(|check| check.ok),
+        checks,
+    }
+}
+
+fn check(name: &str, ok: bool, error: &str) -> ServiceCheck {
+    ServiceCheck {
+        name: name.to_string(),
+        ok,
+        latency_ms: None,
+        error: (!ok).then(|| error.to_string()),
+    }
+}
+
+fn loop_check(state: &HealthState, now: Instant) -> ServiceCheck {
+    let ticking = state
+        .last_tick
+        .is_some_and(|tick| now.duration_since(tick) < STALE_AFTER);
+    check("game_loop", ticking, "game loop stalled")
+}
+
+// Only when bevygap talks to NATS, i.e. NATS_HOST is set
+fn nats_check() -> Option<ServiceCheck> {
+    if cfg!(not(feature = "bevygap")) {
+        return None;
+    }
+    let host = std::env::var("NATS_HOST").ok().filter(|h| !h.is_empty())?;
+    let address = nats_address(&host);
+    let started = Instant::now();
+    let reachable = address
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .is_some_and(|addr| TcpStream::connect_timeout(&addr, NATS_TIMEOUT).is_ok());
+    let mut nats = check("nats", reachable, &format!("{} unreachable", address));
+    nats.latency_ms = reachable.then(|| started.elapsed().as_millis() as u32);
+    Some(nats)
+}
+
+// NATS_HOST is a host name, or host:port
+fn nats_address(host: &str) -> String {
+    if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{}:{}", host, DEFAULT_NATS_PORT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn probes_fail_when_any_check_fails() {
+        let now = Instant::now();
+        let mut state = HealthState::default();
+        assert!(!loop_check(&state, now).ok);
+
+        state.last_tick = Some(now);
+        assert!(loop_check(&state, now).ok);
+        assert!(!loop_check(&state, now + STALE_AFTER).ok);
+
+        let ready = probe_response(vec![loop_check(&state, now), check("lightyear", true, "")]);
+        assert!(ready.ok);
+        let not_ready = probe_response(vec![
+            loop_check(&state, now),
+            check("lightyear", false, "listener not started"),
+        ]);
+        assert!(!not_ready.ok);
+        assert_eq!(
+            not_ready.checks[1].error.as_deref(),
+            Some("listener not started")
+        );
+
+        assert_eq!(nats_address("nats"), "nats:4222");
+        assert_eq!(nats_address("nats:4333"), "nats:4333");
+    }
+}
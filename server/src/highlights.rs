@@ -0,0 +1,100 @@
+// Post-match highlights: every player's airtime, coins and race splits are tracked over the
+// match, and when it ends the awards go to every client for the results screen and into
+// the match report.
+use bevy::prelude::*;
+
+use crate::match_report::{player_name, send_report, MatchEnded};
+use crate::profiles::AccountId;
+use shared::{
+    match_highlights, Contender, Highlight, MatchStats, Player, PlayerId, PlayerName, RaceProgress,
+    FIXED_DT,
+};
+
+#[cfg(feature = "bevygap")]
+use {
+    lightyear::prelude::*,
+    shared::{Channel1, MatchHighlights},
+};
+
+/// Awards of the match, empty until it ends. The match report takes them from here.
+#[derive(Resource, Default)]
+pub(crate) struct MatchAwards(pub Vec<Highlight>);
+
+pub struct HighlightsPlugin;
+
+impl Plugin for HighlightsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MatchAwards>()
+            .add_systems(FixedUpdate, track_stats)
+            .add_systems(Update, award_highlights.before(send_report));
+    }
+}
+
+fn track_stats(
+    mut commands: Commands,
+    mut players: Query<(
+        Entity,
+        &Player,
+        Option<Ref<RaceProgress>>,
+        Option<&mut MatchStats>,
+    )>,
+) {
+    for (entity, player, progress, stats) in players.iter_mut() {
+        let Some(mut stats) = stats else {
+            commands.entity(entity).insert(MatchStats::default());
+            continue;
+        };
+        stats.track_air(player.grounded, FIXED_DT);
+        if let Some(progress) = progress.filter(|progress| progress.is_changed()) {
+            stats.track_splits(&progress.splits);
+        }
+    }
+}
+
+// Only the first end counts, like the report
+fn award_highlights(
+    mut awards: ResMut<MatchAwards>,
+    mut ended: EventReader<MatchEnded>,
+    players: Query<(
+        &PlayerId,
+        Option<&PlayerName>,
+        Option<&AccountId>,
+        &MatchStats,
+    )>,
+    #[cfg(feature = "bevygap")] mut senders: Query<
+        &mut MessageSender<MatchHighlights>,
+        With<Connected>,
+    >,
+    mut awarded: Local<bool>,
+) {
+    let Some(event) = ended.read().next() else {
+        return;
+    };
+    if *awarded {
+        return;
+    }
+    *awarded = true;
+
+    let contenders: Vec<Contender> = players
+        .iter()
+        .map(|(id, name, account, stats)| Contender {
+            name: player_name(id, name),
+            player_id: account.map(|account| account.0.clone()),
+            stats,
+        })
+        .collect();
+    awards.0 = match_highlights(&event.standings, &contenders);
+    for highlight in &awards.0 {
+        info!("🎖️ {}", highlight.describe());
+    }
+    if awards.0.is_empty() {
+        return;
+    }
+
+    #[cfg(feature = "bevygap")]
+    for mut sender in senders.iter_mut() {
+        sender.send::<Channel1>(MatchHighlights {
+            highlights: awards.0.clone(),
+        });
+    }
+}
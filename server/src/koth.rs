@@ -0,0 +1,230 @@
+// King of the hill: one zone at a time awards points to the player holding it
+use bevy::prelude::*;
+
+use crate::server_plugin::spawn_replicated;
+use shared::{
+    GameMode, LevelDefinition, MatchRoom, Player, PlayerScore, PlayerTransform, ScoringZone, Team,
+};
+
+// Seconds before the next zone takes over
+const ZONE_ROTATION_SECS: f32 = 30.0;
+// Points for every full second a player holds the zone alone
+const POINTS_PER_SECOND: u32 = 1;
+
+// Position of a zone in the level's `zones` list, not replicated
+#[derive(Component)]
+struct ZoneOrder(usize);
+
+// Seconds a player has held the active zone since their last point, not replicated
+#[derive(Component, Default)]
+struct ZoneHoldTime(f32);
+
+#[derive(Resource)]
+struct ZoneRotation {
+    active: usize,
+    timer: Timer,
+}
+
+impl Default for ZoneRotation {
+    fn default() -> Self {
+        Self {
+            active: 0,
+            timer: Timer::from_seconds(ZONE_ROTATION_SECS, TimerMode::Repeating),
+        }
+    }
+}
+
+pub struct KothPlugin;
+
+impl Plugin for KothPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ZoneRotation>()
+            .add_systems(
+                Update,
+                (spawn_zones, rotate_zones)
+                    .chain()
+                    .run_if(resource_equals(GameMode::Koth)),
+            )
+            .add_systems(
+                FixedUpdate,
+                score_zones.run_if(resource_equals(GameMode::Koth)),
+            );
+    }
+}
+
+// Zones come from the level. Level rotation despawns them, so they are respawned for the new level here.
+fn spawn_zones(
+    mut commands: Commands,
+    level: Res<LevelDefinition>,
+    mut rotation: ResMut<ZoneRotation>,
+    zones: Query<(), With<ScoringZone>>,
+    mut warned: Local<Option<String>>,
+) {
+    if !zones.is_empty() {
+        return;
+    }
+    if level.zones.is_empty() {
+        if warned.as_deref() != Some(level.name.as_str()) {
+            warn!("👑 Level '{}' has no zones, nothing to hold", level.name);
+            *warned = Some(level.name.clone());
+        }
+        return;
+    }
+
+    *rotation = ZoneRotation::default();
+    for (order, (mut zone, transform)) in level.zone_bundles().enumerate() {
+        zone.active = order == rotation.active;
+        spawn_replicated(&mut commands, (zone, transform, ZoneOrder(order)));
+    }
+    info!(
+        "👑 Spawned {} zones for level '{}'",
+        level.zones.len(),
+        level.name
+    );
+}
+
+fn rotate_zones(
+    time: Res<Time>,
+    mut rotation: ResMut<ZoneRotation>,
+    mut zones: Query<(&mut ScoringZone, &ZoneOrder)>,
+) {
+    let count = zones.iter().count();
+    if count < 2 || !rotation.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    rotation.active = (rotation.active + 1) % count;
+    for (mut zone, order) in zones.iter_mut() {
+        let active = order.0 == rotation.active;
+        if zone.active != active {
+            zone.active = active;
+            zone.contested = false;
+        }
+    }
+    info!("👑 Zone {} is now active", rotation.active + 1);
+}
+
+// The sole player in the active zone earns points, a contested zone pays nobody. In team
+// matches a zone held by one team only pays every teammate in it. Lobby rooms sharing the
+// server each hold the zone on their own, the zone shows contested if any of them fight
+// over it.
+fn score_zones(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut zones: Query<(&mut ScoringZone, &Transform)>,
+    mut players: Query<
+        (
+            Entity,
+            &PlayerTransform,
+            Option<&mut PlayerScore>,
+            Option<&mut ZoneHoldTime>,
+            Option<&Team>,
+            Option<&MatchRoom>,
+        ),
+        With<Player>,
+    >,
+) {
+    for (mut zone, zone_transform) in zones.iter_mut() {
+        if !zone.active {
+            continue;
+        }
+        let inside: Vec<(Entity, Option<Team>, Option<MatchRoom>)> = players
+            .iter()
+            .filter(|(_, transform, ..)| {
+                transform
+                    .translation
+                    .truncate()
+                    .distance(zone_transform.translation.truncate())
+                    < zone.radius
+            })
+            .map(|(entity, _, _, _, team, room)| (entity, team.copied(), room.cloned()))
+            .collect();
+
+        let contested_in = |room: Option<&MatchRoom>| {
+            let teams: Vec<Option<Team>> = inside
+                .iter()
+                .filter(|(_, _, other)| other.as_ref() == room)
+                .map(|(_, team, _)| *team)
+                .collect();
+            match teams.first() {
+                Some(Some(team)) => teams.iter().any(|other| *other != Some(*team)),
+                _ => teams.len() > 1,
+            }
+        };
+        let contested = inside
+            .iter()
+            .any(|(_, _, room)| contested_in(room.as_ref()));
+        if zone.contested != contested {
+            zone.contested = contested;
+        }
+
+        for (entity, _, score, hold_time, _, room) in players.iter_mut() {
+            let holding =
+                !contested_in(room) && inside.iter().any(|(inside, ..)| *inside == entity);
+            // The tick a player steps in counts too, even before they have a hold time
+            let mut held = match (&hold_time, holding) {
+                (_, false) => 0.0,
+                (Some(hold_time), true) => hold_time.0 + time.delta_secs(),
+                (None, true) => time.delta_secs(),
+            };
+            let point = held >= 1.0;
+            if point {
+                held -= 1.0;
+            }
+            match hold_time {
+                Some(mut hold_time) => hold_time.0 = held,
+                None if holding => {
+                    commands.entity(entity).insert(ZoneHoldTime(held));
+                }
+                None => {}
+            }
+            if !point {
+                continue;
+            }
+            match score {
+                Some(mut score) => score.score += POINTS_PER_SECOND,
+                None => {
+                    commands.entity(entity).insert(PlayerScore {
+                        score: POINTS_PER_SECOND,
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn the_first_tick_in_the_zone_counts() {
+        let mut app = App::new();
+        app.init_resource::<Time>().add_systems(Update, score_zones);
+        app.world_mut().spawn((
+            ScoringZone {
+                radius: 50.0,
+                active: true,
+                contested: false,
+            },
+            Transform::default(),
+        ));
+        let player = app
+            .world_mut()
+            .spawn((Player::default(), PlayerTransform::default()))
+            .id();
+
+        // 0.6s on stepping in, 0.6s more: a full second held
+        for _ in 0..2 {
+            app.world_mut()
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_millis(600));
+            app.update();
+        }
+        let score = app.world().get::<PlayerScore>(player).map(|s| s.score);
+        assert_eq!(score, Some(POINTS_PER_SECOND));
+        let held = app.world().get::<ZoneHoldTime>(player).unwrap().0;
+        assert!((held - 0.2).abs() < 1e-4, "{held}");
+    }
+}
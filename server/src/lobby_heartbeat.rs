@@ -0,0 +1,102 @@
+// Tells the lobby service every few seconds which of its rooms this server has open. The
+// lobby's reconciliation compares that with its own rooms and Edgegap's deployments, and
+// closes rooms whose server is gone or stops servers nobody needs (see `shared::reconcile`).
+use bevy::prelude::*;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::server_plugin::RoomRegistry;
+use shared::{ServerHeartbeat, ServerRoomStatus};
+
+const HEARTBEAT_INTERVAL_SECS: f32 = 15.0;
+const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// From --lobby-url, VOIDLOOP_LOBBY_TOKEN and Edgegap's ARBITRIUM_REQUEST_ID
+#[derive(Resource, Clone, Debug, Default)]
+pub struct LobbyHeartbeatConfig {
+    pub lobby_url: Option<String>,
+    pub token: Option<String>,
+    pub deployment: Option<String>,
+}
+
+pub struct LobbyHeartbeatPlugin {
+    pub config: LobbyHeartbeatConfig,
+}
+
+impl Plugin for LobbyHeartbeatPlugin {
+    fn build(&self, app: &mut App) {
+        let Some(url) = &self.config.lobby_url else {
+            return;
+        };
+        info!(
+            "💓 Sending server heartbeats to {} every {}s (deployment {})",
+            url,
+            HEARTBEAT_INTERVAL_SECS,
+            self.config.deployment.as_deref().unwrap_or("-")
+        );
+        app.insert_resource(self.config.clone())
+            .add_systems(Update, send_heartbeat);
+    }
+}
+
+fn send_heartbeat(
+    time: Res<Time>,
+    config: Res<LobbyHeartbeatConfig>,
+    rooms: Res<RoomRegistry>,
+    mut timer: Local<Option<Timer>>,
+    mut in_flight: Local<Option<JoinHandle<()>>>,
+) {
+    let timer = timer
+        .get_or_insert_with(|| Timer::from_seconds(HEARTBEAT_INTERVAL_SECS, TimerMode::Repeating));
+    if !timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    // A lobby that is slow to answer gets one heartbeat at a time
+    if in_flight.as_ref().is_some_and(|post| !post.is_finished()) {
+        return;
+    }
+    let Some(url) = &config.lobby_url else {
+        return;
+    };
+    let mut room_list: Vec<ServerRoomStatus> = rooms
+        .rooms
+        .values()
+        .map(|room| ServerRoomStatus {
+            room_id: room.room_id.clone(),
+            players: room.current_players,
+        })
+        .collect();
+    room_list.sort_by(|a, b| a.room_id.cmp(&b.room_id));
+    let heartbeat = ServerHeartbeat {
+        deployment: config.deployment.clone(),
+        rooms: room_list,
+    };
+    *in_flight = Some(post_heartbeat(
+        format!("{}/servers/heartbeat", url.trim_end_matches('/')),
+        config.token.clone(),
+        heartbeat,
+    ));
+}
+
+// Off the main thread like the match report, a missed heartbeat is only logged
+fn post_heartbeat(
+    url: String,
+    token: Option<String>,
+    heartbeat: ServerHeartbeat,
+) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut request = reqwest::blocking::Client::new()
+            .post(&url)
+            .json(&heartbeat)
+            .timeout(HEARTBEAT_TIMEOUT);
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        if let Err(e) = request
+            .send()
+            .and_then(|response| response.error_for_status())
+        {
+            warn!("💓 Failed to send the server heartbeat to {}: {}", url, e);
+        }
+    })
+}
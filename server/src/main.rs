@@ -1,10 +1,38 @@
 use bevy::prelude::*;
 use clap::Parser;
-use server_plugin::ServerPlugin;
+use server_plugin::{LevelRotation, ServerPlugin};
+use shared::{
+    BotStyle, GameConfig, GameMode, LevelDefinition, DEFAULT_COUNTDOWN_SECS, DEFAULT_GAME_CONFIG,
+    DEFAULT_LEVEL,
+};
 use std::env;
+use std::path::Path;
 
+mod anti_cheat;
+mod bots;
 mod build_info;
+#[cfg(feature = "bevygap")]
+mod clock_sync;
+mod countdown;
+mod fall_out;
+mod game_config;
+mod ghost_mode;
+mod health;
+mod highlights;
+mod koth;
+mod lobby_heartbeat;
+mod match_report;
+mod net_quality;
+mod profiles;
+mod race;
+mod ranked;
+#[cfg(feature = "bevygap")]
+mod relevance;
+mod room_events;
 mod server_plugin;
+mod shutdown;
+mod spawns;
+mod teams;
 //test
 
 #[derive(Parser, Debug)]
@@ -29,6 +57,112 @@ struct Args {
     /// NATS certificate contents (for Edgegap deployment workaround)
     #[arg(long)]
     ca_contents: Option<String>,
+
+    /// Level to load: a built-in level name, a name under assets/levels/, or a path to a .json file
+    #[arg(long, env = "VOIDLOOP_LEVEL", default_value = DEFAULT_LEVEL)]
+    level: String,
+
+    /// Additional levels to rotate through after --level (comma separated)
+    #[arg(long, env = "VOIDLOOP_LEVEL_ROTATION", value_delimiter = ',')]
+    level_rotation: Vec<String>,
+
+    /// Seconds each level stays active when a rotation is configured
+    #[arg(long, default_value_t = 300)]
+    rotation_interval: u64,
+
+    /// Game mode to run (casual, ranked, custom, coop, ghost, koth, race)
+    #[arg(long, env = "VOIDLOOP_GAME_MODE", default_value_t = GameMode::Casual)]
+    game_mode: GameMode,
+
+    /// Physics to play with: a built-in preset (default, low_gravity) or a path to a .json file
+    #[arg(long, env = "VOIDLOOP_GAME_CONFIG", default_value = DEFAULT_GAME_CONFIG)]
+    game_config: String,
+
+    /// Overrides the game config's gravity, negative pulls down
+    #[arg(long, env = "VOIDLOOP_GRAVITY", allow_negative_numbers = true)]
+    gravity: Option<f32>,
+
+    /// Overrides the game config's running speed
+    #[arg(long, env = "VOIDLOOP_MOVE_SPEED")]
+    move_speed: Option<f32>,
+
+    /// Overrides the game config's jump force
+    #[arg(long, env = "VOIDLOOP_JUMP_FORCE")]
+    jump_force: Option<f32>,
+
+    /// Overrides the game config's round time in seconds, 0 for no limit
+    #[arg(long, env = "VOIDLOOP_ROUND_SECS")]
+    round_secs: Option<u32>,
+
+    /// Overrides whether the level keeps its pickups (true or false)
+    #[arg(long, env = "VOIDLOOP_PICKUPS")]
+    pickups: Option<bool>,
+
+    /// Leaderboard API endpoint that final race results are POSTed to
+    #[arg(long, env = "VOIDLOOP_LEADERBOARD_URL")]
+    leaderboard_url: Option<String>,
+
+    /// Teams to split players into, 0 for free-for-all
+    #[arg(long, env = "VOIDLOOP_TEAMS", default_value_t = 0)]
+    teams: usize,
+
+    /// Let teammates stomp each other
+    #[arg(long, env = "VOIDLOOP_FRIENDLY_FIRE")]
+    friendly_fire: bool,
+
+    /// Seconds counted down before players can move (0 disables the countdown)
+    #[arg(long, env = "VOIDLOOP_COUNTDOWN_SECS", default_value_t = DEFAULT_COUNTDOWN_SECS)]
+    countdown_secs: u32,
+
+    /// Fill matches with bots up to this many players (0 disables bots)
+    #[arg(
+        long,
+        visible_alias = "bots",
+        env = "VOIDLOOP_BOT_FILL",
+        default_value_t = 0
+    )]
+    bot_fill: usize,
+
+    /// How bots play: passive, normal or aggressive
+    #[arg(long, env = "VOIDLOOP_BOT_STYLE", default_value_t = BotStyle::Normal)]
+    bot_style: BotStyle,
+
+    /// Matchmaker endpoint the humans' skill ratings are fetched from to tune bot difficulty
+    #[arg(long, env = "VOIDLOOP_SKILL_RATINGS_URL")]
+    skill_ratings_url: Option<String>,
+
+    /// Port for the /healthz and /readyz probes (0 disables them)
+    #[arg(long, env = "VOIDLOOP_HEALTH_PORT", default_value_t = 6422)]
+    health_port: u16,
+
+    /// Lobby service API the match result, heartbeats and anti-cheat reports go to, e.g. http://lobby:3001/lobby/api
+    #[arg(long, env = "VOIDLOOP_LOBBY_URL")]
+    lobby_url: Option<String>,
+
+    /// Lobby room this server was started for, set by the matchmaker
+    #[arg(long, env = "VOIDLOOP_ROOM_ID")]
+    room_id: Option<String>,
+
+    /// Bearer token for the lobby's match result, heartbeat and anti-cheat endpoints
+    #[arg(long, env = "VOIDLOOP_LOBBY_TOKEN", hide_env_values = true)]
+    lobby_token: Option<String>,
+
+    /// Edgegap deployment this server runs in, sent with the lobby heartbeat
+    #[arg(long, env = "ARBITRIUM_REQUEST_ID")]
+    deployment_id: Option<String>,
+
+    /// Movement violations that get a client disconnected, 0 only logs and reports them
+    #[arg(long, env = "VOIDLOOP_ANTICHEAT_KICK_AFTER", default_value_t = 5)]
+    anticheat_kick_after: u32,
+
+    /// Seconds players get to wrap up after SIGTERM before the server exits
+    #[arg(long, env = "VOIDLOOP_SHUTDOWN_GRACE_SECS", default_value_t = 30)]
+    shutdown_grace: u64,
+
+    /// Start even without a valid LIGHTYEAR_PRIVATE_KEY, using the all-zero key anyone can
+    /// sign connect tokens with (local development only)
+    #[arg(long)]
+    allow_dummy_key: bool,
 }
 
 fn main() {
@@ -40,9 +174,54 @@ fn main() {
         handle_ca_contents(ca_contents);
     }
 
+    // Only players the matchmaker handed a connect token may join, and that only holds
+    // if nobody else can sign tokens
+    #[cfg(feature = "bevygap")]
+    if let Err(e) = server_plugin::read_lightyear_private_key_from_env() {
+        if !args.allow_dummy_key {
+            eprintln!(
+                "❌ {}. Set the key the matchmaker signs connect tokens with, or pass --allow-dummy-key for local development",
+                e
+            );
+            std::process::exit(1);
+        }
+    }
+
     // Generate certificate digest using the same approach as bevygap-spaceships
     let cert_digest = generate_certificate_digest();
 
+    // Modes tied to a level (co-op) load it unless a level was picked explicitly
+    let first_level = match args.game_mode.required_level() {
+        Some(required) if args.level == DEFAULT_LEVEL => required.to_string(),
+        _ => args.level.clone(),
+    };
+
+    let mut levels = Vec::new();
+    for name in std::iter::once(&first_level).chain(args.level_rotation.iter()) {
+        match load_level(name) {
+            Ok(level) => levels.push(level),
+            Err(e) => {
+                eprintln!("❌ Failed to load level '{}': {}", name, e);
+                std::process::exit(1);
+            }
+        }
+    }
+    let game_config = match load_game_config(&args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!(
+                "❌ Failed to load game config '{}': {}",
+                args.game_config, e
+            );
+            std::process::exit(1);
+        }
+    };
+
+    let rotation = LevelRotation::new(
+        levels,
+        std::time::Duration::from_secs(args.rotation_interval),
+    );
+
     // Display the logo at startup
 
     println!(
@@ -80,6 +259,54 @@ fn main() {
     info!("📡 Listening on {}:{}", args.host, args.port);
     info!("🚢 Transport port: {}", args.transport_port);
     info!("🔄 Transport type: {}", args.transport);
+    info!(
+        "🎲 Game mode: {} ({})",
+        args.game_mode,
+        args.game_mode.description()
+    );
+    info!(
+        "🗺️  Level: {} ({} platforms)",
+        rotation.current().name,
+        rotation.current().platforms.len()
+    );
+    if rotation.level_count() > 1 {
+        info!(
+            "🔁 Level rotation: {} levels, {}s each",
+            rotation.level_count(),
+            args.rotation_interval
+        );
+    }
+    if game_config != GameConfig::default() {
+        info!(
+            "🪐 Physics: move speed {}, jump force {}, gravity {}, max fall speed {}",
+            game_config.move_speed,
+            game_config.jump_force,
+            game_config.gravity,
+            game_config.max_fall_speed
+        );
+    }
+    if game_config.round_secs > 0 {
+        info!("⏱️ Round time: {}s", game_config.round_secs);
+    }
+    if !game_config.pickups {
+        info!("🚫 Pickups off");
+    }
+    if args.bot_fill > 0 {
+        info!(
+            "🤖 Bot fill: up to {} players, {} bots",
+            args.bot_fill, args.bot_style
+        );
+    }
+    let team_rules = shared::TeamRules {
+        teams: args.teams.max(args.game_mode.team_count()),
+        friendly_fire: args.friendly_fire,
+    };
+    if team_rules.teams > 0 {
+        info!(
+            "👥 Teams: {}, friendly fire: {}",
+            team_rules.teams, team_rules.friendly_fire
+        );
+    }
     info!("📋 {}", build_info.format_for_log());
     info!("🔧 Build Details:");
     info!("   Git SHA: {}", build_info.git_sha);
@@ -88,6 +315,7 @@ fn main() {
     info!("   Target: {}", build_info.target_triple);
     info!("   Author: {}", build_info.git_commit_author);
     info!("   System: {}", build_info.system_info);
+    info!("   Assets: {}", build_info.asset_manifest_version);
 
     // Log certificate digest information
     if let Some(ref digest) = cert_digest {
@@ -97,7 +325,124 @@ fn main() {
         warn!("🔐 No certificate digest available - WebTransport may not work");
     }
 
-    App::new().add_plugins(ServerPlugin::new(cert_digest)).run();
+    App::new()
+        .add_plugins(ServerPlugin::new(
+            cert_digest,
+            rotation,
+            args.game_mode,
+            args.leaderboard_url,
+            bots::BotConfig {
+                fill: args.bot_fill,
+                style: args.bot_style,
+                ratings_url: args.skill_ratings_url,
+            },
+        ))
+        .add_plugins(health::HealthPlugin {
+            port: args.health_port,
+        })
+        .add_plugins(teams::TeamsPlugin { rules: team_rules })
+        .add_plugins(game_config::GameConfigPlugin {
+            config: game_config,
+        })
+        .add_plugins(countdown::CountdownPlugin {
+            secs: args.countdown_secs,
+        })
+        .add_plugins(net_quality::NetworkQualityPlugin)
+        .add_plugins(anti_cheat::AntiCheatPlugin {
+            config: anti_cheat::AntiCheatConfig {
+                kick_after: args.anticheat_kick_after,
+                lobby_url: args.lobby_url.clone(),
+                room_id: args.room_id.clone(),
+                token: args.lobby_token.clone(),
+            },
+        })
+        .add_plugins(lobby_heartbeat::LobbyHeartbeatPlugin {
+            config: lobby_heartbeat::LobbyHeartbeatConfig {
+                lobby_url: args.lobby_url.clone(),
+                token: args.lobby_token.clone(),
+                deployment: args.deployment_id.clone(),
+            },
+        })
+        .add_plugins(room_events::RoomEventsPlugin {
+            config: room_events::RoomEventsConfig {
+                origin: format!(
+                    "server:{}",
+                    args.deployment_id
+                        .clone()
+                        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+                ),
+                deployment: args.deployment_id,
+                room_id: args.room_id.clone(),
+            },
+        })
+        .add_plugins(match_report::MatchReportPlugin {
+            config: match_report::MatchReportConfig {
+                lobby_url: args.lobby_url,
+                room_id: args.room_id,
+                token: args.lobby_token,
+            },
+        })
+        .add_plugins(highlights::HighlightsPlugin)
+        .add_plugins(shutdown::ShutdownPlugin {
+            grace: std::time::Duration::from_secs(args.shutdown_grace),
+        })
+        .run();
+}
+
+/// Resolve the `--level` argument to a level definition.
+/// Files on disk win over built-in levels so maps can be edited without recompiling.
+fn load_level(level: &str) -> Result<LevelDefinition, shared::LevelError> {
+    let candidates = [
+        Path::new(level).to_path_buf(),
+        Path::new("assets/levels").join(format!("{}.json", level)),
+    ];
+
+    if let Some(path) = candidates.iter().find(|p| p.is_file()) {
+        return LevelDefinition::load(path);
+    }
+
+    LevelDefinition::builtin(level).ok_or_else(|| {
+        shared::LevelError::Io(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("no level file or built-in level named '{}'", level),
+        ))
+    })
+}
+
+/// Resolve `--game-config` like `--level`, then apply the single value overrides on top.
+fn load_game_config(args: &Args) -> Result<GameConfig, shared::GameConfigError> {
+    let path = Path::new(&args.game_config);
+    let mut config = if path.is_file() {
+        GameConfig::load(path)?
+    } else {
+        GameConfig::builtin(&args.game_config).ok_or_else(|| {
+            shared::GameConfigError::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!(
+                    "no file or built-in preset named '{}' (presets: {})",
+                    args.game_config,
+                    GameConfig::builtin_names().collect::<Vec<_>>().join(", ")
+                ),
+            ))
+        })?
+    };
+    if let Some(gravity) = args.gravity {
+        config.gravity = gravity;
+    }
+    if let Some(move_speed) = args.move_speed {
+        config.move_speed = move_speed;
+    }
+    if let Some(jump_force) = args.jump_force {
+        config.jump_force = jump_force;
+    }
+    if let Some(round_secs) = args.round_secs {
+        config.round_secs = round_secs;
+    }
+    if let Some(pickups) = args.pickups {
+        config.pickups = pickups;
+    }
+    config.validate()?;
+    Ok(config)
 }
 
 /// Generate certificate digest using the same approach as bevygap-spaceships
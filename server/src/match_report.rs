@@ -0,0 +1,303 @@
+// Tells the lobby service when this server's match is over, so the room is marked
+// finished with its result instead of staying "started" forever. The matchmaker starts
+// servers for lobby rooms with VOIDLOOP_ROOM_ID set; without it there is nobody to tell.
+use bevy::prelude::*;
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::highlights::MatchAwards;
+use crate::net_quality::MatchNetwork;
+use crate::profiles::AccountId;
+use crate::shutdown::Draining;
+use shared::{
+    BotTag, GameConfig, GameMode, LevelDefinition, MatchEndReason, MatchReport, MatchStanding,
+    Player, PlayerId, PlayerName, PlayerScore,
+};
+
+const REPORT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// From --lobby-url, --room-id and VOIDLOOP_LOBBY_TOKEN
+#[derive(Resource, Clone, Debug, Default)]
+pub struct MatchReportConfig {
+    pub lobby_url: Option<String>,
+    pub room_id: Option<String>,
+    pub token: Option<String>,
+}
+
+pub struct MatchReportPlugin {
+    pub config: MatchReportConfig,
+}
+
+/// A mode's match is over. Only the first one per server is reported, the lobby room
+/// is gone after that.
+#[derive(Event)]
+pub struct MatchEnded {
+    pub reason: MatchEndReason,
+    /// Best first
+    pub standings: Vec<MatchStanding>,
+}
+
+#[derive(Resource, Default)]
+struct MatchClock {
+    // Seconds since startup when the first human joined
+    started: Option<f64>,
+    reported: bool,
+}
+
+impl Plugin for MatchReportPlugin {
+    fn build(&self, app: &mut App) {
+        if self.config.room_id.is_some() && self.config.lobby_url.is_none() {
+            warn!("🏁 --room-id without --lobby-url, the match result won't be reported");
+        }
+        app.insert_resource(self.config.clone())
+            .init_resource::<MatchClock>()
+            .add_event::<MatchEnded>()
+            .add_systems(
+                Update,
+                (
+                    track_players,
+                    end_on_shutdown,
+                    end_on_round_time,
+                    send_report,
+                )
+                    .chain(),
+            );
+    }
+}
+
+// The match starts with the first human and is abandoned when the last one leaves
+fn track_players(
+    time: Res<Time>,
+    mut clock: ResMut<MatchClock>,
+    humans: Query<(), (With<Player>, Without<BotTag>)>,
+    mut ended: EventWriter<MatchEnded>,
+) {
+    match clock.started {
+        None if !humans.is_empty() => clock.started = Some(time.elapsed_secs_f64()),
+        Some(_) if humans.is_empty() && !clock.reported => {
+            ended.write(MatchEnded {
+                reason: MatchEndReason::Abandoned,
+                standings: Vec::new(),
+            });
+        }
+        _ => {}
+    }
+}
+
+// Races end themselves when the server drains, every other mode ends on its score
+fn end_on_shutdown(
+    mode: Res<GameMode>,
+    draining: Option<Res<Draining>>,
+    clock: Res<MatchClock>,
+    players: Query<
+        (
+            &PlayerId,
+            Option<&PlayerName>,
+            Option<&AccountId>,
+            Option<&PlayerScore>,
+        ),
+        With<Player>,
+    >,
+    mut ended: EventWriter<MatchEnded>,
+) {
+    if !draining.is_some_and(|draining| draining.is_added())
+        || *mode == GameMode::Race
+        || clock.started.is_none()
+    {
+        return;
+    }
+    ended.write(MatchEnded {
+        reason: MatchEndReason::Shutdown,
+        standings: score_standings(players.iter()),
+    });
+}
+
+// A rule set's round time ends the match on its score. Races end when they're decided.
+fn end_on_round_time(
+    time: Res<Time>,
+    mode: Res<GameMode>,
+    config: Res<GameConfig>,
+    clock: Res<MatchClock>,
+    players: Query<
+        (
+            &PlayerId,
+            Option<&PlayerName>,
+            Option<&AccountId>,
+            Option<&PlayerScore>,
+        ),
+        With<Player>,
+    >,
+    mut ended: EventWriter<MatchEnded>,
+) {
+    let Some(started) = clock.started else {
+        return;
+    };
+    if config.round_secs == 0
+        || clock.reported
+        || *mode == GameMode::Race
+        || time.elapsed_secs_f64() - started < config.round_secs as f64
+    {
+        return;
+    }
+    info!("⏱️ Round time is up after {}s", config.round_secs);
+    ended.write(MatchEnded {
+        reason: MatchEndReason::Completed,
+        standings: score_standings(players.iter()),
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn send_report(
+    time: Res<Time>,
+    mode: Res<GameMode>,
+    level: Res<LevelDefinition>,
+    config: Res<MatchReportConfig>,
+    draining: Option<Res<Draining>>,
+    network: Res<MatchNetwork>,
+    awards: Res<MatchAwards>,
+    mut clock: ResMut<MatchClock>,
+    mut ended: EventReader<MatchEnded>,
+) {
+    for event in ended.read() {
+        if clock.reported {
+            continue;
+        }
+        clock.reported = true;
+        let report = MatchReport {
+            game_mode: mode.id().to_string(),
+            level: level.name.clone(),
+            reason: event.reason,
+            ended_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs()),
+            duration_secs: clock
+                .started
+                .map_or(0.0, |started| (time.elapsed_secs_f64() - started) as f32),
+            standings: event.standings.clone(),
+            network: network.summaries(),
+            highlights: awards.0.clone(),
+        };
+        info!(
+            "🏁 Match over ({:?}) after {:.0}s",
+            report.reason, report.duration_secs
+        );
+        for quality in &report.network {
+            info!(
+                "📶 {}: {:?}, rtt {:.0}/{:.0} ms (avg/p95), jitter {:.0} ms, loss {:.1}%, {:.1} corrections/min",
+                quality.name,
+                quality.verdict,
+                quality.rtt_avg_ms,
+                quality.rtt_p95_ms,
+                quality.jitter_ms,
+                quality.packet_loss * 100.0,
+                quality.corrections_per_min
+            );
+        }
+
+        let (Some(url), Some(room_id)) = (&config.lobby_url, &config.room_id) else {
+            continue;
+        };
+        let post = post_report(
+            format!("{}/rooms/{}/finish", url.trim_end_matches('/'), room_id),
+            config.token.clone(),
+            report,
+        );
+        // The process is about to exit and would take the post with it
+        if draining.is_some() {
+            let _ = post.join();
+        }
+    }
+}
+
+// Post on a background thread so a slow lobby never stalls the tick
+fn post_report(url: String, token: Option<String>, report: MatchReport) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut request = reqwest::blocking::Client::new()
+            .post(&url)
+            .json(&report)
+            .timeout(REPORT_TIMEOUT);
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        match request
+            .send()
+            .and_then(|response| response.error_for_status())
+        {
+            Ok(_) => info!("📤 Match result reported to {}", url),
+            Err(e) => warn!("📤 Failed to report the match result to {}: {}", url, e),
+        }
+    })
+}
+
+pub(crate) fn player_name(id: &PlayerId, name: Option<&PlayerName>) -> String {
+    name.map_or_else(|| format!("Player {}", id.id), |name| name.name.clone())
+}
+
+/// Standings of a mode that ranks by `PlayerScore`, best first
+pub(crate) fn score_standings<'a>(
+    players: impl Iterator<
+        Item = (
+            &'a PlayerId,
+            Option<&'a PlayerName>,
+            Option<&'a AccountId>,
+            Option<&'a PlayerScore>,
+        ),
+    >,
+) -> Vec<MatchStanding> {
+    rank_by_score(
+        players
+            .map(|(id, name, account, score)| MatchStanding {
+                place: 0,
+                name: player_name(id, name),
+                player_id: account.map(|account| account.0.clone()),
+                score: score.map_or(0, |s| s.score),
+                time: None,
+            })
+            .collect(),
+    )
+}
+
+// Highest score first, tied players share a place (1, 1, 3)
+fn rank_by_score(mut standings: Vec<MatchStanding>) -> Vec<MatchStanding> {
+    standings.sort_by_key(|standing| std::cmp::Reverse(standing.score));
+    let mut previous: Option<(u32, usize)> = None;
+    for (i, standing) in standings.iter_mut().enumerate() {
+        standing.place = match previous {
+            Some((score, place)) if score == standing.score => place,
+            _ => i + 1,
+        };
+        previous = Some((standing.score, standing.place));
+    }
+    standings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scored(name: &str, score: u32) -> MatchStanding {
+        MatchStanding {
+            place: 0,
+            name: name.to_string(),
+            player_id: None,
+            score,
+            time: None,
+        }
+    }
+
+    #[test]
+    fn ties_share_a_place() {
+        let standings = rank_by_score(vec![
+            scored("b", 3),
+            scored("a", 7),
+            scored("c", 3),
+            scored("d", 1),
+        ]);
+        let places: Vec<(&str, usize)> = standings
+            .iter()
+            .map(|s| (s.name.as_str(), s.place))
+            .collect();
+        assert_eq!(places, vec![("a", 1), ("b", 2), ("c", 2), ("d", 4)]);
+        assert!(rank_by_score(Vec::new()).is_empty());
+    }
+}
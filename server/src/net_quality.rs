@@ -0,0 +1,234 @@
+// Connection quality over a match: every client sends a `NetworkProbe` once a second on
+// an unreliable channel. Gaps in the sequence numbers are packet loss, the round trips
+// the client timed give latency and jitter, and its correction count shows how often
+// prediction went wrong. The match report carries one summary per player.
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+use shared::{ConnectionVerdict, NetworkProbe, NetworkQuality};
+
+#[cfg(feature = "bevygap")]
+use {
+    crate::match_report::player_name,
+    lightyear::prelude::*,
+    shared::{Player, PlayerId, PlayerName, PlayerStatus, ProbeChannel, ProbeEcho},
+};
+
+pub struct NetworkQualityPlugin;
+
+impl Plugin for NetworkQualityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MatchNetwork>();
+
+        #[cfg(feature = "bevygap")]
+        app.add_systems(
+            Update,
+            (receive_probes, name_connections, share_pings).chain(),
+        );
+    }
+}
+
+/// Probe statistics of every client that connected during the match, kept after they
+/// leave so an abandoned match still shows whose connection dropped
+#[derive(Resource, Default)]
+pub struct MatchNetwork {
+    clients: HashMap<Entity, ClientProbes>,
+}
+
+impl MatchNetwork {
+    pub fn summaries(&self) -> Vec<NetworkQuality> {
+        let mut summaries: Vec<NetworkQuality> = self
+            .clients
+            .iter()
+            .filter(|(_, probes)| probes.received > 0)
+            .map(|(client, probes)| {
+                let name = probes
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format!("client {:?}", client));
+                probes.summary(name)
+            })
+            .collect();
+        summaries.sort_by(|a, b| a.name.cmp(&b.name));
+        summaries
+    }
+}
+
+#[derive(Default)]
+struct ClientProbes {
+    name: Option<String>,
+    first_seq: Option<u32>,
+    highest_seq: u32,
+    received: u32,
+    rtts_ms: Vec<f32>,
+    // Corrections the client had counted when its first probe arrived, and since
+    first_corrections: u32,
+    corrections: u32,
+    first_seen: f64,
+    last_seen: f64,
+}
+
+impl ClientProbes {
+    #[cfg_attr(not(feature = "bevygap"), allow(dead_code))]
+    fn record(&mut self, now: f64, probe: &NetworkProbe) {
+        if self.first_seq.is_none() {
+            self.first_seq = Some(probe.seq);
+            self.first_corrections = probe.corrections;
+            self.first_seen = now;
+        }
+        self.highest_seq = self.highest_seq.max(probe.seq);
+        self.received += 1;
+        if let Some(rtt) = probe.rtt_ms {
+            self.rtts_ms.push(rtt as f32);
+        }
+        self.corrections = probe.corrections;
+        self.last_seen = now;
+    }
+
+    // The round trip nameplates show, the latest rather than an average so it reacts
+    #[cfg_attr(not(feature = "bevygap"), allow(dead_code))]
+    fn latest_rtt_ms(&self) -> Option<u16> {
+        self.rtts_ms.last().map(|rtt| *rtt as u16)
+    }
+
+    fn summary(&self, name: String) -> NetworkQuality {
+        let sent = self.highest_seq - self.first_seq.unwrap_or_default() + 1;
+        let packet_loss = (1.0 - self.received as f32 / sent as f32).max(0.0);
+
+        let rtt_avg_ms = if self.rtts_ms.is_empty() {
+            0.0
+        } else {
+            self.rtts_ms.iter().sum::<f32>() / self.rtts_ms.len() as f32
+        };
+        let mut sorted = self.rtts_ms.clone();
+        sorted.sort_by(f32::total_cmp);
+        let rtt_p95_ms = sorted
+            .get((sorted.len() * 95 / 100).min(sorted.len().saturating_sub(1)))
+            .copied()
+            .unwrap_or_default();
+        let jitter_ms = if self.rtts_ms.len() < 2 {
+            0.0
+        } else {
+            self.rtts_ms
+                .windows(2)
+                .map(|pair| (pair[1] - pair[0]).abs())
+                .sum::<f32>()
+                / (self.rtts_ms.len() - 1) as f32
+        };
+
+        let connected_secs = (self.last_seen - self.first_seen) as f32;
+        let corrections = self.corrections.saturating_sub(self.first_corrections);
+        let corrections_per_min = if connected_secs > 0.0 {
+            corrections as f32 * 60.0 / connected_secs
+        } else {
+            0.0
+        };
+
+        NetworkQuality {
+            name,
+            connected_secs,
+            rtt_avg_ms,
+            rtt_p95_ms,
+            jitter_ms,
+            packet_loss,
+            corrections_per_min,
+            verdict: ConnectionVerdict::rate(rtt_p95_ms, jitter_ms, packet_loss),
+        }
+    }
+}
+
+#[cfg(feature = "bevygap")]
+fn receive_probes(
+    time: Res<Time<Real>>,
+    mut network: ResMut<MatchNetwork>,
+    mut clients: Query<(
+        Entity,
+        &mut MessageReceiver<NetworkProbe>,
+        &mut MessageSender<ProbeEcho>,
+    )>,
+) {
+    let now = time.elapsed_secs_f64();
+    for (client, mut receiver, mut sender) in clients.iter_mut() {
+        for probe in receiver.receive() {
+            sender.send::<ProbeChannel>(ProbeEcho { seq: probe.seq });
+            network
+                .clients
+                .entry(client)
+                .or_default()
+                .record(now, &probe);
+        }
+    }
+}
+
+#[cfg(feature = "bevygap")]
+fn name_connections(
+    mut network: ResMut<MatchNetwork>,
+    players: Query<(&ControlledBy, &PlayerId, Option<&PlayerName>), With<Player>>,
+) {
+    for (owner, id, name) in players.iter() {
+        if let Some(probes) = network.clients.get_mut(&owner.owner) {
+            probes.name = Some(player_name(id, name));
+        }
+    }
+}
+
+#[cfg(feature = "bevygap")]
+fn share_pings(
+    network: Res<MatchNetwork>,
+    mut players: Query<(&ControlledBy, &mut PlayerStatus), With<Player>>,
+) {
+    for (owner, mut status) in players.iter_mut() {
+        let ping_ms = network
+            .clients
+            .get(&owner.owner)
+            .and_then(ClientProbes::latest_rtt_ms);
+        if status.ping_ms != ping_ms {
+            status.ping_ms = ping_ms;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probe(seq: u32, rtt_ms: u16, corrections: u32) -> NetworkProbe {
+        NetworkProbe {
+            seq,
+            rtt_ms: Some(rtt_ms),
+            corrections,
+        }
+    }
+
+    #[test]
+    fn summaries_show_loss_jitter_and_corrections() {
+        let mut probes = ClientProbes::default();
+        // Probes 3 and 6 got lost
+        for (second, seq, rtt) in [(0, 1, 40), (1, 2, 60), (3, 4, 40), (4, 5, 60), (6, 7, 40)] {
+            probes.record(second as f64, &probe(seq, rtt, 2 + seq));
+        }
+        let summary = probes.summary("Player7".to_string());
+        assert!((summary.packet_loss - 2.0 / 7.0).abs() < 1e-6);
+        assert_eq!(summary.rtt_avg_ms, 48.0);
+        assert_eq!(summary.rtt_p95_ms, 60.0);
+        assert_eq!(summary.jitter_ms, 20.0);
+        assert_eq!(summary.connected_secs, 6.0);
+        assert_eq!(summary.corrections_per_min, 60.0);
+        assert_eq!(summary.verdict, ConnectionVerdict::Poor);
+
+        // A client that never got an echo back has no round trips yet
+        let mut quiet = ClientProbes::default();
+        quiet.record(
+            0.0,
+            &NetworkProbe {
+                seq: 0,
+                rtt_ms: None,
+                corrections: 0,
+            },
+        );
+        let summary = quiet.summary("Player8".to_string());
+        assert_eq!(summary.rtt_avg_ms, 0.0);
+        assert_eq!(summary.packet_loss, 0.0);
+        assert_eq!(summary.verdict, ConnectionVerdict::Good);
+    }
+}
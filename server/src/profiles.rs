@@ -0,0 +1,162 @@
+// Player profiles: each client says what it wants to be called and which color it wants,
+// the server checks them and replicates them on the player that client controls. The
+// lobby account id it sends stays on the server, for rating ranked matches, and the team
+// the lobby room put it on replaces the one it was given on spawning. The lobby room
+// isn't up to the client: it comes from the connect token the matchmaker signed, and
+// decides which entities the connection and its player are replicated and play with.
+use bevy::prelude::*;
+
+#[cfg(feature = "bevygap")]
+use lightyear::prelude::{server::NetcodeServer, *};
+
+#[cfg(feature = "bevygap")]
+use shared::{
+    room_from_token_user_data, sanitize_display_name, MatchRoom, PlayerColor, SetPlayerProfile,
+    Team, TeamRules,
+};
+use shared::{Player, PlayerId, PlayerName};
+
+// Profile a client asked for, kept on its connection until its player exists
+#[derive(Component)]
+struct ClientProfile {
+    name: String,
+    color: usize,
+    player_id: Option<String>,
+    team: Option<usize>,
+}
+
+/// Lobby account of the player's client, not replicated. Clients can send any id, the
+/// lobby service only rates the accounts it matched into the game.
+#[derive(Component, Clone, Debug)]
+pub struct AccountId(pub String);
+
+pub struct ProfilesPlugin;
+
+impl Plugin for ProfilesPlugin {
+    fn build(&self, app: &mut App) {
+        #[cfg(feature = "bevygap")]
+        app.add_systems(
+            Update,
+            (
+                (join_token_rooms, place_players_in_rooms).chain(),
+                (receive_profiles, apply_profiles).chain(),
+            ),
+        );
+        app.add_systems(Update, name_unnamed_players);
+    }
+}
+
+// Clients join the lobby room their connect token was minted for, quick match tokens name
+// none and share the room with the empty id
+#[cfg(feature = "bevygap")]
+fn join_token_rooms(
+    mut commands: Commands,
+    servers: Query<&NetcodeServer>,
+    clients: Query<(Entity, &RemoteId), Added<Connected>>,
+) {
+    for (client, remote) in clients.iter() {
+        let PeerId::Netcode(id) = remote.0 else {
+            continue;
+        };
+        let room_id = servers
+            .iter()
+            .find_map(|server| server.user_data(id))
+            .and_then(|data| room_from_token_user_data(&data))
+            .unwrap_or_default();
+        if !room_id.is_empty() {
+            info!(
+                "🎟️ Client {:?} holds a token for lobby room {}",
+                client, room_id
+            );
+        }
+        commands.entity(client).insert(MatchRoom(room_id));
+    }
+}
+
+// A player plays in its client's room, and is seen by that room only
+#[cfg(feature = "bevygap")]
+fn place_players_in_rooms(
+    mut commands: Commands,
+    rooms: Query<&MatchRoom>,
+    players: Query<(Entity, &ControlledBy), (With<Player>, Without<MatchRoom>)>,
+) {
+    for (entity, owner) in players.iter() {
+        if let Ok(room) = rooms.get(owner.owner) {
+            commands.entity(entity).insert(room.clone());
+        }
+    }
+}
+
+#[cfg(feature = "bevygap")]
+fn receive_profiles(
+    mut commands: Commands,
+    mut receivers: Query<(Entity, &mut MessageReceiver<SetPlayerProfile>)>,
+) {
+    for (client, mut receiver) in receivers.iter_mut() {
+        for message in receiver.receive() {
+            match sanitize_display_name(&message.name) {
+                Some(name) => {
+                    info!("🏷️ Client {:?} is called {}", client, name);
+                    commands.entity(client).insert(ClientProfile {
+                        name,
+                        color: message.color,
+                        player_id: message.player_id.clone().filter(|id| !id.is_empty()),
+                        team: message.team,
+                    });
+                }
+                None => warn!("🏷️ Client {:?} sent an invalid name", client),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "bevygap")]
+fn apply_profiles(
+    mut commands: Commands,
+    rules: Res<TeamRules>,
+    profiles: Query<Ref<ClientProfile>>,
+    players: Query<(Entity, &ControlledBy, Option<&PlayerName>), With<Player>>,
+) {
+    for (entity, owner, current) in players.iter() {
+        let Ok(profile) = profiles.get(owner.owner) else {
+            continue;
+        };
+        if current.is_none() || profile.is_changed() {
+            commands.entity(entity).insert((
+                PlayerName {
+                    name: profile.name.clone(),
+                },
+                PlayerColor::from_palette(profile.color),
+            ));
+            match &profile.player_id {
+                Some(id) => commands.entity(entity).insert(AccountId(id.clone())),
+                None => commands.entity(entity).remove::<AccountId>(),
+            };
+            // Teams the match doesn't have are ignored, the player keeps the one it got
+            if let Some(team) = profile.team.filter(|team| *team < rules.teams) {
+                commands.entity(entity).insert(Team(team));
+            }
+        }
+    }
+}
+
+// Players whose client never sent a name still get one
+fn name_unnamed_players(
+    mut commands: Commands,
+    players: Query<(Entity, &PlayerId), (With<Player>, Without<PlayerName>)>,
+    #[cfg(feature = "bevygap")] owners: Query<&ControlledBy>,
+    #[cfg(feature = "bevygap")] profiles: Query<(), With<ClientProfile>>,
+) {
+    for (entity, id) in players.iter() {
+        #[cfg(feature = "bevygap")]
+        if owners
+            .get(entity)
+            .is_ok_and(|owner| profiles.contains(owner.owner))
+        {
+            continue;
+        }
+        commands.entity(entity).insert(PlayerName {
+            name: format!("Player{}", id.id),
+        });
+    }
+}
@@ -0,0 +1,266 @@
+// Race mode: pass the level's checkpoints in order, fastest to the last one wins
+use bevy::prelude::*;
+
+use crate::match_report::{player_name, MatchEnded};
+use crate::profiles::AccountId;
+use crate::server_plugin::spawn_replicated;
+use crate::shutdown::Draining;
+use crate::spawns::respawn_all;
+use shared::{
+    Checkpoint, GameMode, LevelDefinition, MatchEndReason, MatchStanding, Player, PlayerId,
+    PlayerName, PlayerTransform, RaceProgress, RaceResults, Team,
+};
+
+// Players who haven't finished by then are listed without a time
+const RACE_TIMEOUT_SECS: f32 = 300.0;
+// Results stay on screen this long before the next race starts
+const RESULTS_SECS: f32 = 10.0;
+
+// Where to POST final results, from --leaderboard-url
+#[derive(Resource, Clone, Debug, Default)]
+pub struct LeaderboardConfig {
+    pub url: Option<String>,
+}
+
+#[derive(Resource, Default)]
+struct RaceClock {
+    elapsed: f32,
+    /// Running once the race is over, the next race starts when it finishes
+    results: Option<Timer>,
+}
+
+pub struct RacePlugin;
+
+impl Plugin for RacePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RaceClock>()
+            .init_resource::<LeaderboardConfig>()
+            .add_systems(
+                Update,
+                (spawn_checkpoints, join_race, finish_race, restart_race)
+                    .chain()
+                    .run_if(resource_equals(GameMode::Race)),
+            )
+            .add_systems(
+                FixedUpdate,
+                pass_checkpoints.run_if(resource_equals(GameMode::Race)),
+            );
+    }
+}
+
+// Checkpoints come from the level, a new level (rotation) means a new race
+fn spawn_checkpoints(
+    mut commands: Commands,
+    level: Res<LevelDefinition>,
+    mut clock: ResMut<RaceClock>,
+    checkpoints: Query<(), With<Checkpoint>>,
+    mut racers: Query<&mut RaceProgress>,
+    mut warned: Local<Option<String>>,
+) {
+    if !checkpoints.is_empty() {
+        return;
+    }
+    if level.checkpoints.is_empty() {
+        if warned.as_deref() != Some(level.name.as_str()) {
+            warn!(
+                "🏁 Level '{}' has no checkpoints, nothing to race",
+                level.name
+            );
+            *warned = Some(level.name.clone());
+        }
+        return;
+    }
+
+    for checkpoint in level.checkpoint_bundles() {
+        spawn_replicated(&mut commands, checkpoint);
+    }
+    *clock = RaceClock::default();
+    for mut progress in racers.iter_mut() {
+        *progress = RaceProgress::default();
+    }
+    info!(
+        "🏁 Race on '{}' with {} checkpoints",
+        level.name,
+        level.checkpoints.len()
+    );
+}
+
+// Everyone who joins mid-race starts from zero
+fn join_race(
+    mut commands: Commands,
+    players: Query<Entity, (With<Player>, Without<RaceProgress>)>,
+) {
+    for entity in players.iter() {
+        commands.entity(entity).insert(RaceProgress::default());
+    }
+}
+
+fn pass_checkpoints(
+    time: Res<Time>,
+    mut clock: ResMut<RaceClock>,
+    checkpoints: Query<(&Checkpoint, &Transform)>,
+    mut racers: Query<(&PlayerId, &PlayerTransform, &mut RaceProgress)>,
+) {
+    if clock.results.is_some() || checkpoints.is_empty() {
+        return;
+    }
+    // The clock only runs while someone is racing
+    if racers.is_empty() {
+        clock.elapsed = 0.0;
+        return;
+    }
+    clock.elapsed += time.delta_secs();
+    let total = checkpoints.iter().count();
+
+    for (player_id, transform, mut progress) in racers.iter_mut() {
+        if progress.finished.is_some() {
+            continue;
+        }
+        let next = progress.splits.len();
+        let reached = checkpoints
+            .iter()
+            .any(|(checkpoint, checkpoint_transform)| {
+                checkpoint.index == next
+                    && transform
+                        .translation
+                        .truncate()
+                        .distance(checkpoint_transform.translation.truncate())
+                        < checkpoint.radius
+            });
+        if !reached {
+            continue;
+        }
+
+        // Falls so far count against the player's time
+        let time = clock.elapsed + progress.penalty;
+        progress.splits.push(time);
+        info!(
+            "🏁 Player {} split {}/{}: {:.2}s",
+            player_id.id,
+            next + 1,
+            total,
+            time
+        );
+        if progress.splits.len() == total {
+            progress.finished = Some(time);
+            info!("🏆 Player {} finished in {:.2}s", player_id.id, time);
+        }
+    }
+}
+
+// The race is over when everybody finished, time ran out or the server is shutting down
+fn finish_race(
+    mut clock: ResMut<RaceClock>,
+    level: Res<LevelDefinition>,
+    leaderboard: Res<LeaderboardConfig>,
+    draining: Option<Res<Draining>>,
+    racers: Query<(
+        &PlayerId,
+        &RaceProgress,
+        Option<&PlayerName>,
+        Option<&AccountId>,
+    )>,
+    mut ended: EventWriter<MatchEnded>,
+) {
+    if clock.results.is_some() || racers.is_empty() {
+        return;
+    }
+    let all_finished = racers.iter().all(|(_, p, _, _)| p.finished.is_some());
+    if !all_finished && clock.elapsed < RACE_TIMEOUT_SECS && draining.is_none() {
+        return;
+    }
+
+    let progress: Vec<(u32, &RaceProgress)> =
+        racers.iter().map(|(id, p, _, _)| (id.id, p)).collect();
+    let results = RaceResults::new(&level.name, progress.iter().copied());
+    ended.write(MatchEnded {
+        reason: if draining.is_some() {
+            MatchEndReason::Shutdown
+        } else {
+            MatchEndReason::Completed
+        },
+        standings: results
+            .results
+            .iter()
+            .map(|result| {
+                let racer = racers.iter().find(|(id, ..)| id.id == result.player_id);
+                MatchStanding {
+                    place: result.place,
+                    name: racer
+                        .map(|(id, _, name, _)| player_name(id, name))
+                        .unwrap_or_default(),
+                    player_id: racer
+                        .and_then(|(_, _, _, account)| account.map(|account| account.0.clone())),
+                    score: result.splits.len() as u32,
+                    time: result.time,
+                }
+            })
+            .collect(),
+    });
+    for result in &results.results {
+        match result.time {
+            Some(time) => info!(
+                "🏆 {}. Player {} - {:.2}s",
+                result.place, result.player_id, time
+            ),
+            None => info!("🏆 {}. Player {} - DNF", result.place, result.player_id),
+        }
+    }
+    if let Some(url) = leaderboard.url.clone() {
+        let post = post_results(url, results);
+        // The process is about to exit and would take the post with it
+        if draining.is_some() {
+            let _ = post.join();
+        }
+    }
+    clock.results = Some(Timer::from_seconds(RESULTS_SECS, TimerMode::Once));
+}
+
+// Post on a background thread so a slow leaderboard never stalls the tick
+fn post_results(url: String, results: RaceResults) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        match reqwest::blocking::Client::new()
+            .post(&url)
+            .json(&results)
+            .send()
+            .and_then(|response| response.error_for_status())
+        {
+            Ok(_) => info!("📤 Race results posted to {}", url),
+            Err(e) => warn!("📤 Failed to post race results to {}: {}", url, e),
+        }
+    });
+}
+
+fn restart_race(
+    time: Res<Time>,
+    level: Res<LevelDefinition>,
+    mut clock: ResMut<RaceClock>,
+    mut racers: Query<(
+        &PlayerId,
+        Option<&Team>,
+        &mut RaceProgress,
+        &mut PlayerTransform,
+        &mut Player,
+    )>,
+) {
+    let Some(timer) = clock.results.as_mut() else {
+        return;
+    };
+    if !timer.tick(time.delta()).finished() {
+        return;
+    }
+
+    *clock = RaceClock::default();
+    for (_, _, mut progress, _, mut player) in racers.iter_mut() {
+        *progress = RaceProgress::default();
+        player.velocity = Vec2::ZERO;
+    }
+    // Back to the level's spawn points
+    respawn_all(
+        &level,
+        racers
+            .iter_mut()
+            .map(|(id, team, _, transform, _)| (id, team, transform)),
+    );
+    info!("🏁 Next race starting");
+}
@@ -0,0 +1,106 @@
+// Ranked mode: the casual rules on a clock. When the time is up the server reports the
+// standings with the players' account ids, so the lobby service can update their
+// ratings, tells every player where they placed and drains. The matchmaker deploys one
+// server per ranked match.
+use bevy::prelude::*;
+
+use crate::match_report::{score_standings, MatchEnded};
+use crate::profiles::AccountId;
+use crate::shutdown::{request_shutdown, Draining};
+use shared::{BotTag, GameMode, MatchEndReason, Player, PlayerId, PlayerName, PlayerScore};
+
+#[cfg(feature = "bevygap")]
+use {
+    lightyear::prelude::*,
+    shared::{Channel1, RankedMatchOver},
+};
+
+const RANKED_MATCH_SECS: f32 = 180.0;
+
+#[derive(Resource, Default)]
+struct RankedClock {
+    elapsed: f32,
+    over: bool,
+}
+
+pub struct RankedPlugin;
+
+impl Plugin for RankedPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RankedClock>().add_systems(
+            Update,
+            finish_ranked_match.run_if(resource_equals(GameMode::Ranked)),
+        );
+    }
+}
+
+// The clock only runs while humans are playing, a shutdown ends the match unrated. Bots
+// fill the match but aren't part of its standings
+#[allow(clippy::too_many_arguments)]
+fn finish_ranked_match(
+    time: Res<Time>,
+    mut clock: ResMut<RankedClock>,
+    draining: Option<Res<Draining>>,
+    players: Query<
+        (
+            Entity,
+            &PlayerId,
+            Option<&PlayerName>,
+            Option<&AccountId>,
+            Option<&PlayerScore>,
+        ),
+        (With<Player>, Without<BotTag>),
+    >,
+    mut ended: EventWriter<MatchEnded>,
+    #[cfg(feature = "bevygap")] owners: Query<&ControlledBy>,
+    #[cfg(feature = "bevygap")] mut senders: Query<&mut MessageSender<RankedMatchOver>>,
+) {
+    if clock.over || draining.is_some() || players.is_empty() {
+        return;
+    }
+    clock.elapsed += time.delta_secs();
+    if clock.elapsed < RANKED_MATCH_SECS {
+        return;
+    }
+    clock.over = true;
+
+    let standings = score_standings(
+        players
+            .iter()
+            .map(|(_, id, name, account, score)| (id, name, account, score)),
+    );
+    for standing in &standings {
+        info!(
+            "🏆 {}. {} - {} points{}",
+            standing.place,
+            standing.name,
+            standing.score,
+            if standing.player_id.is_some() {
+                ""
+            } else {
+                " (unrated)"
+            }
+        );
+    }
+
+    // Same places as the standings: one more than the number of players who scored more
+    #[cfg(feature = "bevygap")]
+    for (entity, .., score) in players.iter() {
+        let score = score.map_or(0, |s| s.score);
+        let Ok(owner) = owners.get(entity) else {
+            continue;
+        };
+        if let Ok(mut sender) = senders.get_mut(owner.owner) {
+            sender.send::<Channel1>(RankedMatchOver {
+                place: 1 + standings.iter().filter(|s| s.score > score).count(),
+                players: standings.len(),
+            });
+        }
+    }
+
+    ended.write(MatchEnded {
+        reason: MatchEndReason::Completed,
+        standings,
+    });
+    request_shutdown();
+}
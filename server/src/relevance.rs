@@ -0,0 +1,82 @@
+// Interest management: several lobby rooms can share one deployment, and each client then
+// only receives the entities of its own room. The room comes from the client's connect
+// token (see `profiles::join_token_rooms`), never from anything the client sends, and the
+// connection and its players join a lightyear room for it. Quick match clients share the
+// room with the empty id. Entities without a `MatchRoom`, like the level, stay visible to
+// every client.
+use bevy::prelude::*;
+use lightyear::prelude::*;
+use shared::MatchRoom;
+use std::collections::HashMap;
+
+// Lightyear room the entity was put in, keeps the room open while it exists
+#[derive(Component)]
+struct InNetworkRoom(Entity);
+
+/// Lightyear room entity of every lobby room seen so far
+#[derive(Resource, Default)]
+struct NetworkRooms(HashMap<String, Entity>);
+
+impl NetworkRooms {
+    fn get_or_spawn(&mut self, commands: &mut Commands, room_id: &str) -> Entity {
+        *self.0.entry(room_id.to_string()).or_insert_with(|| {
+            info!("👁️ Opening replication room for lobby room {}", room_id);
+            commands.spawn(Room::default()).id()
+        })
+    }
+}
+
+pub struct RelevancePlugin;
+
+impl Plugin for RelevancePlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<RoomPlugin>() {
+            app.add_plugins(RoomPlugin);
+        }
+        app.init_resource::<NetworkRooms>()
+            .add_systems(Update, (join_rooms, close_empty_rooms).chain());
+    }
+}
+
+// Connections join as senders, everything else as entities. Only entities in a room get a
+// `NetworkVisibility`, the rest keep replicating to everyone. A connection's room is fixed
+// by its token, so nothing ever moves to another room.
+fn join_rooms(
+    mut commands: Commands,
+    mut rooms: ResMut<NetworkRooms>,
+    joined: Query<(Entity, &MatchRoom, Has<Connected>), Added<MatchRoom>>,
+) {
+    for (entity, room_id, is_client) in joined.iter() {
+        let room = rooms.get_or_spawn(&mut commands, &room_id.0);
+        if is_client {
+            info!("👁️ Client {:?} sees lobby room {}", entity, room_id.0);
+            commands.trigger(RoomEvent {
+                room,
+                target: RoomTarget::AddSender(entity),
+            });
+        } else {
+            commands.entity(entity).insert(NetworkVisibility::default());
+            commands.trigger(RoomEvent {
+                room,
+                target: RoomTarget::AddEntity(entity),
+            });
+        }
+        commands.entity(entity).insert(InNetworkRoom(room));
+    }
+}
+
+// Rooms whose clients and entities are all gone are opened again by the next player
+fn close_empty_rooms(
+    mut commands: Commands,
+    mut rooms: ResMut<NetworkRooms>,
+    members: Query<&InNetworkRoom>,
+) {
+    rooms.0.retain(|room_id, room| {
+        let used = members.iter().any(|member| member.0 == *room);
+        if !used {
+            info!("👁️ Closing replication room for lobby room {}", room_id);
+            commands.entity(*room).despawn();
+        }
+        used
+    });
+}
@@ -0,0 +1,420 @@
+// Room event bus. The lobby service, the matchmaker and game servers each see a different
+// part of a room's life, and until now only learned about the others' through polling and
+// Edgegap. Each of them publishes what it sees on NATS (`shared::RoomEvent`, subjects under
+// `rooms.events`) and listens to the others. This server publishes rooms starting on it,
+// players joining and leaving them and matches ending, and opens rooms the matchmaker
+// started on its deployment before their first player arrives. The connection reuses
+// bevygap's NATS setup (NATS_HOST, NATS_USER, NATS_PASSWORD, NATS_CA), without it the
+// server keeps its events to itself.
+use bevy::prelude::*;
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "bevygap")]
+use std::sync::{mpsc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::match_report::MatchEnded;
+use crate::server_plugin::RoomRegistry;
+use shared::{GameMode, LevelDefinition, MatchEndReason, RoomEvent, RoomEventKind};
+
+// From --room-id and Edgegap's ARBITRIUM_REQUEST_ID
+#[derive(Resource, Clone, Debug, Default)]
+pub struct RoomEventsConfig {
+    /// Who we are on the bus, `server:{deployment}`
+    pub origin: String,
+    pub deployment: Option<String>,
+    pub room_id: Option<String>,
+}
+
+pub struct RoomEventsPlugin {
+    pub config: RoomEventsConfig,
+}
+
+impl Plugin for RoomEventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.config.clone())
+            .init_resource::<FinishedRooms>()
+            .add_event::<PublishRoomEvent>()
+            .add_event::<RoomEventReceived>()
+            .add_systems(
+                Update,
+                (publish_match_end, publish_room_changes, apply_remote_events).chain(),
+            );
+
+        #[cfg(feature = "bevygap")]
+        if let Some(bus) = connect(&self.config.origin) {
+            app.insert_resource(bus)
+                .add_systems(Update, (receive_events, send_events).chain());
+        }
+    }
+}
+
+/// Something this server saw happen to a room, for the other services
+#[derive(Event, Clone, Debug)]
+pub struct PublishRoomEvent(pub RoomEvent);
+
+/// Another service's room event
+#[derive(Event, Clone, Debug)]
+pub struct RoomEventReceived(pub RoomEvent);
+
+// Rooms whose end went out already, a room the match ended in is also closed later
+#[derive(Resource, Default)]
+struct FinishedRooms(HashSet<String>);
+
+// Ends of the channels to the thread that talks to NATS
+#[cfg(feature = "bevygap")]
+#[derive(Resource)]
+struct RoomEventBus {
+    outgoing: tokio::sync::mpsc::UnboundedSender<RoomEvent>,
+    incoming: Mutex<mpsc::Receiver<RoomEvent>>,
+}
+
+fn event(config: &RoomEventsConfig, room_id: &str, kind: RoomEventKind) -> PublishRoomEvent {
+    let sent_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_millis() as u64)
+        .unwrap_or(0);
+    PublishRoomEvent(RoomEvent {
+        origin: config.origin.clone(),
+        room_id: room_id.to_string(),
+        sent_at,
+        kind,
+    })
+}
+
+// Compares the registry with what it held last frame: rooms that appeared have started
+// here, names that appeared or went are players joining and leaving, and rooms that were
+// closed for standing empty are finished
+fn publish_room_changes(
+    config: Res<RoomEventsConfig>,
+    rooms: Res<RoomRegistry>,
+    mut finished: ResMut<FinishedRooms>,
+    mut published: EventWriter<PublishRoomEvent>,
+    mut known: Local<HashMap<String, Vec<String>>>,
+) {
+    if !rooms.is_changed() {
+        return;
+    }
+    for (room_id, room) in rooms.rooms.iter() {
+        let before = match known.get(room_id) {
+            Some(before) => before.clone(),
+            None => {
+                published.write(event(
+                    &config,
+                    room_id,
+                    RoomEventKind::Started {
+                        deployment: config.deployment.clone(),
+                    },
+                ));
+                Vec::new()
+            }
+        };
+        for player in room.player_names.iter().filter(|p| !before.contains(p)) {
+            published.write(event(
+                &config,
+                room_id,
+                RoomEventKind::PlayerJoined {
+                    player: player.clone(),
+                },
+            ));
+        }
+        for player in before.iter().filter(|p| !room.player_names.contains(p)) {
+            published.write(event(
+                &config,
+                room_id,
+                RoomEventKind::PlayerLeft {
+                    player: player.clone(),
+                },
+            ));
+        }
+        known.insert(room_id.clone(), room.player_names.clone());
+    }
+
+    let closed: Vec<String> = known
+        .keys()
+        .filter(|room_id| !rooms.rooms.contains_key(*room_id))
+        .cloned()
+        .collect();
+    for room_id in closed {
+        known.remove(&room_id);
+        if !finished.0.insert(room_id.clone()) {
+            continue;
+        }
+        published.write(event(
+            &config,
+            &room_id,
+            RoomEventKind::Finished {
+                reason: MatchEndReason::Abandoned,
+            },
+        ));
+    }
+}
+
+// Match rules cover the whole deployment, so its end finishes every room on it
+fn publish_match_end(
+    config: Res<RoomEventsConfig>,
+    rooms: Res<RoomRegistry>,
+    mut ended: EventReader<MatchEnded>,
+    mut finished: ResMut<FinishedRooms>,
+    mut published: EventWriter<PublishRoomEvent>,
+) {
+    for match_ended in ended.read() {
+        let room_ids = rooms.rooms.keys().chain(config.room_id.iter());
+        for room_id in room_ids {
+            if finished.0.insert(room_id.clone()) {
+                published.write(event(
+                    &config,
+                    room_id,
+                    RoomEventKind::Finished {
+                        reason: match_ended.reason,
+                    },
+                ));
+            }
+        }
+    }
+}
+
+fn apply_remote_events(
+    config: Res<RoomEventsConfig>,
+    game_mode: Res<GameMode>,
+    level: Res<LevelDefinition>,
+    mut rooms: ResMut<RoomRegistry>,
+    mut received: EventReader<RoomEventReceived>,
+) {
+    for RoomEventReceived(event) in received.read() {
+        match &event.kind {
+            // Heartbeats list the room from now on, not only once its first player is in
+            RoomEventKind::Started { deployment }
+                if deployment.is_some() && *deployment == config.deployment =>
+            {
+                if !rooms.rooms.contains_key(&event.room_id) {
+                    info!(
+                        "📣 {} started room '{}' here, opening it",
+                        event.origin, event.room_id
+                    );
+                    rooms.create_room(
+                        event.room_id.clone(),
+                        String::new(),
+                        game_mode.id().to_string(),
+                        level.name.clone(),
+                    );
+                }
+            }
+            RoomEventKind::Finished { reason } if rooms.rooms.contains_key(&event.room_id) => {
+                warn!(
+                    "📣 {} finished room '{}' ({:?}) while it is still open here",
+                    event.origin, event.room_id, reason
+                );
+            }
+            _ => debug!("📣 {} {:?} {}", event.origin, event.kind, event.room_id),
+        }
+    }
+}
+
+#[cfg(feature = "bevygap")]
+fn receive_events(bus: Res<RoomEventBus>, mut received: EventWriter<RoomEventReceived>) {
+    let incoming = bus.incoming.lock().unwrap();
+    received.write_batch(incoming.try_iter().map(RoomEventReceived));
+}
+
+#[cfg(feature = "bevygap")]
+fn send_events(bus: Res<RoomEventBus>, mut published: EventReader<PublishRoomEvent>) {
+    for PublishRoomEvent(event) in published.read() {
+        info!("📣 {} {}", event.subject(), event.room_id);
+        // Only fails once the connection thread gave up, which it already logged
+        let _ = bus.outgoing.send(event.clone());
+    }
+}
+
+// Only when bevygap talks to NATS, i.e. NATS_HOST is set. The connection lives on its own
+// thread and runtime, a slow or missing NATS never holds up a tick.
+#[cfg(feature = "bevygap")]
+fn connect(origin: &str) -> Option<RoomEventBus> {
+    use bevygap_shared::nats::BevygapNats;
+    use futures::StreamExt;
+    use shared::ROOM_EVENTS_SUBJECT;
+
+    std::env::var("NATS_HOST").ok().filter(|h| !h.is_empty())?;
+    let (outgoing, mut to_publish) = tokio::sync::mpsc::unbounded_channel::<RoomEvent>();
+    let (forward, incoming) = mpsc::channel();
+    let origin = origin.to_string();
+    std::thread::spawn(move || {
+        let runtime = match tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        {
+            Ok(runtime) => runtime,
+            Err(e) => {
+                warn!("📣 Room events disabled, no runtime: {}", e);
+                return;
+            }
+        };
+        runtime.block_on(async move {
+            let nats = match BevygapNats::new_and_connect(&origin).await {
+                Ok(nats) => nats,
+                Err(e) => {
+                    warn!("📣 Room events disabled, can't connect to NATS: {}", e);
+                    return;
+                }
+            };
+            let client = nats.client();
+            let mut subscriber = match client.subscribe(format!("{}.>", ROOM_EVENTS_SUBJECT)).await
+            {
+                Ok(subscriber) => subscriber,
+                Err(e) => {
+                    warn!("📣 Room events disabled, can't subscribe: {}", e);
+                    return;
+                }
+            };
+            info!("📣 Publishing room events as {}", origin);
+            loop {
+                tokio::select! {
+                    Some(event) = to_publish.recv() => {
+                        let payload = match serde_json::to_vec(&event) {
+                            Ok(payload) => payload,
+                            Err(e) => {
+                                warn!("📣 Can't encode {:?}: {}", event, e);
+                                continue;
+                            }
+                        };
+                        if let Err(e) = client.publish(event.subject(), payload.into()).await {
+                            warn!("📣 Failed to publish {}: {}", event.subject(), e);
+                        }
+                    }
+                    Some(message) = subscriber.next() => {
+                        match serde_json::from_slice::<RoomEvent>(&message.payload) {
+                            Ok(event) if event.origin == origin => {}
+                            Ok(event) => {
+                                if forward.send(event).is_err() {
+                                    return;
+                                }
+                            }
+                            Err(e) => debug!("📣 Ignoring {}: {}", message.subject, e),
+                        }
+                    }
+                    else => return,
+                }
+            }
+        });
+    });
+    Some(RoomEventBus {
+        outgoing,
+        incoming: Mutex::new(incoming),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.insert_resource(RoomRegistry::new())
+            .insert_resource(GameMode::Casual)
+            .insert_resource(LevelDefinition::default())
+            .add_event::<MatchEnded>()
+            .add_plugins(RoomEventsPlugin {
+                config: RoomEventsConfig {
+                    origin: "server:ab12cd".to_string(),
+                    deployment: Some("ab12cd".to_string()),
+                    room_id: None,
+                },
+            });
+        app
+    }
+
+    fn published(app: &mut App) -> Vec<(String, RoomEventKind)> {
+        app.world_mut()
+            .resource_mut::<Events<PublishRoomEvent>>()
+            .drain()
+            .map(|PublishRoomEvent(event)| (event.room_id, event.kind))
+            .collect()
+    }
+
+    fn set_players(app: &mut App, room_id: &str, players: &[&str]) {
+        let mut rooms = app.world_mut().resource_mut::<RoomRegistry>();
+        rooms.rooms.get_mut(room_id).unwrap().player_names =
+            players.iter().map(|p| p.to_string()).collect();
+    }
+
+    #[test]
+    fn room_life_goes_out_as_events() {
+        let mut app = app();
+        app.world_mut().resource_mut::<RoomRegistry>().create_room(
+            "R1".to_string(),
+            String::new(),
+            "casual".to_string(),
+            "default".to_string(),
+        );
+        set_players(&mut app, "R1", &["Ada"]);
+        app.update();
+        let room = "R1".to_string();
+        assert_eq!(
+            published(&mut app),
+            vec![
+                (
+                    room.clone(),
+                    RoomEventKind::Started {
+                        deployment: Some("ab12cd".to_string())
+                    }
+                ),
+                (
+                    room.clone(),
+                    RoomEventKind::PlayerJoined {
+                        player: "Ada".to_string()
+                    }
+                ),
+            ]
+        );
+
+        set_players(&mut app, "R1", &["Bo"]);
+        app.update();
+        assert_eq!(
+            published(&mut app),
+            vec![
+                (
+                    room.clone(),
+                    RoomEventKind::PlayerJoined {
+                        player: "Bo".to_string()
+                    }
+                ),
+                (
+                    room.clone(),
+                    RoomEventKind::PlayerLeft {
+                        player: "Ada".to_string()
+                    }
+                ),
+            ]
+        );
+
+        app.world_mut().resource_mut::<RoomRegistry>().rooms.clear();
+        app.update();
+        assert_eq!(
+            published(&mut app),
+            vec![(
+                room,
+                RoomEventKind::Finished {
+                    reason: MatchEndReason::Abandoned
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn rooms_started_on_this_deployment_are_opened() {
+        let mut app = app();
+        for (room_id, deployment) in [("R1", "ab12cd"), ("R2", "ef34gh")] {
+            app.world_mut().send_event(RoomEventReceived(RoomEvent {
+                origin: "matchmaker-1".to_string(),
+                room_id: room_id.to_string(),
+                sent_at: 1,
+                kind: RoomEventKind::Started {
+                    deployment: Some(deployment.to_string()),
+                },
+            }));
+        }
+        app.update();
+        let rooms = &app.world().resource::<RoomRegistry>().rooms;
+        assert!(rooms.contains_key("R1"));
+        assert!(!rooms.contains_key("R2"));
+    }
+}
@@ -2,7 +2,7 @@
 use bevy::log::{Level, LogPlugin};
 use bevy::prelude::*;
 use leafwing_input_manager::prelude::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
 
 #[cfg(feature = "bevygap")]
@@ -11,23 +11,46 @@ use bevygap_server_plugin::prelude::*;
 use lightyear::prelude::server::{NetcodeConfig, NetcodeServer};
 #[cfg(feature = "bevygap")]
 use lightyear::prelude::{server, *};
+#[cfg(feature = "bevygap")]
+use shared::Channel1;
 
+use crate::bots::{BotConfig, BotsPlugin};
 use crate::build_info::BuildInfo;
+#[cfg(feature = "bevygap")]
+use crate::clock_sync::ClockSyncPlugin;
+use crate::fall_out::FallOutPlugin;
+use crate::ghost_mode::GhostModePlugin;
+use crate::koth::KothPlugin;
+use crate::profiles::ProfilesPlugin;
+use crate::race::{LeaderboardConfig, RacePlugin};
+use crate::ranked::RankedPlugin;
+#[cfg(feature = "bevygap")]
+use crate::relevance::RelevancePlugin;
+use crate::spawns::SpawnsPlugin;
 use shared::{
-    Platform, Player, PlayerActions, PlayerAnimationState, PlayerColor, PlayerId, PlayerTransform,
-    RoomInfo, SharedPlugin,
+    collect_pickups_system, Checkpoint, Door, GameConfig, GameMode, Hazard, LevelDefinition,
+    MatchRoom, Pickup, Platform, Player, PlayerActions, PlayerAnimationState, PlayerColor,
+    PlayerId, PlayerName, PlayerStatus, PlayerTransform, PressurePlate, RoomInfo, ScoringZone,
+    SharedPlugin, WorldBounds,
 };
 
 // Constants for Lightyear private key handling
 const DUMMY_PRIVATE_KEY: [u8; 32] = [0; 32]; // All zeros for local development
 
-/// Parse the LIGHTYEAR_PRIVATE_KEY environment variable into a 32-byte array
+/// Read the LIGHTYEAR_PRIVATE_KEY environment variable, see `parse_private_key`
+pub fn read_lightyear_private_key_from_env() -> Result<[u8; 32], String> {
+    let key_str = std::env::var("LIGHTYEAR_PRIVATE_KEY")
+        .map_err(|_| "LIGHTYEAR_PRIVATE_KEY is not set".to_string())?;
+    parse_private_key(&key_str)
+}
+
+/// Parse a private key into a 32-byte array
 /// Supports formats like:
 /// - "[1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23,24,25,26,27,28,29,30,31,32]"
 /// - "1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23,24,25,26,27,28,29,30,31,32"
-fn read_lightyear_private_key_from_env() -> Option<[u8; 32]> {
-    let key_str = std::env::var("LIGHTYEAR_PRIVATE_KEY").ok()?;
-
+///
+/// The all-zero development key is rejected: anyone can sign connect tokens with it.
+fn parse_private_key(key_str: &str) -> Result<[u8; 32], String> {
     // Remove brackets and whitespace
     let cleaned = key_str
         .trim()
@@ -36,36 +59,47 @@ fn read_lightyear_private_key_from_env() -> Option<[u8; 32]> {
         .replace(' ', "");
 
     // Split by comma and parse each byte
-    let bytes: Result<Vec<u8>, _> = cleaned.split(',').map(|s| s.trim().parse::<u8>()).collect();
-
-    match bytes {
-        Ok(byte_vec) if byte_vec.len() == 32 => {
-            let mut key = [0u8; 32];
-            key.copy_from_slice(&byte_vec);
-            info!("🔐 Successfully parsed LIGHTYEAR_PRIVATE_KEY from environment");
-            Some(key)
-        }
-        Ok(byte_vec) => {
-            warn!(
-                "🔐 LIGHTYEAR_PRIVATE_KEY has wrong length: expected 32 bytes, got {}",
-                byte_vec.len()
-            );
-            None
-        }
-        Err(e) => {
-            warn!("🔐 Failed to parse LIGHTYEAR_PRIVATE_KEY: {}", e);
-            None
-        }
+    let bytes = cleaned
+        .split(',')
+        .map(|s| s.trim().parse::<u8>())
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|e| format!("Failed to parse LIGHTYEAR_PRIVATE_KEY: {}", e))?;
+
+    let key: [u8; 32] = bytes.as_slice().try_into().map_err(|_| {
+        format!(
+            "LIGHTYEAR_PRIVATE_KEY has wrong length: expected 32 bytes, got {}",
+            bytes.len()
+        )
+    })?;
+    if key == DUMMY_PRIVATE_KEY {
+        return Err("LIGHTYEAR_PRIVATE_KEY is the all-zero development key".to_string());
     }
+    Ok(key)
 }
 
 pub struct ServerPlugin {
     pub cert_digest: Option<String>,
+    pub levels: LevelRotation,
+    pub game_mode: GameMode,
+    pub leaderboard_url: Option<String>,
+    pub bots: BotConfig,
 }
 
 impl ServerPlugin {
-    pub fn new(cert_digest: Option<String>) -> Self {
-        Self { cert_digest }
+    pub fn new(
+        cert_digest: Option<String>,
+        levels: LevelRotation,
+        game_mode: GameMode,
+        leaderboard_url: Option<String>,
+        bots: BotConfig,
+    ) -> Self {
+        Self {
+            cert_digest,
+            levels,
+            game_mode,
+            leaderboard_url,
+            bots,
+        }
     }
 }
 
@@ -112,6 +146,15 @@ impl Plugin for ServerPlugin {
 
             // Add Bevygap integration (NATS, metadata)
             app.add_plugins(BevygapServerPlugin);
+
+            // Hint clients whose clock drifts to speed up or slow down their fixed step
+            app.add_plugins(ClockSyncPlugin);
+
+            // Players of rooms sharing this deployment only receive their own room's entities
+            app.add_plugins(RelevancePlugin);
+
+            // Clients move within the level's bounds too
+            app.add_systems(Update, send_world_bounds);
         }
 
         // Shared game logic
@@ -126,6 +169,31 @@ impl Plugin for ServerPlugin {
 
         app.insert_resource(ServerMetadata::new(self.cert_digest.clone()));
 
+        // Level layout selected via --level (and optional --level-rotation)
+        app.insert_resource(self.levels.current().clone());
+        app.insert_resource(self.levels.current().bounds);
+        app.insert_resource(self.levels.clone());
+
+        // Game mode selected via --game-mode, mode specific rules check this resource
+        app.insert_resource(self.game_mode);
+        app.add_plugins((GhostModePlugin, KothPlugin, RacePlugin, RankedPlugin));
+        app.insert_resource(LeaderboardConfig {
+            url: self.leaderboard_url.clone(),
+        });
+
+        // Names and colors picked by the players, replicated as PlayerName and PlayerColor
+        app.add_plugins(ProfilesPlugin);
+
+        // New players start on a free spawn point of the level, on a team in team modes
+        app.add_plugins(SpawnsPlugin);
+
+        // Players falling below the level's kill plane respawn, for a penalty
+        app.add_plugins(FallOutPlugin);
+
+        // Bots top matches up via --bot-fill, difficulty follows the humans' skill ratings
+        app.add_plugins(BotsPlugin);
+        app.insert_resource(self.bots.clone());
+
         // Server-specific systems
         app.add_systems(Startup, (setup_world, setup_server_metadata));
 
@@ -133,10 +201,15 @@ impl Plugin for ServerPlugin {
             Update,
             (
                 handle_player_management,
-                manage_room_lifecycle,
+                (manage_room_lifecycle, mark_room_hosts).chain(),
                 log_server_status,
+                (rotate_levels, stock_room_pickups).chain(),
             ),
         );
+
+        // Pickups are collected on the server only and the result replicated
+        app.init_resource::<StockedRooms>();
+        app.add_systems(FixedUpdate, collect_pickups_system);
     }
 }
 
@@ -148,20 +221,27 @@ fn setup_netcode_server(mut commands: Commands) {
         .and_then(|s| s.parse().ok())
         .unwrap_or(80085);
 
-    let key = read_lightyear_private_key_from_env().unwrap_or_else(|| {
-        warn!("LIGHTYEAR_PRIVATE_KEY not set, using dummy key");
-        DUMMY_PRIVATE_KEY
-    });
+    // Netcode only lets in clients holding a connect token signed with this key, which
+    // the matchmaker mints per matched player. main() refuses to start without a real
+    // key unless --allow-dummy-key was passed.
+    let key = match read_lightyear_private_key_from_env() {
+        Ok(key) => {
+            info!("🔐 Using LIGHTYEAR_PRIVATE_KEY from environment");
+            key
+        }
+        Err(e) => {
+            warn!(
+                "🔐 {}, using dummy private key for development (insecure!)",
+                e
+            );
+            DUMMY_PRIVATE_KEY
+        }
+    };
 
     info!(
         "🔐 Setting up Lightyear server with protocol_id: {}",
         protocol_id
     );
-    if std::env::var("LIGHTYEAR_PRIVATE_KEY").is_ok() {
-        info!("🔐 Using LIGHTYEAR_PRIVATE_KEY from environment");
-    } else {
-        warn!("🔐 Using dummy private key for development (insecure!)");
-    }
 
     let netcode_config = NetcodeConfig::default()
         .with_protocol_id(protocol_id)
@@ -171,34 +251,132 @@ fn setup_netcode_server(mut commands: Commands) {
     commands.spawn(NetcodeServer::new(netcode_config));
 }
 
-fn setup_world(mut commands: Commands) {
-    info!("Setting up game world...");
+#[cfg(feature = "bevygap")]
+fn send_world_bounds(
+    bounds: Res<WorldBounds>,
+    mut senders: Query<(&mut MessageSender<WorldBounds>, Ref<Connected>)>,
+) {
+    for (mut sender, connected) in senders.iter_mut() {
+        if connected.is_added() || bounds.is_changed() {
+            sender.send::<Channel1>(*bounds);
+        }
+    }
+}
 
-    // Spawn platforms (these will be replicated to clients in networked mode)
-    let platform_positions = vec![
-        Vec3::new(-200.0, -100.0, 0.0),
-        Vec3::new(0.0, 0.0, 0.0),
-        Vec3::new(200.0, -50.0, 0.0),
-        Vec3::new(-300.0, 50.0, 0.0),
-        Vec3::new(300.0, 100.0, 0.0),
-    ];
+fn setup_world(mut commands: Commands, level: Res<LevelDefinition>) {
+    info!("Setting up game world for level '{}'...", level.name);
 
-    for pos in platform_positions {
-        #[cfg(feature = "bevygap")]
-        {
-            commands.spawn((
-                Platform,
-                Transform::from_translation(pos),
-                Replicate::default(),
-            ));
+    spawn_level(&mut commands, &level);
+
+    info!(
+        "World setup complete with {} platforms",
+        level.platforms.len()
+    );
+    if level.is_coop() {
+        info!(
+            "🤝 Co-op level: {} pressure plates, {} doors",
+            level.plates.len(),
+            level.doors.len()
+        );
+    }
+}
+
+// Spawn platforms and co-op triggers (these will be replicated to clients in networked mode).
+// Pickups are stocked per lobby room, see `stock_room_pickups`.
+fn spawn_level(commands: &mut Commands, level: &LevelDefinition) {
+    for platform in level.platform_bundles() {
+        spawn_replicated(commands, platform);
+    }
+    for plate in level.plate_bundles() {
+        spawn_replicated(commands, plate);
+    }
+    for door in level.door_bundles() {
+        spawn_replicated(commands, door);
+    }
+}
+
+pub(crate) fn spawn_replicated(commands: &mut Commands, bundle: impl Bundle) -> Entity {
+    #[cfg(feature = "bevygap")]
+    {
+        commands.spawn((bundle, Replicate::default())).id()
+    }
+    #[cfg(not(feature = "bevygap"))]
+    {
+        commands.spawn(bundle).id()
+    }
+}
+
+// Lobby rooms holding the level's pickups, None for players without a room
+#[derive(Resource, Default)]
+struct StockedRooms(HashSet<Option<MatchRoom>>);
+
+// Each lobby room on this server collects its own copy of the level's pickups. A room is
+// stocked when its first player arrives and cleared out once it has none left, level
+// rotation restocks every room. Rule sets can take the pickups out.
+fn stock_room_pickups(
+    mut commands: Commands,
+    mut stocked: ResMut<StockedRooms>,
+    level: Res<LevelDefinition>,
+    config: Res<GameConfig>,
+    players: Query<Option<&MatchRoom>, With<Player>>,
+    pickups: Query<(Entity, Option<&MatchRoom>), With<Pickup>>,
+) {
+    if !config.pickups {
+        return;
+    }
+    let rooms: HashSet<Option<MatchRoom>> = players.iter().map(|room| room.cloned()).collect();
+    for room in rooms.difference(&stocked.0) {
+        for pickup in level.pickup_bundles() {
+            let entity = spawn_replicated(&mut commands, pickup);
+            if let Some(room) = room {
+                commands.entity(entity).insert(room.clone());
+            }
         }
-        #[cfg(not(feature = "bevygap"))]
-        {
-            commands.spawn((Platform, Transform::from_translation(pos)));
+    }
+    for (entity, room) in pickups.iter() {
+        if !rooms.contains(&room.cloned()) {
+            commands.entity(entity).despawn();
         }
     }
+    stocked.0 = rooms;
+}
+
+// Swap to the next level in the rotation once the current one has run its course
+fn rotate_levels(
+    mut commands: Commands,
+    mut rotation: ResMut<LevelRotation>,
+    mut level: ResMut<LevelDefinition>,
+    mut bounds: ResMut<WorldBounds>,
+    mut stocked: ResMut<StockedRooms>,
+    level_entities: Query<
+        Entity,
+        Or<(
+            With<Platform>,
+            With<PressurePlate>,
+            With<Door>,
+            With<Pickup>,
+            With<Hazard>,
+            With<ScoringZone>,
+            With<Checkpoint>,
+        )>,
+    >,
+    time: Res<Time>,
+) {
+    if rotation.level_count() < 2 || !rotation.timer.tick(time.delta()).just_finished() {
+        return;
+    }
 
-    info!("World setup complete with {} platforms", 5);
+    let next = rotation.advance().clone();
+    info!("🔁 Rotating level '{}' -> '{}'", level.name, next.name);
+
+    for entity in level_entities.iter() {
+        commands.entity(entity).despawn();
+    }
+    spawn_level(&mut commands, &next);
+    stocked.0.clear();
+
+    *bounds = next.bounds;
+    *level = next;
 }
 
 // Player management system that handles room logic
@@ -243,58 +421,80 @@ fn handle_player_management(mut commands: Commands, existing_players: Query<Enti
     }
 }
 
-// Room lifecycle management - handles auto-cleanup and game state
+// Room lifecycle: each lobby room on this server counts its own players, rooms players
+// come from are opened on first sight and closed after standing empty for a while. Quick
+// match players share the room with the empty id, which isn't a lobby room.
 fn manage_room_lifecycle(
     mut room_registry: ResMut<RoomRegistry>,
-    players: Query<Entity, With<Player>>,
+    players: Query<(&MatchRoom, Option<&PlayerName>), With<Player>>,
+    game_mode: Res<GameMode>,
+    level: Res<LevelDefinition>,
     time: Res<Time>,
 ) {
-    let current_player_count = players.iter().count() as u32;
+    let mut members: HashMap<&str, Vec<String>> = HashMap::new();
+    for (room, name) in players.iter().filter(|(room, _)| !room.0.is_empty()) {
+        members
+            .entry(room.0.as_str())
+            .or_default()
+            .extend(name.map(|name| name.name.clone()));
+    }
+    for room_id in members.keys() {
+        if !room_registry.rooms.contains_key(*room_id) {
+            info!("Opening room '{}' for its first player", room_id);
+            room_registry.create_room(
+                room_id.to_string(),
+                String::new(),
+                game_mode.id().to_string(),
+                level.name.clone(),
+            );
+        }
+    }
 
-    // Update player count for all rooms
+    let now = time.elapsed_secs_f64();
     let mut rooms_to_remove = Vec::new();
-    let room_ids: Vec<String> = room_registry.rooms.keys().cloned().collect();
+    for (room_id, room) in room_registry.rooms.iter_mut() {
+        let names = members.remove(room_id.as_str()).unwrap_or_default();
+        let old_count = room.current_players;
+        room.current_players = names.len() as u32;
+        if room.host_name.is_empty() {
+            room.host_name = names.first().cloned().unwrap_or_default();
+        }
+        room.player_names = names;
+
+        if room.current_players > old_count {
+            info!(
+                "Player joined room '{}'. Players: {}/{}",
+                room.room_id, room.current_players, room.max_players
+            );
+        } else if room.current_players < old_count {
+            info!(
+                "Player left room '{}'. Players: {}/{}",
+                room.room_id, room.current_players, room.max_players
+            );
+        }
 
-    for room_id in room_ids {
-        if let Some(room) = room_registry.rooms.get_mut(&room_id) {
-            let old_count = room.current_players;
-            room.current_players = current_player_count;
+        // Note: Game start message moved to when host actually presses START GAME button
+        // This prevents showing start messages immediately upon joining
 
-            if room.current_players > old_count {
-                info!(
-                    "Player joined room '{}'. Players: {}/{}",
-                    room.room_id, room.current_players, room.max_players
-                );
-            } else if room.current_players < old_count {
+        // Auto-cleanup empty rooms after 30 seconds
+        if room.current_players > 0 {
+            room.empty_since = None;
+            continue;
+        }
+        match room.empty_since {
+            None => {
+                room.empty_since = Some(now);
+                info!("Room '{}' is now empty - starting cleanup timer", room_id);
+            }
+            Some(empty_since) if now - empty_since > ROOM_CLEANUP_SECS => {
                 info!(
-                    "Player left room '{}'. Players: {}/{}",
-                    room.room_id, room.current_players, room.max_players
+                    "Room '{}' has been empty for {:.1}s - cleaning up",
+                    room_id,
+                    now - empty_since
                 );
+                rooms_to_remove.push(room_id.clone());
             }
-
-            // Note: Game start message moved to when host actually presses START GAME button
-            // This prevents showing start messages immediately upon joining
-
-            // Auto-cleanup empty rooms after 30 seconds
-            if room.current_players == 0 {
-                if room.created_time.is_none() {
-                    room.created_time = Some(time.elapsed_secs_f64());
-                    info!("Room '{}' is now empty - starting cleanup timer", room_id);
-                } else if let Some(empty_since) = room.created_time {
-                    let empty_duration = time.elapsed_secs_f64() - empty_since;
-                    if empty_duration > 30.0 {
-                        // 30 seconds cleanup time
-                        info!(
-                            "Room '{}' has been empty for {:.1}s - cleaning up",
-                            room_id, empty_duration
-                        );
-                        rooms_to_remove.push(room_id.clone());
-                    }
-                }
-            } else {
-                // Reset cleanup timer if players are present
-                room.created_time = None;
-            }
+            Some(_) => {}
         }
     }
 
@@ -305,6 +505,44 @@ fn manage_room_lifecycle(
     }
 }
 
+// Every player gets a status for the others' nameplates, and each room's host its crown
+fn mark_room_hosts(
+    mut commands: Commands,
+    room_registry: Res<RoomRegistry>,
+    mut players: Query<
+        (
+            Entity,
+            Option<&MatchRoom>,
+            Option<&PlayerName>,
+            Option<&mut PlayerStatus>,
+        ),
+        With<Player>,
+    >,
+) {
+    for (entity, room, name, status) in players.iter_mut() {
+        let host = match (room, name) {
+            (Some(room), Some(name)) => room_registry
+                .rooms
+                .get(&room.0)
+                .is_some_and(|room| room.host_name == name.name),
+            _ => false,
+        };
+        match status {
+            Some(mut status) => {
+                if status.host != host {
+                    status.host = host;
+                }
+            }
+            None => {
+                commands.entity(entity).insert(PlayerStatus {
+                    host,
+                    ping_ms: None,
+                });
+            }
+        }
+    }
+}
+
 // Server metadata resource - stores server information for diagnostics and client verification
 #[derive(Resource, Debug, Clone)]
 pub struct ServerMetadata {
@@ -356,7 +594,8 @@ impl ServerMetadata {
                     "git_branch": self.build_info.git_branch,
                     "build_timestamp": self.build_info.build_timestamp,
                     "rustc_version": self.build_info.rustc_version,
-                    "target_triple": self.build_info.target_triple
+                    "target_triple": self.build_info.target_triple,
+                    "asset_manifest_version": self.build_info.asset_manifest_version
                 },
                 "certificate_digest": self.certificate_digest,
                 "fqdn": self.fqdn,
@@ -378,6 +617,11 @@ fn setup_server_metadata(mut metadata: ResMut<ServerMetadata>, time: Res<Time>)
     info!("  ⏰ Build Time: {}", metadata.build_info.build_timestamp);
     info!("  🦀 Rust Version: {}", metadata.build_info.rustc_version);
     info!("  🎯 Target: {}", metadata.build_info.target_triple);
+    info!(
+        "  📦 Asset Manifest: {} ({} assets)",
+        metadata.build_info.asset_manifest_version,
+        shared::ASSET_MANIFEST.len()
+    );
 
     if let Some(ref digest) = metadata.certificate_digest {
         info!("  🔐 Certificate Digest: {}...", &digest[..16]);
@@ -444,6 +688,46 @@ fn log_server_status(
     }
 }
 
+// Ordered list of levels the server cycles through
+#[derive(Resource, Clone, Debug)]
+pub struct LevelRotation {
+    pub levels: Vec<LevelDefinition>,
+    pub current: usize,
+    pub timer: Timer,
+}
+
+impl LevelRotation {
+    pub fn new(levels: Vec<LevelDefinition>, interval: std::time::Duration) -> Self {
+        let levels = if levels.is_empty() {
+            vec![LevelDefinition::default()]
+        } else {
+            levels
+        };
+        Self {
+            levels,
+            current: 0,
+            timer: Timer::new(interval, TimerMode::Repeating),
+        }
+    }
+
+    pub fn current(&self) -> &LevelDefinition {
+        &self.levels[self.current]
+    }
+
+    pub fn level_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Move to the next level (wrapping around) and return it
+    pub fn advance(&mut self) -> &LevelDefinition {
+        self.current = (self.current + 1) % self.levels.len();
+        self.current()
+    }
+}
+
+// Seconds a room may stand empty before it is closed
+const ROOM_CLEANUP_SECS: f64 = 30.0;
+
 // Room management resource - tracks active rooms and player counts
 #[derive(Resource, Default)]
 pub struct RoomRegistry {
@@ -456,10 +740,12 @@ pub struct RoomData {
     pub room_id: String,
     pub host_name: String,
     pub game_mode: String,
+    pub level: String,
     pub current_players: u32,
     pub max_players: u32,
     pub player_names: Vec<String>,
-    pub created_time: Option<f64>,
+    /// When the last player left, None while anyone is in the room
+    pub empty_since: Option<f64>,
     pub started: bool, // Track if the room has been explicitly started
 }
 
@@ -483,21 +769,22 @@ impl RoomRegistry {
         }
     }
 
-    #[allow(dead_code)]
     pub fn create_room(
         &mut self,
         room_id: String,
         host_name: String,
         game_mode: String,
+        level: String,
     ) -> RoomData {
         let room_data = RoomData {
             room_id: room_id.clone(),
             host_name,
             game_mode,
-            current_players: 1,
+            level,
+            current_players: 0,
             max_players: 4,
             player_names: Vec::new(),
-            created_time: None,
+            empty_since: None,
             started: false, // Rooms start as not started
         };
         self.rooms.insert(room_id.clone(), room_data.clone());
@@ -510,10 +797,16 @@ impl RoomRegistry {
             .values()
             .map(|room| RoomInfo {
                 room_id: room.room_id.clone(),
+                // Codes are handed out by the lobby service
+                code: String::new(),
                 current_players: room.current_players,
                 max_players: room.max_players,
                 host_name: room.host_name.clone(),
                 game_mode: room.game_mode.clone(),
+                level: room.level.clone(),
+                has_password: false,
+                map: room.level.clone(),
+                region: None,
             })
             .collect()
     }
@@ -567,3 +860,144 @@ impl MatchmakingQueue {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn private_key_parsing() {
+        let key = parse_private_key("[1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]")
+            .unwrap();
+        assert_eq!(key[0], 1);
+        assert_eq!(key[31], 1);
+
+        assert!(parse_private_key("1,2,3").is_err());
+        assert!(parse_private_key("not a key").is_err());
+        // The development key would let anyone mint connect tokens
+        assert!(parse_private_key(&["0"; 32].join(",")).is_err());
+    }
+
+    #[test]
+    fn rooms_count_their_own_players() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .insert_resource(RoomRegistry::new())
+            .insert_resource(GameMode::Casual)
+            .insert_resource(LevelDefinition::default())
+            .add_systems(Update, manage_room_lifecycle);
+        let player = |room: &str, name: &str| {
+            (
+                Player::default(),
+                MatchRoom(room.to_string()),
+                PlayerName {
+                    name: name.to_string(),
+                },
+            )
+        };
+        app.world_mut().spawn(player("R1", "ann"));
+        app.world_mut().spawn(player("R1", "bob"));
+        let carl = app.world_mut().spawn(player("R2", "carl")).id();
+        // Bots and local players belong to no room
+        app.world_mut().spawn(Player::default());
+        app.update();
+
+        let rooms = &app.world().resource::<RoomRegistry>().rooms;
+        assert_eq!(rooms.len(), 2);
+        assert_eq!(rooms["R1"].current_players, 2);
+        assert_eq!(rooms["R1"].host_name, "ann");
+        assert_eq!(rooms["R2"].player_names, vec!["carl".to_string()]);
+
+        // Carl leaving empties R2 only
+        app.world_mut().despawn(carl);
+        app.update();
+        let rooms = &app.world().resource::<RoomRegistry>().rooms;
+        assert_eq!(rooms["R1"].current_players, 2);
+        assert_eq!(rooms["R2"].current_players, 0);
+        assert!(rooms["R2"].empty_since.is_some());
+        assert!(rooms["R1"].empty_since.is_none());
+    }
+
+    #[test]
+    fn room_hosts_are_marked() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .insert_resource(RoomRegistry::new())
+            .insert_resource(GameMode::Casual)
+            .insert_resource(LevelDefinition::default())
+            .add_systems(Update, (manage_room_lifecycle, mark_room_hosts).chain());
+        let ann = app
+            .world_mut()
+            .spawn((
+                Player::default(),
+                MatchRoom("R1".to_string()),
+                PlayerName {
+                    name: "ann".to_string(),
+                },
+            ))
+            .id();
+        app.update();
+        let bob = app
+            .world_mut()
+            .spawn((
+                Player::default(),
+                MatchRoom("R1".to_string()),
+                PlayerName {
+                    name: "bob".to_string(),
+                },
+            ))
+            .id();
+        let bot = app.world_mut().spawn(Player::default()).id();
+        app.update();
+        app.update();
+
+        let host = |entity| app.world().get::<PlayerStatus>(entity).map(|s| s.host);
+        assert_eq!(host(ann), Some(true));
+        assert_eq!(host(bob), Some(false));
+        assert_eq!(host(bot), Some(false));
+    }
+
+    #[test]
+    fn rooms_sharing_a_server_dont_interact() {
+        use shared::{stomp_system, PlayerScore, Stunned, TeamRules};
+
+        let mut app = App::new();
+        app.init_resource::<TeamRules>()
+            .add_systems(Update, (stomp_system, collect_pickups_system));
+        let player = |room: &str, y: f32, falling: bool| {
+            (
+                Player {
+                    velocity: Vec2::new(0.0, if falling { -100.0 } else { 0.0 }),
+                    grounded: !falling,
+                },
+                PlayerTransform {
+                    translation: Vec3::new(0.0, y, 0.0),
+                },
+                MatchRoom(room.to_string()),
+            )
+        };
+        let pickup = |room: &str| {
+            (
+                Pickup { value: 1 },
+                Transform::from_xyz(0.0, 45.0, 0.0),
+                MatchRoom(room.to_string()),
+            )
+        };
+        // Ann of R1 lands on the heads of Bob of R2 and Carl of R1, and on a coin of each room
+        let ann = app.world_mut().spawn(player("R1", 25.0, true)).id();
+        let bob = app.world_mut().spawn(player("R2", 0.0, false)).id();
+        let carl = app.world_mut().spawn(player("R1", 0.0, false)).id();
+        let r1_coin = app.world_mut().spawn(pickup("R1")).id();
+        let r2_coin = app.world_mut().spawn(pickup("R2")).id();
+        app.update();
+
+        assert!(app.world().get::<Stunned>(carl).is_some());
+        assert!(app.world().get::<Stunned>(bob).is_none());
+        assert!(app.world().get_entity(r1_coin).is_err());
+        assert!(app.world().get_entity(r2_coin).is_ok());
+        assert_eq!(
+            app.world().get::<PlayerScore>(ann).map(|score| score.score),
+            Some(1)
+        );
+    }
+}
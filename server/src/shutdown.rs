@@ -0,0 +1,113 @@
+// Graceful shutdown: on SIGTERM (or Ctrl-C) the server tells its players how long they
+// have left, turns away new connections, ends the running match so its results get
+// persisted, and exits once the grace period is over or everyone has left. A second
+// signal exits right away.
+use bevy::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+#[cfg(feature = "bevygap")]
+use {
+    lightyear::prelude::*,
+    shared::{Channel1, ServerShuttingDown},
+};
+
+static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+pub struct ShutdownPlugin {
+    pub grace: Duration,
+}
+
+/// Present once a shutdown started. Mode rules check it to wrap up their match, the
+/// readiness probe to take the server out of rotation.
+#[derive(Resource)]
+pub struct Draining {
+    timer: Timer,
+}
+
+#[derive(Resource)]
+struct ShutdownGrace(Duration);
+
+/// Drain and exit as if a shutdown signal came in, for servers that host a single match
+pub(crate) fn request_shutdown() {
+    SHUTDOWN_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+impl Plugin for ShutdownPlugin {
+    fn build(&self, app: &mut App) {
+        let handler = ctrlc::set_handler(|| {
+            if SHUTDOWN_REQUESTED.swap(true, Ordering::SeqCst) {
+                warn!("🛑 Second shutdown signal, exiting now");
+                std::process::exit(1);
+            }
+        });
+        if let Err(e) = handler {
+            error!("🛑 Can't handle shutdown signals: {}", e);
+            return;
+        }
+
+        app.insert_resource(ShutdownGrace(self.grace))
+            .add_systems(Update, (start_draining, finish_draining).chain());
+
+        #[cfg(feature = "bevygap")]
+        app.add_observer(refuse_connections);
+    }
+}
+
+fn start_draining(
+    mut commands: Commands,
+    grace: Res<ShutdownGrace>,
+    draining: Option<Res<Draining>>,
+    #[cfg(feature = "bevygap")] mut senders: Query<&mut MessageSender<ServerShuttingDown>>,
+) {
+    if draining.is_some() || !SHUTDOWN_REQUESTED.load(Ordering::SeqCst) {
+        return;
+    }
+    info!(
+        "🛑 Shutting down, draining players for {}s",
+        grace.0.as_secs_f32()
+    );
+    #[cfg(feature = "bevygap")]
+    for mut sender in senders.iter_mut() {
+        sender.send::<Channel1>(ServerShuttingDown {
+            seconds: grace.0.as_secs_f32(),
+        });
+    }
+    commands.insert_resource(Draining {
+        timer: Timer::new(grace.0, TimerMode::Once),
+    });
+}
+
+fn finish_draining(
+    time: Res<Time<Real>>,
+    draining: Option<ResMut<Draining>>,
+    mut exit: EventWriter<AppExit>,
+    #[cfg(feature = "bevygap")] clients: Query<(), With<Connected>>,
+) {
+    let Some(mut draining) = draining else {
+        return;
+    };
+    // Without bevygap there is nobody to wait for
+    #[cfg(feature = "bevygap")]
+    let empty = clients.is_empty();
+    #[cfg(not(feature = "bevygap"))]
+    let empty = true;
+
+    if draining.timer.tick(time.delta()).finished() || empty {
+        info!("🛑 Drained, exiting");
+        exit.write(AppExit::Success);
+    }
+}
+
+// Clients that connect while draining would only see the countdown
+#[cfg(feature = "bevygap")]
+fn refuse_connections(
+    trigger: Trigger<OnAdd, Connected>,
+    mut commands: Commands,
+    draining: Option<Res<Draining>>,
+) {
+    if draining.is_some() {
+        info!("🛑 Refusing client {:?}, shutting down", trigger.target());
+        commands.trigger_targets(Disconnect, trigger.target());
+    }
+}
@@ -0,0 +1,84 @@
+// Spawn placement: every new player, human or bot, is moved to a spawn point of the level
+// that no other player is standing on. In team modes they are put on the smallest team
+// first and spawn at that team's group of points.
+use bevy::prelude::*;
+
+use shared::{LevelDefinition, Player, PlayerId, PlayerTransform, Team, TeamRules};
+
+pub struct SpawnsPlugin;
+
+impl Plugin for SpawnsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, place_new_players);
+    }
+}
+
+fn place_new_players(
+    mut commands: Commands,
+    rules: Res<TeamRules>,
+    level: Res<LevelDefinition>,
+    mut players: Query<(
+        Entity,
+        &PlayerId,
+        Ref<Player>,
+        &mut PlayerTransform,
+        Option<&Team>,
+    )>,
+) {
+    if !players.iter().any(|(_, _, player, ..)| player.is_added()) {
+        return;
+    }
+    let mut occupied: Vec<Vec2> = Vec::new();
+    let mut team_sizes = vec![0; rules.teams];
+    let mut new_players = Vec::new();
+    for (entity, id, player, transform, team) in players.iter_mut() {
+        if player.is_added() {
+            new_players.push((entity, id, transform, team));
+            continue;
+        }
+        occupied.push(transform.translation.truncate());
+        if let Some(size) = team.and_then(|team| team_sizes.get_mut(team.0)) {
+            *size += 1;
+        }
+    }
+
+    // Lowest ids first, so the same players get the same points every time
+    new_players.sort_by_key(|(_, id, ..)| id.id);
+    for (entity, id, mut transform, team) in new_players {
+        let team = match team {
+            Some(team) => Some(team.0),
+            None => assign_team(&team_sizes).inspect(|team| {
+                commands.entity(entity).insert(Team(*team));
+                info!("👥 Player {} joins team {}", id.id, team);
+            }),
+        };
+        if let Some(size) = team.and_then(|team| team_sizes.get_mut(team)) {
+            *size += 1;
+        }
+        transform.translation = level.spawn_point(team, &occupied);
+        occupied.push(transform.translation.truncate());
+    }
+}
+
+// The smallest team, lowest number on a tie. None outside team modes.
+fn assign_team(team_sizes: &[usize]) -> Option<usize> {
+    team_sizes
+        .iter()
+        .enumerate()
+        .min_by_key(|(team, size)| (**size, *team))
+        .map(|(team, _)| team)
+}
+
+/// Spawn points for everyone at once, e.g. when a race restarts. Lowest ids pick first.
+pub(crate) fn respawn_all<'a>(
+    level: &LevelDefinition,
+    players: impl Iterator<Item = (&'a PlayerId, Option<&'a Team>, Mut<'a, PlayerTransform>)>,
+) {
+    let mut players: Vec<_> = players.collect();
+    players.sort_by_key(|(id, ..)| id.id);
+    let mut occupied = Vec::new();
+    for (_, team, mut transform) in players {
+        transform.translation = level.spawn_point(team.map(|team| team.0), &occupied);
+        occupied.push(transform.translation.truncate());
+    }
+}
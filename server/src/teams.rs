@@ -0,0 +1,57 @@
+// Team rules of the match: how many teams there are and whether teammates can stomp each
+// other. Clients hear them as soon as they connect. A player the lobby put on another team
+// than the one they spawned on moves over to their new team's spawn points.
+use bevy::prelude::*;
+
+#[cfg(feature = "bevygap")]
+use {lightyear::prelude::*, shared::Channel1};
+
+use shared::{LevelDefinition, Player, PlayerId, PlayerTransform, Team, TeamRules};
+
+pub struct TeamsPlugin {
+    pub rules: TeamRules,
+}
+
+impl Plugin for TeamsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.rules)
+            .add_systems(Update, move_to_team_spawn);
+
+        #[cfg(feature = "bevygap")]
+        app.add_systems(Update, send_team_rules);
+    }
+}
+
+#[cfg(feature = "bevygap")]
+fn send_team_rules(
+    rules: Res<TeamRules>,
+    mut senders: Query<&mut MessageSender<TeamRules>, Added<Connected>>,
+) {
+    for mut sender in senders.iter_mut() {
+        sender.send::<Channel1>(*rules);
+    }
+}
+
+// A team set after spawning, not the one the player spawned with
+fn move_to_team_spawn(
+    level: Res<LevelDefinition>,
+    mut players: Query<(&PlayerId, Ref<Team>, &mut PlayerTransform), With<Player>>,
+) {
+    let moved = |team: &Ref<Team>| team.is_changed() && !team.is_added();
+    if !players.iter().any(|(_, team, _)| moved(&team)) {
+        return;
+    }
+    let mut occupied: Vec<Vec2> = players
+        .iter()
+        .filter(|(_, team, _)| !moved(team))
+        .map(|(_, _, transform)| transform.translation.truncate())
+        .collect();
+    for (id, team, mut transform) in players.iter_mut() {
+        if !moved(&team) {
+            continue;
+        }
+        info!("👥 Player {} moves to {}", id.id, team.name());
+        transform.translation = level.spawn_point(Some(team.0), &occupied);
+        occupied.push(transform.translation.truncate());
+    }
+}
@@ -0,0 +1,81 @@
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Assets the client verifies after downloading them, relative to `assets/`.
+// Directories are scanned recursively and are allowed to be missing.
+const MANIFEST_ASSETS: &[&str] = &["vey.glb", "levels", "audio"];
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR")?);
+    let assets_dir = manifest_dir.join("../assets");
+
+    // Watching the directory itself picks up newly added asset folders
+    println!("cargo:rerun-if-changed={}", assets_dir.display());
+
+    let mut files = Vec::new();
+    for entry in MANIFEST_ASSETS {
+        let path = assets_dir.join(entry);
+        if path.exists() {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+        if path.is_dir() {
+            collect_files(&path, &mut files)?;
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    // The manifest version covers every entry so a single string identifies the asset set
+    let mut version_hasher = Sha256::new();
+    let mut entries = String::new();
+    for file in &files {
+        let bytes = fs::read(file)?;
+        let relative = file
+            .strip_prefix(&assets_dir)?
+            .to_string_lossy()
+            .replace('\\', "/");
+        let hash = hex::encode(Sha256::digest(&bytes));
+
+        version_hasher.update(relative.as_bytes());
+        version_hasher.update(hash.as_bytes());
+        entries.push_str(&format!(
+            "    AssetManifestEntry {{ path: {:?}, sha256: {:?}, size: {} }},\n",
+            relative,
+            hash,
+            bytes.len()
+        ));
+    }
+    let version = hex::encode(version_hasher.finalize());
+
+    let generated = format!(
+        "pub const ASSET_MANIFEST_VERSION: &str = {:?};\n\
+         pub const ASSET_MANIFEST: &[AssetManifestEntry] = &[\n{}];\n",
+        &version[..16],
+        entries
+    );
+    let out_dir = PathBuf::from(env::var("OUT_DIR")?);
+    fs::write(out_dir.join("asset_manifest.rs"), generated)?;
+    Ok(())
+}
+
+fn collect_files(dir: &Path, out: &mut Vec<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with('.') || n.ends_with(".md"));
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            collect_files(&path, out)?;
+        } else {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,2361 @@
+// JSON bodies exchanged with the lobby service and the matchmaker over HTTP/WebSocket.
+// Both sides build against these so the client and the services can't drift apart.
+use bevy::prelude::Reflect;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use crate::bot::BotStyle;
+use crate::game_mode::GameMode;
+use crate::highlights::Highlight;
+use crate::party::MAX_PARTY_SIZE;
+use crate::protocol_plugin::RoomInfo;
+
+/// A room as the lobby service reports it (`GET /lobby/api/rooms` and friends)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ServerLobbyRoom {
+    pub id: String,
+    /// Short code players see and type instead of the id, see `room_code`
+    #[serde(default)]
+    pub code: String,
+    pub host_name: String,
+    /// The host's account, set by services that take player tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host_id: Option<String>,
+    /// One of the ids in `GameMode`
+    pub game_mode: String,
+    /// Unix timestamp in seconds
+    pub created_at: u64,
+    pub started: bool,
+    pub current_players: u32,
+    pub max_players: u32,
+    #[serde(default)]
+    pub level: String,
+    /// Joining takes the password the host set
+    #[serde(default)]
+    pub has_password: bool,
+    /// Map name shown in the room browser, empty from older services
+    #[serde(default)]
+    pub map: String,
+    /// Edgegap location of the room's game server, None until it is deployed
+    #[serde(default)]
+    pub region: Option<String>,
+    /// Teams the room plays in, 0 for free-for-all
+    #[serde(default)]
+    pub teams: usize,
+    /// Teammates can stomp each other
+    #[serde(default)]
+    pub friendly_fire: bool,
+    /// Who is in the room, empty from older services
+    #[serde(default)]
+    pub members: Vec<RoomMember>,
+    /// The host keeps the room open after a match, with its results
+    #[serde(default)]
+    pub publish_results: bool,
+    /// Outcome of the room's last match, for players who come back to it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_match: Option<RoomMatchResult>,
+    /// `RuleSet` of a custom room, as the RON the host sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rules: Option<String>,
+    /// Server bots fill the match's empty slots up to `max_players`
+    #[serde(default)]
+    pub bot_backfill: bool,
+    /// How those bots play
+    #[serde(default)]
+    pub bot_style: BotStyle,
+}
+
+/// A player in a room
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RoomMember {
+    pub name: String,
+    /// Account of the player, from the token they joined with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player_id: Option<String>,
+    /// Team the player is on in team rooms, None until the service put them on one
+    #[serde(default)]
+    pub team: Option<usize>,
+    /// The player said they are ready to start, see `POST /rooms/{id}/ready`. The host is
+    /// ready from the start.
+    #[serde(default)]
+    pub ready: bool,
+    /// Unix timestamp in seconds, 0 from older services
+    #[serde(default)]
+    pub joined_at: u64,
+}
+
+impl ServerLobbyRoom {
+    /// Whether the player with this name and account hosts the room
+    pub fn is_host(&self, name: &str, player_id: Option<&str>) -> bool {
+        match (&self.host_id, player_id) {
+            (Some(host_id), Some(player_id)) => host_id == player_id,
+            _ => self.host_name == name,
+        }
+    }
+
+    /// Take a player out of the room when they leave or are kicked. When the host goes, the
+    /// member who has been in the room longest takes over and is ready right away.
+    pub fn remove_member(&mut self, name: &str, player_id: Option<&str>) -> Option<RoomMember> {
+        let index = self
+            .members
+            .iter()
+            .position(|member| member.is(name, player_id))?;
+        let removed = self.members.remove(index);
+        self.current_players = self.members.len() as u32;
+        if self.is_host(name, player_id) {
+            if let Some(next) = self.members.iter_mut().min_by_key(|m| m.joined_at) {
+                next.ready = true;
+                self.host_name = next.name.clone();
+                self.host_id = next.player_id.clone();
+            }
+        }
+        Some(removed)
+    }
+}
+
+impl RoomMember {
+    /// Whether this is the player with this name and account. Ids decide when both sides
+    /// have one, names are not unique.
+    pub fn is(&self, name: &str, player_id: Option<&str>) -> bool {
+        match (&self.player_id, player_id) {
+            (Some(id), Some(player_id)) => id == player_id,
+            _ => self.name == name,
+        }
+    }
+
+    /// "joined 3m ago" for the room roster, None when the service didn't say
+    pub fn joined_ago(&self, now: u64) -> Option<String> {
+        if self.joined_at == 0 {
+            return None;
+        }
+        let secs = now.saturating_sub(self.joined_at);
+        Some(match secs {
+            0..60 => "joined just now".to_string(),
+            60..3600 => format!("joined {}m ago", secs / 60),
+            _ => format!("joined {}h ago", secs / 3600),
+        })
+    }
+}
+
+impl From<ServerLobbyRoom> for RoomInfo {
+    fn from(room: ServerLobbyRoom) -> Self {
+        Self {
+            room_id: room.id,
+            code: room.code,
+            current_players: room.current_players,
+            max_players: room.max_players,
+            host_name: room.host_name,
+            game_mode: room.game_mode,
+            level: room.level,
+            has_password: room.has_password,
+            map: room.map,
+            region: room.region,
+        }
+    }
+}
+
+/// Body of `POST /lobby/api/rooms`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreateRoomRequest {
+    pub host_name: String,
+    pub game_mode: String,
+    pub max_players: u32,
+    pub level: String,
+    /// The host's account, when it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host_id: Option<String>,
+    /// Players have to send this to join, None for an open room
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// Teams to split players into, 0 for free-for-all
+    #[serde(default)]
+    pub teams: usize,
+    #[serde(default)]
+    pub friendly_fire: bool,
+    /// Keep the room and the results of its match once it ends
+    #[serde(default)]
+    pub publish_results: bool,
+    /// Custom rooms only: a `RuleSet` in RON, rejected with 400 `INVALID_RULES` unless
+    /// `RuleSet::from_ron` takes it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rules: Option<String>,
+    /// Let server bots fill the match's empty slots
+    #[serde(default)]
+    pub bot_backfill: bool,
+    /// How those bots play
+    #[serde(default)]
+    pub bot_style: BotStyle,
+}
+
+/// Rooms one player can have open at once
+pub const MAX_ACTIVE_ROOMS_PER_PLAYER: usize = 2;
+/// Seconds a player waits after creating a room before the next one
+pub const ROOM_CREATE_COOLDOWN_SECS: u64 = 30;
+
+/// Who a new room counts against: the host's account, or the `hash_ip` of their address
+/// for players without one
+pub fn room_creator(host_id: Option<&str>, ip_hash: &str) -> String {
+    match host_id {
+        Some(id) => format!("player:{}", id),
+        None => format!("ip:{}", ip_hash),
+    }
+}
+
+/// What the lobby service remembers about one `room_creator`
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct RoomCreatorRecord {
+    /// Rooms they host that are still open
+    pub active_rooms: usize,
+    /// Unix timestamp in seconds of the last room they created
+    pub last_created_at: Option<u64>,
+}
+
+impl RoomCreatorRecord {
+    /// Ok if they may create a room at `now`, otherwise what `POST /rooms` answers with
+    pub fn check(&self, now: u64) -> Result<(), ServiceError> {
+        if self.active_rooms >= MAX_ACTIVE_ROOMS_PER_PLAYER {
+            return Err(ServiceError {
+                error: "TOO_MANY_ROOMS".to_string(),
+                message: Some(format!("already hosting {} rooms", self.active_rooms)),
+                retry_after_secs: None,
+            });
+        }
+        let ready_at = self
+            .last_created_at
+            .map_or(0, |last| last + ROOM_CREATE_COOLDOWN_SECS);
+        if now < ready_at {
+            return Err(ServiceError {
+                error: "ROOM_CREATE_COOLDOWN".to_string(),
+                message: Some("created a room moments ago".to_string()),
+                retry_after_secs: Some(ready_at - now),
+            });
+        }
+        Ok(())
+    }
+
+    pub fn created(&mut self, now: u64) {
+        self.active_rooms += 1;
+        self.last_created_at = Some(now);
+    }
+
+    /// One of their rooms closed, expired or was handed to another host
+    pub fn closed(&mut self) {
+        self.active_rooms = self.active_rooms.saturating_sub(1);
+    }
+}
+
+/// Match requests one player or address may make per `MATCH_REQUEST_WINDOW_SECS`
+pub const MATCH_REQUESTS_PER_WINDOW: u32 = 5;
+pub const MATCH_REQUEST_WINDOW_SECS: u64 = 60;
+/// Edgegap deployments the matchmaker runs at once, unless its flag says otherwise
+pub const DEFAULT_MAX_DEPLOYMENTS: usize = 20;
+/// Longest level name a match request may ask for
+pub const MAX_LEVEL_NAME_LEN: usize = 32;
+
+/// Ok if a `/matchmaker/ws` request's `game_mode` and `level` are ones a server can be
+/// started with, otherwise the error the matchmaker closes the socket with. Level names
+/// end up in `VOIDLOOP_LEVEL`, which the server also reads as a path, so they are kept
+/// to plain names.
+pub fn validate_match_request(
+    game_mode: &str,
+    level: Option<&str>,
+) -> Result<GameMode, ServiceError> {
+    let Some(mode) = GameMode::from_id(game_mode) else {
+        return Err(ServiceError {
+            error: "UNKNOWN_GAME_MODE".to_string(),
+            message: Some(format!("no game mode '{}'", game_mode)),
+            retry_after_secs: None,
+        });
+    };
+    if let Some(level) = level {
+        let plain = level
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !plain || level.is_empty() || level.len() > MAX_LEVEL_NAME_LEN {
+            return Err(ServiceError {
+                error: "INVALID_LEVEL".to_string(),
+                message: Some(format!("'{}' is not a level name", level)),
+                retry_after_secs: None,
+            });
+        }
+    }
+    Ok(mode)
+}
+
+/// Match requests one key (a `player:{id}` or `ip:{hash}`, see `room_creator`) made in
+/// the current window
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct MatchRequestWindow {
+    /// Unix timestamp in seconds the window opened
+    pub started_at: u64,
+    pub requests: u32,
+}
+
+impl MatchRequestWindow {
+    /// Count a request at `now`, or say how long until the next one is allowed. The
+    /// matchmaker checks the player's window and the address's, so neither a new address
+    /// nor a new account resets the count.
+    pub fn request(&mut self, now: u64) -> Result<(), ServiceError> {
+        if now >= self.started_at + MATCH_REQUEST_WINDOW_SECS {
+            *self = Self {
+                started_at: now,
+                requests: 0,
+            };
+        }
+        if self.requests >= MATCH_REQUESTS_PER_WINDOW {
+            return Err(ServiceError {
+                error: "RATE_LIMITED".to_string(),
+                message: Some(format!(
+                    "{} match requests in {}s",
+                    self.requests, MATCH_REQUEST_WINDOW_SECS
+                )),
+                retry_after_secs: Some(self.started_at + MATCH_REQUEST_WINDOW_SECS - now),
+            });
+        }
+        self.requests += 1;
+        Ok(())
+    }
+
+    /// Windows that ran out can be dropped
+    pub fn expired(&self, now: u64) -> bool {
+        now >= self.started_at + MATCH_REQUEST_WINDOW_SECS
+    }
+}
+
+/// Deployments the matchmaker has running. Every `lobby_deploy` takes a slot first, and
+/// gives it back once the deployment is stopped or failed to start.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeploymentSlots {
+    pub max: usize,
+    pub active: usize,
+}
+
+impl DeploymentSlots {
+    pub fn new(max: usize) -> Self {
+        Self { max, active: 0 }
+    }
+
+    pub fn acquire(&mut self) -> Result<(), ServiceError> {
+        if self.active >= self.max {
+            return Err(ServiceError {
+                error: "TOO_MANY_DEPLOYMENTS".to_string(),
+                message: Some(format!("{} of {} servers running", self.active, self.max)),
+                retry_after_secs: Some(30),
+            });
+        }
+        self.active += 1;
+        Ok(())
+    }
+
+    pub fn release(&mut self) {
+        self.active = self.active.saturating_sub(1);
+    }
+}
+
+/// Body of the room `join`, `leave` and `heartbeat` calls
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RoomPlayerRequest {
+    pub player_name: String,
+    /// The player's account, when it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player_id: Option<String>,
+    /// The room's password, only checked by `join`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+}
+
+/// Rooms per page when a room query does not say
+pub const ROOMS_PER_PAGE: u32 = 10;
+/// Most rooms one page can hold
+pub const MAX_ROOMS_PER_PAGE: u32 = 50;
+
+/// Query string of `GET /lobby/api/rooms`, every filter is optional
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Reflect)]
+#[cfg_attr(
+    feature = "openapi",
+    derive(utoipa::IntoParams),
+    into_params(parameter_in = Query)
+)]
+#[serde(default)]
+pub struct RoomQuery {
+    /// Only rooms of this `GameMode` id
+    pub game_mode: Option<String>,
+    /// Only rooms with a free slot
+    pub has_space: bool,
+    /// Part of the room code or host name, any case
+    pub search: Option<String>,
+    pub sort: RoomSort,
+    /// Zero-based
+    pub page: u32,
+    /// `ROOMS_PER_PAGE` if not given, at most `MAX_ROOMS_PER_PAGE`
+    pub per_page: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum RoomSort {
+    /// Most recently created first
+    #[default]
+    Newest,
+    /// Fullest rooms first, newest first among equally full ones
+    MostPlayers,
+}
+
+/// One page of `GET /lobby/api/rooms` when it was called with a query
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RoomPage {
+    pub rooms: Vec<ServerLobbyRoom>,
+    /// Matching rooms over all pages
+    pub total: u32,
+}
+
+/// Body of `GET /lobby/api/rooms`: services without room queries answer every room
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(untagged)]
+pub enum RoomListResponse {
+    Page(RoomPage),
+    All(Vec<ServerLobbyRoom>),
+}
+
+impl RoomQuery {
+    pub fn per_page(&self) -> u32 {
+        self.per_page
+            .unwrap_or(ROOMS_PER_PAGE)
+            .clamp(1, MAX_ROOMS_PER_PAGE)
+    }
+
+    /// Started rooms can't be joined, so they never match
+    pub fn matches(&self, room: &ServerLobbyRoom) -> bool {
+        if room.started {
+            return false;
+        }
+        if self
+            .game_mode
+            .as_ref()
+            .is_some_and(|mode| *mode != room.game_mode)
+        {
+            return false;
+        }
+        if self.has_space && room.current_players >= room.max_players {
+            return false;
+        }
+        match self.search.as_deref().map(str::trim) {
+            Some(search) if !search.is_empty() => {
+                let search = search.to_lowercase();
+                room.code.to_lowercase().contains(&search)
+                    || room.host_name.to_lowercase().contains(&search)
+            }
+            _ => true,
+        }
+    }
+
+    /// Filter, sort and cut out the requested page. The lobby service answers queries
+    /// with this, and the client runs it itself against services that can't.
+    pub fn apply(&self, rooms: Vec<ServerLobbyRoom>) -> RoomPage {
+        let mut rooms: Vec<ServerLobbyRoom> = rooms
+            .into_iter()
+            .filter(|room| self.matches(room))
+            .collect();
+        match self.sort {
+            RoomSort::Newest => rooms.sort_by_key(|room| Reverse(room.created_at)),
+            RoomSort::MostPlayers => {
+                rooms.sort_by_key(|room| (Reverse(room.current_players), Reverse(room.created_at)))
+            }
+        }
+        let total = rooms.len() as u32;
+        let per_page = self.per_page() as usize;
+        let rooms = rooms
+            .into_iter()
+            .skip(self.page as usize * per_page)
+            .take(per_page)
+            .collect();
+        RoomPage { rooms, total }
+    }
+
+    /// `?game_mode=..&page=..` for the request URL, empty filters left out
+    pub fn to_query_string(&self) -> String {
+        let mut params = Vec::new();
+        if let Some(mode) = &self.game_mode {
+            params.push(format!("game_mode={}", url_encode(mode)));
+        }
+        if self.has_space {
+            params.push("has_space=true".to_string());
+        }
+        if let Some(search) = self.search.as_deref().map(str::trim) {
+            if !search.is_empty() {
+                params.push(format!("search={}", url_encode(search)));
+            }
+        }
+        params.push(format!(
+            "sort={}",
+            match self.sort {
+                RoomSort::Newest => "newest",
+                RoomSort::MostPlayers => "most_players",
+            }
+        ));
+        params.push(format!("page={}", self.page));
+        params.push(format!("per_page={}", self.per_page()));
+        format!("?{}", params.join("&"))
+    }
+}
+
+/// `ETag` of `GET /rooms` while the room list is at `version`, a counter the service
+/// bumps on every room change. Each query has its own URL, so the version alone tells a
+/// client whether its page is current.
+pub fn room_list_etag(version: u64) -> String {
+    format!("\"rooms-{}\"", version)
+}
+
+/// Whether an `If-None-Match` header names `etag`, and a `GET` can be answered with 304.
+/// `*` matches anything, and weak tags compare like strong ones.
+pub fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let etag = opaque(etag);
+    if_none_match
+        .split(',')
+        .map(opaque)
+        .any(|tag| tag == "*" || tag == etag)
+}
+
+// Percent-encode everything but the unreserved URL characters
+pub(crate) fn url_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Longest display name, in characters
+pub const MAX_DISPLAY_NAME_LEN: usize = 16;
+
+/// Trim a display name and check it: 2 to `MAX_DISPLAY_NAME_LEN` characters,
+/// letters, digits, spaces, `-` and `_` only
+pub fn sanitize_display_name(name: &str) -> Option<String> {
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    let length = name.chars().count();
+    let allowed = name
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == ' ' || c == '-' || c == '_');
+    (allowed && (2..=MAX_DISPLAY_NAME_LEN).contains(&length)).then_some(name)
+}
+
+/// Body of `POST /lobby/api/rooms/{id}/team`: put `member` on `team`. The host can move
+/// anyone, everyone else only themselves.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TeamChangeRequest {
+    /// Who is asking
+    pub player_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player_id: Option<String>,
+    pub member: String,
+    /// Account of `member`, when the roster had one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub member_id: Option<String>,
+    pub team: usize,
+}
+
+/// Body of `POST /lobby/api/rooms/{id}/ready`: the player is ready to start, or no longer
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ReadyRequest {
+    pub player_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player_id: Option<String>,
+    pub ready: bool,
+}
+
+/// Body of `POST /lobby/api/rooms/{id}/kick`: the host takes `member` out of the room
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct KickRequest {
+    /// Who is asking
+    pub player_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player_id: Option<String>,
+    pub member: String,
+    /// Account of `member`, when the roster had one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub member_id: Option<String>,
+}
+
+/// Seconds the host waits for everyone to be ready before they may start anyway
+pub const READY_CHECK_SECS: u64 = 30;
+
+/// Seconds until a room with these members may start, 0 once everyone is ready or the ready
+/// check ran out. Every join starts the check over. Members from older services have no
+/// join time, so their rooms can always start.
+pub fn ready_check_secs_left(members: &[RoomMember], now: u64) -> u64 {
+    if members.iter().all(|member| member.ready) {
+        return 0;
+    }
+    let last_join = members.iter().map(|member| member.joined_at).max();
+    (last_join.unwrap_or(0) + READY_CHECK_SECS).saturating_sub(now)
+}
+
+/// Body of `POST /lobby/api/players` and `PUT /lobby/api/players/{id}`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DisplayNameRequest {
+    pub display_name: String,
+}
+
+/// A persistent player account as the lobby service stores it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PlayerAccount {
+    pub player_id: String,
+    pub display_name: String,
+}
+
+/// Body of `GET /lobby/api/players/{id}/rating`, accounts that never played ranked get
+/// `EloConfig::initial` and zero games
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PlayerRating {
+    pub player_id: String,
+    pub rating: f32,
+    /// Ranked matches played, the first few move the rating faster
+    pub games: u32,
+}
+
+/// Body of `POST /lobby/api/ranked`, sent by the matchmaker before it deploys a ranked
+/// match's server. `match_id` becomes the server's `VOIDLOOP_ROOM_ID`, and only the
+/// accounts listed here are rated when the server reports the result.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RankedMatch {
+    pub match_id: String,
+    pub players: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MatchmakingRequest {
+    pub player_id: String,
+    pub game_mode: String,
+    /// Region with the lowest ping, picks the queue the ticket waits in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// Milliseconds to every region the client could reach, by region id
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub pings: BTreeMap<String, u32>,
+    /// Party the ticket queues with, see `ready_units`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub party_id: Option<String>,
+    /// Members of that party, the matchmaker waits for this many tickets
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub party_size: Option<usize>,
+}
+
+/// The region with the lowest ping, ties go to the first by name so every caller agrees
+pub fn best_region<'a>(pings: impl IntoIterator<Item = (&'a String, &'a u32)>) -> Option<&'a str> {
+    pings
+        .into_iter()
+        .min_by_key(|(region, ms)| (**ms, region.as_str()))
+        .map(|(region, _)| region.as_str())
+}
+
+/// Value of the `pings` query parameter that hands a client's pings to the matchmaker,
+/// e.g. `eu:42,us:120`
+pub fn format_pings<'a>(pings: impl IntoIterator<Item = (&'a String, &'a u32)>) -> String {
+    let pings: BTreeMap<&String, &u32> = pings.into_iter().collect();
+    let pings: Vec<String> = pings
+        .into_iter()
+        .map(|(region, ms)| format!("{}:{}", region, ms))
+        .collect();
+    pings.join(",")
+}
+
+/// Reads the `pings` query parameter, entries that don't parse are left out
+pub fn parse_pings(param: &str) -> BTreeMap<String, u32> {
+    param
+        .split(',')
+        .filter_map(|entry| {
+            let (region, ms) = entry.trim().split_once(':')?;
+            let ms = ms.parse().ok()?;
+            (!region.is_empty()).then(|| (region.to_string(), ms))
+        })
+        .collect()
+}
+
+/// Region to deploy a match's server in: of the regions every player reached, the one
+/// whose slowest player has the lowest ping, so nobody is far away to make someone else
+/// closer. When the players share no region, the best region most of them asked for.
+pub fn match_region(tickets: &[MatchmakingRequest]) -> Option<String> {
+    let first = tickets.first()?;
+    let shared = first
+        .pings
+        .keys()
+        .filter(|region| tickets.iter().all(|t| t.pings.contains_key(*region)))
+        .min_by_key(|region| {
+            let worst = tickets.iter().map(|t| t.pings[*region]).max();
+            let total: u32 = tickets.iter().map(|t| t.pings[*region]).sum();
+            (worst, total)
+        });
+    if let Some(region) = shared {
+        return Some(region.clone());
+    }
+    let mut asked: BTreeMap<&str, usize> = BTreeMap::new();
+    for region in tickets.iter().filter_map(|t| t.region.as_deref()) {
+        *asked.entry(region).or_default() += 1;
+    }
+    asked
+        .into_iter()
+        .max_by_key(|(region, count)| (*count, Reverse(*region)))
+        .map(|(region, _)| region.to_string())
+}
+
+/// Bytes of user data a netcode connect token carries
+pub const CONNECT_TOKEN_USER_DATA_BYTES: usize = 256;
+
+/// User data the matchmaker signs into the connect token of a lobby room's player: the
+/// room id as UTF-8, zero padded. None for ids that don't fit.
+pub fn room_token_user_data(room_id: &str) -> Option<[u8; CONNECT_TOKEN_USER_DATA_BYTES]> {
+    let bytes = room_id.as_bytes();
+    if bytes.is_empty() || bytes.len() > CONNECT_TOKEN_USER_DATA_BYTES || bytes.contains(&0) {
+        return None;
+    }
+    let mut data = [0; CONNECT_TOKEN_USER_DATA_BYTES];
+    data[..bytes.len()].copy_from_slice(bytes);
+    Some(data)
+}
+
+/// Lobby room a connect token was minted for, None for quick match tokens. The token is
+/// signed, so unlike anything the client sends this can't name somebody else's room.
+pub fn room_from_token_user_data(data: &[u8]) -> Option<String> {
+    let len = data.iter().position(|b| *b == 0).unwrap_or(data.len());
+    std::str::from_utf8(&data[..len])
+        .ok()
+        .filter(|room_id| !room_id.is_empty())
+        .map(str::to_string)
+}
+
+/// Sent by the game server to the matchmaker at match start to tune bot difficulty
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SkillRatingsRequest {
+    pub players: Vec<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SkillRatingsResponse {
+    pub ratings: Vec<f32>,
+}
+
+/// Body of `POST /lobby/api/rooms/{id}/finish`, sent by a room's game server when its
+/// match is over. The lobby marks the room finished, keeps the result and drops the
+/// room from the active list, unless the room publishes its results.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MatchReport {
+    pub game_mode: String,
+    pub level: String,
+    pub reason: MatchEndReason,
+    /// Unix timestamp in seconds
+    pub ended_at: u64,
+    pub duration_secs: f32,
+    /// Best first, empty when nobody was left to rank
+    pub standings: Vec<MatchStanding>,
+    /// How each player's connection held up, to tell a broken game from broken Wi-Fi
+    #[serde(default)]
+    pub network: Vec<NetworkQuality>,
+    /// Awards handed out at the end, the lobby service keeps them as achievements
+    #[serde(default)]
+    pub highlights: Vec<Highlight>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum MatchEndReason {
+    /// The mode decided the match, e.g. every racer finished
+    Completed,
+    /// Every player left
+    Abandoned,
+    /// The game server shut down mid-match
+    Shutdown,
+}
+
+/// How long a room keeps its last match's results, unless the service is told otherwise
+pub const ROOM_RESULTS_RETENTION_SECS: u64 = 24 * 60 * 60;
+
+/// What a room with `publish_results` shows of its last match
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RoomMatchResult {
+    pub game_mode: String,
+    pub level: String,
+    /// Unix timestamp in seconds
+    pub ended_at: u64,
+    pub duration_secs: f32,
+    /// Best first
+    pub standings: Vec<MatchStanding>,
+}
+
+impl RoomMatchResult {
+    /// The part of a report a room keeps. None for abandoned matches, nobody was left to
+    /// rank.
+    pub fn from_report(report: &MatchReport) -> Option<Self> {
+        if report.reason == MatchEndReason::Abandoned || report.standings.is_empty() {
+            return None;
+        }
+        Some(Self {
+            game_mode: report.game_mode.clone(),
+            level: report.level.clone(),
+            ended_at: report.ended_at,
+            duration_secs: report.duration_secs,
+            standings: report.standings.clone(),
+        })
+    }
+
+    /// Older than `retention_secs` at `now`, the service drops it
+    pub fn expired(&self, now: u64, retention_secs: u64) -> bool {
+        now.saturating_sub(self.ended_at) > retention_secs
+    }
+}
+
+/// One entry of `GET /lobby/api/matches`: a finished room and the report its server sent
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MatchHistoryEntry {
+    pub room_id: String,
+    #[serde(flatten)]
+    pub report: MatchReport,
+}
+
+/// One player's connection over a match, from the probes their client sent once a second
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct NetworkQuality {
+    pub name: String,
+    /// Seconds the player was connected during the match
+    pub connected_secs: f32,
+    pub rtt_avg_ms: f32,
+    pub rtt_p95_ms: f32,
+    /// Average change between consecutive round trips
+    pub jitter_ms: f32,
+    /// Share of probes that never arrived, 0 to 1
+    pub packet_loss: f32,
+    /// Times the server had to correct the client's prediction, per minute
+    pub corrections_per_min: f32,
+    pub verdict: ConnectionVerdict,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionVerdict {
+    Good,
+    Fair,
+    /// Expect rubber banding, whatever the player reports is likely the connection
+    Poor,
+}
+
+impl ConnectionVerdict {
+    pub fn rate(rtt_p95_ms: f32, jitter_ms: f32, packet_loss: f32) -> Self {
+        if rtt_p95_ms > 250.0 || jitter_ms > 50.0 || packet_loss > 0.05 {
+            Self::Poor
+        } else if rtt_p95_ms > 120.0 || jitter_ms > 20.0 || packet_loss > 0.01 {
+            Self::Fair
+        } else {
+            Self::Good
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MatchStanding {
+    /// Tied players share a place
+    pub place: usize,
+    pub name: String,
+    /// Account id the player's client sent, ranked matches rate these
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player_id: Option<String>,
+    /// Points in scoring modes, checkpoints passed in a race
+    pub score: u32,
+    /// Race time in seconds, for racers who finished
+    pub time: Option<f32>,
+}
+
+/// Body of `GET /lobby/api/status`, shown on the client's server status page
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ServiceStatusResponse {
+    /// Message of the day, e.g. an outage or maintenance notice
+    #[serde(default)]
+    pub motd: Option<String>,
+    #[serde(default)]
+    pub regions: Vec<RegionCapacity>,
+}
+
+/// One entry of `GET /lobby/api/regions`. The client times a request to `ping_url` to
+/// estimate its latency to each room's region.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RegionEndpoint {
+    pub region: String,
+    pub ping_url: String,
+}
+
+/// How full the game servers in one Edgegap region are
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RegionCapacity {
+    pub region: String,
+    pub players: u32,
+    pub capacity: u32,
+}
+
+/// Body of `GET /status.json`: every service's health in one document, for uptime
+/// monitors and the client's status page
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct StatusDocument {
+    /// True when every check passed
+    pub ok: bool,
+    /// Unix timestamp in seconds of when the checks ran
+    pub checked_at: u64,
+    pub checks: Vec<ServiceCheck>,
+    #[serde(flatten)]
+    pub details: ServiceStatusResponse,
+}
+
+/// One service the lobby service probed, e.g. `matchmaker`, `nats` or `edgegap`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ServiceCheck {
+    pub name: String,
+    pub ok: bool,
+    #[serde(default)]
+    pub latency_ms: Option<u32>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// A mutating lobby or admin action, as the lobby service's audit log records it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AuditEntry {
+    /// Unix timestamp in milliseconds
+    pub at: u64,
+    /// Account id of whoever did it, or their display name if they have no account
+    pub actor: String,
+    pub action: AuditAction,
+    /// Room or player the action was aimed at
+    #[serde(default)]
+    pub target: Option<String>,
+    /// `hash_ip` of the caller's address, the address itself is never stored
+    pub ip_hash: String,
+    pub ok: bool,
+    /// Why the action was refused or failed
+    #[serde(default)]
+    pub error: Option<String>,
+    /// What else there is to know, like the rule an anti-cheat flag is for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    CreateRoom,
+    JoinRoom,
+    LeaveRoom,
+    StartRoom,
+    Kick,
+    Ban,
+    Unban,
+    /// Any other call under the admin API
+    Admin,
+    /// A repair made by room reconciliation, see `shared::reconcile`
+    Reconcile,
+    /// A game server caught a player moving in a way the game doesn't allow
+    AntiCheat,
+}
+
+/// Query string of `GET /lobby/api/admin/audit`, every filter is optional
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(
+    feature = "openapi",
+    derive(utoipa::IntoParams),
+    into_params(parameter_in = Query)
+)]
+#[serde(default)]
+pub struct AuditQuery {
+    pub actor: Option<String>,
+    pub action: Option<AuditAction>,
+    pub target: Option<String>,
+    /// Only entries at or after this unix timestamp in milliseconds
+    pub since: Option<u64>,
+    /// Newest entries first, at most this many
+    pub limit: Option<usize>,
+}
+
+/// Movement rule a game server caught a player breaking
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum MovementViolation {
+    /// Moved further in one tick than the level allows, see `max_player_step`
+    Speed,
+    /// Jumped without standing on anything
+    AirJump,
+    /// Pressed jump faster than a person can
+    InputRate,
+}
+
+/// Body of `POST /lobby/api/servers/anticheat`, sent by a game server the first time it
+/// catches a player breaking a movement rule and when it kicks them
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AntiCheatReport {
+    /// Lobby room the player came from, None for quick matches
+    #[serde(default)]
+    pub room_id: Option<String>,
+    /// Account id of the player, or their display name if they have none
+    pub player: String,
+    pub violation: MovementViolation,
+    /// Strikes the player has at the moment, they wear off over time
+    pub strikes: u32,
+    /// The server disconnected the player for it
+    pub kicked: bool,
+    /// What was measured, for whoever reviews it
+    pub detail: String,
+}
+
+/// Header every lobby and matchmaker call carries its trace id in. The services log it
+/// with the request, so a player's report can be matched to the service's logs.
+pub const TRACE_HEADER: &str = "X-Request-Id";
+
+/// Body of a failed lobby or matchmaker call
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ServiceError {
+    /// Stable code like `ROOM_NOT_FOUND`, for players to report and logs to search
+    pub error: String,
+    /// What went wrong in words, for the logs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Seconds until trying again can work, for errors that pass on their own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_secs: Option<u64>,
+}
+
+impl ServiceError {
+    /// Code for a service that answered with a bare status
+    pub fn from_status(status: u16) -> Self {
+        let error = match status {
+            400 => "BAD_REQUEST".to_string(),
+            401 => "UNAUTHORIZED".to_string(),
+            403 => "FORBIDDEN".to_string(),
+            404 => "NOT_FOUND".to_string(),
+            409 => "CONFLICT".to_string(),
+            429 => "RATE_LIMITED".to_string(),
+            500..=599 => "SERVICE_ERROR".to_string(),
+            status => format!("HTTP_{}", status),
+        };
+        Self {
+            error,
+            message: None,
+            retry_after_secs: None,
+        }
+    }
+
+    /// The call never got an answer
+    pub fn unreachable() -> Self {
+        Self {
+            error: "UNREACHABLE".to_string(),
+            message: None,
+            retry_after_secs: None,
+        }
+    }
+
+    /// The service answered with something we can't read, like a proxy's HTML error page
+    pub fn bad_response() -> Self {
+        Self {
+            error: "BAD_RESPONSE".to_string(),
+            message: None,
+            retry_after_secs: None,
+        }
+    }
+
+    /// What the player can do about it, for the errors they can fix themselves
+    pub fn hint(&self) -> Option<String> {
+        match self.error.as_str() {
+            "TOO_MANY_ROOMS" => Some(format!(
+                "you already host {} rooms, leave one first",
+                MAX_ACTIVE_ROOMS_PER_PLAYER
+            )),
+            "ROOM_CREATE_COOLDOWN" => Some(match self.retry_after_secs {
+                Some(secs) => format!("wait {}s before creating another room", secs),
+                None => "wait a little before creating another room".to_string(),
+            }),
+            "RATE_LIMITED" => Some(match self.retry_after_secs {
+                Some(secs) => format!("too many attempts, try again in {}s", secs),
+                None => "too many attempts, try again in a minute".to_string(),
+            }),
+            "TOO_MANY_DEPLOYMENTS" => Some("all servers are busy, try again shortly".to_string()),
+            "MATCHMAKING_TIMEOUT" => Some("no server was ready in time, try again".to_string()),
+            "DISCONNECTED" => Some("lost the connection to the server".to_string()),
+            "UNKNOWN_GAME_MODE" => {
+                Some("this game mode isn't available, update the game".to_string())
+            }
+            "PARTY_FULL" => Some(format!("parties have at most {} players", MAX_PARTY_SIZE)),
+            "NOT_PARTY_LEADER" => Some("only the party leader can do that".to_string()),
+            "NOT_ROOM_HOST" => Some("only the room's host can do that".to_string()),
+            "NOT_IN_ROOM" => Some("you're no longer in this room".to_string()),
+            "KICKED_FROM_ROOM" => Some("the host removed you from this room".to_string()),
+            "CANNOT_KICK_SELF" => Some("leave the room instead".to_string()),
+            "EMPTY_MESSAGE" => Some("type something first".to_string()),
+            "PLAYERS_NOT_READY" => Some(match self.retry_after_secs {
+                Some(secs) => format!("not everyone is ready, or start in {}s", secs),
+                None => "not everyone is ready yet".to_string(),
+            }),
+            "TOKEN_EXPIRED" => Some("your session expired, try again".to_string()),
+            _ => None,
+        }
+    }
+
+    /// "error ROOM_NOT_FOUND — trace 7f3a…", short enough for a toast. The start of the
+    /// id is plenty to find the request in the logs.
+    pub fn toast(&self, trace_id: &str) -> String {
+        let short: String = trace_id.chars().take(4).collect();
+        format!("error {} — trace {}…", self.error, short)
+    }
+}
+
+/// Tries of a GET that found the service briefly away, the first one included
+pub const API_ATTEMPTS: u32 = 3;
+
+/// Statuses a proxy answers with while the service behind it restarts or is overloaded,
+/// worth trying again
+pub fn is_retryable_status(status: u16) -> bool {
+    matches!(status, 502..=504)
+}
+
+/// Milliseconds to wait before retry `retry` (1 for the first): 250, doubling up to 2s
+pub fn retry_delay_ms(retry: u32) -> u64 {
+    (250u64 << retry.saturating_sub(1).min(3)).min(2_000)
+}
+
+/// A `Content-Type` with a JSON body: `application/json` with or without a charset, or a
+/// `+json` type like `application/problem+json`
+pub fn is_json_content_type(content_type: &str) -> bool {
+    let media_type = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    media_type == "application/json"
+        || (media_type.starts_with("application/") && media_type.ends_with("+json"))
+}
+
+/// Salted hash of a client address for logs: the same address gives the same hash,
+/// so abuse can be traced, but the address can't be read back out
+#[cfg(feature = "service")]
+pub fn hash_ip(salt: &str, ip: &str) -> String {
+    let digest = Sha256::digest(format!("{}:{}", salt, ip));
+    hex::encode(&digest[..8])
+}
+
+/// Published on the `lobby.rooms` NATS subject by the lobby replica that changed a room,
+/// so the other replicas push the change to their own WebSockets. `origin` is the
+/// publishing replica's id, replicas skip their own changes.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum RoomChange {
+    Updated {
+        origin: String,
+        // Boxed since rooms carry their last match, the JSON is the same
+        room: Box<ServerLobbyRoom>,
+    },
+    Removed {
+        origin: String,
+        room_id: String,
+    },
+}
+
+/// Subjects room events go out on, `rooms.events.{room_id}.{kind}`. Subscribe to
+/// `rooms.events.>` for everything, or e.g. `rooms.events.*.finished`.
+pub const ROOM_EVENTS_SUBJECT: &str = "rooms.events";
+
+/// Something that happened to a room, published on NATS by the service that saw it: the
+/// lobby service creates rooms, the matchmaker starts them, game servers see players come
+/// and go and matches end. Every service keeps its view of the room from these.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RoomEvent {
+    /// Publishing service, e.g. `lobby-1` or `server:{deployment}`. Services skip their own.
+    pub origin: String,
+    pub room_id: String,
+    /// Unix timestamp in milliseconds
+    pub sent_at: u64,
+    #[serde(flatten)]
+    pub kind: RoomEventKind,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RoomEventKind {
+    Created {
+        game_mode: String,
+        host_name: String,
+    },
+    /// A game server is running the room's match
+    Started {
+        /// Edgegap deployment it runs in
+        deployment: Option<String>,
+    },
+    Finished {
+        reason: MatchEndReason,
+    },
+    PlayerJoined {
+        player: String,
+    },
+    PlayerLeft {
+        player: String,
+    },
+}
+
+impl RoomEventKind {
+    /// Last token of the event's subject
+    pub fn name(&self) -> &'static str {
+        match self {
+            RoomEventKind::Created { .. } => "created",
+            RoomEventKind::Started { .. } => "started",
+            RoomEventKind::Finished { .. } => "finished",
+            RoomEventKind::PlayerJoined { .. } => "player_joined",
+            RoomEventKind::PlayerLeft { .. } => "player_left",
+        }
+    }
+}
+
+impl RoomEvent {
+    /// NATS subject to publish this event on. Characters NATS gives a meaning to can't be
+    /// part of a subject token, room ids that have them get them replaced.
+    pub fn subject(&self) -> String {
+        let room: String = self
+            .room_id
+            .chars()
+            .map(|c| match c {
+                '.' | '*' | '>' => '_',
+                c if c.is_whitespace() => '_',
+                c => c,
+            })
+            .collect();
+        format!("{}.{}.{}", ROOM_EVENTS_SUBJECT, room, self.kind.name())
+    }
+}
+
+/// One matchmaker queue. Each queue is owned by a single matchmaker instance at a time,
+/// which is the only one reading its NATS subject.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct QueueShard {
+    /// One of the ids in `GameMode`
+    pub game_mode: String,
+    /// Edgegap region, e.g. `eu`
+    pub region: String,
+}
+
+impl QueueShard {
+    /// NATS subject that tickets for this queue are published on
+    pub fn subject(&self) -> String {
+        format!("matchmaker.queue.{}.{}", self.region, self.game_mode)
+    }
+
+    /// Instance that owns this queue, see `shard_owner`
+    pub fn owner<'a>(&self, instances: &'a [String]) -> Option<&'a str> {
+        shard_owner(&format!("{}.{}", self.region, self.game_mode), instances)
+    }
+}
+
+/// Picks the instance that owns `key` (a queue, or a room id) by rendezvous hashing:
+/// every instance agrees without talking to the others, and when one joins or leaves
+/// only the keys it owned move.
+pub fn shard_owner<'a>(key: &str, instances: &'a [String]) -> Option<&'a str> {
+    instances
+        .iter()
+        .max_by_key(|instance| {
+            let digest = Sha256::digest(format!("{}:{}", instance, key));
+            let mut score = [0u8; 8];
+            score.copy_from_slice(&digest[..8]);
+            u64::from_be_bytes(score)
+        })
+        .map(String::as_str)
+}
+
+/// Value of the `match.{match_id}` key an instance creates before deploying a match.
+/// The key is created only if absent, so exactly one instance deploys each match.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MatchClaim {
+    /// Instance id of the matchmaker that won the claim
+    pub owner: String,
+    /// Room id the match will be played in
+    pub room_id: String,
+    /// Unix timestamp in milliseconds
+    pub claimed_at: u64,
+}
+
+/// One queue's numbers in the matchmaker's `GET /matchmaker/shards`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShardMetrics {
+    pub shard: QueueShard,
+    pub owner: String,
+    /// Tickets waiting right now
+    pub queued: u32,
+    pub matches_deployed: u64,
+    /// Claims this instance lost to another one, should stay at 0 outside rebalances
+    pub claims_lost: u64,
+}
+
+/// Messages on a room's voice socket. The relay fills in `from` and routes on `to`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum VoiceSignal {
+    /// First message after connecting: everyone already in the room's voice
+    Peers {
+        peers: Vec<String>,
+    },
+    Left {
+        player: String,
+    },
+    Offer {
+        #[serde(default)]
+        from: String,
+        #[serde(default)]
+        to: String,
+        sdp: String,
+    },
+    Answer {
+        #[serde(default)]
+        from: String,
+        #[serde(default)]
+        to: String,
+        sdp: String,
+    },
+    Ice {
+        #[serde(default)]
+        from: String,
+        #[serde(default)]
+        to: String,
+        candidate: String,
+        sdp_mid: Option<String>,
+        sdp_m_line_index: Option<u16>,
+    },
+}
+
+/// What kind of GPU the client renders on, from wgpu's adapter device type. Browsers
+/// often hide it, so WebGL2 clients mostly show up as `Unknown`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum GpuClass {
+    Discrete,
+    Integrated,
+    Virtual,
+    /// Software rendering, e.g. llvmpipe or SwiftShader
+    Software,
+    Unknown,
+}
+
+impl GpuClass {
+    /// From the `Debug` name of `wgpu::DeviceType`, as the client logs it
+    pub fn from_device_type(device_type: &str) -> Self {
+        match device_type {
+            "DiscreteGpu" => Self::Discrete,
+            "IntegratedGpu" => Self::Integrated,
+            "VirtualGpu" => Self::Virtual,
+            "Cpu" => Self::Software,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// Graphics quality the client renders at, lowest first
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum QualityPreset {
+    /// No anti-aliasing, no shadows
+    Low,
+    /// 4x MSAA
+    Medium,
+    /// 4x MSAA and shadows
+    High,
+}
+
+impl QualityPreset {
+    pub const ALL: [QualityPreset; 3] = [Self::Low, Self::Medium, Self::High];
+
+    /// Used until the lobby has enough reports for the GPU class
+    pub fn default_for(gpu_class: GpuClass) -> Self {
+        match gpu_class {
+            GpuClass::Discrete => Self::High,
+            GpuClass::Integrated | GpuClass::Unknown => Self::Medium,
+            GpuClass::Virtual | GpuClass::Software => Self::Low,
+        }
+    }
+
+    pub fn lower(self) -> Self {
+        match self {
+            Self::High => Self::Medium,
+            Self::Medium | Self::Low => Self::Low,
+        }
+    }
+}
+
+/// Frame rate a preset has to hold on a GPU class before it becomes the class default
+pub const TARGET_FPS: f32 = 55.0;
+/// Reports a GPU class, backend and preset need before their frame rates count
+pub const MIN_HARDWARE_REPORTS: u32 = 20;
+
+/// Body of `POST /lobby/api/telemetry/hardware`, sent once per session by clients whose
+/// player agreed to share hardware info
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct HardwareReport {
+    pub gpu_class: GpuClass,
+    /// `Debug` name of `wgpu::Backend`, `Gl` for WebGL2
+    pub backend: String,
+    /// Adapter name the driver reports, e.g. "ANGLE (Intel, Mesa Intel(R) UHD Graphics 620)"
+    pub adapter: String,
+    pub preset: QualityPreset,
+    /// Average frame rate over the sample
+    pub avg_fps: f32,
+    /// Frame rate of the slowest 5% of frames
+    pub low_fps: f32,
+    pub sample_secs: f32,
+}
+
+/// One row of `GET /lobby/api/admin/telemetry/hardware`: every report of a GPU class,
+/// backend and preset
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct HardwareSummary {
+    pub gpu_class: GpuClass,
+    pub backend: String,
+    pub preset: QualityPreset,
+    pub reports: u32,
+    /// Median of the reports' average frame rates
+    pub median_fps: f32,
+    /// Median of the reports' slowest 5% frame rates
+    pub median_low_fps: f32,
+}
+
+/// One entry of `GET /lobby/api/telemetry/presets`: the default preset for clients on a
+/// GPU class and backend that haven't picked one themselves
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PresetRecommendation {
+    pub gpu_class: GpuClass,
+    pub backend: String,
+    pub preset: QualityPreset,
+}
+
+/// Group reports into the admin report's rows, reference implementation for the lobby
+/// service
+pub fn summarize_hardware(reports: &[HardwareReport]) -> Vec<HardwareSummary> {
+    let mut groups: BTreeMap<(GpuClass, &str, QualityPreset), Vec<&HardwareReport>> =
+        BTreeMap::new();
+    for report in reports {
+        groups
+            .entry((report.gpu_class, report.backend.as_str(), report.preset))
+            .or_default()
+            .push(report);
+    }
+    groups
+        .into_iter()
+        .map(|((gpu_class, backend, preset), reports)| HardwareSummary {
+            gpu_class,
+            backend: backend.to_string(),
+            preset,
+            reports: reports.len() as u32,
+            median_fps: median(reports.iter().map(|r| r.avg_fps)),
+            median_low_fps: median(reports.iter().map(|r| r.low_fps)),
+        })
+        .collect()
+}
+
+/// Per GPU class and backend, the highest preset that holds `TARGET_FPS`. When even the
+/// lowest measured preset is too slow, one below that. Classes without enough reports get
+/// no recommendation, and clients stay on `QualityPreset::default_for`.
+pub fn recommend_presets(summaries: &[HardwareSummary]) -> Vec<PresetRecommendation> {
+    let mut measured: BTreeMap<(GpuClass, &str), Vec<&HardwareSummary>> = BTreeMap::new();
+    for summary in summaries {
+        if summary.reports >= MIN_HARDWARE_REPORTS {
+            measured
+                .entry((summary.gpu_class, summary.backend.as_str()))
+                .or_default()
+                .push(summary);
+        }
+    }
+    measured
+        .into_iter()
+        .map(|((gpu_class, backend), summaries)| {
+            let fast = summaries
+                .iter()
+                .filter(|s| s.median_fps >= TARGET_FPS)
+                .map(|s| s.preset)
+                .max();
+            let slowest = summaries
+                .iter()
+                .map(|s| s.preset)
+                .min()
+                .unwrap_or(QualityPreset::Low);
+            PresetRecommendation {
+                gpu_class,
+                backend: backend.to_string(),
+                preset: fast.unwrap_or_else(|| slowest.lower()),
+            }
+        })
+        .collect()
+}
+
+fn median(values: impl Iterator<Item = f32>) -> f32 {
+    let mut values: Vec<f32> = values.collect();
+    values.sort_by(f32::total_cmp);
+    match values.len() {
+        0 => 0.0,
+        n if n % 2 == 1 => values[n / 2],
+        n => (values[n / 2 - 1] + values[n / 2]) / 2.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::highlights::Award;
+    use serde::de::DeserializeOwned;
+    use std::fmt::Debug;
+
+    fn round_trip<T: Serialize + DeserializeOwned + PartialEq + Debug>(value: T) {
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(serde_json::from_str::<T>(&json).unwrap(), value);
+    }
+
+    #[test]
+    fn token_user_data_names_the_room() {
+        let data = room_token_user_data("ROOM042").unwrap();
+        assert_eq!(room_from_token_user_data(&data).as_deref(), Some("ROOM042"));
+
+        // Quick match tokens carry no user data
+        assert_eq!(
+            room_from_token_user_data(&[0; CONNECT_TOKEN_USER_DATA_BYTES]),
+            None
+        );
+        assert_eq!(room_from_token_user_data(&[0xff, 0xfe, 0]), None);
+        assert!(room_token_user_data("").is_none());
+        assert!(room_token_user_data(&"R".repeat(CONNECT_TOKEN_USER_DATA_BYTES + 1)).is_none());
+    }
+
+    #[test]
+    fn dtos_round_trip() {
+        round_trip(ServerLobbyRoom {
+            id: "ROOM042".to_string(),
+            code: "K7QX2M".to_string(),
+            host_name: "Player7".to_string(),
+            host_id: Some("p7".to_string()),
+            game_mode: "race".to_string(),
+            created_at: 1_760_000_000,
+            started: false,
+            current_players: 2,
+            max_players: 4,
+            level: "towers".to_string(),
+            has_password: true,
+            map: "Towers".to_string(),
+            region: Some("eu-west".to_string()),
+            teams: 2,
+            friendly_fire: false,
+            members: vec![
+                RoomMember {
+                    name: "Player7".to_string(),
+                    player_id: Some("p7".to_string()),
+                    team: Some(0),
+                    ready: true,
+                    joined_at: 1_760_000_000,
+                },
+                RoomMember {
+                    name: "Player8".to_string(),
+                    player_id: None,
+                    team: None,
+                    ready: false,
+                    joined_at: 0,
+                },
+            ],
+            publish_results: true,
+            last_match: Some(RoomMatchResult {
+                game_mode: "race".to_string(),
+                level: "towers".to_string(),
+                ended_at: 1_760_000_300,
+                duration_secs: 94.5,
+                standings: vec![MatchStanding {
+                    place: 1,
+                    name: "Player7".to_string(),
+                    player_id: None,
+                    score: 5,
+                    time: Some(61.25),
+                }],
+            }),
+            rules: Some("(name: \"Moon\", gravity: -350.0)".to_string()),
+            bot_backfill: true,
+            bot_style: BotStyle::Aggressive,
+        });
+        round_trip(RegionEndpoint {
+            region: "eu-west".to_string(),
+            ping_url: "https://eu-west.ping.voidloop.quest/".to_string(),
+        });
+        round_trip(CreateRoomRequest {
+            host_name: "Player7".to_string(),
+            game_mode: "casual".to_string(),
+            max_players: 4,
+            level: "default".to_string(),
+            host_id: Some("7f3c2a".to_string()),
+            password: Some("hunter2".to_string()),
+            teams: 2,
+            friendly_fire: true,
+            publish_results: true,
+            rules: None,
+            bot_backfill: true,
+            bot_style: BotStyle::Passive,
+        });
+        round_trip(TeamChangeRequest {
+            player_name: "Player7".to_string(),
+            player_id: None,
+            member: "Player8".to_string(),
+            member_id: Some("8e1d4b".to_string()),
+            team: 1,
+        });
+        round_trip(RoomPlayerRequest {
+            player_name: "Player7".to_string(),
+            player_id: None,
+            password: None,
+        });
+        round_trip(PlayerAccount {
+            player_id: "7f3c2a".to_string(),
+            display_name: "Nova".to_string(),
+        });
+        round_trip(MatchmakingRequest {
+            player_id: "abc".to_string(),
+            game_mode: "ranked".to_string(),
+            region: None,
+            pings: BTreeMap::new(),
+            party_id: None,
+            party_size: None,
+        });
+        round_trip(MatchmakingRequest {
+            player_id: "abc".to_string(),
+            game_mode: "casual".to_string(),
+            region: Some("eu".to_string()),
+            pings: BTreeMap::from([("eu".to_string(), 40), ("us".to_string(), 120)]),
+            party_id: Some("9d1e".to_string()),
+            party_size: Some(2),
+        });
+        round_trip(SkillRatingsRequest {
+            players: vec![0, 1],
+        });
+        round_trip(PlayerRating {
+            player_id: "7f3c2a".to_string(),
+            rating: 1264.5,
+            games: 3,
+        });
+        round_trip(RankedMatch {
+            match_id: "RANKED-9f1e".to_string(),
+            players: vec!["7f3c2a".to_string(), "b81d04".to_string()],
+        });
+        round_trip(SkillRatingsResponse {
+            ratings: vec![1200.0, 1450.5],
+        });
+        round_trip(MatchReport {
+            game_mode: "race".to_string(),
+            level: "canyon".to_string(),
+            reason: MatchEndReason::Completed,
+            ended_at: 1_760_000_300,
+            duration_secs: 94.5,
+            standings: vec![MatchStanding {
+                place: 1,
+                name: "Player7".to_string(),
+                player_id: Some("7f3c2a".to_string()),
+                score: 5,
+                time: Some(61.25),
+            }],
+            network: vec![NetworkQuality {
+                name: "Player7".to_string(),
+                connected_secs: 94.0,
+                rtt_avg_ms: 48.5,
+                rtt_p95_ms: 71.0,
+                jitter_ms: 6.25,
+                packet_loss: 0.0,
+                corrections_per_min: 1.5,
+                verdict: ConnectionVerdict::Good,
+            }],
+            highlights: vec![Highlight {
+                award: Award::LongestAirtime,
+                name: "Player7".to_string(),
+                player_id: Some("7f3c2a".to_string()),
+                value: 2.4,
+            }],
+        });
+        round_trip(ServiceStatusResponse {
+            motd: Some("Maintenance at 18:00 UTC".to_string()),
+            regions: vec![RegionCapacity {
+                region: "eu-west".to_string(),
+                players: 12,
+                capacity: 64,
+            }],
+        });
+        round_trip(StatusDocument {
+            ok: false,
+            checked_at: 1_760_000_000,
+            checks: vec![
+                ServiceCheck {
+                    name: "matchmaker".to_string(),
+                    ok: true,
+                    latency_ms: Some(12),
+                    error: None,
+                },
+                ServiceCheck {
+                    name: "nats".to_string(),
+                    ok: false,
+                    latency_ms: None,
+                    error: Some("connection refused".to_string()),
+                },
+            ],
+            details: ServiceStatusResponse::default(),
+        });
+        round_trip(AuditEntry {
+            at: 1_760_000_000_000,
+            actor: "7f3c2a".to_string(),
+            action: AuditAction::Kick,
+            target: Some("Player12".to_string()),
+            ip_hash: "5d41402abc4b2a76".to_string(),
+            ok: false,
+            error: Some("not the host".to_string()),
+            detail: None,
+        });
+        round_trip(AntiCheatReport {
+            room_id: Some("ROOM042".to_string()),
+            player: "7f3c2a".to_string(),
+            violation: MovementViolation::Speed,
+            strikes: 5,
+            kicked: true,
+            detail: "moved 240.0 units in a tick, 31.5 allowed".to_string(),
+        });
+        round_trip(RoomChange::Removed {
+            origin: "lobby-1".to_string(),
+            room_id: "ROOM042".to_string(),
+        });
+        round_trip(RoomEvent {
+            origin: "server:ab12cd".to_string(),
+            room_id: "ROOM042".to_string(),
+            sent_at: 1_700_000_000_000,
+            kind: RoomEventKind::Finished {
+                reason: MatchEndReason::Completed,
+            },
+        });
+        round_trip(ShardMetrics {
+            shard: QueueShard {
+                game_mode: "casual".to_string(),
+                region: "eu".to_string(),
+            },
+            owner: "mm-1".to_string(),
+            queued: 3,
+            matches_deployed: 12,
+            claims_lost: 0,
+        });
+        round_trip(HardwareReport {
+            gpu_class: GpuClass::Integrated,
+            backend: "Gl".to_string(),
+            adapter: "ANGLE (Intel, Mesa Intel(R) UHD Graphics 620)".to_string(),
+            preset: QualityPreset::Medium,
+            avg_fps: 58.5,
+            low_fps: 41.0,
+            sample_secs: 60.0,
+        });
+        round_trip(PresetRecommendation {
+            gpu_class: GpuClass::Unknown,
+            backend: "Gl".to_string(),
+            preset: QualityPreset::Low,
+        });
+        round_trip(MatchClaim {
+            owner: "mm-1".to_string(),
+            room_id: "ROOM042".to_string(),
+            claimed_at: 1_700_000_000_000,
+        });
+        round_trip(VoiceSignal::Ice {
+            from: "Player7".to_string(),
+            to: "Player12".to_string(),
+            candidate: "candidate:1 1 udp 2122260223 10.0.0.2 54400 typ host".to_string(),
+            sdp_mid: Some("0".to_string()),
+            sdp_m_line_index: Some(0),
+        });
+    }
+
+    fn room(id: &str, created_at: u64, current_players: u32) -> ServerLobbyRoom {
+        ServerLobbyRoom {
+            id: id.to_string(),
+            code: String::new(),
+            host_name: format!("host of {}", id),
+            host_id: None,
+            game_mode: "casual".to_string(),
+            created_at,
+            started: false,
+            current_players,
+            max_players: 4,
+            level: String::new(),
+            has_password: false,
+            map: String::new(),
+            region: None,
+            teams: 0,
+            friendly_fire: false,
+            members: Vec::new(),
+            publish_results: false,
+            last_match: None,
+            rules: None,
+            bot_backfill: false,
+            bot_style: BotStyle::Normal,
+        }
+    }
+
+    #[test]
+    fn room_queries_filter_sort_and_page() {
+        let mut started = room("STARTED", 5, 1);
+        started.started = true;
+        let mut race = room("RACE1", 4, 1);
+        race.game_mode = "race".to_string();
+        let rooms = vec![
+            room("OLD", 1, 3),
+            room("FULL", 2, 4),
+            room("NEW", 3, 1),
+            race,
+            started,
+        ];
+        let ids =
+            |page: RoomPage| -> Vec<String> { page.rooms.into_iter().map(|r| r.id).collect() };
+
+        let all = RoomQuery::default().apply(rooms.clone());
+        assert_eq!(all.total, 4);
+        assert_eq!(ids(all), ["RACE1", "NEW", "FULL", "OLD"]);
+
+        let query = RoomQuery {
+            game_mode: Some("casual".to_string()),
+            has_space: true,
+            sort: RoomSort::MostPlayers,
+            ..RoomQuery::default()
+        };
+        assert_eq!(ids(query.apply(rooms.clone())), ["OLD", "NEW"]);
+
+        let query = RoomQuery {
+            search: Some(" host of n".to_string()),
+            ..RoomQuery::default()
+        };
+        assert_eq!(ids(query.apply(rooms.clone())), ["NEW"]);
+
+        let query = RoomQuery {
+            page: 1,
+            per_page: Some(3),
+            ..RoomQuery::default()
+        };
+        let page = query.apply(rooms);
+        assert_eq!(page.total, 4);
+        assert_eq!(ids(page), ["OLD"]);
+    }
+
+    #[test]
+    fn room_queries_encode_for_urls() {
+        let query = RoomQuery {
+            game_mode: Some("koth".to_string()),
+            search: Some("Zoë & co".to_string()),
+            ..RoomQuery::default()
+        };
+        assert_eq!(
+            query.to_query_string(),
+            "?game_mode=koth&search=Zo%C3%AB%20%26%20co&sort=newest&page=0&per_page=10"
+        );
+    }
+
+    #[test]
+    fn unchanged_room_lists_are_not_sent_again() {
+        let etag = room_list_etag(41);
+        assert_eq!(etag, "\"rooms-41\"");
+        assert!(etag_matches("\"rooms-41\"", &etag));
+        assert!(etag_matches("W/\"rooms-41\"", &etag));
+        assert!(etag_matches("\"rooms-7\", \"rooms-41\"", &etag));
+        assert!(etag_matches("*", &etag));
+        assert!(!etag_matches("\"rooms-40\"", &etag));
+        assert!(!etag_matches("", &etag));
+    }
+
+    #[test]
+    fn shard_owners_are_stable_and_move_little() {
+        let instances: Vec<String> = (0..4).map(|i| format!("mm-{}", i)).collect();
+        assert_eq!(shard_owner("room", &[]), None);
+        let before: Vec<&str> = (0..200)
+            .map(|i| shard_owner(&format!("ROOM{}", i), &instances).unwrap())
+            .collect();
+        // Every instance gets some keys
+        for instance in &instances {
+            assert!(before.iter().any(|owner| owner == instance));
+        }
+
+        // Dropping an instance only moves the keys it owned
+        let rest = &instances[1..];
+        for (i, owner) in before.iter().enumerate() {
+            let after = shard_owner(&format!("ROOM{}", i), rest).unwrap();
+            if *owner != "mm-0" {
+                assert_eq!(after, *owner);
+            }
+        }
+
+        let shard = QueueShard {
+            game_mode: "race".to_string(),
+            region: "eu".to_string(),
+        };
+        assert_eq!(shard.subject(), "matchmaker.queue.eu.race");
+        assert_eq!(shard.owner(&instances), shard.owner(&instances));
+    }
+
+    fn hardware(gpu_class: GpuClass, preset: QualityPreset, avg_fps: f32) -> HardwareReport {
+        HardwareReport {
+            gpu_class,
+            backend: "Gl".to_string(),
+            adapter: String::new(),
+            preset,
+            avg_fps,
+            low_fps: avg_fps / 2.0,
+            sample_secs: 60.0,
+        }
+    }
+
+    #[test]
+    fn presets_follow_measured_frame_rates() {
+        let mut reports = Vec::new();
+        for i in 0..MIN_HARDWARE_REPORTS {
+            let jitter = (i % 3) as f32;
+            // Integrated GPUs hold 60 on medium but not on high
+            reports.push(hardware(
+                GpuClass::Integrated,
+                QualityPreset::Medium,
+                59.0 + jitter,
+            ));
+            reports.push(hardware(
+                GpuClass::Integrated,
+                QualityPreset::High,
+                40.0 + jitter,
+            ));
+            // Software rendering can't even hold low
+            reports.push(hardware(
+                GpuClass::Software,
+                QualityPreset::Medium,
+                20.0 + jitter,
+            ));
+        }
+        // Too few reports to say anything about discrete GPUs
+        reports.push(hardware(GpuClass::Discrete, QualityPreset::High, 20.0));
+
+        let summaries = summarize_hardware(&reports);
+        assert_eq!(summaries.len(), 4);
+        let high = summaries
+            .iter()
+            .find(|s| s.gpu_class == GpuClass::Integrated && s.preset == QualityPreset::High)
+            .unwrap();
+        assert_eq!(high.reports, MIN_HARDWARE_REPORTS);
+        assert_eq!(high.median_fps, 41.0);
+        assert_eq!(high.median_low_fps, 20.5);
+
+        let recommended: Vec<(GpuClass, QualityPreset)> = recommend_presets(&summaries)
+            .into_iter()
+            .map(|r| (r.gpu_class, r.preset))
+            .collect();
+        assert_eq!(
+            recommended,
+            vec![
+                (GpuClass::Integrated, QualityPreset::Medium),
+                (GpuClass::Software, QualityPreset::Low),
+            ]
+        );
+        assert_eq!(
+            GpuClass::from_device_type("IntegratedGpu"),
+            GpuClass::Integrated
+        );
+        assert_eq!(GpuClass::from_device_type("Other"), GpuClass::Unknown);
+    }
+
+    #[test]
+    fn display_names_are_sanitized() {
+        assert_eq!(
+            sanitize_display_name("  Void   Runner "),
+            Some("Void Runner".to_string())
+        );
+        assert_eq!(sanitize_display_name("x"), None);
+        assert_eq!(sanitize_display_name("<script>"), None);
+        assert_eq!(sanitize_display_name(&"a".repeat(17)), None);
+        assert_eq!(sanitize_display_name("Zoë_9"), Some("Zoë_9".to_string()));
+    }
+
+    #[cfg(feature = "service")]
+    #[test]
+    fn ip_hashes_are_stable_and_salted() {
+        let hash = hash_ip("salt", "203.0.113.7");
+        assert_eq!(hash.len(), 16);
+        assert_eq!(hash, hash_ip("salt", "203.0.113.7"));
+        assert_ne!(hash, hash_ip("other salt", "203.0.113.7"));
+        assert_ne!(hash, hash_ip("salt", "203.0.113.8"));
+    }
+
+    #[test]
+    fn service_errors_name_a_code_and_the_trace() {
+        let error: ServiceError =
+            serde_json::from_str(r#"{"error":"ROOM_NOT_FOUND","message":"no room R1"}"#).unwrap();
+        assert_eq!(
+            error.toast("7f3a91c2d4e5b6a7"),
+            "error ROOM_NOT_FOUND — trace 7f3a…"
+        );
+        assert_eq!(ServiceError::from_status(404).error, "NOT_FOUND");
+        assert_eq!(ServiceError::from_status(503).error, "SERVICE_ERROR");
+        assert_eq!(ServiceError::from_status(418).error, "HTTP_418");
+    }
+
+    #[test]
+    fn only_json_bodies_are_parsed_and_gateway_errors_retried() {
+        assert!(is_json_content_type("application/json"));
+        assert!(is_json_content_type("Application/JSON; charset=utf-8"));
+        assert!(is_json_content_type("application/problem+json"));
+        // The proxy's error page behind the "<!DOCTYPE" panics
+        assert!(!is_json_content_type("text/html; charset=utf-8"));
+        assert!(!is_json_content_type(""));
+
+        assert!(is_retryable_status(502));
+        assert!(is_retryable_status(504));
+        assert!(!is_retryable_status(500));
+        assert!(!is_retryable_status(429));
+        assert_eq!(
+            (1..=5).map(retry_delay_ms).collect::<Vec<_>>(),
+            [250, 500, 1_000, 2_000, 2_000]
+        );
+    }
+
+    #[test]
+    fn room_creation_is_capped_and_cooled_down() {
+        assert_eq!(room_creator(Some("p1"), "abcd"), "player:p1");
+        assert_eq!(room_creator(None, "abcd"), "ip:abcd");
+
+        let mut record = RoomCreatorRecord::default();
+        assert_eq!(record.check(1_000), Ok(()));
+        record.created(1_000);
+        let error = record.check(1_010).unwrap_err();
+        assert_eq!(error.error, "ROOM_CREATE_COOLDOWN");
+        assert_eq!(error.retry_after_secs, Some(ROOM_CREATE_COOLDOWN_SECS - 10));
+        assert_eq!(
+            error.hint().unwrap(),
+            format!(
+                "wait {}s before creating another room",
+                ROOM_CREATE_COOLDOWN_SECS - 10
+            )
+        );
+        assert_eq!(record.check(1_000 + ROOM_CREATE_COOLDOWN_SECS), Ok(()));
+
+        record.created(2_000);
+        let error = record.check(3_000).unwrap_err();
+        assert_eq!(error.error, "TOO_MANY_ROOMS");
+        assert_eq!(error.retry_after_secs, None);
+        record.closed();
+        assert_eq!(record.check(3_000), Ok(()));
+    }
+
+    #[test]
+    fn room_events_go_out_on_their_room_and_kind() {
+        let event = RoomEvent {
+            origin: "lobby-1".to_string(),
+            room_id: "ROOM042".to_string(),
+            sent_at: 1,
+            kind: RoomEventKind::PlayerJoined {
+                player: "Player7".to_string(),
+            },
+        };
+        assert_eq!(event.subject(), "rooms.events.ROOM042.player_joined");
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"origin":"lobby-1","room_id":"ROOM042","sent_at":1,"type":"player_joined","player":"Player7"}"#
+        );
+        let odd = RoomEvent {
+            room_id: "a.b *>".to_string(),
+            ..event
+        };
+        assert_eq!(odd.subject(), "rooms.events.a_b___.player_joined");
+    }
+
+    #[test]
+    fn match_requests_are_validated_and_limited() {
+        assert_eq!(
+            validate_match_request("koth", Some("sky_temple-2")),
+            Ok(GameMode::Koth)
+        );
+        assert_eq!(validate_match_request("casual", None), Ok(GameMode::Casual));
+        for (mode, level, code) in [
+            ("deathmatch", None, "UNKNOWN_GAME_MODE"),
+            ("casual", Some("../../etc/passwd"), "INVALID_LEVEL"),
+            ("casual", Some(""), "INVALID_LEVEL"),
+        ] {
+            assert_eq!(validate_match_request(mode, level).unwrap_err().error, code);
+        }
+
+        let mut window = MatchRequestWindow::default();
+        for _ in 0..MATCH_REQUESTS_PER_WINDOW {
+            assert_eq!(window.request(100), Ok(()));
+        }
+        let error = window.request(110).unwrap_err();
+        assert_eq!(error.error, "RATE_LIMITED");
+        assert_eq!(error.retry_after_secs, Some(MATCH_REQUEST_WINDOW_SECS - 10));
+        assert!(window.expired(100 + MATCH_REQUEST_WINDOW_SECS));
+        assert_eq!(window.request(100 + MATCH_REQUEST_WINDOW_SECS), Ok(()));
+
+        let mut slots = DeploymentSlots::new(1);
+        assert_eq!(slots.acquire(), Ok(()));
+        assert_eq!(slots.acquire().unwrap_err().error, "TOO_MANY_DEPLOYMENTS");
+        slots.release();
+        assert_eq!(slots.acquire(), Ok(()));
+    }
+
+    #[test]
+    fn rooms_keep_decided_matches_for_a_while() {
+        let mut report = MatchReport {
+            game_mode: "casual".to_string(),
+            level: "default".to_string(),
+            reason: MatchEndReason::Completed,
+            ended_at: 1_000,
+            duration_secs: 180.0,
+            standings: vec![MatchStanding {
+                place: 1,
+                name: "Player7".to_string(),
+                player_id: None,
+                score: 12,
+                time: None,
+            }],
+            network: Vec::new(),
+            highlights: Vec::new(),
+        };
+        let result = RoomMatchResult::from_report(&report).unwrap();
+        assert_eq!(result.standings, report.standings);
+        assert!(!result.expired(
+            1_000 + ROOM_RESULTS_RETENTION_SECS,
+            ROOM_RESULTS_RETENTION_SECS
+        ));
+        assert!(result.expired(
+            1_001 + ROOM_RESULTS_RETENTION_SECS,
+            ROOM_RESULTS_RETENTION_SECS
+        ));
+
+        report.reason = MatchEndReason::Abandoned;
+        assert_eq!(RoomMatchResult::from_report(&report), None);
+    }
+
+    #[test]
+    fn wire_format_matches_the_services() {
+        // Rooms from older services have no code, level, password, map or region
+        let room: ServerLobbyRoom = serde_json::from_str(
+            r#"{"id":"R1","host_name":"h","game_mode":"casual","created_at":1,"started":true,"current_players":1,"max_players":4}"#,
+        )
+        .unwrap();
+        let room = RoomInfo::from(room);
+        assert_eq!(room.code, "");
+        assert_eq!(room.level, "");
+        assert!(!room.has_password);
+        assert_eq!(room.map, "");
+        assert_eq!(room.region, None);
+
+        // Players without an account leave the id out entirely
+        let json = serde_json::to_string(&RoomPlayerRequest {
+            player_name: "Player7".to_string(),
+            player_id: None,
+            password: None,
+        })
+        .unwrap();
+        assert_eq!(json, r#"{"player_name":"Player7"}"#);
+
+        // History entries are the report with the room id next to it
+        let entry: MatchHistoryEntry = serde_json::from_str(
+            r#"{"room_id":"R1","game_mode":"race","level":"l","reason":"completed","ended_at":1,"duration_secs":2.0,"standings":[],"network":[]}"#,
+        )
+        .unwrap();
+        assert_eq!(entry.report.reason, MatchEndReason::Completed);
+
+        // Reports from servers without network probes have no network section
+        let report: MatchReport = serde_json::from_str(
+            r#"{"game_mode":"koth","level":"l","reason":"shutdown","ended_at":1,"duration_secs":2.0,"standings":[]}"#,
+        )
+        .unwrap();
+        assert!(report.network.is_empty());
+
+        // Loss and jitter make a connection poor even at a low round trip
+        assert_eq!(
+            ConnectionVerdict::rate(40.0, 5.0, 0.0),
+            ConnectionVerdict::Good
+        );
+        assert_eq!(
+            ConnectionVerdict::rate(150.0, 5.0, 0.0),
+            ConnectionVerdict::Fair
+        );
+        assert_eq!(
+            ConnectionVerdict::rate(40.0, 5.0, 0.08),
+            ConnectionVerdict::Poor
+        );
+        assert_eq!(
+            ConnectionVerdict::rate(40.0, 60.0, 0.0),
+            ConnectionVerdict::Poor
+        );
+
+        // The lobby matches on the snake_case reason
+        assert_eq!(
+            serde_json::to_string(&MatchEndReason::Abandoned).unwrap(),
+            r#""abandoned""#
+        );
+
+        // Services without room queries answer with every room
+        let rooms: RoomListResponse = serde_json::from_str("[]").unwrap();
+        assert_eq!(rooms, RoomListResponse::All(Vec::new()));
+        let rooms: RoomListResponse = serde_json::from_str(r#"{"rooms":[],"total":0}"#).unwrap();
+        assert_eq!(rooms, RoomListResponse::Page(RoomPage::default()));
+
+        // A status without regions or MOTD is still a status
+        let status: ServiceStatusResponse = serde_json::from_str("{}").unwrap();
+        assert_eq!(status, ServiceStatusResponse::default());
+
+        // The status document carries the MOTD and regions at the top level
+        let document: StatusDocument = serde_json::from_str(
+            r#"{"ok":true,"checked_at":1,"checks":[],"motd":"hi","regions":[]}"#,
+        )
+        .unwrap();
+        assert_eq!(document.details.motd.as_deref(), Some("hi"));
+
+        // Clients leave `from` for the relay to fill in
+        let offer: VoiceSignal =
+            serde_json::from_str(r#"{"type":"offer","to":"Player12","sdp":"v=0"}"#).unwrap();
+        assert_eq!(
+            offer,
+            VoiceSignal::Offer {
+                from: String::new(),
+                to: "Player12".to_string(),
+                sdp: "v=0".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn matches_are_deployed_near_all_their_players() {
+        let ticket = |region: &str, pings: &[(&str, u32)]| MatchmakingRequest {
+            player_id: "p".to_string(),
+            game_mode: "casual".to_string(),
+            region: Some(region.to_string()),
+            pings: pings.iter().map(|(r, ms)| (r.to_string(), *ms)).collect(),
+            party_id: None,
+            party_size: None,
+        };
+        let near_eu = ticket("eu", &[("eu", 20), ("us", 110), ("asia", 250)]);
+        assert_eq!(best_region(&near_eu.pings), Some("eu"));
+        assert_eq!(format_pings(&near_eu.pings), "asia:250,eu:20,us:110");
+        assert_eq!(parse_pings(&format_pings(&near_eu.pings)), near_eu.pings);
+        assert_eq!(parse_pings("eu:20,us,:5,asia:x").len(), 1);
+
+        // Europe is 20ms for one and 190ms for the other, the US is fairer to both
+        let near_us_east = ticket("us", &[("eu", 190), ("us", 60)]);
+        assert_eq!(
+            match_region(&[near_eu.clone(), near_us_east]),
+            Some("us".to_string())
+        );
+        // Nothing measured in common, most asked for wins
+        let eu_only = ticket("eu", &[("eu", 30)]);
+        let asia_only = ticket("asia", &[("asia", 30)]);
+        assert_eq!(
+            match_region(&[eu_only.clone(), asia_only, eu_only]),
+            Some("eu".to_string())
+        );
+        assert_eq!(match_region(&[]), None);
+    }
+
+    #[test]
+    fn hosts_hand_over_when_they_leave() {
+        let member = |name: &str, id: Option<&str>, joined_at: u64| RoomMember {
+            name: name.to_string(),
+            player_id: id.map(str::to_string),
+            team: None,
+            ready: false,
+            joined_at,
+        };
+        let mut room = room("R", 0, 3);
+        room.host_name = "Nova".to_string();
+        room.host_id = Some("p1".to_string());
+        room.members = vec![
+            member("Nova", Some("p1"), 100),
+            member("Io", None, 300),
+            member("Vex", Some("p2"), 200),
+        ];
+
+        // Same name, other account
+        assert!(room.remove_member("Nova", Some("p9")).is_none());
+        assert!(room.remove_member("Io", None).is_some());
+        assert_eq!(room.current_players, 2);
+        assert!(room.is_host("Nova", Some("p1")));
+
+        room.remove_member("Nova", Some("p1")).unwrap();
+        assert!(room.is_host("Vex", Some("p2")));
+        assert_eq!(room.host_name, "Vex");
+        assert!(room.members[0].ready);
+        room.remove_member("Vex", Some("p2")).unwrap();
+        assert_eq!(room.current_players, 0);
+    }
+
+    #[test]
+    fn roster_says_when_players_joined_and_are_ready() {
+        let member: RoomMember = serde_json::from_str(r#"{"name":"Nova"}"#).unwrap();
+        assert!(!member.ready);
+        assert_eq!(member.joined_ago(1_000), None);
+        let member = RoomMember {
+            joined_at: 1_000,
+            ..member
+        };
+        assert_eq!(member.joined_ago(1_030).unwrap(), "joined just now");
+        assert_eq!(member.joined_ago(1_000 + 5 * 60).unwrap(), "joined 5m ago");
+        assert_eq!(member.joined_ago(1_000 + 7_200).unwrap(), "joined 2h ago");
+        // Clocks that are behind the service's
+        assert_eq!(member.joined_ago(900).unwrap(), "joined just now");
+
+        let mut members = vec![
+            RoomMember {
+                ready: true,
+                ..member.clone()
+            },
+            RoomMember {
+                name: "Vex".to_string(),
+                joined_at: 1_010,
+                ..member
+            },
+        ];
+        assert_eq!(
+            ready_check_secs_left(&members, 1_020),
+            READY_CHECK_SECS - 10
+        );
+        assert_eq!(ready_check_secs_left(&members, 1_010 + READY_CHECK_SECS), 0);
+        members[1].ready = true;
+        assert_eq!(ready_check_secs_left(&members, 1_020), 0);
+        // Older services say nothing about either
+        members[1].ready = false;
+        members[1].joined_at = 0;
+        members[0].joined_at = 0;
+        assert_eq!(ready_check_secs_left(&members, 1_020), 0);
+    }
+}
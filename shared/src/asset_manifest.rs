@@ -0,0 +1,121 @@
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+// An asset hashed at build time, see shared/build.rs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AssetManifestEntry {
+    /// Path relative to the `assets/` folder, as passed to the asset server
+    pub path: &'static str,
+    pub sha256: &'static str,
+    pub size: usize,
+}
+
+// Generated by build.rs: ASSET_MANIFEST_VERSION and ASSET_MANIFEST
+include!(concat!(env!("OUT_DIR"), "/asset_manifest.rs"));
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetIntegrityError {
+    /// The asset is not part of the manifest this binary was built with
+    Unknown(String),
+    /// Size differs, usually a truncated download or a different asset version
+    SizeMismatch {
+        path: String,
+        expected: usize,
+        actual: usize,
+    },
+    /// Same size but different content
+    HashMismatch {
+        path: String,
+        expected: String,
+        actual: String,
+    },
+}
+
+impl fmt::Display for AssetIntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetIntegrityError::Unknown(path) => {
+                write!(f, "{} is not in the asset manifest", path)
+            }
+            AssetIntegrityError::SizeMismatch {
+                path,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{} is {} bytes, expected {} (stale CDN cache or truncated download?)",
+                path, actual, expected
+            ),
+            AssetIntegrityError::HashMismatch {
+                path,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{} hash {} does not match manifest {} (corrupted or stale asset)",
+                path,
+                &actual[..8],
+                &expected[..8]
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AssetIntegrityError {}
+
+/// Look up the manifest entry for an asset path
+pub fn asset_manifest_entry(path: &str) -> Option<&'static AssetManifestEntry> {
+    ASSET_MANIFEST.iter().find(|entry| entry.path == path)
+}
+
+/// Check downloaded asset bytes against the manifest embedded at build time
+pub fn verify_asset(path: &str, bytes: &[u8]) -> Result<(), AssetIntegrityError> {
+    let entry =
+        asset_manifest_entry(path).ok_or_else(|| AssetIntegrityError::Unknown(path.into()))?;
+
+    if entry.size != bytes.len() {
+        return Err(AssetIntegrityError::SizeMismatch {
+            path: path.into(),
+            expected: entry.size,
+            actual: bytes.len(),
+        });
+    }
+
+    let actual = hex::encode(Sha256::digest(bytes));
+    if actual != entry.sha256 {
+        return Err(AssetIntegrityError::HashMismatch {
+            path: path.into(),
+            expected: entry.sha256.into(),
+            actual,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_matches_embedded_levels() {
+        let json = include_bytes!("../../assets/levels/default.json");
+        assert_eq!(verify_asset("levels/default.json", json), Ok(()));
+        assert!(asset_manifest_entry("vey.glb").is_some());
+    }
+
+    #[test]
+    fn tampered_asset_is_rejected() {
+        let mut json = include_bytes!("../../assets/levels/default.json").to_vec();
+        let last = json.len() - 1;
+        json[last] ^= 0xff;
+        assert!(matches!(
+            verify_asset("levels/default.json", &json),
+            Err(AssetIntegrityError::HashMismatch { .. })
+        ));
+        json.pop();
+        assert!(matches!(
+            verify_asset("levels/default.json", &json),
+            Err(AssetIntegrityError::SizeMismatch { .. })
+        ));
+    }
+}
@@ -0,0 +1,261 @@
+// Player tokens: the lobby service hands every client a short-lived JWT on
+// `POST /lobby/api/auth/guest`, and the room calls that change something need it. The
+// token says who the player is, so the service stops trusting the `player_name` in request
+// bodies. Signing and checking the JWT is the service's part, see "Player tokens" in
+// docs/lobby-service.md. This is what both sides agree on.
+use serde::{Deserialize, Serialize};
+
+use crate::api::{RoomMember, ServerLobbyRoom, ServiceError};
+
+/// Seconds a token is valid
+pub const AUTH_TOKEN_TTL_SECS: u64 = 15 * 60;
+/// Clients ask for a new token this many seconds before theirs expires
+pub const AUTH_REFRESH_MARGIN_SECS: u64 = 2 * 60;
+/// An expired token still proves who asks for a new one for this long, so a tab that
+/// slept through the expiry keeps its player
+pub const AUTH_REFRESH_GRACE_SECS: u64 = 24 * 60 * 60;
+
+/// Body of `POST /lobby/api/auth/guest`. A still valid token in `Authorization` keeps the
+/// player it names, without one the service makes a new account unless `player_id` is an
+/// account no token has claimed yet.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GuestAuthRequest {
+    pub display_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player_id: Option<String>,
+}
+
+/// Answer to `POST /lobby/api/auth/guest`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AuthToken {
+    /// The JWT, sent back as `Authorization: Bearer <token>`
+    pub token: String,
+    /// Account the token is for, a new one for first-time players
+    pub player_id: String,
+    pub display_name: String,
+    /// Unix timestamp in seconds, the token's `exp`
+    pub expires_at: u64,
+}
+
+impl AuthToken {
+    pub fn needs_refresh(&self, now: u64) -> bool {
+        now + AUTH_REFRESH_MARGIN_SECS >= self.expires_at
+    }
+}
+
+/// Claims inside the JWT
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PlayerClaims {
+    /// Player id
+    pub sub: String,
+    /// Display name the player shows in rooms
+    pub name: String,
+    /// Unix timestamps in seconds
+    pub iat: u64,
+    pub exp: u64,
+}
+
+impl PlayerClaims {
+    pub fn new(player_id: &str, display_name: &str, now: u64) -> Self {
+        Self {
+            sub: player_id.to_string(),
+            name: display_name.to_string(),
+            iat: now,
+            exp: now + AUTH_TOKEN_TTL_SECS,
+        }
+    }
+
+    /// Whether the token is good for a call at `now`
+    pub fn check(&self, now: u64) -> Result<(), ServiceError> {
+        if now >= self.exp {
+            return Err(refusal("TOKEN_EXPIRED"));
+        }
+        Ok(())
+    }
+
+    /// Whether `member` is the player the token names
+    pub fn names(&self, member: &RoomMember) -> bool {
+        member.is(&self.name, Some(&self.sub))
+    }
+
+    /// Whether `/auth/guest` takes the token as proof of who asks for a new one
+    pub fn can_refresh(&self, now: u64) -> bool {
+        now < self.exp + AUTH_REFRESH_GRACE_SECS
+    }
+}
+
+/// The token in an `Authorization: Bearer <token>` header
+pub fn bearer_token(authorization: &str) -> Option<&str> {
+    let (scheme, token) = authorization.trim().split_once(' ')?;
+    let token = token.trim();
+    (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then_some(token)
+}
+
+/// Room calls that need a token
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RoomAction<'a> {
+    Join,
+    Leave,
+    Heartbeat,
+    Ready,
+    Chat,
+    Start,
+    /// Taking `member` out of the room
+    Kick {
+        member: &'a RoomMember,
+    },
+    /// Moving `member` to another team
+    ChangeTeam {
+        member: &'a RoomMember,
+    },
+}
+
+/// Whether the player the token names may do `action` in `room`. Joining acts on the
+/// token's own player, leaving, heartbeats, readiness and chat need them in the room, starting
+/// and kicking are up to the host, and only the host moves others. Rooms and members from
+/// before player ids were stored are matched by name.
+pub fn authorize_room_action(
+    claims: &PlayerClaims,
+    room: &ServerLobbyRoom,
+    action: RoomAction,
+) -> Result<(), ServiceError> {
+    let is_host = room.is_host(&claims.name, Some(&claims.sub));
+    let is_member = is_host || room.members.iter().any(|m| claims.names(m));
+    match action {
+        RoomAction::Join => Ok(()),
+        RoomAction::Leave | RoomAction::Heartbeat | RoomAction::Ready | RoomAction::Chat
+            if !is_member =>
+        {
+            Err(refusal("NOT_IN_ROOM"))
+        }
+        RoomAction::Leave | RoomAction::Heartbeat | RoomAction::Ready | RoomAction::Chat => Ok(()),
+        RoomAction::Start if !is_member => Err(refusal("NOT_IN_ROOM")),
+        RoomAction::Start if !is_host => Err(refusal("NOT_ROOM_HOST")),
+        RoomAction::Start => Ok(()),
+        RoomAction::Kick { .. } if !is_host => Err(refusal("NOT_ROOM_HOST")),
+        RoomAction::Kick { member } if claims.names(member) => Err(refusal("CANNOT_KICK_SELF")),
+        RoomAction::Kick { .. } => Ok(()),
+        RoomAction::ChangeTeam { .. } if !is_member => Err(refusal("NOT_IN_ROOM")),
+        RoomAction::ChangeTeam { member } if is_host || claims.names(member) => Ok(()),
+        RoomAction::ChangeTeam { .. } => Err(refusal("NOT_ROOM_HOST")),
+    }
+}
+
+fn refusal(code: &str) -> ServiceError {
+    ServiceError {
+        error: code.to_string(),
+        message: None,
+        retry_after_secs: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::BotStyle;
+
+    #[test]
+    fn tokens_decide_who_may_do_what() {
+        let host = PlayerClaims::new("p1", "Nova", 1_000);
+        let guest = PlayerClaims::new("p2", "Vex", 1_000);
+        let stranger = PlayerClaims::new("p3", "Io", 1_000);
+        let room = ServerLobbyRoom {
+            id: "r".to_string(),
+            code: "K7QX2M".to_string(),
+            host_name: "Nova".to_string(),
+            host_id: Some("p1".to_string()),
+            game_mode: "casual".to_string(),
+            created_at: 0,
+            started: false,
+            current_players: 2,
+            max_players: 4,
+            level: String::new(),
+            has_password: false,
+            map: String::new(),
+            region: None,
+            teams: 2,
+            friendly_fire: false,
+            members: [("Nova", "p1"), ("Vex", "p2")]
+                .map(|(name, id)| RoomMember {
+                    name: name.to_string(),
+                    player_id: Some(id.to_string()),
+                    team: None,
+                    ready: true,
+                    joined_at: 0,
+                })
+                .to_vec(),
+            publish_results: false,
+            last_match: None,
+            rules: None,
+            bot_backfill: false,
+            bot_style: BotStyle::default(),
+        };
+
+        assert!(authorize_room_action(&host, &room, RoomAction::Start).is_ok());
+        let error = authorize_room_action(&guest, &room, RoomAction::Start).unwrap_err();
+        assert_eq!(error.error, "NOT_ROOM_HOST");
+        assert!(authorize_room_action(&stranger, &room, RoomAction::Join).is_ok());
+        assert!(authorize_room_action(&stranger, &room, RoomAction::Heartbeat).is_err());
+        assert!(authorize_room_action(&guest, &room, RoomAction::Leave).is_ok());
+        assert!(authorize_room_action(&guest, &room, RoomAction::Ready).is_ok());
+        assert!(authorize_room_action(&stranger, &room, RoomAction::Ready).is_err());
+        assert!(authorize_room_action(&guest, &room, RoomAction::Chat).is_ok());
+        assert!(authorize_room_action(&stranger, &room, RoomAction::Chat).is_err());
+        let error = authorize_room_action(&stranger, &room, RoomAction::Leave).unwrap_err();
+        assert_eq!(error.error, "NOT_IN_ROOM");
+        let error = authorize_room_action(&stranger, &room, RoomAction::Start).unwrap_err();
+        assert_eq!(error.error, "NOT_IN_ROOM");
+        // Same name, other player: the id decides
+        let impostor = PlayerClaims::new("p9", "Nova", 1_000);
+        assert!(authorize_room_action(&impostor, &room, RoomAction::Start).is_err());
+
+        let kick_vex = RoomAction::Kick {
+            member: &room.members[1],
+        };
+        assert!(authorize_room_action(&host, &room, kick_vex).is_ok());
+        let error = authorize_room_action(&guest, &room, kick_vex).unwrap_err();
+        assert_eq!(error.error, "NOT_ROOM_HOST");
+        let kick_nova = RoomAction::Kick {
+            member: &room.members[0],
+        };
+        let error = authorize_room_action(&host, &room, kick_nova).unwrap_err();
+        assert_eq!(error.error, "CANNOT_KICK_SELF");
+
+        let move_vex = RoomAction::ChangeTeam {
+            member: &room.members[1],
+        };
+        assert!(authorize_room_action(&host, &room, move_vex).is_ok());
+        assert!(authorize_room_action(&guest, &room, move_vex).is_ok());
+        let move_nova = RoomAction::ChangeTeam {
+            member: &room.members[0],
+        };
+        assert!(authorize_room_action(&guest, &room, move_nova).is_err());
+        // A member named like Vex is not Vex: moving Vex is up to Vex and the host
+        let vex_lookalike = RoomMember {
+            player_id: Some("p4".to_string()),
+            ..room.members[1].clone()
+        };
+        let mut crowded = room.clone();
+        crowded.members.push(vex_lookalike.clone());
+        let move_lookalike = RoomAction::ChangeTeam {
+            member: &vex_lookalike,
+        };
+        let error = authorize_room_action(&guest, &crowded, move_lookalike).unwrap_err();
+        assert_eq!(error.error, "NOT_ROOM_HOST");
+
+        assert!(host.check(1_000 + AUTH_TOKEN_TTL_SECS - 1).is_ok());
+        assert_eq!(
+            host.check(1_000 + AUTH_TOKEN_TTL_SECS).unwrap_err().error,
+            "TOKEN_EXPIRED"
+        );
+        assert!(host.can_refresh(host.exp + AUTH_REFRESH_GRACE_SECS - 1));
+        assert!(!host.can_refresh(host.exp + AUTH_REFRESH_GRACE_SECS));
+
+        assert_eq!(bearer_token("Bearer abc.def.ghi"), Some("abc.def.ghi"));
+        assert_eq!(bearer_token("bearer  abc "), Some("abc"));
+        assert_eq!(bearer_token("Basic abc"), None);
+        assert_eq!(bearer_token("Bearer"), None);
+    }
+}
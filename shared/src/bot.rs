@@ -0,0 +1,405 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Rating assumed when the matchmaker has nothing for the players in a match
+pub const DEFAULT_SKILL_RATING: f32 = 1200.0;
+// Ratings at which bots stop getting any easier / harder
+const EASIEST_RATING: f32 = 800.0;
+const HARDEST_RATING: f32 = 2000.0;
+
+// How well a server-controlled bot plays
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct BotDifficulty {
+    /// Seconds between a bot noticing something and acting on it
+    pub reaction_time: f32,
+    /// Chance (0..1) that a bot actually jumps when it means to
+    pub jump_accuracy: f32,
+}
+
+impl BotDifficulty {
+    pub const EASY: Self = Self {
+        reaction_time: 0.6,
+        jump_accuracy: 0.5,
+    };
+    pub const HARD: Self = Self {
+        reaction_time: 0.12,
+        jump_accuracy: 0.95,
+    };
+
+    /// Bots that roughly match a player of the given skill rating
+    pub fn from_rating(rating: f32) -> Self {
+        let t = ((rating - EASIEST_RATING) / (HARDEST_RATING - EASIEST_RATING)).clamp(0.0, 1.0);
+        Self {
+            reaction_time: Self::EASY.reaction_time.lerp(Self::HARD.reaction_time, t),
+            jump_accuracy: Self::EASY.jump_accuracy.lerp(Self::HARD.jump_accuracy, t),
+        }
+    }
+
+    /// Bots that match the average of the humans in a match
+    pub fn from_ratings(ratings: &[f32]) -> Self {
+        if ratings.is_empty() {
+            return Self::default();
+        }
+        Self::from_rating(ratings.iter().sum::<f32>() / ratings.len() as f32)
+    }
+}
+
+impl Default for BotDifficulty {
+    fn default() -> Self {
+        Self::from_rating(DEFAULT_SKILL_RATING)
+    }
+}
+
+// How bots play in a room, picked by its host. The id travels in `bot_style` on the lobby
+// API and the matchmaker query string.
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum BotStyle {
+    /// Wander the level, grab pickups close by and keep out of everyone's way
+    Passive,
+    /// Go for whatever the mode is about
+    #[default]
+    Normal,
+    /// Hunt down players close by and stomp them, then go for the objective
+    Aggressive,
+}
+
+// A player this close is worth hunting down / getting away from
+const HUNT_RANGE: f32 = 250.0;
+const FLEE_RANGE: f32 = 120.0;
+// Passive bots only walk over to pickups this close
+const PICKUP_RANGE: f32 = 200.0;
+// Close enough to the goal to stop walking
+const ARRIVE_DISTANCE: f32 = 10.0;
+// A goal this far above the bot is worth jumping for
+const JUMP_THRESHOLD: f32 = 40.0;
+// Hunting bots jump for a stomp once this close sideways
+const POUNCE_DISTANCE: f32 = 60.0;
+
+impl BotStyle {
+    pub const ALL: &'static [BotStyle] =
+        &[BotStyle::Passive, BotStyle::Normal, BotStyle::Aggressive];
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            BotStyle::Passive => "passive",
+            BotStyle::Normal => "normal",
+            BotStyle::Aggressive => "aggressive",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|style| style.id() == id)
+    }
+
+    /// The style after this one, wrapping around
+    pub fn next(&self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|style| style == self)
+            .unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Behavior tree bots of this style think with. Every tree picks where to go first,
+    /// then jumps any gap on the way.
+    pub fn tree(&self) -> BotNode {
+        use BotNode::*;
+        let movement = match self {
+            BotStyle::Passive => Selector(vec![
+                Sequence(vec![
+                    Check(BotCheck::RivalWithin(FLEE_RANGE)),
+                    Act(BotAct::Flee),
+                ]),
+                Sequence(vec![
+                    Check(BotCheck::PickupWithin(PICKUP_RANGE)),
+                    Act(BotAct::Seek(BotGoal::Pickup)),
+                ]),
+                Act(BotAct::Patrol),
+            ]),
+            BotStyle::Normal => Selector(vec![
+                Act(BotAct::Seek(BotGoal::Objective)),
+                Act(BotAct::Seek(BotGoal::Pickup)),
+                Act(BotAct::Patrol),
+            ]),
+            BotStyle::Aggressive => Selector(vec![
+                Sequence(vec![
+                    Check(BotCheck::RivalWithin(HUNT_RANGE)),
+                    Act(BotAct::Pounce),
+                ]),
+                Act(BotAct::Seek(BotGoal::Objective)),
+                Act(BotAct::Seek(BotGoal::Pickup)),
+                Act(BotAct::Patrol),
+            ]),
+        };
+        Sequence(vec![
+            movement,
+            Optional(Box::new(Sequence(vec![
+                Check(BotCheck::GapAhead),
+                Act(BotAct::Jump),
+            ]))),
+        ])
+    }
+}
+
+impl fmt::Display for BotStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+impl FromStr for BotStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_id(s).ok_or_else(|| {
+            let known: Vec<_> = Self::ALL.iter().map(|s| s.id()).collect();
+            format!(
+                "unknown bot style '{}' (expected one of {})",
+                s,
+                known.join(", ")
+            )
+        })
+    }
+}
+
+/// What a bot knows when it reacts, gathered by the server
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BotSenses {
+    pub position: Vec2,
+    pub grounded: bool,
+    /// No ground a step to the left / right, see `ground_ahead`
+    pub gap_left: bool,
+    pub gap_right: bool,
+    /// What the mode is about: the next checkpoint or the live scoring zone
+    pub objective: Option<Vec2>,
+    pub pickup: Option<Vec2>,
+    /// The nearest other player
+    pub rival: Option<Vec2>,
+    /// Way the bot walks while patrolling, already turned around at the level's edges
+    pub patrol: f32,
+}
+
+impl BotSenses {
+    fn goal(&self, goal: BotGoal) -> Option<Vec2> {
+        match goal {
+            BotGoal::Objective => self.objective,
+            BotGoal::Pickup => self.pickup,
+            BotGoal::Rival => self.rival,
+        }
+    }
+
+    fn within(&self, target: Option<Vec2>, range: f32) -> bool {
+        target.is_some_and(|target| target.distance(self.position) <= range)
+    }
+}
+
+/// What a bot decided: which way to walk and whether to jump
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BotIntent {
+    /// -1 left, 0 stand still, 1 right
+    pub direction: f32,
+    pub jump: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BotGoal {
+    Objective,
+    Pickup,
+    Rival,
+}
+
+/// Conditions, they succeed when they hold
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BotCheck {
+    /// Standing at a gap in the way the bot decided to walk
+    GapAhead,
+    PickupWithin(f32),
+    RivalWithin(f32),
+}
+
+/// Actions, they fail when what they need isn't there
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BotAct {
+    /// Walk to the goal, jumping when it is above
+    Seek(BotGoal),
+    /// Walk at the nearest player and jump to land on them
+    Pounce,
+    /// Walk away from the nearest player
+    Flee,
+    Patrol,
+    Jump,
+}
+
+/// A small behavior tree, ticked once per reaction
+#[derive(Clone, Debug, PartialEq)]
+pub enum BotNode {
+    /// Runs its children in order until one fails
+    Sequence(Vec<BotNode>),
+    /// Runs its children in order until one succeeds
+    Selector(Vec<BotNode>),
+    /// Runs its child and succeeds whatever it did
+    Optional(Box<BotNode>),
+    Check(BotCheck),
+    Act(BotAct),
+}
+
+impl BotNode {
+    /// Decide what to do, starting from standing still
+    pub fn decide(&self, senses: &BotSenses) -> BotIntent {
+        let mut intent = BotIntent::default();
+        self.tick(senses, &mut intent);
+        intent
+    }
+
+    /// Run the node, writing its actions into `intent`. True if it succeeded.
+    pub fn tick(&self, senses: &BotSenses, intent: &mut BotIntent) -> bool {
+        match self {
+            BotNode::Sequence(children) => children.iter().all(|child| child.tick(senses, intent)),
+            BotNode::Selector(children) => children.iter().any(|child| child.tick(senses, intent)),
+            BotNode::Optional(child) => {
+                child.tick(senses, intent);
+                true
+            }
+            BotNode::Check(check) => match *check {
+                BotCheck::GapAhead => {
+                    senses.grounded
+                        && ((intent.direction < 0.0 && senses.gap_left)
+                            || (intent.direction > 0.0 && senses.gap_right))
+                }
+                BotCheck::PickupWithin(range) => senses.within(senses.pickup, range),
+                BotCheck::RivalWithin(range) => senses.within(senses.rival, range),
+            },
+            BotNode::Act(act) => match *act {
+                BotAct::Seek(goal) => {
+                    let Some(target) = senses.goal(goal) else {
+                        return false;
+                    };
+                    let offset = target - senses.position;
+                    intent.direction = if offset.x.abs() < ARRIVE_DISTANCE {
+                        0.0
+                    } else {
+                        offset.x.signum()
+                    };
+                    intent.jump |= offset.y > JUMP_THRESHOLD;
+                    true
+                }
+                BotAct::Pounce => {
+                    let Some(rival) = senses.rival else {
+                        return false;
+                    };
+                    let offset = rival - senses.position;
+                    intent.direction = offset.x.signum();
+                    intent.jump |= senses.grounded && offset.x.abs() < POUNCE_DISTANCE;
+                    true
+                }
+                BotAct::Flee => {
+                    let Some(rival) = senses.rival else {
+                        return false;
+                    };
+                    // Right on top of each other, any way out will do
+                    let away = senses.position.x - rival.x;
+                    intent.direction = if away == 0.0 {
+                        senses.patrol
+                    } else {
+                        away.signum()
+                    };
+                    true
+                }
+                BotAct::Patrol => {
+                    intent.direction = senses.patrol;
+                    true
+                }
+                BotAct::Jump => {
+                    intent.jump = true;
+                    true
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn better_players_get_harder_bots() {
+        let casual = BotDifficulty::from_rating(1000.0);
+        let pro = BotDifficulty::from_rating(1800.0);
+        assert!(pro.reaction_time < casual.reaction_time);
+        assert!(pro.jump_accuracy > casual.jump_accuracy);
+
+        assert_eq!(BotDifficulty::from_rating(0.0), BotDifficulty::EASY);
+        assert_eq!(
+            BotDifficulty::from_rating(5000.0),
+            BotDifficulty::from_rating(HARDEST_RATING)
+        );
+        assert_eq!(BotDifficulty::from_ratings(&[]), BotDifficulty::default());
+        assert_eq!(
+            BotDifficulty::from_ratings(&[1000.0, 1400.0]),
+            BotDifficulty::from_rating(1200.0)
+        );
+    }
+
+    #[test]
+    fn styles_pick_different_moves() {
+        let senses = BotSenses {
+            position: Vec2::ZERO,
+            grounded: true,
+            objective: Some(Vec2::new(-500.0, 0.0)),
+            pickup: Some(Vec2::new(150.0, 0.0)),
+            rival: Some(Vec2::new(40.0, 0.0)),
+            patrol: 1.0,
+            ..default()
+        };
+        // Normal bots ignore the rival and go for the objective
+        assert_eq!(
+            BotStyle::Normal.tree().decide(&senses),
+            BotIntent {
+                direction: -1.0,
+                jump: false
+            }
+        );
+        // Aggressive bots jump at the rival right next to them
+        assert_eq!(
+            BotStyle::Aggressive.tree().decide(&senses),
+            BotIntent {
+                direction: 1.0,
+                jump: true
+            }
+        );
+        // Passive bots back off from it
+        assert_eq!(BotStyle::Passive.tree().decide(&senses).direction, -1.0);
+
+        // With nobody around a passive bot heads for the pickup close by, and jumps the
+        // gap on the way
+        let alone = BotSenses {
+            rival: None,
+            gap_right: true,
+            ..senses
+        };
+        assert_eq!(
+            BotStyle::Passive.tree().decide(&alone),
+            BotIntent {
+                direction: 1.0,
+                jump: true
+            }
+        );
+        // Nothing to go for at all: patrol
+        let empty = BotSenses {
+            position: Vec2::ZERO,
+            grounded: true,
+            patrol: -1.0,
+            ..default()
+        };
+        for style in BotStyle::ALL {
+            assert_eq!(style.tree().decide(&empty).direction, -1.0);
+            assert_eq!(style.id().parse::<BotStyle>(), Ok(*style));
+        }
+        assert!("berserk".parse::<BotStyle>().is_err());
+    }
+}
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+use crate::level::LevelDefinition;
+
+// Ordered single-player level list, compiled in like the built-in levels
+const BUILTIN_CAMPAIGN: &str = include_str!("../../assets/campaign.json");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Campaign {
+    pub name: String,
+    /// Level names in play order, each must be a built-in level
+    pub levels: Vec<String>,
+}
+
+impl Default for Campaign {
+    fn default() -> Self {
+        serde_json::from_str(BUILTIN_CAMPAIGN).expect("built-in campaign must parse")
+    }
+}
+
+impl Campaign {
+    /// Level definition for the campaign stage at `index`
+    pub fn level(&self, index: usize) -> Option<LevelDefinition> {
+        self.levels
+            .get(index)
+            .and_then(|name| LevelDefinition::builtin(name))
+    }
+
+    pub fn is_last(&self, index: usize) -> bool {
+        index + 1 >= self.levels.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn campaign_levels_are_playable() {
+        let campaign = Campaign::default();
+        assert!(!campaign.levels.is_empty());
+        for index in 0..campaign.levels.len() {
+            let level = campaign
+                .level(index)
+                .expect("campaign level must be built in");
+            assert!(level.goal.is_some(), "{} has no goal", level.name);
+        }
+    }
+}
@@ -0,0 +1,178 @@
+// Room chat: players in a lobby room talk before the match starts. The lobby service keeps
+// the last messages of each room and hands new ones out on a long poll, which works through
+// every proxy a WebSocket might not. See "Room chat" in docs/lobby-service.md.
+use serde::{Deserialize, Serialize};
+
+use crate::api::ServiceError;
+
+/// Messages a room keeps, and the room screen shows
+pub const CHAT_HISTORY_LEN: usize = 50;
+/// Longest message in characters
+pub const MAX_CHAT_MESSAGE_LEN: usize = 200;
+/// Seconds the service holds a chat poll open when nothing new was said
+pub const CHAT_POLL_SECS: u64 = 25;
+/// Messages one player may send per `CHAT_WINDOW_SECS`
+pub const CHAT_MESSAGES_PER_WINDOW: usize = 5;
+pub const CHAT_WINDOW_SECS: u64 = 10;
+
+/// One message in a room's chat
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ChatMessage {
+    /// Counts up per room from 1, polls ask for what came after the last one they saw
+    pub seq: u64,
+    pub player_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player_id: Option<String>,
+    pub text: String,
+    /// Unix timestamp in seconds
+    pub sent_at: u64,
+}
+
+/// Body of `POST /lobby/api/rooms/{id}/chat`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ChatRequest {
+    pub player_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player_id: Option<String>,
+    pub text: String,
+}
+
+/// Query of `GET /lobby/api/rooms/{id}/chat`
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(
+    feature = "openapi",
+    derive(utoipa::IntoParams),
+    into_params(parameter_in = Query)
+)]
+pub struct ChatQuery {
+    /// `seq` of the last message the client has, 0 for the whole history
+    #[serde(default)]
+    pub after: u64,
+}
+
+/// A message as it is stored and shown: whitespace collapsed, control characters dropped
+/// and cut at `MAX_CHAT_MESSAGE_LEN`. None if nothing is left.
+pub fn sanitize_chat_message(text: &str) -> Option<String> {
+    let text: String = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_CHAT_MESSAGE_LEN)
+        .collect();
+    (!text.is_empty()).then_some(text)
+}
+
+/// The chat of one room, as the lobby service keeps it
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ChatLog {
+    /// The last `CHAT_HISTORY_LEN` messages, oldest first
+    pub messages: Vec<ChatMessage>,
+    pub last_seq: u64,
+}
+
+impl ChatLog {
+    /// Add a message from a member of the room. Refused with 400 `EMPTY_MESSAGE` when
+    /// sanitizing leaves nothing, and 429 `RATE_LIMITED` for a player who already sent
+    /// `CHAT_MESSAGES_PER_WINDOW` in the last `CHAT_WINDOW_SECS`.
+    pub fn post(&mut self, request: &ChatRequest, now: u64) -> Result<ChatMessage, ServiceError> {
+        let Some(text) = sanitize_chat_message(&request.text) else {
+            return Err(ServiceError {
+                error: "EMPTY_MESSAGE".to_string(),
+                message: None,
+                retry_after_secs: None,
+            });
+        };
+        let recent: Vec<u64> = self
+            .messages
+            .iter()
+            .filter(|message| {
+                message.player_name == request.player_name
+                    && message.player_id == request.player_id
+                    && message.sent_at + CHAT_WINDOW_SECS > now
+            })
+            .map(|message| message.sent_at)
+            .collect();
+        if recent.len() >= CHAT_MESSAGES_PER_WINDOW {
+            return Err(ServiceError {
+                error: "RATE_LIMITED".to_string(),
+                message: Some("too many chat messages".to_string()),
+                retry_after_secs: Some(recent[0] + CHAT_WINDOW_SECS - now),
+            });
+        }
+        self.last_seq += 1;
+        let message = ChatMessage {
+            seq: self.last_seq,
+            player_name: request.player_name.clone(),
+            player_id: request.player_id.clone(),
+            text,
+            sent_at: now,
+        };
+        self.messages.push(message.clone());
+        if self.messages.len() > CHAT_HISTORY_LEN {
+            self.messages.remove(0);
+        }
+        Ok(message)
+    }
+
+    /// Messages after `seq`, what a poll with `after` answers once there are any
+    pub fn after(&self, seq: u64) -> &[ChatMessage] {
+        let start = self.messages.partition_point(|message| message.seq <= seq);
+        &self.messages[start..]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rooms_keep_their_last_messages() {
+        let request = |name: &str, text: &str| ChatRequest {
+            player_name: name.to_string(),
+            player_id: None,
+            text: text.to_string(),
+        };
+        assert_eq!(
+            sanitize_chat_message("  gl\u{7}  hf\n "),
+            Some("gl hf".to_string())
+        );
+        assert_eq!(sanitize_chat_message(" \n\t"), None);
+        assert_eq!(
+            sanitize_chat_message(&"a".repeat(500)).unwrap().len(),
+            MAX_CHAT_MESSAGE_LEN
+        );
+
+        let mut log = ChatLog::default();
+        for i in 0..CHAT_MESSAGES_PER_WINDOW {
+            log.post(&request("Nova", &format!("hi {}", i)), 100)
+                .unwrap();
+        }
+        let error = log.post(&request("Nova", "one more"), 105).unwrap_err();
+        assert_eq!(error.error, "RATE_LIMITED");
+        assert_eq!(error.retry_after_secs, Some(5));
+        // Others can still talk, and Nova again once the window moved on
+        log.post(&request("Vex", "hey"), 105).unwrap();
+        log.post(&request("Nova", "one more"), 110).unwrap();
+        assert_eq!(
+            log.post(&request("Vex", " "), 110).unwrap_err().error,
+            "EMPTY_MESSAGE"
+        );
+
+        assert_eq!(log.after(0).len(), 7);
+        assert_eq!(log.after(5).len(), 2);
+        assert_eq!(log.after(5)[0].text, "hey");
+        assert!(log.after(7).is_empty());
+
+        for i in 0..CHAT_HISTORY_LEN as u64 {
+            log.post(&request("Io", "spam"), 1_000 + i * CHAT_WINDOW_SECS)
+                .unwrap();
+        }
+        assert_eq!(log.messages.len(), CHAT_HISTORY_LEN);
+        assert_eq!(log.after(0)[0].seq, 8);
+        assert_eq!(log.last_seq, 57);
+    }
+}
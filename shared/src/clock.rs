@@ -0,0 +1,104 @@
+use std::collections::VecDeque;
+
+/// Fixed simulation ticks per second, on the server and on every client
+pub const TICK_RATE_HZ: f64 = 60.0;
+/// Seconds between two `ClockReport`s from a client
+pub const CLOCK_REPORT_SECS: f32 = 1.0;
+/// A client never runs its fixed step more than this much faster or slower than normal
+pub const MAX_TICK_RATE_ADJUST: f64 = 0.05;
+
+// Seconds of reports needed before a client's rate is trusted. Report arrival jitters by
+// a few tens of milliseconds, over this window that is well under the deadband.
+const MIN_WINDOW_SECS: f64 = 10.0;
+// Reports older than this are dropped, so the estimate follows a device that changes
+// speed (battery saver switching on)
+const MAX_WINDOW_SECS: f64 = 30.0;
+// Rates this close to 1 are left alone
+const DEADBAND: f64 = 0.02;
+
+/// Estimates how fast a client's simulation runs compared to the server's, from the
+/// fixed tick counts the client reports and the server time they arrive at
+#[derive(Default, Clone, Debug)]
+pub struct ClockSkew {
+    // (server seconds, client fixed ticks), oldest first
+    samples: VecDeque<(f64, u32)>,
+}
+
+impl ClockSkew {
+    pub fn observe(&mut self, server_secs: f64, client_ticks: u32) {
+        // Tick counts only go up, a lower one means the client started over
+        if self
+            .samples
+            .back()
+            .is_some_and(|&(_, ticks)| client_ticks < ticks)
+        {
+            self.samples.clear();
+        }
+        self.samples.push_back((server_secs, client_ticks));
+        while self
+            .samples
+            .front()
+            .is_some_and(|&(secs, _)| server_secs - secs > MAX_WINDOW_SECS)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Client ticks per server tick, once there is enough history: above 1 the client
+    /// runs fast, below 1 it runs slow
+    pub fn rate(&self) -> Option<f64> {
+        let (first_secs, first_ticks) = *self.samples.front()?;
+        let (last_secs, last_ticks) = *self.samples.back()?;
+        let elapsed = last_secs - first_secs;
+        if elapsed < MIN_WINDOW_SECS {
+            return None;
+        }
+        Some((last_ticks - first_ticks) as f64 / (elapsed * TICK_RATE_HZ))
+    }
+
+    /// The factor the client should multiply its tick rate by, if it is off by more than
+    /// the deadband. Forget the history after sending one, it describes the old speed.
+    pub fn hint(&self) -> Option<f32> {
+        let rate = self.rate()?;
+        if rate <= 0.0 || (rate - 1.0).abs() < DEADBAND {
+            return None;
+        }
+        Some((1.0 / rate).clamp(1.0 - MAX_TICK_RATE_ADJUST, 1.0 + MAX_TICK_RATE_ADJUST) as f32)
+    }
+
+    pub fn reset(&mut self) {
+        self.samples.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reports(rate: f64, secs: u32) -> ClockSkew {
+        let mut skew = ClockSkew::default();
+        for second in 0..=secs {
+            let ticks = (second as f64 * TICK_RATE_HZ * rate) as u32;
+            skew.observe(second as f64, ticks);
+        }
+        skew
+    }
+
+    #[test]
+    fn skewed_clients_get_hints() {
+        // Not enough history yet
+        assert_eq!(reports(1.1, 5).hint(), None);
+        // Close enough to the server
+        assert_eq!(reports(1.01, 20).hint(), None);
+
+        let slow = reports(0.97, 20).hint().unwrap();
+        assert!(slow > 1.0 && slow < 1.05);
+        // Way off is capped
+        assert_eq!(reports(1.5, 20).hint(), Some(0.95));
+
+        // A client that reconnected starts a fresh window
+        let mut skew = reports(1.1, 20);
+        skew.observe(21.0, 60);
+        assert_eq!(skew.rate(), None);
+    }
+}
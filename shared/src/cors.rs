@@ -0,0 +1,212 @@
+// CORS for the lobby service: which origins may call it from a browser, and which methods
+// each route takes. The services used to allow any origin. They now read an allow-list,
+// check each request against it and answer preflights from `CorsPolicy::preflight`, see
+// "Allowed origins" in docs/lobby-service.md. Dev mode allows every origin again.
+
+use crate::api::TRACE_HEADER;
+
+/// Seconds browsers may cache a preflight answer
+pub const DEFAULT_PREFLIGHT_MAX_AGE_SECS: u64 = 600;
+
+/// Request headers the client sends besides the simple ones
+pub const ALLOWED_HEADERS: &[&str] = &[
+    "Content-Type",
+    "Authorization",
+    TRACE_HEADER,
+    "If-None-Match",
+];
+
+/// Response headers scripts may read
+pub const EXPOSED_HEADERS: &[&str] = &[TRACE_HEADER, "ETag", "Retry-After"];
+
+/// Methods of every lobby service route, by route template relative to the path prefix
+/// (`/lobby/api`, or `/api` for the local server). `{..}` matches one path segment.
+pub const ROUTE_METHODS: &[(&str, &[&str])] = &[
+    ("/auth/guest", &["POST"]),
+    ("/rooms", &["GET", "POST"]),
+    ("/rooms/{id}", &["GET"]),
+    ("/rooms/{id}/join", &["POST"]),
+    ("/rooms/{id}/leave", &["POST"]),
+    ("/rooms/{id}/start", &["POST"]),
+    ("/rooms/{id}/heartbeat", &["POST"]),
+    ("/rooms/{id}/ready", &["POST"]),
+    ("/rooms/{id}/kick", &["POST"]),
+    ("/rooms/{id}/chat", &["GET", "POST"]),
+    ("/rooms/{id}/team", &["POST"]),
+    ("/rooms/{id}/finish", &["POST"]),
+    ("/rooms/{id}/voice", &["GET"]),
+    ("/matches", &["GET"]),
+    ("/status", &["GET"]),
+    ("/regions", &["GET"]),
+    ("/players", &["POST"]),
+    ("/players/{id}", &["PUT"]),
+    ("/players/{id}/rating", &["GET"]),
+    ("/players/{id}/presence", &["POST"]),
+    ("/players/{id}/friends", &["POST"]),
+    ("/players/{id}/friends/{friend_id}", &["DELETE"]),
+    ("/players/{id}/friends/{friend_id}/invite", &["POST"]),
+    ("/leaderboard", &["GET"]),
+    ("/ranked", &["POST"]),
+    ("/parties", &["POST"]),
+    ("/parties/join", &["POST"]),
+    ("/parties/invites", &["GET"]),
+    ("/parties/{id}/invite", &["POST"]),
+    ("/parties/{id}/queue", &["POST"]),
+    ("/parties/{id}/leave", &["POST"]),
+    ("/parties/{id}/heartbeat", &["POST"]),
+    ("/servers/heartbeat", &["POST"]),
+    ("/servers/anticheat", &["POST"]),
+    ("/telemetry/hardware", &["POST"]),
+    ("/telemetry/presets", &["GET"]),
+    ("/admin/rooms", &["GET"]),
+    ("/admin/audit", &["GET"]),
+    ("/admin/telemetry/hardware", &["GET"]),
+    ("/openapi.json", &["GET"]),
+];
+
+/// Methods `path` takes, None for paths no route serves
+pub fn route_methods(path: &str) -> Option<&'static [&'static str]> {
+    let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+    ROUTE_METHODS
+        .iter()
+        .find(|(template, _)| {
+            let template: Vec<&str> = template.split('/').collect();
+            template.len() == segments.len()
+                && template
+                    .iter()
+                    .zip(&segments)
+                    .all(|(t, s)| t.starts_with('{') || t == s)
+        })
+        .map(|(_, methods)| *methods)
+}
+
+/// The origin an allow-list entry or `Origin` header names, as browsers send it:
+/// `scheme://host[:port]` in lowercase, without a path. None if it isn't one.
+pub fn normalize_origin(origin: &str) -> Option<String> {
+    let origin = origin.trim().trim_end_matches('/').to_lowercase();
+    let (scheme, host) = origin.split_once("://")?;
+    if !matches!(scheme, "http" | "https") || host.is_empty() || host.contains(['/', '?', '#']) {
+        return None;
+    }
+    Some(origin)
+}
+
+/// What a browser origin may do with the service
+#[derive(Clone, Debug, PartialEq)]
+pub struct CorsPolicy {
+    /// Normalized origins, see `normalize_origin`
+    pub origins: Vec<String>,
+    /// Dev mode: any origin, for local clients on changing ports
+    pub permissive: bool,
+    pub max_age_secs: u64,
+}
+
+impl CorsPolicy {
+    /// Policy from `--allowed-origins`, a comma or space separated list. `*` is refused,
+    /// dev mode is its own flag so it can't slip into production with the list.
+    pub fn from_list(list: &str, permissive: bool) -> Result<Self, String> {
+        let mut origins = Vec::new();
+        for entry in list
+            .split([',', ' '])
+            .filter(|entry| !entry.trim().is_empty())
+        {
+            let origin = normalize_origin(entry)
+                .ok_or_else(|| format!("{:?} is not an origin like https://example.com", entry))?;
+            if !origins.contains(&origin) {
+                origins.push(origin);
+            }
+        }
+        if origins.is_empty() && !permissive {
+            return Err("no allowed origins, pass some or turn on dev mode".to_string());
+        }
+        Ok(Self {
+            origins,
+            permissive,
+            max_age_secs: DEFAULT_PREFLIGHT_MAX_AGE_SECS,
+        })
+    }
+
+    pub fn allows(&self, origin: &str) -> bool {
+        self.permissive
+            || normalize_origin(origin).is_some_and(|origin| self.origins.contains(&origin))
+    }
+
+    /// Headers for a response to `origin`, empty for origins that aren't allowed. The
+    /// origin is echoed rather than `*`, so the answer varies with it.
+    pub fn response_headers(&self, origin: &str) -> Vec<(&'static str, String)> {
+        if !self.allows(origin) {
+            return Vec::new();
+        }
+        vec![
+            ("Access-Control-Allow-Origin", origin.to_string()),
+            ("Access-Control-Expose-Headers", EXPOSED_HEADERS.join(", ")),
+            ("Vary", "Origin".to_string()),
+        ]
+    }
+
+    /// Answer to an `OPTIONS` preflight for `path`: the headers of a 204, or the status to
+    /// refuse it with. 403 for an origin that isn't allowed, 404 for unknown paths and 405
+    /// for a method the route doesn't take.
+    pub fn preflight(
+        &self,
+        origin: &str,
+        path: &str,
+        method: &str,
+    ) -> Result<Vec<(&'static str, String)>, u16> {
+        if !self.allows(origin) {
+            return Err(403);
+        }
+        let methods = route_methods(path).ok_or(404u16)?;
+        if !methods
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(method))
+        {
+            return Err(405);
+        }
+        let mut headers = self.response_headers(origin);
+        headers.push(("Access-Control-Allow-Methods", methods.join(", ")));
+        headers.push(("Access-Control-Allow-Headers", ALLOWED_HEADERS.join(", ")));
+        headers.push(("Access-Control-Max-Age", self.max_age_secs.to_string()));
+        Ok(headers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_listed_origins_get_cors_headers() {
+        let policy =
+            CorsPolicy::from_list("https://voidloop.quest/, http://localhost:8080", false).unwrap();
+        assert!(policy.allows("https://Voidloop.quest"));
+        assert!(policy.allows("http://localhost:8080"));
+        assert!(!policy.allows("http://localhost:8081"));
+        assert!(!policy.allows("https://voidloop.quest.evil.example"));
+        assert!(policy.response_headers("https://evil.example").is_empty());
+
+        assert!(CorsPolicy::from_list("*", false).is_err());
+        assert!(CorsPolicy::from_list("", false).is_err());
+        let dev = CorsPolicy::from_list("", true).unwrap();
+        assert!(dev.allows("http://127.0.0.1:5173"));
+
+        let headers = policy
+            .preflight("https://voidloop.quest", "/rooms/K7QX2M/join", "post")
+            .unwrap();
+        assert!(headers.contains(&("Access-Control-Allow-Methods", "POST".to_string())));
+        assert!(headers.contains(&("Access-Control-Max-Age", "600".to_string())));
+        assert_eq!(
+            policy.preflight("https://voidloop.quest", "/rooms/K7QX2M/join", "DELETE"),
+            Err(405)
+        );
+        assert_eq!(
+            policy.preflight("https://voidloop.quest", "/nowhere", "GET"),
+            Err(404)
+        );
+        assert_eq!(
+            policy.preflight("https://evil.example", "/rooms", "GET"),
+            Err(403)
+        );
+        assert_eq!(route_methods("/parties/invites/"), Some(&["GET"][..]));
+    }
+}
@@ -0,0 +1,83 @@
+// Match countdown: nobody moves before the match's go tick, so players who connected a
+// moment apart still start together. The server picks a tick for each room once the room
+// has its players and tells that room's clients, and movement checks it on both sides so
+// prediction agrees with the server.
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::clock::TICK_RATE_HZ;
+use crate::simulation::{SimTick, FIXED_DT};
+
+/// Seconds counted down before a match unless the server is told otherwise
+pub const DEFAULT_COUNTDOWN_SECS: u32 = 3;
+/// Seconds "GO!" stays on screen after the countdown
+pub const GO_SECS: f32 = 1.0;
+
+/// Server -> client, and a resource on both: players can't move before `go_tick`. On the
+/// server every player also carries their room's as a component, rooms count down apart.
+#[derive(
+    Resource, Component, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq,
+)]
+pub struct Countdown {
+    /// `SimTick` players get control at, 0 for no countdown
+    pub go_tick: u32,
+}
+
+impl Countdown {
+    /// Counts `secs` down from `tick`
+    pub fn starting_at(tick: SimTick, secs: u32) -> Self {
+        Self {
+            go_tick: tick.0.saturating_add(secs * TICK_RATE_HZ as u32),
+        }
+    }
+
+    pub fn is_running(&self, tick: SimTick) -> bool {
+        tick.0 < self.go_tick
+    }
+
+    /// Whole seconds left at `tick`, rounded up: 3, 2, 1, then 0 from the go tick on
+    pub fn seconds_left(&self, tick: SimTick) -> u32 {
+        self.go_tick
+            .saturating_sub(tick.0)
+            .div_ceil(TICK_RATE_HZ as u32)
+    }
+
+    /// Simulated seconds since the go tick, None while counting down or without a countdown
+    pub fn secs_since_go(&self, tick: SimTick) -> Option<f32> {
+        (self.go_tick > 0 && !self.is_running(tick))
+            .then(|| (tick.0 - self.go_tick) as f32 * FIXED_DT)
+    }
+}
+
+/// Run condition for the systems that move players on their input
+pub fn countdown_over(countdown: Res<Countdown>, tick: Res<SimTick>) -> bool {
+    !countdown.is_running(*tick)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_down_to_the_go_tick() {
+        let countdown = Countdown::starting_at(SimTick(100), 3);
+        assert_eq!(countdown.go_tick, 100 + 3 * TICK_RATE_HZ as u32);
+
+        assert!(countdown.is_running(SimTick(100)));
+        assert_eq!(countdown.seconds_left(SimTick(100)), 3);
+        assert_eq!(countdown.seconds_left(SimTick(countdown.go_tick - 1)), 1);
+        assert_eq!(countdown.secs_since_go(SimTick(100)), None);
+
+        assert!(!countdown.is_running(SimTick(countdown.go_tick)));
+        assert_eq!(countdown.seconds_left(SimTick(countdown.go_tick)), 0);
+        assert_eq!(
+            countdown.secs_since_go(SimTick(countdown.go_tick + TICK_RATE_HZ as u32)),
+            Some(1.0)
+        );
+
+        // No countdown: everyone moves from the start, and there is no "GO!"
+        let none = Countdown::default();
+        assert!(!none.is_running(SimTick(0)));
+        assert_eq!(none.secs_since_go(SimTick(10)), None);
+    }
+}
@@ -0,0 +1,134 @@
+// Friends: players add each other by their persistent player id and see who is online and
+// in which room. Clients send a presence heartbeat while the lobby is open, and the answer
+// carries the friends list and any room invites waiting for them. The lobby service keeps
+// the lists, see "Friends" in docs/lobby-service.md.
+use serde::{Deserialize, Serialize};
+
+/// Most friends one account can have
+pub const MAX_FRIENDS: usize = 100;
+/// Seconds between presence heartbeats
+pub const PRESENCE_INTERVAL_SECS: u64 = 15;
+/// Seconds without a heartbeat before a player shows as offline, a few missed heartbeats
+pub const ONLINE_TIMEOUT_SECS: u64 = 3 * PRESENCE_INTERVAL_SECS;
+/// Seconds a room invite waits to be seen
+pub const ROOM_INVITE_TTL_SECS: u64 = 5 * 60;
+
+/// One entry of a friends list
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Friend {
+    pub player_id: String,
+    pub display_name: String,
+    pub online: bool,
+    /// Code of the room they are in, only while online
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room_code: Option<String>,
+}
+
+/// Body of `POST /lobby/api/players/{id}/friends`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AddFriendRequest {
+    pub friend_id: String,
+}
+
+/// Body of `POST /lobby/api/players/{id}/presence`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PresenceRequest {
+    pub display_name: String,
+    /// Code of the room the player is in, so friends can follow
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room_code: Option<String>,
+}
+
+/// Answer to a presence heartbeat
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PresenceResponse {
+    /// Online friends first, see `sort_friends`
+    pub friends: Vec<Friend>,
+    /// Invites sent since the last heartbeat, each is handed out once
+    #[serde(default)]
+    pub invites: Vec<RoomInvite>,
+}
+
+/// Body of `POST /lobby/api/players/{id}/friends/{friend_id}/invite`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RoomInviteRequest {
+    pub room_code: String,
+}
+
+/// An invite to a friend's room
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RoomInvite {
+    pub from_id: String,
+    pub from_name: String,
+    pub room_code: String,
+    /// Unix timestamp in seconds
+    pub sent_at: u64,
+}
+
+impl RoomInvite {
+    pub fn expired(&self, now: u64) -> bool {
+        self.sent_at + ROOM_INVITE_TTL_SECS <= now
+    }
+}
+
+/// Whether a player last seen at `last_seen` still counts as online at `now`
+pub fn is_online(last_seen: u64, now: u64) -> bool {
+    now < last_seen + ONLINE_TIMEOUT_SECS
+}
+
+/// The order friends lists are shown in: online players first, those in a room before
+/// those who aren't, then by name
+pub fn sort_friends(friends: &mut [Friend]) {
+    friends.sort_by_key(|friend| {
+        (
+            !friend.online,
+            friend.room_code.is_none(),
+            friend.display_name.to_lowercase(),
+        )
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn friend(name: &str, online: bool, room_code: Option<&str>) -> Friend {
+        Friend {
+            player_id: name.to_lowercase(),
+            display_name: name.to_string(),
+            online,
+            room_code: room_code.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn online_friends_in_rooms_come_first() {
+        let mut friends = vec![
+            friend("zed", false, None),
+            friend("Io", true, None),
+            friend("Vex", true, Some("K7QX2M")),
+            friend("alma", false, None),
+        ];
+        sort_friends(&mut friends);
+        let names: Vec<&str> = friends.iter().map(|f| f.display_name.as_str()).collect();
+        assert_eq!(names, vec!["Vex", "Io", "alma", "zed"]);
+
+        assert!(is_online(100, 100 + ONLINE_TIMEOUT_SECS - 1));
+        assert!(!is_online(100, 100 + ONLINE_TIMEOUT_SECS));
+
+        let invite = RoomInvite {
+            from_id: "vex".to_string(),
+            from_name: "Vex".to_string(),
+            room_code: "K7QX2M".to_string(),
+            sent_at: 1_000,
+        };
+        assert!(!invite.expired(1_000 + ROOM_INVITE_TTL_SECS - 1));
+        assert!(invite.expired(1_000 + ROOM_INVITE_TTL_SECS));
+    }
+}
@@ -0,0 +1,179 @@
+// Physics and rules of a match: how fast players run, how hard they jump and how strongly
+// they fall, how long a round lasts and whether the level keeps its pickups. The server
+// loads it from `--game-config` and the single value overrides (which a custom room's
+// `RuleSet` sets), and sends it to every client as it connects, so a room can play with
+// low gravity without either binary being rebuilt. Both sides keep it as a resource, the
+// movement systems read it.
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// Name of the physics every match had before they could be configured
+pub const DEFAULT_GAME_CONFIG: &str = "default";
+
+// Presets compiled into the server, so a deployment only needs to pass a name
+const BUILTIN_GAME_CONFIGS: &[(&str, GameConfig)] = &[
+    (DEFAULT_GAME_CONFIG, GameConfig::STANDARD),
+    (
+        "low_gravity",
+        GameConfig {
+            move_speed: 200.0,
+            jump_force: 300.0,
+            gravity: -350.0,
+            max_fall_speed: -300.0,
+            ..GameConfig::STANDARD
+        },
+    ),
+];
+
+// Server -> client: platformer physics in units and seconds, and the match rules. Fields
+// left out of a config file keep their standard value.
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Reflect)]
+#[serde(default)]
+#[reflect(Resource)]
+pub struct GameConfig {
+    /// Horizontal speed while a direction is held
+    pub move_speed: f32,
+    /// Upward speed a jump starts with
+    pub jump_force: f32,
+    /// Vertical acceleration while airborne, negative pulls down
+    pub gravity: f32,
+    /// Fastest fall, negative like `gravity`
+    pub max_fall_speed: f32,
+    /// Seconds until the match ends on its score, 0 for no limit. Races end when decided.
+    pub round_secs: u32,
+    /// Levels keep their pickups
+    pub pickups: bool,
+}
+
+impl GameConfig {
+    const STANDARD: Self = Self {
+        move_speed: 200.0,
+        jump_force: 400.0,
+        gravity: -800.0,
+        max_fall_speed: -500.0,
+        round_secs: 0,
+        pickups: true,
+    };
+
+    /// Parse a config from its JSON representation
+    pub fn from_json(json: &str) -> Result<Self, GameConfigError> {
+        let config: Self = serde_json::from_str(json).map_err(GameConfigError::Parse)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Load a config from a file on disk
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, GameConfigError> {
+        let json = std::fs::read_to_string(path).map_err(GameConfigError::Io)?;
+        Self::from_json(&json)
+    }
+
+    /// Look up one of the presets compiled into the binary
+    pub fn builtin(name: &str) -> Option<Self> {
+        BUILTIN_GAME_CONFIGS
+            .iter()
+            .find(|(preset, _)| *preset == name)
+            .map(|(_, config)| *config)
+    }
+
+    /// Names of the compiled in presets, for error messages
+    pub fn builtin_names() -> impl Iterator<Item = &'static str> {
+        BUILTIN_GAME_CONFIGS.iter().map(|(name, _)| *name)
+    }
+
+    /// Physics players can actually play with: they move, jump, and fall back down
+    pub fn validate(&self) -> Result<(), GameConfigError> {
+        let invalid = |reason: &str| Err(GameConfigError::Invalid(reason.to_string()));
+        let values = [
+            self.move_speed,
+            self.jump_force,
+            self.gravity,
+            self.max_fall_speed,
+        ];
+        if values.iter().any(|value| !value.is_finite()) {
+            return invalid("values must be finite numbers");
+        }
+        if self.move_speed <= 0.0 || self.jump_force <= 0.0 {
+            return invalid("move_speed and jump_force must be positive");
+        }
+        if self.gravity >= 0.0 || self.max_fall_speed >= 0.0 {
+            return invalid("gravity and max_fall_speed must be negative");
+        }
+        Ok(())
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+#[derive(Debug)]
+pub enum GameConfigError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for GameConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameConfigError::Io(e) => write!(f, "failed to read game config file: {}", e),
+            GameConfigError::Parse(e) => write!(f, "invalid game config: {}", e),
+            GameConfigError::Invalid(reason) => write!(f, "invalid game config: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for GameConfigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_are_valid() {
+        for name in GameConfig::builtin_names() {
+            let config = GameConfig::builtin(name).unwrap();
+            assert!(config.validate().is_ok(), "{} is invalid", name);
+        }
+        assert_eq!(
+            GameConfig::builtin(DEFAULT_GAME_CONFIG),
+            Some(GameConfig::default())
+        );
+        assert_eq!(GameConfig::builtin("moon"), None);
+    }
+
+    #[test]
+    fn files_only_need_what_they_change() {
+        let config = GameConfig::from_json(r#"{"gravity": -400.0}"#).unwrap();
+        assert_eq!(
+            config,
+            GameConfig {
+                gravity: -400.0,
+                ..default()
+            }
+        );
+    }
+
+    #[test]
+    fn unplayable_physics_are_rejected() {
+        for json in [
+            r#"{"gravity": 100.0}"#,
+            r#"{"jump_force": 0.0}"#,
+            r#"{"max_fall_speed": 500.0}"#,
+        ] {
+            assert!(matches!(
+                GameConfig::from_json(json),
+                Err(GameConfigError::Invalid(_))
+            ));
+        }
+        assert!(matches!(
+            GameConfig::from_json("{"),
+            Err(GameConfigError::Parse(_))
+        ));
+    }
+}
@@ -0,0 +1,116 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+// Every game mode the lobby offers and the server knows how to run.
+// The id is what travels in `RoomInfo::game_mode` and the matchmaker query string.
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum GameMode {
+    #[default]
+    Casual,
+    Ranked,
+    Custom,
+    Coop,
+    Ghost,
+    Koth,
+    Race,
+}
+
+impl GameMode {
+    pub const ALL: &'static [GameMode] = &[
+        GameMode::Casual,
+        GameMode::Ranked,
+        GameMode::Custom,
+        GameMode::Coop,
+        GameMode::Ghost,
+        GameMode::Koth,
+        GameMode::Race,
+    ];
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            GameMode::Casual => "casual",
+            GameMode::Ranked => "ranked",
+            GameMode::Custom => "custom",
+            GameMode::Coop => "coop",
+            GameMode::Ghost => "ghost",
+            GameMode::Koth => "koth",
+            GameMode::Race => "race",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|mode| mode.id() == id)
+    }
+
+    /// One-line explanation shown in the lobby
+    pub fn description(&self) -> &'static str {
+        match self {
+            GameMode::Casual => "Free play, no stakes",
+            GameMode::Ranked => "Rated 3 minute matches against players of your skill",
+            GameMode::Custom => "Host picks the rules",
+            GameMode::Coop => "Work together to open the doors",
+            GameMode::Ghost => "One hidden player sabotages the rest",
+            GameMode::Koth => "Hold the scoring zone, it moves every 30s",
+            GameMode::Race => "First through every checkpoint wins",
+        }
+    }
+
+    /// Level the mode needs, if it can't be played on any level
+    pub fn required_level(&self) -> Option<&'static str> {
+        match self {
+            GameMode::Coop => Some("coop"),
+            _ => None,
+        }
+    }
+
+    /// Teams the mode always plays in, 0 for free-for-all. No mode needs teams yet, the
+    /// server's `--teams` splits any mode into teams.
+    pub fn team_count(&self) -> usize {
+        0
+    }
+
+    /// Players needed before the mode's rules kick in
+    pub fn min_players(&self) -> usize {
+        match self {
+            GameMode::Coop | GameMode::Ghost => 2,
+            _ => 1,
+        }
+    }
+}
+
+impl fmt::Display for GameMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+impl FromStr for GameMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_id(s).ok_or_else(|| {
+            let known: Vec<_> = Self::ALL.iter().map(|m| m.id()).collect();
+            format!(
+                "unknown game mode '{}' (expected one of {})",
+                s,
+                known.join(", ")
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_round_trip() {
+        for mode in GameMode::ALL {
+            assert_eq!(mode.id().parse::<GameMode>(), Ok(*mode));
+        }
+        assert!("deathmatch".parse::<GameMode>().is_err());
+    }
+}
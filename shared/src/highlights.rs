@@ -0,0 +1,251 @@
+// Post-match highlights: the server keeps a few numbers per player over the match and
+// hands out awards from them when it ends. They go to the players' results screens and
+// with the match report to the lobby service, which turns them into achievements.
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::api::MatchStanding;
+
+// Shorter hops than this are just walking over gaps, not worth an award
+const MIN_AIRTIME_SECS: f32 = 1.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Award {
+    /// First place in the standings
+    Mvp,
+    /// Longest time off the ground in one go
+    LongestAirtime,
+    MostCoins,
+    /// Quickest stretch between two race checkpoints (or the start and the first)
+    FastestSplit,
+}
+
+impl Award {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Award::Mvp => "MVP",
+            Award::LongestAirtime => "Frequent Flyer",
+            Award::MostCoins => "Coin Hoarder",
+            Award::FastestSplit => "Speed Demon",
+        }
+    }
+}
+
+/// One award for one player. Tied players each get one.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Highlight {
+    pub award: Award,
+    pub name: String,
+    /// Account id the player's client sent, achievements are kept for these
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player_id: Option<String>,
+    /// Seconds for airtime and splits, coins for coins, the score (or race time) for MVP
+    pub value: f32,
+}
+
+impl Highlight {
+    /// "Frequent Flyer: Player7 (2.4s in the air)"
+    pub fn describe(&self) -> String {
+        let detail = match self.award {
+            Award::Mvp => "top of the standings".to_string(),
+            Award::LongestAirtime => format!("{:.1}s in the air", self.value),
+            Award::MostCoins => format!("{} coins", self.value as u32),
+            Award::FastestSplit => format!("{:.2}s split", self.value),
+        };
+        format!("{}: {} ({})", self.award.title(), self.name, detail)
+    }
+}
+
+/// What the server has seen of a player this match. Server only, never replicated.
+#[derive(Component, Clone, Debug, Default, PartialEq)]
+pub struct MatchStats {
+    // Seconds off the ground so far in the current jump
+    airborne: f32,
+    pub longest_airtime: f32,
+    pub coins: u32,
+    pub fastest_split: Option<f32>,
+}
+
+impl MatchStats {
+    /// Called every fixed step with the player's `grounded` after the step
+    pub fn track_air(&mut self, grounded: bool, dt: f32) {
+        if grounded {
+            self.airborne = 0.0;
+            return;
+        }
+        self.airborne += dt;
+        self.longest_airtime = self.longest_airtime.max(self.airborne);
+    }
+
+    /// Race splits are times since the start, each stretch is the gap to the one before
+    pub fn track_splits(&mut self, splits: &[f32]) {
+        self.fastest_split = std::iter::once(0.0)
+            .chain(splits.iter().copied())
+            .zip(splits.iter().copied())
+            .map(|(previous, split)| split - previous)
+            .min_by(f32::total_cmp);
+    }
+}
+
+/// A player up for awards: name, account id and stats
+pub struct Contender<'a> {
+    pub name: String,
+    pub player_id: Option<String>,
+    pub stats: &'a MatchStats,
+}
+
+/// Awards for a finished match, MVP first
+pub fn match_highlights(standings: &[MatchStanding], contenders: &[Contender]) -> Vec<Highlight> {
+    let mut highlights: Vec<Highlight> = standings
+        .iter()
+        .filter(|standing| standing.place == 1)
+        .map(|standing| Highlight {
+            award: Award::Mvp,
+            name: standing.name.clone(),
+            player_id: standing.player_id.clone(),
+            value: standing.time.unwrap_or(standing.score as f32),
+        })
+        .collect();
+    award_best(
+        &mut highlights,
+        Award::LongestAirtime,
+        contenders,
+        Best::Highest,
+        |stats| Some(stats.longest_airtime).filter(|airtime| *airtime >= MIN_AIRTIME_SECS),
+    );
+    award_best(
+        &mut highlights,
+        Award::MostCoins,
+        contenders,
+        Best::Highest,
+        |stats| Some(stats.coins as f32).filter(|coins| *coins > 0.0),
+    );
+    award_best(
+        &mut highlights,
+        Award::FastestSplit,
+        contenders,
+        Best::Lowest,
+        |stats| stats.fastest_split,
+    );
+    highlights
+}
+
+enum Best {
+    Highest,
+    Lowest,
+}
+
+// Everyone who has the best `score`, players without one can't win it
+fn award_best(
+    highlights: &mut Vec<Highlight>,
+    award: Award,
+    contenders: &[Contender],
+    best: Best,
+    score: impl Fn(&MatchStats) -> Option<f32>,
+) {
+    let scores = contenders
+        .iter()
+        .filter_map(|contender| score(contender.stats));
+    let best = match best {
+        Best::Highest => scores.max_by(f32::total_cmp),
+        Best::Lowest => scores.min_by(f32::total_cmp),
+    };
+    let Some(best) = best else {
+        return;
+    };
+    highlights.extend(
+        contenders
+            .iter()
+            .filter(|contender| score(contender.stats) == Some(best))
+            .map(|contender| Highlight {
+                award,
+                name: contender.name.clone(),
+                player_id: contender.player_id.clone(),
+                value: best,
+            }),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contender<'a>(name: &str, stats: &'a MatchStats) -> Contender<'a> {
+        Contender {
+            name: name.to_string(),
+            player_id: None,
+            stats,
+        }
+    }
+
+    #[test]
+    fn stats_track_the_best_jump_and_split() {
+        let mut stats = MatchStats::default();
+        for grounded in [false, false, false, true, false] {
+            stats.track_air(grounded, 0.5);
+        }
+        assert_eq!(stats.longest_airtime, 1.5);
+
+        stats.track_splits(&[5.0, 8.0, 14.0]);
+        assert_eq!(stats.fastest_split, Some(3.0));
+        stats.track_splits(&[]);
+        assert_eq!(stats.fastest_split, None);
+    }
+
+    #[test]
+    fn awards_go_to_the_best_and_tied_players_share() {
+        let flyer = MatchStats {
+            longest_airtime: 2.5,
+            coins: 4,
+            fastest_split: Some(6.0),
+            ..default()
+        };
+        let collector = MatchStats {
+            longest_airtime: 0.5,
+            coins: 4,
+            fastest_split: Some(3.0),
+            ..default()
+        };
+        let walker = MatchStats::default();
+        let standings = vec![MatchStanding {
+            place: 1,
+            name: "b".to_string(),
+            player_id: Some("acct-b".to_string()),
+            score: 9,
+            time: None,
+        }];
+
+        let highlights = match_highlights(
+            &standings,
+            &[
+                contender("a", &flyer),
+                contender("b", &collector),
+                contender("c", &walker),
+            ],
+        );
+        let awarded: Vec<(Award, &str, f32)> = highlights
+            .iter()
+            .map(|h| (h.award, h.name.as_str(), h.value))
+            .collect();
+        assert_eq!(
+            awarded,
+            vec![
+                (Award::Mvp, "b", 9.0),
+                (Award::LongestAirtime, "a", 2.5),
+                (Award::MostCoins, "a", 4.0),
+                (Award::MostCoins, "b", 4.0),
+                (Award::FastestSplit, "b", 3.0),
+            ]
+        );
+        assert_eq!(highlights[0].player_id.as_deref(), Some("acct-b"));
+    }
+
+    #[test]
+    fn nothing_to_award_in_an_empty_match() {
+        let walker = MatchStats::default();
+        assert!(match_highlights(&[], &[contender("c", &walker)]).is_empty());
+    }
+}
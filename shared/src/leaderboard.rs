@@ -0,0 +1,263 @@
+// Leaderboard: the lobby service keeps running totals per account, updates them from
+// every match report whose standings carry a `player_id`, and answers
+// `GET /lobby/api/leaderboard` with `rank_players` over those totals.
+use serde::{Deserialize, Serialize};
+
+use crate::api::{url_encode, MatchEndReason, MatchReport, MatchStanding};
+use crate::game_mode::GameMode;
+
+/// Rows a leaderboard query gets when it does not say
+pub const LEADERBOARD_SIZE: u32 = 20;
+/// Most rows one query can ask for
+pub const MAX_LEADERBOARD_SIZE: u32 = 100;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardSort {
+    /// Ranked rating, accounts that never played ranked are left out
+    #[default]
+    Rating,
+    /// Matches won, in any mode that decides a winner
+    Wins,
+    /// Coins collected over all matches
+    Coins,
+}
+
+impl LeaderboardSort {
+    pub const ALL: &'static [LeaderboardSort] = &[
+        LeaderboardSort::Rating,
+        LeaderboardSort::Wins,
+        LeaderboardSort::Coins,
+    ];
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            LeaderboardSort::Rating => "rating",
+            LeaderboardSort::Wins => "wins",
+            LeaderboardSort::Coins => "coins",
+        }
+    }
+}
+
+/// Query string of `GET /lobby/api/leaderboard`
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(
+    feature = "openapi",
+    derive(utoipa::IntoParams),
+    into_params(parameter_in = Query)
+)]
+#[serde(default)]
+pub struct LeaderboardQuery {
+    pub sort: LeaderboardSort,
+    /// `LEADERBOARD_SIZE` if not given, at most `MAX_LEADERBOARD_SIZE`
+    pub limit: Option<u32>,
+    /// Account asking, its own row comes back even when it is outside the top
+    pub player_id: Option<String>,
+}
+
+impl LeaderboardQuery {
+    pub fn limit(&self) -> u32 {
+        self.limit
+            .unwrap_or(LEADERBOARD_SIZE)
+            .clamp(1, MAX_LEADERBOARD_SIZE)
+    }
+
+    /// `?sort=..&limit=..` for the request URL
+    pub fn to_query_string(&self) -> String {
+        let mut query = format!("?sort={}&limit={}", self.sort.id(), self.limit());
+        if let Some(player_id) = &self.player_id {
+            query.push_str(&format!("&player_id={}", url_encode(player_id)));
+        }
+        query
+    }
+}
+
+/// Running totals of one account, as the lobby service stores them
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PlayerStats {
+    pub player_id: String,
+    pub display_name: String,
+    pub rating: f32,
+    /// Ranked matches played
+    pub ranked_games: u32,
+    pub wins: u32,
+    pub coins: u32,
+}
+
+impl PlayerStats {
+    pub fn new(player_id: &str, display_name: &str, rating: f32) -> Self {
+        Self {
+            player_id: player_id.to_string(),
+            display_name: display_name.to_string(),
+            rating,
+            ranked_games: 0,
+            wins: 0,
+            coins: 0,
+        }
+    }
+
+    /// Add one standing of a finished match. Only completed matches have a winner, and
+    /// only the modes that score with coins add to `coins`: a race scores checkpoints and
+    /// king of the hill scores time on the hill.
+    pub fn record(&mut self, report: &MatchReport, standing: &MatchStanding) {
+        if report.reason == MatchEndReason::Completed && standing.place == 1 {
+            self.wins += 1;
+        }
+        let coin_mode = !matches!(
+            GameMode::from_id(&report.game_mode),
+            None | Some(GameMode::Race) | Some(GameMode::Koth)
+        );
+        if coin_mode {
+            self.coins += standing.score;
+        }
+    }
+}
+
+/// One row of the leaderboard
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LeaderboardEntry {
+    /// Tied accounts share a rank
+    pub rank: u32,
+    #[serde(flatten)]
+    pub stats: PlayerStats,
+}
+
+/// Body of `GET /lobby/api/leaderboard`
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Leaderboard {
+    pub sort: LeaderboardSort,
+    /// Best first
+    pub entries: Vec<LeaderboardEntry>,
+    /// The asking account's row, None if it has none or it is already in `entries`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub own: Option<LeaderboardEntry>,
+}
+
+/// Rank the stored totals for one query. Ties share a rank and are listed by name.
+pub fn rank_players(query: &LeaderboardQuery, players: Vec<PlayerStats>) -> Leaderboard {
+    let key = |stats: &PlayerStats| match query.sort {
+        LeaderboardSort::Rating => stats.rating,
+        LeaderboardSort::Wins => stats.wins as f32,
+        LeaderboardSort::Coins => stats.coins as f32,
+    };
+    let mut players: Vec<PlayerStats> = players
+        .into_iter()
+        .filter(|stats| query.sort != LeaderboardSort::Rating || stats.ranked_games > 0)
+        .collect();
+    players.sort_by(|a, b| {
+        key(b)
+            .total_cmp(&key(a))
+            .then_with(|| a.display_name.cmp(&b.display_name))
+    });
+
+    let mut ranked = Vec::with_capacity(players.len());
+    let mut rank = 0;
+    for (i, stats) in players.into_iter().enumerate() {
+        if ranked
+            .last()
+            .is_none_or(|prev: &LeaderboardEntry| key(&prev.stats) != key(&stats))
+        {
+            rank = i as u32 + 1;
+        }
+        ranked.push(LeaderboardEntry { rank, stats });
+    }
+
+    let limit = query.limit() as usize;
+    let own = query.player_id.as_ref().and_then(|player_id| {
+        ranked
+            .iter()
+            .skip(limit)
+            .find(|entry| entry.stats.player_id == *player_id)
+            .cloned()
+    });
+    ranked.truncate(limit);
+    Leaderboard {
+        sort: query.sort,
+        entries: ranked,
+        own,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(name: &str, rating: f32, wins: u32, coins: u32) -> PlayerStats {
+        PlayerStats {
+            ranked_games: 5,
+            wins,
+            coins,
+            ..PlayerStats::new(name, name, rating)
+        }
+    }
+
+    #[test]
+    fn leaderboard_ranks_ties_together_and_finds_the_asker() {
+        let players = vec![
+            stats("ann", 1300.0, 2, 40),
+            stats("bob", 1250.0, 5, 40),
+            stats("cat", 1250.0, 1, 90),
+            PlayerStats::new("dan", "dan", 1200.0),
+        ];
+
+        // Unrated accounts stay off the rating board, ties share a rank
+        let query = LeaderboardQuery::default();
+        let board = rank_players(&query, players.clone());
+        let rows: Vec<(u32, &str)> = board
+            .entries
+            .iter()
+            .map(|e| (e.rank, e.stats.display_name.as_str()))
+            .collect();
+        assert_eq!(rows, vec![(1, "ann"), (2, "bob"), (2, "cat")]);
+
+        let query = LeaderboardQuery {
+            sort: LeaderboardSort::Coins,
+            limit: Some(1),
+            player_id: Some("ann".to_string()),
+        };
+        let board = rank_players(&query, players.clone());
+        assert_eq!(board.entries[0].stats.display_name, "cat");
+        assert_eq!(board.own.as_ref().map(|e| e.rank), Some(2));
+        assert_eq!(query.to_query_string(), "?sort=coins&limit=1&player_id=ann");
+
+        // No `own` row for an asker already on the board
+        let query = LeaderboardQuery {
+            sort: LeaderboardSort::Wins,
+            player_id: Some("bob".to_string()),
+            ..LeaderboardQuery::default()
+        };
+        let board = rank_players(&query, players);
+        assert_eq!(board.entries.len(), 4);
+        assert_eq!(board.own, None);
+    }
+
+    #[test]
+    fn only_coin_modes_add_coins() {
+        let standing = MatchStanding {
+            place: 1,
+            name: "ann".to_string(),
+            player_id: Some("ann".to_string()),
+            score: 12,
+            time: None,
+        };
+        let report = |game_mode: &str, reason| MatchReport {
+            game_mode: game_mode.to_string(),
+            level: "default".to_string(),
+            reason,
+            ended_at: 0,
+            duration_secs: 180.0,
+            standings: vec![standing.clone()],
+            network: Vec::new(),
+            highlights: Vec::new(),
+        };
+        let mut ann = PlayerStats::new("ann", "ann", 1200.0);
+        ann.record(&report("ranked", MatchEndReason::Completed), &standing);
+        ann.record(&report("race", MatchEndReason::Completed), &standing);
+        ann.record(&report("casual", MatchEndReason::Shutdown), &standing);
+        assert_eq!((ann.wins, ann.coins), (2, 24));
+    }
+}
@@ -0,0 +1,505 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+use crate::protocol_plugin::{
+    Checkpoint, Door, Pickup, Platform, PlatformKind, PressurePlate, ScoringZone, PLATFORM_WIDTH,
+};
+
+/// Name of the level used when nothing else is requested
+pub const DEFAULT_LEVEL: &str = "default";
+
+/// Where players start in levels that list no spawn points
+pub const DEFAULT_SPAWN: Vec3 = Vec3::new(0.0, 100.0, 0.0);
+/// Closest two players may spawn to each other, a bit more than a player is wide
+pub const SPAWN_CLEARANCE: f32 = 40.0;
+// How many clearances a crowded spawn point is allowed to shift a player sideways
+const MAX_SPAWN_SHIFTS: usize = 8;
+
+// Levels compiled into every binary so the WASM client never has to fetch them
+const BUILTIN_LEVELS: &[(&str, &str)] = &[
+    (
+        DEFAULT_LEVEL,
+        include_str!("../../assets/levels/default.json"),
+    ),
+    ("towers", include_str!("../../assets/levels/towers.json")),
+    ("coop", include_str!("../../assets/levels/coop.json")),
+];
+
+// How far the floor reaches past the level's edges, so it doesn't end under the players
+const FLOOR_OVERHANG: f32 = 100.0;
+// Same as every other platform
+const FLOOR_THICKNESS: f32 = 20.0;
+
+// A single platform in a level file
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PlatformDefinition {
+    pub x: f32,
+    pub y: f32,
+    #[serde(default)]
+    pub kind: PlatformKind,
+    #[serde(default = "default_platform_width")]
+    pub width: f32,
+}
+
+fn default_platform_width() -> f32 {
+    PLATFORM_WIDTH
+}
+
+// Server -> client: edges of the level. Players can't walk past `min_x` and `max_x`, and
+// the floor is a platform spanning them with its top at `floor_y`. Levels without a floor
+// have pits, and players falling below `kill_y` are respawned. Both sides keep it as a
+// resource, the movement systems read it.
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Reflect)]
+#[serde(default)]
+#[reflect(Resource)]
+pub struct WorldBounds {
+    pub min_x: f32,
+    pub max_x: f32,
+    pub floor: bool,
+    pub floor_y: f32,
+    pub kill_y: f32,
+}
+
+impl Default for WorldBounds {
+    // The arena every level had before levels could set their own
+    fn default() -> Self {
+        Self {
+            min_x: -400.0,
+            max_x: 400.0,
+            floor: true,
+            floor_y: -215.0,
+            kill_y: -600.0,
+        }
+    }
+}
+
+impl WorldBounds {
+    /// The floor as a level platform, None for levels without one
+    pub fn floor(&self) -> Option<PlatformDefinition> {
+        self.floor.then(|| PlatformDefinition {
+            x: (self.min_x + self.max_x) / 2.0,
+            y: self.floor_y - FLOOR_THICKNESS / 2.0,
+            kind: PlatformKind::Static,
+            width: self.max_x - self.min_x + 2.0 * FLOOR_OVERHANG,
+        })
+    }
+
+    /// Below the kill plane, the player has fallen out of the world
+    pub fn fell_out(&self, position: Vec3) -> bool {
+        position.y < self.kill_y
+    }
+}
+
+impl PlatformDefinition {
+    pub fn translation(&self) -> Vec3 {
+        Vec3::new(self.x, self.y, 0.0)
+    }
+}
+
+// A single position of interest in a level, e.g. the campaign goal
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct LevelPoint {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl LevelPoint {
+    pub fn translation(&self) -> Vec3 {
+        Vec3::new(self.x, self.y, 0.0)
+    }
+}
+
+// Pressure plate in a co-op level, doors refer to it by `id`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PlateDefinition {
+    pub id: String,
+    pub x: f32,
+    pub y: f32,
+}
+
+// Door that blocks the way until all of its plates are pressed at the same time
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DoorDefinition {
+    pub x: f32,
+    pub y: f32,
+    pub plates: Vec<String>,
+    /// Stay open once opened instead of closing when a plate is released
+    #[serde(default)]
+    pub latch: bool,
+}
+
+// Coin placed in a level
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PickupDefinition {
+    pub x: f32,
+    pub y: f32,
+    #[serde(default = "default_pickup_value")]
+    pub value: u32,
+}
+
+fn default_pickup_value() -> u32 {
+    1
+}
+
+// King-of-the-hill scoring zone, zones take turns being active in the order listed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ZoneDefinition {
+    pub x: f32,
+    pub y: f32,
+    #[serde(default = "default_zone_radius")]
+    pub radius: f32,
+}
+
+fn default_zone_radius() -> f32 {
+    60.0
+}
+
+// Race checkpoint, passed in the order listed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CheckpointDefinition {
+    pub x: f32,
+    pub y: f32,
+    #[serde(default = "default_checkpoint_radius")]
+    pub radius: f32,
+}
+
+fn default_checkpoint_radius() -> f32 {
+    40.0
+}
+
+// Level layout loaded from `assets/levels/<name>.json`
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LevelDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub platforms: Vec<PlatformDefinition>,
+    /// Edges and floor, the original 800 unit arena if left out
+    #[serde(default)]
+    pub bounds: WorldBounds,
+    /// Where the player has to get to in campaign mode
+    #[serde(default)]
+    pub goal: Option<LevelPoint>,
+    #[serde(default)]
+    pub plates: Vec<PlateDefinition>,
+    #[serde(default)]
+    pub doors: Vec<DoorDefinition>,
+    #[serde(default)]
+    pub pickups: Vec<PickupDefinition>,
+    /// Only used by the king-of-the-hill game mode
+    #[serde(default)]
+    pub zones: Vec<ZoneDefinition>,
+    /// Only used by the race game mode
+    #[serde(default)]
+    pub checkpoints: Vec<CheckpointDefinition>,
+    /// Where players without a team start, `DEFAULT_SPAWN` if empty
+    #[serde(default)]
+    pub spawns: Vec<LevelPoint>,
+    /// One group of spawn points per team, for team modes. Teams without a group use
+    /// `spawns`.
+    #[serde(default)]
+    pub team_spawns: Vec<Vec<LevelPoint>>,
+}
+
+impl Default for LevelDefinition {
+    fn default() -> Self {
+        Self::builtin(DEFAULT_LEVEL).expect("default level must be built in")
+    }
+}
+
+#[derive(Debug)]
+pub enum LevelError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelError::Io(e) => write!(f, "failed to read level file: {}", e),
+            LevelError::Parse(e) => write!(f, "invalid level definition: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+impl LevelDefinition {
+    /// Parse a level from its JSON representation
+    pub fn from_json(json: &str) -> Result<Self, LevelError> {
+        serde_json::from_str(json).map_err(LevelError::Parse)
+    }
+
+    /// Load a level from a file on disk
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LevelError> {
+        let json = std::fs::read_to_string(path).map_err(LevelError::Io)?;
+        Self::from_json(&json)
+    }
+
+    /// Look up one of the levels compiled into the binary
+    pub fn builtin(name: &str) -> Option<Self> {
+        BUILTIN_LEVELS
+            .iter()
+            .find(|(level_name, _)| *level_name == name)
+            .and_then(|(_, json)| Self::from_json(json).ok())
+    }
+
+    /// Names of all built-in levels
+    pub fn builtin_names() -> impl Iterator<Item = &'static str> {
+        BUILTIN_LEVELS.iter().map(|(name, _)| *name)
+    }
+
+    /// Platform entities (without any networking components) for this level, the floor
+    /// last
+    pub fn platform_bundles(&self) -> impl Iterator<Item = (Platform, Transform)> + '_ {
+        self.platforms
+            .iter()
+            .cloned()
+            .chain(self.bounds.floor())
+            .map(|p| {
+                (
+                    Platform::new(p.kind.clone(), p.translation().truncate()).with_width(p.width),
+                    Transform::from_translation(p.translation()),
+                )
+            })
+    }
+
+    /// Pressure plates for co-op levels, all released
+    pub fn plate_bundles(&self) -> impl Iterator<Item = (PressurePlate, Transform)> + '_ {
+        self.plates.iter().map(|p| {
+            (
+                PressurePlate {
+                    id: p.id.clone(),
+                    pressed: false,
+                },
+                Transform::from_xyz(p.x, p.y, 0.0),
+            )
+        })
+    }
+
+    /// Doors for co-op levels, all closed
+    pub fn door_bundles(&self) -> impl Iterator<Item = (Door, Transform)> + '_ {
+        self.doors.iter().map(|d| {
+            (
+                Door {
+                    plates: d.plates.clone(),
+                    latch: d.latch,
+                    open: false,
+                },
+                Transform::from_xyz(d.x, d.y, 0.0),
+            )
+        })
+    }
+
+    /// Coins for this level
+    pub fn pickup_bundles(&self) -> impl Iterator<Item = (Pickup, Transform)> + '_ {
+        self.pickups.iter().map(|p| {
+            (
+                Pickup { value: p.value },
+                Transform::from_xyz(p.x, p.y, 0.0),
+            )
+        })
+    }
+
+    /// King-of-the-hill zones, all inactive until the game mode picks one
+    pub fn zone_bundles(&self) -> impl Iterator<Item = (ScoringZone, Transform)> + '_ {
+        self.zones.iter().map(|z| {
+            (
+                ScoringZone {
+                    radius: z.radius,
+                    active: false,
+                    contested: false,
+                },
+                Transform::from_xyz(z.x, z.y, 0.0),
+            )
+        })
+    }
+
+    /// Race checkpoints, numbered in the order they have to be passed
+    pub fn checkpoint_bundles(&self) -> impl Iterator<Item = (Checkpoint, Transform)> + '_ {
+        self.checkpoints.iter().enumerate().map(|(index, c)| {
+            (
+                Checkpoint {
+                    index,
+                    radius: c.radius,
+                },
+                Transform::from_xyz(c.x, c.y, 0.0),
+            )
+        })
+    }
+
+    /// Co-op levels have at least one door that needs plates pressed together
+    pub fn is_coop(&self) -> bool {
+        !self.doors.is_empty()
+    }
+
+    /// Where a player of `team` starts, given where the other players are. The first
+    /// listed spawn point with no player within `SPAWN_CLEARANCE` wins. When every point
+    /// is taken, the player is shifted sideways from one until there is room.
+    pub fn spawn_point(&self, team: Option<usize>, occupied: &[Vec2]) -> Vec3 {
+        let group = team
+            .and_then(|team| self.team_spawns.get(team))
+            .filter(|group| !group.is_empty())
+            .unwrap_or(&self.spawns);
+        let candidates: Vec<Vec3> = if group.is_empty() {
+            vec![DEFAULT_SPAWN]
+        } else {
+            group.iter().map(LevelPoint::translation).collect()
+        };
+        let free = |point: Vec3| {
+            occupied
+                .iter()
+                .all(|other| other.distance(point.truncate()) >= SPAWN_CLEARANCE)
+        };
+
+        for shift in 0..=MAX_SPAWN_SHIFTS {
+            for candidate in &candidates {
+                let offset = Vec3::X * SPAWN_CLEARANCE * shift as f32;
+                for point in [*candidate + offset, *candidate - offset] {
+                    if free(point) {
+                        return point;
+                    }
+                }
+            }
+        }
+        // A full lobby on a tiny level, overlapping beats not spawning
+        candidates[0]
+    }
+
+    /// Whether `spawn_point` could have put a player at `point`, for any team and any
+    /// players in the way. Respawns are the only moves allowed to skip across the level.
+    pub fn is_spawn_point(&self, point: Vec3) -> bool {
+        let candidates: Vec<Vec3> = self
+            .spawns
+            .iter()
+            .chain(self.team_spawns.iter().flatten())
+            .map(LevelPoint::translation)
+            .chain(std::iter::once(DEFAULT_SPAWN))
+            .collect();
+        (0..=MAX_SPAWN_SHIFTS).any(|shift| {
+            let offset = Vec3::X * SPAWN_CLEARANCE * shift as f32;
+            candidates.iter().any(|candidate| {
+                (*candidate + offset).distance(point) < 1.0
+                    || (*candidate - offset).distance(point) < 1.0
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_levels_parse() {
+        for name in LevelDefinition::builtin_names() {
+            let level = LevelDefinition::builtin(name).expect("built-in level should parse");
+            assert_eq!(level.name, name);
+            assert!(!level.platforms.is_empty());
+        }
+    }
+
+    #[test]
+    fn coop_doors_reference_existing_plates() {
+        let level = LevelDefinition::builtin("coop").unwrap();
+        assert!(level.is_coop());
+        for door in &level.doors {
+            assert!(door.plates.len() >= 2, "co-op doors need two players");
+            for plate in &door.plates {
+                assert!(level.plates.iter().any(|p| &p.id == plate));
+            }
+        }
+    }
+
+    #[test]
+    fn moving_platforms_loop_through_their_path() {
+        let level = LevelDefinition::from_json(
+            r#"{ "name": "lift", "platforms": [
+                { "x": 10.0, "y": 0.0, "kind": { "moving": { "path": [[100.0, 0.0]], "speed": 50.0 } } },
+                { "x": 0.0, "y": 0.0 }
+            ] }"#,
+        )
+        .unwrap();
+        let (moving, _) = level.platform_bundles().next().unwrap();
+        assert_eq!(moving.position_at(0.0), Vec2::new(10.0, 0.0));
+        assert_eq!(moving.position_at(1.0), Vec2::new(60.0, 0.0));
+        assert_eq!(moving.position_at(2.0), Vec2::new(110.0, 0.0));
+        // Back to the start after the full 200 unit loop
+        assert_eq!(moving.position_at(4.0), Vec2::new(10.0, 0.0));
+        assert_eq!(level.platforms[1].kind, PlatformKind::Static);
+    }
+
+    #[test]
+    fn spawns_avoid_other_players() {
+        let level = LevelDefinition::from_json(
+            r#"{ "name": "arena",
+                 "spawns": [{ "x": -100.0, "y": 50.0 }, { "x": 100.0, "y": 50.0 }],
+                 "team_spawns": [[{ "x": -300.0, "y": 0.0 }], []] }"#,
+        )
+        .unwrap();
+        assert_eq!(level.spawn_point(None, &[]), Vec3::new(-100.0, 50.0, 0.0));
+        // The first point is taken, the second one is free
+        let taken = [Vec2::new(-90.0, 50.0)];
+        assert_eq!(level.spawn_point(None, &taken), Vec3::new(100.0, 50.0, 0.0));
+        // Both taken: shift sideways from the first one
+        let taken = [Vec2::new(-100.0, 50.0), Vec2::new(100.0, 50.0)];
+        assert_eq!(level.spawn_point(None, &taken), Vec3::new(-60.0, 50.0, 0.0));
+
+        // Team 0 has its own group, team 1's is empty and team 2 has none
+        assert_eq!(level.spawn_point(Some(0), &[]), Vec3::new(-300.0, 0.0, 0.0));
+        assert_eq!(
+            level.spawn_point(Some(1), &[]),
+            Vec3::new(-100.0, 50.0, 0.0)
+        );
+        assert_eq!(
+            level.spawn_point(Some(2), &[]),
+            Vec3::new(-100.0, 50.0, 0.0)
+        );
+
+        let empty = LevelDefinition::from_json(r#"{ "name": "empty" }"#).unwrap();
+        assert_eq!(empty.spawn_point(None, &[]), DEFAULT_SPAWN);
+
+        // Wherever a player was spawned counts as a spawn point, nothing else does
+        assert!(level.is_spawn_point(Vec3::new(-60.0, 50.0, 0.0)));
+        assert!(level.is_spawn_point(Vec3::new(-300.0, 0.0, 0.0)));
+        assert!(!level.is_spawn_point(Vec3::new(-80.0, 50.0, 0.0)));
+        assert!(!level.is_spawn_point(Vec3::new(100.0, 90.0, 0.0)));
+    }
+
+    #[test]
+    fn levels_set_their_own_bounds() {
+        let arena = LevelDefinition::from_json(r#"{ "name": "arena" }"#).unwrap();
+        assert_eq!(arena.bounds, WorldBounds::default());
+
+        let wide = LevelDefinition::from_json(
+            r#"{ "name": "wide", "platforms": [{ "x": 900.0, "y": 0.0, "width": 400.0 }],
+                 "bounds": { "min_x": -200.0, "max_x": 1200.0, "floor_y": -300.0 } }"#,
+        )
+        .unwrap();
+        let platforms: Vec<_> = wide.platform_bundles().collect();
+        assert_eq!(platforms.len(), 2);
+        assert_eq!(platforms[0].0.width, 400.0);
+        // The floor covers the whole level with its top at floor_y
+        let (floor, transform) = &platforms[1];
+        assert_eq!(floor.width, 1600.0);
+        assert_eq!(transform.translation, Vec3::new(500.0, -310.0, 0.0));
+        // Left out fields keep their defaults
+        assert_eq!(wide.bounds.kill_y, WorldBounds::default().kill_y);
+
+        let pits = LevelDefinition::from_json(
+            r#"{ "name": "pits", "platforms": [{ "x": 0.0, "y": 0.0 }],
+                 "bounds": { "floor": false, "kill_y": -400.0 } }"#,
+        )
+        .unwrap();
+        assert_eq!(pits.platform_bundles().count(), 1);
+        assert!(pits.bounds.fell_out(Vec3::new(0.0, -401.0, 0.0)));
+        assert!(!pits.bounds.fell_out(Vec3::new(0.0, -399.0, 0.0)));
+    }
+
+    #[test]
+    fn missing_platforms_default_to_empty() {
+        let level = LevelDefinition::from_json(r#"{ "name": "empty" }"#).unwrap();
+        assert!(level.platforms.is_empty());
+    }
+}
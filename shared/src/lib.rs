@@ -1,5 +1,55 @@
+pub mod api;
+pub mod asset_manifest;
+pub mod auth;
+pub mod bot;
+pub mod campaign;
+pub mod chat;
+pub mod clock;
+pub mod cors;
+pub mod countdown;
+pub mod friends;
+pub mod game_config;
+pub mod game_mode;
+pub mod highlights;
+pub mod leaderboard;
+pub mod level;
+pub mod network;
+#[cfg(feature = "openapi")]
+pub mod openapi;
+pub mod party;
 pub mod protocol_plugin;
+pub mod race;
+pub mod rating;
+pub mod reconcile;
+pub mod room_code;
+pub mod rule_set;
 pub mod shared_plugin;
+pub mod simulation;
+pub mod warm_pool;
 
+pub use api::*;
+pub use asset_manifest::*;
+pub use auth::*;
+pub use bot::*;
+pub use campaign::*;
+pub use chat::*;
+pub use clock::*;
+pub use cors::*;
+pub use countdown::*;
+pub use friends::*;
+pub use game_config::*;
+pub use game_mode::*;
+pub use highlights::*;
+pub use leaderboard::*;
+pub use level::*;
+pub use network::*;
+pub use party::*;
 pub use protocol_plugin::*;
+pub use race::*;
+pub use rating::*;
+pub use reconcile::*;
+pub use room_code::*;
+pub use rule_set::*;
 pub use shared_plugin::*;
+pub use simulation::*;
+pub use warm_pool::*;
@@ -0,0 +1,52 @@
+// Input netcode knobs. Clients apply each input a few ticks after it was pressed, which
+// gives it time to reach the server, and through the server the other clients, before
+// the tick it belongs to is simulated. Lightyear keeps every player's inputs in a
+// per-tick buffer, so remote players move on their real inputs instead of jittering
+// between corrections, and a player joining late starts from the inputs repeated in the
+// next message instead of waiting for a fresh press.
+use bevy::prelude::*;
+use lightyear::prelude::client::InputDelayConfig;
+
+/// Read by `ProtocolPlugin` when it registers the inputs, so insert it before adding the
+/// protocol to change the redundancy or rebroadcasting. Clients also re-apply the delays
+/// whenever it changes.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct NetworkConfig {
+    /// Ticks every input is held back before it is applied, also on the pressing client
+    pub input_delay_ticks: u16,
+    /// Round trips longer than this many ticks are covered by prediction, not more delay
+    pub max_input_delay_ticks: u16,
+    /// Most ticks a client predicts ahead of the server before it waits
+    pub max_predicted_ticks: u16,
+    /// Ticks of earlier inputs repeated in every input message, so a lost packet costs
+    /// nothing and a late joiner fills its buffer from the first message
+    pub input_redundancy: u16,
+    /// The server forwards every player's inputs to the other clients
+    pub rebroadcast_inputs: bool,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            // 2 ticks is 33 ms, unnoticeable for a platformer and enough for most jitter
+            input_delay_ticks: 2,
+            max_input_delay_ticks: 6,
+            max_predicted_ticks: 100,
+            input_redundancy: 10,
+            rebroadcast_inputs: true,
+        }
+    }
+}
+
+impl NetworkConfig {
+    pub fn input_delay(&self) -> InputDelayConfig {
+        InputDelayConfig {
+            minimum_input_delay_ticks: self.input_delay_ticks,
+            maximum_input_delay_before_prediction: self
+                .max_input_delay_ticks
+                .max(self.input_delay_ticks),
+            maximum_predicted_ticks: self.max_predicted_ticks,
+        }
+    }
+}
@@ -0,0 +1,741 @@
+// OpenAPI document for the lobby service and matchmaker HTTP APIs. Neither service lives in
+// this repository, so the routes are declared here on empty functions, next to the types
+// their bodies use, and the services serve `api_doc()` (see "OpenAPI document" in
+// docs/lobby-service.md). Only built with the `openapi` feature.
+//
+// The functions only carry the `#[utoipa::path]` attributes and are never called.
+#![allow(dead_code)]
+
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use crate::api::*;
+use crate::auth::*;
+use crate::chat::*;
+use crate::friends::*;
+use crate::leaderboard::*;
+use crate::party::*;
+use crate::reconcile::*;
+
+/// Where the lobby service serves the document
+pub const OPENAPI_PATH: &str = "/lobby/api/openapi.json";
+/// Where the lobby service serves Swagger UI for it
+pub const SWAGGER_UI_PATH: &str = "/lobby/api/docs";
+
+/// The lobby service and matchmaker routes with their request and response bodies
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Voidloop Quest services",
+        description = "Lobby service (`/lobby/api`) and matchmaker (`/matchmaker`) routes. \
+                       Error answers carry a `ServiceError`."
+    ),
+    paths(
+        guest_token,
+        list_rooms,
+        create_room,
+        get_room,
+        join_room,
+        leave_room,
+        start_room,
+        room_heartbeat,
+        ready_room,
+        kick_member,
+        room_chat,
+        send_chat,
+        change_team,
+        finish_room,
+        room_voice,
+        match_history,
+        create_player,
+        rename_player,
+        player_rating,
+        leaderboard,
+        record_ranked_match,
+        presence,
+        add_friend,
+        remove_friend,
+        invite_friend,
+        create_party,
+        join_party,
+        invite_to_party,
+        queue_party,
+        leave_party,
+        party_heartbeat,
+        party_invites,
+        status,
+        regions,
+        server_heartbeat,
+        anti_cheat_report,
+        hardware_report,
+        preset_recommendations,
+        admin_rooms,
+        admin_audit,
+        admin_hardware,
+        matchmaker_ws,
+        matchmaker_health,
+        matchmaker_shards,
+    ),
+    components(schemas(ServiceError)),
+    modifiers(&BearerToken),
+    tags(
+        (name = "auth", description = "Player tokens"),
+        (name = "rooms", description = "Rooms and the room browser"),
+        (name = "players", description = "Accounts, ratings and the leaderboard"),
+        (name = "friends", description = "Friends lists, presence and room invites"),
+        (name = "parties", description = "Parties that queue together"),
+        (name = "service", description = "Status and regions"),
+        (name = "servers", description = "Calls from game servers"),
+        (name = "telemetry", description = "Opt-in hardware reports"),
+        (name = "admin", description = "Operator routes"),
+        (name = "matchmaker", description = "matchmaker-httpd"),
+    )
+)]
+pub struct ApiDoc;
+
+/// The document as the services serve it
+pub fn api_doc() -> utoipa::openapi::OpenApi {
+    ApiDoc::openapi()
+}
+
+// Game servers, the matchmaker and operators send `VOIDLOOP_LOBBY_TOKEN` as a bearer token
+// ("token"), players the JWT from `/auth/guest` ("player")
+struct BearerToken;
+
+impl Modify for BearerToken {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "token",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+        components.add_security_scheme(
+            "player",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/lobby/api/auth/guest",
+    tag = "auth",
+    request_body = GuestAuthRequest,
+    responses(
+        (status = 200, body = AuthToken),
+        (status = 400, description = "The name fails `sanitize_display_name`", body = ServiceError),
+    )
+)]
+fn guest_token() {}
+
+#[utoipa::path(
+    get,
+    path = "/lobby/api/rooms",
+    tag = "rooms",
+    params(RoomQuery),
+    responses(
+        (
+            status = 200,
+            description = "A page of rooms, or every room from older services",
+            body = RoomListResponse
+        ),
+        (status = 304, description = "The list hasn't changed since the `If-None-Match` ETag"),
+    )
+)]
+fn list_rooms() {}
+
+#[utoipa::path(
+    post,
+    path = "/lobby/api/rooms",
+    tag = "rooms",
+    request_body = CreateRoomRequest,
+    security(("player" = [])),
+    responses(
+        (status = 200, body = ServerLobbyRoom),
+        (status = 401, description = "No token, or an expired one", body = ServiceError),
+        (status = 400, description = "`INVALID_RULES`", body = ServiceError),
+        (status = 429, description = "Room creation limits", body = ServiceError),
+    )
+)]
+fn create_room() {}
+
+#[utoipa::path(
+    get,
+    path = "/lobby/api/rooms/{id}",
+    tag = "rooms",
+    params(("id" = String, Path, description = "Room id or code")),
+    responses(
+        (status = 200, description = "The room with its members", body = ServerLobbyRoom),
+        (status = 404, body = ServiceError),
+    )
+)]
+fn get_room() {}
+
+#[utoipa::path(
+    post,
+    path = "/lobby/api/rooms/{id}/join",
+    tag = "rooms",
+    params(("id" = String, Path, description = "Room id or code")),
+    request_body = RoomPlayerRequest,
+    security(("player" = [])),
+    responses(
+        (status = 200, body = ServerLobbyRoom),
+        (status = 401, description = "No token, or an expired one", body = ServiceError),
+        (
+            status = 403,
+            description = "Wrong password, or `KICKED_FROM_ROOM`",
+            body = ServiceError
+        ),
+        (status = 404, body = ServiceError),
+    )
+)]
+fn join_room() {}
+
+#[utoipa::path(
+    post,
+    path = "/lobby/api/rooms/{id}/leave",
+    tag = "rooms",
+    params(("id" = String, Path, description = "Room id or code")),
+    request_body = RoomPlayerRequest,
+    security(("player" = [])),
+    responses(
+        (status = 200),
+        (status = 401, body = ServiceError),
+        (status = 403, description = "`NOT_IN_ROOM`", body = ServiceError),
+    )
+)]
+fn leave_room() {}
+
+#[utoipa::path(
+    post,
+    path = "/lobby/api/rooms/{id}/start",
+    tag = "rooms",
+    params(("id" = String, Path, description = "Room id or code")),
+    security(("player" = [])),
+    responses(
+        (status = 200),
+        (status = 401, body = ServiceError),
+        (
+            status = 403,
+            description = "`NOT_IN_ROOM`, or `NOT_ROOM_HOST` for members",
+            body = ServiceError
+        ),
+        (
+            status = 409,
+            description = "`PLAYERS_NOT_READY`, with the seconds left of the ready check",
+            body = ServiceError
+        ),
+    )
+)]
+fn start_room() {}
+
+#[utoipa::path(
+    post,
+    path = "/lobby/api/rooms/{id}/ready",
+    tag = "rooms",
+    params(("id" = String, Path, description = "Room id or code")),
+    request_body = ReadyRequest,
+    security(("player" = [])),
+    responses(
+        (status = 200, body = ServerLobbyRoom),
+        (status = 401, description = "No token, or an expired one", body = ServiceError),
+        (status = 403, description = "`NOT_IN_ROOM`", body = ServiceError),
+    )
+)]
+fn ready_room() {}
+
+#[utoipa::path(
+    post,
+    path = "/lobby/api/rooms/{id}/kick",
+    tag = "rooms",
+    params(("id" = String, Path, description = "Room id or code")),
+    request_body = KickRequest,
+    security(("player" = [])),
+    responses(
+        (status = 200, body = ServerLobbyRoom),
+        (status = 400, description = "`CANNOT_KICK_SELF`", body = ServiceError),
+        (status = 401, description = "No token, or an expired one", body = ServiceError),
+        (status = 403, description = "`NOT_ROOM_HOST`", body = ServiceError),
+        (status = 404, description = "No such member", body = ServiceError),
+    )
+)]
+fn kick_member() {}
+
+#[utoipa::path(
+    get,
+    path = "/lobby/api/rooms/{id}/chat",
+    tag = "rooms",
+    params(("id" = String, Path, description = "Room id or code"), ChatQuery),
+    responses(
+        (
+            status = 200,
+            description = "Messages after `after`, as soon as there are any, or none after \
+                           `CHAT_POLL_SECS`",
+            body = [ChatMessage]
+        ),
+        (status = 404, description = "The room is gone", body = ServiceError),
+    )
+)]
+fn room_chat() {}
+
+#[utoipa::path(
+    post,
+    path = "/lobby/api/rooms/{id}/chat",
+    tag = "rooms",
+    params(("id" = String, Path, description = "Room id or code")),
+    request_body = ChatRequest,
+    security(("player" = [])),
+    responses(
+        (status = 200, body = ChatMessage),
+        (status = 400, description = "`EMPTY_MESSAGE`", body = ServiceError),
+        (status = 401, description = "No token, or an expired one", body = ServiceError),
+        (status = 403, description = "`NOT_IN_ROOM`", body = ServiceError),
+        (status = 429, description = "`RATE_LIMITED`", body = ServiceError),
+    )
+)]
+fn send_chat() {}
+
+#[utoipa::path(
+    post,
+    path = "/lobby/api/rooms/{id}/heartbeat",
+    tag = "rooms",
+    params(("id" = String, Path, description = "Room id or code")),
+    request_body = RoomPlayerRequest,
+    security(("player" = [])),
+    responses(
+        (status = 200, body = ServerLobbyRoom),
+        (status = 401, description = "No token, or an expired one", body = ServiceError),
+        (status = 403, description = "`NOT_IN_ROOM`", body = ServiceError),
+        (status = 404, description = "The room is gone", body = ServiceError),
+    )
+)]
+fn room_heartbeat() {}
+
+#[utoipa::path(
+    post,
+    path = "/lobby/api/rooms/{id}/team",
+    tag = "rooms",
+    params(("id" = String, Path, description = "Room id or code")),
+    request_body = TeamChangeRequest,
+    security(("player" = [])),
+    responses(
+        (status = 200, body = ServerLobbyRoom),
+        (status = 401, description = "No token, or an expired one", body = ServiceError),
+        (status = 403, description = "The player can't move that member", body = ServiceError),
+    )
+)]
+fn change_team() {}
+
+#[utoipa::path(
+    post,
+    path = "/lobby/api/rooms/{id}/finish",
+    tag = "servers",
+    params(("id" = String, Path, description = "Room id")),
+    request_body = MatchReport,
+    security(("token" = [])),
+    responses((status = 200), (status = 401, body = ServiceError))
+)]
+fn finish_room() {}
+
+#[utoipa::path(
+    get,
+    path = "/lobby/api/rooms/{id}/voice",
+    tag = "rooms",
+    params(
+        ("id" = String, Path, description = "Room id"),
+        ("player_name" = String, Query),
+    ),
+    responses(
+        (status = 101, description = "WebSocket carrying `VoiceSignal` messages"),
+        (status = 404, body = ServiceError),
+    )
+)]
+fn room_voice() {}
+
+#[utoipa::path(
+    get,
+    path = "/lobby/api/matches",
+    tag = "rooms",
+    params(
+        ("player" = Option<String>, Query, description = "Only this player's matches"),
+        ("limit" = Option<u32>, Query, description = "Default 20, at most 100"),
+    ),
+    responses((status = 200, description = "Newest first", body = [MatchHistoryEntry]))
+)]
+fn match_history() {}
+
+#[utoipa::path(
+    post,
+    path = "/lobby/api/players",
+    tag = "players",
+    request_body = DisplayNameRequest,
+    responses(
+        (status = 200, body = PlayerAccount),
+        (status = 400, description = "The name fails `sanitize_display_name`", body = ServiceError),
+    )
+)]
+fn create_player() {}
+
+#[utoipa::path(
+    put,
+    path = "/lobby/api/players/{id}",
+    tag = "players",
+    params(("id" = String, Path, description = "Player id")),
+    request_body = DisplayNameRequest,
+    responses(
+        (status = 200, body = PlayerAccount),
+        (status = 404, body = ServiceError),
+    )
+)]
+fn rename_player() {}
+
+#[utoipa::path(
+    get,
+    path = "/lobby/api/players/{id}/rating",
+    tag = "players",
+    params(("id" = String, Path, description = "Player id")),
+    responses(
+        (status = 200, body = PlayerRating),
+        (status = 404, body = ServiceError),
+    )
+)]
+fn player_rating() {}
+
+#[utoipa::path(
+    get,
+    path = "/lobby/api/leaderboard",
+    tag = "players",
+    params(LeaderboardQuery),
+    responses((status = 200, body = Leaderboard))
+)]
+fn leaderboard() {}
+
+#[utoipa::path(
+    post,
+    path = "/lobby/api/ranked",
+    tag = "servers",
+    request_body = RankedMatch,
+    security(("token" = [])),
+    responses((status = 200), (status = 401, body = ServiceError))
+)]
+fn record_ranked_match() {}
+
+#[utoipa::path(
+    post,
+    path = "/lobby/api/players/{id}/presence",
+    tag = "friends",
+    params(("id" = String, Path, description = "Player id")),
+    request_body = PresenceRequest,
+    responses(
+        (status = 200, body = PresenceResponse),
+        (status = 404, body = ServiceError),
+    )
+)]
+fn presence() {}
+
+#[utoipa::path(
+    post,
+    path = "/lobby/api/players/{id}/friends",
+    tag = "friends",
+    params(("id" = String, Path, description = "Player id")),
+    request_body = AddFriendRequest,
+    responses(
+        (status = 200, body = [Friend]),
+        (status = 404, description = "Unknown friend id", body = ServiceError),
+    )
+)]
+fn add_friend() {}
+
+#[utoipa::path(
+    delete,
+    path = "/lobby/api/players/{id}/friends/{friend_id}",
+    tag = "friends",
+    params(
+        ("id" = String, Path, description = "Player id"),
+        ("friend_id" = String, Path),
+    ),
+    responses((status = 200, body = [Friend]))
+)]
+fn remove_friend() {}
+
+#[utoipa::path(
+    post,
+    path = "/lobby/api/players/{id}/friends/{friend_id}/invite",
+    tag = "friends",
+    params(
+        ("id" = String, Path, description = "Player id"),
+        ("friend_id" = String, Path),
+    ),
+    request_body = RoomInviteRequest,
+    responses(
+        (status = 200),
+        (status = 403, description = "They aren't friends", body = ServiceError),
+    )
+)]
+fn invite_friend() {}
+
+#[utoipa::path(
+    post,
+    path = "/lobby/api/parties",
+    tag = "parties",
+    request_body = PartyRequest,
+    responses((status = 200, body = Party))
+)]
+fn create_party() {}
+
+#[utoipa::path(
+    post,
+    path = "/lobby/api/parties/join",
+    tag = "parties",
+    request_body(content = PartyRequest, description = "With `code`"),
+    responses(
+        (status = 200, body = Party),
+        (status = 404, description = "Unknown code", body = ServiceError),
+        (status = 409, description = "`PARTY_FULL`", body = ServiceError),
+    )
+)]
+fn join_party() {}
+
+#[utoipa::path(
+    post,
+    path = "/lobby/api/parties/{id}/invite",
+    tag = "parties",
+    params(("id" = String, Path, description = "Party id")),
+    request_body = PartyInviteRequest,
+    responses(
+        (status = 200, body = Party),
+        (status = 403, description = "`NOT_PARTY_LEADER`", body = ServiceError),
+    )
+)]
+fn invite_to_party() {}
+
+#[utoipa::path(
+    post,
+    path = "/lobby/api/parties/{id}/queue",
+    tag = "parties",
+    params(("id" = String, Path, description = "Party id")),
+    request_body = PartyQueueRequest,
+    responses(
+        (status = 200, body = Party),
+        (status = 403, description = "`NOT_PARTY_LEADER`", body = ServiceError),
+    )
+)]
+fn queue_party() {}
+
+#[utoipa::path(
+    post,
+    path = "/lobby/api/parties/{id}/leave",
+    tag = "parties",
+    params(("id" = String, Path, description = "Party id")),
+    request_body = PartyRequest,
+    responses((status = 200))
+)]
+fn leave_party() {}
+
+#[utoipa::path(
+    post,
+    path = "/lobby/api/parties/{id}/heartbeat",
+    tag = "parties",
+    params(("id" = String, Path, description = "Party id")),
+    request_body = PartyRequest,
+    responses(
+        (status = 200, body = Party),
+        (status = 404, description = "The party is gone", body = ServiceError),
+    )
+)]
+fn party_heartbeat() {}
+
+#[utoipa::path(
+    get,
+    path = "/lobby/api/parties/invites",
+    tag = "parties",
+    params(("player_name" = String, Query)),
+    responses((status = 200, body = [PartyInvite]))
+)]
+fn party_invites() {}
+
+#[utoipa::path(
+    get,
+    path = "/lobby/api/status",
+    tag = "service",
+    responses((status = 200, body = ServiceStatusResponse))
+)]
+fn status() {}
+
+#[utoipa::path(
+    get,
+    path = "/lobby/api/regions",
+    tag = "service",
+    responses((status = 200, body = [RegionEndpoint]))
+)]
+fn regions() {}
+
+#[utoipa::path(
+    post,
+    path = "/lobby/api/servers/heartbeat",
+    tag = "servers",
+    request_body = ServerHeartbeat,
+    security(("token" = [])),
+    responses((status = 200), (status = 401, body = ServiceError))
+)]
+fn server_heartbeat() {}
+
+#[utoipa::path(
+    post,
+    path = "/lobby/api/servers/anticheat",
+    tag = "servers",
+    request_body = AntiCheatReport,
+    security(("token" = [])),
+    responses((status = 200), (status = 401, body = ServiceError))
+)]
+fn anti_cheat_report() {}
+
+#[utoipa::path(
+    post,
+    path = "/lobby/api/telemetry/hardware",
+    tag = "telemetry",
+    request_body = HardwareReport,
+    responses((status = 200))
+)]
+fn hardware_report() {}
+
+#[utoipa::path(
+    get,
+    path = "/lobby/api/telemetry/presets",
+    tag = "telemetry",
+    responses((status = 200, body = [PresetRecommendation]))
+)]
+fn preset_recommendations() {}
+
+#[utoipa::path(
+    get,
+    path = "/lobby/api/admin/rooms",
+    tag = "admin",
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Every room, started or not", body = [TrackedRoom]),
+        (status = 401, body = ServiceError),
+    )
+)]
+fn admin_rooms() {}
+
+#[utoipa::path(
+    get,
+    path = "/lobby/api/admin/audit",
+    tag = "admin",
+    params(AuditQuery),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Newest first", body = [AuditEntry]),
+        (status = 401, body = ServiceError),
+    )
+)]
+fn admin_audit() {}
+
+#[utoipa::path(
+    get,
+    path = "/lobby/api/admin/telemetry/hardware",
+    tag = "admin",
+    security(("token" = [])),
+    responses(
+        (status = 200, body = [HardwareSummary]),
+        (status = 401, body = ServiceError),
+    )
+)]
+fn admin_hardware() {}
+
+#[utoipa::path(
+    get,
+    path = "/matchmaker/ws",
+    tag = "matchmaker",
+    params(
+        ("game_mode" = String, Query, description = "A `GameMode` id"),
+        ("level" = String, Query),
+        ("player_id" = Option<String>, Query, description = "Needed for ranked"),
+        ("trace_id" = Option<String>, Query),
+        ("room_id" = Option<String>, Query, description = "Lobby room, signed into the connect token"),
+        ("teams" = Option<usize>, Query),
+        ("friendly_fire" = Option<bool>, Query),
+        ("rules" = Option<String>, Query, description = "`RuleSet` as RON"),
+        ("region" = Option<String>, Query, description = "Region with the lowest ping"),
+        ("pings" = Option<String>, Query, description = "Pings per region, e.g. `eu:42,us:120`"),
+        ("party_id" = Option<String>, Query),
+        ("party_size" = Option<usize>, Query),
+        ("bot_fill" = Option<usize>, Query, description = "Fill the match with bots up to this many players"),
+        ("bot_style" = Option<String>, Query, description = "A `BotStyle` id"),
+    ),
+    responses(
+        (status = 101, description = "WebSocket running bevygap's session and ticket exchange"),
+        (status = 400, body = ServiceError),
+        (status = 403, description = "`NOT_IN_ROOM`", body = ServiceError),
+        (status = 429, body = ServiceError),
+        (status = 503, description = "`TOO_MANY_DEPLOYMENTS`", body = ServiceError),
+    )
+)]
+fn matchmaker_ws() {}
+
+#[utoipa::path(
+    get,
+    path = "/matchmaker/healthz",
+    tag = "matchmaker",
+    responses(
+        (status = 200, description = "Connected to NATS"),
+        (status = 503, description = "NATS or the KV buckets are unreachable"),
+    )
+)]
+fn matchmaker_health() {}
+
+#[utoipa::path(
+    get,
+    path = "/matchmaker/shards",
+    tag = "matchmaker",
+    responses((status = 200, description = "The shards this instance owns", body = [ShardMetrics]))
+)]
+fn matchmaker_shards() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn document_covers_both_services() {
+        let doc = api_doc();
+        assert!(doc.paths.paths.contains_key("/lobby/api/rooms"));
+        assert!(doc.paths.paths.contains_key("/matchmaker/ws"));
+        let schemas = &doc.components.as_ref().unwrap().schemas;
+        for schema in [
+            "ServerLobbyRoom",
+            "RoomListResponse",
+            "Party",
+            "ServiceError",
+        ] {
+            assert!(schemas.contains_key(schema), "{} is missing", schema);
+        }
+        // Serves as JSON
+        assert!(doc.to_json().unwrap().starts_with('{'));
+    }
+
+    #[test]
+    fn cors_knows_every_lobby_route() {
+        for (path, item) in api_doc().paths.paths {
+            let Some(route) = path.strip_prefix("/lobby/api") else {
+                continue;
+            };
+            let methods = crate::cors::route_methods(route).unwrap_or_default();
+            let documented = [
+                ("GET", item.get.is_some()),
+                ("POST", item.post.is_some()),
+                ("PUT", item.put.is_some()),
+                ("DELETE", item.delete.is_some()),
+            ];
+            for (method, _) in documented.iter().filter(|(_, documented)| *documented) {
+                assert!(methods.contains(method), "CORS refuses {} {}", method, path);
+            }
+        }
+    }
+}
@@ -0,0 +1,212 @@
+// Parties: friends who want to play together form a party in the lobby and queue as one.
+// The lobby service keeps the parties (see "Parties" in docs/lobby-service.md). Only the
+// leader queues: the other members see the queue on their next party heartbeat and open
+// their own matchmaker connection with the same party id. The matchmaker holds a party's
+// tickets until all of them are in and then puts them in one match. Parties use room codes,
+// so inviting someone is telling them a code like `K7QX2M`, or inviting them by name.
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::api::MatchmakingRequest;
+
+/// Most players in one party, a party has to fit in one match
+pub const MAX_PARTY_SIZE: usize = 4;
+
+/// A party as the lobby service answers it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Party {
+    pub id: String,
+    /// Room code style code others join with
+    pub code: String,
+    /// Name of the member who queues, the creator until they leave
+    pub leader: String,
+    /// Names of the members, the leader included, in the order they joined
+    pub members: Vec<String>,
+    /// Names invited by the leader who haven't joined yet
+    #[serde(default)]
+    pub invited: Vec<String>,
+    /// The leader's last queue, members follow it into matchmaking
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue: Option<PartyQueue>,
+}
+
+impl Party {
+    pub fn is_leader(&self, player_name: &str) -> bool {
+        self.leader == player_name
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.members.len() >= MAX_PARTY_SIZE
+    }
+
+    /// The party without `player_name`. A leaving leader hands over to whoever joined
+    /// next, None when nobody is left and the party closes.
+    pub fn without(mut self, player_name: &str) -> Option<Party> {
+        self.members.retain(|member| member != player_name);
+        if self.leader == player_name {
+            self.leader = self.members.first()?.clone();
+        }
+        (!self.members.is_empty()).then_some(self)
+    }
+}
+
+/// What the leader queued for
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PartyQueue {
+    pub game_mode: String,
+    pub level: String,
+    /// Region the leader queued in, the members queue there too
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// Unix timestamp in milliseconds, a newer one is a new queue
+    pub queued_at: u64,
+}
+
+/// Body of `POST /lobby/api/parties`, `/parties/join`, `/parties/{id}/leave` and
+/// `/parties/{id}/heartbeat`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PartyRequest {
+    pub player_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player_id: Option<String>,
+    /// Code of the party to join, only read by `join`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+/// Body of `POST /lobby/api/parties/{id}/invite`, only the leader may invite
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PartyInviteRequest {
+    /// Who is asking
+    pub player_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player_id: Option<String>,
+    /// Name of the player to invite
+    pub invitee: String,
+}
+
+/// Body of `POST /lobby/api/parties/{id}/queue`, only the leader may queue
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PartyQueueRequest {
+    pub player_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player_id: Option<String>,
+    pub game_mode: String,
+    pub level: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+}
+
+/// One entry of `GET /lobby/api/parties/invites?player_name=..`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PartyInvite {
+    pub party_id: String,
+    pub code: String,
+    pub leader: String,
+}
+
+/// Tickets that can go into a match together, in the order their first ticket arrived:
+/// a solo ticket on its own, a party once all `party_size` of its tickets are in. Parties
+/// still missing members keep waiting.
+pub fn ready_units(tickets: &[MatchmakingRequest]) -> Vec<Vec<&MatchmakingRequest>> {
+    let mut units: Vec<Vec<&MatchmakingRequest>> = Vec::new();
+    let mut parties: HashMap<&str, usize> = HashMap::new();
+    for ticket in tickets {
+        match &ticket.party_id {
+            Some(party_id) => match parties.get(party_id.as_str()) {
+                Some(&unit) => units[unit].push(ticket),
+                None => {
+                    parties.insert(party_id, units.len());
+                    units.push(vec![ticket]);
+                }
+            },
+            None => units.push(vec![ticket]),
+        }
+    }
+    units.retain(|unit| {
+        let size = unit[0].party_size.unwrap_or(1).max(1);
+        unit.len() >= size
+    });
+    units
+}
+
+/// Units for one match of up to `capacity` players, first come first served. A party that
+/// doesn't fit anymore leaves room for smaller units behind it instead of being split.
+pub fn fill_match<'a>(
+    units: &[Vec<&'a MatchmakingRequest>],
+    capacity: usize,
+) -> Vec<&'a MatchmakingRequest> {
+    let mut tickets = Vec::new();
+    for unit in units {
+        if tickets.len() + unit.len() <= capacity {
+            tickets.extend(unit.iter().copied());
+        }
+    }
+    tickets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn ticket(player_id: &str, party: Option<(&str, usize)>) -> MatchmakingRequest {
+        MatchmakingRequest {
+            player_id: player_id.to_string(),
+            game_mode: "casual".to_string(),
+            region: None,
+            pings: BTreeMap::new(),
+            party_id: party.map(|(id, _)| id.to_string()),
+            party_size: party.map(|(_, size)| size),
+        }
+    }
+
+    fn ids(tickets: &[&MatchmakingRequest]) -> Vec<String> {
+        tickets.iter().map(|t| t.player_id.clone()).collect()
+    }
+
+    #[test]
+    fn parties_land_in_one_match() {
+        let tickets = vec![
+            ticket("solo1", None),
+            ticket("a1", Some(("a", 3))),
+            ticket("b1", Some(("b", 2))),
+            ticket("a2", Some(("a", 3))),
+            ticket("solo2", None),
+            ticket("a3", Some(("a", 3))),
+        ];
+        // Party b is still waiting for its second member
+        let units = ready_units(&tickets);
+        assert_eq!(units.len(), 3);
+        assert_eq!(ids(&units[1]), vec!["a1", "a2", "a3"]);
+
+        // With 3 seats the party doesn't fit next to solo1, and solo2 gets the seat
+        assert_eq!(ids(&fill_match(&units, 4)), vec!["solo1", "a1", "a2", "a3"]);
+        assert_eq!(ids(&fill_match(&units, 3)), vec!["solo1", "solo2"]);
+    }
+
+    #[test]
+    fn leaders_hand_over_when_they_leave() {
+        let party = Party {
+            id: "p".to_string(),
+            code: "K7QX2M".to_string(),
+            leader: "Nova".to_string(),
+            members: vec!["Nova".to_string(), "Vex".to_string(), "Io".to_string()],
+            ..Default::default()
+        };
+        assert!(party.is_leader("Nova"));
+        assert!(!party.is_full());
+
+        let party = party.without("Nova").unwrap();
+        assert_eq!(party.leader, "Vex");
+        let party = party.without("Io").unwrap();
+        assert_eq!(party.leader, "Vex");
+        assert_eq!(party.without("Vex"), None);
+    }
+}
@@ -3,16 +3,134 @@ use leafwing_input_manager::prelude::*;
 use lightyear::prelude::*;
 use serde::{Deserialize, Serialize};
 
-// Simple player actions for platformer
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Actionlike)]
+use crate::countdown::Countdown;
+use crate::game_config::GameConfig;
+use crate::highlights::Highlight;
+use crate::level::WorldBounds;
+use crate::network::NetworkConfig;
+
+// Simple player actions for platformer. Each action has a stable id that is what goes
+// over the wire, so new actions never shift the existing ones. Pick new ids from the
+// range the action belongs to, and never reuse the id of a removed action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Actionlike)]
+#[repr(u16)]
 pub enum PlayerActions {
-    MoveLeft,
-    MoveRight,
-    Jump,
+    MoveLeft = 0,
+    MoveRight = 1,
+    Jump = 2,
+    // Ghost mode: drop a hazard (ignored for everyone but the ghost). Older than the
+    // ranges, so it keeps the id it always had.
+    Sabotage = 3,
+}
+
+/// Action ids for moving around (Down, Dash, ...)
+pub const MOVEMENT_ACTION_IDS: std::ops::RangeInclusive<u16> = 0..=31;
+/// Action ids for game mode abilities
+pub const ABILITY_ACTION_IDS: std::ops::RangeInclusive<u16> = 32..=63;
+/// Action ids for social actions (Emote, ...)
+pub const SOCIAL_ACTION_IDS: std::ops::RangeInclusive<u16> = 64..=95;
+
+impl PlayerActions {
+    pub const ALL: [PlayerActions; 4] = [
+        PlayerActions::MoveLeft,
+        PlayerActions::MoveRight,
+        PlayerActions::Jump,
+        PlayerActions::Sabotage,
+    ];
+
+    /// Variant names, in `ALL` order
+    const NAMES: [&'static str; 4] = ["MoveLeft", "MoveRight", "Jump", "Sabotage"];
+
+    /// The id this action is sent as
+    pub fn id(self) -> u16 {
+        self as u16
+    }
+
+    pub fn from_id(id: u16) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.id() == id)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PlayerActions::MoveLeft => "MoveLeft",
+            PlayerActions::MoveRight => "MoveRight",
+            PlayerActions::Jump => "Jump",
+            PlayerActions::Sabotage => "Sabotage",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+}
+
+// Written like a derived unit variant but with the stable id as the variant index, so
+// binary formats get the same bytes the derive used to write, and text formats the name
+impl Serialize for PlayerActions {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_unit_variant("PlayerActions", self.id() as u32, self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for PlayerActions {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_enum("PlayerActions", &Self::NAMES, ActionVisitor)
+    }
+}
+
+struct ActionVisitor;
+
+impl<'de> serde::de::Visitor<'de> for ActionVisitor {
+    type Value = PlayerActions;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a player action id or name")
+    }
+
+    fn visit_enum<A: serde::de::EnumAccess<'de>>(self, data: A) -> Result<Self::Value, A::Error> {
+        use serde::de::VariantAccess;
+        let (ActionTag(action), variant) = data.variant()?;
+        variant.unit_variant()?;
+        Ok(action)
+    }
+}
+
+// The variant part of an action: its id, or its name in text formats
+struct ActionTag(PlayerActions);
+
+impl<'de> Deserialize<'de> for ActionTag {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_identifier(ActionTagVisitor)
+    }
+}
+
+struct ActionTagVisitor;
+
+impl serde::de::Visitor<'_> for ActionTagVisitor {
+    type Value = ActionTag;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a player action id or name")
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, id: u64) -> Result<Self::Value, E> {
+        u16::try_from(id)
+            .ok()
+            .and_then(PlayerActions::from_id)
+            .map(ActionTag)
+            .ok_or_else(|| E::invalid_value(serde::de::Unexpected::Unsigned(id), &self))
+    }
+
+    fn visit_str<E: serde::de::Error>(self, name: &str) -> Result<Self::Value, E> {
+        PlayerActions::from_name(name)
+            .map(ActionTag)
+            .ok_or_else(|| E::unknown_variant(name, &PlayerActions::NAMES))
+    }
 }
 
 // Player component with position and velocity
-#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
 pub struct Player {
     pub velocity: Vec2,
     pub grounded: bool,
@@ -28,7 +146,8 @@ impl Default for Player {
 }
 
 // Transform component for position
-#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
 pub struct PlayerTransform {
     pub translation: Vec3,
 }
@@ -41,12 +160,213 @@ impl Default for PlayerTransform {
     }
 }
 
+// How a platform behaves, set per platform in the level file
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, Reflect)]
+#[serde(rename_all = "lowercase")]
+pub enum PlatformKind {
+    #[default]
+    Static,
+    /// Loops through `path` (offsets from where the platform is placed) at `speed` units/s
+    Moving { path: Vec<Vec2>, speed: f32 },
+    /// Falls away `delay` seconds after a player steps on it and comes back after `respawn`
+    Crumbling { delay: f32, respawn: f32 },
+    /// Launches players that land on it upwards with `strength`
+    Bouncy { strength: f32 },
+}
+
+/// Width of a level platform unless the level says otherwise
+pub const PLATFORM_WIDTH: f32 = 200.0;
+
 // Platform component for level geometry
-#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq)]
-pub struct Platform;
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct Platform {
+    pub kind: PlatformKind,
+    /// Left to right, the floor spans the whole level
+    pub width: f32,
+    /// Where the level placed the platform, moving platforms travel relative to it
+    pub origin: Vec2,
+    /// False while a crumbling platform has fallen away
+    pub solid: bool,
+    /// Seconds since a crumbling platform was stepped on (or fell), None while untouched
+    pub crumble_timer: Option<f32>,
+}
+
+impl Platform {
+    pub fn new(kind: PlatformKind, origin: Vec2) -> Self {
+        Self {
+            kind,
+            width: PLATFORM_WIDTH,
+            origin,
+            solid: true,
+            crumble_timer: None,
+        }
+    }
+
+    pub fn with_width(mut self, width: f32) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Position of the platform `elapsed` seconds into the match.
+    /// Only depends on the level data and the time, so server and clients agree without syncing.
+    pub fn position_at(&self, elapsed: f32) -> Vec2 {
+        let PlatformKind::Moving { path, speed } = &self.kind else {
+            return self.origin;
+        };
+        // Closed loop: origin -> path[0] -> ... -> path[n-1] -> origin
+        let points: Vec<Vec2> = std::iter::once(Vec2::ZERO)
+            .chain(path.iter().copied())
+            .collect();
+        let segments: Vec<(Vec2, Vec2)> = points
+            .iter()
+            .zip(points.iter().cycle().skip(1))
+            .map(|(a, b)| (*a, *b))
+            .collect();
+        let loop_length: f32 = segments.iter().map(|(a, b)| a.distance(*b)).sum();
+        if loop_length <= 0.0 || *speed <= 0.0 {
+            return self.origin;
+        }
+
+        let mut distance = (elapsed * speed).rem_euclid(loop_length);
+        for (a, b) in segments {
+            let length = a.distance(b);
+            if distance <= length {
+                return self.origin + a.lerp(b, distance / length.max(f32::EPSILON));
+            }
+            distance -= length;
+        }
+        self.origin
+    }
+}
+
+// Co-op pressure plate, pressed while any player stands on it
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct PressurePlate {
+    pub id: String,
+    pub pressed: bool,
+}
+
+// Co-op door, opens while all of its plates are pressed at the same time
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct Door {
+    pub plates: Vec<String>,
+    pub latch: bool,
+    pub open: bool,
+}
+
+// Collectible coin, removed by the server when a player touches it
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct Pickup {
+    pub value: u32,
+}
+
+// Points a player has collected this match
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Default, Reflect)]
+#[reflect(Component)]
+pub struct PlayerScore {
+    pub score: u32,
+}
+
+// Race checkpoint, players have to pass them in `index` order
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct Checkpoint {
+    pub index: usize,
+    pub radius: f32,
+}
+
+// A player's progress through the race, replicated to everyone for the standings
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Default, Reflect)]
+#[reflect(Component)]
+pub struct RaceProgress {
+    /// Race time (seconds) at which each checkpoint was reached, in checkpoint order
+    pub splits: Vec<f32>,
+    /// Race time of the last checkpoint, once all of them are done
+    pub finished: Option<f32>,
+    /// Seconds added for falling out of the world, counted in every later split
+    #[serde(default)]
+    pub penalty: f32,
+}
+
+// Player was stomped on and can't move until `remaining` seconds have passed
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct Stunned {
+    pub remaining: f32,
+}
+
+// King-of-the-hill zone. Only the active zone scores, and only while a single player holds it.
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct ScoringZone {
+    pub radius: f32,
+    pub active: bool,
+    /// More than one player inside, nobody scores
+    pub contested: bool,
+}
+
+// Ghost mode roles. Assigned by the server and only ever sent to the player it belongs to.
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub enum PlayerRole {
+    Runner,
+    Ghost,
+}
+
+// Side a player is on in team modes, numbered from 0. Picks the level's spawn group.
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Component)]
+pub struct Team(pub usize);
+
+impl Team {
+    /// Name tags and the HUD are tinted with this, out of range teams wrap around
+    pub fn color(&self) -> Color {
+        TEAM_COLORS[self.0 % TEAM_COLORS.len()]
+    }
+
+    /// "Team A", "Team B", ...
+    pub fn name(&self) -> String {
+        format!("Team {}", (b'A' + (self.0 % 26) as u8) as char)
+    }
+}
+
+/// One color per team, distinct from each other at a glance
+pub const TEAM_COLORS: [Color; 4] = [
+    Color::srgb(0.25, 0.55, 1.0),
+    Color::srgb(1.0, 0.45, 0.2),
+    Color::srgb(0.35, 0.9, 0.4),
+    Color::srgb(0.9, 0.4, 0.9),
+];
+
+// Server -> client: how the match splits players into teams, sent once connected. Both
+// sides keep it as a resource, the stomp rules read it.
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct TeamRules {
+    /// 0 for free-for-all
+    pub teams: usize,
+    /// Teammates can stomp each other
+    pub friendly_fire: bool,
+}
+
+// Trap dropped by the ghost, knocks runners back on contact until it expires
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct Hazard;
+
+// Server -> client: tells a player which ghost mode role they got
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RoleAssignment {
+    pub role: PlayerRole,
+}
 
 // Color component for visual representation
-#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
 pub struct PlayerColor {
     pub color: Color,
 }
@@ -54,17 +374,38 @@ pub struct PlayerColor {
 impl Default for PlayerColor {
     fn default() -> Self {
         Self {
-            color: Color::srgb(0.0, 0.5, 1.0),
+            color: PLAYER_COLORS[0],
+        }
+    }
+}
+
+impl PlayerColor {
+    /// Color `index` of the palette players pick from, out of range indexes wrap around
+    pub fn from_palette(index: usize) -> Self {
+        Self {
+            color: PLAYER_COLORS[index % PLAYER_COLORS.len()],
         }
     }
 }
 
+/// Colors players can pick on the settings screen, the first one is the default
+pub const PLAYER_COLORS: [Color; 6] = [
+    Color::srgb(0.0, 0.5, 1.0),
+    Color::srgb(1.0, 0.3, 0.3),
+    Color::srgb(0.3, 0.85, 0.3),
+    Color::srgb(1.0, 0.8, 0.1),
+    Color::srgb(0.7, 0.35, 1.0),
+    Color::srgb(1.0, 0.55, 0.1),
+];
+
 // Player animation state for 3D character
-#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
 pub struct PlayerAnimationState {
     pub is_moving: bool,
     pub is_jumping: bool,
     pub facing_left: bool,
+    pub is_stunned: bool,
 }
 
 impl Default for PlayerAnimationState {
@@ -73,12 +414,14 @@ impl Default for PlayerAnimationState {
             is_moving: false,
             is_jumping: false,
             facing_left: false,
+            is_stunned: false,
         }
     }
 }
 
 // Player ID for differentiation in multiplayer
-#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
 pub struct PlayerId {
     pub id: u32,
 }
@@ -89,24 +432,142 @@ impl Default for PlayerId {
     }
 }
 
+// Display name shown above a player, see `sanitize_display_name`
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct PlayerName {
+    pub name: String,
+}
+
+// Marks a server-controlled player, nameplates show a bot badge for it
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct BotTag;
+
+/// Lobby room a client connection, player or pickup belongs to on the server, not
+/// replicated. The server reads it from the connect token the matchmaker signed, and only
+/// entities of the same room interact. Quick match players share the room with the empty
+/// id, local players have none.
+#[derive(Component, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MatchRoom(pub String);
+
+// What other players' nameplates show besides the name, kept up to date by the server
+#[derive(Component, Serialize, Deserialize, Clone, Debug, Default, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct PlayerStatus {
+    /// Hosts the player's lobby room, the first player the server saw in it
+    pub host: bool,
+    /// Latest round trip the player's client measured, None for bots and local players
+    pub ping_ms: Option<u16>,
+}
+
+// Client -> server: the display name and color the player picked, sent once connected
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SetPlayerProfile {
+    pub name: String,
+    /// Index into `PLAYER_COLORS`
+    pub color: usize,
+    /// Lobby account id, ranked matches rate the account instead of the name
+    pub player_id: Option<String>,
+    /// Team the lobby room put the player on, None outside team rooms
+    pub team: Option<usize>,
+}
+
+// Client -> server: fixed ticks this client has simulated since it connected, sent every
+// `CLOCK_REPORT_SECS` so the server can tell when its clock runs fast or slow
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ClockReport {
+    pub fixed_ticks: u32,
+}
+
+// Server -> client: your player fell out of the world and was put back on a spawn point,
+// at the cost of some score or race time
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RespawnEvent {
+    pub points_lost: u32,
+    pub seconds_added: f32,
+}
+
+// Server -> client: multiply your fixed tick rate by `speed`, see `ClockSkew`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TickRateHint {
+    pub speed: f32,
+}
+
+// Server -> client: the server's `SimTick` and the checksum of its world at that tick,
+// sent every `TICK_SYNC_INTERVAL` ticks
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct TickSync {
+    pub tick: u32,
+    pub checksum: u64,
+}
+
+// Server -> client: the server is going down in `seconds`, finish up
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ServerShuttingDown {
+    pub seconds: f32,
+}
+
+// Server -> client: the ranked match is over and this client's player came `place` of
+// `players`. The new rating shows in the lobby once the lobby service has it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RankedMatchOver {
+    pub place: usize,
+    pub players: usize,
+}
+
+// Server -> client: awards of the match that just ended, for the results screen
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MatchHighlights {
+    pub highlights: Vec<Highlight>,
+}
+
+// Client -> server, once a second on `ProbeChannel`: probes that never arrive are the
+// packet loss, and the server echoes the rest so the client can time the round trip
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct NetworkProbe {
+    pub seq: u32,
+    /// Round trip of the last echoed probe, in milliseconds
+    pub rtt_ms: Option<u16>,
+    /// Prediction corrections since connecting, see `is_prediction_correction`
+    pub corrections: u32,
+}
+
+// Server -> client: the probe with this `seq` arrived
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ProbeEcho {
+    pub seq: u32,
+}
+
 // Channel for reliable messages
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Channel1;
 
+// Channel for network probes, unreliable so lost packets stay lost
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProbeChannel;
+
 // Room management data structures
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
 pub struct RoomInfo {
     pub room_id: String,
+    /// What players see and type, the id stays internal
+    #[serde(default)]
+    pub code: String,
     pub current_players: u32,
     pub max_players: u32,
     pub host_name: String,
     pub game_mode: String,
-}
-
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
-pub struct MatchmakingRequest {
-    pub player_id: String,
-    pub game_mode: String,
+    #[serde(default)]
+    pub level: String,
+    /// Locked rooms need a password to join
+    #[serde(default)]
+    pub has_password: bool,
+    #[serde(default)]
+    pub map: String,
+    /// Where the room's game server runs, once it has one
+    #[serde(default)]
+    pub region: Option<String>,
 }
 
 // Messages for room operations
@@ -183,7 +644,48 @@ impl Plugin for ProtocolPlugin {
         app.register_component::<PlayerId>()
             .add_prediction(PredictionMode::Once);
 
+        // Crumbling platforms change state during the match
         app.register_component::<Platform>()
+            .add_prediction(PredictionMode::Simple);
+
+        // Co-op triggers change state during the match, keep predicted copies in sync
+        app.register_component::<PressurePlate>()
+            .add_prediction(PredictionMode::Simple);
+
+        app.register_component::<Door>()
+            .add_prediction(PredictionMode::Simple);
+
+        app.register_component::<Pickup>()
+            .add_prediction(PredictionMode::Once);
+
+        app.register_component::<PlayerScore>()
+            .add_prediction(PredictionMode::Simple);
+
+        app.register_component::<Hazard>()
+            .add_prediction(PredictionMode::Once);
+
+        app.register_component::<ScoringZone>()
+            .add_prediction(PredictionMode::Simple);
+
+        app.register_component::<Stunned>()
+            .add_prediction(PredictionMode::Full);
+
+        app.register_component::<Checkpoint>()
+            .add_prediction(PredictionMode::Once);
+
+        app.register_component::<RaceProgress>()
+            .add_prediction(PredictionMode::Simple);
+
+        app.register_component::<PlayerName>()
+            .add_prediction(PredictionMode::Simple);
+
+        app.register_component::<PlayerStatus>()
+            .add_prediction(PredictionMode::Simple);
+
+        app.register_component::<BotTag>()
+            .add_prediction(PredictionMode::Once);
+
+        app.register_component::<Team>()
             .add_prediction(PredictionMode::Once);
 
         // Register channel for room messages
@@ -191,11 +693,75 @@ impl Plugin for ProtocolPlugin {
             mode: ChannelMode::OrderedReliable(ReliableSettings::default()),
             ..default()
         });
+        app.add_channel::<ProbeChannel>(ChannelSettings {
+            mode: ChannelMode::UnorderedUnreliable,
+            ..default()
+        });
+
+        // Ghost mode role, sent to each player individually
+        app.add_message::<RoleAssignment>()
+            .add_direction(NetworkDirection::ServerToClient);
+
+        app.add_message::<TeamRules>()
+            .add_direction(NetworkDirection::ServerToClient);
 
-        // Register input
-        app.add_plugins(lightyear::prelude::input::leafwing::InputPlugin::<
-            PlayerActions,
-        >::default());
+        // Level edges, sent on connecting and whenever the level rotates
+        app.add_message::<WorldBounds>()
+            .add_direction(NetworkDirection::ServerToClient);
+
+        app.add_message::<SetPlayerProfile>()
+            .add_direction(NetworkDirection::ClientToServer);
+
+        // Falling out of the world, for the client's shake and fade
+        app.add_message::<RespawnEvent>()
+            .add_direction(NetworkDirection::ServerToClient);
+
+        // Tick rate hints for clients whose clock drifts
+        app.add_message::<ClockReport>()
+            .add_direction(NetworkDirection::ClientToServer);
+        app.add_message::<TickRateHint>()
+            .add_direction(NetworkDirection::ServerToClient);
+
+        app.add_message::<TickSync>()
+            .add_direction(NetworkDirection::ServerToClient);
+
+        // Countdown before the server drains its players and exits
+        app.add_message::<ServerShuttingDown>()
+            .add_direction(NetworkDirection::ServerToClient);
+
+        // End of a ranked match, just before the server drains
+        app.add_message::<RankedMatchOver>()
+            .add_direction(NetworkDirection::ServerToClient);
+
+        // Awards at the end of any match
+        app.add_message::<MatchHighlights>()
+            .add_direction(NetworkDirection::ServerToClient);
+
+        // Connection quality probes, summarized in the match report
+        app.add_message::<NetworkProbe>()
+            .add_direction(NetworkDirection::ClientToServer);
+        app.add_message::<ProbeEcho>()
+            .add_direction(NetworkDirection::ServerToClient);
+
+        // Physics of the match, sent on connecting
+        app.add_message::<GameConfig>()
+            .add_direction(NetworkDirection::ServerToClient);
+
+        // When players get control, sent when the countdown starts and on connecting during it
+        app.add_message::<Countdown>()
+            .add_direction(NetworkDirection::ServerToClient);
+
+        // Register input, buffered per tick and forwarded to the other clients
+        let network = *app.world_mut().get_resource_or_init::<NetworkConfig>();
+        app.add_plugins(
+            lightyear::prelude::input::leafwing::InputPlugin::<PlayerActions> {
+                config: lightyear::prelude::input::InputConfig::<PlayerActions> {
+                    packet_redundancy: network.input_redundancy,
+                    rebroadcast_inputs: network.rebroadcast_inputs,
+                    ..default()
+                },
+            },
+        );
     }
 }
 
@@ -214,3 +780,60 @@ pub fn protocol() -> ProtocolPlugin {
 //
 // Remember to register new components in the ProtocolPlugin build() method above!
 // ==== END CUSTOM GAME CODE AREA ====
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::de::value::{Error, U32Deserializer};
+
+    // What `#[derive(Serialize, Deserialize)]` on PlayerActions wrote before the ids
+    // were pinned: the variant's position
+    const LEGACY_IDS: [(u32, PlayerActions); 4] = [
+        (0, PlayerActions::MoveLeft),
+        (1, PlayerActions::MoveRight),
+        (2, PlayerActions::Jump),
+        (3, PlayerActions::Sabotage),
+    ];
+
+    #[test]
+    fn legacy_action_streams_still_decode() {
+        for (id, action) in LEGACY_IDS {
+            assert_eq!(action.id() as u32, id);
+            let decoded = PlayerActions::deserialize(U32Deserializer::<Error>::new(id));
+            assert_eq!(decoded, Ok(action));
+        }
+        // Actions from a newer build are rejected, not mistaken for another action
+        assert!(PlayerActions::deserialize(U32Deserializer::<Error>::new(40)).is_err());
+
+        // Text formats keep using the names
+        for action in PlayerActions::ALL {
+            let json = serde_json::to_string(&action).unwrap();
+            assert_eq!(json, format!("\"{}\"", action.name()));
+            assert_eq!(
+                serde_json::from_str::<PlayerActions>(&json).unwrap(),
+                action
+            );
+        }
+    }
+
+    #[test]
+    fn action_ids_are_unique_and_reserved() {
+        for (action, name) in PlayerActions::ALL.into_iter().zip(PlayerActions::NAMES) {
+            assert_eq!(action.name(), name);
+            assert_eq!(PlayerActions::from_id(action.id()), Some(action));
+            let id = action.id();
+            assert!(
+                MOVEMENT_ACTION_IDS.contains(&id)
+                    || ABILITY_ACTION_IDS.contains(&id)
+                    || SOCIAL_ACTION_IDS.contains(&id),
+                "{:?} has id {} outside the reserved ranges",
+                action,
+                id
+            );
+        }
+        let mut ids: Vec<u16> = PlayerActions::ALL.iter().map(|a| a.id()).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), PlayerActions::ALL.len());
+    }
+}
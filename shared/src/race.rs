@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+use crate::protocol_plugin::RaceProgress;
+
+// One line of the live standings
+#[derive(Clone, Debug, PartialEq)]
+pub struct Standing {
+    pub player_id: u32,
+    pub checkpoints: usize,
+    pub last_split: Option<f32>,
+    pub finished: Option<f32>,
+}
+
+/// Order racers for the standings: finishers by time, then everyone else by
+/// checkpoints passed, ties broken by who got to their last checkpoint first.
+pub fn standings<'a>(racers: impl IntoIterator<Item = (u32, &'a RaceProgress)>) -> Vec<Standing> {
+    let mut standings: Vec<Standing> = racers
+        .into_iter()
+        .map(|(player_id, progress)| Standing {
+            player_id,
+            checkpoints: progress.splits.len(),
+            last_split: progress.splits.last().copied(),
+            finished: progress.finished,
+        })
+        .collect();
+
+    standings.sort_by(|a, b| match (a.finished, b.finished) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => b.checkpoints.cmp(&a.checkpoints).then_with(|| {
+            let a = a.last_split.unwrap_or(f32::INFINITY);
+            let b = b.last_split.unwrap_or(f32::INFINITY);
+            a.total_cmp(&b)
+        }),
+    });
+    standings
+}
+
+// Final results of a race, posted to the leaderboard API
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RaceResults {
+    pub level: String,
+    pub results: Vec<RaceResult>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RaceResult {
+    pub place: usize,
+    pub player_id: u32,
+    /// Total time in seconds, None if the player didn't finish
+    pub time: Option<f32>,
+    pub splits: Vec<f32>,
+}
+
+impl RaceResults {
+    pub fn new<'a>(
+        level: &str,
+        racers: impl IntoIterator<Item = (u32, &'a RaceProgress)> + Clone,
+    ) -> Self {
+        let results = standings(racers.clone())
+            .into_iter()
+            .enumerate()
+            .map(|(i, standing)| RaceResult {
+                place: i + 1,
+                player_id: standing.player_id,
+                time: standing.finished,
+                splits: racers
+                    .clone()
+                    .into_iter()
+                    .find(|(id, _)| *id == standing.player_id)
+                    .map(|(_, progress)| progress.splits.clone())
+                    .unwrap_or_default(),
+            })
+            .collect();
+        Self {
+            level: level.to_string(),
+            results,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(splits: &[f32], finished: Option<f32>) -> RaceProgress {
+        RaceProgress {
+            splits: splits.to_vec(),
+            finished,
+            penalty: 0.0,
+        }
+    }
+
+    #[test]
+    fn finishers_lead_then_most_checkpoints() {
+        let slow_finisher = progress(&[5.0, 10.0, 20.0], Some(20.0));
+        let fast_finisher = progress(&[4.0, 8.0, 15.0], Some(15.0));
+        let two_early = progress(&[3.0, 6.0], None);
+        let two_late = progress(&[3.0, 7.0], None);
+        let none = progress(&[], None);
+
+        let order: Vec<u32> = standings([
+            (0, &none),
+            (1, &two_late),
+            (2, &slow_finisher),
+            (3, &two_early),
+            (4, &fast_finisher),
+        ])
+        .iter()
+        .map(|s| s.player_id)
+        .collect();
+        assert_eq!(order, vec![4, 2, 3, 1, 0]);
+    }
+
+    #[test]
+    fn results_carry_places_and_splits() {
+        let a = progress(&[1.0, 2.0], Some(2.0));
+        let b = progress(&[1.5], None);
+        let results = RaceResults::new("default", [(7, &b), (3, &a)]);
+        assert_eq!(results.results[0].player_id, 3);
+        assert_eq!(results.results[0].place, 1);
+        assert_eq!(results.results[0].splits, vec![1.0, 2.0]);
+        assert_eq!(results.results[1].time, None);
+    }
+}
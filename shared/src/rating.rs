@@ -0,0 +1,153 @@
+// Ranked ratings: Elo for matches of any size, where every pair of players in a match
+// counts as one game between the two. The lobby service keeps each account's rating and
+// updates it with `EloConfig::rate_match` when a ranked server reports its result, and the
+// matchmaker only puts tickets together whose ratings pass `ratings_match`.
+use serde::{Deserialize, Serialize};
+
+/// How far apart two tickets may be rated when they enter the queue
+pub const RATING_BAND_START: f32 = 100.0;
+/// The band widens by this much per second of waiting...
+pub const RATING_BAND_GROWTH_PER_SEC: f32 = 5.0;
+/// ...up to this, so a long wait never pits a beginner against the top
+pub const RATING_BAND_MAX: f32 = 400.0;
+
+/// Knobs of the rating formula, the lobby service's `--elo-*` flags
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct EloConfig {
+    /// Rating new accounts start at
+    pub initial: f32,
+    /// Most a settled rating moves in one match
+    pub k_factor: f32,
+    /// K during the first `provisional_games` matches, so new accounts find their level fast
+    pub provisional_k: f32,
+    pub provisional_games: u32,
+    /// Rating difference at which the stronger player is expected to win 10 to 1
+    pub scale: f32,
+}
+
+impl Default for EloConfig {
+    fn default() -> Self {
+        Self {
+            initial: 1200.0,
+            k_factor: 32.0,
+            provisional_k: 64.0,
+            provisional_games: 10,
+            scale: 400.0,
+        }
+    }
+}
+
+/// One rated account in a finished ranked match
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RatedPlayer {
+    pub rating: f32,
+    /// Ranked matches played before this one
+    pub games: u32,
+    /// `MatchStanding::place`, tied players share one
+    pub place: usize,
+}
+
+impl EloConfig {
+    /// Chance that a player rated `rating` beats one rated `opponent`
+    pub fn expected(&self, rating: f32, opponent: f32) -> f32 {
+        1.0 / (1.0 + 10f32.powf((opponent - rating) / self.scale))
+    }
+
+    /// Rating change of every player, in the order given. The better place wins each
+    /// pair and a shared place is a draw. The pairs are averaged, so a four player
+    /// match moves a rating about as much as a duel does.
+    pub fn rate_match(&self, players: &[RatedPlayer]) -> Vec<f32> {
+        let opponents = players.len().saturating_sub(1).max(1) as f32;
+        players
+            .iter()
+            .enumerate()
+            .map(|(i, player)| {
+                let surprise: f32 = players
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .map(|(_, other)| {
+                        let score = match player.place.cmp(&other.place) {
+                            std::cmp::Ordering::Less => 1.0,
+                            std::cmp::Ordering::Equal => 0.5,
+                            std::cmp::Ordering::Greater => 0.0,
+                        };
+                        score - self.expected(player.rating, other.rating)
+                    })
+                    .sum();
+                let k = if player.games < self.provisional_games {
+                    self.provisional_k
+                } else {
+                    self.k_factor
+                };
+                k * surprise / opponents
+            })
+            .collect()
+    }
+}
+
+/// How far apart in rating a ticket accepts opponents after waiting `wait_secs`
+pub fn rating_band(wait_secs: f32) -> f32 {
+    (RATING_BAND_START + wait_secs.max(0.0) * RATING_BAND_GROWTH_PER_SEC).min(RATING_BAND_MAX)
+}
+
+/// Whether two tickets may share a match: each has to be inside the other's band
+pub fn ratings_match(rating: f32, wait_secs: f32, other: f32, other_wait_secs: f32) -> bool {
+    (rating - other).abs() <= rating_band(wait_secs).min(rating_band(other_wait_secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rated(rating: f32, games: u32, place: usize) -> RatedPlayer {
+        RatedPlayer {
+            rating,
+            games,
+            place,
+        }
+    }
+
+    #[test]
+    fn wins_against_stronger_players_count_more() {
+        let elo = EloConfig::default();
+
+        // An even duel moves both ratings by half of K
+        let duel = elo.rate_match(&[rated(1200.0, 20, 1), rated(1200.0, 20, 2)]);
+        assert_eq!(duel, vec![16.0, -16.0]);
+
+        // Beating a stronger player gains more than beating a weaker one
+        let upset = elo.rate_match(&[rated(1000.0, 20, 1), rated(1400.0, 20, 2)]);
+        let expected = elo.rate_match(&[rated(1400.0, 20, 1), rated(1000.0, 20, 2)]);
+        assert!(upset[0] > 25.0 && expected[0] < 7.0);
+
+        // Settled players trade points, provisional ones move twice as fast
+        let four = elo.rate_match(&[
+            rated(1300.0, 20, 1),
+            rated(1200.0, 20, 2),
+            rated(1200.0, 20, 2),
+            rated(1100.0, 20, 4),
+        ]);
+        assert!(four.iter().sum::<f32>().abs() < 1e-3);
+        assert_eq!(four[1], four[2]);
+        let new = elo.rate_match(&[rated(1200.0, 0, 1), rated(1200.0, 20, 2)]);
+        assert_eq!(new, vec![32.0, -16.0]);
+
+        // Nobody to play against, nothing changes
+        assert_eq!(elo.rate_match(&[rated(1200.0, 0, 1)]), vec![0.0]);
+    }
+
+    #[test]
+    fn rating_bands_widen_with_the_wait() {
+        assert_eq!(rating_band(0.0), RATING_BAND_START);
+        assert_eq!(rating_band(20.0), 200.0);
+        assert_eq!(rating_band(600.0), RATING_BAND_MAX);
+
+        assert!(ratings_match(1200.0, 0.0, 1290.0, 0.0));
+        assert!(!ratings_match(1200.0, 0.0, 1350.0, 0.0));
+        // Both have to have waited long enough
+        assert!(!ratings_match(1200.0, 60.0, 1350.0, 0.0));
+        assert!(ratings_match(1200.0, 60.0, 1350.0, 10.0));
+    }
+}
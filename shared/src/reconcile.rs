@@ -0,0 +1,364 @@
+// Room reconciliation: the lobby service, the game servers and Edgegap each hold part of a
+// room's state, and a crash or a lost message in any of them leaves the three disagreeing.
+// The lobby service periodically feeds `reconcile` what each one says, applies the repairs
+// it returns and logs the report (see "Room reconciliation" in docs/lobby-service.md).
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Body of `POST /lobby/api/servers/heartbeat`, sent by game servers that know the lobby
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ServerHeartbeat {
+    /// Edgegap request id of the server's deployment (`ARBITRIUM_REQUEST_ID`), None outside
+    /// Edgegap
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deployment: Option<String>,
+    /// Lobby rooms the server has open, empty ones included until it closes them
+    #[serde(default)]
+    pub rooms: Vec<ServerRoomStatus>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ServerRoomStatus {
+    pub room_id: String,
+    pub players: u32,
+}
+
+/// A server's latest heartbeat and when the lobby got it
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReceivedHeartbeat {
+    pub heartbeat: ServerHeartbeat,
+    /// Unix timestamp in seconds
+    pub received_at: u64,
+}
+
+/// What the lobby service knows about one of its rooms. `GET /lobby/api/admin/rooms`
+/// answers with these, which `lobby reap` checks deployments against.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TrackedRoom {
+    pub id: String,
+    /// Unix timestamp in seconds of the room's start, None until it starts
+    pub started_at: Option<u64>,
+    /// Edgegap request id of the deployment the matchmaker started for the room
+    pub deployment: Option<String>,
+}
+
+/// An Edgegap deployment, as `GET /v1/deployments` lists it
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrackedDeployment {
+    pub request_id: String,
+    /// Unix timestamp in seconds
+    pub started_at: u64,
+    /// Deploying or ready, false once it is stopping or stopped
+    pub running: bool,
+}
+
+/// Tuning of `reconcile`, the defaults are what the lobby service's flags default to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReconcileSettings {
+    /// Seconds a room or deployment may look inconsistent after starting, servers take a
+    /// while to boot and send their first heartbeat
+    pub grace_secs: u64,
+    /// Heartbeats older than this are from a server that is gone
+    pub heartbeat_timeout_secs: u64,
+}
+
+impl Default for ReconcileSettings {
+    fn default() -> Self {
+        Self {
+            grace_secs: 120,
+            heartbeat_timeout_secs: 60,
+        }
+    }
+}
+
+/// Why a started room is closed
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RoomProblem {
+    /// No deployment was recorded for it and no server reports it
+    NoDeployment,
+    /// Edgegap no longer runs its deployment
+    DeploymentStopped,
+    /// Its server is alive but doesn't have the room open
+    NotOnServer,
+}
+
+/// One fix for the lobby service to apply
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "repair", rename_all = "snake_case")]
+pub enum Repair {
+    /// Close the room as an abandoned match, like a `finish` without standings
+    CloseRoom {
+        room_id: String,
+        problem: RoomProblem,
+    },
+    /// Remember the deployment a server reported the room on
+    AttachDeployment { room_id: String, deployment: String },
+    /// Terminate a deployment that only hosts rooms the lobby doesn't know
+    StopDeployment { request_id: String },
+}
+
+/// Outcome of one reconciliation run, logged as JSON
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ReconcileReport {
+    /// Unix timestamp in seconds
+    pub checked_at: u64,
+    pub rooms: usize,
+    pub deployments: usize,
+    pub heartbeats: usize,
+    pub repairs: Vec<Repair>,
+}
+
+/// Compare the three views of the world and say what to repair. Only call it with a
+/// complete deployment list, a failed Edgegap call would look like every server stopped.
+pub fn reconcile(
+    rooms: &[TrackedRoom],
+    deployments: &[TrackedDeployment],
+    heartbeats: &[ReceivedHeartbeat],
+    now: u64,
+    settings: &ReconcileSettings,
+) -> ReconcileReport {
+    let past_grace = |started_at: u64| started_at + settings.grace_secs <= now;
+    let heartbeats: Vec<&ServerHeartbeat> = heartbeats
+        .iter()
+        .filter(|received| received.received_at + settings.heartbeat_timeout_secs >= now)
+        .map(|received| &received.heartbeat)
+        .collect();
+    let running: HashSet<&str> = deployments
+        .iter()
+        .filter(|deployment| deployment.running)
+        .map(|deployment| deployment.request_id.as_str())
+        .collect();
+    // Where servers say each room is, and what each deployment says it hosts
+    let mut hosted_on: HashMap<&str, Option<&str>> = HashMap::new();
+    let mut reported: HashMap<&str, Vec<&str>> = HashMap::new();
+    for heartbeat in &heartbeats {
+        for room in &heartbeat.rooms {
+            hosted_on.insert(&room.room_id, heartbeat.deployment.as_deref());
+        }
+        if let Some(deployment) = &heartbeat.deployment {
+            reported
+                .entry(deployment)
+                .or_default()
+                .extend(heartbeat.rooms.iter().map(|room| room.room_id.as_str()));
+        }
+    }
+
+    let mut repairs = Vec::new();
+    for room in rooms {
+        if !room.started_at.is_some_and(past_grace) {
+            continue;
+        }
+        let hosted = hosted_on.get(room.id.as_str()).copied();
+        let problem = match (room.deployment.as_deref(), hosted) {
+            (None, Some(Some(deployment))) => {
+                repairs.push(Repair::AttachDeployment {
+                    room_id: room.id.clone(),
+                    deployment: deployment.to_string(),
+                });
+                None
+            }
+            // A live server has it open
+            (_, Some(_)) => None,
+            (None, None) => Some(RoomProblem::NoDeployment),
+            (Some(deployment), None) if !running.contains(deployment) => {
+                Some(RoomProblem::DeploymentStopped)
+            }
+            // Servers that don't send heartbeats get the benefit of the doubt
+            (Some(deployment), None) if reported.contains_key(deployment) => {
+                Some(RoomProblem::NotOnServer)
+            }
+            (Some(_), None) => None,
+        };
+        if let Some(problem) = problem {
+            repairs.push(Repair::CloseRoom {
+                room_id: room.id.clone(),
+                problem,
+            });
+        }
+    }
+
+    let known: HashSet<&str> = rooms.iter().map(|room| room.id.as_str()).collect();
+    let claimed: HashSet<&str> = rooms
+        .iter()
+        .filter_map(|room| room.deployment.as_deref())
+        .collect();
+    for deployment in deployments {
+        if !deployment.running || !past_grace(deployment.started_at) {
+            continue;
+        }
+        let id = deployment.request_id.as_str();
+        // Quick matches never report rooms, so only servers with rooms can be orphans
+        let Some(hosted) = reported.get(id).filter(|hosted| !hosted.is_empty()) else {
+            continue;
+        };
+        if !claimed.contains(id) && !hosted.iter().any(|room| known.contains(room)) {
+            repairs.push(Repair::StopDeployment {
+                request_id: id.to_string(),
+            });
+        }
+    }
+
+    ReconcileReport {
+        checked_at: now,
+        rooms: rooms.len(),
+        deployments: deployments.len(),
+        heartbeats: heartbeats.len(),
+        repairs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 10_000;
+
+    fn room(id: &str, started_at: Option<u64>, deployment: Option<&str>) -> TrackedRoom {
+        TrackedRoom {
+            id: id.to_string(),
+            started_at,
+            deployment: deployment.map(str::to_string),
+        }
+    }
+
+    fn deployment(request_id: &str, running: bool) -> TrackedDeployment {
+        TrackedDeployment {
+            request_id: request_id.to_string(),
+            started_at: 1_000,
+            running,
+        }
+    }
+
+    fn heartbeat(deployment: &str, rooms: &[&str], received_at: u64) -> ReceivedHeartbeat {
+        ReceivedHeartbeat {
+            heartbeat: ServerHeartbeat {
+                deployment: Some(deployment.to_string()),
+                rooms: rooms
+                    .iter()
+                    .map(|room_id| ServerRoomStatus {
+                        room_id: room_id.to_string(),
+                        players: 1,
+                    })
+                    .collect(),
+            },
+            received_at,
+        }
+    }
+
+    fn close(room_id: &str, problem: RoomProblem) -> Repair {
+        Repair::CloseRoom {
+            room_id: room_id.to_string(),
+            problem,
+        }
+    }
+
+    #[test]
+    fn consistent_state_needs_no_repairs() {
+        let report = reconcile(
+            &[
+                room("R1", Some(1_000), Some("D1")),
+                // Not started, nothing to check yet
+                room("R2", None, None),
+                // Started moments ago, its server may still be booting
+                room("R3", Some(NOW - 10), None),
+            ],
+            &[deployment("D1", true), deployment("QUICK", true)],
+            &[heartbeat("D1", &["R1"], NOW - 5)],
+            NOW,
+            &ReconcileSettings::default(),
+        );
+        assert_eq!(report.repairs, Vec::new());
+        assert_eq!(
+            (report.rooms, report.deployments, report.heartbeats),
+            (3, 2, 1)
+        );
+    }
+
+    #[test]
+    fn started_rooms_without_a_server_are_closed() {
+        let report = reconcile(
+            &[
+                room("GONE", Some(1_000), Some("D1")),
+                room("NEVER", Some(1_000), None),
+                room("DROPPED", Some(1_000), Some("D2")),
+                room("SILENT", Some(1_000), Some("D3")),
+            ],
+            &[
+                deployment("D1", false),
+                deployment("D2", true),
+                deployment("D3", true),
+            ],
+            &[
+                heartbeat("D2", &["OTHER"], NOW),
+                // Too old to count
+                heartbeat("D3", &[], NOW - 500),
+            ],
+            NOW,
+            &ReconcileSettings::default(),
+        );
+        assert_eq!(
+            report.repairs,
+            vec![
+                close("GONE", RoomProblem::DeploymentStopped),
+                close("NEVER", RoomProblem::NoDeployment),
+                close("DROPPED", RoomProblem::NotOnServer),
+            ]
+        );
+    }
+
+    #[test]
+    fn deployments_hosting_only_unknown_rooms_are_stopped() {
+        let report = reconcile(
+            &[room("R1", None, None)],
+            &[
+                deployment("ORPHAN", true),
+                deployment("HOSTS_R1", true),
+                deployment("QUICK", true),
+            ],
+            &[
+                heartbeat("ORPHAN", &["DELETED"], NOW),
+                heartbeat("HOSTS_R1", &["R1"], NOW),
+                heartbeat("QUICK", &[], NOW),
+            ],
+            NOW,
+            &ReconcileSettings::default(),
+        );
+        assert_eq!(
+            report.repairs,
+            vec![Repair::StopDeployment {
+                request_id: "ORPHAN".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn reported_deployments_are_attached() {
+        let report = reconcile(
+            &[room("R1", Some(1_000), None)],
+            &[deployment("D1", true)],
+            &[heartbeat("D1", &["R1"], NOW)],
+            NOW,
+            &ReconcileSettings::default(),
+        );
+        assert_eq!(
+            report.repairs,
+            vec![Repair::AttachDeployment {
+                room_id: "R1".to_string(),
+                deployment: "D1".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn repairs_are_tagged_json() {
+        let json = serde_json::to_string(&close("R1", RoomProblem::NotOnServer)).unwrap();
+        assert_eq!(
+            json,
+            r#"{"repair":"close_room","room_id":"R1","problem":"not_on_server"}"#
+        );
+    }
+}
@@ -0,0 +1,116 @@
+// Room codes: the short name players read out and type to join a room, like `K7QX2M`. The
+// lobby service hands one out with every room (`ServerLobbyRoom::code`) and keeps using the
+// room's UUID for everything else. Codes leave out characters that are easy to mix up
+// (0/O, 1/I), and typing is forgiving about case, spaces and dashes.
+
+/// Characters of a room code
+pub const ROOM_CODE_LEN: usize = 6;
+/// 32 characters, so each one takes exactly 5 bits of entropy
+pub const ROOM_CODE_ALPHABET: &[u8; 32] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+/// Fresh codes drawn before `unique_room_code` gives up
+pub const ROOM_CODE_ATTEMPTS: usize = 32;
+
+/// The code for 30 bits of `entropy`, higher bits are ignored
+pub fn room_code(entropy: u32) -> String {
+    (0..ROOM_CODE_LEN)
+        .map(|index| ROOM_CODE_ALPHABET[((entropy >> (5 * index)) & 0x1f) as usize] as char)
+        .collect()
+}
+
+/// A code no other room has, drawing from `entropy` until `taken` says no. None after
+/// `ROOM_CODE_ATTEMPTS` collisions in a row, the service should answer 503 then.
+pub fn unique_room_code(
+    mut entropy: impl FnMut() -> u32,
+    taken: impl Fn(&str) -> bool,
+) -> Option<String> {
+    (0..ROOM_CODE_ATTEMPTS)
+        .map(|_| room_code(entropy()))
+        .find(|code| !taken(code))
+}
+
+/// The code a player typed, upper-cased and without spaces or dashes. None if it can't be
+/// a room code at all.
+pub fn parse_room_code(typed: &str) -> Option<String> {
+    let code: String = typed
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    let valid =
+        code.len() == ROOM_CODE_LEN && code.bytes().all(|byte| ROOM_CODE_ALPHABET.contains(&byte));
+    valid.then_some(code)
+}
+
+/// Link that opens the web client at `origin` on the Join Room page with `code` filled in,
+/// like `https://voidloop.quest/?join=K7QX2M`
+pub fn room_link(origin: &str, code: &str) -> String {
+    format!("{}/?join={}", origin.trim_end_matches('/'), code)
+}
+
+/// The room code of a `join` parameter in a page's query string (`location.search`, with or
+/// without the `?`). None if there is none or it can't be a room code.
+pub fn join_code_from_query(search: &str) -> Option<String> {
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("join="))
+        .and_then(parse_room_code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_use_the_alphabet() {
+        for entropy in [0, 1, 0x3fff_ffff, 0xdead_beef, u32::MAX] {
+            let code = room_code(entropy);
+            assert_eq!(code.len(), ROOM_CODE_LEN);
+            assert_eq!(parse_room_code(&code), Some(code));
+        }
+        assert_ne!(room_code(1), room_code(2));
+    }
+
+    #[test]
+    fn typing_is_forgiving() {
+        assert_eq!(parse_room_code(" k7q-x2m "), Some("K7QX2M".to_string()));
+        assert_eq!(parse_room_code("K7Q X2M"), Some("K7QX2M".to_string()));
+    }
+
+    #[test]
+    fn lookalikes_and_lengths_are_rejected() {
+        assert_eq!(parse_room_code("K7QX20"), None);
+        assert_eq!(parse_room_code("K7QXIM"), None);
+        assert_eq!(parse_room_code("K7QX2"), None);
+        assert_eq!(parse_room_code("K7QX2MM"), None);
+        assert_eq!(
+            parse_room_code("3f2b8c1e-0000-4000-8000-000000000000"),
+            None
+        );
+    }
+
+    #[test]
+    fn links_carry_the_code() {
+        let link = room_link("https://voidloop.quest/", "K7QX2M");
+        assert_eq!(link, "https://voidloop.quest/?join=K7QX2M");
+        assert_eq!(
+            join_code_from_query(link.split_once('?').unwrap().1),
+            Some("K7QX2M".to_string())
+        );
+        assert_eq!(
+            join_code_from_query("?debug=1&join=k7q-x2m"),
+            Some("K7QX2M".to_string())
+        );
+        assert_eq!(join_code_from_query("?join=ROOM123"), None);
+        assert_eq!(join_code_from_query("?rejoin=K7QX2M"), None);
+        assert_eq!(join_code_from_query(""), None);
+    }
+
+    #[test]
+    fn taken_codes_are_skipped() {
+        let mut draws = [7, 7, 8].into_iter();
+        let code = unique_room_code(|| draws.next().unwrap(), |code| code == room_code(7));
+        assert_eq!(code, Some(room_code(8)));
+        assert_eq!(unique_room_code(|| 7, |_| true), None);
+    }
+}
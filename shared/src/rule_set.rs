@@ -0,0 +1,276 @@
+// Rule sets of the custom game mode: a small RON file (see assets/rules/) with the physics,
+// round time, friendly fire and pickups a host wants. The host picks one when creating a
+// custom room, the lobby service validates it, and the matchmaker starts the room's
+// server with `env_vars`, which the server reads into its `GameConfig` and team rules.
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use crate::game_config::GameConfig;
+
+/// Rule set picked for a new custom room
+pub const DEFAULT_RULE_SET: &str = "classic";
+/// Largest rule set the lobby service takes, in bytes
+pub const MAX_RULE_SET_LEN: usize = 2048;
+/// Longest rule set name, in characters
+pub const MAX_RULE_SET_NAME_LEN: usize = 24;
+
+// What a host may ask for. Wider than any fun game, narrower than what breaks one.
+const GRAVITY_RANGE: RangeInclusive<f32> = -2000.0..=-100.0;
+const MOVE_SPEED_RANGE: RangeInclusive<f32> = 50.0..=500.0;
+const JUMP_FORCE_RANGE: RangeInclusive<f32> = 100.0..=800.0;
+const ROUND_SECS_RANGE: RangeInclusive<u32> = 30..=3600;
+
+// Rule sets compiled into every binary, hosts pick from these
+const BUILTIN_RULE_SETS: &[(&str, &str)] = &[
+    (
+        DEFAULT_RULE_SET,
+        include_str!("../../assets/rules/classic.ron"),
+    ),
+    ("moon", include_str!("../../assets/rules/moon.ron")),
+    ("sprint", include_str!("../../assets/rules/sprint.ron")),
+    ("brawl", include_str!("../../assets/rules/brawl.ron")),
+];
+
+/// A custom room's rules. Anything a file leaves out plays like a normal match.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct RuleSet {
+    /// Shown to players in the room
+    pub name: String,
+    pub gravity: f32,
+    pub move_speed: f32,
+    pub jump_force: f32,
+    /// Seconds until the match ends on its score, 0 for no limit
+    pub round_secs: u32,
+    pub friendly_fire: bool,
+    /// Levels keep their pickups
+    pub pickups: bool,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        let config = GameConfig::default();
+        Self {
+            name: "Custom".to_string(),
+            gravity: config.gravity,
+            move_speed: config.move_speed,
+            jump_force: config.jump_force,
+            round_secs: config.round_secs,
+            friendly_fire: false,
+            pickups: config.pickups,
+        }
+    }
+}
+
+impl RuleSet {
+    /// Parse and validate a rule set, what the lobby service does with a host's
+    pub fn from_ron(ron: &str) -> Result<Self, RuleSetError> {
+        if ron.len() > MAX_RULE_SET_LEN {
+            return Err(RuleSetError::TooLong);
+        }
+        let rules: Self = ron::from_str(ron).map_err(RuleSetError::Parse)?;
+        rules.validate()?;
+        Ok(rules)
+    }
+
+    /// Look up one of the rule sets compiled into the binary
+    pub fn builtin(name: &str) -> Option<Self> {
+        Self::builtin_ron(name).and_then(|ron| Self::from_ron(ron).ok())
+    }
+
+    /// Source of a compiled in rule set, what a host's client sends the lobby
+    pub fn builtin_ron(name: &str) -> Option<&'static str> {
+        BUILTIN_RULE_SETS
+            .iter()
+            .find(|(rules_name, _)| *rules_name == name)
+            .map(|(_, ron)| *ron)
+    }
+
+    /// Names of the compiled in rule sets, in the order hosts cycle through them
+    pub fn builtin_names() -> impl Iterator<Item = &'static str> {
+        BUILTIN_RULE_SETS.iter().map(|(name, _)| *name)
+    }
+
+    pub fn validate(&self) -> Result<(), RuleSetError> {
+        let invalid = |reason: String| Err(RuleSetError::Invalid(reason));
+        let name_len = self.name.trim().chars().count();
+        if name_len == 0 || name_len > MAX_RULE_SET_NAME_LEN {
+            return invalid(format!(
+                "name must be 1 to {} characters",
+                MAX_RULE_SET_NAME_LEN
+            ));
+        }
+        for (field, value, range) in [
+            ("gravity", self.gravity, GRAVITY_RANGE),
+            ("move_speed", self.move_speed, MOVE_SPEED_RANGE),
+            ("jump_force", self.jump_force, JUMP_FORCE_RANGE),
+        ] {
+            if !range.contains(&value) {
+                return invalid(format!(
+                    "{} must be between {} and {}",
+                    field,
+                    range.start(),
+                    range.end()
+                ));
+            }
+        }
+        if self.round_secs != 0 && !ROUND_SECS_RANGE.contains(&self.round_secs) {
+            return invalid(format!(
+                "round_secs must be 0 or between {} and {}",
+                ROUND_SECS_RANGE.start(),
+                ROUND_SECS_RANGE.end()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Environment the matchmaker starts the room's server with
+    pub fn env_vars(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("VOIDLOOP_GRAVITY", self.gravity.to_string()),
+            ("VOIDLOOP_MOVE_SPEED", self.move_speed.to_string()),
+            ("VOIDLOOP_JUMP_FORCE", self.jump_force.to_string()),
+            ("VOIDLOOP_ROUND_SECS", self.round_secs.to_string()),
+            ("VOIDLOOP_FRIENDLY_FIRE", self.friendly_fire.to_string()),
+            ("VOIDLOOP_PICKUPS", self.pickups.to_string()),
+        ]
+    }
+
+    /// `config` with these rules applied, what the server ends up playing with
+    pub fn apply(&self, config: GameConfig) -> GameConfig {
+        GameConfig {
+            gravity: self.gravity,
+            move_speed: self.move_speed,
+            jump_force: self.jump_force,
+            round_secs: self.round_secs,
+            pickups: self.pickups,
+            ..config
+        }
+    }
+
+    /// One line for the room screen, e.g. "Sprint: 3 min, fast, no pickups"
+    pub fn summary(&self) -> String {
+        let standard = Self::default();
+        let mut parts = Vec::new();
+        if self.round_secs > 0 {
+            parts.push(format!(
+                "{}:{:02}",
+                self.round_secs / 60,
+                self.round_secs % 60
+            ));
+        }
+        if self.gravity > standard.gravity {
+            parts.push("low gravity".to_string());
+        } else if self.gravity < standard.gravity {
+            parts.push("high gravity".to_string());
+        }
+        if self.move_speed > standard.move_speed {
+            parts.push("fast".to_string());
+        } else if self.move_speed < standard.move_speed {
+            parts.push("slow".to_string());
+        }
+        if self.jump_force != standard.jump_force {
+            parts.push(format!("jump {:.0}", self.jump_force));
+        }
+        if self.friendly_fire {
+            parts.push("friendly fire".to_string());
+        }
+        if !self.pickups {
+            parts.push("no pickups".to_string());
+        }
+        if parts.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}: {}", self.name, parts.join(", "))
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RuleSetError {
+    TooLong,
+    Parse(ron::error::SpannedError),
+    Invalid(String),
+}
+
+impl fmt::Display for RuleSetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleSetError::TooLong => {
+                write!(f, "rule set is longer than {} bytes", MAX_RULE_SET_LEN)
+            }
+            RuleSetError::Parse(e) => write!(f, "invalid rule set: {}", e),
+            RuleSetError::Invalid(reason) => write!(f, "invalid rule set: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for RuleSetError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_rule_sets_are_valid() {
+        for name in RuleSet::builtin_names() {
+            let ron = RuleSet::builtin_ron(name).unwrap();
+            if let Err(e) = RuleSet::from_ron(ron) {
+                panic!("{}: {}", name, e);
+            }
+        }
+        let classic = RuleSet::builtin(DEFAULT_RULE_SET).unwrap();
+        assert_eq!(classic.apply(GameConfig::default()), GameConfig::default());
+    }
+
+    #[test]
+    fn rule_sets_only_need_what_they_change() {
+        let rules = RuleSet::from_ron(r#"(name: "Moon", gravity: -350.0)"#).unwrap();
+        assert_eq!(
+            rules.apply(GameConfig::default()),
+            GameConfig {
+                gravity: -350.0,
+                ..GameConfig::default()
+            }
+        );
+        assert_eq!(rules.summary(), "Moon: low gravity");
+        assert_eq!(
+            RuleSet::builtin("sprint").unwrap().summary(),
+            "Sprint: 3:00, fast, no pickups"
+        );
+    }
+
+    #[test]
+    fn out_of_range_rules_are_rejected() {
+        for ron in [
+            r#"(name: "Up", gravity: 100.0)"#,
+            r#"(name: "Zoom", move_speed: 5000.0)"#,
+            r#"(name: "Blink", round_secs: 5)"#,
+            r#"(name: "")"#,
+        ] {
+            assert!(
+                matches!(RuleSet::from_ron(ron), Err(RuleSetError::Invalid(_))),
+                "{}",
+                ron
+            );
+        }
+        assert!(matches!(
+            RuleSet::from_ron("(name: "),
+            Err(RuleSetError::Parse(_))
+        ));
+        assert!(matches!(
+            RuleSet::from_ron(&" ".repeat(MAX_RULE_SET_LEN + 1)),
+            Err(RuleSetError::TooLong)
+        ));
+    }
+
+    #[test]
+    fn env_vars_cover_every_rule() {
+        let rules = RuleSet::builtin("brawl").unwrap();
+        let env = rules.env_vars();
+        assert!(env.contains(&("VOIDLOOP_ROUND_SECS", "300".to_string())));
+        assert!(env.contains(&("VOIDLOOP_FRIENDLY_FIRE", "true".to_string())));
+        assert!(env.contains(&("VOIDLOOP_GRAVITY", "-800".to_string())));
+    }
+}
@@ -1,21 +1,50 @@
 use bevy::prelude::*;
 use leafwing_input_manager::prelude::*;
 
+use crate::countdown::{countdown_over, Countdown};
+use crate::game_config::GameConfig;
+use crate::highlights::MatchStats;
+use crate::level::{LevelDefinition, WorldBounds};
 use crate::protocol_plugin::{
-    Platform, Player, PlayerActions, PlayerAnimationState, PlayerTransform,
+    Door, Hazard, MatchRoom, Pickup, Platform, PlatformKind, Player, PlayerActions,
+    PlayerAnimationState, PlayerId, PlayerRole, PlayerScore, PlayerTransform, PressurePlate,
+    RaceProgress, RespawnEvent, Stunned, Team, TeamRules,
+};
+use crate::simulation::{
+    advance_tick_system, record_checksum_system, ChecksumHistory, SimTick, FIXED_DT,
 };
 
 pub struct SharedPlugin;
 
 impl Plugin for SharedPlugin {
     fn build(&self, app: &mut App) {
+        // Free-for-all until the server says otherwise
+        app.init_resource::<TeamRules>();
+        // The original arena until the level or the server says otherwise
+        app.init_resource::<WorldBounds>();
+        // Standard physics until the server says otherwise
+        app.init_resource::<GameConfig>();
+        app.init_resource::<SimTick>()
+            .init_resource::<ChecksumHistory>();
+        // No countdown until the server or a local game starts one
+        app.init_resource::<Countdown>();
+
         app.add_systems(
             FixedUpdate,
             (
-                player_movement_system,
+                advance_tick_system,
+                moving_platform_system,
+                crumbling_platform_system,
+                stun_recovery_system,
+                player_movement_system.run_if(countdown_over),
                 update_animation_state_system,
                 apply_gravity_system,
                 ground_detection_system,
+                stomp_system,
+                pressure_plate_system,
+                door_system,
+                door_collision_system,
+                record_checksum_system,
             )
                 .chain(),
         );
@@ -24,19 +53,42 @@ impl Plugin for SharedPlugin {
 
 // ==== CORE PLATFORMER SYSTEMS ====
 
-// Constants for platformer physics
-const MOVE_SPEED: f32 = 200.0;
-const JUMP_FORCE: f32 = 400.0;
-const GRAVITY: f32 = -800.0;
-const MAX_FALL_SPEED: f32 = -500.0;
+// Constants for platformer physics, the configurable ones are in `GameConfig`
 const PLAYER_SIZE: f32 = 30.0;
 const PLATFORM_HEIGHT: f32 = 20.0;
+const STOMP_BOUNCE: f32 = 350.0;
+pub const STUN_SECS: f32 = 1.5;
+pub const PLATE_WIDTH: f32 = 60.0;
+pub const DOOR_WIDTH: f32 = 20.0;
+pub const DOOR_HEIGHT: f32 = 120.0;
+pub const PICKUP_RADIUS: f32 = 25.0;
+pub const HAZARD_RADIUS: f32 = 20.0;
+const HAZARD_KNOCKBACK: Vec2 = Vec2::new(250.0, 300.0);
+// Deepest drop `ground_ahead` still walks down instead of calling it a gap
+const GAP_DROP: f32 = 60.0;
 
-// Handle player movement based on input
+// Handle player movement based on input. Players with a `Countdown` of their own wait for
+// it, the `Countdown` resource holds back everyone.
 pub fn player_movement_system(
-    mut query: Query<(&mut Player, &ActionState<PlayerActions>), With<Player>>,
+    config: Res<GameConfig>,
+    tick: Res<SimTick>,
+    mut query: Query<(
+        &mut Player,
+        &ActionState<PlayerActions>,
+        Has<Stunned>,
+        Option<&Countdown>,
+    )>,
 ) {
-    for (mut player, action_state) in query.iter_mut() {
+    for (mut player, action_state, stunned, countdown) in query.iter_mut() {
+        if countdown.is_some_and(|countdown| countdown.is_running(*tick)) {
+            continue;
+        }
+        // Stunned players ignore input until they recover
+        if stunned {
+            player.velocity.x = 0.0;
+            continue;
+        }
+
         // Horizontal movement
         let mut move_delta = 0.0;
 
@@ -47,11 +99,11 @@ pub fn player_movement_system(
             move_delta += 1.0;
         }
 
-        player.velocity.x = move_delta * MOVE_SPEED;
+        player.velocity.x = move_delta * config.move_speed;
 
         // Jump (only when grounded)
         if action_state.just_pressed(&PlayerActions::Jump) && player.grounded {
-            player.velocity.y = JUMP_FORCE;
+            player.velocity.y = config.jump_force;
             player.grounded = false;
         }
     }
@@ -59,9 +111,9 @@ pub fn player_movement_system(
 
 // Update animation state based on player movement
 pub fn update_animation_state_system(
-    mut query: Query<(&Player, &mut PlayerAnimationState), With<Player>>,
+    mut query: Query<(&Player, &mut PlayerAnimationState, Has<Stunned>), With<Player>>,
 ) {
-    for (player, mut anim_state) in query.iter_mut() {
+    for (player, mut anim_state, stunned) in query.iter_mut() {
         // Update movement state
         anim_state.is_moving = player.velocity.x.abs() > 10.0;
 
@@ -74,76 +126,506 @@ pub fn update_animation_state_system(
 
         // Update jumping state
         anim_state.is_jumping = !player.grounded;
+
+        anim_state.is_stunned = stunned;
     }
 }
 
-// Apply gravity to players
+// Apply gravity to players. The floor is a platform, landing on it is up to
+// `ground_detection_system`.
 pub fn apply_gravity_system(
+    bounds: Res<WorldBounds>,
+    config: Res<GameConfig>,
     mut query: Query<(&mut Player, &mut PlayerTransform)>,
-    time: Res<Time>,
 ) {
-    let dt = time.delta_secs();
+    let dt = FIXED_DT;
 
     for (mut player, mut transform) in query.iter_mut() {
         // Apply gravity if not grounded
         if !player.grounded {
-            player.velocity.y += GRAVITY * dt;
-            player.velocity.y = player.velocity.y.max(MAX_FALL_SPEED);
+            player.velocity.y += config.gravity * dt;
+            player.velocity.y = player.velocity.y.max(config.max_fall_speed);
         }
 
         // Apply velocity to position
         transform.translation.x += player.velocity.x * dt;
         transform.translation.y += player.velocity.y * dt;
 
-        // Keep player in bounds (simple boundary)
-        transform.translation.x = transform.translation.x.clamp(-400.0, 400.0);
-
-        // Ground check (simple floor at y = -200)
-        if transform.translation.y <= -200.0 {
-            transform.translation.y = -200.0;
-            player.velocity.y = 0.0;
-            player.grounded = true;
-        }
+        // Keep player inside the level
+        transform.translation.x = transform.translation.x.clamp(bounds.min_x, bounds.max_x);
     }
 }
 
+// Whether a player's feet are on top of `platform` at `platform_position`
+fn stands_on(
+    player: &Player,
+    player_position: Vec3,
+    platform: &Platform,
+    platform_position: Vec3,
+) -> bool {
+    let player_bottom = player_position.y - PLAYER_SIZE / 2.0;
+    let player_left = player_position.x - PLAYER_SIZE / 2.0;
+    let player_right = player_position.x + PLAYER_SIZE / 2.0;
+
+    let platform_top = platform_position.y + PLATFORM_HEIGHT / 2.0;
+    let platform_bottom = platform_position.y - PLATFORM_HEIGHT / 2.0;
+    let platform_left = platform_position.x - platform.width / 2.0;
+    let platform_right = platform_position.x + platform.width / 2.0;
+
+    player_bottom <= platform_top
+        && player_bottom >= platform_bottom
+        && player_right >= platform_left
+        && player_left <= platform_right
+        && player.velocity.y <= 0.0
+}
+
+/// Whether a player at `position` walking `direction` (-1 or 1) still has a solid platform
+/// under their feet one step ahead. A platform a little lower counts, one further down
+/// than `GAP_DROP` is a gap to jump. Bots use it to jump pits instead of walking into them.
+pub fn ground_ahead<'a>(
+    position: Vec3,
+    direction: f32,
+    platforms: impl IntoIterator<Item = (&'a Platform, &'a Transform)>,
+) -> bool {
+    let feet = position.y - PLAYER_SIZE / 2.0;
+    let ahead = position.x + direction.signum() * PLAYER_SIZE;
+    platforms.into_iter().any(|(platform, transform)| {
+        let top = transform.translation.y + PLATFORM_HEIGHT / 2.0;
+        platform.solid
+            && (transform.translation.x - ahead).abs() <= platform.width / 2.0
+            && top <= feet + PLATFORM_HEIGHT
+            && top >= feet - GAP_DROP
+    })
+}
+
 // Detect if player is on ground or platform
 pub fn ground_detection_system(
-    mut players: Query<(&mut Player, &PlayerTransform), With<Player>>,
-    platforms: Query<&Transform, (With<Platform>, Without<Player>)>,
+    tick: Res<SimTick>,
+    mut players: Query<(&mut Player, &mut PlayerTransform), With<Player>>,
+    platforms: Query<(&Platform, &Transform), Without<Player>>,
 ) {
-    for (mut player, player_transform) in players.iter_mut() {
-        let player_bottom = player_transform.translation.y - PLAYER_SIZE / 2.0;
-        let player_left = player_transform.translation.x - PLAYER_SIZE / 2.0;
-        let player_right = player_transform.translation.x + PLAYER_SIZE / 2.0;
-
-        // Check collision with platforms
-        let mut on_platform = false;
-        for platform_transform in platforms.iter() {
-            let platform_top = platform_transform.translation.y + PLATFORM_HEIGHT / 2.0;
-            let platform_bottom = platform_transform.translation.y - PLATFORM_HEIGHT / 2.0;
-            let platform_left = platform_transform.translation.x - 100.0; // Platform width
-            let platform_right = platform_transform.translation.x + 100.0;
-
-            // Check if player is on top of platform
-            if player_bottom <= platform_top
-                && player_bottom >= platform_bottom
-                && player_right >= platform_left
-                && player_left <= platform_right
-                && player.velocity.y <= 0.0
-            {
-                on_platform = true;
-                break;
+    let elapsed = tick.elapsed_secs();
+
+    for (mut player, mut player_transform) in players.iter_mut() {
+        // Check collision with platforms (crumbled ones are gone)
+        let standing_on = platforms.iter().find(|(platform, platform_transform)| {
+            platform.solid
+                && stands_on(
+                    &player,
+                    player_transform.translation,
+                    platform,
+                    platform_transform.translation,
+                )
+        });
+        let on_platform = standing_on.is_some();
+
+        if let Some((platform, platform_transform)) = standing_on {
+            match &platform.kind {
+                // Bouncy platforms throw the player back up instead of catching them
+                PlatformKind::Bouncy { strength } => {
+                    player.velocity.y = *strength;
+                    player.grounded = false;
+                    continue;
+                }
+                // Moving platforms carry the player along
+                PlatformKind::Moving { .. } => {
+                    let delta =
+                        platform.position_at(elapsed) - platform.position_at(elapsed - FIXED_DT);
+                    player_transform.translation.x += delta.x;
+                    player_transform.translation.y =
+                        platform_transform.translation.y + (PLATFORM_HEIGHT + PLAYER_SIZE) / 2.0;
+                }
+                _ => {}
             }
         }
 
-        // Update grounded state (also check floor)
-        if on_platform || player_transform.translation.y <= -200.0 {
+        // Update grounded state
+        if on_platform {
             if !player.grounded && player.velocity.y <= 0.0 {
                 player.grounded = true;
                 player.velocity.y = 0.0;
             }
-        } else if player_transform.translation.y > -200.0 {
+        } else {
+            player.grounded = false;
+        }
+    }
+}
+
+// ==== STOMP ====
+
+// Landing on another player's head bounces you off and stuns them. Teammates pass through
+// each other unless friendly fire is on, and players of different lobby rooms sharing a
+// server never meet.
+pub fn stomp_system(
+    mut commands: Commands,
+    rules: Res<TeamRules>,
+    mut players: Query<(
+        Entity,
+        &mut Player,
+        &PlayerTransform,
+        Has<Stunned>,
+        Option<&Team>,
+        Option<&MatchRoom>,
+    )>,
+) {
+    let mut pairs = players.iter_combinations_mut();
+    while let Some([a, b]) = pairs.fetch_next() {
+        let (a_entity, mut a_player, a_transform, a_stunned, a_team, a_room) = a;
+        let (b_entity, mut b_player, b_transform, b_stunned, b_team, b_room) = b;
+        if a_room != b_room {
+            continue;
+        }
+        if !rules.friendly_fire && a_team.is_some() && a_team == b_team {
+            continue;
+        }
+        let dx = (a_transform.translation.x - b_transform.translation.x).abs();
+        let dy = a_transform.translation.y - b_transform.translation.y;
+        if dx >= PLAYER_SIZE * 0.8 {
+            continue;
+        }
+
+        // Whoever is above and falling onto the other one's head does the stomping
+        let (stomper, victim, victim_stunned) =
+            if dy > PLAYER_SIZE * 0.5 && dy < PLAYER_SIZE && a_player.velocity.y < 0.0 {
+                (&mut a_player, b_entity, b_stunned)
+            } else if -dy > PLAYER_SIZE * 0.5 && -dy < PLAYER_SIZE && b_player.velocity.y < 0.0 {
+                (&mut b_player, a_entity, a_stunned)
+            } else {
+                continue;
+            };
+        stomper.velocity.y = STOMP_BOUNCE;
+        stomper.grounded = false;
+
+        // No stun-locking: a stunned player can still be bounced on but the stun isn't refreshed
+        if !victim_stunned {
+            commands.entity(victim).insert(Stunned {
+                remaining: STUN_SECS,
+            });
+            info!("💫 Player {:?} got stomped", victim);
+        }
+    }
+}
+
+pub fn stun_recovery_system(mut commands: Commands, mut stunned: Query<(Entity, &mut Stunned)>) {
+    for (entity, mut stun) in stunned.iter_mut() {
+        stun.remaining -= FIXED_DT;
+        if stun.remaining <= 0.0 {
+            commands.entity(entity).remove::<Stunned>();
+        }
+    }
+}
+
+// ==== PLATFORM VARIANTS ====
+
+// Moving platforms follow their path as a pure function of the tick, no state to replicate
+pub fn moving_platform_system(
+    tick: Res<SimTick>,
+    mut platforms: Query<(&Platform, &mut Transform)>,
+) {
+    let elapsed = tick.elapsed_secs();
+    for (platform, mut transform) in platforms.iter_mut() {
+        if matches!(platform.kind, PlatformKind::Moving { .. }) {
+            let position = platform.position_at(elapsed);
+            transform.translation.x = position.x;
+            transform.translation.y = position.y;
+        }
+    }
+}
+
+// Crumbling platforms fall away a moment after someone steps on them and come back later
+pub fn crumbling_platform_system(
+    players: Query<(&Player, &PlayerTransform)>,
+    mut platforms: Query<(&mut Platform, &Transform), Without<Player>>,
+) {
+    let dt = FIXED_DT;
+    for (mut platform, transform) in platforms.iter_mut() {
+        let PlatformKind::Crumbling { delay, respawn } = platform.kind else {
+            continue;
+        };
+
+        let Some(timer) = platform.crumble_timer else {
+            let stepped_on = platform.solid
+                && players.iter().any(|(player, player_transform)| {
+                    stands_on(
+                        player,
+                        player_transform.translation,
+                        &platform,
+                        transform.translation,
+                    )
+                });
+            if stepped_on {
+                platform.crumble_timer = Some(0.0);
+            }
+            continue;
+        };
+
+        let timer = timer + dt;
+        if platform.solid && timer >= delay {
+            platform.solid = false;
+            platform.crumble_timer = Some(0.0);
+        } else if !platform.solid && timer >= respawn {
+            platform.solid = true;
+            platform.crumble_timer = None;
+        } else {
+            platform.crumble_timer = Some(timer);
+        }
+    }
+}
+
+// ==== CO-OP TRIGGERS ====
+
+// A plate is pressed while any player stands on it
+pub fn pressure_plate_system(
+    players: Query<&PlayerTransform, With<Player>>,
+    mut plates: Query<(&mut PressurePlate, &Transform)>,
+) {
+    for (mut plate, plate_transform) in plates.iter_mut() {
+        let pressed = players.iter().any(|player| {
+            (player.translation.x - plate_transform.translation.x).abs()
+                <= (PLATE_WIDTH + PLAYER_SIZE) / 2.0
+                && (player.translation.y - plate_transform.translation.y).abs() <= PLAYER_SIZE
+        });
+        // Only write on change so replication isn't triggered every tick
+        if plate.pressed != pressed {
+            plate.pressed = pressed;
+        }
+    }
+}
+
+// A door opens while all of its plates are pressed together (and stays open if it latches)
+pub fn door_system(plates: Query<&PressurePlate>, mut doors: Query<&mut Door>) {
+    for mut door in doors.iter_mut() {
+        if door.open && door.latch {
+            continue;
+        }
+        let open = !door.plates.is_empty()
+            && door
+                .plates
+                .iter()
+                .all(|id| plates.iter().any(|plate| &plate.id == id && plate.pressed));
+        if door.open != open {
+            door.open = open;
+        }
+    }
+}
+
+// Closed doors are walls: push players back to the side they came from
+pub fn door_collision_system(
+    mut players: Query<(&mut Player, &mut PlayerTransform)>,
+    doors: Query<(&Door, &Transform), Without<Player>>,
+) {
+    for (mut player, mut transform) in players.iter_mut() {
+        for (door, door_transform) in doors.iter() {
+            if door.open {
+                continue;
+            }
+            let dx = transform.translation.x - door_transform.translation.x;
+            let dy = transform.translation.y - door_transform.translation.y;
+            let half_width = (DOOR_WIDTH + PLAYER_SIZE) / 2.0;
+            let half_height = (DOOR_HEIGHT + PLAYER_SIZE) / 2.0;
+            if dx.abs() < half_width && dy.abs() < half_height {
+                transform.translation.x = door_transform.translation.x + half_width * dx.signum();
+                player.velocity.x = 0.0;
+            }
+        }
+    }
+}
+
+// ==== PREDICTION ====
+
+// Slack for moving platforms carrying the player and for frame time jitter
+const SNAP_SLACK: f32 = 4.0;
+
+// Fastest a player moves on its own, in units per second: running while jumping, bounced
+// off a head or a hazard, or falling at full speed, with a tenth to spare. Anything well
+// past this in one frame was a correction.
+fn max_player_speed(config: &GameConfig) -> f32 {
+    let vertical = config
+        .jump_force
+        .max(STOMP_BOUNCE)
+        .max(HAZARD_KNOCKBACK.y)
+        .max(-config.max_fall_speed);
+    config.move_speed.max(HAZARD_KNOCKBACK.x).hypot(vertical) * 1.1
+}
+
+// A rollback that changed the outcome moves the predicted player further in one frame
+// than it can move by itself. Rollbacks that changed nothing are invisible anyway.
+pub fn is_prediction_correction(config: &GameConfig, last: Vec3, now: Vec3, dt: f32) -> bool {
+    now.distance(last) > max_player_speed(config) * dt + SNAP_SLACK
+}
+
+// Furthest a player can get in one fixed tick on `level` without being respawned: running,
+// jumping or falling, launched by its fastest bouncy platform, carried by its fastest
+// moving one, plus being snapped onto a platform or pushed out of a door. The server
+// clamps anything further, see its movement validation.
+pub fn max_player_step(config: &GameConfig, level: &LevelDefinition) -> f32 {
+    let (launch, carry) = level.platforms.iter().fold(
+        (0.0f32, 0.0f32),
+        |(launch, carry), platform| match &platform.kind {
+            PlatformKind::Bouncy { strength } => (launch.max(strength.abs()), carry),
+            PlatformKind::Moving { speed, .. } => (launch, carry.max(speed.abs())),
+            _ => (launch, carry),
+        },
+    );
+    (max_player_speed(config) + launch + carry) * FIXED_DT
+        + PLATFORM_HEIGHT
+        + DOOR_WIDTH
+        + SNAP_SLACK
+}
+
+// ==== PICKUPS ====
+
+// Also used by clients to predict their own pickups, so both sides agree on a touch
+pub fn touches_pickup(player: Vec3, pickup: Vec3) -> bool {
+    player.truncate().distance(pickup.truncate()) < PICKUP_RADIUS
+}
+
+// Authoritative coin collection: whoever of the pickup's lobby room touches it first gets
+// its value. Not part of SharedPlugin because clients must not despawn replicated pickups
+// themselves.
+#[allow(clippy::type_complexity)]
+pub fn collect_pickups_system(
+    mut commands: Commands,
+    mut players: Query<
+        (
+            Entity,
+            &PlayerTransform,
+            Option<&MatchRoom>,
+            Option<&mut PlayerScore>,
+            Option<&mut MatchStats>,
+        ),
+        With<Player>,
+    >,
+    pickups: Query<(Entity, &Pickup, &Transform, Option<&MatchRoom>)>,
+) {
+    for (pickup_entity, pickup, pickup_transform, pickup_room) in pickups.iter() {
+        let collector = players.iter_mut().find(|(_, transform, room, ..)| {
+            *room == pickup_room
+                && touches_pickup(transform.translation, pickup_transform.translation)
+        });
+        let Some((player_entity, _, _, score, stats)) = collector else {
+            continue;
+        };
+        if let Some(mut stats) = stats {
+            stats.coins += pickup.value;
+        }
+
+        match score {
+            Some(mut score) => score.score += pickup.value,
+            None => {
+                commands.entity(player_entity).insert(PlayerScore {
+                    score: pickup.value,
+                });
+            }
+        }
+        commands.entity(pickup_entity).despawn();
+        info!(
+            "🪙 Player {:?} collected a pickup worth {}",
+            player_entity, pickup.value
+        );
+    }
+}
+
+// ==== FALLING OUT ====
+
+// Score lost per fall, never below zero
+const FALL_PENALTY_POINTS: u32 = 1;
+// Race time added per fall
+const FALL_PENALTY_SECS: f32 = 3.0;
+
+/// A player fell below the kill plane and was put back on a spawn point
+#[derive(Event, Clone, Debug, PartialEq)]
+pub struct PlayerFellOut {
+    pub entity: Entity,
+    /// What it cost, as the player's client is told
+    pub penalty: RespawnEvent,
+}
+
+// A player below the level's kill plane is put back on a free spawn point. It costs a point
+// of score, and in races a few seconds on the clock. Authoritative like pickups: the server
+// runs it for its matches, and the client for the games it plays without one.
+#[allow(clippy::type_complexity)]
+pub fn respawn_fallen_players_system(
+    bounds: Res<WorldBounds>,
+    level: Res<LevelDefinition>,
+    mut players: Query<(
+        Entity,
+        &PlayerId,
+        &mut Player,
+        &mut PlayerTransform,
+        Option<&Team>,
+        Option<&mut PlayerScore>,
+        Option<&mut RaceProgress>,
+    )>,
+    mut fell_out: EventWriter<PlayerFellOut>,
+) {
+    let fallen: Vec<Entity> = players
+        .iter()
+        .filter(|(.., transform, _, _, _)| bounds.fell_out(transform.translation))
+        .map(|(entity, ..)| entity)
+        .collect();
+    if fallen.is_empty() {
+        return;
+    }
+    let mut occupied: Vec<Vec2> = players
+        .iter()
+        .filter(|(entity, ..)| !fallen.contains(entity))
+        .map(|(.., transform, _, _, _)| transform.translation.truncate())
+        .collect();
+
+    for entity in fallen {
+        let Ok((_, id, mut player, mut transform, team, score, progress)) = players.get_mut(entity)
+        else {
+            continue;
+        };
+        transform.translation = level.spawn_point(team.map(|team| team.0), &occupied);
+        occupied.push(transform.translation.truncate());
+        player.velocity = Vec2::ZERO;
+        player.grounded = false;
+
+        let mut points_lost = 0;
+        if let Some(mut score) = score {
+            points_lost = score.score.min(FALL_PENALTY_POINTS);
+            score.score -= points_lost;
+        }
+        let mut seconds_added = 0.0;
+        if let Some(mut progress) = progress.filter(|progress| progress.finished.is_none()) {
+            progress.penalty += FALL_PENALTY_SECS;
+            seconds_added = FALL_PENALTY_SECS;
+        }
+        info!(
+            "🕳️ Player {} fell out of the world (-{} points, +{}s)",
+            id.id, points_lost, seconds_added
+        );
+        fell_out.write(PlayerFellOut {
+            entity,
+            penalty: RespawnEvent {
+                points_lost,
+                seconds_added,
+            },
+        });
+    }
+}
+
+// ==== GHOST MODE ====
+
+// Runners touching a hazard get thrown back and up, the ghost walks through its own traps.
+// Server only, like pickups, since clients don't know who the ghost is.
+pub fn hazard_knockback_system(
+    mut players: Query<(&mut Player, &PlayerTransform, Option<&PlayerRole>)>,
+    hazards: Query<&Transform, (With<Hazard>, Without<Player>)>,
+) {
+    for (mut player, transform, role) in players.iter_mut() {
+        if role == Some(&PlayerRole::Ghost) {
+            continue;
+        }
+        for hazard in hazards.iter() {
+            let offset = transform.translation.truncate() - hazard.translation.truncate();
+            if offset.length() >= HAZARD_RADIUS + PLAYER_SIZE / 2.0 {
+                continue;
+            }
+            let direction = if offset.x < 0.0 { -1.0 } else { 1.0 };
+            player.velocity = Vec2::new(direction * HAZARD_KNOCKBACK.x, HAZARD_KNOCKBACK.y);
             player.grounded = false;
         }
     }
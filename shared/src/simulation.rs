@@ -0,0 +1,169 @@
+// Deterministic simulation: every fixed step advances the world by exactly `FIXED_DT` and
+// counts one `SimTick`, whatever the wall clock or time dilation says. Anything that is a
+// function of time (moving platforms) reads the tick, so two machines on the same tick
+// agree. The server sends its tick with a checksum of its world state, and clients
+// compare it with the one they computed for that tick.
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+use crate::clock::TICK_RATE_HZ;
+use crate::protocol_plugin::Platform;
+
+/// Seconds one fixed step simulates, the same on every machine
+pub const FIXED_DT: f32 = (1.0 / TICK_RATE_HZ) as f32;
+/// Ticks between two `TickSync`s from the server
+pub const TICK_SYNC_INTERVAL: u32 = TICK_RATE_HZ as u32;
+// Checksums kept for comparing with the server's, a few seconds' worth
+const CHECKSUM_HISTORY: usize = 4 * TICK_RATE_HZ as usize;
+// Positions are compared to a hundredth of a unit, float noise below that is ignored
+const QUANTUM: f32 = 100.0;
+
+/// Fixed steps simulated since the match started. Clients take the server's.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct SimTick(pub u32);
+
+impl SimTick {
+    /// Simulated seconds at this tick
+    pub fn elapsed_secs(&self) -> f32 {
+        self.0 as f32 * FIXED_DT
+    }
+}
+
+/// Checksums of the last few ticks, oldest first
+#[derive(Resource, Default, Clone, Debug)]
+pub struct ChecksumHistory(VecDeque<(u32, u64)>);
+
+impl ChecksumHistory {
+    pub fn record(&mut self, tick: u32, checksum: u64) {
+        // Ticks only go up, a lower one means the tick was reset
+        if self.0.back().is_some_and(|&(last, _)| tick <= last) {
+            self.0.clear();
+        }
+        self.0.push_back((tick, checksum));
+        while self.0.len() > CHECKSUM_HISTORY {
+            self.0.pop_front();
+        }
+    }
+
+    pub fn get(&self, tick: u32) -> Option<u64> {
+        self.0
+            .iter()
+            .find(|(recorded, _)| *recorded == tick)
+            .map(|(_, checksum)| *checksum)
+    }
+
+    pub fn latest(&self) -> Option<(u32, u64)> {
+        self.0.back().copied()
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// Order-independent checksum of the world state the simulation owns. Entity ids and query
+/// order differ between machines, so every entity is hashed on its own and the hashes are
+/// added up. Players are left out: their inputs reach the server late and prediction
+/// corrects them anyway.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StateChecksum(u64);
+
+impl StateChecksum {
+    pub fn add_platform(&mut self, platform: &Platform, position: Vec3) {
+        let mut hash = Fnv::default();
+        hash.write_f32(position.x);
+        hash.write_f32(position.y);
+        hash.write_u32(platform.solid as u32);
+        hash.write_f32(platform.crumble_timer.unwrap_or(-1.0));
+        self.0 = self.0.wrapping_add(hash.0);
+    }
+
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+// FNV-1a, stable across platforms and releases unlike std's hasher
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv {
+    fn write_u32(&mut self, value: u32) {
+        for byte in value.to_le_bytes() {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_f32(&mut self, value: f32) {
+        self.write_u32((value * QUANTUM).round() as i32 as u32);
+    }
+}
+
+// First in the fixed step: the tick everything after it simulates
+pub fn advance_tick_system(mut tick: ResMut<SimTick>) {
+    tick.0 = tick.0.wrapping_add(1);
+}
+
+// Last in the fixed step: remember what the world looked like at this tick
+pub fn record_checksum_system(
+    tick: Res<SimTick>,
+    mut history: ResMut<ChecksumHistory>,
+    platforms: Query<(&Platform, &Transform)>,
+) {
+    let mut checksum = StateChecksum::default();
+    for (platform, transform) in platforms.iter() {
+        checksum.add_platform(platform, transform.translation);
+    }
+    history.record(tick.0, checksum.value());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol_plugin::PlatformKind;
+
+    #[test]
+    fn checksum_ignores_order_but_not_state() {
+        let a = Platform::new(PlatformKind::Static, Vec2::ZERO);
+        let mut b = Platform::new(PlatformKind::Static, Vec2::new(100.0, 0.0));
+        let at = |x: f32| Vec3::new(x, 0.0, 0.0);
+
+        let mut one = StateChecksum::default();
+        one.add_platform(&a, at(0.0));
+        one.add_platform(&b, at(100.0));
+        let mut other = StateChecksum::default();
+        other.add_platform(&b, at(100.0));
+        other.add_platform(&a, at(0.0001));
+        assert_eq!(one, other);
+
+        b.solid = false;
+        let mut crumbled = StateChecksum::default();
+        crumbled.add_platform(&a, at(0.0));
+        crumbled.add_platform(&b, at(100.0));
+        assert_ne!(one, crumbled);
+    }
+
+    #[test]
+    fn history_keeps_recent_ticks() {
+        let mut history = ChecksumHistory::default();
+        for tick in 0..1000 {
+            history.record(tick, tick as u64 * 7);
+        }
+        assert_eq!(history.get(999), Some(999 * 7));
+        assert_eq!(history.get(10), None);
+        assert_eq!(history.latest(), Some((999, 999 * 7)));
+
+        // A reset tick starts over
+        history.record(5, 1);
+        assert_eq!(history.get(999), None);
+        assert_eq!(history.latest(), Some((5, 1)));
+        assert!((SimTick(120).elapsed_secs() - 2.0).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,163 @@
+// Warm servers: a quick match used to wait 30-60s for a fresh Edgegap deployment. The
+// matchmaker keeps a few idle servers deployed per region and game mode instead, hands
+// one out when a match is made and deploys a replacement in the background. This is the
+// pool's bookkeeping, the matchmaker drives it (see "Warm server pool" in
+// docs/matchmaker.md). Only servers started with the standard settings of their mode can
+// be warm, a room with its own level or rules still gets a fresh deployment.
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::api::QueueShard;
+use crate::game_mode::GameMode;
+
+/// Seconds an idle server may wait for a match before it is replaced, well inside
+/// Edgegap's limit on a deployment's lifetime
+pub const DEFAULT_WARM_MAX_IDLE_SECS: u64 = 30 * 60;
+
+/// A deployed server nobody plays on yet
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct WarmServer {
+    /// Edgegap request id
+    pub request_id: String,
+    /// Address clients connect to
+    pub url: String,
+    /// Unix timestamp in seconds it became ready
+    pub ready_at: u64,
+}
+
+/// Idle servers of one `QueueShard`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WarmPool {
+    /// Idle servers to keep ready
+    pub target: usize,
+    /// Ready servers, oldest first
+    pub idle: Vec<WarmServer>,
+    /// Deployments started for the pool that are not ready yet
+    pub deploying: usize,
+}
+
+impl WarmPool {
+    pub fn new(target: usize) -> Self {
+        Self {
+            target,
+            ..Default::default()
+        }
+    }
+
+    /// A server for a match that was just made. The oldest goes first, it is the next to
+    /// be recycled. None when the pool ran dry, and the match deploys its own.
+    pub fn take(&mut self) -> Option<WarmServer> {
+        (!self.idle.is_empty()).then(|| self.idle.remove(0))
+    }
+
+    /// Deployments to start now to get back to `target`, counted as deploying
+    pub fn replenish(&mut self) -> usize {
+        let missing = self.target.saturating_sub(self.idle.len() + self.deploying);
+        self.deploying += missing;
+        missing
+    }
+
+    /// One of the pool's deployments is ready
+    pub fn ready(&mut self, server: WarmServer) {
+        self.deploying = self.deploying.saturating_sub(1);
+        self.idle.push(server);
+    }
+
+    /// One of the pool's deployments failed or timed out, `replenish` tries again
+    pub fn failed(&mut self) {
+        self.deploying = self.deploying.saturating_sub(1);
+    }
+
+    /// Idle servers older than `max_idle_secs`, taken out of the pool for the matchmaker
+    /// to stop. `replenish` replaces them.
+    pub fn expire(&mut self, now: u64, max_idle_secs: u64) -> Vec<WarmServer> {
+        let (expired, idle) = std::mem::take(&mut self.idle)
+            .into_iter()
+            .partition(|server| server.ready_at + max_idle_secs <= now);
+        self.idle = idle;
+        expired
+    }
+}
+
+/// Parse the matchmaker's `MM_WARM_POOL`, e.g. `eu:casual=2,us:casual=1`: idle servers
+/// to keep per region and game mode. Empty means no pool.
+pub fn parse_warm_pool(spec: &str) -> Result<HashMap<QueueShard, usize>, String> {
+    let mut pools = HashMap::new();
+    for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (shard, size) = entry
+            .split_once('=')
+            .ok_or_else(|| format!("'{}' is not region:game_mode=size", entry))?;
+        let (region, game_mode) = shard
+            .split_once(':')
+            .ok_or_else(|| format!("'{}' is not region:game_mode", shard))?;
+        if GameMode::from_id(game_mode).is_none() {
+            return Err(format!("'{}' is not a game mode", game_mode));
+        }
+        let size: usize = size
+            .parse()
+            .map_err(|_| format!("'{}' is not a pool size", size))?;
+        let shard = QueueShard {
+            game_mode: game_mode.to_string(),
+            region: region.to_string(),
+        };
+        if pools.insert(shard, size).is_some() {
+            return Err(format!("{}:{} is listed twice", region, game_mode));
+        }
+    }
+    Ok(pools)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(id: &str, ready_at: u64) -> WarmServer {
+        WarmServer {
+            request_id: id.to_string(),
+            url: format!("{}.edgegap.net:4000", id),
+            ready_at,
+        }
+    }
+
+    #[test]
+    fn pools_refill_what_they_hand_out() {
+        let mut pool = WarmPool::new(2);
+        assert_eq!(pool.take(), None);
+        assert_eq!(pool.replenish(), 2);
+        assert_eq!(pool.replenish(), 0);
+
+        pool.ready(server("a", 10));
+        pool.failed();
+        assert_eq!(pool.replenish(), 1);
+        pool.ready(server("b", 20));
+
+        assert_eq!(pool.take(), Some(server("a", 10)));
+        assert_eq!(pool.replenish(), 1);
+
+        assert_eq!(pool.expire(1_000, 600), vec![server("b", 20)]);
+        assert!(pool.idle.is_empty());
+        assert_eq!(pool.replenish(), 1);
+    }
+
+    #[test]
+    fn pool_sizes_come_from_the_environment() {
+        let pools = parse_warm_pool(" eu:casual=2, us:race=1 ").unwrap();
+        assert_eq!(pools.len(), 2);
+        let eu = QueueShard {
+            game_mode: "casual".to_string(),
+            region: "eu".to_string(),
+        };
+        assert_eq!(pools[&eu], 2);
+        assert!(parse_warm_pool("").unwrap().is_empty());
+
+        for spec in [
+            "eu:casual",
+            "eu=2",
+            "eu:brawl=1",
+            "eu:casual=x",
+            "eu:casual=1,eu:casual=2",
+        ] {
+            assert!(parse_warm_pool(spec).is_err(), "{}", spec);
+        }
+    }
+}
@@ -1,7 +1,15 @@
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
 use clap::{Parser, Subcommand};
 use edgegap_async::apis::{configuration::Configuration, lobbies_api};
 use edgegap_async::models::{LobbyCreatePayload, LobbyDeployPayload, LobbyTerminatePayload};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::time::{Duration, Instant};
+
+mod output;
+
+use output::{OutputFormat, RecordPrinter};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -17,6 +25,10 @@ struct Cli {
     #[arg(long, env = "EDGEGAP_TOKEN")]
     token: String,
 
+    /// How to print answers
+    #[arg(long, value_enum, global = true, default_value_t = OutputFormat::Json)]
+    output: OutputFormat,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -26,15 +38,61 @@ enum Commands {
     /// Create a new lobby with the given name
     Create { name: String },
     /// Deploy a lobby by name
-    Deploy { name: String },
+    Deploy {
+        name: String,
+        /// Wait until the deployment is ready, then print its URL
+        #[arg(long)]
+        wait: bool,
+        /// Give up waiting after this many seconds
+        #[arg(long, default_value_t = 120)]
+        timeout_secs: u64,
+    },
     /// Terminate a lobby by name
     Terminate { name: String },
     /// Delete a lobby by name
     Delete { name: String },
+    /// Delete every lobby whose name matches a filter
+    DeleteAll {
+        /// Name to match, `*` stands for any run of characters, e.g. `test-*`
+        #[arg(long)]
+        filter: String,
+        /// Terminate each lobby's deployment before deleting it
+        #[arg(long)]
+        terminate: bool,
+        /// Print what would be deleted without deleting it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Poll lobbies and print every status change until Ctrl-C
+    Watch {
+        /// Lobbies to watch, every lobby if none are given
+        names: Vec<String>,
+        #[arg(long, default_value_t = 5)]
+        interval_secs: u64,
+    },
     /// Get lobby details by name
     Get { name: String },
     /// List all lobbies
     List,
+    /// Stop deployments no lobby room uses anymore
+    Reap {
+        /// Lobby service API, e.g. https://voidloop.quest/lobby/api
+        #[arg(long, env = "VOIDLOOP_LOBBY_URL")]
+        lobby_url: String,
+        /// Admin token for the lobby service
+        #[arg(long, env = "VOIDLOOP_LOBBY_TOKEN")]
+        lobby_token: Option<String>,
+        /// Leave deployments younger than this alone. Quick matches have no room, so keep
+        /// it above the longest match.
+        #[arg(long, default_value_t = 3600)]
+        min_age_secs: u64,
+        /// Print what would be stopped without stopping it
+        #[arg(long)]
+        dry_run: bool,
+        /// Keep running and reap every this many seconds
+        #[arg(long)]
+        every_secs: Option<u64>,
+    },
 }
 
 #[tokio::main]
@@ -48,35 +106,449 @@ async fn main() -> Result<()> {
         key: cli.token,
     });
 
+    let output = cli.output;
     match cli.command {
         Commands::Create { name } => {
             let payload = LobbyCreatePayload::new(name);
             let res = lobbies_api::lobby_create(&cfg, payload).await?;
-            println!("{}", serde_json::to_string_pretty(&res)?);
+            output.print(&res)?;
         }
-        Commands::Deploy { name } => {
-            let payload = LobbyDeployPayload { name };
+        Commands::Deploy {
+            name,
+            wait,
+            timeout_secs,
+        } => {
+            let payload = LobbyDeployPayload { name: name.clone() };
             let res = lobbies_api::lobby_deploy(&cfg, payload).await?;
-            println!("{}", serde_json::to_string_pretty(&res)?);
+            if wait {
+                let url = wait_until_ready(&cfg, &name, Duration::from_secs(timeout_secs)).await?;
+                output.print(&json!({ "name": name, "url": url }))?;
+            } else {
+                output.print(&res)?;
+            }
         }
         Commands::Terminate { name } => {
             let payload = LobbyTerminatePayload { name };
             let res = lobbies_api::lobby_terminate(&cfg, payload).await?;
-            println!("{}", serde_json::to_string_pretty(&res)?);
+            output.print(&res)?;
         }
         Commands::Delete { name } => {
             let res = lobbies_api::lobby_delete(&cfg, &name).await?;
-            println!("{}", serde_json::to_string_pretty(&res)?);
+            output.print(&res)?;
         }
+        Commands::DeleteAll {
+            filter,
+            terminate,
+            dry_run,
+        } => delete_all(&cfg, output, &filter, terminate, dry_run).await?,
+        Commands::Watch {
+            names,
+            interval_secs,
+        } => watch(&cfg, output, &names, interval_secs).await?,
         Commands::Get { name } => {
             let res = lobbies_api::lobby_get(&cfg, &name).await?;
-            println!("{}", serde_json::to_string_pretty(&res)?);
+            output.print(&res)?;
         }
         Commands::List => {
             let res = lobbies_api::lobby_list(&cfg).await?;
-            println!("{}", serde_json::to_string_pretty(&res)?);
+            output.print(&res)?;
+        }
+        Commands::Reap {
+            lobby_url,
+            lobby_token,
+            min_age_secs,
+            dry_run,
+            every_secs,
+        } => {
+            let reaper = Reaper {
+                lobby_url,
+                lobby_token,
+                min_age: Duration::from_secs(min_age_secs),
+                dry_run,
+                output,
+            };
+            match every_secs {
+                None => reaper.run(&cfg).await?,
+                Some(every_secs) => reaper.run_forever(&cfg, every_secs).await,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// A deploy call returns before the server is up. Poll the lobby, backing off
+// from 1s to 10s, until Edgegap reports it ready and hands out its URL.
+async fn wait_until_ready(cfg: &Configuration, name: &str, timeout: Duration) -> Result<String> {
+    let started = Instant::now();
+    let mut delay = Duration::from_secs(1);
+    let mut last_status = String::new();
+
+    loop {
+        // Read the fields off the JSON so any lobby payload version works
+        let lobby = serde_json::to_value(lobbies_api::lobby_get(cfg, name).await?)?;
+        let status = lobby["status"].as_str().unwrap_or_default().to_string();
+        let url = lobby["url"].as_str().filter(|url| !url.is_empty());
+        let joinable = lobby["is_joinable"].as_bool().unwrap_or(false);
+
+        if status != last_status {
+            eprintln!("[{:>3}s] {}: {}", started.elapsed().as_secs(), name, status);
+            last_status = status.clone();
+        }
+        if let Some(url) = url.filter(|_| joinable || status.eq_ignore_ascii_case("ready")) {
+            return Ok(url.to_string());
+        }
+        if status.eq_ignore_ascii_case("terminated") || status.eq_ignore_ascii_case("error") {
+            bail!("lobby {} stopped while deploying ({})", name, status);
+        }
+        if started.elapsed() + delay > timeout {
+            bail!(
+                "lobby {} not ready after {}s (last status {})",
+                name,
+                timeout.as_secs(),
+                status
+            );
+        }
+
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(Duration::from_secs(10));
+    }
+}
+
+// Deployments outlive their match when a server crashes or never gets a player, and keep
+// costing money until Edgegap's own time limit. The reaper compares Edgegap's running
+// deployments with the ones the lobby service's rooms use, and stops the others once they
+// are old enough that they can't still be starting up.
+struct Reaper {
+    lobby_url: String,
+    lobby_token: Option<String>,
+    min_age: Duration,
+    dry_run: bool,
+    output: OutputFormat,
+}
+
+struct Deployment {
+    request_id: String,
+    age: Duration,
+}
+
+impl Reaper {
+    async fn run_forever(&self, cfg: &Configuration, every_secs: u64) {
+        let mut interval = tokio::time::interval(Duration::from_secs(every_secs.max(1)));
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = tokio::signal::ctrl_c() => return,
+            }
+            // A failed run is retried on the next tick, never taken for "no rooms"
+            if let Err(e) = self.run(cfg).await {
+                eprintln!("reap failed: {:#}", e);
+            }
+        }
+    }
+
+    async fn run(&self, cfg: &Configuration) -> Result<()> {
+        // Rooms first: a deployment started in between is too young to be reaped anyway
+        let claimed = self.claimed_deployments(cfg).await?;
+        let deployments = running_deployments(cfg).await?;
+        let orphans: Vec<&Deployment> = deployments
+            .iter()
+            .filter(|d| d.age >= self.min_age && !claimed.contains(&d.request_id))
+            .collect();
+        eprintln!(
+            "{} deployments, {} used by rooms, {} orphaned",
+            deployments.len(),
+            claimed.len(),
+            orphans.len()
+        );
+        let mut results = Vec::new();
+        for orphan in orphans {
+            let (result, error) = if self.dry_run {
+                ("would_stop", None)
+            } else {
+                match stop_deployment(cfg, &orphan.request_id).await {
+                    Ok(()) => ("stopped", None),
+                    Err(e) => ("failed", Some(format!("{:#}", e))),
+                }
+            };
+            results.push(json!({
+                "request_id": orphan.request_id,
+                "up": format_age(orphan.age),
+                "result": result,
+                "error": error,
+            }));
+        }
+        self.output.print(&results)
+    }
+
+    // Every room the service knows, started ones included, as `[TrackedRoom]`
+    async fn claimed_deployments(&self, cfg: &Configuration) -> Result<HashSet<String>> {
+        let url = format!("{}/admin/rooms", self.lobby_url.trim_end_matches('/'));
+        let mut request = cfg.client.get(&url);
+        if let Some(token) = &self.lobby_token {
+            request = request.bearer_auth(token);
+        }
+        let rooms: Vec<Value> = request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("listing rooms at {}", url))?
+            .json()
+            .await?;
+        Ok(rooms
+            .iter()
+            .filter_map(|room| room["deployment"].as_str())
+            .map(str::to_string)
+            .collect())
+    }
+}
+
+// `GET /v1/deployments`, every page. Fields are read off the JSON like in
+// `wait_until_ready`, so any payload version works.
+async fn running_deployments(cfg: &Configuration) -> Result<Vec<Deployment>> {
+    let now = Utc::now();
+    let mut deployments = Vec::new();
+    let mut page = 1;
+    loop {
+        let url = format!("{}/v1/deployments?page={}", cfg.base_path, page);
+        let body: Value = edgegap_request(cfg, cfg.client.get(&url))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("listing deployments at {}", url))?
+            .json()
+            .await?;
+        for deployment in body["data"].as_array().into_iter().flatten() {
+            let Some(request_id) = deployment["request_id"].as_str() else {
+                continue;
+            };
+            let status = deployment["status"].as_str().unwrap_or_default();
+            if status.to_ascii_lowercase().contains("terminat") {
+                continue;
+            }
+            // Without a start time its age is unknown, and unknown is never old enough
+            let Some(started) = deployment["start_time"].as_str().and_then(parse_time) else {
+                eprintln!("skipping {}: no start time", request_id);
+                continue;
+            };
+            deployments.push(Deployment {
+                request_id: request_id.to_string(),
+                age: (now - started).to_std().unwrap_or_default(),
+            });
+        }
+        if !body["pagination"]["has_next"].as_bool().unwrap_or(false) {
+            return Ok(deployments);
+        }
+        page += 1;
+    }
+}
+
+async fn stop_deployment(cfg: &Configuration, request_id: &str) -> Result<()> {
+    let url = format!("{}/v1/stop/{}", cfg.base_path, request_id);
+    edgegap_request(cfg, cfg.client.delete(&url))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())?;
+    Ok(())
+}
+
+// Authorized the same way as the generated client's calls
+fn edgegap_request(
+    cfg: &Configuration,
+    request: reqwest::RequestBuilder,
+) -> reqwest::RequestBuilder {
+    match &cfg.api_key {
+        Some(key) => {
+            let value = match &key.prefix {
+                Some(prefix) => format!("{} {}", prefix, key.key),
+                None => key.key.clone(),
+            };
+            request.header(reqwest::header::AUTHORIZATION, value)
+        }
+        None => request,
+    }
+}
+
+// Edgegap answers "2024-05-13 18:53:49.962938" in UTC, newer payloads RFC 3339
+fn parse_time(time: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(time)
+        .map(|time| time.with_timezone(&Utc))
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S%.f").map(|time| time.and_utc())
+        })
+        .ok()
+}
+
+fn format_age(age: Duration) -> String {
+    let minutes = age.as_secs() / 60;
+    format!("{}h{:02}m", minutes / 60, minutes % 60)
+}
+
+// Lobbies of a list answer, which wraps them differently between API versions
+fn lobby_items(list: &Value) -> Vec<&Value> {
+    match list {
+        Value::Array(items) => items.iter().collect(),
+        Value::Object(fields) => fields
+            .values()
+            .filter_map(Value::as_array)
+            .flatten()
+            .filter(|item| item["name"].is_string())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// `*` matches any run of characters, everything else itself
+fn matches_filter(filter: &str, name: &str) -> bool {
+    let mut parts = filter.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No `*` at all
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
         }
     }
+    rest.ends_with(last)
+}
+
+// Keeps going past lobbies that fail, and fails at the end if any did
+async fn delete_all(
+    cfg: &Configuration,
+    output: OutputFormat,
+    filter: &str,
+    terminate: bool,
+    dry_run: bool,
+) -> Result<()> {
+    if filter.is_empty() {
+        bail!("--filter can't be empty, use `*` to delete every lobby");
+    }
+    let list = serde_json::to_value(lobbies_api::lobby_list(cfg).await?)?;
+    let names: Vec<String> = lobby_items(&list)
+        .into_iter()
+        .filter_map(|lobby| lobby["name"].as_str())
+        .filter(|name| matches_filter(filter, name))
+        .map(str::to_string)
+        .collect();
 
+    let mut results = Vec::new();
+    let mut failed = 0;
+    for name in names {
+        let outcome = if dry_run {
+            Ok("would_delete")
+        } else {
+            delete_lobby(cfg, &name, terminate)
+                .await
+                .map(|()| "deleted")
+        };
+        let (result, error) = match outcome {
+            Ok(result) => (result, None),
+            Err(e) => {
+                failed += 1;
+                ("failed", Some(format!("{:#}", e)))
+            }
+        };
+        results.push(json!({ "name": name, "result": result, "error": error }));
+    }
+    output.print(&results)?;
+    if failed > 0 {
+        bail!(
+            "{} of {} lobbies could not be deleted",
+            failed,
+            results.len()
+        );
+    }
     Ok(())
 }
+
+async fn delete_lobby(cfg: &Configuration, name: &str, terminate: bool) -> Result<()> {
+    if terminate {
+        let payload = LobbyTerminatePayload {
+            name: name.to_string(),
+        };
+        lobbies_api::lobby_terminate(cfg, payload)
+            .await
+            .context("terminating")?;
+    }
+    lobbies_api::lobby_delete(cfg, name)
+        .await
+        .context("deleting")?;
+    Ok(())
+}
+
+// Polls the lobbies and prints one record per status change. A lobby that can't be read
+// anymore is reported as `gone`, once.
+async fn watch(
+    cfg: &Configuration,
+    output: OutputFormat,
+    names: &[String],
+    interval_secs: u64,
+) -> Result<()> {
+    let mut printer = RecordPrinter::new(output, &["time", "name", "from", "to"]);
+    let mut last: BTreeMap<String, String> = BTreeMap::new();
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs.max(1)));
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+        let statuses = match lobby_statuses(cfg, names).await {
+            Ok(statuses) => statuses,
+            Err(e) => {
+                eprintln!("poll failed: {:#}", e);
+                continue;
+            }
+        };
+        let gone: Vec<String> = last
+            .keys()
+            .filter(|name| !statuses.contains_key(*name))
+            .cloned()
+            .collect();
+        let changes = statuses
+            .iter()
+            .filter(|(name, status)| last.get(*name) != Some(status))
+            .map(|(name, status)| (name.clone(), status.clone()))
+            .chain(gone.into_iter().map(|name| (name, "gone".to_string())))
+            .collect::<Vec<_>>();
+        for (name, status) in changes {
+            let from = if status == "gone" {
+                last.remove(&name)
+            } else {
+                last.insert(name.clone(), status.clone())
+            };
+            printer.print(&json!({
+                "time": Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                "name": name,
+                "from": from,
+                "to": status,
+            }))?;
+        }
+    }
+}
+
+// Status of each watched lobby, or of every lobby. Named lobbies that can't be read are
+// left out, like lobbies that left the list.
+async fn lobby_statuses(cfg: &Configuration, names: &[String]) -> Result<BTreeMap<String, String>> {
+    let status = |lobby: &Value| lobby["status"].as_str().unwrap_or("unknown").to_string();
+    if names.is_empty() {
+        let list = serde_json::to_value(lobbies_api::lobby_list(cfg).await?)?;
+        return Ok(lobby_items(&list)
+            .into_iter()
+            .filter_map(|lobby| Some((lobby["name"].as_str()?.to_string(), status(lobby))))
+            .collect());
+    }
+    let mut statuses = BTreeMap::new();
+    for name in names {
+        if let Ok(lobby) = lobbies_api::lobby_get(cfg, name).await {
+            statuses.insert(name.clone(), status(&serde_json::to_value(lobby)?));
+        }
+    }
+    Ok(statuses)
+}
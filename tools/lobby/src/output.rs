@@ -0,0 +1,153 @@
+// How commands print what Edgegap answers. JSON is what the tool always printed, tables
+// are for people and YAML for config-minded scripts. Streams such as `watch` print one
+// record at a time: a JSON object per line, a YAML document each, or a table row.
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Json,
+    Table,
+    Yaml,
+}
+
+impl OutputFormat {
+    /// Print a whole answer
+    pub fn print<T: Serialize>(self, value: &T) -> Result<()> {
+        let value = serde_json::to_value(value)?;
+        match self {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&value)?),
+            OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&value)?),
+            OutputFormat::Table => {
+                let rows = rows(&value);
+                let columns = columns(&rows);
+                print_table(&columns, &rows, true, &mut Vec::new());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Prints the records of a stream as they come
+pub struct RecordPrinter {
+    format: OutputFormat,
+    columns: Vec<String>,
+    printed_header: bool,
+    // Table columns only widen, so rows printed later line up with earlier ones
+    widths: Vec<usize>,
+}
+
+impl RecordPrinter {
+    /// `columns` are the table's, every record has the same fields
+    pub fn new(format: OutputFormat, columns: &[&str]) -> Self {
+        Self {
+            format,
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            printed_header: false,
+            widths: Vec::new(),
+        }
+    }
+
+    pub fn print<T: Serialize>(&mut self, record: &T) -> Result<()> {
+        let record = serde_json::to_value(record)?;
+        match self.format {
+            OutputFormat::Json => println!("{}", serde_json::to_string(&record)?),
+            OutputFormat::Yaml => print!("---\n{}", serde_yaml::to_string(&record)?),
+            OutputFormat::Table => {
+                print_table(
+                    &self.columns,
+                    &[&record],
+                    !self.printed_header,
+                    &mut self.widths,
+                );
+                self.printed_header = true;
+            }
+        }
+        Ok(())
+    }
+}
+
+// The rows of an answer: the items of a list, the list an envelope like
+// `{"data": [...], "count": 3}` carries, or the one object
+fn rows(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(items) => items.iter().collect(),
+        Value::Object(fields) => {
+            let mut lists = fields.values().filter(|v| v.is_array());
+            match (lists.next(), lists.next()) {
+                (Some(Value::Array(items)), None) => items.iter().collect(),
+                _ => vec![value],
+            }
+        }
+        _ => vec![value],
+    }
+}
+
+// Every field any row has, in the order they first show up
+fn columns(rows: &[&Value]) -> Vec<String> {
+    let mut columns: Vec<String> = Vec::new();
+    for row in rows {
+        let Some(fields) = row.as_object() else {
+            continue;
+        };
+        for key in fields.keys() {
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+    }
+    columns
+}
+
+fn print_table(columns: &[String], rows: &[&Value], header: bool, widths: &mut Vec<usize>) {
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| match row {
+            Value::Object(fields) => columns.iter().map(|c| cell(fields.get(c))).collect(),
+            // A list of names or the like, one column without a header
+            other => vec![cell(Some(other))],
+        })
+        .collect();
+    let columns: Vec<String> = if columns.is_empty() {
+        vec![String::new()]
+    } else {
+        columns.to_vec()
+    };
+    widths.resize(columns.len(), 0);
+    for (i, column) in columns.iter().enumerate() {
+        let widest = cells
+            .iter()
+            .filter_map(|row| row.get(i))
+            .map(|c| c.chars().count())
+            .chain([column.chars().count()])
+            .max()
+            .unwrap_or(0);
+        widths[i] = widths[i].max(widest);
+    }
+    let line = |values: &[String]| {
+        let padded: Vec<String> = values
+            .iter()
+            .zip(widths.iter())
+            .map(|(value, width)| format!("{:<width$}", value, width = width))
+            .collect();
+        println!("{}", padded.join("  ").trim_end());
+    };
+    if header && !columns.iter().all(String::is_empty) {
+        line(&columns.iter().map(|c| c.to_uppercase()).collect::<Vec<_>>());
+    }
+    for row in &cells {
+        line(row);
+    }
+}
+
+// Strings without their quotes, nested values as compact JSON
+fn cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => "-".to_string(),
+        Some(Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+    }
+}
@@ -0,0 +1,129 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use wasmparser::{KnownCustom, Name, Payload, TypeRef};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "size-report",
+    about = "Print per-crate code size contributions of a wasm bundle"
+)]
+struct Cli {
+    /// Path to the wasm file (build without `strip` so the name section is kept)
+    #[arg(default_value = "target/wasm32-unknown-unknown/wasm-release/voidloop-quest-client.wasm")]
+    wasm: PathBuf,
+
+    /// Only show the N largest crates
+    #[arg(short, long, default_value_t = 30)]
+    top: usize,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let bytes =
+        std::fs::read(&cli.wasm).with_context(|| format!("reading {}", cli.wasm.display()))?;
+
+    let mut imported_functions = 0u32;
+    let mut body_sizes: Vec<usize> = Vec::new();
+    let mut function_names: HashMap<u32, String> = HashMap::new();
+    let mut data_size = 0usize;
+
+    for payload in wasmparser::Parser::new(0).parse_all(&bytes) {
+        match payload? {
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    if matches!(import?.ty, TypeRef::Func(_)) {
+                        imported_functions += 1;
+                    }
+                }
+            }
+            Payload::CodeSectionEntry(body) => {
+                body_sizes.push(body.range().len());
+            }
+            Payload::DataSection(reader) => {
+                data_size += reader.range().len();
+            }
+            Payload::CustomSection(reader) => {
+                if let KnownCustom::Name(names) = reader.as_known() {
+                    for subsection in names {
+                        if let Name::Function(map) = subsection? {
+                            for naming in map {
+                                let naming = naming?;
+                                function_names.insert(naming.index, naming.name.to_string());
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if function_names.is_empty() {
+        eprintln!("⚠️  No name section found - per-crate attribution will be unavailable");
+    }
+
+    let mut per_crate: HashMap<String, usize> = HashMap::new();
+    for (i, size) in body_sizes.iter().enumerate() {
+        let index = imported_functions + i as u32;
+        let krate = function_names
+            .get(&index)
+            .map(|name| crate_of(name))
+            .unwrap_or_else(|| "<unnamed>".to_string());
+        *per_crate.entry(krate).or_default() += size;
+    }
+
+    let code_total: usize = body_sizes.iter().sum();
+    let mut rows: Vec<_> = per_crate.into_iter().collect();
+    rows.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+
+    println!("📦 {}", cli.wasm.display());
+    println!(
+        "   file: {}  code: {}  data: {}",
+        human(bytes.len()),
+        human(code_total),
+        human(data_size)
+    );
+    println!();
+    println!("{:>10}  {:>6}  crate", "size", "%");
+    for (krate, size) in rows.iter().take(cli.top) {
+        let pct = if code_total > 0 {
+            *size as f64 * 100.0 / code_total as f64
+        } else {
+            0.0
+        };
+        println!("{:>10}  {:>5.1}%  {}", human(*size), pct, krate);
+    }
+    if rows.len() > cli.top {
+        let rest: usize = rows.iter().skip(cli.top).map(|(_, s)| s).sum();
+        println!(
+            "{:>10}          ({} more crates)",
+            human(rest),
+            rows.len() - cli.top
+        );
+    }
+
+    Ok(())
+}
+
+/// Derive the owning crate from a (possibly mangled) function name
+fn crate_of(raw: &str) -> String {
+    let demangled = format!("{:#}", rustc_demangle::demangle(raw));
+    // Trait impls demangle as `<bevy_ecs::World as core::fmt::Debug>::fmt`
+    let path = demangled.trim_start_matches('<').trim_start_matches('&');
+    match path.split_once("::") {
+        Some((krate, _)) if !krate.is_empty() && !krate.contains(' ') => krate.to_string(),
+        _ => "<other>".to_string(),
+    }
+}
+
+fn human(bytes: usize) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.2} MiB", bytes as f64 / (1024.0 * 1024.0))
+    } else if bytes >= 1024 {
+        format!("{:.1} KiB", bytes as f64 / 1024.0)
+    } else {
+        format!("{} B", bytes)
+    }
+}
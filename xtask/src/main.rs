@@ -0,0 +1,519 @@
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use std::thread;
+use std::time::Duration;
+
+const PROTOCOL_SOURCE: &str = "shared/src/protocol_plugin.rs";
+const PROTOCOL_SNAPSHOT: &str = "shared/protocol.snapshot";
+// Built web client, ready to serve or deploy
+const WEB_DIST: &str = "target/web";
+const SW_TEMPLATE: &str = "client/sw.template.js";
+// The page and offline fallback, copied from client/www next to the wasm bundle
+const WEB_STATIC_FILES: &[&str] = &["index.html", "offline.html", "logo.svg"];
+// Files served next to index.html that make up the web app shell
+const SHELL_FILES: &[&str] = &[
+    "./",
+    "./index.html",
+    "./offline.html",
+    "./voidloop-quest.js",
+    "./voidloop-quest_bg.wasm",
+    "./logo.svg",
+];
+
+#[derive(Parser, Debug)]
+#[command(name = "xtask", about = "Voidloop Quest developer workflow commands")]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Run a local game server and serve the web client (plus the bevygap matchmaker if requested)
+    Dev {
+        /// Port for the static web client
+        #[arg(long, default_value_t = 8000)]
+        web_port: u16,
+
+        /// Path to a bevygap checkout; runs its matchmaker and matchmaker httpd alongside
+        #[arg(long, env = "BEVYGAP_DIR")]
+        bevygap: Option<PathBuf>,
+
+        /// Level passed to the server
+        #[arg(long, default_value = "default")]
+        level: String,
+
+        /// Skip rebuilding the web client before serving it
+        #[arg(long)]
+        no_build: bool,
+    },
+    /// Build the wasm client into target/web
+    BuildWeb {
+        /// Build without the bevygap feature (no external services needed)
+        #[arg(long, env = "DISABLE_BEVYGAP")]
+        no_bevygap: bool,
+    },
+    /// Generate sw.js for a web client wasm-bindgen already wrote into DIST
+    ServiceWorker {
+        /// Directory holding voidloop-quest.js and voidloop-quest_bg.wasm
+        #[arg(default_value = WEB_DIST)]
+        dist: PathBuf,
+    },
+    /// Regenerate shared/protocol.snapshot from the protocol registration order
+    FmtProtocolSnapshots {
+        /// Fail instead of writing if the snapshot is out of date (for CI)
+        #[arg(long)]
+        check: bool,
+    },
+    /// Run a local server with N bots playing in its match
+    RunBots {
+        /// Number of bots
+        count: u32,
+
+        /// Level passed to the server
+        #[arg(long, default_value = "default")]
+        level: String,
+    },
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let root = workspace_root();
+    std::env::set_current_dir(&root)?;
+
+    match cli.command {
+        Commands::Dev {
+            web_port,
+            bevygap,
+            level,
+            no_build,
+        } => dev(web_port, bevygap, &level, no_build),
+        Commands::BuildWeb { no_bevygap } => build_web(no_bevygap),
+        Commands::ServiceWorker { dist } => write_service_worker(&dist),
+        Commands::FmtProtocolSnapshots { check } => fmt_protocol_snapshots(check),
+        Commands::RunBots { count, level } => run_bots(count, &level),
+    }
+}
+
+fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("xtask lives inside the workspace")
+        .to_path_buf()
+}
+
+fn cargo() -> Command {
+    Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()))
+}
+
+fn run(cmd: &mut Command) -> Result<()> {
+    println!("▶️  {:?}", cmd);
+    let status = cmd
+        .status()
+        .with_context(|| format!("failed to start {:?}", cmd))?;
+    if !status.success() {
+        bail!("{:?} exited with {}", cmd, status);
+    }
+    Ok(())
+}
+
+// ==== dev ====
+
+fn dev(web_port: u16, bevygap: Option<PathBuf>, level: &str, no_build: bool) -> Result<()> {
+    if !no_build {
+        build_web(bevygap.is_none())?;
+    } else if !Path::new(WEB_DIST).join("index.html").exists() {
+        bail!(
+            "{} holds no web client yet, run without --no-build once",
+            WEB_DIST
+        );
+    }
+
+    let mut children: Vec<(&str, Child)> = Vec::new();
+
+    let mut server = cargo();
+    server.args(["run", "-p", "server"]);
+    if bevygap.is_none() {
+        server.arg("--no-default-features");
+    }
+    server.args(["--", "--level", level]);
+    if bevygap.is_some() && std::env::var("LIGHTYEAR_PRIVATE_KEY").is_err() {
+        // Matchmaker and server both fall back to the zero key
+        server.arg("--allow-dummy-key");
+    }
+    children.push(("server", server.spawn().context("starting server")?));
+
+    if let Some(dir) = &bevygap {
+        // Same invocations as documented in the README, run from the bevygap checkout
+        let mut matchmaker = cargo();
+        matchmaker
+            .current_dir(dir)
+            .args(["run", "-p", "bevygap_matchmaker", "--"])
+            .args(["--app-name", "voidloop-quest", "--app-version", "0"])
+            .args(["--lightyear-protocol-id", "80085"]);
+        if let Ok(key) = std::env::var("LIGHTYEAR_PRIVATE_KEY") {
+            matchmaker.args(["--lightyear-private-key", &key]);
+        }
+        children.push(("matchmaker", matchmaker.spawn()?));
+
+        let mut httpd = cargo();
+        httpd
+            .current_dir(dir)
+            .args(["run", "-p", "bevygap_matchmaker_httpd"]);
+        children.push(("matchmaker-httpd", httpd.spawn()?));
+    } else {
+        println!(
+            "ℹ️  No --bevygap checkout given: running without lobby/matchmaker (LOCAL PLAY only)"
+        );
+    }
+
+    thread::spawn(move || {
+        if let Err(e) = serve_web(web_port) {
+            eprintln!("❌ web server stopped: {e:#}");
+        }
+    });
+    println!("🌐 Web client at http://localhost:{}", web_port);
+
+    // Stop everything as soon as one process exits
+    loop {
+        for (name, child) in children.iter_mut() {
+            if let Some(status) = child.try_wait()? {
+                println!("⏹️  {} exited with {}, shutting down", name, status);
+                for (_, other) in children.iter_mut() {
+                    let _ = other.kill();
+                }
+                return Ok(());
+            }
+        }
+        thread::sleep(Duration::from_millis(250));
+    }
+}
+
+/// Tiny static file server for target/web (assets come straight from ./assets)
+fn serve_web(port: u16) -> Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    for stream in listener.incoming().flatten() {
+        thread::spawn(move || {
+            let _ = handle_web_request(stream);
+        });
+    }
+    Ok(())
+}
+
+fn handle_web_request(mut stream: TcpStream) -> Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let path = request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or("/")
+        .split('?')
+        .next()
+        .unwrap_or("/");
+
+    let file = if path.contains("..") {
+        None
+    } else if let Some(asset) = path.strip_prefix("/assets/") {
+        Some(Path::new("assets").join(asset))
+    } else if path == "/" {
+        Some(Path::new(WEB_DIST).join("index.html"))
+    } else {
+        Some(Path::new(WEB_DIST).join(path.trim_start_matches('/')))
+    };
+
+    match file.and_then(|f| std::fs::read(&f).ok().map(|body| (f, body))) {
+        Some((file, body)) => {
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+                content_type(&file),
+                body.len()
+            )?;
+            stream.write_all(&body)?;
+        }
+        None => {
+            write!(
+                stream,
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("js") => "application/javascript",
+        Some("wasm") => "application/wasm",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("glb") => "model/gltf-binary",
+        _ => "application/octet-stream",
+    }
+}
+
+// ==== build-web ====
+
+fn build_web(no_bevygap: bool) -> Result<()> {
+    let mut build = cargo();
+    build
+        .args(["build", "--profile", "wasm-release"])
+        .args(["--target", "wasm32-unknown-unknown"])
+        .args([
+            "--package",
+            "voidloop-quest-client",
+            "--no-default-features",
+        ])
+        .env("RUSTFLAGS", "--cfg getrandom_backend=\"wasm_js\"");
+    if !no_bevygap {
+        build.args(["--features", "bevygap"]);
+    }
+    run(&mut build)?;
+
+    let dist = Path::new(WEB_DIST);
+    let wasm = dist.join("voidloop-quest_bg.wasm");
+    run(Command::new("wasm-bindgen")
+        .args(["--no-typescript", "--target", "web"])
+        .args(["--remove-name-section", "--remove-producers-section"])
+        .arg("--out-dir")
+        .arg(dist)
+        .args(["--out-name", "voidloop-quest"])
+        .arg("./target/wasm32-unknown-unknown/wasm-release/voidloop-quest-client.wasm"))?;
+
+    if Command::new("wasm-opt").arg("--version").output().is_ok() {
+        run(Command::new("wasm-opt")
+            .args([
+                "-Oz",
+                "--enable-bulk-memory",
+                "--enable-nontrapping-float-to-int",
+            ])
+            .arg("-o")
+            .arg(&wasm)
+            .arg(&wasm))?;
+    }
+
+    for file in WEB_STATIC_FILES {
+        std::fs::copy(Path::new("client/www").join(file), dist.join(file))
+            .with_context(|| format!("copying client/www/{}", file))?;
+    }
+    write_service_worker(dist)?;
+
+    let size = std::fs::metadata(&wasm)?.len();
+    println!(
+        "✅ Web client built: {} ({:.2} MiB)",
+        wasm.display(),
+        size as f64 / (1024.0 * 1024.0)
+    );
+    Ok(())
+}
+
+// ==== service-worker ====
+
+/// Writes `sw.js` next to the wasm bundle in `dist`. The cache is named after a hash of
+/// the bundle, so browsers drop it exactly when a deployment changes the game.
+fn write_service_worker(dist: &Path) -> Result<()> {
+    let mut hasher = DefaultHasher::new();
+    for file in ["voidloop-quest_bg.wasm", "voidloop-quest.js"] {
+        let path = dist.join(file);
+        let bytes = std::fs::read(&path)
+            .with_context(|| format!("reading {}, run wasm-bindgen first", path.display()))?;
+        bytes.hash(&mut hasher);
+    }
+    let cache_version = format!("{:016x}", hasher.finish());
+
+    let mut urls: Vec<String> = SHELL_FILES.iter().map(|s| s.to_string()).collect();
+    let mut assets = Vec::new();
+    collect_assets(Path::new("assets"), Path::new("assets"), &mut assets)?;
+    assets.sort();
+    urls.extend(assets.into_iter().map(|a| format!("./assets/{}", a)));
+
+    let template = std::fs::read_to_string(SW_TEMPLATE).context("reading the sw.js template")?;
+    let precache = format!(
+        "[\n{}\n]",
+        urls.iter()
+            .map(|u| format!("    \"{}\",", u))
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
+    let sw = template
+        .replace("__CACHE_VERSION__", &cache_version)
+        .replace("__PRECACHE_URLS__", &precache);
+    std::fs::write(dist.join("sw.js"), sw)?;
+    println!(
+        "✅ Service worker {} precaches {} files",
+        cache_version,
+        urls.len()
+    );
+    Ok(())
+}
+
+/// Gather asset paths (relative to `root`) that the web client may request
+fn collect_assets(root: &Path, dir: &Path, out: &mut Vec<String>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+
+        // Design backups and docs are never loaded by the game, and the deployment's
+        // network.json may change without a new build
+        if name == "logo-backups"
+            || name == "network.json"
+            || name.ends_with(".md")
+            || name.starts_with('.')
+        {
+            continue;
+        }
+
+        if path.is_dir() {
+            collect_assets(root, &path, out)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            out.push(relative.to_string_lossy().replace('\\', "/"));
+        }
+    }
+    Ok(())
+}
+
+// ==== fmt-protocol-snapshots ====
+
+/// Lightyear assigns network ids in registration order, so reordering or removing
+/// registrations silently breaks compatibility between client and server builds.
+/// The snapshot makes such changes show up in review.
+fn fmt_protocol_snapshots(check: bool) -> Result<()> {
+    let source = std::fs::read_to_string(PROTOCOL_SOURCE)?;
+    let snapshot = protocol_snapshot(&source);
+
+    let existing = std::fs::read_to_string(PROTOCOL_SNAPSHOT).unwrap_or_default();
+    if existing == snapshot {
+        println!("✅ {} is up to date", PROTOCOL_SNAPSHOT);
+        return Ok(());
+    }
+    if check {
+        bail!(
+            "{} is out of date - run `cargo xtask fmt-protocol-snapshots` and review the diff",
+            PROTOCOL_SNAPSHOT
+        );
+    }
+    std::fs::write(PROTOCOL_SNAPSHOT, snapshot)?;
+    println!("📝 Updated {}", PROTOCOL_SNAPSHOT);
+    Ok(())
+}
+
+fn protocol_snapshot(source: &str) -> String {
+    let mut lines = vec![
+        "# Generated by `cargo xtask fmt-protocol-snapshots` - do not edit.".to_string(),
+        "# Order matters: lightyear assigns network ids by registration order.".to_string(),
+    ];
+    let mut inputs = Vec::new();
+
+    let compact: String = source
+        .lines()
+        .filter(|l| !l.trim_start().starts_with("//"))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    for statement in compact.split(';') {
+        let statement: String = statement.split_whitespace().collect();
+        if let Some(name) = generic_arg(&statement, "register_component::<") {
+            let mut line = format!("component {}", name);
+            for (call, label) in [
+                ("add_prediction(PredictionMode::", "prediction"),
+                ("add_interpolation(InterpolationMode::", "interpolation"),
+            ] {
+                if let Some(mode) = call_arg(&statement, call) {
+                    line.push_str(&format!(" {}={}", label, mode));
+                }
+            }
+            lines.push(line);
+        } else if let Some(name) = generic_arg(&statement, "register_resource::<") {
+            lines.push(format!("resource {}", name));
+        } else if let Some(name) = generic_arg(&statement, "add_message::<") {
+            lines.push(format!("message {}", name));
+        } else if let Some(name) = generic_arg(&statement, "add_channel::<") {
+            lines.push(format!("channel {}", name));
+        } else if let Some(name) = generic_arg(&statement, "InputPlugin::<") {
+            lines.push(format!("input {}", name));
+            inputs.push(name);
+        }
+    }
+
+    // Input enums are serialized by their explicit discriminant, or by position
+    for input in inputs {
+        for (variant, id) in enum_variants(source, &input) {
+            lines.push(format!("input-variant {}::{} = {}", input, variant, id));
+        }
+    }
+
+    lines.join("\n") + "\n"
+}
+
+fn generic_arg(statement: &str, prefix: &str) -> Option<String> {
+    let start = statement.find(prefix)? + prefix.len();
+    let end = statement[start..].find('>')? + start;
+    Some(statement[start..end].trim_end_matches(',').to_string())
+}
+
+fn call_arg(statement: &str, prefix: &str) -> Option<String> {
+    let start = statement.find(prefix)? + prefix.len();
+    let end = statement[start..].find(')')? + start;
+    Some(statement[start..end].to_string())
+}
+
+fn enum_variants(source: &str, name: &str) -> Vec<(String, u64)> {
+    let Some(start) = source.find(&format!("pub enum {} {{", name)) else {
+        return Vec::new();
+    };
+    let body = &source[start..];
+    let body = &body[body.find('{').unwrap_or(0) + 1..body.find('}').unwrap_or(body.len())];
+    let mut next_id = 0;
+    body.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with("//") && !l.starts_with('#'))
+        .filter_map(|l| {
+            let variant = l
+                .split(|c: char| !c.is_alphanumeric() && c != '_')
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            if variant.is_empty() {
+                return None;
+            }
+            // `Variant = 3,` pins the id, otherwise it follows the previous one
+            let id = l
+                .split_once('=')
+                .and_then(|(_, id)| id.trim().trim_end_matches(',').parse().ok())
+                .unwrap_or(next_id);
+            next_id = id + 1;
+            Some((variant, id))
+        })
+        .collect()
+}
+
+// ==== run-bots ====
+
+// Players a server without bevygap spawns for local testing (`handle_player_management`)
+const LOCAL_SERVER_PLAYERS: u32 = 2;
+
+// Native clients of a build without bevygap never connect, they play locally. The server's
+// own bots play through the same inputs and systems instead, next to its local players.
+fn run_bots(count: u32, level: &str) -> Result<()> {
+    if count == 0 {
+        bail!("need at least one bot");
+    }
+
+    // --bots fills up to a player count, the local players take the first places
+    let fill = (count + LOCAL_SERVER_PLAYERS).to_string();
+    let mut server = cargo();
+    server
+        .args(["run", "-p", "server", "--no-default-features", "--"])
+        .args(["--level", level, "--bots", &fill]);
+    println!("🤖 Server with {} bots", count);
+    run(&mut server)
+}